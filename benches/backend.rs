@@ -0,0 +1,103 @@
+//! Compares how long libgit2 and gitoxide take to decide whether a worktree
+//! is dirty, the check behind `repo list --dirty`, `status` and `worktree
+//! status`, and to clone it as a bare repository as `repo get` does. Run
+//! with `cargo bench --features gix`, optionally passing the path of a large
+//! worktree; otherwise a synthetic one is generated.
+
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+const ITERATIONS: u32 = 10;
+const GENERATED_FILES: usize = 20_000;
+
+fn main() {
+    let generated = tempfile::tempdir().unwrap();
+    let worktree = match std::env::args().skip(1).find(|arg| !arg.starts_with('-')) {
+        Some(path) => path.into(),
+        None => {
+            generate(generated.path());
+            generated.path().to_path_buf()
+        }
+    };
+    println!("Worktree: {}", worktree.display());
+
+    let libgit2 = measure(|| {
+        let repo = git2::Repository::open(&worktree).unwrap();
+        let mut options = git2::StatusOptions::new();
+        options.include_untracked(true).include_ignored(false);
+        !repo.statuses(Some(&mut options)).unwrap().is_empty()
+    });
+    let gix = measure(|| {
+        let repo = gix::open(&worktree).unwrap();
+        repo.status(gix::features::progress::Discard)
+            .unwrap()
+            .untracked_files(gix::status::UntrackedFiles::Files)
+            .into_iter(None)
+            .unwrap()
+            .next()
+            .is_some()
+    });
+    println!("Dirty check:");
+    println!("libgit2: {libgit2:?} per run");
+    println!("gix:     {gix:?} per run");
+
+    let clones = tempfile::tempdir().unwrap();
+    let source = worktree.display().to_string();
+    let next_path = {
+        let count = std::cell::Cell::new(0);
+        move || {
+            count.set(count.get() + 1);
+            clones.path().join(count.get().to_string())
+        }
+    };
+    let libgit2 = measure(|| {
+        git2::build::RepoBuilder::new()
+            .bare(true)
+            .clone(&source, &next_path())
+            .is_ok()
+    });
+    let gix = measure(|| {
+        let path = next_path();
+        std::fs::create_dir_all(&path).unwrap();
+        gix::prepare_clone_bare(source.as_str(), path)
+            .unwrap()
+            .fetch_only(
+                gix::progress::Discard,
+                &std::sync::atomic::AtomicBool::new(false),
+            )
+            .is_ok()
+    });
+    println!("Bare clone:");
+    println!("libgit2: {libgit2:?} per run");
+    println!("gix:     {gix:?} per run");
+}
+
+/// Returns the mean time of `run` after one warm-up run.
+fn measure(run: impl Fn() -> bool) -> Duration {
+    run();
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        std::hint::black_box(run());
+    }
+    start.elapsed() / ITERATIONS
+}
+
+/// Creates a repository at `path` with one commit of many small files in
+/// nested directories.
+fn generate(path: &Path) {
+    let repo = git2::Repository::init(path).unwrap();
+    for i in 0..GENERATED_FILES {
+        let dir = path.join(format!("dir{}/sub{}", i % 100, i % 7));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join(format!("file{i}.txt")), format!("{i}\n")).unwrap();
+    }
+    let mut index = repo.index().unwrap();
+    index
+        .add_all(["*"], git2::IndexAddOption::DEFAULT, None)
+        .unwrap();
+    index.write().unwrap();
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let signature = git2::Signature::now("Bench", "bench@example.com").unwrap();
+    repo.commit(Some("HEAD"), &signature, &signature, "Generate", &tree, &[])
+        .unwrap();
+}
@@ -0,0 +1,378 @@
+use serde::Deserialize;
+use std::sync::OnceLock;
+use tracing::warn;
+
+/// Library used to clone, fetch and inspect repositories, from `[git]
+/// backend`. With libgit2, remotes it lacks the transport for go through
+/// the git CLI (see [`crate::transport`]); gitoxide speaks all of them.
+/// Pushes always use libgit2 or the git CLI.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GitBackend {
+    #[default]
+    Libgit2,
+    /// gitoxide, available when neoghq is built with the `gix` feature.
+    Gix,
+}
+
+static BACKEND: OnceLock<GitBackend> = OnceLock::new();
+
+/// Selects the backend for the rest of the process. Without the `gix`
+/// feature, a configured `gix` falls back to libgit2 with a warning.
+pub fn init(backend: GitBackend) {
+    let backend = if backend == GitBackend::Gix && !cfg!(feature = "gix") {
+        warn!("neoghq was built without the gix feature; using libgit2");
+        GitBackend::Libgit2
+    } else {
+        backend
+    };
+    let _ = BACKEND.set(backend);
+}
+
+/// Returns the backend chosen by [`init`], libgit2 if it was never called.
+#[cfg(feature = "gix")]
+pub fn current() -> GitBackend {
+    BACKEND.get().copied().unwrap_or_default()
+}
+
+/// Implementations on top of gitoxide.
+#[cfg(feature = "gix")]
+pub mod gix {
+    use crate::config::{Credentials, Prune};
+    use crate::credentials::resolve_token;
+    use crate::transport::Transport;
+    use ::gix::bstr::{BStr, ByteSlice};
+    use ::gix::credentials::{helper, protocol};
+    use ::gix::remote::{Connection, Direction, ref_map};
+    use anyhow::{Context, Result, anyhow};
+    use std::collections::HashSet;
+    use std::path::Path;
+    use std::sync::atomic::AtomicBool;
+
+    type BoxedTransport = Box<dyn ::gix::protocol::transport::client::Transport + Send>;
+
+    /// Like the libgit2 clone of `repo get`: a bare repository whose
+    /// `origin` branches are tracked under `refs/remotes/origin`, with a
+    /// local branch for the remote's HEAD.
+    pub fn clone_bare(url: &str, path: &Path, credentials: &Credentials) -> Result<()> {
+        let token = https_token(url, credentials)?;
+        // gitoxide clones into an existing directory only
+        std::fs::create_dir_all(path)?;
+        let credentials = credentials.clone();
+        let mut prepare = ::gix::prepare_clone_bare(url, path)?
+            .with_in_memory_config_overrides(ssh_command(&credentials))
+            .configure_connection(move |connection| {
+                authenticate(connection, &credentials, token.clone())?;
+                Ok(())
+            });
+        let (_, outcome) = prepare
+            .fetch_only(::gix::progress::Discard, &AtomicBool::new(false))
+            .with_context(|| format!("Failed to clone {url}"))?;
+        write_fetch_head(path, url, &outcome.ref_map.mappings)
+    }
+
+    /// Like [`crate::commands::repo::get::fetch_remote`]: fetches the
+    /// configured refspecs of the remote `name`, then deletes the
+    /// remote-tracking branches, and local tags, `prune` asks for that the
+    /// remote no longer has.
+    pub fn fetch(
+        bare_repo_path: &Path,
+        name: &str,
+        credentials: &Credentials,
+        prune: Prune,
+    ) -> Result<()> {
+        let options = ::gix::open::Options::default().config_overrides(ssh_command(credentials));
+        let repo = ::gix::open_opts(bare_repo_path, options)?;
+        let remote = repo.find_remote(name)?;
+        let url = remote
+            .url(Direction::Fetch)
+            .map(|url| url.to_bstring().to_string())
+            .unwrap_or_default();
+        let mut connection = remote.connect(Direction::Fetch)?;
+        authenticate(
+            &mut connection,
+            credentials,
+            https_token(&url, credentials)?,
+        )?;
+
+        let mut options = ref_map::Options::default();
+        if prune.tags {
+            options.extra_refspecs.push(
+                ::gix::refspec::parse(
+                    "+refs/tags/*:refs/tags/*".into(),
+                    ::gix::refspec::parse::Operation::Fetch,
+                )?
+                .to_owned(),
+            );
+        }
+        let outcome = connection
+            .prepare_fetch(::gix::progress::Discard, options)?
+            .receive(::gix::progress::Discard, &AtomicBool::new(false))
+            .with_context(|| format!("Failed to fetch {name}"))?;
+        write_fetch_head(bare_repo_path, &url, &outcome.ref_map.mappings)?;
+
+        let fetched = outcome
+            .ref_map
+            .mappings
+            .iter()
+            .filter_map(|mapping| mapping.local.as_ref().map(|local| local.as_bstr()))
+            .collect::<HashSet<_>>();
+        let mut prefixes = Vec::new();
+        if prune.branches || prune.tags {
+            prefixes.push(format!("refs/remotes/{name}/"));
+        }
+        if prune.tags {
+            prefixes.push("refs/tags/".to_string());
+        }
+        for prefix in prefixes {
+            prune_refs(&repo, &prefix, &fetched)?;
+        }
+        Ok(())
+    }
+
+    /// Records the fetched refs in `FETCH_HEAD`, which gitoxide doesn't
+    /// write but git and libgit2 do, and whose age is the last fetch of
+    /// `repo list` and `status`. A bare repository has no branch to merge
+    /// into, so none of them is for merging.
+    fn write_fetch_head(
+        git_dir: &Path,
+        url: &str,
+        mappings: &[::gix::protocol::fetch::refmap::Mapping],
+    ) -> Result<()> {
+        let mut content = String::new();
+        for mapping in mappings {
+            let (Some(name), Some(id)) = (mapping.remote.as_name(), mapping.remote.as_id()) else {
+                continue;
+            };
+            let name = name.to_str_lossy();
+            // Like git, leave out the remote's HEAD that a clone asks for
+            if !name.starts_with("refs/") {
+                continue;
+            }
+            let description = if let Some(branch) = name.strip_prefix("refs/heads/") {
+                format!("branch '{branch}'")
+            } else if let Some(tag) = name.strip_prefix("refs/tags/") {
+                format!("tag '{tag}'")
+            } else {
+                format!("'{name}'")
+            };
+            content.push_str(&format!("{id}\tnot-for-merge\t{description} of {url}\n"));
+        }
+        std::fs::write(git_dir.join("FETCH_HEAD"), content)?;
+        Ok(())
+    }
+
+    /// Deletes the refs under `prefix` that weren't fetched, leaving the
+    /// remote's `HEAD` alone.
+    fn prune_refs(repo: &::gix::Repository, prefix: &str, fetched: &HashSet<&BStr>) -> Result<()> {
+        let references = repo.references()?;
+        for reference in references
+            .prefixed(prefix)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|error| anyhow!(error))?
+        {
+            let name = reference.name().as_bstr();
+            if name.ends_with(b"/HEAD") || fetched.contains(name) {
+                continue;
+            }
+            reference.delete()?;
+        }
+        Ok(())
+    }
+
+    /// Reads the configured HTTPS token up front, for HTTPS remotes only,
+    /// so a failing `token_command` fails the operation instead of the
+    /// handshake.
+    fn https_token(url: &str, credentials: &Credentials) -> Result<Option<String>> {
+        if Transport::for_url(url) == Transport::Https {
+            resolve_token(credentials)
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Has gitoxide run `ssh` with the configured key, as libgit2 is given
+    /// it.
+    fn ssh_command(credentials: &Credentials) -> Vec<String> {
+        credentials
+            .ssh_key
+            .iter()
+            .map(|key| {
+                let key = key.display().to_string().replace('\'', r"'\''");
+                format!("core.sshCommand=ssh -i '{key}' -o IdentitiesOnly=yes")
+            })
+            .collect()
+    }
+
+    /// Answers credential requests with `token`, or else through the git
+    /// credential helpers, prompting only when `credentials.prompt` allows.
+    // The error type is gitoxide's
+    #[allow(clippy::result_large_err)]
+    fn authenticate(
+        connection: &mut Connection<'_, '_, BoxedTransport>,
+        credentials: &Credentials,
+        token: Option<String>,
+    ) -> Result<()> {
+        let url = connection.transport_mut().to_url().into_owned();
+        let (mut cascade, _, mut prompt) = connection
+            .remote()
+            .repo()
+            .config_snapshot()
+            .credential_helpers(::gix::url::parse(url.as_ref())?)?;
+        if !credentials.prompt {
+            prompt.mode = ::gix::prompt::Mode::Disable;
+            prompt.askpass = None;
+        }
+        connection.set_credentials(move |action| match (&action, &token) {
+            (helper::Action::Get(context), Some(token)) => Ok(Some(protocol::Outcome {
+                identity: ::gix::sec::identity::Account {
+                    username: context
+                        .username
+                        .clone()
+                        .unwrap_or_else(|| "x-access-token".to_string()),
+                    password: token.clone(),
+                    oauth_refresh_token: None,
+                },
+                next: context.clone().into(),
+            })),
+            _ => cascade.invoke(action, prompt.clone()),
+        });
+        Ok(())
+    }
+
+    /// Like [`crate::status::is_worktree_dirty`]: untracked files count,
+    /// ignored ones don't, and directories that aren't worktrees are clean.
+    pub fn is_worktree_dirty(worktree: &Path) -> Result<bool> {
+        let Ok(repo) = ::gix::open(worktree) else {
+            return Ok(false);
+        };
+        let mut changes = repo
+            .status(::gix::features::progress::Discard)?
+            .untracked_files(::gix::status::UntrackedFiles::Files)
+            .into_iter(None)?;
+        Ok(changes.next().transpose()?.is_some())
+    }
+
+    #[cfg(test)]
+    #[cfg_attr(coverage_nightly, coverage(off))]
+    mod tests {
+        use super::*;
+        use crate::test_utils::{commit_all, create_managed_repository, create_source_repository};
+        use git2::BranchType;
+
+        #[test]
+        fn test_is_worktree_dirty_matches_libgit2() {
+            let temp_dir = tempfile::tempdir().unwrap();
+            let repo_path = temp_dir.path().join("github.com/user/repo");
+            create_managed_repository(&repo_path);
+            let worktree = repo_path.join("main");
+            let check = || {
+                let dirty = is_worktree_dirty(&worktree).unwrap();
+                assert_eq!(dirty, crate::status::is_worktree_dirty(&worktree).unwrap());
+                dirty
+            };
+
+            assert!(!check());
+            std::fs::write(worktree.join("NEW"), "new\n").unwrap();
+            assert!(check());
+            commit_all(&git2::Repository::open(&worktree).unwrap(), "Add NEW");
+            assert!(!check());
+            std::fs::write(worktree.join("README"), "changed\n").unwrap();
+            assert!(check());
+            assert!(!is_worktree_dirty(temp_dir.path()).unwrap());
+        }
+
+        #[test]
+        fn test_clone_bare_matches_libgit2() {
+            let temp_dir = tempfile::tempdir().unwrap();
+            let source = temp_dir.path().join("source");
+            create_source_repository(&source);
+            let path = temp_dir.path().join("github.com/user/repo/.git");
+
+            clone_bare(source.to_str().unwrap(), &path, &Credentials::default()).unwrap();
+            let repo = git2::Repository::open_bare(&path).unwrap();
+            assert_eq!(
+                crate::status::default_branch(&repo).as_deref(),
+                Some("main")
+            );
+            assert!(repo.find_branch("main", BranchType::Local).is_ok());
+            assert!(repo.find_branch("origin/main", BranchType::Remote).is_ok());
+            let remote = repo.find_remote("origin").unwrap();
+            assert_eq!(
+                remote.fetch_refspecs().unwrap().get(0),
+                Some("+refs/heads/*:refs/remotes/origin/*")
+            );
+            let head = repo.find_branch("main", BranchType::Local).unwrap();
+            assert_eq!(
+                std::fs::read_to_string(path.join("FETCH_HEAD")).unwrap(),
+                format!(
+                    "{}\tnot-for-merge\tbranch 'main' of {}\n",
+                    head.get().target().unwrap(),
+                    source.display()
+                )
+            );
+        }
+
+        #[test]
+        fn test_fetch_prunes_what_was_asked_for() {
+            let temp_dir = tempfile::tempdir().unwrap();
+            let source_path = temp_dir.path().join("source");
+            let source = create_source_repository(&source_path);
+            let path = temp_dir.path().join("github.com/user/repo/.git");
+            clone_bare(
+                source_path.to_str().unwrap(),
+                &path,
+                &Credentials::default(),
+            )
+            .unwrap();
+            let repo = git2::Repository::open_bare(&path).unwrap();
+            let credentials = Credentials::default();
+            let prune_tags = Prune {
+                branches: true,
+                tags: true,
+            };
+
+            let head = source.head().unwrap().peel_to_commit().unwrap();
+            source.branch("feature", &head, false).unwrap();
+            source
+                .tag_lightweight("v1", head.as_object(), false)
+                .unwrap();
+            fetch(&path, "origin", &credentials, prune_tags).unwrap();
+            assert!(
+                repo.find_branch("origin/feature", BranchType::Remote)
+                    .is_ok()
+            );
+            assert!(repo.find_reference("refs/tags/v1").is_ok());
+
+            source
+                .find_branch("feature", BranchType::Local)
+                .unwrap()
+                .delete()
+                .unwrap();
+            source.tag_delete("v1").unwrap();
+            fetch(&path, "origin", &credentials, Prune::default()).unwrap();
+            assert!(
+                repo.find_branch("origin/feature", BranchType::Remote)
+                    .is_ok()
+            );
+            fetch(
+                &path,
+                "origin",
+                &credentials,
+                Prune {
+                    branches: true,
+                    tags: false,
+                },
+            )
+            .unwrap();
+            assert!(
+                repo.find_branch("origin/feature", BranchType::Remote)
+                    .is_err()
+            );
+            assert!(repo.find_reference("refs/tags/v1").is_ok());
+            fetch(&path, "origin", &credentials, prune_tags).unwrap();
+            assert!(repo.find_reference("refs/tags/v1").is_err());
+            assert!(repo.find_branch("origin/main", BranchType::Remote).is_ok());
+        }
+    }
+}
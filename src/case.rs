@@ -0,0 +1,142 @@
+use crate::{giturl, scan::RepoEntry};
+use anyhow::Result;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Finds the repository at `dir/<names...>` whose names match ignoring
+/// case, spelled as on disk. An exact match wins at each level.
+pub fn find_ignoring_case(dir: &Path, names: &[&str]) -> Result<Option<PathBuf>> {
+    let mut path = dir.to_path_buf();
+    for name in names {
+        let entries = match std::fs::read_dir(&path) {
+            Ok(entries) => entries,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(error) => return Err(error.into()),
+        };
+        let mut found = None;
+        for entry in entries {
+            let entry = entry?;
+            let entry_name = entry.file_name().to_string_lossy().into_owned();
+            if entry_name == *name {
+                found = Some(entry_name);
+                break;
+            }
+            if found.is_none() && entry_name.to_lowercase() == name.to_lowercase() {
+                found = Some(entry_name);
+            }
+        }
+        let Some(found) = found else {
+            return Ok(None);
+        };
+        path.push(found);
+    }
+    Ok(path.join(".git").exists().then_some(path))
+}
+
+/// Returns the pairs of repositories under the same root whose
+/// `host/owner/repo` differ only in case, which share one directory on
+/// case-insensitive filesystems such as macOS and Windows.
+pub fn collisions(entries: &[RepoEntry]) -> Vec<(&RepoEntry, &RepoEntry)> {
+    let mut seen = HashMap::<(Option<&Path>, String), &RepoEntry>::new();
+    let mut collisions = Vec::new();
+    for entry in entries {
+        let key = (entry.root(), entry.spec().to_lowercase());
+        match seen.get(&key) {
+            Some(first) => collisions.push((*first, entry)),
+            None => {
+                seen.insert(key, entry);
+            }
+        }
+    }
+    collisions
+}
+
+/// Whether the `origin` of the repository in `repo_dir` is `url`, ignoring
+/// case and protocol, as hosts such as GitHub do.
+pub fn has_origin(repo_dir: &Path, url: &str) -> bool {
+    let origin = git2::Repository::open_bare(repo_dir.join(".git"))
+        .ok()
+        .and_then(|repo| Some(repo.find_remote("origin").ok()?.url()?.to_string()));
+    let (Some(origin), Ok(url)) = (origin, giturl::parse(url)) else {
+        return false;
+    };
+    giturl::parse(&origin).is_ok_and(|origin| {
+        origin.host.eq_ignore_ascii_case(&url.host)
+            && origin.owner.eq_ignore_ascii_case(&url.owner)
+            && origin.repo.eq_ignore_ascii_case(&url.repo)
+    })
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_managed_repository;
+
+    #[test]
+    fn test_find_ignoring_case() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        let repo_dir = root.join("github.com/User/Repo");
+        std::fs::create_dir_all(repo_dir.join(".git")).unwrap();
+
+        assert_eq!(
+            find_ignoring_case(root, &["GitHub.com", "user", "repo"]).unwrap(),
+            Some(repo_dir.clone())
+        );
+        assert_eq!(
+            find_ignoring_case(root, &["github.com", "User", "Repo"]).unwrap(),
+            Some(repo_dir)
+        );
+        assert_eq!(
+            find_ignoring_case(root, &["github.com", "user", "other"]).unwrap(),
+            None
+        );
+        assert_eq!(
+            find_ignoring_case(&root.join("missing"), &["user"]).unwrap(),
+            None
+        );
+        // A directory without a bare repository isn't a repository
+        std::fs::create_dir_all(root.join("github.com/user/plain")).unwrap();
+        assert_eq!(
+            find_ignoring_case(root, &["github.com", "user", "plain"]).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_collisions() {
+        let entry = |root: &str, owner: &str, repo: &str| {
+            RepoEntry::from_path(&Path::new(root).join("github.com").join(owner).join(repo))
+                .unwrap()
+        };
+        let entries = [
+            entry("/a", "User", "Repo"),
+            entry("/a", "user", "repo"),
+            entry("/a", "user", "other"),
+            // The same spec in another root is a separate directory
+            entry("/b", "user", "repo"),
+        ];
+
+        let collisions = collisions(&entries);
+        assert_eq!(collisions.len(), 1);
+        assert_eq!(collisions[0].0.spec(), "github.com/User/Repo");
+        assert_eq!(collisions[0].1.spec(), "github.com/user/repo");
+    }
+
+    #[test]
+    fn test_has_origin() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo_dir = temp_dir.path().join("github.com/user/repo");
+        let repo = create_managed_repository(&repo_dir);
+        // The local clone source isn't a URL
+        assert!(!has_origin(&repo_dir, "https://github.com/user/repo"));
+
+        repo.remote_set_url("origin", "git@github.com:User/Repo.git")
+            .unwrap();
+        assert!(has_origin(&repo_dir, "https://github.com/user/repo"));
+        assert!(has_origin(&repo_dir, "https://GitHub.com/User/Repo.git"));
+        assert!(!has_origin(&repo_dir, "https://github.com/user/other"));
+        assert!(!has_origin(&repo_dir, "https://gitlab.com/user/repo"));
+    }
+}
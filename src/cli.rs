@@ -1,10 +1,59 @@
-use clap::{Parser, Subcommand};
+use crate::{completion, config::CloneProtocol};
+use clap::{ArgAction, Args, Parser, Subcommand, ValueEnum};
+use clap_complete::engine::ArgValueCandidates;
+use serde::Deserialize;
+use std::path::PathBuf;
 
 #[derive(Parser)]
 #[command(name = "neoghq")]
 #[command(about = "Git Worktree-Based Repository Manager")]
 #[command(version)]
+#[command(args_override_self = true)]
 pub struct Cli {
+    /// Configuration profile to use (also read from NEOGHQ_PROFILE)
+    #[arg(long, global = true)]
+    pub profile: Option<String>,
+
+    /// Ignore the default arguments configured in [defaults]
+    #[arg(long, global = true)]
+    pub no_defaults: bool,
+
+    /// Configuration file to use (also read from NEOGHQ_CONFIG)
+    #[arg(long, global = true, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+
+    /// Output format for commands that list or report status
+    #[arg(long, global = true, value_name = "FORMAT", default_value = "text")]
+    pub output: OutputFormat,
+
+    /// Show debug output; repeat for trace output (NEOGHQ_LOG sets a filter instead)
+    #[arg(short, long, global = true, action = ArgAction::Count, conflicts_with = "quiet")]
+    pub verbose: u8,
+
+    /// Only print results and errors; `switch` also stays silent when the target is missing
+    #[arg(short, long, global = true)]
+    pub quiet: bool,
+
+    /// Never prompt; fail instead (also when stdin is not a terminal)
+    #[arg(long, global = true)]
+    pub no_input: bool,
+
+    /// Print what destructive commands would remove or change without doing it
+    #[arg(long, global = true)]
+    pub dry_run: bool,
+
+    /// Use only this root, given as a configured root name or a path, instead of NEOGHQ_ROOT and the configured roots
+    #[arg(long, global = true, value_name = "ROOT")]
+    pub root: Option<String>,
+
+    /// Descend into symlinked directories when walking roots (skipped by default)
+    #[arg(long, global = true)]
+    pub follow_symlinks: bool,
+
+    /// When to color output (NO_COLOR disables auto colors; default from [ui] color)
+    #[arg(long, global = true, value_name = "WHEN")]
+    pub color: Option<ColorChoice>,
+
     #[command(subcommand)]
     pub command: Commands,
 }
@@ -16,40 +65,1087 @@ pub enum Commands {
         #[command(subcommand)]
         command: RepoCommands,
     },
+    /// Clone or update a repository (same as `repo get`)
+    #[command(alias = "clone")]
+    Get {
+        url: String,
+        /// Protocol of the URL built from owner/repo, overriding clone.protocol
+        #[arg(long)]
+        protocol: Option<CloneProtocol>,
+        /// Open the worktree with editor.command, $VISUAL or $EDITOR
+        #[arg(long)]
+        open: bool,
+    },
+    /// List managed repositories (same as `repo list`)
+    List {
+        /// Print one tab-separated row per repository with a header row
+        #[arg(long)]
+        tsv: bool,
+        /// Print repositories as a JSON array (same as --output json)
+        #[arg(long, conflicts_with = "tsv")]
+        json: bool,
+        /// Print each repository using a template such as '{host}/{owner}/{repo}\t{path}'
+        #[arg(long, value_name = "TEMPLATE", conflicts_with_all = ["tsv", "json"])]
+        format: Option<String>,
+        /// Terminate entries with NUL instead of newline, for xargs -0 and fzf --read0
+        #[arg(short = '0', long, conflicts_with = "json")]
+        print0: bool,
+        /// Print absolute repository paths instead of host/owner/repo
+        #[arg(short = 'p', long, conflicts_with_all = ["tsv", "json", "format"])]
+        full_path: bool,
+        /// Print the shortest unambiguous suffix of host/owner/repo
+        #[arg(long, conflicts_with_all = ["tsv", "json", "format", "full_path"])]
+        unique: bool,
+        /// Print a table with default branch, worktrees, dirty worktrees, last fetch and size
+        #[arg(short = 'l', long, conflicts_with_all = ["tsv", "json", "format", "full_path", "unique", "print0"])]
+        long: bool,
+        /// Only repositories whose host/owner/repo contains this text or matches this glob
+        query: Option<String>,
+        /// Only repositories from this host (glob)
+        #[arg(long)]
+        host: Option<String>,
+        /// Only repositories of this owner (glob)
+        #[arg(long)]
+        owner: Option<String>,
+        /// Only repositories with uncommitted changes in some worktree
+        #[arg(long)]
+        dirty: bool,
+        /// Only repositories with this tag
+        #[arg(long)]
+        tag: Option<String>,
+        /// Order repositories by name, modification time, last switch, or size
+        #[arg(long, value_name = "KEY")]
+        sort: Option<SortKey>,
+        /// Also list directories that aren't git repositories, to clean them up
+        #[arg(long)]
+        include_broken: bool,
+        /// List archived repositories, with the path of their archive, instead
+        #[arg(long, conflicts_with_all = ["long", "include_broken"])]
+        archived: bool,
+    },
+    /// Remove a repository and its worktrees (same as `repo remove`)
+    #[command(alias = "remove")]
+    Rm {
+        #[arg(add = ArgValueCandidates::new(completion::repo_candidates))]
+        repo: String,
+        /// Remove without asking for confirmation
+        #[arg(long, short)]
+        yes: bool,
+    },
     /// Worktree operations
     Worktree {
         #[command(subcommand)]
         command: WorktreeCommands,
     },
+    /// Interactively write a new configuration file
+    Init {
+        /// Overwrite an existing configuration file
+        #[arg(long)]
+        force: bool,
+    },
     /// Show neoghq root directory path
-    Root,
+    Root {
+        /// Print only the root containing this repository
+        #[arg(add = ArgValueCandidates::new(completion::repo_candidates))]
+        repo: Option<String>,
+        /// Create the root directories that don't exist yet
+        #[arg(long, conflicts_with = "repo")]
+        create: bool,
+        /// Terminate entries with NUL instead of newline
+        #[arg(short = '0', long)]
+        print0: bool,
+    },
+    /// Read and write the configuration file
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommands,
+    },
+    /// Print a shell completion script
+    Completions { shell: CompletionShell },
+    /// Generate man pages or a Markdown reference from the CLI definitions
+    Docs {
+        #[command(subcommand)]
+        command: DocsCommands,
+    },
+    /// Print shell functions (ncd, nwt) that cd into repositories and worktrees
+    ShellInit {
+        shell: InitShell,
+        /// Also bind Alt-G and Alt-W to pick a repository and a worktree
+        #[arg(long)]
+        key_bindings: bool,
+    },
+    /// Search the contents of files across repositories
+    Grep {
+        /// Regular expression to search for
+        pattern: String,
+        /// Match case-insensitively
+        #[arg(short, long)]
+        ignore_case: bool,
+        /// Treat the pattern as a literal string
+        #[arg(short = 'F', long)]
+        fixed_strings: bool,
+        /// Print only the paths of files with matches
+        #[arg(short = 'l', long, conflicts_with = "json")]
+        files_with_matches: bool,
+        #[command(flatten)]
+        scope: SearchScope,
+        /// Print matches as a JSON array (same as --output json)
+        #[arg(long)]
+        json: bool,
+    },
+    /// Find files by name across repositories
+    Find {
+        /// Glob matched against file names, or against paths within the worktree if it contains '/'
+        pattern: String,
+        #[command(flatten)]
+        scope: SearchScope,
+        /// Terminate paths with NUL instead of newline, for xargs -0
+        #[arg(short = '0', long)]
+        print0: bool,
+    },
+    /// Group repositories (and optionally specific worktrees) into named workspaces
+    Workspace {
+        #[command(subcommand)]
+        command: WorkspaceCommands,
+    },
+    /// Restore the most recently removed repository or worktree from the trash
+    Undo,
+    /// Manage the cached repository index of each root
+    Index {
+        #[command(subcommand)]
+        command: IndexCommands,
+    },
+    /// Fetch repositories and cache their status in the background
+    Daemon {
+        #[command(subcommand)]
+        command: DaemonCommands,
+    },
+    /// Keep the index current while repositories are cloned or removed outside neoghq
+    Watch,
+    /// Print the current repository, branch and dirty marker for a shell prompt
+    Prompt {
+        /// Directory to describe instead of the current directory
+        #[arg(long)]
+        from: Option<PathBuf>,
+        /// Template over {host}, {owner}, {repo}, {worktree}, {branch} and {dirty} (default: prompt.format)
+        #[arg(long, value_name = "TEMPLATE")]
+        format: Option<String>,
+        /// Skip the worktree status check, for very large worktrees
+        #[arg(long)]
+        no_dirty: bool,
+    },
+    /// List tmux sessions started in worktrees
+    Sessions {
+        /// Print the sessions as a JSON array (same as --output json)
+        #[arg(long)]
+        json: bool,
+    },
+    /// List, restore or empty removed repositories and worktrees
+    Trash {
+        #[command(subcommand)]
+        command: TrashCommands,
+    },
+    /// Run a command in the default worktree of every repository
+    Run {
+        /// Only repositories whose host/owner/repo matches this glob or ends with this text
+        #[arg(long, value_name = "QUERY", add = ArgValueCandidates::new(completion::repo_candidates))]
+        filter: Option<String>,
+        /// Only repositories with this tag
+        #[arg(long)]
+        tag: Option<String>,
+        /// Number of repositories to run the command in at once (default: one per CPU)
+        #[arg(short, long, value_name = "N", value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..))]
+        jobs: Option<usize>,
+        /// The command and its arguments, after --
+        #[arg(last = true, required = true, value_name = "COMMAND")]
+        command: Vec<String>,
+    },
+    /// Find the commit that broke a command with git bisect, in a throwaway worktree
+    Bisect {
+        #[arg(add = ArgValueCandidates::new(completion::repo_candidates))]
+        repo: String,
+        /// A revision where the command succeeds; repeat for several
+        #[arg(long, required = true, value_name = "REF")]
+        good: Vec<String>,
+        /// A revision where the command fails
+        #[arg(long, value_name = "REF")]
+        bad: String,
+        /// The command and its arguments, after --; exit code 125 skips a commit
+        #[arg(last = true, required = true, value_name = "COMMAND")]
+        command: Vec<String>,
+    },
+    /// Show the repositories and worktrees switched to, most recent first
+    History {
+        /// Show at most this many entries
+        #[arg(short = 'n', long, value_name = "N")]
+        limit: Option<usize>,
+        /// Print the history as a JSON array (same as --output json)
+        #[arg(long)]
+        json: bool,
+    },
+    /// Show the disk usage of repositories and their worktrees, largest first
+    Du {
+        /// Only repositories whose host/owner/repo contains this text
+        query: Option<String>,
+        /// Number of directories to measure at once (default: one per CPU)
+        #[arg(short, long, value_name = "N", value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..))]
+        jobs: Option<usize>,
+        /// Print the sizes in bytes as a JSON array (same as --output json)
+        #[arg(long)]
+        json: bool,
+    },
+    /// Summarize dirty worktrees, unpushed commits, gone upstreams and failed fetches
+    Status {
+        /// Only repositories whose host/owner/repo contains this text
+        query: Option<String>,
+        /// List the affected repositories and worktrees under each count
+        #[arg(short = 'l', long)]
+        long: bool,
+        /// Number of repositories to inspect at once (default: one per CPU)
+        #[arg(short, long, value_name = "N", value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..))]
+        jobs: Option<usize>,
+        /// Print the affected repositories as JSON (same as --output json)
+        #[arg(long, conflicts_with = "long")]
+        json: bool,
+    },
+    /// Find broken worktree links, stale worktrees, plain clones and other layout problems
+    Doctor {
+        /// Apply the safe repairs: relink moved worktrees, prune deleted ones and reset a missing default branch
+        #[arg(long)]
+        fix: bool,
+        /// Print the problems as a JSON array (same as --output json)
+        #[arg(long)]
+        json: bool,
+    },
+    /// Move a root and rewrite the worktree links that point into it
+    Relocate {
+        /// Where the root is, or was before you moved it
+        old_root: PathBuf,
+        /// Where the root should be, or already is
+        new_root: PathBuf,
+    },
+    /// Print a standard location for the repository containing the current directory
+    Path {
+        what: PathKind,
+        /// Directory to resolve from instead of the current directory
+        #[arg(long)]
+        from: Option<PathBuf>,
+    },
+    /// Replace this binary with the latest GitHub release
+    SelfUpdate {
+        /// Only print the newer version, if there is one
+        #[arg(long)]
+        check: bool,
+        /// Update even when the binary looks like a package-manager install
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum DocsCommands {
+    /// Write a man page for every command, or print the top-level one
+    Man {
+        /// Directory to write neoghq.1, neoghq-repo.1 and the other pages into
+        #[arg(long, value_name = "DIR")]
+        out_dir: Option<PathBuf>,
+    },
+    /// Print a Markdown reference of every command
+    Markdown,
+}
+
+#[derive(Subcommand)]
+pub enum ConfigCommands {
+    /// Print the value of a configuration key in effect, from a flag, the environment or the file
+    Get { key: String },
+    /// Set a configuration key in the configuration file
+    Set { key: String, value: String },
+    /// Remove a configuration key from the configuration file
+    Unset { key: String },
+    /// List all keys set in the configuration file
+    List {
+        /// Print the configuration as JSON
+        #[arg(long)]
+        json: bool,
+        /// Print effective settings with the file, variable, or default they came from
+        #[arg(long, conflicts_with = "json")]
+        origin: bool,
+    },
+    /// Check the configuration file for errors, unknown keys, and missing paths
+    Validate,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Human-readable text
+    #[default]
+    Text,
+    /// JSON documents with stable field names
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorChoice {
+    /// Color when writing to a terminal
+    #[default]
+    Auto,
+    /// Always color
+    Always,
+    /// Never color
+    Never,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SortKey {
+    /// Alphabetically by name
+    Name,
+    /// Most recently modified first
+    Mtime,
+    /// Most frecent first: switched to often and recently
+    Recent,
+    /// Largest on disk first
+    Size,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum CompletionShell {
+    Bash,
+    Zsh,
+    Fish,
+    Elvish,
+    Powershell,
+    Nushell,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum InitShell {
+    Bash,
+    Zsh,
+    Fish,
+    Nushell,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum PathKind {
+    /// Directory holding the bare repository and all worktrees
+    RepoRoot,
+    /// The bare repository shared by all worktrees
+    BareGitDir,
+    /// Top-level directory of the current worktree
+    WorktreeRoot,
+    /// Cache directory shared by all worktrees of the repository
+    CacheDir,
+}
+
+/// Flags giving `switch` a strict contract for editor plugins and scripts:
+/// it never creates anything, never prompts, and only ever prints the path.
+#[derive(Debug, Clone, Copy, Default, Args)]
+pub struct SwitchFlags {
+    /// Never create the target if it doesn't exist, such as the default worktree
+    /// of a compacted repository
+    #[arg(long)]
+    pub no_create: bool,
+    /// Only check that the target exists, printing nothing
+    #[arg(long)]
+    pub exists_only: bool,
+    /// The global `--quiet`: print nothing when the target doesn't exist and
+    /// exit with status 3.
+    #[arg(from_global)]
+    pub quiet: bool,
+    /// Open the worktree with editor.command, $VISUAL or $EDITOR
+    #[arg(long, conflicts_with = "exists_only")]
+    pub open: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum License {
+    #[value(name = "MIT", alias = "mit")]
+    Mit,
+    #[value(name = "ISC", alias = "isc")]
+    Isc,
+    #[value(name = "Unlicense", alias = "unlicense")]
+    Unlicense,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum GitignoreTemplate {
+    #[value(name = "Rust", alias = "rust")]
+    Rust,
+    #[value(name = "Node", alias = "node")]
+    Node,
+    #[value(name = "Python", alias = "python")]
+    Python,
+    #[value(name = "Go", alias = "go")]
+    Go,
 }
 
 #[derive(Subcommand)]
 pub enum RepoCommands {
-    /// Clone repository and create default branch worktree
-    Clone { url: String },
+    /// Clone, update, or adopt a repository and create its branch worktree
+    #[command(alias = "clone")]
+    Get {
+        url: String,
+        /// Protocol of the URL built from owner/repo, overriding clone.protocol
+        #[arg(long)]
+        protocol: Option<CloneProtocol>,
+        /// Open the worktree with editor.command, $VISUAL or $EDITOR
+        #[arg(long)]
+        open: bool,
+    },
     /// Create a new repository and initialize worktree
-    Create { url: String },
+    Create {
+        url: String,
+        /// Protocol of the URL built from owner/repo, overriding clone.protocol
+        #[arg(long)]
+        protocol: Option<CloneProtocol>,
+        /// Start without an initial commit; the worktree begins on an unborn branch
+        #[arg(long)]
+        empty: bool,
+        /// Seed the worktree from a template name, directory, or repository URL
+        #[arg(long)]
+        template: Option<String>,
+        /// Add a LICENSE file
+        #[arg(long)]
+        license: Option<License>,
+        /// Add a .gitignore for a language
+        #[arg(long)]
+        gitignore: Option<GitignoreTemplate>,
+    },
     /// Navigate to repository directory
-    Switch { repo: String },
+    Switch {
+        /// Repository to switch to; choose one in the picker when omitted
+        #[arg(
+            add = ArgValueCandidates::new(completion::repo_candidates),
+            required_if_eq_any([("exists_only", "true"), ("quiet", "true")])
+        )]
+        repo: Option<String>,
+        #[command(flatten)]
+        flags: SwitchFlags,
+    },
     /// List all managed repositories
-    List,
+    List {
+        /// Print one tab-separated row per repository with a header row
+        #[arg(long)]
+        tsv: bool,
+        /// Print repositories as a JSON array (same as --output json)
+        #[arg(long, conflicts_with = "tsv")]
+        json: bool,
+        /// Print each repository using a template such as '{host}/{owner}/{repo}\t{path}'
+        #[arg(long, value_name = "TEMPLATE", conflicts_with_all = ["tsv", "json"])]
+        format: Option<String>,
+        /// Terminate entries with NUL instead of newline, for xargs -0 and fzf --read0
+        #[arg(short = '0', long, conflicts_with = "json")]
+        print0: bool,
+        /// Print absolute repository paths instead of host/owner/repo
+        #[arg(short = 'p', long, conflicts_with_all = ["tsv", "json", "format"])]
+        full_path: bool,
+        /// Print the shortest unambiguous suffix of host/owner/repo
+        #[arg(long, conflicts_with_all = ["tsv", "json", "format", "full_path"])]
+        unique: bool,
+        /// Print a table with default branch, worktrees, dirty worktrees, last fetch and size
+        #[arg(short = 'l', long, conflicts_with_all = ["tsv", "json", "format", "full_path", "unique", "print0"])]
+        long: bool,
+        /// Only repositories whose host/owner/repo contains this text or matches this glob
+        query: Option<String>,
+        /// Only repositories from this host (glob)
+        #[arg(long)]
+        host: Option<String>,
+        /// Only repositories of this owner (glob)
+        #[arg(long)]
+        owner: Option<String>,
+        /// Only repositories with uncommitted changes in some worktree
+        #[arg(long)]
+        dirty: bool,
+        /// Only repositories with this tag
+        #[arg(long)]
+        tag: Option<String>,
+        /// Order repositories by name, modification time, last switch, or size
+        #[arg(long, value_name = "KEY")]
+        sort: Option<SortKey>,
+        /// Also list directories that aren't git repositories, to clean them up
+        #[arg(long)]
+        include_broken: bool,
+        /// List archived repositories, with the path of their archive, instead
+        #[arg(long, conflicts_with_all = ["long", "include_broken"])]
+        archived: bool,
+    },
+    /// Fetch every remote of repositories, several at once
+    Fetch {
+        #[arg(
+            required_unless_present = "all",
+            conflicts_with = "all",
+            add = ArgValueCandidates::new(completion::repo_candidates)
+        )]
+        repos: Vec<String>,
+        /// Fetch every managed repository
+        #[arg(long, short)]
+        all: bool,
+        /// Number of repositories to fetch at once (default: one per CPU)
+        #[arg(short, long, value_name = "N", value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..))]
+        jobs: Option<usize>,
+        /// Retry a failed fetch this many times (authentication failures are not retried)
+        #[arg(long, value_name = "N", default_value_t = 2)]
+        retries: u32,
+        /// Delete remote-tracking branches the remote no longer has (also `fetch.prune`)
+        #[arg(long)]
+        prune: bool,
+        /// Also delete local tags the remote no longer has (also `fetch.prune_tags`)
+        #[arg(long)]
+        prune_tags: bool,
+    },
+    /// Prune reflogs, gc, repack and write the commit-graph of repositories
+    Maintain {
+        #[arg(
+            required_unless_present = "all",
+            conflicts_with = "all",
+            add = ArgValueCandidates::new(completion::repo_candidates)
+        )]
+        repos: Vec<String>,
+        /// Maintain every managed repository
+        #[arg(long, short)]
+        all: bool,
+        /// Number of repositories to maintain at once (default: one per CPU)
+        #[arg(short, long, value_name = "N", value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..))]
+        jobs: Option<usize>,
+        /// Skip repositories maintained within this many days
+        #[arg(long, value_name = "DAYS")]
+        older_than: Option<u64>,
+    },
+    /// Show remotes, default branch, worktrees, size and last fetch of a repository
+    Info {
+        #[arg(add = ArgValueCandidates::new(completion::repo_candidates))]
+        repo: String,
+        /// Print the details as a JSON object (same as --output json)
+        #[arg(long)]
+        json: bool,
+    },
+    /// Remove a repository and all its worktrees
+    #[command(alias = "rm")]
+    Remove {
+        #[arg(add = ArgValueCandidates::new(completion::repo_candidates))]
+        repo: String,
+        /// Remove without asking for confirmation
+        #[arg(long, short)]
+        yes: bool,
+    },
+    /// Move a repository to a new host/owner/repo after a rename or transfer
+    #[command(alias = "move")]
+    Rename {
+        #[arg(add = ArgValueCandidates::new(completion::repo_candidates))]
+        repo: String,
+        /// New owner/repo, or host/owner/repo to change the host too
+        new_spec: String,
+        /// Point the origin remote at the new location
+        #[arg(long)]
+        update_remote: bool,
+    },
+    /// Update a fork's default branch from its upstream remote and push it to origin
+    SyncFork {
+        #[arg(
+            required_unless_present = "all",
+            conflicts_with = "all",
+            add = ArgValueCandidates::new(completion::repo_candidates)
+        )]
+        repos: Vec<String>,
+        /// Sync every managed repository with an upstream remote
+        #[arg(long, short)]
+        all: bool,
+        /// Replay commits upstream doesn't have on top of it instead of failing
+        #[arg(long)]
+        rebase: bool,
+        /// Update the local branch without pushing it to origin
+        #[arg(long)]
+        no_push: bool,
+    },
+    /// Compress a repository into <root>/.neoghq/archive and remove it from the root
+    Archive {
+        #[arg(add = ArgValueCandidates::new(completion::repo_candidates))]
+        repo: String,
+        /// Keep only the bare repository, leaving out the worktrees
+        #[arg(long)]
+        bare_only: bool,
+    },
+    /// Convert a plain `git clone` into a bare repository with a worktree
+    Adopt {
+        /// Path of the clone, or with --in-place also a repository spec
+        repo: String,
+        /// Convert the clone where it is instead of moving it to where its origin belongs
+        #[arg(long)]
+        in_place: bool,
+    },
+    /// Remove every worktree of a repository, keeping the bare repository
+    Compact {
+        #[arg(add = ArgValueCandidates::new(completion::repo_candidates))]
+        repo: String,
+    },
+    /// Restore an archived repository
+    Unarchive {
+        /// Archived repository as host/owner/repo or owner/repo
+        repo: String,
+    },
+    /// List a repository first in repo list and the picker
+    Pin {
+        #[arg(add = ArgValueCandidates::new(completion::repo_candidates))]
+        repo: String,
+    },
+    /// Stop listing a repository first
+    Unpin {
+        #[arg(add = ArgValueCandidates::new(completion::repo_candidates))]
+        repo: String,
+    },
+    /// Install the shared git hooks from [git_hooks] into repositories
+    Hooks {
+        #[command(subcommand)]
+        command: HooksCommands,
+    },
+    /// Manage the remotes of repositories
+    Remote {
+        #[command(subcommand)]
+        command: RemoteCommands,
+    },
+    /// Group repositories with tags such as client-a, oss or archived
+    Tag {
+        #[command(subcommand)]
+        command: TagCommands,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum IndexCommands {
+    /// Walk every root again and rewrite its <root>/.neoghq/index.json
+    Rebuild,
+}
+
+#[derive(Subcommand)]
+pub enum DaemonCommands {
+    /// Start the daemon in the background
+    Start,
+    /// Stop the running daemon
+    Stop,
+    /// Show whether the daemon is running and what it has cached
+    Status {
+        /// Print the state as JSON (same as --output json)
+        #[arg(long)]
+        json: bool,
+    },
+    /// Run the daemon in the foreground, e.g. under systemd or launchd
+    Run,
+}
+
+#[derive(Subcommand)]
+pub enum TrashCommands {
+    /// List the removals in the trash, most recent first
+    List {
+        /// Print the entries as a JSON array (same as --output json)
+        #[arg(long)]
+        json: bool,
+    },
+    /// Move a removal back where it was (default: the most recent one)
+    Restore {
+        /// Entry ID from `trash list`
+        id: Option<String>,
+    },
+    /// Delete everything in the trash for good
+    Empty,
+}
+
+#[derive(Subcommand)]
+pub enum WorkspaceCommands {
+    /// Create a workspace from repositories, each owner/repo or owner/repo@worktree
+    Create {
+        name: String,
+        #[arg(required = true, add = ArgValueCandidates::new(completion::repo_candidates))]
+        repos: Vec<String>,
+        /// Replace an existing workspace of the same name
+        #[arg(long, short)]
+        force: bool,
+    },
+    /// Print the workspace directory, which links to each worktree and holds an editor workspace file
+    Switch { name: String },
+    /// List workspaces, or the worktrees of one workspace
+    List { name: Option<String> },
+    /// Run a command in every worktree of a workspace
+    Exec {
+        name: String,
+        /// Number of worktrees to run the command in at once (default: one per CPU)
+        #[arg(short, long, value_name = "N", value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..))]
+        jobs: Option<usize>,
+        /// The command and its arguments, after --
+        #[arg(last = true, required = true, value_name = "COMMAND")]
+        command: Vec<String>,
+    },
+    /// Delete a workspace, keeping its repositories
+    #[command(alias = "rm")]
+    Remove { name: String },
+    /// Write a VS Code workspace covering every worktree of a repository
+    Code {
+        #[arg(add = ArgValueCandidates::new(completion::repo_candidates))]
+        repo: String,
+        /// Open the workspace file with editor.command, $VISUAL or $EDITOR
+        #[arg(long)]
+        open: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum HooksCommands {
+    /// Copy the template hooks or set core.hooksPath in existing repositories
+    Sync {
+        #[arg(
+            required_unless_present = "all",
+            conflicts_with = "all",
+            add = ArgValueCandidates::new(completion::repo_candidates)
+        )]
+        repos: Vec<String>,
+        /// Sync every managed repository
+        #[arg(long, short)]
+        all: bool,
+        /// Replace hooks that differ from the templates
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum RemoteCommands {
+    /// List the remotes and their URLs
+    List {
+        #[arg(
+            required_unless_present = "all",
+            conflicts_with = "all",
+            add = ArgValueCandidates::new(completion::repo_candidates)
+        )]
+        repos: Vec<String>,
+        /// List the remotes of every managed repository
+        #[arg(long, short)]
+        all: bool,
+    },
+    /// Add a remote; the URL may use {host}, {owner} and {repo}
+    Add {
+        name: String,
+        url: String,
+        #[arg(
+            required_unless_present = "all",
+            conflicts_with = "all",
+            add = ArgValueCandidates::new(completion::repo_candidates)
+        )]
+        repos: Vec<String>,
+        /// Add the remote to every managed repository
+        #[arg(long, short)]
+        all: bool,
+    },
+    /// Remove a remote
+    #[command(alias = "rm")]
+    Remove {
+        name: String,
+        #[arg(
+            required_unless_present = "all",
+            conflicts_with = "all",
+            add = ArgValueCandidates::new(completion::repo_candidates)
+        )]
+        repos: Vec<String>,
+        /// Remove the remote from every managed repository that has it
+        #[arg(long, short)]
+        all: bool,
+    },
+    /// Change the URL of a remote, to a URL that may use {host}, {owner} and {repo}
+    SetUrl {
+        name: String,
+        /// New URL; with --replace, the first repository
+        #[arg(required_unless_present = "replace")]
+        url: Option<String>,
+        #[arg(add = ArgValueCandidates::new(completion::repo_candidates))]
+        repos: Vec<String>,
+        /// Change the remote of every managed repository that has it
+        #[arg(long, short)]
+        all: bool,
+        /// Replace OLD with NEW in the current URL instead, e.g. an old hostname
+        #[arg(long, num_args = 2, value_names = ["OLD", "NEW"])]
+        replace: Option<Vec<String>>,
+    },
+    /// Check whether origin moved, as renamed and transferred repositories redirect
+    Update {
+        #[arg(
+            required_unless_present = "all",
+            conflicts_with = "all",
+            add = ArgValueCandidates::new(completion::repo_candidates)
+        )]
+        repos: Vec<String>,
+        /// Check every managed repository
+        #[arg(long, short)]
+        all: bool,
+        /// Point origin at the URL it redirects to
+        #[arg(long)]
+        follow_redirect: bool,
+        /// Also move the repository to the host/owner/repo of the new URL
+        #[arg(long, requires = "follow_redirect")]
+        rename: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum TagCommands {
+    /// Add tags to a repository
+    Add {
+        #[arg(add = ArgValueCandidates::new(completion::repo_candidates))]
+        repo: String,
+        #[arg(required = true)]
+        tags: Vec<String>,
+    },
+    /// Remove tags from a repository
+    #[command(alias = "rm")]
+    Remove {
+        #[arg(add = ArgValueCandidates::new(completion::repo_candidates))]
+        repo: String,
+        #[arg(required = true)]
+        tags: Vec<String>,
+    },
+    /// List the tags of a repository, or every tag in use
+    List {
+        #[arg(add = ArgValueCandidates::new(completion::repo_candidates))]
+        repo: Option<String>,
+    },
+}
+
+/// Which repositories and worktrees `grep` and `find` search.
+#[derive(Args, Debug, Clone)]
+pub struct SearchScope {
+    /// Only repositories whose host/owner/repo matches this glob or ends with this text
+    #[arg(long, value_name = "GLOB", add = ArgValueCandidates::new(completion::repo_candidates))]
+    pub repo: Option<String>,
+    /// Worktrees to search: 'default', 'all', or a worktree directory name
+    #[arg(long, value_name = "WORKTREE", default_value = "default")]
+    pub worktree: String,
+    /// Number of worktrees to search at once (default: one per CPU)
+    #[arg(short, long, value_name = "N", value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..))]
+    pub jobs: Option<usize>,
 }
 
 #[derive(Subcommand)]
 pub enum WorktreeCommands {
     /// Create worktree from default branch
-    Create { branch: String },
+    Create {
+        #[arg(
+            required_unless_present = "issue",
+            conflicts_with = "issue",
+            add = ArgValueCandidates::new(completion::branch_candidates)
+        )]
+        branch: Option<String>,
+        /// Name the branch after an issue, by number on origin's forge or by URL
+        #[arg(long, value_name = "N|URL")]
+        issue: Option<String>,
+        /// Start a new branch from this local branch and record it as its stack parent
+        #[arg(long, value_name = "BRANCH", add = ArgValueCandidates::new(completion::branch_candidates))]
+        stack_on: Option<String>,
+        /// Open the worktree with editor.command, $VISUAL or $EDITOR
+        #[arg(long)]
+        open: bool,
+    },
     /// Navigate to specified worktree
-    Switch { branch: String },
+    Switch {
+        /// Branch or worktree directory to switch to; choose one in the picker when omitted
+        #[arg(
+            add = ArgValueCandidates::new(completion::worktree_candidates),
+            required_if_eq_any([("exists_only", "true"), ("quiet", "true")])
+        )]
+        branch: Option<String>,
+        #[command(flatten)]
+        flags: SwitchFlags,
+    },
+    /// Open a worktree in a tmux session named repo@branch, or with open.command
+    Open {
+        /// Branch or worktree directory to open; choose one in the picker when omitted
+        #[arg(add = ArgValueCandidates::new(completion::worktree_candidates))]
+        branch: Option<String>,
+        /// Create or attach to a tmux session rooted at the worktree
+        #[arg(long)]
+        tmux: bool,
+    },
     /// Remove worktree
     #[command(alias = "rm")]
-    Remove { branch: String },
-    /// Remove worktrees merged to default branch
-    Clean,
+    Remove {
+        #[arg(add = ArgValueCandidates::new(completion::worktree_candidates))]
+        branch: String,
+        /// Remove the worktree even if it has uncommitted changes
+        #[arg(long, short)]
+        force: bool,
+    },
+    /// Create a detached worktree for experiments that expires, at a revision or HEAD
+    Scratch {
+        /// Revision to check out (default: the current worktree's HEAD)
+        reference: Option<String>,
+        /// How long to keep it, such as 30m, 12h, 2d or 1w (default: 1d)
+        #[arg(long, value_name = "DURATION")]
+        ttl: Option<String>,
+    },
+    /// Create a detached worktree for reviewing a pull request or branch of origin
+    Review {
+        /// Pull request number, #number or URL, or branch of origin
+        target: String,
+        /// Make the checked-out files read-only
+        #[arg(long)]
+        read_only: bool,
+    },
+    /// Remove worktrees merged to default branch, expired scratch worktrees and finished reviews
+    Clean {
+        /// Also remove worktrees whose upstream branch was deleted on the remote
+        #[arg(long)]
+        gone: bool,
+    },
     /// Show status of all worktrees
-    Status,
-    /// List all managed worktrees
-    List,
+    Status {
+        /// Print worktree status as a JSON array (same as --output json)
+        #[arg(long)]
+        json: bool,
+        /// Show the files and lines each worktree changes against the default branch
+        #[arg(long)]
+        diffstat: bool,
+    },
+    /// Diff two worktrees, uncommitted changes included, or any two revisions
+    Diff {
+        /// Worktree, by branch or directory, or revision to diff from
+        #[arg(add = ArgValueCandidates::new(completion::worktree_candidates))]
+        from: String,
+        /// Worktree, by branch or directory, or revision to diff to
+        #[arg(add = ArgValueCandidates::new(completion::worktree_candidates))]
+        to: String,
+        /// Print the changed files and their line counts instead of the patch
+        #[arg(long)]
+        stat: bool,
+    },
+    /// Show branch, upstream and changed files of a worktree (default: the current one)
+    Info {
+        #[arg(add = ArgValueCandidates::new(completion::worktree_candidates))]
+        branch: Option<String>,
+        /// Print the details as a JSON object (same as --output json)
+        #[arg(long)]
+        json: bool,
+    },
+    /// Set or clear upstream tracking for worktree branches
+    FixUpstream,
+    /// Push the current worktree's branch, tracking it on the remote after the first push
+    Push {
+        /// Overwrite the remote branch, unless it changed since it was last fetched
+        #[arg(long)]
+        force_with_lease: bool,
+    },
+    /// Rebase the current worktree's branch onto its stack parent or the default branch
+    Rebase {
+        /// Also restack the branches stacked on it, each onto its rebased parent
+        #[arg(long)]
+        stack: bool,
+        /// Resolve conflicts with git mergetool in the branch's worktree and carry on instead of stopping
+        #[arg(long)]
+        resolve: bool,
+    },
+    /// List the worktrees of the current repository
+    List {
+        /// Print worktrees as a JSON array (same as --output json)
+        #[arg(long)]
+        json: bool,
+        /// Print each worktree using a template such as '{branch}\t{path}'
+        #[arg(long, value_name = "TEMPLATE", conflicts_with = "json")]
+        format: Option<String>,
+        /// Terminate entries with NUL instead of newline, for xargs -0 and fzf --read0
+        #[arg(short = '0', long, conflicts_with = "json")]
+        print0: bool,
+        /// Order worktrees by name, modification time, last switch, or size
+        #[arg(long, value_name = "KEY")]
+        sort: Option<SortKey>,
+        /// Indent stacked branches under their stack parent
+        #[arg(long, conflicts_with_all = ["json", "format"])]
+        stack: bool,
+    },
+}
+
+/// Finds the `--config` argument before the command line is parsed, so the
+/// file's `[defaults]` can be applied.
+pub fn find_config_arg(args: &[String]) -> Option<PathBuf> {
+    let mut args = args.iter().skip(1).take_while(|arg| *arg != "--");
+    while let Some(arg) = args.next() {
+        if arg == "--config" {
+            return args.next().map(PathBuf::from);
+        }
+        if let Some(path) = arg.strip_prefix("--config=") {
+            return Some(PathBuf::from(path));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_switch_requires_target_when_scripted() {
+        assert!(Cli::try_parse_from(["neoghq", "repo", "switch"]).is_ok());
+        assert!(Cli::try_parse_from(["neoghq", "repo", "switch", "--quiet"]).is_err());
+        assert!(Cli::try_parse_from(["neoghq", "worktree", "switch", "--exists-only"]).is_err());
+        assert!(Cli::try_parse_from(["neoghq", "worktree", "switch", "-q", "main"]).is_ok());
+        assert!(Cli::try_parse_from(["neoghq", "-vv", "repo", "list"]).is_ok());
+        assert!(Cli::try_parse_from(["neoghq", "-v", "-q", "repo", "list"]).is_err());
+    }
+
+    #[test]
+    fn test_remote_set_url_forms() {
+        let set_url = |argv: &[&str]| {
+            let cli =
+                Cli::try_parse_from(["neoghq", "repo", "remote", "set-url"].iter().chain(argv))
+                    .ok()?;
+            match cli.command {
+                Commands::Repo {
+                    command:
+                        RepoCommands::Remote {
+                            command:
+                                RemoteCommands::SetUrl {
+                                    url,
+                                    repos,
+                                    all,
+                                    replace,
+                                    ..
+                                },
+                        },
+                } => Some((url, repos, all, replace)),
+                _ => None,
+            }
+        };
+        let (url, repos, _, replace) = set_url(&["origin", "git@x:{owner}/{repo}", "a/b"]).unwrap();
+        assert_eq!(
+            (url.as_deref(), repos, replace),
+            (Some("git@x:{owner}/{repo}"), args(&["a/b"]), None)
+        );
+        // With --replace, the URL slot holds the first repository
+        let (url, repos, _, replace) =
+            set_url(&["origin", "a/b", "c/d", "--replace", "old", "new"]).unwrap();
+        assert_eq!(url.as_deref(), Some("a/b"));
+        assert_eq!(repos, args(&["c/d"]));
+        assert_eq!(replace, Some(args(&["old", "new"])));
+        let (url, _, all, _) = set_url(&["origin", "--all", "--replace", "old", "new"]).unwrap();
+        assert_eq!((url, all), (None, true));
+        assert!(set_url(&["origin"]).is_none());
+    }
+
+    #[test]
+    fn test_find_config_arg() {
+        assert_eq!(
+            find_config_arg(&args(&["neoghq", "--config", "/tmp/a.toml", "root"])),
+            Some(PathBuf::from("/tmp/a.toml"))
+        );
+        assert_eq!(
+            find_config_arg(&args(&["neoghq", "repo", "list", "--config=b.toml"])),
+            Some(PathBuf::from("b.toml"))
+        );
+        assert_eq!(find_config_arg(&args(&["neoghq", "root"])), None);
+        assert_eq!(
+            find_config_arg(&args(&["neoghq", "run", "--", "--config", "x"])),
+            None
+        );
+    }
 }
@@ -0,0 +1,199 @@
+use crate::{
+    commands::repo::switch::{find_repository_path, not_found_message},
+    config::Config,
+    dry_run, exit,
+    lock::RepoLock,
+    scan::RepoEntry,
+    transport::run_git,
+};
+use anyhow::{Context, Result, anyhow, bail};
+use git2::{Oid, Repository};
+use std::path::Path;
+use tracing::{info, warn};
+
+/// Finds the commit of `repo` between the `good` commits and `bad` that
+/// first makes `command` fail, with `git bisect run` in a detached worktree
+/// of its own, so none of the repository's worktrees are checked out
+/// elsewhere. The worktree is removed afterwards, whether or not the
+/// bisection finished.
+pub fn execute(
+    config: Config,
+    repo: String,
+    good: Vec<String>,
+    bad: String,
+    command: Vec<String>,
+) -> Result<()> {
+    let Some(repo_path) = find_repository_path(&config, &repo)? else {
+        return Err(exit::not_found(false, not_found_message(&config, &repo)?));
+    };
+    let entry = RepoEntry::from_path(&repo_path)
+        .ok_or_else(|| anyhow!("Not a repository directory: {}", repo_path.display()))?;
+    let bare_repo_path = entry.bare_repository_path();
+    let repo = Repository::open_bare(&bare_repo_path)?;
+    let bad = resolve(&repo, &bad)?;
+    let good = good
+        .iter()
+        .map(|good| resolve(&repo, good))
+        .collect::<Result<Vec<_>>>()?;
+    if command.is_empty() {
+        bail!("No command given");
+    }
+
+    let worktree = std::env::temp_dir().join(format!("neoghq-bisect-{}", std::process::id()));
+    let mut culprit = None;
+    dry_run::apply(
+        &config,
+        format_args!(
+            "bisect {} in {} with `{}`",
+            entry.spec(),
+            worktree.display(),
+            command.join(" ")
+        ),
+        || {
+            culprit = Some(bisect(&bare_repo_path, &worktree, bad, &good, &command)?);
+            Ok(())
+        },
+    )?;
+    let Some(culprit) = culprit else {
+        return Ok(());
+    };
+
+    let commit = repo.find_commit(culprit)?;
+    println!(
+        "{} is the first bad commit: {}",
+        commit.id(),
+        commit.summary().unwrap_or_default()
+    );
+    let author = commit.author();
+    println!(
+        "Author: {} <{}>",
+        author.name().unwrap_or_default(),
+        author.email().unwrap_or_default()
+    );
+    Ok(())
+}
+
+/// Resolves `revision` to a commit of `repo`.
+fn resolve(repo: &Repository, revision: &str) -> Result<Oid> {
+    Ok(repo
+        .revparse_single(revision)
+        .and_then(|object| object.peel_to_commit())
+        .with_context(|| format!("No commit {revision}"))?
+        .id())
+}
+
+/// Adds a detached worktree at `worktree`, bisects in it and removes it,
+/// returning the first bad commit.
+fn bisect(
+    bare_repo_path: &Path,
+    worktree: &Path,
+    bad: Oid,
+    good: &[Oid],
+    command: &[String],
+) -> Result<Oid> {
+    let path = worktree.to_string_lossy();
+    {
+        let _lock = RepoLock::acquire(bare_repo_path)?;
+        run_git(
+            Some(bare_repo_path),
+            true,
+            &[
+                "worktree",
+                "add",
+                "--quiet",
+                "--detach",
+                &path,
+                &bad.to_string(),
+            ],
+        )?;
+    }
+    info!("Bisecting in {}", worktree.display());
+    let result = run_bisect(worktree, bad, good, command);
+    if let Err(error) = remove_worktree(bare_repo_path, worktree) {
+        warn!(
+            "Failed to remove the bisect worktree {}: {error:#}",
+            worktree.display()
+        );
+    }
+    result
+}
+
+fn run_bisect(worktree: &Path, bad: Oid, good: &[Oid], command: &[String]) -> Result<Oid> {
+    let revisions = std::iter::once(bad)
+        .chain(good.iter().copied())
+        .map(|id| id.to_string())
+        .collect::<Vec<_>>();
+    let mut args = vec!["bisect", "start"];
+    args.extend(revisions.iter().map(String::as_str));
+    run_git(Some(worktree), true, &args)?;
+
+    let mut args = vec!["bisect", "run"];
+    args.extend(command.iter().map(String::as_str));
+    run_git(Some(worktree), true, &args)?;
+
+    // Bisect refs are per worktree, so they go before the worktree does
+    Repository::open(worktree)?
+        .refname_to_id("refs/bisect/bad")
+        .context("git bisect run didn't find the first bad commit")
+}
+
+fn remove_worktree(bare_repo_path: &Path, worktree: &Path) -> Result<()> {
+    let _lock = RepoLock::acquire(bare_repo_path)?;
+    run_git(
+        Some(bare_repo_path),
+        true,
+        &["worktree", "remove", "--force", &worktree.to_string_lossy()],
+    )
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use crate::test_utils::{commit_all, create_managed_repository};
+
+    #[test]
+    fn test_bisect_finds_first_bad_commit() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo_dir = temp_dir.path().join("github.com/user/repo");
+        let bare = create_managed_repository(&repo_dir);
+        let main_path = repo_dir.join("main");
+        let main = Repository::open(&main_path).unwrap();
+        let good = main.head().unwrap().target().unwrap();
+        let mut commits = Vec::new();
+        for step in 1..=6 {
+            std::fs::write(main_path.join("STEP"), step.to_string()).unwrap();
+            if step == 4 {
+                std::fs::write(main_path.join("BROKEN"), "").unwrap();
+            }
+            commits.push(commit_all(&main, &format!("Step {step}")));
+        }
+        std::fs::write(main_path.join("WIP"), "").unwrap();
+
+        let worktree = temp_dir.path().join("bisect");
+        let command = ["sh", "-c", "test ! -e BROKEN"].map(String::from);
+        let culprit = bisect(bare.path(), &worktree, commits[5], &[good], &command).unwrap();
+        assert_eq!(culprit, commits[3]);
+        assert!(!worktree.exists());
+        assert!(bare.find_worktree("bisect").is_err());
+        // The user's worktree is untouched
+        assert_eq!(main.head().unwrap().target(), Some(commits[5]));
+        assert!(main_path.join("WIP").is_file());
+
+        let config = Config {
+            root: temp_dir.path().to_path_buf(),
+            ..Default::default()
+        };
+        let run = |bad: &str| {
+            execute(
+                config.clone(),
+                "user/repo".to_string(),
+                vec![good.to_string()],
+                bad.to_string(),
+                command.to_vec(),
+            )
+        };
+        run("main").unwrap();
+        assert!(run("missing").is_err());
+    }
+}
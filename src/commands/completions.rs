@@ -0,0 +1,50 @@
+use crate::cli::{Cli, CompletionShell};
+use anyhow::Result;
+use clap::CommandFactory;
+use clap_complete::env::{Bash, Elvish, EnvCompleter, Fish, Powershell, Zsh};
+use std::io::Write;
+
+pub fn execute(shell: CompletionShell) -> Result<()> {
+    write_completions(shell, &mut std::io::stdout())
+}
+
+/// Writes the completion script for `shell`. Apart from nushell, the script
+/// calls back into neoghq so repository and branch names complete too.
+fn write_completions(shell: CompletionShell, out: &mut impl Write) -> Result<()> {
+    let completer: &dyn EnvCompleter = match shell {
+        CompletionShell::Bash => &Bash,
+        CompletionShell::Zsh => &Zsh,
+        CompletionShell::Fish => &Fish,
+        CompletionShell::Elvish => &Elvish,
+        CompletionShell::Powershell => &Powershell,
+        CompletionShell::Nushell => {
+            clap_complete::generate(
+                clap_complete_nushell::Nushell,
+                &mut Cli::command(),
+                "neoghq",
+                out,
+            );
+            return Ok(());
+        }
+    };
+
+    completer.write_registration("COMPLETE", "neoghq", "neoghq", "neoghq", out)?;
+    Ok(())
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use clap::ValueEnum;
+
+    #[test]
+    fn test_write_completions() {
+        for shell in CompletionShell::value_variants() {
+            let mut out = Vec::new();
+            write_completions(*shell, &mut out).unwrap();
+            let script = String::from_utf8(out).unwrap();
+            assert!(script.contains("neoghq"), "{shell:?}");
+        }
+    }
+}
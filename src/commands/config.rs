@@ -0,0 +1,1075 @@
+use crate::config::{Config, MAX_DAEMON_FETCH_INTERVAL_MINUTES, Origin, Setting};
+use anyhow::{Context, Result, anyhow};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use toml_edit::{DocumentMut, Item, Table, Value};
+
+/// The type of value a configuration key accepts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ValueKind {
+    String,
+    Path,
+    Enum(&'static [&'static str]),
+    /// A whole number from zero up to the given maximum.
+    Integer(u64),
+    /// `true` or `false`.
+    Bool,
+    /// An array of strings, edited directly in the file.
+    List,
+}
+
+/// A key accepted in the configuration file. `*` matches a single
+/// user-chosen name (a root name or a host).
+struct KeySpec {
+    pattern: &'static [&'static str],
+    kind: ValueKind,
+    default: Option<&'static str>,
+}
+
+const SCHEMA: &[KeySpec] = &[
+    KeySpec {
+        pattern: &["general", "root"],
+        kind: ValueKind::Path,
+        default: Some("~/src/repos"),
+    },
+    KeySpec {
+        pattern: &["general", "user"],
+        kind: ValueKind::String,
+        default: None,
+    },
+    KeySpec {
+        pattern: &["general", "default_host"],
+        kind: ValueKind::String,
+        default: None,
+    },
+    KeySpec {
+        pattern: &["general", "crash_reports"],
+        kind: ValueKind::Enum(&["panic", "always", "never"]),
+        default: Some("panic"),
+    },
+    KeySpec {
+        pattern: &["git", "default_branch"],
+        kind: ValueKind::String,
+        default: Some("main"),
+    },
+    KeySpec {
+        pattern: &["git", "backend"],
+        kind: ValueKind::Enum(&["libgit2", "gix"]),
+        default: Some("libgit2"),
+    },
+    KeySpec {
+        pattern: &["clone", "protocol"],
+        kind: ValueKind::Enum(&["https", "ssh"]),
+        default: Some("https"),
+    },
+    KeySpec {
+        pattern: &["clone", "fork_upstream"],
+        kind: ValueKind::Bool,
+        default: Some("true"),
+    },
+    KeySpec {
+        pattern: &["worktree", "issue_branch"],
+        kind: ValueKind::String,
+        default: Some("{user}/{issue}-{slug}"),
+    },
+    KeySpec {
+        pattern: &["worktree", "issue_description"],
+        kind: ValueKind::Bool,
+        default: Some("true"),
+    },
+    KeySpec {
+        pattern: &["git_hooks", "template_dir"],
+        kind: ValueKind::Path,
+        default: None,
+    },
+    KeySpec {
+        pattern: &["git_hooks", "hooks_path"],
+        kind: ValueKind::Path,
+        default: None,
+    },
+    KeySpec {
+        pattern: &["picker", "command"],
+        kind: ValueKind::String,
+        default: None,
+    },
+    KeySpec {
+        pattern: &["open", "command"],
+        kind: ValueKind::String,
+        default: None,
+    },
+    KeySpec {
+        pattern: &["editor", "command"],
+        kind: ValueKind::String,
+        default: None,
+    },
+    KeySpec {
+        pattern: &["prompt", "format"],
+        kind: ValueKind::String,
+        default: Some("{owner}/{repo}@{branch}{dirty}"),
+    },
+    KeySpec {
+        pattern: &["ui", "color"],
+        kind: ValueKind::Enum(&["auto", "always", "never"]),
+        default: Some("auto"),
+    },
+    KeySpec {
+        pattern: &["ui", "icons"],
+        kind: ValueKind::Enum(&["none", "nerd-font"]),
+        default: Some("none"),
+    },
+    KeySpec {
+        pattern: &["trash", "retention_days"],
+        kind: ValueKind::Integer(i64::MAX as u64),
+        default: Some("30"),
+    },
+    KeySpec {
+        pattern: &["daemon", "fetch_interval_minutes"],
+        kind: ValueKind::Integer(MAX_DAEMON_FETCH_INTERVAL_MINUTES),
+        default: Some("30"),
+    },
+    KeySpec {
+        pattern: &["daemon", "status_interval_seconds"],
+        kind: ValueKind::Integer(i64::MAX as u64),
+        default: Some("60"),
+    },
+    KeySpec {
+        pattern: &["daemon", "watch"],
+        kind: ValueKind::Bool,
+        default: Some("true"),
+    },
+    KeySpec {
+        pattern: &["fetch", "follow_redirects"],
+        kind: ValueKind::Bool,
+        default: Some("false"),
+    },
+    KeySpec {
+        pattern: &["fetch", "prune"],
+        kind: ValueKind::Bool,
+        default: Some("false"),
+    },
+    KeySpec {
+        pattern: &["fetch", "prune_tags"],
+        kind: ValueKind::Bool,
+        default: Some("false"),
+    },
+    KeySpec {
+        pattern: &["roots", "*"],
+        kind: ValueKind::Path,
+        default: None,
+    },
+    KeySpec {
+        pattern: &["hosts", "*", "root"],
+        kind: ValueKind::Path,
+        default: None,
+    },
+    KeySpec {
+        pattern: &["hosts", "*", "protocol"],
+        kind: ValueKind::Enum(&["https", "ssh"]),
+        default: None,
+    },
+    KeySpec {
+        pattern: &["hosts", "*", "forge"],
+        kind: ValueKind::Enum(&["github", "gitlab"]),
+        default: None,
+    },
+    KeySpec {
+        pattern: &["hosts", "*", "api_url"],
+        kind: ValueKind::String,
+        default: None,
+    },
+    KeySpec {
+        pattern: &["aliases", "*", "host"],
+        kind: ValueKind::String,
+        default: None,
+    },
+    KeySpec {
+        pattern: &["aliases", "*", "protocol"],
+        kind: ValueKind::Enum(&["https", "ssh"]),
+        default: None,
+    },
+    KeySpec {
+        pattern: &["templates", "*"],
+        kind: ValueKind::String,
+        default: None,
+    },
+    KeySpec {
+        pattern: &["repo", "*", "default_worktree"],
+        kind: ValueKind::String,
+        default: None,
+    },
+    KeySpec {
+        pattern: &["repo", "*", "links"],
+        kind: ValueKind::List,
+        default: None,
+    },
+    KeySpec {
+        pattern: &["repo", "*", "sparse"],
+        kind: ValueKind::List,
+        default: None,
+    },
+    KeySpec {
+        pattern: &["repo", "*", "render", "*"],
+        // Relative to the repository directory, so not checked for existence
+        kind: ValueKind::String,
+        default: None,
+    },
+    KeySpec {
+        pattern: &["repo", "*", "daemon"],
+        kind: ValueKind::Bool,
+        default: None,
+    },
+    KeySpec {
+        pattern: &["repo", "*", "hooks", "post_create"],
+        kind: ValueKind::List,
+        default: None,
+    },
+    KeySpec {
+        pattern: &["defaults", "*"],
+        kind: ValueKind::List,
+        default: None,
+    },
+    KeySpec {
+        pattern: &["profile", "*", "root"],
+        kind: ValueKind::Path,
+        default: None,
+    },
+    KeySpec {
+        pattern: &["profile", "*", "default_host"],
+        kind: ValueKind::String,
+        default: None,
+    },
+    KeySpec {
+        pattern: &["profile", "*", "ssh_key"],
+        kind: ValueKind::Path,
+        default: None,
+    },
+    KeySpec {
+        pattern: &["profile", "*", "token_env"],
+        kind: ValueKind::String,
+        default: None,
+    },
+    KeySpec {
+        pattern: &["profile", "*", "token_command"],
+        kind: ValueKind::String,
+        default: None,
+    },
+    KeySpec {
+        pattern: &["profile", "*", "user_name"],
+        kind: ValueKind::String,
+        default: None,
+    },
+    KeySpec {
+        pattern: &["profile", "*", "user_email"],
+        kind: ValueKind::String,
+        default: None,
+    },
+];
+
+/// Prints the value of `key` in effect, wherever it comes from, as
+/// `config list --origin` shows it.
+pub fn get(config: Config, key: String) -> Result<()> {
+    println!("{}", effective_value(&config, &key)?);
+    Ok(())
+}
+
+/// Returns the value of `key` a flag, environment variable, profile,
+/// gitconfig or the file sets, else its default.
+fn effective_value(config: &Config, key: &str) -> Result<String> {
+    let path = config_file_path(config)?;
+    let (segments, spec) = resolve_key(key)?;
+    let document = load_document(&path)?;
+
+    effective_settings(config, &path, &document)
+        .remove(&format_key(&segments))
+        .map(|setting| setting.value)
+        .or_else(|| spec.default.map(str::to_string))
+        .ok_or_else(|| anyhow!("{key} is not set"))
+}
+
+pub fn set(config: Config, key: String, value: String) -> Result<()> {
+    let path = config_file_path(&config)?;
+    let (segments, spec) = resolve_key(&key)?;
+    validate_value(spec, &value).with_context(|| format!("Invalid value for {key}"))?;
+
+    let mut document = load_document(&path)?;
+    set_value(&mut document, &segments, typed_value(spec, &value))?;
+    save_document(&path, &document)
+}
+
+pub fn unset(config: Config, key: String) -> Result<()> {
+    let path = config_file_path(&config)?;
+    let (segments, _) = resolve_key(&key)?;
+
+    let mut document = load_document(&path)?;
+    if !remove_value(&mut document, &segments) {
+        return Err(anyhow!("{key} is not set"));
+    }
+    save_document(&path, &document)
+}
+
+pub fn list(config: Config, json: bool, origin: bool) -> Result<()> {
+    let path = config_file_path(&config)?;
+    let document = load_document(&path)?;
+
+    if origin {
+        for (key, setting) in effective_settings(&config, &path, &document) {
+            println!("{}\t{key} = {}", setting.origin, setting.value);
+        }
+    } else if json {
+        let value: toml::Value = toml::from_str(&document.to_string())?;
+        println!("{}", serde_json::to_string_pretty(&value)?);
+    } else {
+        for (key, value) in list_values(&document) {
+            println!("{key} = {value}");
+        }
+    }
+
+    Ok(())
+}
+
+/// A problem found by `config validate`.
+#[derive(Debug, PartialEq, Eq)]
+enum Problem {
+    Error(String),
+    Warning(String),
+}
+
+pub fn validate(config: Config) -> Result<()> {
+    let path = config_file_path(&config)?;
+    if !path.exists() {
+        println!("No configuration file at {}", path.display());
+        return Ok(());
+    }
+
+    let problems = validate_file(&path, dirs::home_dir().as_deref())?;
+    let mut errors = 0;
+    for problem in &problems {
+        match problem {
+            Problem::Error(message) => {
+                errors += 1;
+                eprintln!("error: {message}");
+            }
+            Problem::Warning(message) => eprintln!("warning: {message}"),
+        }
+    }
+
+    if errors > 0 {
+        return Err(anyhow!("{} has {errors} error(s)", path.display()));
+    }
+    println!("{} is valid", path.display());
+    Ok(())
+}
+
+/// Type-checks the configuration file, flags unknown keys, and verifies
+/// that referenced paths and hook programs exist.
+fn validate_file(path: &Path, home: Option<&Path>) -> Result<Vec<Problem>> {
+    let mut value = crate::config::read_config_value(path)?;
+    let mut problems = Vec::new();
+
+    if let Err(error) = crate::config::interpolate_env(&mut value, &|name| std::env::var(name).ok())
+    {
+        problems.push(Problem::Error(error.to_string()));
+    }
+    check_value(&value, &mut Vec::new(), home, &mut problems);
+    if problems
+        .iter()
+        .all(|problem| matches!(problem, Problem::Warning(_)))
+        && let Err(error) = crate::config::check_config_value(value)
+    {
+        problems.push(Problem::Error(format!("{error:#}")));
+    }
+
+    Ok(problems)
+}
+
+fn check_value(
+    value: &toml::Value,
+    prefix: &mut Vec<String>,
+    home: Option<&Path>,
+    problems: &mut Vec<Problem>,
+) {
+    if let toml::Value::Table(table) = value {
+        for (key, value) in table {
+            prefix.push(key.clone());
+            check_value(value, prefix, home, problems);
+            prefix.pop();
+        }
+        return;
+    }
+
+    let key = format_key(prefix);
+    let Some(spec) = find_spec(prefix) else {
+        problems.push(Problem::Error(format!("unknown key {key}")));
+        return;
+    };
+
+    match (spec.kind, value) {
+        (ValueKind::String | ValueKind::Path, toml::Value::String(text)) if text.is_empty() => {
+            problems.push(Problem::Error(format!("{key} must not be empty")));
+        }
+        (ValueKind::String, toml::Value::String(_)) => {}
+        (ValueKind::Path, toml::Value::String(text)) => {
+            let path = match (Path::new(text).strip_prefix("~").ok(), home) {
+                (Some(rest), Some(home)) => home.join(rest),
+                _ => PathBuf::from(text),
+            };
+            if !path.exists() {
+                problems.push(Problem::Warning(format!(
+                    "{key} refers to a missing path: {}",
+                    path.display()
+                )));
+            }
+        }
+        (ValueKind::Enum(choices), toml::Value::String(text)) => {
+            if !choices.contains(&text.as_str()) {
+                problems.push(Problem::Error(format!(
+                    "{key} must be one of {}, got {text}",
+                    choices.join(", ")
+                )));
+            }
+        }
+        (ValueKind::Integer(max), toml::Value::Integer(number)) => {
+            if *number < 0 {
+                problems.push(Problem::Error(format!("{key} must not be negative")));
+            } else if *number as u64 > max {
+                problems.push(Problem::Error(format!(
+                    "{key} must be at most {max}, got {number}"
+                )));
+            }
+        }
+        (ValueKind::Integer(_), _) => {
+            problems.push(Problem::Error(format!("{key} must be a whole number")));
+        }
+        (ValueKind::Bool, toml::Value::Boolean(_)) => {}
+        (ValueKind::Bool, _) => {
+            problems.push(Problem::Error(format!("{key} must be true or false")));
+        }
+        (ValueKind::List, toml::Value::Array(values)) => {
+            if values.iter().any(|value| !value.is_str()) {
+                problems.push(Problem::Error(format!("{key} must be a list of strings")));
+            } else if prefix.last().is_some_and(|last| last == "post_create") {
+                for command in values.iter().filter_map(toml::Value::as_str) {
+                    if !program_exists(command) {
+                        problems.push(Problem::Warning(format!(
+                            "{key} runs a program that was not found: {command}"
+                        )));
+                    }
+                }
+            }
+        }
+        (ValueKind::List, _) => {
+            problems.push(Problem::Error(format!("{key} must be a list of strings")));
+        }
+        _ => problems.push(Problem::Error(format!("{key} must be a string"))),
+    }
+}
+
+/// Returns whether the program a shell command starts with can be found.
+fn program_exists(command: &str) -> bool {
+    let Some(program) = command.split_whitespace().next() else {
+        return false;
+    };
+    if program.contains('/') {
+        return Path::new(program).exists();
+    }
+
+    std::env::var_os("PATH")
+        .is_some_and(|paths| std::env::split_paths(&paths).any(|dir| dir.join(program).is_file()))
+}
+
+/// Returns every key set in the file, overlaid with the effective values
+/// the loader resolved from other sources.
+fn effective_settings(
+    config: &Config,
+    path: &Path,
+    document: &DocumentMut,
+) -> BTreeMap<String, Setting> {
+    let mut settings = list_values(document)
+        .into_iter()
+        .map(|(key, value)| {
+            let origin = Origin::File(path.to_path_buf());
+            (key, Setting { value, origin })
+        })
+        .collect::<BTreeMap<_, _>>();
+
+    for (key, setting) in &config.origins {
+        let key = format_key(&parse_key(key).unwrap_or_else(|_| vec![key.clone()]));
+        let from_file = matches!(&setting.origin, Origin::File(_));
+        if !from_file || !settings.contains_key(&key) {
+            settings.insert(key, setting.clone());
+        }
+    }
+    settings
+}
+
+pub(super) fn config_file_path(config: &Config) -> Result<PathBuf> {
+    config
+        .config_path
+        .clone()
+        .ok_or_else(|| anyhow!("Could not determine the configuration file path"))
+}
+
+/// Splits a dotted key into table segments, honoring `"quoted.segments"`
+/// and the `root` shorthand for `general.root`.
+fn parse_key(key: &str) -> Result<Vec<String>> {
+    let mut segments = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+
+    for c in key.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            '.' if !quoted => segments.push(std::mem::take(&mut current)),
+            c => current.push(c),
+        }
+    }
+    segments.push(current);
+
+    if quoted || segments.iter().any(String::is_empty) {
+        return Err(anyhow!("Invalid configuration key: {key}"));
+    }
+
+    if segments.len() == 1 {
+        segments.insert(0, "general".to_string());
+    }
+
+    Ok(segments)
+}
+
+/// Parses `key` and finds the schema entry it refers to. Unquoted hosts
+/// containing dots (`hosts.gitlab.example.com.root`) are joined back up.
+fn resolve_key(key: &str) -> Result<(Vec<String>, &'static KeySpec)> {
+    let mut segments = parse_key(key)?;
+
+    if segments[0] == "hosts" && segments.len() > 3 {
+        let last = segments.pop().unwrap();
+        let host = segments.drain(1..).collect::<Vec<_>>().join(".");
+        segments.extend([host, last]);
+    }
+    if segments[0] == "roots" && segments.len() > 2 {
+        let name = segments.drain(1..).collect::<Vec<_>>().join(".");
+        segments.push(name);
+    }
+
+    let spec = find_spec(&segments).ok_or_else(|| anyhow!("Unknown configuration key: {key}"))?;
+
+    Ok((segments, spec))
+}
+
+fn find_spec(segments: &[String]) -> Option<&'static KeySpec> {
+    SCHEMA.iter().find(|spec| {
+        spec.pattern.len() == segments.len()
+            && spec
+                .pattern
+                .iter()
+                .zip(segments)
+                .all(|(pattern, segment)| *pattern == "*" || pattern == segment)
+    })
+}
+
+fn validate_value(spec: &KeySpec, value: &str) -> Result<()> {
+    match spec.kind {
+        ValueKind::String | ValueKind::Path if value.is_empty() => {
+            Err(anyhow!("value must not be empty"))
+        }
+        ValueKind::Enum(choices) if !choices.contains(&value) => Err(anyhow!(
+            "expected one of {}, got {value}",
+            choices.join(", ")
+        )),
+        ValueKind::Integer(_) if value.parse::<u64>().is_err() => {
+            Err(anyhow!("expected a whole number, got {value}"))
+        }
+        ValueKind::Integer(max) if value.parse::<u64>().is_ok_and(|number| number > max) => {
+            Err(anyhow!("expected a whole number up to {max}, got {value}"))
+        }
+        ValueKind::Bool if value.parse::<bool>().is_err() => {
+            Err(anyhow!("expected true or false, got {value}"))
+        }
+        ValueKind::List => Err(anyhow!(
+            "this key takes a list of strings; edit the configuration file directly"
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Writes a new configuration file holding only `values`, checked against
+/// the schema as `config set` does.
+pub(super) fn write_file(path: &Path, values: &[(&str, String)]) -> Result<()> {
+    let mut document = DocumentMut::new();
+    for (key, value) in values {
+        let (segments, spec) = resolve_key(key)?;
+        validate_value(spec, value).with_context(|| format!("Invalid value for {key}"))?;
+        set_value(&mut document, &segments, typed_value(spec, value))?;
+    }
+    save_document(path, &document)
+}
+
+fn load_document(path: &Path) -> Result<DocumentMut> {
+    if !path.exists() {
+        return Ok(DocumentMut::new());
+    }
+
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read config file: {}", path.display()))?;
+    content
+        .parse::<DocumentMut>()
+        .with_context(|| format!("Failed to parse config file: {}", path.display()))
+}
+
+fn save_document(path: &Path, document: &DocumentMut) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, document.to_string())
+        .with_context(|| format!("Failed to write config file: {}", path.display()))
+}
+
+/// Converts a validated command-line `value` to the TOML type of `spec`.
+fn typed_value(spec: &KeySpec, value: &str) -> Value {
+    match (spec.kind, value.parse::<i64>(), value.parse::<bool>()) {
+        (ValueKind::Integer(_), Ok(number), _) => Value::from(number),
+        (ValueKind::Bool, _, Ok(flag)) => Value::from(flag),
+        _ => Value::from(value),
+    }
+}
+
+fn set_value(document: &mut DocumentMut, segments: &[String], value: Value) -> Result<()> {
+    let (key, tables) = segments.split_last().unwrap();
+    let mut table = document.as_table_mut();
+    for segment in tables {
+        let item = table.entry(segment).or_insert_with(|| {
+            let mut table = Table::new();
+            table.set_implicit(true);
+            Item::Table(table)
+        });
+        table = item
+            .as_table_mut()
+            .ok_or_else(|| anyhow!("{segment} is not a table"))?;
+    }
+
+    match table.get_mut(key).and_then(Item::as_value_mut) {
+        // Keep the comments and formatting around an existing value
+        Some(existing) => {
+            let decor = existing.decor().clone();
+            *existing = value;
+            *existing.decor_mut() = decor;
+        }
+        None => {
+            table.insert(key, toml_edit::value(value));
+        }
+    }
+    Ok(())
+}
+
+fn remove_value(document: &mut DocumentMut, segments: &[String]) -> bool {
+    let (key, tables) = segments.split_last().unwrap();
+    let mut table = document.as_table_mut();
+    for segment in tables {
+        match table.get_mut(segment).and_then(Item::as_table_mut) {
+            Some(inner) => table = inner,
+            None => return false,
+        }
+    }
+    table.remove(key).is_some()
+}
+
+fn list_values(document: &DocumentMut) -> Vec<(String, String)> {
+    let mut values = Vec::new();
+    collect_values(document.as_table(), &mut Vec::new(), &mut values);
+    values
+}
+
+fn collect_values(table: &Table, prefix: &mut Vec<String>, values: &mut Vec<(String, String)>) {
+    for (key, item) in table.iter() {
+        prefix.push(key.to_string());
+        match item {
+            Item::Table(table) => collect_values(table, prefix, values),
+            Item::Value(value) => values.push((format_key(prefix), display_value(value))),
+            _ => {}
+        }
+        prefix.pop();
+    }
+}
+
+fn format_key(segments: &[String]) -> String {
+    segments
+        .iter()
+        .map(|segment| {
+            if !segment
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+            {
+                format!("\"{segment}\"")
+            } else {
+                segment.clone()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+fn display_value(value: &Value) -> String {
+    match value.as_str() {
+        Some(value) => value.to_string(),
+        None => value.clone().decorated("", "").to_string(),
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+
+    fn create_config(temp_dir: &Path) -> Config {
+        Config {
+            config_path: Some(temp_dir.join("neoghq/config.toml")),
+            ..Default::default()
+        }
+    }
+
+    fn segments(key: &str) -> Vec<String> {
+        resolve_key(key).unwrap().0
+    }
+
+    #[test]
+    fn test_resolve_key() {
+        assert_eq!(segments("root"), ["general", "root"]);
+        assert_eq!(segments("general.root"), ["general", "root"]);
+        assert_eq!(segments("clone.protocol"), ["clone", "protocol"]);
+        assert_eq!(segments("roots.work"), ["roots", "work"]);
+        assert_eq!(
+            segments("hosts.gitlab.company.com.root"),
+            ["hosts", "gitlab.company.com", "root"]
+        );
+        assert_eq!(
+            segments("hosts.\"gitlab.company.com\".root"),
+            ["hosts", "gitlab.company.com", "root"]
+        );
+        assert_eq!(
+            segments("profile.work.token_env"),
+            ["profile", "work", "token_env"]
+        );
+    }
+
+    #[test]
+    fn test_set_integer_key() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config = create_config(temp_dir.path());
+        set(config.clone(), "trash.retention_days".into(), "7".into()).unwrap();
+        let content = std::fs::read_to_string(config.config_path.unwrap()).unwrap();
+        assert!(content.contains("retention_days = 7"));
+
+        let config = create_config(temp_dir.path());
+        assert!(set(config, "trash.retention_days".into(), "soon".into()).is_err());
+    }
+
+    #[test]
+    fn test_set_bool_key() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config = create_config(temp_dir.path());
+        let key = "repo.\"github.com/user/big\".daemon";
+        set(config.clone(), key.into(), "false".into()).unwrap();
+        let content = std::fs::read_to_string(config.config_path.unwrap()).unwrap();
+        assert!(content.contains("daemon = false"));
+
+        let config = create_config(temp_dir.path());
+        assert!(set(config, key.into(), "no".into()).is_err());
+    }
+
+    #[test]
+    fn test_resolve_key_rejects_unknown_keys() {
+        assert!(resolve_key("general.unknown").is_err());
+        assert!(resolve_key("unknown.key").is_err());
+        assert!(resolve_key("hosts.github.com").is_err());
+        assert!(resolve_key("general..root").is_err());
+        assert!(resolve_key("hosts.\"github.com.root").is_err());
+    }
+
+    #[test]
+    fn test_validate_value() {
+        let (_, spec) = resolve_key("clone.protocol").unwrap();
+        assert!(validate_value(spec, "ssh").is_ok());
+        assert!(validate_value(spec, "ftp").is_err());
+
+        let (_, spec) = resolve_key("root").unwrap();
+        assert!(validate_value(spec, "~/repos").is_ok());
+        assert!(validate_value(spec, "").is_err());
+
+        let (_, spec) = resolve_key("trash.retention_days").unwrap();
+        assert!(validate_value(spec, &i64::MAX.to_string()).is_ok());
+        assert!(validate_value(spec, &u64::MAX.to_string()).is_err());
+
+        let (_, spec) = resolve_key("daemon.fetch_interval_minutes").unwrap();
+        let max = MAX_DAEMON_FETCH_INTERVAL_MINUTES;
+        assert!(validate_value(spec, &max.to_string()).is_ok());
+        assert!(validate_value(spec, &(max + 1).to_string()).is_err());
+        assert!(validate_value(spec, &i64::MAX.to_string()).is_err());
+    }
+
+    fn get_value(document: &DocumentMut, segments: &[String]) -> Option<String> {
+        let mut item = document.as_item();
+        for segment in segments {
+            item = item.get(segment)?;
+        }
+        item.as_value().map(display_value)
+    }
+
+    #[test]
+    fn test_set_and_get() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config = create_config(temp_dir.path());
+        let path = config.config_path.clone().unwrap();
+
+        set(config.clone(), "clone.protocol".into(), "ssh".into()).unwrap();
+        set(
+            config.clone(),
+            "hosts.gitlab.company.com.root".into(),
+            "/work".into(),
+        )
+        .unwrap();
+
+        let document = load_document(&path).unwrap();
+        assert_eq!(
+            get_value(&document, &segments("clone.protocol")).as_deref(),
+            Some("ssh")
+        );
+        assert_eq!(
+            get_value(&document, &segments("hosts.gitlab.company.com.root")).as_deref(),
+            Some("/work")
+        );
+        assert!(get(config.clone(), "clone.protocol".into()).is_ok());
+        // Unset keys fall back to their default, if any
+        assert!(get(config.clone(), "git.default_branch".into()).is_ok());
+        assert!(get(config, "general.user".into()).is_err());
+    }
+
+    #[test]
+    fn test_get_prefers_overrides_to_the_file() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = create_config(temp_dir.path());
+        set(config.clone(), "root".into(), "~/src".into()).unwrap();
+        set(config.clone(), "clone.protocol".into(), "ssh".into()).unwrap();
+        config.origins.insert(
+            "general.root".to_string(),
+            Setting {
+                value: "/override".to_string(),
+                origin: Origin::Flag("--root"),
+            },
+        );
+
+        assert_eq!(effective_value(&config, "root").unwrap(), "/override");
+        assert_eq!(effective_value(&config, "clone.protocol").unwrap(), "ssh");
+        assert_eq!(
+            effective_value(&config, "git.default_branch").unwrap(),
+            "main"
+        );
+    }
+
+    #[test]
+    fn test_set_rejects_invalid_values() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config = create_config(temp_dir.path());
+
+        assert!(set(config.clone(), "clone.protocol".into(), "ftp".into()).is_err());
+        assert!(set(config.clone(), "unknown".into(), "value".into()).is_err());
+        assert!(!config.config_path.unwrap().exists());
+    }
+
+    #[test]
+    fn test_set_preserves_comments() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config = create_config(temp_dir.path());
+        let path = config.config_path.clone().unwrap();
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(
+            &path,
+            "# neoghq configuration\n[general]\n# where repositories live\nroot = \"~/src\" # personal\n",
+        )
+        .unwrap();
+
+        set(config, "root".into(), "~/repos".into()).unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            content,
+            "# neoghq configuration\n[general]\n# where repositories live\nroot = \"~/repos\" # personal\n"
+        );
+    }
+
+    #[test]
+    fn test_unset() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config = create_config(temp_dir.path());
+        let path = config.config_path.clone().unwrap();
+
+        set(config.clone(), "roots.work".into(), "/work".into()).unwrap();
+        unset(config.clone(), "roots.work".into()).unwrap();
+
+        let document = load_document(&path).unwrap();
+        assert_eq!(get_value(&document, &segments("roots.work")), None);
+        assert!(unset(config, "roots.work".into()).is_err());
+    }
+
+    #[test]
+    fn test_list_values() {
+        let document =
+            "[general]\nroot = \"~/src\"\n\n[hosts.\"gitlab.company.com\"]\nroot = \"/work\"\n"
+                .parse::<DocumentMut>()
+                .unwrap();
+
+        assert_eq!(
+            list_values(&document),
+            vec![
+                ("general.root".to_string(), "~/src".to_string()),
+                (
+                    "hosts.\"gitlab.company.com\".root".to_string(),
+                    "/work".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_list() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config = create_config(temp_dir.path());
+        set(config.clone(), "root".into(), "~/src".into()).unwrap();
+
+        assert!(list(config.clone(), false, false).is_ok());
+        assert!(list(config.clone(), true, false).is_ok());
+        assert!(list(config, false, true).is_ok());
+    }
+
+    #[test]
+    fn test_validate_file() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("config.toml");
+        std::fs::write(
+            &path,
+            format!(
+                r#"
+[general]
+root = "{root}"
+crash_reports = "sometimes"
+colour = "auto"
+
+[roots]
+missing = "~/does-not-exist"
+
+[repo."github.com/org/app".hooks]
+post_create = ["sh -c true", "definitely-not-a-program --flag"]
+
+[defaults]
+"repo list" = "--tsv"
+"#,
+                root = temp_dir.path().display()
+            ),
+        )
+        .unwrap();
+
+        let problems = validate_file(&path, Some(temp_dir.path())).unwrap();
+
+        assert_eq!(
+            problems,
+            vec![
+                Problem::Error("defaults.\"repo list\" must be a list of strings".to_string()),
+                Problem::Error("unknown key general.colour".to_string()),
+                Problem::Error(
+                    "general.crash_reports must be one of panic, always, never, got sometimes"
+                        .to_string()
+                ),
+                Problem::Warning(
+                    "repo.\"github.com/org/app\".hooks.post_create runs a program that was not found: definitely-not-a-program --flag"
+                        .to_string()
+                ),
+                Problem::Warning(format!(
+                    "roots.missing refers to a missing path: {}",
+                    temp_dir.path().join("does-not-exist").display()
+                )),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_valid_file() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config = create_config(temp_dir.path());
+        set(config.clone(), "clone.protocol".into(), "ssh".into()).unwrap();
+
+        assert!(validate(config).is_ok());
+    }
+
+    #[test]
+    fn test_validate_invalid_file() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config = create_config(temp_dir.path());
+        let path = config.config_path.clone().unwrap();
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, "[general]\nunknown = 1\n").unwrap();
+
+        assert!(validate(config).is_err());
+    }
+
+    #[test]
+    fn test_set_rejects_list_keys() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config = create_config(temp_dir.path());
+
+        let result = set(config, "defaults.root".into(), "--x".into());
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_effective_settings() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("config.toml");
+        let document = "[general]\nroot = \"~/src\"\nuser = \"me\"\n"
+            .parse::<DocumentMut>()
+            .unwrap();
+        let config = Config {
+            origins: BTreeMap::from([
+                (
+                    "general.root".to_string(),
+                    Setting {
+                        value: "/override".to_string(),
+                        origin: Origin::Env("NEOGHQ_ROOT"),
+                    },
+                ),
+                (
+                    "general.user".to_string(),
+                    Setting {
+                        value: "me".to_string(),
+                        origin: Origin::File(path.clone()),
+                    },
+                ),
+                (
+                    "git.default_branch".to_string(),
+                    Setting {
+                        value: "main".to_string(),
+                        origin: Origin::Default,
+                    },
+                ),
+            ]),
+            ..Default::default()
+        };
+
+        let settings = effective_settings(&config, &path, &document)
+            .into_iter()
+            .map(|(key, setting)| format!("{}\t{key} = {}", setting.origin, setting.value))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            settings,
+            [
+                "env:NEOGHQ_ROOT\tgeneral.root = /override".to_string(),
+                format!("file:{}\tgeneral.user = me", path.display()),
+                "default\tgit.default_branch = main".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_config_file_path_missing() {
+        assert!(config_file_path(&Config::default()).is_err());
+    }
+}
@@ -0,0 +1,209 @@
+use crate::{
+    cli::OutputFormat,
+    config::Config,
+    daemon::{self, DaemonInfo},
+};
+use anyhow::{Context, Result, bail};
+use serde::Serialize;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+use tracing::info;
+
+/// How long `daemon start` waits for the new daemon to answer.
+const START_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The daemon's state, as printed by `daemon status --json`.
+#[derive(Debug, Serialize)]
+struct DaemonStatus {
+    running: bool,
+    #[serde(flatten)]
+    info: Option<DaemonInfo>,
+}
+
+/// Starts `neoghq daemon run` in the background with the same configuration
+/// file, profile and root, logging to `<cache dir>/daemon.log`.
+pub fn start(config: Config) -> Result<()> {
+    if let Some(info) = daemon::info(&config)? {
+        info!("The daemon is already running (PID {})", info.pid);
+        return Ok(());
+    }
+    let socket = daemon::socket_path(&config)?;
+    let log_path = socket.with_file_name("daemon.log");
+    if let Some(parent) = log_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let log = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&log_path)
+        .with_context(|| format!("Failed to open {}", log_path.display()))?;
+
+    let mut command = run_command(&config)?;
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(log);
+    detach(&mut command);
+    let mut child = command.spawn().context("Failed to start the daemon")?;
+
+    let started = Instant::now();
+    while started.elapsed() < START_TIMEOUT {
+        if let Some(info) = daemon::info(&config)? {
+            info!("Started the daemon (PID {})", info.pid);
+            return Ok(());
+        }
+        if let Some(status) = child.try_wait()? {
+            bail!(
+                "The daemon exited with {status}; see {}",
+                log_path.display()
+            );
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
+    bail!(
+        "The daemon didn't answer within {} seconds; see {}",
+        START_TIMEOUT.as_secs(),
+        log_path.display()
+    )
+}
+
+/// `neoghq daemon run` with the configuration file, profile and `--root` this
+/// process was started with.
+fn run_command(config: &Config) -> Result<Command> {
+    let mut command = Command::new(std::env::current_exe()?);
+    if let Some(config_path) = &config.config_path {
+        command.arg("--config").arg(config_path);
+    }
+    if let Some(profile) = &config.profile {
+        command.args(["--profile", profile]);
+    }
+    if let Some(root) = config.root_flag() {
+        command.arg("--root").arg(root);
+    }
+    command.args(["daemon", "run"]);
+    Ok(command)
+}
+
+/// Keeps the daemon running after the terminal that started it closes.
+#[cfg(unix)]
+fn detach(command: &mut Command) {
+    use std::os::unix::process::CommandExt;
+    command.process_group(0);
+}
+
+#[cfg(not(unix))]
+fn detach(_command: &mut Command) {}
+
+pub fn stop(config: Config) -> Result<()> {
+    if daemon::stop(&config)? {
+        info!("Stopped the daemon");
+    } else {
+        info!("The daemon is not running");
+    }
+    Ok(())
+}
+
+pub fn status(config: Config, json: bool) -> Result<()> {
+    let info = daemon::info(&config)?;
+    if json || config.output == OutputFormat::Json {
+        let status = DaemonStatus {
+            running: info.is_some(),
+            info,
+        };
+        println!("{}", serde_json::to_string_pretty(&status)?);
+        return Ok(());
+    }
+
+    let Some(info) = info else {
+        println!("The daemon is not running");
+        return Ok(());
+    };
+    println!("Running (PID {}) since {}", info.pid, info.started);
+    println!("Socket: {}", info.socket.display());
+    println!(
+        "Repositories: {} cached, {} disabled",
+        info.repositories, info.disabled
+    );
+    println!(
+        "Last refresh: {}",
+        info.last_refresh.as_deref().unwrap_or("pending")
+    );
+    println!(
+        "Next fetch: {}",
+        info.next_fetch.as_deref().unwrap_or("none scheduled")
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stop_and_status_without_daemon() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config = Config {
+            cache_dir: Some(temp_dir.path().to_path_buf()),
+            ..Default::default()
+        };
+
+        assert!(stop(config.clone()).is_ok());
+        assert!(status(config.clone(), false).is_ok());
+        assert!(status(config, true).is_ok());
+    }
+
+    #[test]
+    fn test_run_command_forwards_flags() {
+        let args = |config: &Config| {
+            run_command(config)
+                .unwrap()
+                .get_args()
+                .map(|arg| arg.to_string_lossy().into_owned())
+                .collect::<Vec<_>>()
+        };
+        let mut config = Config {
+            config_path: Some("/etc/neoghq.toml".into()),
+            profile: Some("work".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            args(&config),
+            [
+                "--config",
+                "/etc/neoghq.toml",
+                "--profile",
+                "work",
+                "daemon",
+                "run"
+            ]
+        );
+
+        config.use_only_root("/work/repos").unwrap();
+        assert_eq!(
+            args(&config),
+            [
+                "--config",
+                "/etc/neoghq.toml",
+                "--profile",
+                "work",
+                "--root",
+                "/work/repos",
+                "daemon",
+                "run"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_daemon_status_json() {
+        let status = DaemonStatus {
+            running: false,
+            info: None,
+        };
+        assert_eq!(
+            serde_json::to_value(&status).unwrap(),
+            serde_json::json!({ "running": false })
+        );
+    }
+}
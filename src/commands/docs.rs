@@ -0,0 +1,197 @@
+use crate::cli::Cli;
+use anyhow::{Context, Result};
+use clap::{Arg, ArgAction, Command, CommandFactory};
+use std::io::Write;
+use std::path::Path;
+use tracing::info;
+
+/// Writes a man page for neoghq and each of its subcommands into `out_dir`,
+/// or prints the top-level page without one.
+pub fn man(out_dir: Option<&Path>) -> Result<()> {
+    let Some(out_dir) = out_dir else {
+        let command = Cli::command();
+        clap_mangen::Man::new(command).render(&mut std::io::stdout())?;
+        return Ok(());
+    };
+    std::fs::create_dir_all(out_dir)?;
+    clap_mangen::generate_to(Cli::command(), out_dir)
+        .with_context(|| format!("Failed to write man pages to {}", out_dir.display()))?;
+    info!("Wrote man pages to {}", out_dir.display());
+    Ok(())
+}
+
+/// Prints a Markdown reference of every command, argument and option.
+pub fn markdown() -> Result<()> {
+    write_markdown(&mut std::io::stdout())
+}
+
+fn write_markdown(out: &mut impl Write) -> Result<()> {
+    let mut command = Cli::command().disable_help_subcommand(true);
+    command.build();
+    writeln!(out, "# Command-Line Reference")?;
+    writeln!(out)?;
+    writeln!(
+        out,
+        "Generated by `neoghq docs markdown` from the CLI definitions."
+    )?;
+    write_command(out, &command, true)
+}
+
+fn write_command(out: &mut impl Write, command: &Command, root: bool) -> Result<()> {
+    let name = command
+        .get_bin_name()
+        .unwrap_or_else(|| command.get_name())
+        .to_string();
+    writeln!(out)?;
+    writeln!(out, "## `{name}`")?;
+    if let Some(about) = command.get_long_about().or(command.get_about()) {
+        writeln!(out)?;
+        writeln!(out, "{about}")?;
+    }
+    let usage = command.clone().render_usage().to_string();
+    writeln!(out)?;
+    writeln!(
+        out,
+        "**Usage:** `{}`",
+        usage.trim_start_matches("Usage:").trim()
+    )?;
+
+    let subcommands = command
+        .get_subcommands()
+        .filter(|subcommand| !subcommand.is_hide_set())
+        .collect::<Vec<_>>();
+    if !subcommands.is_empty() {
+        writeln!(out)?;
+        writeln!(out, "**Commands:**")?;
+        writeln!(out)?;
+        for subcommand in &subcommands {
+            let about = subcommand
+                .get_about()
+                .map(|about| format!(" — {about}"))
+                .unwrap_or_default();
+            writeln!(out, "* `{}`{about}", subcommand.get_name())?;
+        }
+    }
+
+    // Global options are documented once, on the top-level command
+    let args = command
+        .get_arguments()
+        .filter(|arg| !arg.is_hide_set() && (root || !arg.is_global_set()))
+        .collect::<Vec<_>>();
+    let (positionals, options): (Vec<_>, Vec<_>) =
+        args.into_iter().partition(|arg| arg.is_positional());
+    for (title, args) in [("Arguments", positionals), ("Options", options)] {
+        if args.is_empty() {
+            continue;
+        }
+        writeln!(out)?;
+        writeln!(out, "**{title}:**")?;
+        writeln!(out)?;
+        for arg in args {
+            writeln!(out, "* {}", describe(arg))?;
+        }
+    }
+
+    for subcommand in subcommands {
+        write_command(out, subcommand, false)?;
+    }
+    Ok(())
+}
+
+/// Formats an argument as `` `-s`, `--long <VALUE>` — help [values] ``.
+fn describe(arg: &Arg) -> String {
+    let value = arg
+        .get_value_names()
+        .map(|names| {
+            names
+                .iter()
+                .map(|name| format!("<{name}>"))
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .unwrap_or_else(|| format!("<{}>", arg.get_id().as_str().to_uppercase()));
+    let takes_value = matches!(arg.get_action(), ArgAction::Set | ArgAction::Append);
+    let mut flags = Vec::new();
+    if let Some(short) = arg.get_short() {
+        flags.push(format!("-{short}"));
+    }
+    if let Some(long) = arg.get_long() {
+        flags.push(format!("--{long}"));
+    }
+    let mut text = if arg.is_positional() {
+        format!("`{value}`")
+    } else if takes_value {
+        let last = flags.pop().unwrap_or_default();
+        flags.push(format!("{last} {value}"));
+        flags
+            .iter()
+            .map(|flag| format!("`{flag}`"))
+            .collect::<Vec<_>>()
+            .join(", ")
+    } else {
+        flags
+            .iter()
+            .map(|flag| format!("`{flag}`"))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    if let Some(help) = arg.get_long_help().or(arg.get_help()) {
+        text.push_str(&format!(" — {help}"));
+    }
+    let values = arg
+        .get_possible_values()
+        .iter()
+        .filter(|value| !value.is_hide_set())
+        .map(|value| format!("`{}`", value.get_name()))
+        .collect::<Vec<_>>();
+    if !values.is_empty() && takes_value {
+        text.push_str(&format!(" (one of {})", values.join(", ")));
+    }
+    let defaults = arg
+        .get_default_values()
+        .iter()
+        .map(|value| value.to_string_lossy().into_owned())
+        .collect::<Vec<_>>();
+    if !defaults.is_empty() {
+        text.push_str(&format!(" (default: `{}`)", defaults.join(" ")));
+    }
+    text
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_markdown_covers_every_command() {
+        let mut out = Vec::new();
+        write_markdown(&mut out).unwrap();
+        let markdown = String::from_utf8(out).unwrap();
+
+        assert!(markdown.starts_with("# Command-Line Reference\n"));
+        assert!(markdown.contains("\n## `neoghq repo get`\n"));
+        assert!(markdown.contains("\n## `neoghq worktree switch`\n"));
+        assert!(
+            markdown
+                .contains("* `--protocol <PROTOCOL>` — Protocol of the URL built from owner/repo")
+        );
+        assert!(markdown.contains("* `<URL>`"));
+        // Global options only appear on the top-level command
+        assert_eq!(markdown.matches("`--dry-run`").count(), 1);
+        assert_eq!(markdown.matches("`--root <ROOT>`").count(), 1);
+    }
+
+    #[test]
+    fn test_man_writes_a_page_per_command() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let out_dir = temp_dir.path().join("man");
+
+        man(Some(&out_dir)).unwrap();
+        assert!(out_dir.join("neoghq.1").is_file());
+        assert!(out_dir.join("neoghq-repo-get.1").is_file());
+        assert!(out_dir.join("neoghq-docs-man.1").is_file());
+        let page = std::fs::read_to_string(out_dir.join("neoghq-repo-get.1")).unwrap();
+        assert!(page.contains(".TH neoghq-repo-get 1"));
+    }
+}
@@ -0,0 +1,538 @@
+use super::relocate;
+use crate::{
+    cli::OutputFormat,
+    config::Config,
+    dry_run,
+    scan::{self, RepoEntry},
+    status,
+    style::{Color, Style},
+    transport,
+};
+use anyhow::{Result, anyhow};
+use git2::{BranchType, Repository, WorktreeLockStatus, WorktreePruneOptions};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::ffi::OsString;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// A layout problem, as printed by `doctor --json`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+struct Problem {
+    kind: ProblemKind,
+    path: PathBuf,
+    message: String,
+    /// Whether `--fix` can repair it.
+    fixable: bool,
+    /// Whether `--fix` repaired it.
+    fixed: bool,
+    #[serde(skip)]
+    repair: Option<Repair>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+enum ProblemKind {
+    /// A directory that can't be read.
+    Unreadable,
+    /// A repository directory without a usable bare repository at `.git`.
+    BrokenRepository,
+    /// A regular clone instead of a bare repository with worktrees.
+    PlainClone,
+    /// A worktree whose `.git` file and the bare repository's record of it
+    /// don't point at each other, usually after moving either.
+    BrokenWorktreeLink,
+    /// A worktree the bare repository records but whose directory is gone.
+    StaleWorktree,
+    NoWorktrees,
+    /// HEAD of the bare repository doesn't name an existing branch.
+    MissingDefaultBranch,
+}
+
+/// A repair `--fix` may apply without losing work.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Repair {
+    /// Rewrites both links with `git worktree repair`.
+    RelinkWorktree { bare: PathBuf, worktree: PathBuf },
+    /// Rewrites the links of a repository after its root moved.
+    Relocate {
+        repo: PathBuf,
+        old_root: PathBuf,
+        new_root: PathBuf,
+    },
+    /// Drops the record of a deleted worktree.
+    PruneWorktree { bare: PathBuf, name: String },
+    /// Points HEAD at `branch`, creating it from `origin/<branch>` if needed.
+    SetDefaultBranch { bare: PathBuf, branch: String },
+}
+
+impl fmt::Display for Repair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::RelinkWorktree { worktree, .. } => {
+                write!(f, "repair the links of worktree {}", worktree.display())
+            }
+            Self::Relocate { repo, old_root, .. } => write!(
+                f,
+                "rewrite the links of {} that point into {}",
+                repo.display(),
+                old_root.display()
+            ),
+            Self::PruneWorktree { bare, name } => {
+                write!(f, "prune stale worktree {name} of {}", bare.display())
+            }
+            Self::SetDefaultBranch { bare, branch } => {
+                write!(
+                    f,
+                    "set the default branch of {} to {branch}",
+                    bare.display()
+                )
+            }
+        }
+    }
+}
+
+impl Repair {
+    fn apply(&self) -> Result<()> {
+        match self {
+            Self::RelinkWorktree { bare, worktree } => transport::run_git(
+                Some(bare),
+                false,
+                &["worktree", "repair", &worktree.to_string_lossy()],
+            ),
+            Self::Relocate {
+                repo,
+                old_root,
+                new_root,
+            } => relocate::relink_repository(repo, old_root, new_root).map(drop),
+            Self::PruneWorktree { bare, name } => {
+                let repo = Repository::open_bare(bare)?;
+                repo.find_worktree(name)?
+                    .prune(Some(&mut WorktreePruneOptions::new()))?;
+                Ok(())
+            }
+            Self::SetDefaultBranch { bare, branch } => {
+                let repo = Repository::open_bare(bare)?;
+                if repo.find_branch(branch, BranchType::Local).is_err() {
+                    let commit = repo
+                        .find_reference(&format!("refs/remotes/origin/{branch}"))?
+                        .peel_to_commit()?;
+                    repo.branch(branch, &commit, false)?
+                        .set_upstream(Some(&format!("origin/{branch}")))?;
+                }
+                // set_head refuses branches checked out in a worktree
+                repo.reference_symbolic(
+                    "HEAD",
+                    &format!("refs/heads/{branch}"),
+                    true,
+                    "neoghq doctor: set default branch",
+                )?;
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Problem {
+    fn new(kind: ProblemKind, path: PathBuf, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            path,
+            message: message.into(),
+            fixable: false,
+            fixed: false,
+            repair: None,
+        }
+    }
+
+    fn with_repair(self, repair: Option<Repair>) -> Self {
+        Self {
+            fixable: repair.is_some(),
+            repair,
+            ..self
+        }
+    }
+}
+
+/// Checks every root for layout problems and prints them; with `fix`,
+/// applies the safe repairs first. Fails while any problem remains.
+pub fn execute(config: Config, fix: bool, json: bool) -> Result<()> {
+    let mut problems = diagnose(&config)?;
+    if fix {
+        for problem in &mut problems {
+            let Some(repair) = problem.repair.clone() else {
+                continue;
+            };
+            let result = dry_run::apply(&config, &repair, || {
+                repair.apply()?;
+                problem.fixed = true;
+                Ok(())
+            });
+            match result {
+                Ok(()) if problem.fixed => info!("Fixed: {repair}"),
+                Ok(()) => {}
+                Err(error) => warn!("Failed to {repair}: {error:#}"),
+            }
+        }
+    }
+
+    if json || config.output == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&problems)?);
+    } else {
+        let style = Style::stdout(&config);
+        for problem in &problems {
+            let note = if problem.fixed {
+                style.paint(Color::Green, " (fixed)")
+            } else if problem.fixable {
+                style.paint(Color::Yellow, " (fixable with --fix)")
+            } else {
+                String::new()
+            };
+            println!("{}: {}{note}", problem.path.display(), problem.message);
+        }
+    }
+
+    match problems.iter().filter(|problem| !problem.fixed).count() {
+        0 => {
+            info!("No problems found");
+            Ok(())
+        }
+        remaining => Err(anyhow!("{remaining} problems need attention")),
+    }
+}
+
+/// Finds the problems of every root, in root order.
+fn diagnose(config: &Config) -> Result<Vec<Problem>> {
+    let mut problems = Vec::new();
+    for root in config.all_roots() {
+        let mut unreadable = Vec::new();
+        let entries = scan::scan_root_lossy(&root.path, &mut unreadable)?;
+        problems.extend(unreadable.into_iter().map(|(path, error)| {
+            Problem::new(
+                ProblemKind::Unreadable,
+                path,
+                format!("can't be read: {error}"),
+            )
+        }));
+        for entry in &entries {
+            if let Err(error) = check_repository(entry, &mut problems) {
+                problems.push(Problem::new(
+                    ProblemKind::BrokenRepository,
+                    entry.path.clone(),
+                    format!("can't be inspected: {error:#}"),
+                ));
+            }
+        }
+    }
+    Ok(problems)
+}
+
+fn check_repository(entry: &RepoEntry, problems: &mut Vec<Problem>) -> Result<()> {
+    let bare = entry.bare_repository_path();
+    if !bare.is_dir() {
+        problems.push(Problem::new(
+            ProblemKind::BrokenRepository,
+            entry.path.clone(),
+            "not a repository: there is no .git directory",
+        ));
+        return Ok(());
+    }
+    for path in [&entry.path, &bare] {
+        if let Err(error) = std::fs::read_dir(path) {
+            problems.push(Problem::new(
+                ProblemKind::Unreadable,
+                path.clone(),
+                format!("can't be read: {error}"),
+            ));
+            return Ok(());
+        }
+    }
+
+    let repo = Repository::open_bare(&bare)?;
+    if repo.config()?.get_bool("core.bare").is_ok_and(|bare| !bare) {
+        problems.push(Problem::new(
+            ProblemKind::PlainClone,
+            entry.path.clone(),
+            format!(
+                "a plain clone rather than a bare repository with worktrees; `neoghq repo adopt --in-place {}` converts it",
+                entry.spec()
+            ),
+        ));
+        return Ok(());
+    }
+
+    let worktrees = entry.worktrees()?;
+    // Records that `git worktree repair` fixes along with a broken link
+    let mut relinked = HashSet::<OsString>::new();
+    for worktree in &worktrees {
+        let Some(admin_dir) = admin_dir(worktree) else {
+            continue;
+        };
+        if links_back(&admin_dir, worktree) {
+            continue;
+        }
+        relinked.extend(admin_dir.file_name().map(ToOwned::to_owned));
+        let problem = match moved_root(entry, &admin_dir) {
+            Some((old_root, new_root)) => Problem::new(
+                ProblemKind::BrokenWorktreeLink,
+                worktree.clone(),
+                format!(
+                    "its links still point into {}; if the whole root moved, `neoghq relocate {} {}` fixes every repository",
+                    old_root.display(),
+                    old_root.display(),
+                    new_root.display()
+                ),
+            )
+            .with_repair(Some(Repair::Relocate {
+                repo: entry.path.clone(),
+                old_root,
+                new_root,
+            })),
+            None => Problem::new(
+                ProblemKind::BrokenWorktreeLink,
+                worktree.clone(),
+                format!(
+                    "its .git file and {} don't point at each other",
+                    admin_dir.display()
+                ),
+            )
+            .with_repair(Some(Repair::RelinkWorktree {
+                bare: bare.clone(),
+                worktree: worktree.clone(),
+            })),
+        };
+        problems.push(problem);
+    }
+
+    let mut registered = 0;
+    for name in repo.worktrees()?.iter().flatten() {
+        if relinked.contains(&OsString::from(name)) {
+            continue;
+        }
+        let worktree = repo.find_worktree(name)?;
+        let locked = matches!(worktree.is_locked(), Ok(WorktreeLockStatus::Locked(_)));
+        if worktree.validate().is_ok() || locked {
+            registered += 1;
+            continue;
+        }
+        problems.push(
+            Problem::new(
+                ProblemKind::StaleWorktree,
+                worktree.path().to_path_buf(),
+                format!("worktree {name} is registered but its directory is gone"),
+            )
+            .with_repair(Some(Repair::PruneWorktree {
+                bare: bare.clone(),
+                name: name.to_string(),
+            })),
+        );
+    }
+
+    if worktrees.is_empty() && registered == 0 {
+        problems.push(Problem::new(
+            ProblemKind::NoWorktrees,
+            entry.path.clone(),
+            "the repository has no worktrees",
+        ));
+    }
+
+    // A repository without commits has no branch to point at yet
+    if repo.is_empty()? {
+        return Ok(());
+    }
+    let message = match status::default_branch(&repo) {
+        Some(branch) if repo.find_branch(&branch, BranchType::Local).is_ok() => return Ok(()),
+        Some(branch) => format!("HEAD names the missing branch {branch}"),
+        None => "HEAD doesn't name a branch".to_string(),
+    };
+    let repair = origin_default_branch(&repo).map(|branch| Repair::SetDefaultBranch {
+        bare: bare.clone(),
+        branch,
+    });
+    problems
+        .push(Problem::new(ProblemKind::MissingDefaultBranch, bare, message).with_repair(repair));
+    Ok(())
+}
+
+/// Returns the directory the `.git` file of `worktree` points at.
+fn admin_dir(worktree: &Path) -> Option<PathBuf> {
+    let content = std::fs::read_to_string(worktree.join(".git")).ok()?;
+    let gitdir = content.trim().strip_prefix("gitdir:")?.trim();
+    Some(worktree.join(gitdir))
+}
+
+/// Returns the root `admin_dir` lies under and the root of `entry`, if
+/// `admin_dir` is where the record would be had the root not moved.
+fn moved_root(entry: &RepoEntry, admin_dir: &Path) -> Option<(PathBuf, PathBuf)> {
+    let root = entry.root()?;
+    let old_root = admin_dir.ancestors().nth(6)?;
+    let expected = old_root
+        .join(entry.spec())
+        .join(".git")
+        .join("worktrees")
+        .join(admin_dir.file_name()?);
+    (old_root != root && admin_dir == expected)
+        .then(|| (old_root.to_path_buf(), root.to_path_buf()))
+}
+
+/// Whether the record at `admin_dir` points back at `worktree`.
+fn links_back(admin_dir: &Path, worktree: &Path) -> bool {
+    let Ok(gitdir) = std::fs::read_to_string(admin_dir.join("gitdir")) else {
+        return false;
+    };
+    let recorded = admin_dir.join(gitdir.trim());
+    match (
+        std::fs::canonicalize(recorded),
+        std::fs::canonicalize(worktree.join(".git")),
+    ) {
+        (Ok(recorded), Ok(actual)) => recorded == actual,
+        _ => false,
+    }
+}
+
+/// Returns the branch `origin/HEAD` points at, if it exists.
+fn origin_default_branch(repo: &Repository) -> Option<String> {
+    let head = repo.find_reference("refs/remotes/origin/HEAD").ok()?;
+    let branch = head
+        .symbolic_target()?
+        .strip_prefix("refs/remotes/origin/")?
+        .to_string();
+    head.resolve().ok()?;
+    Some(branch)
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use crate::test_utils::{create_managed_repository, create_source_repository};
+    use std::fs;
+
+    fn kinds(problems: &[Problem]) -> Vec<ProblemKind> {
+        problems.iter().map(|problem| problem.kind).collect()
+    }
+
+    fn check(path: &Path) -> Vec<Problem> {
+        let mut problems = Vec::new();
+        check_repository(&RepoEntry::from_path(path).unwrap(), &mut problems).unwrap();
+        problems
+    }
+
+    #[test]
+    fn test_healthy_repository() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("github.com/user/repo");
+        create_managed_repository(&path);
+
+        assert_eq!(check(&path), []);
+    }
+
+    #[test]
+    fn test_moved_worktree_is_repaired() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("github.com/user/repo");
+        create_managed_repository(&path);
+        fs::rename(path.join("main"), path.join("moved")).unwrap();
+
+        let problems = check(&path);
+        assert_eq!(kinds(&problems), [ProblemKind::BrokenWorktreeLink]);
+        assert_eq!(problems[0].path, path.join("moved"));
+        problems[0].repair.as_ref().unwrap().apply().unwrap();
+        assert_eq!(check(&path), []);
+    }
+
+    #[test]
+    fn test_moved_root_is_relocated() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let old_root = temp_dir.path().join("old");
+        let new_root = temp_dir.path().join("new");
+        create_managed_repository(&old_root.join("github.com/user/repo"));
+        fs::rename(&old_root, &new_root).unwrap();
+        let path = new_root.join("github.com/user/repo");
+
+        let problems = check(&path);
+        assert_eq!(kinds(&problems), [ProblemKind::BrokenWorktreeLink]);
+        assert_eq!(
+            problems[0].repair,
+            Some(Repair::Relocate {
+                repo: path.clone(),
+                old_root,
+                new_root
+            })
+        );
+        problems[0].repair.as_ref().unwrap().apply().unwrap();
+        assert_eq!(check(&path), []);
+    }
+
+    #[test]
+    fn test_deleted_worktree_is_pruned() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("github.com/user/repo");
+        create_managed_repository(&path);
+        fs::remove_dir_all(path.join("main")).unwrap();
+
+        let problems = check(&path);
+        assert_eq!(
+            kinds(&problems),
+            [ProblemKind::StaleWorktree, ProblemKind::NoWorktrees]
+        );
+        problems[0].repair.as_ref().unwrap().apply().unwrap();
+        assert_eq!(kinds(&check(&path)), [ProblemKind::NoWorktrees]);
+    }
+
+    #[test]
+    fn test_missing_default_branch() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("github.com/user/repo");
+        let bare = create_managed_repository(&path);
+        bare.set_head("refs/heads/gone").unwrap();
+        bare.reference_symbolic(
+            "refs/remotes/origin/HEAD",
+            "refs/remotes/origin/main",
+            true,
+            "test",
+        )
+        .unwrap();
+
+        let problems = check(&path);
+        assert_eq!(kinds(&problems), [ProblemKind::MissingDefaultBranch]);
+        problems[0].repair.as_ref().unwrap().apply().unwrap();
+        assert_eq!(status::default_branch(&bare).as_deref(), Some("main"));
+        assert_eq!(check(&path), []);
+    }
+
+    #[test]
+    fn test_plain_clone_and_stray_directory() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let clone = temp_dir.path().join("github.com/user/clone");
+        create_source_repository(&clone);
+        let stray = temp_dir.path().join("github.com/user/stray");
+        fs::create_dir_all(&stray).unwrap();
+
+        assert_eq!(kinds(&check(&clone)), [ProblemKind::PlainClone]);
+        assert_eq!(kinds(&check(&stray)), [ProblemKind::BrokenRepository]);
+    }
+
+    #[test]
+    fn test_execute() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("github.com/user/repo");
+        create_managed_repository(&path);
+        let config = Config {
+            root: temp_dir.path().to_path_buf(),
+            ..Default::default()
+        };
+        assert!(execute(config.clone(), false, false).is_ok());
+
+        fs::rename(path.join("main"), path.join("moved")).unwrap();
+        assert!(execute(config.clone(), false, true).is_err());
+        let dry_run = Config {
+            dry_run: true,
+            ..config.clone()
+        };
+        assert!(execute(dry_run, true, false).is_err());
+        assert!(execute(config.clone(), true, false).is_ok());
+        assert!(execute(config, false, false).is_ok());
+    }
+}
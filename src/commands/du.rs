@@ -0,0 +1,163 @@
+use crate::{
+    cli::OutputFormat,
+    config::Config,
+    output,
+    scan::{self, RepoEntry},
+    status,
+};
+use anyhow::Result;
+use serde::Serialize;
+use std::path::PathBuf;
+
+/// A repository as printed by `du --json`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+struct RepoUsage {
+    repo: String,
+    path: PathBuf,
+    /// Total bytes of the shared git directory and all worktrees.
+    size: u64,
+    /// Bytes of the shared git directory.
+    git_dir: u64,
+    /// Worktrees, largest first.
+    worktrees: Vec<WorktreeUsage>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+struct WorktreeUsage {
+    path: PathBuf,
+    size: u64,
+}
+
+/// Prints the disk usage of every repository matching `query`, largest
+/// first, with the shared git directory and each worktree below it.
+pub fn execute(
+    config: Config,
+    query: Option<String>,
+    jobs: Option<usize>,
+    json: bool,
+) -> Result<()> {
+    let entries = scan::scan_roots(&config.all_roots())?
+        .into_iter()
+        .filter(|entry| {
+            query
+                .as_deref()
+                .is_none_or(|query| entry.spec().contains(query))
+        })
+        .collect::<Vec<_>>();
+    let usages = repo_usages(&entries, jobs)?;
+
+    if json || config.output == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&usages)?);
+        return Ok(());
+    }
+
+    let mut rows = Vec::new();
+    for usage in &usages {
+        rows.push(vec![output::format_size(usage.size), usage.repo.clone()]);
+        rows.push(vec![
+            output::format_size(usage.git_dir),
+            "  .git".to_string(),
+        ]);
+        for worktree in &usage.worktrees {
+            let name = worktree
+                .path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy();
+            rows.push(vec![
+                output::format_size(worktree.size),
+                format!("  {name}"),
+            ]);
+        }
+    }
+    let total = usages.iter().map(|usage| usage.size).sum();
+    rows.push(vec![output::format_size(total), "total".to_string()]);
+    for line in output::table(&["SIZE", "REPO"], &rows) {
+        println!("{line}");
+    }
+    Ok(())
+}
+
+/// Measures every git directory and worktree of `entries` in parallel.
+fn repo_usages(entries: &[RepoEntry], jobs: Option<usize>) -> Result<Vec<RepoUsage>> {
+    let mut paths = Vec::new();
+    let mut worktree_counts = Vec::new();
+    for entry in entries {
+        let worktrees = entry.worktrees()?;
+        paths.push(entry.bare_repository_path());
+        worktree_counts.push(worktrees.len());
+        paths.extend(worktrees);
+    }
+    let sizes = status::disk_usages(&paths, jobs);
+
+    let mut measured = paths.into_iter().zip(sizes);
+    let mut usages = entries
+        .iter()
+        .zip(worktree_counts)
+        .map(|(entry, count)| {
+            let (_, git_dir) = measured.next().unwrap_or_default();
+            let mut worktrees = measured
+                .by_ref()
+                .take(count)
+                .map(|(path, size)| WorktreeUsage { path, size })
+                .collect::<Vec<_>>();
+            worktrees.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.path.cmp(&b.path)));
+            RepoUsage {
+                repo: entry.spec(),
+                path: entry.path.clone(),
+                size: git_dir + worktrees.iter().map(|worktree| worktree.size).sum::<u64>(),
+                git_dir,
+                worktrees,
+            }
+        })
+        .collect::<Vec<_>>();
+    usages.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.repo.cmp(&b.repo)));
+    Ok(usages)
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn create_repo(root: &std::path::Path, name: &str, sizes: &[(&str, usize)]) {
+        let repo_path = root.join("github.com/user").join(name);
+        fs::create_dir_all(repo_path.join(".git")).unwrap();
+        fs::write(repo_path.join(".git/HEAD"), "ref: refs/heads/main\n").unwrap();
+        for (worktree, size) in sizes {
+            fs::create_dir_all(repo_path.join(worktree)).unwrap();
+            fs::write(repo_path.join(worktree).join("file"), vec![0; *size]).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_repo_usages() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        create_repo(temp_dir.path(), "small", &[("main", 10)]);
+        create_repo(temp_dir.path(), "large", &[("main", 100), ("feature", 300)]);
+        let entries = scan::scan_root(temp_dir.path()).unwrap();
+
+        let usages = repo_usages(&entries, Some(2)).unwrap();
+        assert_eq!(usages[0].repo, "github.com/user/large");
+        assert_eq!(usages[0].size, 21 + 400);
+        assert_eq!(usages[0].git_dir, 21);
+        assert_eq!(usages[0].worktrees[0].size, 300);
+        assert!(usages[0].worktrees[0].path.ends_with("feature"));
+        assert_eq!(usages[1].repo, "github.com/user/small");
+        assert_eq!(usages[1].size, 31);
+    }
+
+    #[test]
+    fn test_execute() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        create_repo(temp_dir.path(), "repo", &[("main", 10)]);
+        let config = Config {
+            root: temp_dir.path().to_path_buf(),
+            ..Default::default()
+        };
+
+        assert!(execute(config.clone(), None, None, false).is_ok());
+        assert!(execute(config, Some("repo".to_string()), Some(1), true).is_ok());
+    }
+}
@@ -0,0 +1,124 @@
+use crate::{
+    config::Config,
+    exit,
+    ignore::glob_match,
+    output::Lines,
+    paths,
+    search::{self, Target, WorktreeFilter},
+    status,
+};
+use anyhow::Result;
+use std::path::PathBuf;
+
+/// Options for `find`.
+#[derive(Debug, Clone)]
+pub struct FindOptions {
+    /// Only repositories whose `host/owner/repo` matches this glob.
+    pub repo: Option<String>,
+    pub worktree: WorktreeFilter,
+    pub jobs: Option<usize>,
+    /// Terminate paths with NUL instead of newline.
+    pub print0: bool,
+}
+
+/// Prints the files in the selected worktrees whose name matches `pattern`,
+/// or whose path within the worktree does if `pattern` contains a `/`.
+/// Exits with status 3 when nothing matches.
+pub fn execute(config: Config, pattern: String, options: FindOptions) -> Result<()> {
+    let targets = search::targets(&config, options.repo.as_deref(), &options.worktree)?;
+    let paths = status::parallel_map(&targets, options.jobs, |target| {
+        find_in_worktree(&pattern, target)
+    })
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>();
+
+    let mut lines = Lines::stdout(options.print0);
+    for path in &paths {
+        lines.write(path.display())?;
+    }
+
+    if paths.is_empty() {
+        return Err(exit::not_found(true, format!("No file matches {pattern}")));
+    }
+    Ok(())
+}
+
+fn find_in_worktree(pattern: &str, target: &Target) -> Vec<PathBuf> {
+    search::files(&target.worktree)
+        .into_iter()
+        .filter(|path| {
+            let relative = path.strip_prefix(&target.worktree).unwrap_or(path);
+            if pattern.contains('/') {
+                glob_match(pattern, &paths::to_slash(relative))
+            } else {
+                path.file_name()
+                    .is_some_and(|name| glob_match(pattern, &name.to_string_lossy()))
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_find_in_worktree() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let worktree = temp_dir.path();
+        fs::create_dir_all(worktree.join("src/bin")).unwrap();
+        fs::write(worktree.join("Cargo.toml"), "").unwrap();
+        fs::write(worktree.join("src/main.rs"), "").unwrap();
+        fs::write(worktree.join("src/bin/tool.rs"), "").unwrap();
+        let target = Target {
+            repo: "github.com/user/repo".to_string(),
+            worktree: worktree.to_path_buf(),
+        };
+
+        assert_eq!(
+            find_in_worktree("*.rs", &target),
+            [
+                worktree.join("src/bin/tool.rs"),
+                worktree.join("src/main.rs")
+            ]
+        );
+        assert_eq!(
+            find_in_worktree("src/*.rs", &target),
+            [
+                worktree.join("src/bin/tool.rs"),
+                worktree.join("src/main.rs")
+            ]
+        );
+        assert_eq!(
+            find_in_worktree("Cargo.toml", &target),
+            [worktree.join("Cargo.toml")]
+        );
+        assert!(find_in_worktree("*.py", &target).is_empty());
+    }
+
+    #[test]
+    fn test_execute() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let worktree = temp_dir.path().join("github.com/user/repo/main");
+        fs::create_dir_all(temp_dir.path().join("github.com/user/repo/.git")).unwrap();
+        fs::create_dir_all(&worktree).unwrap();
+        fs::write(worktree.join("Cargo.toml"), "").unwrap();
+        let config = Config {
+            root: temp_dir.path().to_path_buf(),
+            ..Default::default()
+        };
+        let options = FindOptions {
+            repo: Some("user/repo".to_string()),
+            worktree: WorktreeFilter::All,
+            jobs: Some(1),
+            print0: false,
+        };
+
+        assert!(execute(config.clone(), "Cargo.toml".to_string(), options.clone()).is_ok());
+        let error = execute(config, "*.py".to_string(), options).unwrap_err();
+        assert_eq!(exit::classify(&error), Some(exit::ErrorKind::NotFound));
+    }
+}
@@ -0,0 +1,168 @@
+use crate::{
+    cli::OutputFormat,
+    config::Config,
+    exit,
+    search::{self, Target, WorktreeFilter},
+    status,
+};
+use anyhow::Result;
+use grep_regex::{RegexMatcher, RegexMatcherBuilder};
+use grep_searcher::{BinaryDetection, SearcherBuilder, sinks::Lossy};
+use serde::Serialize;
+use std::path::PathBuf;
+use tracing::debug;
+
+/// Options for `grep`.
+#[derive(Debug, Clone)]
+pub struct GrepOptions {
+    pub ignore_case: bool,
+    /// Treat the pattern as a literal string instead of a regex.
+    pub fixed_strings: bool,
+    /// Print only the paths of files with matches.
+    pub files_with_matches: bool,
+    /// Only repositories whose `host/owner/repo` matches this glob.
+    pub repo: Option<String>,
+    pub worktree: WorktreeFilter,
+    pub jobs: Option<usize>,
+    pub json: bool,
+}
+
+/// A matching line, as printed by `grep --json`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+struct Match {
+    repo: String,
+    path: PathBuf,
+    line: u64,
+    text: String,
+}
+
+/// Prints every line matching `pattern` in the selected worktrees as
+/// `path:line:text`. Exits with status 3 when nothing matches.
+pub fn execute(config: Config, pattern: String, options: GrepOptions) -> Result<()> {
+    let matcher = RegexMatcherBuilder::new()
+        .case_insensitive(options.ignore_case)
+        .fixed_strings(options.fixed_strings)
+        .build(&pattern)?;
+    let targets = search::targets(&config, options.repo.as_deref(), &options.worktree)?;
+    let matches = status::parallel_map(&targets, options.jobs, |target| {
+        search_worktree(&matcher, target)
+    })
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>();
+
+    if options.json || config.output == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&matches)?);
+    } else if options.files_with_matches {
+        let mut paths = matches.iter().map(|m| &m.path).collect::<Vec<_>>();
+        paths.dedup();
+        for path in paths {
+            println!("{}", path.display());
+        }
+    } else {
+        for m in &matches {
+            println!("{}:{}:{}", m.path.display(), m.line, m.text);
+        }
+    }
+
+    if matches.is_empty() {
+        return Err(exit::not_found(true, format!("No match for {pattern}")));
+    }
+    Ok(())
+}
+
+/// Searches the files of one worktree, skipping binary and unreadable files.
+fn search_worktree(matcher: &RegexMatcher, target: &Target) -> Vec<Match> {
+    let mut searcher = SearcherBuilder::new()
+        .binary_detection(BinaryDetection::quit(b'\x00'))
+        .line_number(true)
+        .build();
+    let mut matches = Vec::new();
+    for path in search::files(&target.worktree) {
+        let result = searcher.search_path(
+            matcher,
+            &path,
+            Lossy(|line, text| {
+                matches.push(Match {
+                    repo: target.repo.clone(),
+                    path: path.clone(),
+                    line,
+                    text: text.trim_end_matches(['\r', '\n']).to_string(),
+                });
+                Ok(true)
+            }),
+        );
+        if let Err(error) = result {
+            debug!("Skipping {}: {error}", path.display());
+        }
+    }
+    matches
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn options() -> GrepOptions {
+        GrepOptions {
+            ignore_case: false,
+            fixed_strings: false,
+            files_with_matches: false,
+            repo: None,
+            worktree: WorktreeFilter::Default,
+            jobs: None,
+            json: false,
+        }
+    }
+
+    #[test]
+    fn test_search_worktree() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let worktree = temp_dir.path();
+        fs::write(worktree.join("lib.rs"), "fn parse() {}\nfn Parse() {}\n").unwrap();
+        fs::write(worktree.join("data.bin"), b"fn parse\x00").unwrap();
+        let target = Target {
+            repo: "github.com/user/repo".to_string(),
+            worktree: worktree.to_path_buf(),
+        };
+
+        let matcher = RegexMatcherBuilder::new().build(r"fn \w+\(").unwrap();
+        let matches = search_worktree(&matcher, &target);
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[1].path, worktree.join("lib.rs"));
+        assert_eq!(matches[1].line, 2);
+        assert_eq!(matches[1].text, "fn Parse() {}");
+
+        let matcher = RegexMatcherBuilder::new()
+            .case_insensitive(true)
+            .fixed_strings(true)
+            .build("PARSE()")
+            .unwrap();
+        assert_eq!(search_worktree(&matcher, &target).len(), 2);
+    }
+
+    #[test]
+    fn test_execute() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let worktree = temp_dir.path().join("github.com/user/repo/main");
+        fs::create_dir_all(temp_dir.path().join("github.com/user/repo/.git")).unwrap();
+        fs::create_dir_all(&worktree).unwrap();
+        fs::write(worktree.join("lib.rs"), "fn parse() {}\n").unwrap();
+        let config = Config {
+            root: temp_dir.path().to_path_buf(),
+            ..Default::default()
+        };
+
+        assert!(execute(config.clone(), "parse".to_string(), options()).is_ok());
+        let files_with_matches = GrepOptions {
+            files_with_matches: true,
+            ..options()
+        };
+        assert!(execute(config.clone(), "parse".to_string(), files_with_matches).is_ok());
+
+        let error = execute(config, "missing".to_string(), options()).unwrap_err();
+        assert_eq!(exit::classify(&error), Some(exit::ErrorKind::NotFound));
+    }
+}
@@ -0,0 +1,53 @@
+use crate::{cli::OutputFormat, config::Config, history::History, output, status};
+use anyhow::Result;
+use std::time::{Duration, UNIX_EPOCH};
+
+/// Prints the repositories and worktrees switched to, most recent first,
+/// with their visit counts and frecency scores.
+pub fn execute(config: Config, limit: Option<usize>, json: bool) -> Result<()> {
+    let mut entries = History::load(&config).entries();
+    if let Some(limit) = limit {
+        entries.truncate(limit);
+    }
+
+    if json || config.output == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+        return Ok(());
+    }
+
+    let rows = entries
+        .iter()
+        .map(|visit| {
+            vec![
+                status::format_timestamp(UNIX_EPOCH + Duration::from_secs(visit.last_visit)),
+                visit.count.to_string(),
+                visit.frecency.to_string(),
+                visit.path.display().to_string(),
+            ]
+        })
+        .collect::<Vec<_>>();
+    for line in output::table(&["LAST VISIT", "VISITS", "FRECENCY", "PATH"], &rows) {
+        println!("{line}");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn test_execute() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config = Config {
+            cache_dir: Some(temp_dir.path().to_path_buf()),
+            ..Default::default()
+        };
+        History::record(&config, Path::new("/repos/github.com/user/repo")).unwrap();
+
+        assert!(execute(config.clone(), None, false).is_ok());
+        assert!(execute(config, Some(1), true).is_ok());
+    }
+}
@@ -0,0 +1,35 @@
+use crate::{config::Config, index};
+use anyhow::Result;
+use tracing::info;
+
+/// Rebuilds the repository index of every root from the file system.
+pub fn rebuild(config: Config) -> Result<()> {
+    for root in config.all_roots() {
+        let count = index::rebuild(&root.path)?;
+        info!(
+            "Indexed {count} repositories in {} ({})",
+            root.path.display(),
+            root.name
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rebuild() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(temp_dir.path().join("github.com/user/repo/.git")).unwrap();
+        let config = Config {
+            root: temp_dir.path().to_path_buf(),
+            ..Default::default()
+        };
+
+        rebuild(config).unwrap();
+        assert!(temp_dir.path().join(".neoghq/index.json").is_file());
+    }
+}
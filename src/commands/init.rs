@@ -0,0 +1,178 @@
+use super::config;
+use crate::config::Config;
+use anyhow::{Result, anyhow};
+use std::io::{self, BufRead, Write};
+use tracing::info;
+
+const PROTOCOLS: &[&str] = &["https", "ssh"];
+const DEFAULT_HOST: &str = "github.com";
+
+pub fn execute(config: Config, force: bool) -> Result<()> {
+    if config.no_input {
+        // Every prompt sees closed input and takes its suggestion
+        return init(&config, force, &mut io::empty(), &mut io::sink());
+    }
+    let stdin = io::stdin();
+    init(&config, force, &mut stdin.lock(), &mut io::stderr())
+}
+
+/// Asks for the basic settings on `output` and writes a fresh configuration
+/// file. Prompts go to stderr so an empty answer, or closed stdin, takes the
+/// suggested value.
+fn init(
+    config: &Config,
+    force: bool,
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+) -> Result<()> {
+    let path = config::config_file_path(config)?;
+    if path.exists() && !force {
+        return Err(anyhow!(
+            "Configuration file already exists: {} (use --force to overwrite)",
+            path.display()
+        ));
+    }
+
+    writeln!(output, "Setting up {}", path.display())?;
+    let root = prompt(
+        input,
+        output,
+        "Root directory for repositories",
+        &config.root.display().to_string(),
+    )?;
+    let protocol = loop {
+        let protocol = prompt(input, output, "Clone protocol (https, ssh)", PROTOCOLS[0])?;
+        if PROTOCOLS.contains(&protocol.as_str()) {
+            break protocol;
+        }
+        writeln!(output, "Please answer https or ssh")?;
+    };
+    let default_host = prompt(
+        input,
+        output,
+        "Default host",
+        config.default_host.as_deref().unwrap_or(DEFAULT_HOST),
+    )?;
+
+    config::write_file(
+        &path,
+        &[
+            ("general.root", root),
+            ("general.default_host", default_host),
+            ("clone.protocol", protocol),
+        ],
+    )?;
+    info!("Wrote {}", path.display());
+    Ok(())
+}
+
+/// Prints `question` with its suggested answer and reads a line, returning
+/// the suggestion when the line is empty.
+fn prompt(
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+    question: &str,
+    suggestion: &str,
+) -> Result<String> {
+    write!(output, "{question} [{suggestion}]: ")?;
+    output.flush()?;
+
+    let mut answer = String::new();
+    if input.read_line(&mut answer)? == 0 {
+        // Closed stdin takes every suggestion instead of looping forever
+        writeln!(output)?;
+        return Ok(suggestion.to_string());
+    }
+    match answer.trim() {
+        "" => Ok(suggestion.to_string()),
+        answer => Ok(answer.to_string()),
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use std::path::{Path, PathBuf};
+
+    fn create_config(dir: &Path) -> Config {
+        Config {
+            root: PathBuf::from("/src/repos"),
+            config_path: Some(dir.join("neoghq/config.toml")),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_init_writes_answers() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config = create_config(temp_dir.path());
+        let mut output = Vec::new();
+
+        init(
+            &config,
+            false,
+            &mut "~/code\nftp\nssh\ngitlab.com\n".as_bytes(),
+            &mut output,
+        )
+        .unwrap();
+
+        let written = std::fs::read_to_string(config.config_path.unwrap()).unwrap();
+        let value: toml::Value = toml::from_str(&written).unwrap();
+        assert_eq!(value["general"]["root"].as_str(), Some("~/code"));
+        assert_eq!(
+            value["general"]["default_host"].as_str(),
+            Some("gitlab.com")
+        );
+        assert_eq!(value["clone"]["protocol"].as_str(), Some("ssh"));
+        assert!(
+            String::from_utf8(output)
+                .unwrap()
+                .contains("Please answer https or ssh")
+        );
+    }
+
+    #[test]
+    fn test_init_takes_suggestions_on_empty_input() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config = create_config(temp_dir.path());
+
+        init(&config, false, &mut "".as_bytes(), &mut Vec::new()).unwrap();
+
+        let written = std::fs::read_to_string(config.config_path.unwrap()).unwrap();
+        let value: toml::Value = toml::from_str(&written).unwrap();
+        assert_eq!(value["general"]["root"].as_str(), Some("/src/repos"));
+        assert_eq!(
+            value["general"]["default_host"].as_str(),
+            Some("github.com")
+        );
+        assert_eq!(value["clone"]["protocol"].as_str(), Some("https"));
+    }
+
+    #[test]
+    fn test_execute_without_input_takes_suggestions() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config = Config {
+            no_input: true,
+            ..create_config(temp_dir.path())
+        };
+
+        execute(config.clone(), false).unwrap();
+
+        let written = std::fs::read_to_string(config.config_path.unwrap()).unwrap();
+        assert!(written.contains("protocol = \"https\""));
+    }
+
+    #[test]
+    fn test_init_refuses_to_overwrite() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config = create_config(temp_dir.path());
+        let path = config.config_path.clone().unwrap();
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, "[general]\nuser = \"octocat\"\n").unwrap();
+
+        assert!(init(&config, false, &mut "".as_bytes(), &mut Vec::new()).is_err());
+        assert!(init(&config, true, &mut "".as_bytes(), &mut Vec::new()).is_ok());
+        assert!(!std::fs::read_to_string(path).unwrap().contains("octocat"));
+    }
+}
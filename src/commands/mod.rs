@@ -1,38 +1,449 @@
+pub mod bisect;
+pub mod completions;
+pub mod config;
+pub mod daemon;
+pub mod docs;
+pub mod doctor;
+pub mod du;
+pub mod find;
+pub mod grep;
+pub mod history;
+pub mod index;
+pub mod init;
+pub mod path;
+pub mod prompt;
+pub mod relocate;
 pub mod repo;
 pub mod root;
+pub mod run;
+pub mod self_update;
+pub mod sessions;
+pub mod shell_init;
+pub mod status;
+pub mod trash;
+pub mod watch;
+pub mod workspace;
 pub mod worktree;
 
 use crate::{
-    cli::{Commands, RepoCommands, WorktreeCommands},
+    cli::{
+        Commands, ConfigCommands, DaemonCommands, DocsCommands, HooksCommands, IndexCommands,
+        RemoteCommands, RepoCommands, TagCommands, TrashCommands, WorkspaceCommands,
+        WorktreeCommands,
+    },
     config::Config,
+    search::WorktreeFilter,
 };
 use anyhow::Result;
 
 pub fn execute_command(command: Commands, config: Config) -> Result<()> {
     match command {
         Commands::Repo { command } => execute_repo_command(command, config),
+        Commands::Get {
+            url,
+            protocol,
+            open,
+        } => execute_repo_command(
+            RepoCommands::Get {
+                url,
+                protocol,
+                open,
+            },
+            config,
+        ),
+        Commands::List {
+            tsv,
+            json,
+            format,
+            print0,
+            full_path,
+            unique,
+            long,
+            query,
+            host,
+            owner,
+            dirty,
+            tag,
+            sort,
+            include_broken,
+            archived,
+        } => execute_repo_command(
+            RepoCommands::List {
+                tsv,
+                json,
+                format,
+                print0,
+                full_path,
+                unique,
+                long,
+                query,
+                host,
+                owner,
+                dirty,
+                tag,
+                sort,
+                include_broken,
+                archived,
+            },
+            config,
+        ),
+        Commands::Rm { repo, yes } => {
+            execute_repo_command(RepoCommands::Remove { repo, yes }, config)
+        }
         Commands::Worktree { command } => execute_worktree_command(command, config),
-        Commands::Root => root::execute(),
+        Commands::Init { force } => init::execute(config, force),
+        Commands::Root {
+            repo,
+            create,
+            print0,
+        } => root::execute(config, repo, create, print0),
+        Commands::Config { command } => execute_config_command(command, config),
+        Commands::Grep {
+            pattern,
+            ignore_case,
+            fixed_strings,
+            files_with_matches,
+            scope,
+            json,
+        } => grep::execute(
+            config,
+            pattern,
+            grep::GrepOptions {
+                ignore_case,
+                fixed_strings,
+                files_with_matches,
+                repo: scope.repo,
+                worktree: WorktreeFilter::parse(&scope.worktree),
+                jobs: scope.jobs,
+                json,
+            },
+        ),
+        Commands::Find {
+            pattern,
+            scope,
+            print0,
+        } => find::execute(
+            config,
+            pattern,
+            find::FindOptions {
+                repo: scope.repo,
+                worktree: WorktreeFilter::parse(&scope.worktree),
+                jobs: scope.jobs,
+                print0,
+            },
+        ),
+        Commands::Workspace { command } => execute_workspace_command(command, config),
+        Commands::Run {
+            filter,
+            tag,
+            jobs,
+            command,
+        } => run::execute(config, filter, tag, jobs, command),
+        Commands::Bisect {
+            repo,
+            good,
+            bad,
+            command,
+        } => bisect::execute(config, repo, good, bad, command),
+        Commands::History { limit, json } => history::execute(config, limit, json),
+        Commands::Du { query, jobs, json } => du::execute(config, query, jobs, json),
+        Commands::Status {
+            query,
+            long,
+            jobs,
+            json,
+        } => status::execute(config, query, long, jobs, json),
+        Commands::Doctor { fix, json } => doctor::execute(config, fix, json),
+        Commands::Relocate { old_root, new_root } => relocate::execute(config, old_root, new_root),
+        Commands::Undo => trash::restore(config, None),
+        Commands::Index { command } => match command {
+            IndexCommands::Rebuild => index::rebuild(config),
+        },
+        Commands::Daemon { command } => match command {
+            DaemonCommands::Start => daemon::start(config),
+            DaemonCommands::Stop => daemon::stop(config),
+            DaemonCommands::Status { json } => daemon::status(config, json),
+            DaemonCommands::Run => crate::daemon::run(config),
+        },
+        Commands::Watch => watch::execute(config),
+        Commands::Sessions { json } => sessions::execute(config, json),
+        Commands::Prompt {
+            from,
+            format,
+            no_dirty,
+        } => prompt::execute(config, from, format, no_dirty),
+        Commands::Trash { command } => execute_trash_command(command, config),
+        Commands::Path { what, from } => path::execute(what, from),
+        Commands::Completions { shell } => completions::execute(shell),
+        Commands::Docs { command } => match command {
+            DocsCommands::Man { out_dir } => docs::man(out_dir.as_deref()),
+            DocsCommands::Markdown => docs::markdown(),
+        },
+        Commands::ShellInit {
+            shell,
+            key_bindings,
+        } => shell_init::execute(shell, key_bindings),
+        Commands::SelfUpdate { check, force } => self_update::execute(check, force),
     }
 }
 
 fn execute_repo_command(command: RepoCommands, config: Config) -> Result<()> {
     match command {
-        RepoCommands::Clone { url } => repo::clone::execute(config, url, None),
-        RepoCommands::Create { url } => repo::create::execute(url),
-        RepoCommands::Switch { repo } => repo::switch::execute(repo),
-        RepoCommands::List => repo::list::execute(),
+        RepoCommands::Get {
+            url,
+            protocol,
+            open,
+        } => repo::get::execute(
+            Config {
+                protocol_override: protocol,
+                ..config
+            },
+            url,
+            None,
+            open,
+        ),
+        RepoCommands::Create {
+            url,
+            protocol,
+            empty,
+            template,
+            license,
+            gitignore,
+        } => repo::create::execute(
+            Config {
+                protocol_override: protocol,
+                ..config
+            },
+            url,
+            repo::create::CreateOptions {
+                empty,
+                template,
+                license,
+                gitignore,
+            },
+        ),
+        RepoCommands::Switch { repo, flags } => repo::switch::execute(config, repo, flags),
+        RepoCommands::List {
+            tsv,
+            json,
+            format,
+            print0,
+            full_path,
+            unique,
+            long,
+            query,
+            host,
+            owner,
+            dirty,
+            tag,
+            sort,
+            include_broken,
+            archived,
+        } => repo::list::execute(
+            config,
+            repo::list::ListOptions {
+                tsv,
+                json,
+                format,
+                print0,
+                full_path,
+                unique,
+                long,
+                query,
+                host,
+                owner,
+                dirty,
+                tag,
+                sort,
+                include_broken,
+                archived,
+            },
+        ),
+        RepoCommands::Fetch {
+            repos,
+            all,
+            jobs,
+            retries,
+            prune,
+            prune_tags,
+        } => repo::fetch::execute(config, repos, all, jobs, retries, prune, prune_tags),
+        RepoCommands::Maintain {
+            repos,
+            all,
+            jobs,
+            older_than,
+        } => repo::maintain::execute(config, repos, all, jobs, older_than),
+        RepoCommands::Info { repo, json } => repo::info::execute(config, repo, json),
+        RepoCommands::Remove { repo, yes } => repo::remove::execute(config, repo, yes),
+        RepoCommands::Archive { repo, bare_only } => {
+            repo::archive::archive(config, repo, bare_only)
+        }
+        RepoCommands::Rename {
+            repo,
+            new_spec,
+            update_remote,
+        } => repo::rename::execute(config, repo, new_spec, update_remote),
+        RepoCommands::SyncFork {
+            repos,
+            all,
+            rebase,
+            no_push,
+        } => repo::sync_fork::execute(config, repos, all, rebase, !no_push),
+        RepoCommands::Adopt { repo, in_place } => repo::adopt::execute(config, repo, in_place),
+        RepoCommands::Compact { repo } => repo::compact::execute(config, repo),
+        RepoCommands::Unarchive { repo } => repo::archive::unarchive(config, repo),
+        RepoCommands::Pin { repo } => repo::pin::pin(config, repo),
+        RepoCommands::Unpin { repo } => repo::pin::unpin(config, repo),
+        RepoCommands::Hooks { command } => execute_hooks_command(command, config),
+        RepoCommands::Remote { command } => execute_remote_command(command, config),
+        RepoCommands::Tag { command } => execute_tag_command(command, config),
+    }
+}
+
+fn execute_hooks_command(command: HooksCommands, config: Config) -> Result<()> {
+    match command {
+        HooksCommands::Sync { repos, all, force } => repo::hooks::sync(config, repos, all, force),
+    }
+}
+
+fn execute_remote_command(command: RemoteCommands, config: Config) -> Result<()> {
+    match command {
+        RemoteCommands::List { repos, all } => repo::remote::list(config, repos, all),
+        RemoteCommands::Add {
+            name,
+            url,
+            repos,
+            all,
+        } => repo::remote::add(config, name, url, repos, all),
+        RemoteCommands::Remove { name, repos, all } => {
+            repo::remote::remove(config, name, repos, all)
+        }
+        RemoteCommands::SetUrl {
+            name,
+            url,
+            repos,
+            all,
+            replace,
+        } => {
+            let mut repos = repos;
+            let change = match (url, replace) {
+                (url, Some(replace)) => {
+                    // Without a URL to take, the first repository lands there
+                    repos.splice(0..0, url);
+                    repo::remote::UrlChange::Replace {
+                        old: replace[0].clone(),
+                        new: replace[1].clone(),
+                    }
+                }
+                (Some(url), None) => repo::remote::UrlChange::Set(url),
+                (None, None) => unreachable!("clap requires a URL or --replace"),
+            };
+            repo::remote::set_url(config, name, change, repos, all)
+        }
+        RemoteCommands::Update {
+            repos,
+            all,
+            follow_redirect,
+            rename,
+        } => repo::remote::update(config, repos, all, follow_redirect, rename),
     }
 }
 
-fn execute_worktree_command(command: WorktreeCommands, _config: Config) -> Result<()> {
+fn execute_tag_command(command: TagCommands, config: Config) -> Result<()> {
     match command {
-        WorktreeCommands::Create { branch } => worktree::create::execute(branch),
-        WorktreeCommands::Switch { branch } => worktree::switch::execute(branch),
-        WorktreeCommands::Remove { branch } => worktree::remove::execute(branch),
-        WorktreeCommands::Clean => worktree::clean::execute(),
-        WorktreeCommands::Status => worktree::status::execute(),
-        WorktreeCommands::List => worktree::list::execute(),
+        TagCommands::Add { repo, tags } => repo::tag::add(config, repo, tags),
+        TagCommands::Remove { repo, tags } => repo::tag::remove(config, repo, tags),
+        TagCommands::List { repo } => repo::tag::list(config, repo),
+    }
+}
+
+fn execute_workspace_command(command: WorkspaceCommands, config: Config) -> Result<()> {
+    match command {
+        WorkspaceCommands::Create { name, repos, force } => {
+            workspace::create(config, name, repos, force)
+        }
+        WorkspaceCommands::Switch { name } => workspace::switch(config, name),
+        WorkspaceCommands::List { name } => workspace::list(config, name),
+        WorkspaceCommands::Exec {
+            name,
+            jobs,
+            command,
+        } => workspace::exec(config, name, jobs, command),
+        WorkspaceCommands::Remove { name } => workspace::remove(config, name),
+        WorkspaceCommands::Code { repo, open } => workspace::code(config, repo, open),
+    }
+}
+
+fn execute_trash_command(command: TrashCommands, config: Config) -> Result<()> {
+    match command {
+        TrashCommands::List { json } => trash::list(config, json),
+        TrashCommands::Restore { id } => trash::restore(config, id),
+        TrashCommands::Empty => trash::empty(config),
+    }
+}
+
+fn execute_config_command(command: ConfigCommands, config: Config) -> Result<()> {
+    match command {
+        ConfigCommands::Get { key } => config::get(config, key),
+        ConfigCommands::Set { key, value } => config::set(config, key, value),
+        ConfigCommands::Unset { key } => config::unset(config, key),
+        ConfigCommands::List { json, origin } => config::list(config, json, origin),
+        ConfigCommands::Validate => config::validate(config),
+    }
+}
+
+fn execute_worktree_command(command: WorktreeCommands, config: Config) -> Result<()> {
+    match command {
+        WorktreeCommands::Create {
+            branch,
+            issue,
+            stack_on,
+            open,
+        } => worktree::create::execute(config, branch, issue, stack_on, open),
+        WorktreeCommands::Switch { branch, flags } => {
+            worktree::switch::execute(config, branch, flags)
+        }
+        WorktreeCommands::Open { branch, tmux } => worktree::open::execute(config, branch, tmux),
+        WorktreeCommands::Remove { branch, force } => {
+            worktree::remove::execute(config, branch, force)
+        }
+        WorktreeCommands::Scratch { reference, ttl } => {
+            worktree::scratch::execute(config, ttl, reference)
+        }
+        WorktreeCommands::Review { target, read_only } => {
+            worktree::review::execute(config, target, read_only)
+        }
+        WorktreeCommands::Clean { gone } => worktree::clean::execute(config, gone),
+        WorktreeCommands::Status { json, diffstat } => {
+            worktree::status::execute(config, json, diffstat)
+        }
+        WorktreeCommands::Diff { from, to, stat } => {
+            worktree::diff::execute(config, from, to, stat)
+        }
+        WorktreeCommands::Info { branch, json } => worktree::info::execute(config, branch, json),
+        WorktreeCommands::FixUpstream => worktree::fix_upstream::execute(),
+        WorktreeCommands::Push { force_with_lease } => {
+            worktree::push::execute(config, force_with_lease)
+        }
+        WorktreeCommands::Rebase { stack, resolve } => {
+            worktree::rebase::execute(config, stack, resolve)
+        }
+        WorktreeCommands::List {
+            json,
+            format,
+            print0,
+            sort,
+            stack,
+        } => worktree::list::execute(
+            config,
+            worktree::list::ListOptions {
+                json,
+                format,
+                print0,
+                sort,
+                stack,
+            },
+        ),
     }
 }
 
@@ -45,24 +456,86 @@ mod tests {
         let temp_dir = tempfile::tempdir().expect("Failed to create temp directory");
         Config {
             root: temp_dir.path().to_path_buf(),
+            config_path: Some(temp_dir.path().join("config.toml")),
+            ..Default::default()
+        }
+    }
+
+    fn create_test_config_with_identity(root: &std::path::Path) -> Config {
+        Config {
+            root: root.to_path_buf(),
+            identity: Some(crate::config::Identity {
+                name: "Test".to_string(),
+                email: "test@example.com".to_string(),
+            }),
+            ..Default::default()
         }
     }
 
     #[test]
     fn test_execute_command_root() {
         let config = create_test_config();
-        let command = Commands::Root;
+        let command = Commands::Root {
+            repo: None,
+            create: false,
+            print0: false,
+        };
 
         let result = execute_command(command, config);
         assert!(result.is_ok());
     }
 
     #[test]
-    fn test_execute_command_repo_create() {
+    fn test_execute_command_path_outside_repository() {
+        let temp_dir = tempfile::tempdir().unwrap();
         let config = create_test_config();
+        let command = Commands::Path {
+            what: crate::cli::PathKind::RepoRoot,
+            from: Some(temp_dir.path().to_path_buf()),
+        };
+
+        let result = execute_command(command, config);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_execute_command_config() {
+        let config = create_test_config();
+        let commands = [
+            ConfigCommands::Set {
+                key: "clone.protocol".to_string(),
+                value: "ssh".to_string(),
+            },
+            ConfigCommands::Get {
+                key: "clone.protocol".to_string(),
+            },
+            ConfigCommands::List {
+                json: false,
+                origin: false,
+            },
+            ConfigCommands::Unset {
+                key: "clone.protocol".to_string(),
+            },
+        ];
+
+        for command in commands {
+            let result = execute_command(Commands::Config { command }, config.clone());
+            assert!(result.is_ok());
+        }
+    }
+
+    #[test]
+    fn test_execute_command_repo_create() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config = create_test_config_with_identity(temp_dir.path());
         let command = Commands::Repo {
             command: RepoCommands::Create {
                 url: "https://github.com/user/repo".to_string(),
+                protocol: None,
+                empty: false,
+                template: None,
+                license: None,
+                gitignore: None,
             },
         };
 
@@ -75,36 +548,85 @@ mod tests {
         let config = create_test_config();
         let command = Commands::Repo {
             command: RepoCommands::Switch {
-                repo: "user/repo".to_string(),
+                repo: Some("user/repo".to_string()),
+                flags: Default::default(),
             },
         };
 
         let result = execute_command(command, config);
-        assert!(result.is_ok());
+        assert!(result.is_err()); // Should fail because the repo is not managed
     }
 
     #[test]
     fn test_execute_command_repo_list() {
         let config = create_test_config();
         let command = Commands::Repo {
-            command: RepoCommands::List,
+            command: RepoCommands::List {
+                tsv: false,
+                json: false,
+                format: None,
+                print0: false,
+                full_path: false,
+                unique: true,
+                long: false,
+                query: None,
+                host: None,
+                owner: None,
+                dirty: false,
+                tag: None,
+                sort: None,
+                include_broken: false,
+                archived: false,
+            },
         };
 
         let result = execute_command(command, config);
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_execute_command_top_level_aliases() {
+        let config = create_test_config();
+        let command = Commands::List {
+            tsv: true,
+            json: false,
+            format: None,
+            print0: true,
+            full_path: false,
+            unique: false,
+            long: false,
+            query: Some("repo".to_string()),
+            host: Some("github.com".to_string()),
+            owner: None,
+            dirty: true,
+            tag: None,
+            sort: Some(crate::cli::SortKey::Size),
+            include_broken: true,
+            archived: false,
+        };
+        assert!(execute_command(command, config.clone()).is_ok());
+
+        let command = Commands::Rm {
+            repo: "user/repo".to_string(),
+            yes: true,
+        };
+        assert!(execute_command(command, config).is_err()); // Not managed
+    }
+
     #[test]
     fn test_execute_command_worktree_create() {
         let config = create_test_config();
         let command = Commands::Worktree {
             command: WorktreeCommands::Create {
-                branch: "feature/test".to_string(),
+                branch: Some("feature/test".to_string()),
+                issue: None,
+                stack_on: None,
+                open: false,
             },
         };
 
         let result = execute_command(command, config);
-        assert!(result.is_ok());
+        assert!(result.is_err()); // Should fail because the tests run in a plain clone
     }
 
     #[test]
@@ -112,12 +634,13 @@ mod tests {
         let config = create_test_config();
         let command = Commands::Worktree {
             command: WorktreeCommands::Switch {
-                branch: "feature/test".to_string(),
+                branch: Some("feature/test".to_string()),
+                flags: Default::default(),
             },
         };
 
         let result = execute_command(command, config);
-        assert!(result.is_ok());
+        assert!(result.is_err()); // Should fail because the worktree doesn't exist
     }
 
     #[test]
@@ -126,18 +649,22 @@ mod tests {
         let command = Commands::Worktree {
             command: WorktreeCommands::Remove {
                 branch: "feature/test".to_string(),
+                force: false,
             },
         };
 
         let result = execute_command(command, config);
-        assert!(result.is_ok());
+        assert!(result.is_err()); // Should fail because the worktree doesn't exist
     }
 
     #[test]
     fn test_execute_command_worktree_clean() {
-        let config = create_test_config();
+        let config = Config {
+            dry_run: true,
+            ..create_test_config()
+        };
         let command = Commands::Worktree {
-            command: WorktreeCommands::Clean,
+            command: WorktreeCommands::Clean { gone: false },
         };
 
         let result = execute_command(command, config);
@@ -148,7 +675,10 @@ mod tests {
     fn test_execute_command_worktree_status() {
         let config = create_test_config();
         let command = Commands::Worktree {
-            command: WorktreeCommands::Status,
+            command: WorktreeCommands::Status {
+                json: false,
+                diffstat: false,
+            },
         };
 
         let result = execute_command(command, config);
@@ -159,7 +689,13 @@ mod tests {
     fn test_execute_command_worktree_list() {
         let config = create_test_config();
         let command = Commands::Worktree {
-            command: WorktreeCommands::List,
+            command: WorktreeCommands::List {
+                json: false,
+                format: None,
+                print0: false,
+                sort: None,
+                stack: false,
+            },
         };
 
         let result = execute_command(command, config);
@@ -167,10 +703,12 @@ mod tests {
     }
 
     #[test]
-    fn test_execute_repo_command_clone() {
+    fn test_execute_repo_command_get() {
         let config = create_test_config();
-        let command = RepoCommands::Clone {
+        let command = RepoCommands::Get {
             url: "https://github.com/user/repo".to_string(),
+            protocol: None,
+            open: false,
         };
 
         let result = execute_repo_command(command, config);
@@ -179,9 +717,15 @@ mod tests {
 
     #[test]
     fn test_execute_repo_command_create() {
-        let config = create_test_config();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config = create_test_config_with_identity(temp_dir.path());
         let command = RepoCommands::Create {
             url: "https://github.com/user/repo".to_string(),
+            protocol: None,
+            empty: false,
+            template: None,
+            license: None,
+            gitignore: None,
         };
 
         let result = execute_repo_command(command, config);
@@ -192,17 +736,34 @@ mod tests {
     fn test_execute_repo_command_switch() {
         let config = create_test_config();
         let command = RepoCommands::Switch {
-            repo: "user/repo".to_string(),
+            repo: Some("user/repo".to_string()),
+            flags: Default::default(),
         };
 
         let result = execute_repo_command(command, config);
-        assert!(result.is_ok());
+        assert!(result.is_err()); // Should fail because the repo is not managed
     }
 
     #[test]
     fn test_execute_repo_command_list() {
         let config = create_test_config();
-        let command = RepoCommands::List;
+        let command = RepoCommands::List {
+            tsv: false,
+            json: false,
+            format: Some("{owner}/{repo}".to_string()),
+            print0: false,
+            full_path: false,
+            unique: false,
+            long: false,
+            query: Some("repo".to_string()),
+            host: Some("github.com".to_string()),
+            owner: None,
+            dirty: true,
+            tag: None,
+            sort: Some(crate::cli::SortKey::Size),
+            include_broken: true,
+            archived: false,
+        };
 
         let result = execute_repo_command(command, config);
         assert!(result.is_ok());
@@ -212,22 +773,26 @@ mod tests {
     fn test_execute_worktree_command_create() {
         let config = create_test_config();
         let command = WorktreeCommands::Create {
-            branch: "feature/test".to_string(),
+            branch: Some("feature/test".to_string()),
+            issue: None,
+            stack_on: None,
+            open: false,
         };
 
         let result = execute_worktree_command(command, config);
-        assert!(result.is_ok());
+        assert!(result.is_err()); // Should fail because the tests run in a plain clone
     }
 
     #[test]
     fn test_execute_worktree_command_switch() {
         let config = create_test_config();
         let command = WorktreeCommands::Switch {
-            branch: "feature/test".to_string(),
+            branch: Some("feature/test".to_string()),
+            flags: Default::default(),
         };
 
         let result = execute_worktree_command(command, config);
-        assert!(result.is_ok());
+        assert!(result.is_err());
     }
 
     #[test]
@@ -235,16 +800,20 @@ mod tests {
         let config = create_test_config();
         let command = WorktreeCommands::Remove {
             branch: "feature/test".to_string(),
+            force: false,
         };
 
         let result = execute_worktree_command(command, config);
-        assert!(result.is_ok());
+        assert!(result.is_err());
     }
 
     #[test]
     fn test_execute_worktree_command_clean() {
-        let config = create_test_config();
-        let command = WorktreeCommands::Clean;
+        let config = Config {
+            dry_run: true,
+            ..create_test_config()
+        };
+        let command = WorktreeCommands::Clean { gone: false };
 
         let result = execute_worktree_command(command, config);
         assert!(result.is_ok());
@@ -253,7 +822,10 @@ mod tests {
     #[test]
     fn test_execute_worktree_command_status() {
         let config = create_test_config();
-        let command = WorktreeCommands::Status;
+        let command = WorktreeCommands::Status {
+            json: false,
+            diffstat: false,
+        };
 
         let result = execute_worktree_command(command, config);
         assert!(result.is_ok());
@@ -262,7 +834,13 @@ mod tests {
     #[test]
     fn test_execute_worktree_command_list() {
         let config = create_test_config();
-        let command = WorktreeCommands::List;
+        let command = WorktreeCommands::List {
+            json: false,
+            format: None,
+            print0: false,
+            sort: Some(crate::cli::SortKey::Recent),
+            stack: false,
+        };
 
         let result = execute_worktree_command(command, config);
         assert!(result.is_ok());
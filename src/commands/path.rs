@@ -0,0 +1,89 @@
+use crate::{cli::PathKind, repo_context};
+use anyhow::{Result, anyhow};
+use std::path::{Path, PathBuf};
+
+pub fn execute(what: PathKind, from: Option<PathBuf>) -> Result<()> {
+    let from = match from {
+        Some(from) => from,
+        None => std::env::current_dir()?,
+    };
+
+    let path = resolve_path(what, &from)?;
+    println!("{}", path.display());
+    Ok(())
+}
+
+/// Resolves a standard location for the repository containing `from`.
+fn resolve_path(what: PathKind, from: &Path) -> Result<PathBuf> {
+    let context = repo_context::from_path(from)?;
+
+    match what {
+        PathKind::RepoRoot => Ok(context.repo_dir),
+        PathKind::BareGitDir => Ok(context.git_dir),
+        PathKind::WorktreeRoot => context
+            .worktree
+            .ok_or_else(|| anyhow!("Not inside a worktree: {}", from.display())),
+        PathKind::CacheDir => {
+            let cache_dir = context.git_dir.join("neoghq").join("cache");
+            std::fs::create_dir_all(&cache_dir)?;
+            Ok(cache_dir)
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_managed_repository;
+
+    /// Creates `<temp>/repo/.git` (bare) with a `main` worktree at `<temp>/repo/main`.
+    fn create_layout(temp_dir: &Path) -> (PathBuf, PathBuf) {
+        let repo_dir = temp_dir.join("repo");
+        create_managed_repository(&repo_dir);
+        (repo_dir.canonicalize().unwrap(), repo_dir.join(".git"))
+    }
+
+    #[test]
+    fn test_resolve_path_from_worktree_subdirectory() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let (repo_dir, _) = create_layout(temp_dir.path());
+        let subdir = repo_dir.join("main/src/nested");
+        std::fs::create_dir_all(&subdir).unwrap();
+
+        let repo_root = resolve_path(PathKind::RepoRoot, &subdir).unwrap();
+        let bare_git_dir = resolve_path(PathKind::BareGitDir, &subdir).unwrap();
+        let worktree_root = resolve_path(PathKind::WorktreeRoot, &subdir).unwrap();
+        let cache_dir = resolve_path(PathKind::CacheDir, &subdir).unwrap();
+
+        assert_eq!(repo_root.canonicalize().unwrap(), repo_dir);
+        assert_eq!(bare_git_dir.canonicalize().unwrap(), repo_dir.join(".git"));
+        assert_eq!(worktree_root.canonicalize().unwrap(), repo_dir.join("main"));
+        assert!(cache_dir.is_dir());
+        assert!(cache_dir.starts_with(&bare_git_dir));
+    }
+
+    #[test]
+    fn test_resolve_worktree_root_from_bare_repository() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let (_, bare_repo_path) = create_layout(temp_dir.path());
+
+        assert!(resolve_path(PathKind::WorktreeRoot, &bare_repo_path).is_err());
+    }
+
+    #[test]
+    fn test_resolve_path_outside_repository() {
+        let temp_dir = tempfile::tempdir().unwrap();
+
+        assert!(resolve_path(PathKind::RepoRoot, temp_dir.path()).is_err());
+    }
+
+    #[test]
+    fn test_execute() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let (repo_dir, _) = create_layout(temp_dir.path());
+
+        let result = execute(PathKind::RepoRoot, Some(repo_dir.join("main")));
+        assert!(result.is_ok());
+    }
+}
@@ -0,0 +1,203 @@
+use crate::{config::Config, format::Format, paths, status};
+use anyhow::Result;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// Template used without `--format` or `prompt.format`.
+pub const DEFAULT_PROMPT_FORMAT: &str = "{owner}/{repo}@{branch}{dirty}";
+
+const FIELDS: &[&str] = &["host", "owner", "repo", "worktree", "branch", "dirty"];
+
+/// What the prompt shows about the directory it's in.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+struct PromptInfo {
+    host: String,
+    owner: String,
+    repo: String,
+    /// The worktree directory relative to the repository directory.
+    worktree: Option<String>,
+    /// The worktree's branch, its short commit when detached, or the
+    /// default branch outside a worktree.
+    branch: Option<String>,
+    /// `*` when the worktree has uncommitted changes.
+    dirty: String,
+}
+
+/// Prints a prompt segment for `from` (default: the current directory), or
+/// nothing outside the roots. Only reads files and, unless `no_dirty`, the
+/// worktree's status, so it stays fast enough to run on every prompt.
+pub fn execute(
+    config: Config,
+    from: Option<PathBuf>,
+    format: Option<String>,
+    no_dirty: bool,
+) -> Result<()> {
+    let template = format
+        .or_else(|| config.prompt_format.clone())
+        .unwrap_or_else(|| DEFAULT_PROMPT_FORMAT.to_string());
+    let format = Format::parse(&template, FIELDS)?;
+    let from = match from {
+        Some(from) => from,
+        None => std::env::current_dir()?,
+    };
+    let roots = config
+        .all_roots()
+        .into_iter()
+        .map(|root| root.path)
+        .collect::<Vec<_>>();
+    if let Some(info) = prompt_info(&roots, &from, !no_dirty)? {
+        println!("{}", format.render(&info)?);
+    }
+    Ok(())
+}
+
+fn prompt_info(roots: &[PathBuf], from: &Path, check_dirty: bool) -> Result<Option<PromptInfo>> {
+    let Some(root) = roots
+        .iter()
+        .filter(|root| from.starts_with(root))
+        .max_by_key(|root| root.components().count())
+    else {
+        return Ok(None);
+    };
+    let mut components = from
+        .strip_prefix(root)
+        .unwrap_or(from)
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy().into_owned());
+    let (Some(host), Some(owner), Some(repo)) =
+        (components.next(), components.next(), components.next())
+    else {
+        return Ok(None);
+    };
+    let repo_dir = root.join(&host).join(&owner).join(&repo);
+    if !repo_dir.join(".git").exists() {
+        return Ok(None);
+    }
+
+    // Branch names with slashes nest worktrees, so look for the closest `.git`
+    let worktree = from
+        .ancestors()
+        .take_while(|dir| *dir != repo_dir && dir.starts_with(&repo_dir))
+        .find(|dir| dir.join(".git").exists());
+    let (branch, dirty) = match worktree {
+        Some(worktree) => {
+            let dirty = check_dirty && status::is_worktree_dirty(worktree)?;
+            (head(&git_dir(worktree)), dirty)
+        }
+        None => (head(&repo_dir.join(".git")), false),
+    };
+
+    Ok(Some(PromptInfo {
+        host,
+        owner,
+        repo,
+        worktree: worktree
+            .and_then(|worktree| Some(paths::to_slash(worktree.strip_prefix(&repo_dir).ok()?))),
+        branch,
+        dirty: if dirty { "*" } else { "" }.to_string(),
+    }))
+}
+
+/// Follows a worktree's `.git` file to its git directory.
+fn git_dir(worktree: &Path) -> PathBuf {
+    let dot_git = worktree.join(".git");
+    std::fs::read_to_string(&dot_git)
+        .ok()
+        .and_then(|content| {
+            let gitdir = content.trim().strip_prefix("gitdir:")?.trim().to_string();
+            Some(worktree.join(gitdir))
+        })
+        .unwrap_or(dot_git)
+}
+
+/// Reads the branch `HEAD` names, or the short commit when it's detached.
+fn head(git_dir: &Path) -> Option<String> {
+    let head = std::fs::read_to_string(git_dir.join("HEAD")).ok()?;
+    let head = head.trim();
+    match head.strip_prefix("ref:") {
+        Some(reference) => {
+            let reference = reference.trim();
+            Some(
+                reference
+                    .strip_prefix("refs/heads/")
+                    .unwrap_or(reference)
+                    .to_string(),
+            )
+        }
+        None => Some(head.chars().take(7).collect()),
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_managed_repository;
+
+    #[test]
+    fn test_prompt_info() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path().to_path_buf();
+        let repo_dir = root.join("github.com/user/repo");
+        let repo = create_managed_repository(&repo_dir);
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        let branch = repo.branch("feature/x", &head, false).unwrap();
+        let mut opts = git2::WorktreeAddOptions::new();
+        opts.reference(Some(branch.get()));
+        std::fs::create_dir(repo_dir.join("feature")).unwrap();
+        repo.worktree("feature-x", &repo_dir.join("feature/x"), Some(&opts))
+            .unwrap();
+        std::fs::create_dir(repo_dir.join("main/src")).unwrap();
+        let roots = [root.clone()];
+        let format = Format::parse(DEFAULT_PROMPT_FORMAT, FIELDS).unwrap();
+        let prompt = |from: &Path, check_dirty: bool| {
+            prompt_info(&roots, from, check_dirty)
+                .unwrap()
+                .map(|info| format.render(&info).unwrap())
+        };
+
+        assert_eq!(
+            prompt(&repo_dir.join("main/src"), true).as_deref(),
+            Some("user/repo@main")
+        );
+        assert_eq!(
+            prompt(&repo_dir.join("feature/x"), true).as_deref(),
+            Some("user/repo@feature/x")
+        );
+        std::fs::write(repo_dir.join("main/NEW"), "new\n").unwrap();
+        assert_eq!(
+            prompt(&repo_dir.join("main"), true).as_deref(),
+            Some("user/repo@main*")
+        );
+        assert_eq!(
+            prompt(&repo_dir.join("main"), false).as_deref(),
+            Some("user/repo@main")
+        );
+        // The repository directory shows the default branch
+        assert_eq!(prompt(&repo_dir, true).as_deref(), Some("user/repo@main"));
+        assert_eq!(
+            prompt_info(&roots, &repo_dir.join("feature/x"), false)
+                .unwrap()
+                .unwrap()
+                .worktree
+                .as_deref(),
+            Some("feature/x")
+        );
+
+        assert_eq!(prompt(&root.join("github.com/user"), true), None);
+        assert_eq!(prompt(&root.join("github.com/user/stray"), true), None);
+        assert_eq!(prompt(temp_dir.path().parent().unwrap(), true), None);
+    }
+
+    #[test]
+    fn test_head_detached() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            temp_dir.path().join("HEAD"),
+            "0123456789abcdef0123456789abcdef01234567\n",
+        )
+        .unwrap();
+        assert_eq!(head(temp_dir.path()).as_deref(), Some("0123456"));
+        assert_eq!(head(&temp_dir.path().join("missing")), None);
+    }
+}
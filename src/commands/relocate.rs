@@ -0,0 +1,237 @@
+use crate::{
+    config::Config,
+    dry_run,
+    scan::{self, RepoEntry},
+};
+use anyhow::{Context, Result, bail};
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// Moves the root `old_root` to `new_root`, unless that already happened,
+/// and rewrites the absolute paths worktrees and bare repositories keep of
+/// each other so every checkout works again.
+pub fn execute(config: Config, old_root: PathBuf, new_root: PathBuf) -> Result<()> {
+    let old_root = std::path::absolute(&old_root)?;
+    let new_root = std::path::absolute(&new_root)?;
+    if old_root == new_root {
+        bail!("The old and new root are the same: {}", old_root.display());
+    }
+
+    match (old_root.exists(), new_root.exists()) {
+        (true, true) => bail!(
+            "Both {} and {} exist; move the root yourself or pick a new location",
+            old_root.display(),
+            new_root.display()
+        ),
+        (true, false) => {
+            dry_run::apply(
+                &config,
+                format!("move {} to {}", old_root.display(), new_root.display()),
+                || {
+                    if let Some(parent) = new_root.parent() {
+                        std::fs::create_dir_all(parent)?;
+                    }
+                    std::fs::rename(&old_root, &new_root).with_context(|| {
+                        format!(
+                            "Failed to move {} to {} (across file systems, move it yourself and run relocate again)",
+                            old_root.display(),
+                            new_root.display()
+                        )
+                    })
+                },
+            )?;
+            if config.dry_run {
+                return Ok(());
+            }
+        }
+        (false, true) => {}
+        (false, false) => bail!(
+            "Neither {} nor {} exists",
+            old_root.display(),
+            new_root.display()
+        ),
+    }
+
+    let mut rewritten = 0;
+    for entry in scan::scan_root(&new_root)? {
+        let mut count = 0;
+        dry_run::apply(
+            &config,
+            format!("rewrite the worktree links of {}", entry.spec()),
+            || {
+                count = relink_repository(&entry.path, &old_root, &new_root)?;
+                Ok(())
+            },
+        )?;
+        rewritten += count;
+    }
+    info!("Rewrote {rewritten} worktree links");
+
+    for root in config.all_roots() {
+        if root.path == old_root {
+            warn!(
+                "The {} root is still configured as {}; update the configuration or NEOGHQ_ROOT",
+                root.name,
+                old_root.display()
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Rewrites the links of the repository at `repo_path` that point into
+/// `old_root` to point into `new_root` instead; returns how many changed.
+pub(crate) fn relink_repository(
+    repo_path: &Path,
+    old_root: &Path,
+    new_root: &Path,
+) -> Result<usize> {
+    let mut rewritten = 0;
+    for file in link_files(repo_path, old_root, new_root)? {
+        if let Some(content) = relocated(&file, old_root, new_root)? {
+            std::fs::write(&file, content)?;
+            rewritten += 1;
+        }
+    }
+    Ok(rewritten)
+}
+
+/// Returns the `.git` file of every worktree and the `gitdir` and
+/// `commondir` files of every worktree record of the repository. Worktrees
+/// nested deeper than the repository directory are found through their
+/// records, read as if already moved from `old_root` to `new_root`.
+fn link_files(repo_path: &Path, old_root: &Path, new_root: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let Some(entry) = RepoEntry::from_path(repo_path) else {
+        return Ok(files);
+    };
+    for worktree in entry.worktrees()? {
+        let file = worktree.join(".git");
+        if file.is_file() {
+            files.push(file);
+        }
+    }
+    let Ok(records) = std::fs::read_dir(entry.bare_repository_path().join("worktrees")) else {
+        return Ok(files);
+    };
+    for record in records {
+        let record = record?.path();
+        // libgit2 writes an absolute commondir, git a relative one
+        for name in ["gitdir", "commondir"] {
+            let file = record.join(name);
+            if file.is_file() {
+                files.push(file);
+            }
+        }
+        let gitdir = record.join("gitdir");
+        let worktree_file = match relocated(&gitdir, old_root, new_root) {
+            Ok(Some(content)) => PathBuf::from(content.trim_end()),
+            _ => match std::fs::read_to_string(&gitdir) {
+                Ok(content) => PathBuf::from(content.trim_end()),
+                Err(_) => continue,
+            },
+        };
+        if worktree_file.is_file() && !files.contains(&worktree_file) {
+            files.push(worktree_file);
+        }
+    }
+    Ok(files)
+}
+
+/// Returns the content of the link `file` with its path moved from
+/// `old_root` to `new_root`, or `None` if it points elsewhere.
+fn relocated(file: &Path, old_root: &Path, new_root: &Path) -> Result<Option<String>> {
+    let content = std::fs::read_to_string(file)?;
+    let (prefix, target) = match content.trim_end().strip_prefix("gitdir:") {
+        Some(target) => ("gitdir: ", target.trim_start()),
+        None => ("", content.trim_end()),
+    };
+    let Ok(relative) = Path::new(target).strip_prefix(old_root) else {
+        return Ok(None);
+    };
+    Ok(Some(format!(
+        "{prefix}{}\n",
+        new_root.join(relative).display()
+    )))
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_managed_repository;
+    use git2::Repository;
+
+    #[test]
+    fn test_relocated() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file = temp_dir.path().join(".git");
+        std::fs::write(&file, "gitdir: /old/github.com/u/r/.git/worktrees/main\n").unwrap();
+        assert_eq!(
+            relocated(&file, Path::new("/old"), Path::new("/new")).unwrap(),
+            Some("gitdir: /new/github.com/u/r/.git/worktrees/main\n".to_string())
+        );
+
+        std::fs::write(&file, "/old/github.com/u/r/main/.git\n").unwrap();
+        assert_eq!(
+            relocated(&file, Path::new("/old"), Path::new("/new")).unwrap(),
+            Some("/new/github.com/u/r/main/.git\n".to_string())
+        );
+        assert_eq!(
+            relocated(&file, Path::new("/elsewhere"), Path::new("/new")).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_execute_moves_root_and_relinks_worktrees() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let old_root = temp_dir.path().join("old");
+        let new_root = temp_dir.path().join("nested/new");
+        create_managed_repository(&old_root.join("github.com/user/repo"));
+        let config = Config {
+            root: old_root.clone(),
+            ..Default::default()
+        };
+
+        let dry_run = Config {
+            dry_run: true,
+            ..config.clone()
+        };
+        execute(dry_run, old_root.clone(), new_root.clone()).unwrap();
+        assert!(old_root.exists());
+
+        execute(config.clone(), old_root.clone(), new_root.clone()).unwrap();
+        assert!(!old_root.exists());
+        let worktree = Repository::open(new_root.join("github.com/user/repo/main")).unwrap();
+        assert!(!worktree.is_bare());
+        assert!(worktree.statuses(None).is_ok());
+        let bare = Repository::open_bare(new_root.join("github.com/user/repo/.git")).unwrap();
+        assert!(bare.find_worktree("main").unwrap().validate().is_ok());
+
+        // Already moved: only the links are rewritten, which are now current
+        execute(config.clone(), old_root.clone(), new_root.clone()).unwrap();
+        assert!(execute(config, new_root.clone(), new_root).is_err());
+    }
+
+    #[test]
+    fn test_execute_after_manual_move() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let old_root = temp_dir.path().join("old");
+        let new_root = temp_dir.path().join("new");
+        create_managed_repository(&old_root.join("github.com/user/repo"));
+        std::fs::rename(&old_root, &new_root).unwrap();
+
+        execute(Config::default(), old_root, new_root.clone()).unwrap();
+        let bare = Repository::open_bare(new_root.join("github.com/user/repo/.git")).unwrap();
+        assert!(bare.find_worktree("main").unwrap().validate().is_ok());
+        assert!(
+            execute(
+                Config::default(),
+                temp_dir.path().join("a"),
+                temp_dir.path().join("b")
+            )
+            .is_err()
+        );
+    }
+}
@@ -0,0 +1,320 @@
+use super::get::create_worktree;
+use super::switch::{find_repository_path, not_found_message};
+use crate::{
+    config::Config, dry_run, exit, giturl, index, lock::RepoLock, paths, scan::RepoEntry, status,
+};
+use anyhow::{Context, Result, anyhow, bail};
+use git2::{Repository, RepositoryState};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// Where the working files wait while the clone becomes a bare repository.
+const STAGING_DIR: &str = ".neoghq-adopt";
+
+/// Converts a repository cloned with plain `git clone` into a bare
+/// repository with a worktree for its current branch. With `in_place`,
+/// `repo` is a spec or path inside a root and the repository keeps its
+/// directory; otherwise `repo` is a path anywhere, which is first moved to
+/// where its `origin` belongs under the root.
+pub fn execute(config: Config, repo: String, in_place: bool) -> Result<()> {
+    let repo_dir = if in_place {
+        let path = Path::new(&repo);
+        if !path.join(".git").is_dir() {
+            let Some(repo_path) = find_repository_path(&config, &repo)? else {
+                return Err(exit::not_found(false, not_found_message(&config, &repo)?));
+            };
+            paths::canonicalize(&repo_path)?
+        } else {
+            paths::canonicalize(path)?
+        }
+    } else {
+        let source = paths::canonicalize(Path::new(&repo))
+            .with_context(|| format!("{repo} is not a directory"))?;
+        let target = target_dir(&config, &source)?;
+        dry_run::apply(
+            &config,
+            format_args!("move {} to {}", source.display(), target.display()),
+            || {
+                if let Some(parent) = target.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::rename(&source, &target).with_context(|| {
+                    format!(
+                        "Failed to move {} to {}; move it yourself and use --in-place",
+                        source.display(),
+                        target.display()
+                    )
+                })
+            },
+        )?;
+        if config.dry_run {
+            check_plain_clone(&source)?;
+            println!("Would convert {} in place", target.display());
+            return Ok(());
+        }
+        target
+    };
+
+    let branch = check_plain_clone(&repo_dir)?;
+    let worktree_path = paths::worktree_path(&repo_dir, &branch);
+    dry_run::apply(
+        &config,
+        format_args!(
+            "convert {} to a bare repository with a {branch} worktree",
+            repo_dir.display()
+        ),
+        || convert_in_place(&repo_dir, &branch),
+    )?;
+    if !config.dry_run {
+        if let Some(root) = RepoEntry::from_path(&repo_dir).and_then(|entry| {
+            config
+                .all_roots()
+                .into_iter()
+                .find(|root| entry.root() == Some(root.path.as_path()))
+        }) {
+            index::update(&root.path);
+        }
+        info!("Adopted {}", repo_dir.display());
+        println!("{}", worktree_path.display());
+    }
+    Ok(())
+}
+
+/// Returns where the clone at `source` belongs: `<root>/<host>/<owner>/<repo>`
+/// of its `origin`.
+fn target_dir(config: &Config, source: &Path) -> Result<PathBuf> {
+    let repo = Repository::open(source)?;
+    let remote = repo
+        .find_remote("origin")
+        .map_err(|_| anyhow!("{} has no origin remote to place it by", source.display()))?;
+    let url = remote
+        .url()
+        .ok_or_else(|| anyhow!("The origin URL of {} isn't UTF-8", source.display()))?;
+    let giturl::GitUrl {
+        host, owner, repo, ..
+    } = giturl::parse(url)?;
+    let root = paths::resolve_root(config, &host);
+    let target = paths::repository_dir(&root, &host, &owner, &repo);
+    if target.exists() {
+        bail!("{} already exists", target.display());
+    }
+    Ok(target)
+}
+
+/// Fails unless `repo_dir` is a plain clone that can be converted, and
+/// returns the branch it has checked out.
+fn check_plain_clone(repo_dir: &Path) -> Result<String> {
+    let repo = Repository::open(repo_dir)?;
+    if repo.is_bare()
+        || repo
+            .workdir()
+            .map(paths::canonicalize)
+            .transpose()?
+            .as_deref()
+            != Some(repo_dir)
+    {
+        bail!(
+            "{} is already a bare repository with worktrees",
+            repo_dir.display()
+        );
+    }
+    if repo.state() != RepositoryState::Clean {
+        bail!(
+            "{} is in the middle of a merge, rebase or similar; finish it first",
+            repo_dir.display()
+        );
+    }
+    let branch = status::head_branch(&repo).ok_or_else(|| {
+        anyhow!(
+            "HEAD of {} is detached; check out a branch first",
+            repo_dir.display()
+        )
+    })?;
+    if repo_dir.join(STAGING_DIR).exists() {
+        bail!(
+            "{} exists, left by an interrupted adoption; move its contents back first",
+            repo_dir.join(STAGING_DIR).display()
+        );
+    }
+    Ok(branch)
+}
+
+/// Moves the working files aside, turns `.git` into a bare repository,
+/// adds the worktree for `branch`, and moves the files and the index into
+/// it, so every uncommitted and staged change survives.
+fn convert_in_place(repo_dir: &Path, branch: &str) -> Result<()> {
+    let bare_repo_path = paths::bare_repository_path(repo_dir);
+    let _lock = RepoLock::acquire(&bare_repo_path)?;
+    let staging = repo_dir.join(STAGING_DIR);
+    fs::create_dir(&staging)?;
+    let moved = move_entries(repo_dir, &staging, &[".git", STAGING_DIR, ".git.lock"]);
+    let index = fs::read(bare_repo_path.join("index")).ok();
+
+    let result = moved.and_then(|()| {
+        let repo = Repository::open(&bare_repo_path)?;
+        let mut config = repo.config()?.open_level(git2::ConfigLevel::Local)?;
+        config.set_bool("core.bare", true)?;
+        // Missing unless the clone set one
+        let _ = config.remove("core.worktree");
+        Ok(())
+    });
+    if let Err(error) = result {
+        // Nothing refers to the moved files yet, so put them back
+        let _ = move_entries(&staging, repo_dir, &[]);
+        let _ = fs::remove_dir(&staging);
+        return Err(error);
+    }
+
+    let worktree_path = paths::worktree_path(repo_dir, branch);
+    create_worktree(&bare_repo_path, &worktree_path, branch).with_context(|| {
+        format!(
+            "Failed to add the {branch} worktree; the working files are in {}",
+            staging.display()
+        )
+    })?;
+    // Replace the fresh checkout with the files as they were
+    (|| {
+        for entry in fs::read_dir(&worktree_path)? {
+            let entry = entry?;
+            if entry.file_name() == ".git" {
+                continue;
+            }
+            if entry.file_type()?.is_dir() {
+                fs::remove_dir_all(entry.path())?;
+            } else {
+                fs::remove_file(entry.path())?;
+            }
+        }
+        move_entries(&staging, &worktree_path, &[])
+    })()
+    .with_context(|| {
+        format!(
+            "Failed to move the working files from {}",
+            staging.display()
+        )
+    })?;
+    fs::remove_dir(&staging)?;
+
+    if let Some(index) = index {
+        let worktree_repo = Repository::open(&worktree_path)?;
+        fs::write(worktree_repo.path().join("index"), index)?;
+    }
+    let _ = fs::remove_file(bare_repo_path.join("index"));
+    Ok(())
+}
+
+/// Moves every entry of `from` except `except` into `to`.
+fn move_entries(from: &Path, to: &Path, except: &[&str]) -> Result<()> {
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        if except.iter().any(|except| name == *except) {
+            continue;
+        }
+        fs::rename(entry.path(), to.join(&name))
+            .with_context(|| format!("Failed to move {}", entry.path().display()))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use crate::test_utils::{create_managed_repository, create_source_repository};
+
+    #[test]
+    fn test_convert_in_place_keeps_changes() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo_dir = temp_dir.path().join("github.com/user/repo");
+        let repo = create_source_repository(&repo_dir);
+        // A directory named like the branch is just another file
+        fs::create_dir(repo_dir.join("main")).unwrap();
+        fs::write(repo_dir.join("main/notes"), "notes\n").unwrap();
+        fs::write(repo_dir.join("README"), "changed\n").unwrap();
+        fs::write(repo_dir.join("staged"), "staged\n").unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("staged")).unwrap();
+        index.write().unwrap();
+        let repo_dir = paths::canonicalize(&repo_dir).unwrap();
+
+        let branch = check_plain_clone(&repo_dir).unwrap();
+        assert_eq!(branch, "main");
+        convert_in_place(&repo_dir, &branch).unwrap();
+
+        let bare = Repository::open_bare(repo_dir.join(".git")).unwrap();
+        assert!(bare.is_bare());
+        let worktree = repo_dir.join("main");
+        assert_eq!(
+            fs::read_to_string(worktree.join("README")).unwrap(),
+            "changed\n"
+        );
+        assert_eq!(
+            fs::read_to_string(worktree.join("main/notes")).unwrap(),
+            "notes\n"
+        );
+        let changes = status::changed_files(&worktree)
+            .unwrap()
+            .into_iter()
+            .map(|change| format!("{} {}", change.status, change.path))
+            .collect::<Vec<_>>();
+        assert_eq!(changes, [" M README", "?? main/notes", "A  staged"]);
+        assert!(!repo_dir.join(STAGING_DIR).exists());
+        assert!(check_plain_clone(&repo_dir).is_err());
+    }
+
+    #[test]
+    fn test_check_plain_clone_refuses_managed_and_detached() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let managed = temp_dir.path().join("managed");
+        create_managed_repository(&managed);
+        assert!(check_plain_clone(&paths::canonicalize(&managed).unwrap()).is_err());
+
+        let detached = temp_dir.path().join("detached");
+        let repo = create_source_repository(&detached);
+        let head = repo.head().unwrap().target().unwrap();
+        repo.set_head_detached(head).unwrap();
+        let error = check_plain_clone(&paths::canonicalize(&detached).unwrap()).unwrap_err();
+        assert!(error.to_string().contains("detached"), "{error}");
+    }
+
+    #[test]
+    fn test_execute_moves_into_root() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path().join("root");
+        fs::create_dir(&root).unwrap();
+        let clone = temp_dir.path().join("clone");
+        let repo = create_source_repository(&clone);
+        repo.remote("origin", "https://github.com/User/Repo.git")
+            .unwrap();
+        let config = Config {
+            root: root.clone(),
+            ..Default::default()
+        };
+
+        execute(
+            Config {
+                dry_run: true,
+                ..config.clone()
+            },
+            clone.display().to_string(),
+            false,
+        )
+        .unwrap();
+        assert!(clone.exists());
+
+        execute(config.clone(), clone.display().to_string(), false).unwrap();
+        assert!(!clone.exists());
+        let repo_dir = root.join("github.com/User/Repo");
+        assert!(
+            Repository::open_bare(repo_dir.join(".git"))
+                .unwrap()
+                .is_bare()
+        );
+        assert!(repo_dir.join("main/README").is_file());
+
+        // Adopted repositories are found by spec, and aren't adopted twice
+        assert!(execute(config, "User/Repo".to_string(), true).is_err());
+    }
+}
@@ -0,0 +1,374 @@
+use super::switch::{find_repository_path, not_found_message};
+use crate::{
+    config::Config,
+    dry_run,
+    exit::{self, ErrorKind},
+    index,
+    lock::{self, RepoLock},
+    paths,
+    scan::{self, RepoEntry},
+    status,
+};
+use anyhow::{Context, Result, anyhow, bail};
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+const EXTENSION: &str = "tar.zst";
+
+/// Compresses `repo` into `<root>/.neoghq/archive/<host>/<owner>/<repo>.tar.zst`
+/// and removes it from the root. With `bare_only`, only the bare repository
+/// is kept, so worktrees with uncommitted changes are refused.
+pub fn archive(config: Config, repo: String, bare_only: bool) -> Result<()> {
+    let Some(repo_path) = find_repository_path(&config, &repo)? else {
+        return Err(exit::not_found(false, not_found_message(&config, &repo)?));
+    };
+    let entry = RepoEntry::from_path(&repo_path)
+        .ok_or_else(|| anyhow!("Not a repository directory: {}", repo_path.display()))?;
+    let root = entry
+        .root()
+        .ok_or_else(|| anyhow!("Not a repository directory: {}", repo_path.display()))?;
+    let archive_path = archive_path(root, &entry);
+    if archive_path.exists() {
+        bail!(
+            "{} is already archived at {}; unarchive or delete it first",
+            entry.spec(),
+            archive_path.display()
+        );
+    }
+
+    if bare_only {
+        for worktree in entry.worktrees()? {
+            if status::is_worktree_dirty(&worktree)? {
+                return Err(exit::error(
+                    ErrorKind::DirtyWorktree,
+                    format!(
+                        "{} has uncommitted changes; commit them or archive without --bare-only",
+                        worktree.display()
+                    ),
+                ));
+            }
+        }
+    }
+
+    dry_run::apply(
+        &config,
+        format_args!(
+            "archive {} to {}",
+            repo_path.display(),
+            archive_path.display()
+        ),
+        || {
+            let _lock = RepoLock::acquire(&entry.bare_repository_path())?;
+            write_archive(&entry, &archive_path, bare_only)?;
+            std::fs::remove_dir_all(&repo_path)?;
+            index::update(root);
+            info!(
+                "Archived {} to {}; `neoghq repo unarchive {}` restores it",
+                entry.spec(),
+                archive_path.display(),
+                entry.spec()
+            );
+            Ok(())
+        },
+    )
+}
+
+/// Extracts the archive of `repo` back into its root and deletes the archive.
+pub fn unarchive(config: Config, repo: String) -> Result<()> {
+    let Some((entry, archive_path)) = find_archive(&config, &repo)? else {
+        return Err(exit::not_found(
+            false,
+            format!("No archived repository: {repo}"),
+        ));
+    };
+    if entry.path.exists() {
+        bail!(
+            "Can't unarchive {}: {} exists",
+            entry.spec(),
+            entry.path.display()
+        );
+    }
+
+    dry_run::apply(
+        &config,
+        format_args!(
+            "unarchive {} to {}",
+            archive_path.display(),
+            entry.path.display()
+        ),
+        || {
+            let result = extract_archive(&archive_path, &entry.path);
+            if result.is_err() {
+                // Leaves no half-extracted repository behind
+                let _ = std::fs::remove_dir_all(&entry.path);
+            }
+            result.with_context(|| format!("Failed to extract {}", archive_path.display()))?;
+            std::fs::remove_file(&archive_path)?;
+            if let Some(root) = entry.root() {
+                index::update(root);
+            }
+            info!("Restored {} to {}", entry.spec(), entry.path.display());
+            Ok(())
+        },
+    )
+}
+
+/// Returns the repositories archived under every root, each with the path
+/// of its archive, in `host/owner/repo` order within a root.
+pub(crate) fn archived_entries(config: &Config) -> Result<Vec<RepoEntry>> {
+    let mut entries = Vec::new();
+    for root in config.all_roots() {
+        let archive_dir = root.path.join(".neoghq").join("archive");
+        if !archive_dir.is_dir() {
+            continue;
+        }
+        let mut found = Vec::new();
+        for host_dir in scan::subdirectories(&archive_dir)? {
+            for owner_dir in scan::subdirectories(&host_dir)? {
+                for path in sorted_children(&owner_dir)? {
+                    let Some(repo) = path
+                        .file_name()
+                        .and_then(|name| name.to_str())
+                        .and_then(|name| name.strip_suffix(&format!(".{EXTENSION}")))
+                        .filter(|_| path.is_file())
+                    else {
+                        continue;
+                    };
+                    found.push(RepoEntry {
+                        host: file_name(&host_dir),
+                        owner: file_name(&owner_dir),
+                        repo: repo.to_string(),
+                        path: path.clone(),
+                    });
+                }
+            }
+        }
+        found.sort_by_key(RepoEntry::spec);
+        entries.extend(found);
+    }
+    Ok(entries)
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned()
+}
+
+fn archive_path(root: &Path, entry: &RepoEntry) -> PathBuf {
+    root.join(".neoghq")
+        .join("archive")
+        .join(&entry.host)
+        .join(&entry.owner)
+        .join(format!("{}.{EXTENSION}", entry.repo))
+}
+
+/// Writes the repository directory of `entry` to `archive_path`, leaving
+/// out the lock, and with `bare_only` the worktrees and their records.
+fn write_archive(entry: &RepoEntry, archive_path: &Path, bare_only: bool) -> Result<()> {
+    if let Some(parent) = archive_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    // Written aside first so an interrupted run leaves no truncated archive
+    let partial = archive_path.with_extension("zst.partial");
+    let result = (|| -> Result<()> {
+        let encoder = zstd::Encoder::new(File::create(&partial)?, 0)?;
+        let mut builder = tar::Builder::new(encoder);
+        builder.follow_symlinks(false);
+
+        let bare_repo_path = entry.bare_repository_path();
+        for child in sorted_children(&bare_repo_path)? {
+            let name = child.file_name().unwrap_or_default();
+            if bare_only && name == "worktrees" {
+                continue;
+            }
+            append(&mut builder, Path::new(".git").join(name), &child)?;
+        }
+        if !bare_only {
+            let lock_path = lock::lock_path(&bare_repo_path);
+            for child in sorted_children(&entry.path)? {
+                if child != bare_repo_path && child != lock_path {
+                    append(&mut builder, child.file_name().unwrap_or_default(), &child)?;
+                }
+            }
+        }
+        builder.into_inner()?.finish()?;
+        Ok(())
+    })();
+    if let Err(error) = result {
+        let _ = std::fs::remove_file(&partial);
+        return Err(error.context(format!("Failed to write {}", archive_path.display())));
+    }
+    std::fs::rename(&partial, archive_path)?;
+    Ok(())
+}
+
+fn append(
+    builder: &mut tar::Builder<impl std::io::Write>,
+    name: impl AsRef<Path>,
+    path: &Path,
+) -> Result<()> {
+    if path.is_dir() && !path.is_symlink() {
+        builder.append_dir_all(name, path)?;
+    } else {
+        builder.append_path_with_name(path, name)?;
+    }
+    Ok(())
+}
+
+fn sorted_children(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut children = std::fs::read_dir(dir)?
+        .map(|child| Ok(child?.path()))
+        .collect::<Result<Vec<_>>>()?;
+    children.sort();
+    Ok(children)
+}
+
+fn extract_archive(archive_path: &Path, repo_path: &Path) -> Result<()> {
+    let decoder = zstd::Decoder::new(File::open(archive_path)?)?;
+    let mut archive = tar::Archive::new(decoder);
+    archive.set_preserve_permissions(true);
+    std::fs::create_dir_all(repo_path)?;
+    archive.unpack(repo_path)?;
+    Ok(())
+}
+
+/// Finds the archive of `repo`, given as `host/owner/repo`, `owner/repo`, or
+/// a bare name of the configured user, along with where it restores to.
+fn find_archive(config: &Config, repo: &str) -> Result<Option<(RepoEntry, PathBuf)>> {
+    let parts = repo.split('/').collect::<Vec<_>>();
+    let (host, owner, name) = match (parts.as_slice(), config.user.as_deref()) {
+        ([host, owner, name], _) => (Some(*host), *owner, *name),
+        ([owner, name], _) => (None, *owner, *name),
+        ([name], Some(user)) => (None, user, *name),
+        _ => bail!("Invalid repository format: {repo} (expected owner/repo)"),
+    };
+
+    for root in config.all_roots() {
+        let archive_dir = root.path.join(".neoghq").join("archive");
+        let hosts = match host {
+            Some(host) => vec![host.to_string()],
+            None => {
+                let Ok(dirs) = std::fs::read_dir(&archive_dir) else {
+                    continue;
+                };
+                let mut hosts = dirs
+                    .map(|dir| Ok(dir?.file_name().to_string_lossy().into_owned()))
+                    .collect::<Result<Vec<_>>>()?;
+                hosts.sort();
+                // Prefer the default host when the same owner/repo exists on several
+                if let Some(default_host) = &config.default_host
+                    && let Some(index) = hosts.iter().position(|host| host == default_host)
+                {
+                    let default_host = hosts.remove(index);
+                    hosts.insert(0, default_host);
+                }
+                hosts
+            }
+        };
+        for host in hosts {
+            let entry = RepoEntry {
+                host,
+                owner: paths::owner_dir(owner),
+                repo: name.to_string(),
+                path: PathBuf::new(),
+            };
+            let archive_path = archive_path(&root.path, &entry);
+            if archive_path.is_file() {
+                let path = paths::repository_dir(&root.path, &entry.host, owner, name);
+                return Ok(Some((RepoEntry { path, ..entry }, archive_path)));
+            }
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_managed_repository;
+    use git2::Repository;
+
+    fn config(root: &Path) -> Config {
+        Config {
+            root: root.to_path_buf(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_archive_and_unarchive() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        let repo_path = root.join("github.com/user/repo");
+        create_managed_repository(&repo_path);
+        std::fs::write(repo_path.join("main/notes.txt"), "draft\n").unwrap();
+        let config = config(root);
+
+        let dry_run = Config {
+            dry_run: true,
+            ..config.clone()
+        };
+        archive(dry_run, "user/repo".to_string(), false).unwrap();
+        assert!(repo_path.exists());
+
+        archive(config.clone(), "user/repo".to_string(), false).unwrap();
+        assert!(!repo_path.exists());
+        let archive_path = root.join(".neoghq/archive/github.com/user/repo.tar.zst");
+        assert!(archive_path.is_file());
+        assert!(archive(config.clone(), "user/repo".to_string(), false).is_err());
+        let archived = archived_entries(&config).unwrap();
+        assert_eq!(
+            archived.iter().map(RepoEntry::spec).collect::<Vec<_>>(),
+            ["github.com/user/repo"]
+        );
+        assert_eq!(archived[0].path, archive_path);
+
+        unarchive(config.clone(), "github.com/user/repo".to_string()).unwrap();
+        assert!(!archive_path.exists());
+        assert!(!lock::lock_path(&repo_path.join(".git")).exists());
+        assert_eq!(
+            std::fs::read_to_string(repo_path.join("main/notes.txt")).unwrap(),
+            "draft\n"
+        );
+        let bare = Repository::open_bare(repo_path.join(".git")).unwrap();
+        assert!(bare.find_worktree("main").unwrap().validate().is_ok());
+
+        assert!(archived_entries(&config).unwrap().is_empty());
+        assert!(unarchive(config, "user/repo".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_archive_bare_only() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        let repo_path = root.join("github.com/user/repo");
+        create_managed_repository(&repo_path);
+        let config = config(root);
+
+        std::fs::write(repo_path.join("main/README"), "changed\n").unwrap();
+        assert!(archive(config.clone(), "user/repo".to_string(), true).is_err());
+        assert!(repo_path.exists());
+
+        std::fs::write(repo_path.join("main/README"), "Hello, World!\n").unwrap();
+        archive(config.clone(), "user/repo".to_string(), true).unwrap();
+        unarchive(config, "user/repo".to_string()).unwrap();
+        let bare = Repository::open_bare(repo_path.join(".git")).unwrap();
+        assert!(bare.worktrees().unwrap().is_empty());
+        assert!(bare.find_branch("main", git2::BranchType::Local).is_ok());
+        assert!(!repo_path.join("main").exists());
+    }
+
+    #[test]
+    fn test_find_archive_missing() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config = config(temp_dir.path());
+
+        assert!(find_archive(&config, "user/repo").unwrap().is_none());
+        assert!(find_archive(&config, "repo").is_err());
+        assert!(unarchive(config, "user/repo".to_string()).is_err());
+    }
+}
@@ -0,0 +1,227 @@
+use super::get::create_worktree;
+use super::switch::{find_repository_path, not_found_message};
+use crate::{
+    config::Config,
+    dry_run,
+    events::{self, Event, EventKind},
+    exit::{self, ErrorKind},
+    lock::RepoLock,
+    paths,
+    repo_settings::RepoSettings,
+    scan::RepoEntry,
+    status,
+};
+use anyhow::{Result, anyhow, bail};
+use git2::{Repository, WorktreePruneOptions};
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// Removes every worktree of `repo`, keeping the bare repository with its
+/// full history. Refuses while a worktree has uncommitted changes or a
+/// commit no remote-tracking branch contains.
+pub fn execute(config: Config, repo: String) -> Result<()> {
+    let Some(repo_path) = find_repository_path(&config, &repo)? else {
+        return Err(exit::not_found(false, not_found_message(&config, &repo)?));
+    };
+    let bare_repo_path = paths::bare_repository_path(&repo_path);
+    let lock = RepoLock::acquire(&bare_repo_path)?;
+    let bare = Repository::open_bare(&bare_repo_path)?;
+
+    let mut worktrees = Vec::new();
+    for name in bare.worktrees()?.iter().flatten() {
+        let worktree = bare.find_worktree(name)?;
+        let path = worktree.path().to_path_buf();
+        let mut branch = None;
+        if worktree.validate().is_ok() {
+            check_removable(&path)?;
+            branch = Repository::open(&path)
+                .ok()
+                .and_then(|repo| status::head_branch(&repo));
+        }
+        worktrees.push((worktree, path, branch));
+    }
+    if worktrees.is_empty() {
+        info!("{} has no worktrees", repo_path.display());
+        return Ok(());
+    }
+
+    let mut removed = Vec::new();
+    for (worktree, path, branch) in worktrees {
+        dry_run::apply(
+            &config,
+            format_args!("remove worktree {}", path.display()),
+            || {
+                worktree.prune(Some(
+                    WorktreePruneOptions::new().valid(true).working_tree(true),
+                ))?;
+                info!("Removed worktree {}", path.display());
+                removed.push(Event::worktree(
+                    EventKind::WorktreeRemoved,
+                    &repo_path,
+                    &path,
+                    branch.as_deref(),
+                ));
+                Ok(())
+            },
+        )?;
+    }
+    drop(lock);
+    for event in &removed {
+        events::emit(&config, event);
+    }
+    info!(
+        "Compacted {}; `neoghq repo switch` and `neoghq worktree create` re-create the default worktree",
+        repo_path.display()
+    );
+    Ok(())
+}
+
+/// Fails unless removing the worktree at `path` loses nothing.
+fn check_removable(path: &Path) -> Result<()> {
+    if status::is_worktree_dirty(path)? {
+        return Err(exit::error(
+            ErrorKind::DirtyWorktree,
+            format!(
+                "Worktree has uncommitted changes: {}; commit and push them first",
+                path.display()
+            ),
+        ));
+    }
+    let repo = Repository::open(path)?;
+    let Some(head) = repo.head().ok().and_then(|head| head.target()) else {
+        return Ok(());
+    };
+    if status::unpushed_commits(&repo, head)? > 0 {
+        bail!(
+            "Worktree has unpushed commits: {}; push them first",
+            path.display()
+        );
+    }
+    Ok(())
+}
+
+/// Returns the branch of the default worktree of the repository at
+/// `repo_path`: its `default_worktree` setting, or the branch HEAD of the
+/// bare repository points at.
+pub(crate) fn default_worktree_branch(config: &Config, repo_path: &Path) -> Result<String> {
+    let (entry, settings) = load_settings(config, repo_path)?;
+    default_branch(config, &entry, &settings)
+}
+
+/// Whether every worktree of `repo` is gone, as `repo compact` leaves it.
+pub(crate) fn is_compacted(repo: &Repository) -> Result<bool> {
+    Ok(status::list_worktrees(repo)?
+        .iter()
+        .all(|worktree| !worktree.path.is_dir()))
+}
+
+/// Creates the default worktree of the compacted repository at
+/// `repo_path`, on the branch [`default_worktree_branch`] returns.
+pub(crate) fn restore_default_worktree(config: &Config, repo_path: &Path) -> Result<PathBuf> {
+    let (entry, settings) = load_settings(config, repo_path)?;
+    let bare_repo_path = entry.bare_repository_path();
+    let branch = default_branch(config, &entry, &settings)?;
+    let worktree_path = paths::worktree_path(repo_path, &branch);
+
+    dry_run::apply(
+        config,
+        format_args!("create worktree {}", worktree_path.display()),
+        || {
+            {
+                let _lock = RepoLock::acquire(&bare_repo_path)?;
+                info!(
+                    "Re-creating worktree for branch '{}' in {}",
+                    branch,
+                    worktree_path.display()
+                );
+                create_worktree(&bare_repo_path, &worktree_path, &branch)?;
+                settings.setup_worktree(repo_path, &worktree_path)?;
+            }
+            events::emit(
+                config,
+                &Event::worktree(
+                    EventKind::WorktreeCreated,
+                    repo_path,
+                    &worktree_path,
+                    Some(&branch),
+                ),
+            );
+            Ok(())
+        },
+    )?;
+    Ok(worktree_path)
+}
+
+fn load_settings(config: &Config, repo_path: &Path) -> Result<(RepoEntry, RepoSettings)> {
+    let entry = RepoEntry::from_path(repo_path)
+        .ok_or_else(|| anyhow!("Not a repository directory: {}", repo_path.display()))?;
+    let settings = RepoSettings::load(config, &entry.spec(), repo_path)?;
+    Ok((entry, settings))
+}
+
+fn default_branch(config: &Config, entry: &RepoEntry, settings: &RepoSettings) -> Result<String> {
+    Ok(match settings.default_worktree.clone() {
+        Some(branch) => branch,
+        None => status::default_branch(&Repository::open_bare(entry.bare_repository_path())?)
+            .unwrap_or_else(|| config.default_branch().to_string()),
+    })
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use crate::test_utils::{commit_all, create_managed_repository};
+    use git2::BranchType;
+
+    fn config(root: &Path) -> Config {
+        Config {
+            root: root.to_path_buf(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_execute_and_restore() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo_path = temp_dir.path().join("github.com/user/repo");
+        create_managed_repository(&repo_path);
+        let config = config(temp_dir.path());
+
+        let dry_run = Config {
+            dry_run: true,
+            ..config.clone()
+        };
+        execute(dry_run, "user/repo".to_string()).unwrap();
+        assert!(repo_path.join("main").exists());
+
+        execute(config.clone(), "user/repo".to_string()).unwrap();
+        assert!(!repo_path.join("main").exists());
+        let bare = Repository::open_bare(repo_path.join(".git")).unwrap();
+        assert!(bare.worktrees().unwrap().is_empty());
+        assert!(bare.find_branch("main", BranchType::Local).is_ok());
+        // Nothing left to remove
+        execute(config.clone(), "user/repo".to_string()).unwrap();
+
+        let worktree = restore_default_worktree(&config, &repo_path).unwrap();
+        assert_eq!(worktree, repo_path.join("main"));
+        assert!(Repository::open(&worktree).is_ok());
+    }
+
+    #[test]
+    fn test_execute_refuses_dirty_or_unpushed_worktrees() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo_path = temp_dir.path().join("github.com/user/repo");
+        create_managed_repository(&repo_path);
+        let config = config(temp_dir.path());
+
+        std::fs::write(repo_path.join("main/README"), "changed\n").unwrap();
+        let error = execute(config.clone(), "user/repo".to_string()).unwrap_err();
+        assert_eq!(exit::classify(&error), Some(ErrorKind::DirtyWorktree));
+
+        let worktree = Repository::open(repo_path.join("main")).unwrap();
+        commit_all(&worktree, "Local change");
+        assert!(execute(config, "user/repo".to_string()).is_err());
+        assert!(repo_path.join("main/README").exists());
+    }
+}
@@ -1,7 +1,363 @@
-use anyhow::Result;
+use super::get::{apply_identity, create_worktree};
+use crate::{
+    cli::{GitignoreTemplate, License},
+    config::Config,
+    events::{self, Event, EventKind},
+    githooks, giturl, index, paths,
+    repo_settings::RepoSettings,
+    signing, spec, status,
+    templates::{self, TemplateVars},
+};
+use anyhow::{Result, anyhow};
+use git2::{Oid, Repository, RepositoryInitOptions};
+use std::fs;
+use std::path::Path;
+use std::time::SystemTime;
+use tracing::info;
 
-pub fn execute(url: String) -> Result<()> {
-    println!("Create command for repository: {url}");
-    println!("repo create functionality not yet implemented");
+/// Options for `repo create`.
+#[derive(Debug, Clone, Default)]
+pub struct CreateOptions {
+    /// Leave the branch unborn instead of creating an initial commit.
+    pub empty: bool,
+    /// Template name, directory, or repository URL to seed the worktree from.
+    pub template: Option<String>,
+    pub license: Option<License>,
+    pub gitignore: Option<GitignoreTemplate>,
+}
+
+impl CreateOptions {
+    fn seeds_files(&self) -> bool {
+        self.template.is_some() || self.license.is_some() || self.gitignore.is_some()
+    }
+}
+
+pub fn execute(config: Config, url: String, options: CreateOptions) -> Result<()> {
+    let url = spec::resolve_url(&config, &url)?;
+    let giturl::GitUrl {
+        host, owner, repo, ..
+    } = giturl::parse(&url)?;
+    let root = paths::resolve_root(&config, &host);
+    paths::ensure_root(&root)?;
+    let repo_dir = paths::repository_dir(&root, &host, &owner, &repo);
+    let settings = RepoSettings::load(&config, &format!("{host}/{owner}/{repo}"), &repo_dir)?;
+
+    let branch = config.initial_branch();
+    let bare_repo_path = paths::bare_repository_path(&repo_dir);
+    let worktree_path = paths::worktree_path(&repo_dir, branch);
+
+    info!("Creating repository in {}", bare_repo_path.display());
+    let bare = init_bare_repository(&config, &bare_repo_path, branch)?;
+    if options.empty || options.seeds_files() {
+        create_orphan_worktree(&bare_repo_path, &worktree_path, branch)?;
+
+        let vars = TemplateVars {
+            author: bare
+                .signature()
+                .ok()
+                .and_then(|signature| signature.name().map(str::to_string))
+                .unwrap_or_else(|| owner.clone()),
+            year: status::format_timestamp(SystemTime::now())[..4].to_string(),
+            host,
+            owner,
+            repo_name: repo,
+        };
+        seed_worktree(&config, &worktree_path, &options, &vars)?;
+
+        if !options.empty {
+            let tree = stage_all(&Repository::open(&worktree_path)?)?;
+            create_initial_commit(&bare, branch, tree)?;
+        }
+    } else {
+        let tree = bare.treebuilder(None)?.write()?;
+        create_initial_commit(&bare, branch, tree)?;
+        create_worktree(&bare_repo_path, &worktree_path, branch)?;
+    }
+    settings.setup_worktree(&repo_dir, &worktree_path)?;
+    index::update(&root);
+    events::emit(
+        &config,
+        &Event::worktree(
+            EventKind::WorktreeCreated,
+            &repo_dir,
+            &worktree_path,
+            Some(branch),
+        ),
+    );
+
+    info!("Repository is ready");
+    println!("{}", worktree_path.display());
+    Ok(())
+}
+
+/// Writes the template, license, and .gitignore files into the worktree.
+fn seed_worktree(
+    config: &Config,
+    worktree_path: &Path,
+    options: &CreateOptions,
+    vars: &TemplateVars,
+) -> Result<()> {
+    if let Some(template) = &options.template {
+        templates::apply_template(config, template, worktree_path, vars)?;
+    }
+    if let Some(license) = options.license {
+        templates::write_license(worktree_path, license, vars)?;
+    }
+    if let Some(gitignore) = options.gitignore {
+        templates::write_gitignore(worktree_path, gitignore)?;
+    }
     Ok(())
 }
+
+/// Stages every file in the worktree and returns the resulting tree.
+fn stage_all(worktree: &Repository) -> Result<Oid> {
+    let mut index = worktree.index()?;
+    index.add_all(["*"], git2::IndexAddOption::DEFAULT, None)?;
+    index.write()?;
+    Ok(index.write_tree()?)
+}
+
+/// Initializes a bare repository whose HEAD points at the unborn `branch`.
+fn init_bare_repository(
+    config: &Config,
+    bare_repo_path: &Path,
+    branch: &str,
+) -> Result<Repository> {
+    if bare_repo_path.exists() {
+        return Err(anyhow!(
+            "Repository already exists: {}",
+            bare_repo_path.display()
+        ));
+    }
+
+    let mut options = RepositoryInitOptions::new();
+    options.bare(true).initial_head(branch);
+    let repo = Repository::init_opts(bare_repo_path, &options)?;
+
+    if let Some(identity) = &config.identity {
+        apply_identity(bare_repo_path, identity)?;
+    }
+    githooks::install(&config.git_hooks, bare_repo_path, false)?;
+
+    Ok(repo)
+}
+
+/// Starts `branch` with a commit of `tree` authored by the user, signed if
+/// `commit.gpgSign` is enabled.
+fn create_initial_commit(repo: &Repository, branch: &str, tree: Oid) -> Result<Oid> {
+    let signature = repo.signature().map_err(|_| {
+        anyhow!("No commit identity configured; set user.name and user.email in your gitconfig")
+    })?;
+    let tree = repo.find_tree(tree)?;
+    let commit = signing::commit(repo, &signature, "Initial commit", &tree, &[])?;
+    repo.reference(
+        &format!("refs/heads/{branch}"),
+        commit,
+        false,
+        "repo create: initial commit",
+    )?;
+
+    Ok(commit)
+}
+
+/// Creates a worktree on the unborn `branch`, as `git worktree add --orphan`
+/// does; the branch comes into existence with the first commit made in it.
+/// libgit2 can only add worktrees for existing commits, so the
+/// administrative files are written directly.
+fn create_orphan_worktree(bare_repo_path: &Path, worktree_path: &Path, branch: &str) -> Result<()> {
+    let name = worktree_path
+        .file_name()
+        .ok_or_else(|| anyhow!("Invalid worktree path: {}", worktree_path.display()))?;
+    let admin_dir = bare_repo_path.join("worktrees").join(name);
+    if admin_dir.exists() || worktree_path.exists() {
+        return Err(anyhow!(
+            "Worktree already exists: {}",
+            worktree_path.display()
+        ));
+    }
+
+    fs::create_dir_all(&admin_dir)?;
+    fs::create_dir_all(worktree_path)?;
+    // These paths are written into the worktree's gitdir files for git to read
+    let admin_dir = paths::canonicalize(&admin_dir)?;
+    let worktree_path = paths::canonicalize(worktree_path)?;
+
+    fs::write(
+        admin_dir.join("HEAD"),
+        format!("ref: refs/heads/{branch}\n"),
+    )?;
+    fs::write(admin_dir.join("commondir"), "../..\n")?;
+    fs::write(
+        admin_dir.join("gitdir"),
+        format!("{}\n", worktree_path.join(".git").display()),
+    )?;
+    fs::write(
+        worktree_path.join(".git"),
+        format!("gitdir: {}\n", admin_dir.display()),
+    )?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use crate::config::Identity;
+    use crate::test_utils::commit_all;
+
+    fn config_with_identity() -> Config {
+        Config {
+            identity: Some(Identity {
+                name: "Jane Doe".to_string(),
+                email: "jane@company.com".to_string(),
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_create_initial_commit() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let bare_repo_path = temp_dir.path().join("repo/.git");
+        let config = config_with_identity();
+
+        let repo = init_bare_repository(&config, &bare_repo_path, "trunk").unwrap();
+        let tree = repo.treebuilder(None).unwrap().write().unwrap();
+        let commit = create_initial_commit(&repo, "trunk", tree).unwrap();
+
+        assert_eq!(repo.head().unwrap().shorthand(), Some("trunk"));
+        let commit = repo.find_commit(commit).unwrap();
+        assert_eq!(commit.author().name(), Some("Jane Doe"));
+        assert_eq!(commit.author().email(), Some("jane@company.com"));
+        assert!(commit.time().seconds() > 0);
+        assert_eq!(commit.tree().unwrap().len(), 0);
+        assert_eq!(
+            repo.find_reference("refs/heads/trunk").unwrap().target(),
+            Some(commit.id())
+        );
+    }
+
+    #[test]
+    fn test_init_bare_repository_when_it_exists() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let bare_repo_path = temp_dir.path().join("repo/.git");
+        std::fs::create_dir_all(&bare_repo_path).unwrap();
+
+        let result = init_bare_repository(&config_with_identity(), &bare_repo_path, "main");
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_create_orphan_worktree() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let bare_repo_path = temp_dir.path().join("repo/.git");
+        let worktree_path = temp_dir.path().join("repo/main");
+        let bare = init_bare_repository(&config_with_identity(), &bare_repo_path, "main").unwrap();
+
+        create_orphan_worktree(&bare_repo_path, &worktree_path, "main").unwrap();
+
+        let worktree = Repository::open(&worktree_path).unwrap();
+        assert!(matches!(
+            worktree.head().map(|_| ()),
+            Err(error) if error.code() == git2::ErrorCode::UnbornBranch
+        ));
+        assert!(bare.find_worktree("main").is_ok());
+
+        std::fs::write(worktree_path.join("README"), "hello").unwrap();
+        let commit = commit_all(&worktree, "First real commit");
+        assert_eq!(
+            bare.find_reference("refs/heads/main").unwrap().target(),
+            Some(commit)
+        );
+        assert!(create_orphan_worktree(&bare_repo_path, &worktree_path, "main").is_err());
+    }
+
+    #[test]
+    fn test_execute_creates_repository_and_worktree() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config = Config {
+            root: temp_dir.path().to_path_buf(),
+            init_default_branch: Some("develop".to_string()),
+            ..config_with_identity()
+        };
+
+        execute(
+            config,
+            "https://github.com/user/new".to_string(),
+            CreateOptions::default(),
+        )
+        .unwrap();
+
+        let worktree_path = temp_dir.path().join("github.com/user/new/develop");
+        let worktree = Repository::open(&worktree_path).unwrap();
+        assert_eq!(worktree.head().unwrap().shorthand(), Some("develop"));
+    }
+
+    #[test]
+    fn test_execute_empty_creates_no_commit() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config = Config {
+            root: temp_dir.path().to_path_buf(),
+            ..config_with_identity()
+        };
+
+        execute(
+            config,
+            "https://github.com/user/new".to_string(),
+            CreateOptions {
+                empty: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let repo_dir = temp_dir.path().join("github.com/user/new");
+        let bare = Repository::open_bare(repo_dir.join(".git")).unwrap();
+        assert!(bare.head().is_err());
+        assert!(Repository::open(repo_dir.join("main")).is_ok());
+    }
+
+    #[test]
+    fn test_execute_seeds_initial_commit() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let template = temp_dir.path().join("template");
+        std::fs::create_dir_all(&template).unwrap();
+        std::fs::write(template.join("README.md"), "# {{repo_name}}\n").unwrap();
+        let config = Config {
+            root: temp_dir.path().join("root"),
+            ..config_with_identity()
+        };
+
+        execute(
+            config,
+            "https://github.com/user/widget".to_string(),
+            CreateOptions {
+                template: Some(template.display().to_string()),
+                license: Some(License::Mit),
+                gitignore: Some(GitignoreTemplate::Rust),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let worktree_path = temp_dir.path().join("root/github.com/user/widget/main");
+        assert_eq!(
+            std::fs::read_to_string(worktree_path.join("README.md")).unwrap(),
+            "# widget\n"
+        );
+        let worktree = Repository::open(&worktree_path).unwrap();
+        let commit = worktree.head().unwrap().peel_to_commit().unwrap();
+        let tree = commit.tree().unwrap();
+        for file in ["README.md", "LICENSE", ".gitignore"] {
+            assert!(tree.get_name(file).is_some(), "{file} is not committed");
+        }
+        assert!(
+            std::fs::read_to_string(worktree_path.join("LICENSE"))
+                .unwrap()
+                .contains("Jane Doe")
+        );
+        let statuses = worktree.statuses(None).unwrap();
+        assert!(statuses.is_empty());
+    }
+}
@@ -0,0 +1,296 @@
+use super::{
+    get::fetch_remote,
+    switch::{find_repository_path, not_found_message},
+};
+use crate::{
+    config::{Config, Credentials, Prune},
+    exit::{self, ErrorKind},
+    lock::RepoLock,
+    redirect,
+    scan::{self, RepoEntry},
+    status,
+};
+use anyhow::{Result, anyhow};
+use git2::Repository;
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// How fetching one repository ended.
+#[derive(Debug)]
+struct FetchResult {
+    spec: String,
+    /// The error of the last attempt, if every attempt failed.
+    error: Option<anyhow::Error>,
+}
+
+/// Fetches every remote of `repos`, or of every managed repository with
+/// `all`, up to `jobs` repositories at once. Failed fetches are retried up
+/// to `retries` times, except for authentication failures. `prune` and
+/// `prune_tags` add to `[fetch] prune` and `prune_tags`.
+pub fn execute(
+    config: Config,
+    repos: Vec<String>,
+    all: bool,
+    jobs: Option<usize>,
+    retries: u32,
+    prune: bool,
+    prune_tags: bool,
+) -> Result<()> {
+    let entries = if all {
+        scan::scan_roots(&config.all_roots())?
+    } else {
+        repos
+            .iter()
+            .map(|repo| {
+                let Some(repo_path) = find_repository_path(&config, repo)? else {
+                    return Err(exit::not_found(false, not_found_message(&config, repo)?));
+                };
+                RepoEntry::from_path(&repo_path)
+                    .ok_or_else(|| anyhow!("Not a repository directory: {}", repo_path.display()))
+            })
+            .collect::<Result<Vec<_>>>()?
+    };
+
+    // Concurrent prompts would interleave, so only a single fetch may ask
+    let credentials = Credentials {
+        prompt: config.credentials.prompt && entries.len() == 1,
+        ..config.credentials.clone()
+    };
+    let prune = Prune {
+        branches: config.fetch_prune.branches || prune || prune_tags,
+        tags: config.fetch_prune.tags || prune_tags,
+    };
+    let results = status::parallel_map(&entries, jobs, |entry| {
+        let result =
+            fetch_with_retries(entry, &credentials, retries, config.follow_redirects, prune);
+        FetchResult {
+            spec: entry.spec(),
+            error: result.err(),
+        }
+    });
+
+    let failed = results
+        .iter()
+        .filter_map(|result| Some((&result.spec, result.error.as_ref()?)))
+        .collect::<Vec<_>>();
+    let auth_failures = failed
+        .iter()
+        .filter(|(_, error)| exit::classify(error) == Some(ErrorKind::Auth))
+        .count();
+    info!(
+        "Fetched {} repositories, {} failed ({} authentication)",
+        results.len() - failed.len(),
+        failed.len(),
+        auth_failures
+    );
+    for (spec, error) in &failed {
+        warn!("{spec}: {error:#}");
+    }
+
+    match failed.len() {
+        0 => Ok(()),
+        count if count == auth_failures => Err(exit::error(
+            ErrorKind::Auth,
+            format!("Authentication failed for {count} repositories"),
+        )),
+        count => Err(anyhow!("Failed to fetch {count} repositories")),
+    }
+}
+
+/// Fetches every remote of `entry`, retrying failures with a growing delay,
+/// and records the outcome and whether `origin` moved for `status`. With
+/// `follow_redirects`, a moved `origin` is pointed at its new URL.
+pub(crate) fn fetch_with_retries(
+    entry: &RepoEntry,
+    credentials: &Credentials,
+    retries: u32,
+    follow_redirects: bool,
+    prune: Prune,
+) -> Result<()> {
+    let bare_repo_path = entry.bare_repository_path();
+    let _lock = RepoLock::acquire(&bare_repo_path)?;
+    let mut attempt = 0;
+    let result = loop {
+        let result = fetch_all_remotes(entry, credentials, prune);
+        match &result {
+            Err(error) if attempt < retries && exit::classify(error) != Some(ErrorKind::Auth) => {
+                attempt += 1;
+                warn!("Retrying {} ({attempt}/{retries}): {error:#}", entry.spec());
+                std::thread::sleep(Duration::from_secs(u64::from(attempt)));
+            }
+            _ => break result,
+        }
+    };
+    if result.is_ok() {
+        info!("Fetched {}", entry.spec());
+        if let Err(error) = check_redirect(entry, follow_redirects) {
+            warn!("{}: {error:#}", entry.spec());
+        }
+    }
+    status::record_fetch(&bare_repo_path, &result)?;
+    result
+}
+
+/// Asks whether `origin` of `entry` moved, and either points it at the new
+/// URL with `follow_redirects` or remembers the move for `status`.
+fn check_redirect(entry: &RepoEntry, follow_redirects: bool) -> Result<()> {
+    let bare_repo_path = entry.bare_repository_path();
+    let repo = Repository::open_bare(&bare_repo_path)?;
+    let Some(url) = repo
+        .find_remote("origin")
+        .ok()
+        .and_then(|remote| remote.url().map(str::to_string))
+    else {
+        return Ok(());
+    };
+    match redirect::detect(&url)? {
+        Some(target) if follow_redirects => {
+            repo.remote_set_url("origin", &target)?;
+            info!("{}: origin moved to {target}; updated it", entry.spec());
+            redirect::record(&bare_repo_path, None)
+        }
+        Some(target) => {
+            warn!(
+                "{}: origin moved to {target}; `neoghq repo remote update --follow-redirect {}` updates it",
+                entry.spec(),
+                entry.spec()
+            );
+            redirect::record(&bare_repo_path, Some(&target))
+        }
+        None => redirect::record(&bare_repo_path, None),
+    }
+}
+
+fn fetch_all_remotes(entry: &RepoEntry, credentials: &Credentials, prune: Prune) -> Result<()> {
+    let bare_repo_path = entry.bare_repository_path();
+    let repo = Repository::open_bare(&bare_repo_path)?;
+    for name in repo.remotes()?.iter().flatten() {
+        fetch_remote(&bare_repo_path, name, credentials, prune)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use crate::test_utils::{commit_all, create_managed_repository};
+
+    #[test]
+    fn test_fetch_all_remotes() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("github.com/user/repo");
+        let bare = create_managed_repository(&path);
+        let upstream_path = temp_dir.path().join("upstream");
+        let upstream = crate::test_utils::create_source_repository(&upstream_path);
+        std::fs::write(upstream_path.join("NEW"), "new").unwrap();
+        let commit = commit_all(&upstream, "Add NEW");
+        // The source create_managed_repository cloned from is gone
+        bare.remote_set_url("origin", upstream_path.to_str().unwrap())
+            .unwrap();
+        bare.remote("upstream", upstream_path.to_str().unwrap())
+            .unwrap();
+        let entry = RepoEntry::from_path(&path).unwrap();
+
+        fetch_with_retries(&entry, &Credentials::default(), 0, false, Prune::default()).unwrap();
+        assert_eq!(
+            bare.find_reference("refs/remotes/upstream/main")
+                .unwrap()
+                .target(),
+            Some(commit)
+        );
+        assert_eq!(status::fetch_error(&entry.bare_repository_path()), None);
+    }
+
+    #[test]
+    fn test_fetch_prunes() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("github.com/user/repo");
+        let bare = create_managed_repository(&path);
+        let source_path = temp_dir.path().join("source");
+        let source = crate::test_utils::create_source_repository(&source_path);
+        let head = source.head().unwrap().peel_to_commit().unwrap();
+        source.branch("merged", &head, false).unwrap();
+        source
+            .tag_lightweight("stale", head.as_object(), false)
+            .unwrap();
+        bare.remote_set_url("origin", source_path.to_str().unwrap())
+            .unwrap();
+        let entry = RepoEntry::from_path(&path).unwrap();
+        let fetch = |prune| fetch_with_retries(&entry, &Credentials::default(), 0, false, prune);
+        let exists = |name: &str| bare.find_reference(name).is_ok();
+
+        fetch(Prune::default()).unwrap();
+        assert!(exists("refs/remotes/origin/merged"));
+        assert!(exists("refs/tags/stale"));
+        source
+            .find_branch("merged", git2::BranchType::Local)
+            .unwrap()
+            .delete()
+            .unwrap();
+        source.tag_delete("stale").unwrap();
+
+        fetch(Prune {
+            branches: true,
+            tags: false,
+        })
+        .unwrap();
+        assert!(!exists("refs/remotes/origin/merged"));
+        assert!(exists("refs/remotes/origin/main"));
+        assert!(exists("refs/tags/stale"));
+        fetch(Prune {
+            branches: true,
+            tags: true,
+        })
+        .unwrap();
+        assert!(!exists("refs/tags/stale"));
+        assert!(exists("refs/remotes/origin/main"));
+    }
+
+    #[test]
+    fn test_execute() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("github.com/user/repo");
+        let bare = create_managed_repository(&path);
+        let source_dir = tempfile::tempdir().unwrap();
+        let source_path = source_dir.path();
+        crate::test_utils::create_source_repository(source_path);
+        bare.remote_set_url("origin", source_path.to_str().unwrap())
+            .unwrap();
+        let config = Config {
+            root: temp_dir.path().to_path_buf(),
+            ..Default::default()
+        };
+
+        assert!(execute(config.clone(), Vec::new(), true, Some(2), 0, true, false).is_ok());
+        assert!(
+            execute(
+                config.clone(),
+                vec!["user/repo".to_string()],
+                false,
+                None,
+                0,
+                false,
+                false
+            )
+            .is_ok()
+        );
+        assert!(
+            execute(
+                config.clone(),
+                vec!["user/missing".to_string()],
+                false,
+                None,
+                0,
+                false,
+                false
+            )
+            .is_err()
+        );
+
+        bare.remote_set_url("origin", temp_dir.path().join("gone").to_str().unwrap())
+            .unwrap();
+        assert!(execute(config, Vec::new(), true, None, 1, false, false).is_err());
+        assert!(status::fetch_error(bare.path()).is_some());
+    }
+}
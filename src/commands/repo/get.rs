@@ -0,0 +1,1243 @@
+#[cfg(feature = "gix")]
+use crate::backend::{self, GitBackend};
+use crate::{
+    case,
+    config::{Config, Credentials, Identity, Prune},
+    credentials, editor,
+    events::{self, Event, EventKind},
+    forge::Forge,
+    githooks, giturl, index,
+    lock::RepoLock,
+    metadata::Metadata,
+    paths,
+    repo_settings::RepoSettings,
+    scan::RepoEntry,
+    spec, status,
+    transport::{self, Backend},
+};
+use anyhow::{Result, anyhow, bail};
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// What `repo get` has to do to bring a repository up to date, decided from
+/// the current state of its directory under the root.
+#[derive(Debug, PartialEq, Eq)]
+enum GetAction {
+    /// No bare repository exists yet; clone it.
+    Clone,
+    /// A bare repository with an `origin` remote exists; fetch it.
+    Update,
+    /// A bare repository without `origin` exists (e.g. from `repo create`);
+    /// attach the URL as `origin` and fetch it.
+    Adopt,
+}
+
+/// Gets the repository at `url` and prints its worktree for `branch`,
+/// opening it in the editor with `open`.
+pub fn execute(config: Config, url: String, branch: Option<String>, open: bool) -> Result<()> {
+    let worktree_path = execute_get_command(url, branch, config.clone())?;
+    if open {
+        editor::open(&config, &worktree_path)?;
+    }
+    Ok(())
+}
+
+fn detect_action(bare_repo_path: &Path) -> Result<GetAction> {
+    use git2::Repository;
+
+    if !bare_repo_path.exists() {
+        return Ok(GetAction::Clone);
+    }
+
+    let repo = Repository::open_bare(bare_repo_path).map_err(|_| {
+        anyhow!(
+            "{} exists but is not a bare git repository",
+            bare_repo_path.display()
+        )
+    })?;
+
+    if repo.find_remote("origin").is_ok() {
+        Ok(GetAction::Update)
+    } else {
+        Ok(GetAction::Adopt)
+    }
+}
+
+fn clone_repository_bare(
+    url: &str,
+    path: &std::path::Path,
+    credentials: &Credentials,
+) -> Result<()> {
+    use std::fs;
+
+    // Create parent directories if they don't exist
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    #[cfg(feature = "gix")]
+    if backend::current() == GitBackend::Gix {
+        backend::gix::clone_bare(url, path, credentials)?;
+        return enable_long_paths(path);
+    }
+
+    if transport::select_backend(url)? == Backend::GitCli {
+        transport::git_clone_bare(url, path, credentials.prompt)?;
+    } else {
+        // Clone as bare repository
+        let mut fetch_options = git2::FetchOptions::new();
+        fetch_options.remote_callbacks(credentials::remote_callbacks(credentials));
+        let mut builder = git2::build::RepoBuilder::new();
+        builder.bare(true).fetch_options(fetch_options);
+
+        builder.clone(url, path)?;
+    }
+
+    enable_long_paths(path)
+}
+
+/// Lets git on Windows check out paths longer than `MAX_PATH`, which
+/// worktrees nested under a deep root and branch directory run into.
+fn enable_long_paths(bare_repo_path: &Path) -> Result<()> {
+    if cfg!(windows) {
+        let repo = git2::Repository::open_bare(bare_repo_path)?;
+        let mut config = repo.config()?.open_level(git2::ConfigLevel::Local)?;
+        config.set_bool("core.longpaths", true)?;
+    }
+    Ok(())
+}
+
+fn fetch_repository(bare_repo_path: &Path, credentials: &Credentials, prune: Prune) -> Result<()> {
+    fetch_remote(bare_repo_path, "origin", credentials, prune)
+}
+
+/// Fetches the remote `name` of the bare repository, through libgit2 or the
+/// git CLI depending on the remote's transport, or through gitoxide,
+/// pruning what `prune` asks for.
+pub(crate) fn fetch_remote(
+    bare_repo_path: &Path,
+    name: &str,
+    credentials: &Credentials,
+    prune: Prune,
+) -> Result<()> {
+    use git2::Repository;
+
+    #[cfg(feature = "gix")]
+    if backend::current() == GitBackend::Gix {
+        return backend::gix::fetch(bare_repo_path, name, credentials, prune);
+    }
+
+    let repo = Repository::open_bare(bare_repo_path)?;
+    let mut remote = repo.find_remote(name)?;
+    if transport::select_backend(remote.url().unwrap_or_default())? == Backend::GitCli {
+        return transport::git_fetch(bare_repo_path, name, prune, credentials.prompt);
+    }
+
+    let mut fetch_options = git2::FetchOptions::new();
+    fetch_options.remote_callbacks(credentials::remote_callbacks(credentials));
+    let mut refspecs = Vec::new();
+    if prune.branches {
+        fetch_options.prune(git2::FetchPrune::On);
+    }
+    if prune.tags {
+        // As with `git fetch --prune-tags`, tags are pruned by fetching them
+        // with a refspec of their own next to the configured ones
+        refspecs = remote
+            .fetch_refspecs()?
+            .iter()
+            .flatten()
+            .map(str::to_string)
+            .collect();
+        refspecs.push("+refs/tags/*:refs/tags/*".to_string());
+    }
+    remote.fetch(&refspecs, Some(&mut fetch_options), None)?;
+
+    Ok(())
+}
+
+/// Fetches `refspecs` from the remote `name` of the bare repository instead
+/// of its configured ones, such as the head of a pull request.
+pub(crate) fn fetch_refspecs(
+    bare_repo_path: &Path,
+    name: &str,
+    refspecs: &[&str],
+    credentials: &Credentials,
+) -> Result<()> {
+    use git2::Repository;
+
+    let repo = Repository::open_bare(bare_repo_path)?;
+    let mut remote = repo.find_remote(name)?;
+    if transport::select_backend(remote.url().unwrap_or_default())? == Backend::GitCli {
+        let mut args = vec!["fetch", "--quiet", name];
+        args.extend_from_slice(refspecs);
+        return transport::run_git(Some(bare_repo_path), credentials.prompt, &args);
+    }
+
+    let mut fetch_options = git2::FetchOptions::new();
+    fetch_options.remote_callbacks(credentials::remote_callbacks(credentials));
+    remote.fetch(refspecs, Some(&mut fetch_options), None)?;
+    Ok(())
+}
+
+/// Pushes `refspecs` to the remote `name` of the bare repository, through
+/// libgit2 or the git CLI depending on the remote's transport, failing when
+/// the remote rejects any of them.
+pub(crate) fn push_remote(
+    bare_repo_path: &Path,
+    name: &str,
+    refspecs: &[&str],
+    credentials: &Credentials,
+) -> Result<()> {
+    use git2::Repository;
+
+    let repo = Repository::open_bare(bare_repo_path)?;
+    let mut remote = repo.find_remote(name)?;
+    if transport::select_backend(remote.url().unwrap_or_default())? == Backend::GitCli {
+        return transport::git_push(bare_repo_path, name, refspecs, credentials.prompt);
+    }
+    push_libgit2(&mut remote, refspecs, credentials, None)
+}
+
+/// The ref a force-push may only overwrite while the remote still has it
+/// at the given commit, `None` meaning the remote doesn't have it.
+type Lease<'a> = (&'a str, Option<git2::Oid>);
+
+/// Pushes `refspecs` through libgit2. A `lease` is checked against the
+/// remote's refs as the push negotiates them, in the same connection, so
+/// nothing pushed in between is overwritten.
+fn push_libgit2(
+    remote: &mut git2::Remote,
+    refspecs: &[&str],
+    credentials: &Credentials,
+    lease: Option<Lease>,
+) -> Result<()> {
+    use std::cell::{Cell, RefCell};
+
+    let name = remote.name().unwrap_or("the remote").to_string();
+    // libgit2 reports refs the remote refused here rather than as an error
+    let rejected = RefCell::new(Vec::new());
+    let lease_broken = Cell::new(false);
+    let mut callbacks = credentials::remote_callbacks(credentials);
+    callbacks.push_update_reference(|reference, status| {
+        if let Some(status) = status {
+            rejected
+                .borrow_mut()
+                .push(format!("{reference} ({status})"));
+        }
+        Ok(())
+    });
+    if let Some((reference, expected)) = lease {
+        let lease_broken = &lease_broken;
+        callbacks.push_negotiation(move |updates| {
+            let expected = expected.unwrap_or_else(git2::Oid::zero);
+            if updates
+                .iter()
+                .any(|update| update.dst_refname() == Some(reference) && update.src() != expected)
+            {
+                lease_broken.set(true);
+                return Err(git2::Error::from_str("stale lease"));
+            }
+            Ok(())
+        });
+    }
+    let mut push_options = git2::PushOptions::new();
+    push_options.remote_callbacks(callbacks);
+    let pushed = remote.push(refspecs, Some(&mut push_options));
+    if let Some((reference, _)) = lease
+        && lease_broken.get()
+    {
+        bail!("{reference} on {name} changed since it was last fetched; fetch and review it first");
+    }
+    pushed?;
+
+    let rejected = rejected.borrow();
+    if !rejected.is_empty() {
+        bail!("{name} rejected {}", rejected.join(", "));
+    }
+    Ok(())
+}
+
+/// Force-pushes `reference` to the same name on the remote `name` unless
+/// the remote's copy moved from `expected`, what its remote-tracking branch
+/// last recorded (`None` when the remote didn't have it), like
+/// `git push --force-with-lease`.
+pub(crate) fn force_push_with_lease(
+    bare_repo_path: &Path,
+    name: &str,
+    reference: &str,
+    expected: Option<git2::Oid>,
+    credentials: &Credentials,
+) -> Result<()> {
+    use git2::Repository;
+
+    let refspec = format!("+{reference}:{reference}");
+    let repo = Repository::open_bare(bare_repo_path)?;
+    let mut remote = repo.find_remote(name)?;
+    if transport::select_backend(remote.url().unwrap_or_default())? == Backend::GitCli {
+        let expected = expected.map(|oid| oid.to_string()).unwrap_or_default();
+        return transport::run_git(
+            Some(bare_repo_path),
+            credentials.prompt,
+            &[
+                "push",
+                "--quiet",
+                &format!("--force-with-lease={reference}:{expected}"),
+                name,
+                &refspec,
+            ],
+        );
+    }
+
+    push_libgit2(
+        &mut remote,
+        &[&refspec],
+        credentials,
+        Some((reference, expected)),
+    )
+}
+
+fn adopt_repository(
+    url: &str,
+    bare_repo_path: &Path,
+    credentials: &Credentials,
+    prune: Prune,
+) -> Result<()> {
+    use git2::Repository;
+
+    let repo = Repository::open_bare(bare_repo_path)?;
+    repo.remote("origin", url)?;
+
+    fetch_repository(bare_repo_path, credentials, prune)
+}
+
+/// Writes the profile's commit identity into the repository's local config,
+/// so commits in every worktree use it.
+pub(super) fn apply_identity(bare_repo_path: &Path, identity: &Identity) -> Result<()> {
+    use git2::Repository;
+
+    let repo = Repository::open_bare(bare_repo_path)?;
+    let mut config = repo.config()?.open_level(git2::ConfigLevel::Local)?;
+    config.set_str("user.name", &identity.name)?;
+    config.set_str("user.email", &identity.email)?;
+
+    Ok(())
+}
+
+pub(crate) fn create_worktree(
+    bare_repo_path: &std::path::Path,
+    worktree_path: &std::path::Path,
+    branch: &str,
+) -> Result<()> {
+    use git2::Repository;
+    use std::fs;
+
+    // Create parent directories if they don't exist
+    if let Some(parent) = worktree_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    // Open the bare repository
+    let repo = Repository::open(bare_repo_path)?;
+
+    // Create a local branch from the remote-tracking branch if only that exists
+    let branch_ref = format!("refs/heads/{branch}");
+    if repo.find_reference(&branch_ref).is_err()
+        && let Ok(remote_branch) = repo.find_reference(&format!("refs/remotes/origin/{branch}"))
+    {
+        repo.branch(branch, &remote_branch.peel_to_commit()?, false)?;
+    }
+
+    // Create worktree. Its administrative directory under `.git/worktrees`
+    // can't nest, so branches like `feature/x` are registered as `feature-x`
+    let name = branch.replace('/', "-");
+    let mut opts = git2::WorktreeAddOptions::new();
+
+    if let Ok(reference) = repo.find_reference(&branch_ref) {
+        opts.reference(Some(&reference));
+        repo.worktree(&name, worktree_path, Some(&opts))?;
+    } else {
+        repo.worktree(&name, worktree_path, Some(&opts))?;
+    }
+
+    Ok(())
+}
+
+fn get_repository(
+    config: &Config,
+    settings: &RepoSettings,
+    url: &str,
+    repo_dir: &Path,
+    worktree_path: &Path,
+    branch: &str,
+) -> Result<GetAction> {
+    let bare_repo_path = &paths::bare_repository_path(repo_dir);
+    let _lock = RepoLock::acquire(bare_repo_path)?;
+    let action = detect_action(bare_repo_path)?;
+    match action {
+        GetAction::Clone => {
+            info!("Cloning {} into {}", url, bare_repo_path.display());
+            clone_repository_bare(url, bare_repo_path, &config.credentials)?;
+        }
+        GetAction::Update => {
+            info!("Fetching {} into {}", url, bare_repo_path.display());
+            let result = fetch_repository(bare_repo_path, &config.credentials, config.fetch_prune);
+            status::record_fetch(bare_repo_path, &result)?;
+            result?;
+        }
+        GetAction::Adopt => {
+            info!("Adopting {} as origin of {}", url, bare_repo_path.display());
+            let result =
+                adopt_repository(url, bare_repo_path, &config.credentials, config.fetch_prune);
+            status::record_fetch(bare_repo_path, &result)?;
+            result?;
+        }
+    }
+
+    if let Some(identity) = &config.identity {
+        apply_identity(bare_repo_path, identity)?;
+    }
+    // Hooks of repositories that were already here are left to `repo hooks sync`
+    if action != GetAction::Update {
+        githooks::install(&config.git_hooks, bare_repo_path, false)?;
+    }
+
+    // Create the worktree if it doesn't exist
+    if !worktree_path.exists() {
+        info!(
+            "Creating worktree for branch '{}' in {}",
+            branch,
+            worktree_path.display()
+        );
+        create_worktree(bare_repo_path, worktree_path, branch)?;
+        settings.setup_worktree(repo_dir, worktree_path)?;
+    }
+
+    Ok(action)
+}
+
+/// Returns the directory for `host/owner/repo` under `root`. A repository
+/// whose name differs only in case is reused when it has the same origin, as
+/// on hosts such as GitHub. A different one would share the directory on a
+/// case-insensitive filesystem, which is an error there and a warning
+/// elsewhere.
+fn resolve_repository_dir(
+    root: &Path,
+    host: &str,
+    owner: &str,
+    repo: &str,
+    url: &str,
+) -> Result<PathBuf> {
+    let repo_dir = paths::repository_dir(root, host, owner, repo);
+    let owner = &paths::owner_dir(owner);
+    let requested = format!("{host}/{owner}/{repo}");
+    let recorded = Metadata::load(root)?
+        .canonical_spec(&requested)
+        .map(|spec| root.join(spec))
+        .filter(|path| path.join(".git").exists());
+    let existing = match recorded {
+        Some(path) => Some(path),
+        None => case::find_ignoring_case(root, &[host, owner, repo])?,
+    };
+    let Some(existing) = existing.filter(|existing| *existing != repo_dir) else {
+        return Ok(repo_dir);
+    };
+    let existing_spec = RepoEntry::from_path(&existing)
+        .map_or_else(|| existing.display().to_string(), |entry| entry.spec());
+
+    if case::has_origin(&existing, url) {
+        warn!("Using {existing_spec} for {requested}: the names differ only in case");
+        return Ok(existing);
+    }
+    // On a case-insensitive filesystem the requested spelling finds the
+    // existing directory
+    if repo_dir.exists() {
+        bail!(
+            "{requested} would share the directory of {existing_spec}, a different repository, because this filesystem ignores case"
+        );
+    }
+    warn!(
+        "{requested} and {existing_spec} differ only in case and would share a directory on case-insensitive filesystems such as macOS and Windows"
+    );
+    Ok(repo_dir)
+}
+
+fn execute_get_command(url: String, branch: Option<String>, config: Config) -> Result<PathBuf> {
+    // Expand specs such as `gl:group/proj` or `user/repo` into a clone URL
+    let url = spec::resolve_url(&config, &url)?;
+
+    // Respect ghq.<url>.vcs for users migrating from ghq
+    if let Some(vcs) = config.ghq.vcs_for(&url)
+        && !matches!(vcs, "git" | "github")
+    {
+        return Err(anyhow!(
+            "Unsupported VCS '{vcs}' configured by ghq.<url>.vcs for {url}"
+        ));
+    }
+
+    // Parse the repository URL to extract host, owner, and repo
+    let giturl::GitUrl {
+        host, owner, repo, ..
+    } = giturl::parse(&url)?;
+
+    // Use the selected root, the host's root, or the primary root from config
+    let root = paths::resolve_root(&config, &host);
+    paths::ensure_root(&root)?;
+    let repo_dir = resolve_repository_dir(&root, &host, &owner, &repo, &url)?;
+    let spec = RepoEntry::from_path(&repo_dir)
+        .map_or_else(|| format!("{host}/{owner}/{repo}"), |entry| entry.spec());
+    let settings = RepoSettings::load(&config, &spec, &repo_dir)?;
+
+    // Determine the branch to use, falling back to the repository's default
+    // worktree and then git.default_branch
+    let branch = branch
+        .or_else(|| settings.default_worktree.clone())
+        .unwrap_or_else(|| config.default_branch().to_string());
+    let worktree_path = paths::worktree_path(&repo_dir, &branch);
+    let creates_worktree = !worktree_path.exists();
+
+    let action = get_repository(&config, &settings, &url, &repo_dir, &worktree_path, &branch)?;
+    index::update(&root);
+    // Hooks run once the lock is released, so they can use neoghq themselves
+    if action == GetAction::Clone {
+        let mut parent = None;
+        if config.fork_upstream {
+            match add_fork_upstream(&config, &repo_dir, &owner, &repo, &url) {
+                Ok(found) => parent = found,
+                Err(error) => warn!("Couldn't check whether {spec} is a fork: {error:#}"),
+            }
+        }
+        Metadata::update(&root, |metadata| {
+            metadata.record_spec(&spec);
+            if let Some(parent) = &parent {
+                metadata.record_fork(&spec, parent);
+            }
+            Ok(())
+        })?;
+        events::emit(&config, &Event::repo(EventKind::RepoCloned, &repo_dir));
+    }
+    if creates_worktree {
+        events::emit(
+            &config,
+            &Event::worktree(
+                EventKind::WorktreeCreated,
+                &repo_dir,
+                &worktree_path,
+                Some(&branch),
+            ),
+        );
+    }
+
+    info!("Repository is ready");
+    println!("{}", worktree_path.display());
+    Ok(worktree_path)
+}
+
+/// Asks the forge whether the repository at `repo_dir` is a fork and, if
+/// so, adds the repository it was forked from as the `upstream` remote with
+/// the protocol of `origin_url` and fetches it. Returns the parent's
+/// `host/owner/repo`.
+fn add_fork_upstream(
+    config: &Config,
+    repo_dir: &Path,
+    owner: &str,
+    repo: &str,
+    origin_url: &str,
+) -> Result<Option<String>> {
+    use git2::Repository;
+
+    let host = giturl::parse(origin_url)?.host;
+    let Some(forge) = Forge::for_host(config, &host) else {
+        return Ok(None);
+    };
+    let Some(parent) = forge.repository(owner, repo)?.parent else {
+        return Ok(None);
+    };
+    let parent_spec = format!("{host}/{}/{}", parent.owner, parent.repo);
+    let url = if origin_url.starts_with("https://") || origin_url.starts_with("http://") {
+        parent.https_url
+    } else {
+        parent.ssh_url
+    };
+
+    let bare_repo_path = paths::bare_repository_path(repo_dir);
+    let _lock = RepoLock::acquire(&bare_repo_path)?;
+    let bare = Repository::open_bare(&bare_repo_path)?;
+    if bare.find_remote("upstream").is_ok() {
+        info!("Forked from {parent_spec}; keeping the existing upstream remote");
+        return Ok(Some(parent_spec));
+    }
+    bare.remote("upstream", &url)?;
+    info!("Forked from {parent_spec}; added it as the upstream remote");
+    if let Err(error) = fetch_remote(
+        &bare_repo_path,
+        "upstream",
+        &config.credentials,
+        config.fetch_prune,
+    ) {
+        warn!("Failed to fetch upstream: {error:#}");
+    }
+    Ok(Some(parent_spec))
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod clone_repository_tests {
+    use super::*;
+
+    #[test]
+    fn test_clone_repository_bare() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let bare_repo_path = temp_dir.path().join("repo.git");
+
+        let result = clone_repository_bare(
+            "https://github.com/octocat/Hello-World.git",
+            &bare_repo_path,
+            &Credentials::default(),
+        );
+
+        assert!(result.is_ok());
+        assert!(bare_repo_path.exists());
+        assert!(bare_repo_path.join("HEAD").exists());
+        assert!(bare_repo_path.join("refs").exists());
+    }
+
+    #[test]
+    fn test_clone_repository_bare_error() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let bare_repo_path = temp_dir.path().join("repo.git");
+
+        let result = clone_repository_bare(
+            "https://github.com/r4ai/404_notfound.git",
+            &bare_repo_path,
+            &Credentials::default(),
+        );
+
+        assert!(result.is_err());
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod create_worktree_tests {
+    use super::*;
+
+    #[test]
+    fn test_create_worktree() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let bare_repo_path = temp_dir.path().join("repo.git");
+        let worktree_path = temp_dir.path().join("main");
+
+        // First create a bare repository
+        clone_repository_bare(
+            "https://github.com/octocat/Hello-World.git",
+            &bare_repo_path,
+            &Credentials::default(),
+        )
+        .unwrap();
+
+        // Now test worktree creation
+        let result = create_worktree(&bare_repo_path, &worktree_path, "main");
+
+        assert!(result.is_ok());
+        assert!(worktree_path.exists());
+        assert!(worktree_path.join("README").exists());
+    }
+
+    #[test]
+    fn test_create_worktree_error() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let bare_repo_path = temp_dir.path().join("repo.git");
+        let worktree_path = temp_dir.path().join("main");
+
+        let result = create_worktree(&bare_repo_path, &worktree_path, "main");
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_create_worktree_with_existing_branch_reference() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let bare_repo_path = temp_dir.path().join("repo.git");
+        let worktree_path = temp_dir.path().join("main");
+
+        // First create a bare repository that will have refs/heads/main
+        clone_repository_bare(
+            "https://github.com/r4ai/mercury.git",
+            &bare_repo_path,
+            &Credentials::default(),
+        )
+        .unwrap();
+
+        // Create worktree for main branch - this should work without reference conflict error
+        let result = create_worktree(&bare_repo_path, &worktree_path, "main");
+
+        assert!(result.is_ok());
+        assert!(worktree_path.exists());
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod execute_tests {
+    use super::*;
+    use crate::config;
+
+    #[test]
+    fn test_execute_public_function() {
+        let temp_dir = tempfile::tempdir().unwrap();
+
+        let url = "https://github.com/octocat/Hello-World.git".to_string();
+        let branch = Some("main".to_string());
+        let env = config::Env {
+            neoghq_root: Some(temp_dir.path().to_path_buf()),
+            ..Default::default()
+        };
+        let config = Config::load(env).unwrap();
+
+        let result = execute(config, url, branch, false);
+
+        assert!(result.is_ok());
+
+        // Verify directory structure was created correctly
+        let repo_path = temp_dir.path().join("github.com/octocat/Hello-World");
+        assert!(repo_path.exists());
+        assert!(repo_path.join(".git").exists()); // bare repo
+        assert!(repo_path.join("main").exists()); // worktree
+        assert!(repo_path.join("main/README").exists()); // worktree content
+    }
+
+    #[test]
+    fn test_execute_when_repository_already_exists() {
+        let temp_dir = tempfile::tempdir().unwrap();
+
+        let url = "https://github.com/octocat/Hello-World.git".to_string();
+        let branch = Some("main".to_string());
+        let env = config::Env {
+            neoghq_root: Some(temp_dir.path().to_path_buf()),
+            ..Default::default()
+        };
+        let config = Config::load(env).unwrap();
+
+        // First execution - creates the repository
+        let result1 = execute_get_command(url.clone(), branch.clone(), config.clone());
+        assert!(result1.is_ok());
+
+        // Second execution - repository already exists, should skip cloning but create worktree if needed
+        let result2 = execute_get_command(url, branch, config);
+        assert!(result2.is_ok());
+    }
+
+    #[test]
+    fn test_execute_with_default_branch() {
+        let temp_dir = tempfile::tempdir().unwrap();
+
+        let url = "https://github.com/octocat/Hello-World.git".to_string();
+        let branch = None;
+        let env = config::Env {
+            neoghq_root: Some(temp_dir.path().to_path_buf()),
+            ..Default::default()
+        };
+        let config = Config::load(env).unwrap();
+
+        let result = execute_get_command(url, branch, config);
+
+        assert!(result.is_ok());
+        assert!(
+            temp_dir
+                .path()
+                .join("github.com/octocat/Hello-World/main")
+                .exists()
+        );
+    }
+
+    #[test]
+    fn test_execute_get_command_invalid_url() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let url = "invalid-url".to_string();
+        let branch = Some("main".to_string());
+        let env = config::Env {
+            neoghq_root: Some(temp_dir.path().to_path_buf()),
+            ..Default::default()
+        };
+        let config = Config::load(env).unwrap();
+        let result = execute_get_command(url, branch, config);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_execute_get_command_unsupported_ghq_vcs() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let url = "https://svn.example.com/user/repo".to_string();
+        let config = Config {
+            root: temp_dir.path().to_path_buf(),
+            ghq: crate::gitconfig::GhqConfig {
+                vcs: [(
+                    "https://svn.example.com/".to_string(),
+                    "git-svn".to_string(),
+                )]
+                .into(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let result = execute_get_command(url, None, config);
+        assert!(result.is_err());
+        assert!(!temp_dir.path().join("svn.example.com").exists());
+    }
+
+    #[test]
+    fn test_execute_get_command_clone_error() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let url = "https://github.com/r4ai/404_notfound.git".to_string();
+        let branch = Some("main".to_string());
+        let env = config::Env {
+            neoghq_root: Some(temp_dir.path().to_path_buf()),
+            ..Default::default()
+        };
+        let config = Config::load(env).unwrap();
+        let result = execute_get_command(url, branch, config);
+        assert!(result.is_err());
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod get_repository_tests {
+    use super::*;
+    use crate::test_utils::{commit_all, create_source_repository};
+
+    #[test]
+    fn test_detect_action_clone_when_missing() {
+        let temp_dir = tempfile::tempdir().unwrap();
+
+        let action = detect_action(&temp_dir.path().join(".git")).unwrap();
+        assert_eq!(action, GetAction::Clone);
+    }
+
+    #[test]
+    fn test_detect_action_adopt_without_origin() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let bare_repo_path = temp_dir.path().join(".git");
+        git2::Repository::init_bare(&bare_repo_path).unwrap();
+
+        let action = detect_action(&bare_repo_path).unwrap();
+        assert_eq!(action, GetAction::Adopt);
+    }
+
+    #[test]
+    fn test_detect_action_error_when_not_a_repository() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let bare_repo_path = temp_dir.path().join(".git");
+        std::fs::create_dir_all(&bare_repo_path).unwrap();
+
+        assert!(detect_action(&bare_repo_path).is_err());
+    }
+
+    #[test]
+    fn test_force_push_with_lease_checks_remote_ref() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let source_path = temp_dir.path().join("source");
+        let source = create_source_repository(&source_path);
+        let first = source.refname_to_id("refs/heads/main").unwrap();
+        std::fs::write(source_path.join("NOTES"), "notes\n").unwrap();
+        let second = commit_all(&source, "Add notes");
+        let local_path = temp_dir.path().join("local.git");
+        let local = git2::build::RepoBuilder::new()
+            .bare(true)
+            .clone(source_path.to_str().unwrap(), &local_path)
+            .unwrap();
+        let origin_path = temp_dir.path().join("origin.git");
+        let origin = git2::Repository::init_bare(&origin_path).unwrap();
+        local
+            .remote("mirror", origin_path.to_str().unwrap())
+            .unwrap();
+        let credentials = Credentials::default();
+        let reference = "refs/heads/main";
+
+        force_push_with_lease(&local_path, "mirror", reference, None, &credentials).unwrap();
+        assert_eq!(origin.refname_to_id(reference).unwrap(), second);
+
+        local.reference(reference, first, true, "rewind").unwrap();
+        // The remote is at `second`, so neither a missing nor an older lease holds
+        for stale in [None, Some(first)] {
+            let error =
+                force_push_with_lease(&local_path, "mirror", reference, stale, &credentials)
+                    .unwrap_err();
+            assert!(
+                error
+                    .to_string()
+                    .contains("changed since it was last fetched")
+            );
+            assert_eq!(origin.refname_to_id(reference).unwrap(), second);
+        }
+
+        force_push_with_lease(&local_path, "mirror", reference, Some(second), &credentials)
+            .unwrap();
+        assert_eq!(origin.refname_to_id(reference).unwrap(), first);
+    }
+
+    #[test]
+    fn test_get_repository_clones_then_updates() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let source_path = temp_dir.path().join("source");
+        let source = create_source_repository(&source_path);
+        let url = source_path.to_str().unwrap();
+        let repo_dir = temp_dir.path().join("repo");
+        let bare_repo_path = repo_dir.join(".git");
+        let worktree_path = temp_dir.path().join("repo/main");
+
+        let action = get_repository(
+            &Config::default(),
+            &RepoSettings::default(),
+            url,
+            &repo_dir,
+            &worktree_path,
+            "main",
+        )
+        .unwrap();
+        assert_eq!(action, GetAction::Clone);
+        assert!(worktree_path.join("README").exists());
+
+        std::fs::write(source_path.join("NEW"), "new").unwrap();
+        let new_commit = commit_all(&source, "Add NEW");
+
+        let action = get_repository(
+            &Config::default(),
+            &RepoSettings::default(),
+            url,
+            &repo_dir,
+            &worktree_path,
+            "main",
+        )
+        .unwrap();
+        assert_eq!(action, GetAction::Update);
+
+        let bare = git2::Repository::open_bare(&bare_repo_path).unwrap();
+        let fetched = bare
+            .find_reference("refs/remotes/origin/main")
+            .unwrap()
+            .target()
+            .unwrap();
+        assert_eq!(fetched, new_commit);
+    }
+
+    #[test]
+    fn test_host_rooted_repository_is_found_after_get() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let source_path = temp_dir.path().join("source");
+        create_source_repository(&source_path);
+        let mut config = Config {
+            root: temp_dir.path().join("root"),
+            ..Default::default()
+        };
+        config.hosts.insert(
+            "git.company.com".to_string(),
+            crate::config::HostConfig {
+                root: Some(temp_dir.path().join("work")),
+                ..Default::default()
+            },
+        );
+
+        let root = paths::resolve_root(&config, "git.company.com");
+        let repo_dir =
+            resolve_repository_dir(&root, "git.company.com", "team", "app", "unused").unwrap();
+        get_repository(
+            &config,
+            &RepoSettings::default(),
+            source_path.to_str().unwrap(),
+            &repo_dir,
+            &repo_dir.join("main"),
+            "main",
+        )
+        .unwrap();
+        assert!(repo_dir.starts_with(temp_dir.path().join("work")));
+
+        let found = crate::commands::repo::switch::find_repository_path(
+            &config,
+            "git.company.com/team/app",
+        )
+        .unwrap();
+        assert_eq!(found, Some(repo_dir.clone()));
+        let found =
+            crate::commands::repo::switch::find_repository_path(&config, "team/app").unwrap();
+        assert_eq!(found, Some(repo_dir));
+    }
+
+    #[test]
+    fn test_get_repository_applies_profile_identity() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let source_path = temp_dir.path().join("source");
+        create_source_repository(&source_path);
+        let url = source_path.to_str().unwrap();
+        let repo_dir = temp_dir.path().join("repo");
+        let worktree_path = temp_dir.path().join("repo/main");
+        let config = Config {
+            identity: Some(Identity {
+                name: "Jane Doe".to_string(),
+                email: "jane@company.com".to_string(),
+            }),
+            ..Default::default()
+        };
+
+        get_repository(
+            &config,
+            &RepoSettings::default(),
+            url,
+            &repo_dir,
+            &worktree_path,
+            "main",
+        )
+        .unwrap();
+
+        let worktree = git2::Repository::open(&worktree_path).unwrap();
+        let signature = worktree.signature().unwrap();
+        assert_eq!(signature.name(), Some("Jane Doe"));
+        assert_eq!(signature.email(), Some("jane@company.com"));
+    }
+
+    #[test]
+    fn test_get_repository_sets_shared_hooks_path() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let source_path = temp_dir.path().join("source");
+        create_source_repository(&source_path);
+        let url = source_path.to_str().unwrap();
+        let repo_dir = temp_dir.path().join("repo");
+        let hooks_path = temp_dir.path().join("hooks");
+        let config = Config {
+            git_hooks: crate::config::GitHooksConfig {
+                template_dir: None,
+                hooks_path: Some(hooks_path.clone()),
+            },
+            ..Default::default()
+        };
+
+        get_repository(
+            &config,
+            &RepoSettings::default(),
+            url,
+            &repo_dir,
+            &repo_dir.join("main"),
+            "main",
+        )
+        .unwrap();
+
+        let worktree = git2::Repository::open(repo_dir.join("main")).unwrap();
+        assert_eq!(
+            worktree
+                .config()
+                .unwrap()
+                .get_path("core.hooksPath")
+                .unwrap(),
+            hooks_path
+        );
+    }
+
+    #[test]
+    fn test_get_repository_adopts_existing_bare_repository() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let source_path = temp_dir.path().join("source");
+        create_source_repository(&source_path);
+        let url = source_path.to_str().unwrap();
+        let repo_dir = temp_dir.path().join("repo");
+        let bare_repo_path = repo_dir.join(".git");
+        let worktree_path = temp_dir.path().join("repo/main");
+        git2::Repository::init_bare(&bare_repo_path).unwrap();
+
+        let action = get_repository(
+            &Config::default(),
+            &RepoSettings::default(),
+            url,
+            &repo_dir,
+            &worktree_path,
+            "main",
+        )
+        .unwrap();
+        assert_eq!(action, GetAction::Adopt);
+
+        let bare = git2::Repository::open_bare(&bare_repo_path).unwrap();
+        assert_eq!(bare.find_remote("origin").unwrap().url(), Some(url));
+        assert!(worktree_path.join("README").exists());
+    }
+
+    #[test]
+    fn test_get_repository_sets_up_new_worktree() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let source_path = temp_dir.path().join("source");
+        create_source_repository(&source_path);
+        let url = source_path.to_str().unwrap();
+        let repo_dir = temp_dir.path().join("repo");
+        let worktree_path = repo_dir.join("main");
+        let settings = RepoSettings {
+            hooks: crate::repo_settings::RepoHooks {
+                post_create: Some(vec!["touch set-up".to_string()]),
+            },
+            ..Default::default()
+        };
+
+        get_repository(
+            &Config::default(),
+            &settings,
+            url,
+            &repo_dir,
+            &worktree_path,
+            "main",
+        )
+        .unwrap();
+
+        assert!(worktree_path.join("set-up").exists());
+    }
+
+    #[test]
+    fn test_resolve_repository_dir_with_case_variant() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        let existing = root.join("github.com/User/Repo");
+        let repo = crate::test_utils::create_managed_repository(&existing);
+        repo.remote_set_url("origin", "https://github.com/User/Repo.git")
+            .unwrap();
+
+        // Exact names and new repositories get their own directory
+        assert_eq!(
+            resolve_repository_dir(
+                root,
+                "github.com",
+                "User",
+                "Repo",
+                "https://github.com/User/Repo"
+            )
+            .unwrap(),
+            existing
+        );
+        assert_eq!(
+            resolve_repository_dir(
+                root,
+                "github.com",
+                "user",
+                "other",
+                "https://github.com/user/other"
+            )
+            .unwrap(),
+            root.join("github.com/user/other")
+        );
+        // The same repository spelled differently reuses the directory
+        assert_eq!(
+            resolve_repository_dir(
+                root,
+                "github.com",
+                "user",
+                "repo",
+                "https://github.com/user/repo"
+            )
+            .unwrap(),
+            existing
+        );
+        // A different repository gets its own directory on a case-sensitive
+        // filesystem, and is refused on a case-insensitive one
+        let result = resolve_repository_dir(
+            root,
+            "github.com",
+            "user",
+            "repo",
+            "https://github.com/user/repo-fork",
+        );
+        if root.join("github.com/user/repo").exists() {
+            assert!(result.is_err());
+        } else {
+            assert_eq!(result.unwrap(), root.join("github.com/user/repo"));
+        }
+
+        // The recorded spelling is found without listing directories
+        let mut metadata = Metadata::load(root).unwrap();
+        metadata.record_spec("github.com/User/Repo");
+        metadata.save(root).unwrap();
+        assert_eq!(
+            resolve_repository_dir(
+                root,
+                "GitHub.com",
+                "user",
+                "REPO",
+                "https://github.com/user/repo"
+            )
+            .unwrap(),
+            existing
+        );
+    }
+
+    #[test]
+    fn test_add_fork_upstream() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let parent_path = temp_dir.path().join("parent");
+        create_source_repository(&parent_path);
+        let api_dir = temp_dir.path().join("api/repos/user");
+        std::fs::create_dir_all(&api_dir).unwrap();
+        std::fs::write(
+            api_dir.join("fork"),
+            serde_json::json!({
+                "default_branch": "main",
+                "parent": {
+                    "name": "project",
+                    "owner": {"login": "org"},
+                    "clone_url": parent_path,
+                    "ssh_url": "git@github.com:org/project.git",
+                },
+            })
+            .to_string(),
+        )
+        .unwrap();
+        std::fs::write(api_dir.join("own"), r#"{"default_branch": "main"}"#).unwrap();
+        let repo_dir = temp_dir.path().join("github.com/user/fork");
+        crate::test_utils::create_managed_repository(&repo_dir);
+        let config = Config {
+            hosts: [(
+                "github.com".to_string(),
+                crate::config::HostConfig {
+                    api_url: Some(format!("file://{}", temp_dir.path().join("api").display())),
+                    ..Default::default()
+                },
+            )]
+            .into(),
+            ..Default::default()
+        };
+
+        let parent = add_fork_upstream(
+            &config,
+            &repo_dir,
+            "user",
+            "fork",
+            "https://github.com/user/fork.git",
+        )
+        .unwrap();
+        assert_eq!(parent.as_deref(), Some("github.com/org/project"));
+        let bare = git2::Repository::open_bare(repo_dir.join(".git")).unwrap();
+        assert_eq!(
+            bare.find_remote("upstream").unwrap().url(),
+            parent_path.to_str()
+        );
+        assert!(bare.find_reference("refs/remotes/upstream/main").is_ok());
+
+        assert_eq!(
+            add_fork_upstream(
+                &config,
+                &repo_dir,
+                "user",
+                "own",
+                "https://github.com/user/own"
+            )
+            .unwrap(),
+            None
+        );
+        // Hosts without a known forge aren't asked
+        assert_eq!(
+            add_fork_upstream(
+                &config,
+                &repo_dir,
+                "user",
+                "fork",
+                "https://example.com/user/fork"
+            )
+            .unwrap(),
+            None
+        );
+    }
+}
@@ -0,0 +1,109 @@
+use super::switch::{find_repository_path, not_found_message};
+use crate::{
+    config::Config,
+    dry_run, exit,
+    githooks::{self, Installed},
+    lock::RepoLock,
+    scan::{self, RepoEntry},
+};
+use anyhow::{Result, anyhow, bail};
+use tracing::{info, warn};
+
+/// Applies `[git_hooks]` to `repos`, or to every managed repository with
+/// `all`, for repositories cloned before it was configured.
+pub fn sync(config: Config, repos: Vec<String>, all: bool, force: bool) -> Result<()> {
+    if config.git_hooks.is_empty() {
+        bail!("No shared hooks configured; set git_hooks.template_dir or git_hooks.hooks_path");
+    }
+    let entries = if all {
+        scan::scan_roots(&config.all_roots())?
+    } else {
+        repos
+            .iter()
+            .map(|repo| {
+                let Some(repo_path) = find_repository_path(&config, repo)? else {
+                    return Err(exit::not_found(false, not_found_message(&config, repo)?));
+                };
+                RepoEntry::from_path(&repo_path)
+                    .ok_or_else(|| anyhow!("Not a repository directory: {}", repo_path.display()))
+            })
+            .collect::<Result<Vec<_>>>()?
+    };
+
+    let mut failed = 0;
+    for entry in &entries {
+        let bare_repo_path = entry.bare_repository_path();
+        let mut installed = Installed::Nothing;
+        let result = dry_run::apply(
+            &config,
+            format_args!("sync hooks of {}", entry.spec()),
+            || {
+                let _lock = RepoLock::acquire(&bare_repo_path)?;
+                installed = githooks::install(&config.git_hooks, &bare_repo_path, force)?;
+                Ok(())
+            },
+        );
+        match result.map(|()| installed) {
+            Ok(Installed::HooksPath(path)) => {
+                println!("{}: core.hooksPath = {}", entry.spec(), path.display());
+            }
+            Ok(Installed::Hooks(hooks)) => println!("{}: {}", entry.spec(), hooks.join(", ")),
+            Ok(Installed::Nothing) => info!("{}: up to date", entry.spec()),
+            Err(error) => {
+                failed += 1;
+                warn!("{}: {error:#}", entry.spec());
+            }
+        }
+    }
+
+    match failed {
+        0 => Ok(()),
+        count => Err(anyhow!("Failed to sync the hooks of {count} repositories")),
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use crate::{config::GitHooksConfig, test_utils::create_managed_repository};
+
+    #[test]
+    fn test_sync() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path().join("root");
+        let repo_dir = root.join("github.com/user/repo");
+        create_managed_repository(&repo_dir);
+        let template_dir = temp_dir.path().join("hooks");
+        std::fs::create_dir(&template_dir).unwrap();
+        std::fs::write(template_dir.join("pre-commit"), "#!/bin/sh\n").unwrap();
+        let config = Config {
+            root,
+            ..Default::default()
+        };
+        assert!(sync(config.clone(), vec!["user/repo".to_string()], false, false).is_err());
+
+        let config = Config {
+            git_hooks: GitHooksConfig {
+                template_dir: Some(template_dir),
+                hooks_path: None,
+            },
+            ..config
+        };
+        sync(
+            Config {
+                dry_run: true,
+                ..config.clone()
+            },
+            Vec::new(),
+            true,
+            false,
+        )
+        .unwrap();
+        assert!(!repo_dir.join(".git/hooks/pre-commit").exists());
+
+        sync(config.clone(), vec!["user/repo".to_string()], false, false).unwrap();
+        assert!(repo_dir.join(".git/hooks/pre-commit").is_file());
+        assert!(sync(config, vec!["user/missing".to_string()], false, false).is_err());
+    }
+}
@@ -0,0 +1,165 @@
+use super::switch::{find_repository_path, not_found_message};
+use crate::{
+    cli::OutputFormat,
+    commands::worktree::status::status_line,
+    config::Config,
+    exit,
+    metadata::Metadata,
+    output,
+    scan::RepoEntry,
+    status::{self, WorktreeStatus},
+    style::Style,
+};
+use anyhow::{Result, anyhow};
+use git2::Repository;
+use serde::Serialize;
+use std::path::PathBuf;
+use std::time::{Duration, UNIX_EPOCH};
+
+/// A repository as printed by `repo info --json`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+struct RepoInfo {
+    host: String,
+    owner: String,
+    repo: String,
+    path: PathBuf,
+    default_branch: Option<String>,
+    remotes: Vec<RemoteInfo>,
+    worktrees: Vec<WorktreeStatus>,
+    /// Total size in bytes of the repository and its worktrees.
+    disk_usage: u64,
+    /// RFC 3339 time of the last fetch, if the repository was ever fetched.
+    last_fetch: Option<String>,
+    /// RFC 3339 time `repo maintain` last ran, if it ever did.
+    last_maintenance: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+struct RemoteInfo {
+    name: String,
+    url: Option<String>,
+}
+
+pub fn execute(config: Config, repo: String, json: bool) -> Result<()> {
+    let Some(repo_path) = find_repository_path(&config, &repo)? else {
+        return Err(exit::not_found(false, not_found_message(&config, &repo)?));
+    };
+    let entry = RepoEntry::from_path(&repo_path)
+        .ok_or_else(|| anyhow!("Not a repository directory: {}", repo_path.display()))?;
+    let info = repo_info(&entry)?;
+
+    if json || config.output == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&info)?);
+        return Ok(());
+    }
+
+    let style = Style::stdout(&config);
+    println!("{}", entry.spec());
+    println!("  Path:            {}", info.path.display());
+    println!(
+        "  Default branch:  {}",
+        info.default_branch.as_deref().unwrap_or("-")
+    );
+    println!("  Remotes:");
+    for remote in &info.remotes {
+        println!(
+            "    {}\t{}",
+            remote.name,
+            remote.url.as_deref().unwrap_or("-")
+        );
+    }
+    println!("  Worktrees:");
+    for worktree in &info.worktrees {
+        println!("    {}", status_line(worktree, style));
+    }
+    println!(
+        "  Size:            {}",
+        output::format_size(info.disk_usage)
+    );
+    println!(
+        "  Last fetch:      {}",
+        info.last_fetch.as_deref().unwrap_or("never")
+    );
+    println!(
+        "  Last maintained: {}",
+        info.last_maintenance.as_deref().unwrap_or("never")
+    );
+    Ok(())
+}
+
+fn repo_info(entry: &RepoEntry) -> Result<RepoInfo> {
+    let repo_status = status::collect_repo_status(entry)?;
+    let repo = Repository::open_bare(entry.bare_repository_path())?;
+    let remotes = repo
+        .remotes()?
+        .iter()
+        .flatten()
+        .map(|name| RemoteInfo {
+            name: name.to_string(),
+            url: repo
+                .find_remote(name)
+                .ok()
+                .and_then(|remote| remote.url().map(str::to_string)),
+        })
+        .collect();
+    let last_maintenance = match entry.root() {
+        Some(root) => Metadata::load(root)?.last_maintenance(&entry.spec()),
+        None => None,
+    };
+
+    Ok(RepoInfo {
+        host: entry.host.clone(),
+        owner: entry.owner.clone(),
+        repo: entry.repo.clone(),
+        path: entry.path.clone(),
+        default_branch: repo_status.default_branch,
+        remotes,
+        worktrees: status::collect_worktree_statuses(&repo)?,
+        disk_usage: status::disk_usage(&entry.path),
+        last_fetch: repo_status.last_fetch.map(status::format_timestamp),
+        last_maintenance: last_maintenance
+            .map(|secs| status::format_timestamp(UNIX_EPOCH + Duration::from_secs(secs))),
+    })
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_managed_repository;
+
+    #[test]
+    fn test_repo_info() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("github.com/user/repo");
+        create_managed_repository(&path);
+        std::fs::write(path.join("main/NEW"), "new").unwrap();
+        let entry = RepoEntry::from_path(&path).unwrap();
+
+        let info = repo_info(&entry).unwrap();
+        assert_eq!(info.owner, "user");
+        assert_eq!(info.default_branch.as_deref(), Some("main"));
+        assert_eq!(info.remotes.len(), 1);
+        assert_eq!(info.remotes[0].name, "origin");
+        assert_eq!(info.worktrees.len(), 1);
+        assert!(info.worktrees[0].dirty);
+        assert!(info.disk_usage > 0);
+
+        let json = serde_json::to_value(&info).unwrap();
+        assert_eq!(json["worktrees"][0]["branch"], "main");
+    }
+
+    #[test]
+    fn test_execute() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        create_managed_repository(&temp_dir.path().join("github.com/user/repo"));
+        let config = Config {
+            root: temp_dir.path().to_path_buf(),
+            ..Default::default()
+        };
+
+        assert!(execute(config.clone(), "user/repo".to_string(), false).is_ok());
+        assert!(execute(config.clone(), "user/repo".to_string(), true).is_ok());
+        assert!(execute(config, "user/missing".to_string(), false).is_err());
+    }
+}
@@ -1,109 +1,477 @@
-use crate::config::{Config, Env};
+use super::archive;
+use crate::{
+    case,
+    cli::{OutputFormat, SortKey},
+    config::Config,
+    daemon::{self, CachedStatus},
+    format::Format,
+    ignore::glob_match,
+    metadata,
+    output::{self, Lines},
+    scan::{self, RepoEntry},
+    sort,
+    status::{self, RepoStatus},
+    style::{Color, Icon, Style},
+};
 use anyhow::Result;
+use serde::Serialize;
+use std::collections::HashMap;
 use std::path::PathBuf;
-
-pub fn execute() -> Result<()> {
-    let env = Env::load()?;
-    let config = Config::load(env)?;
-
-    list_worktrees(&config.root)?;
-
-    Ok(())
+use tracing::warn;
+
+/// Placeholders accepted by `repo list --format`, the fields of [`RepoRecord`].
+const FORMAT_FIELDS: &[&str] = &[
+    "host",
+    "owner",
+    "repo",
+    "path",
+    "worktrees",
+    "dirty",
+    "last_fetch",
+    "plain_clone",
+    "archived",
+];
+
+const TSV_HEADER: &str = "host\towner\trepo\tpath\tworktrees\tdirty\tlast_fetch";
+
+const LONG_HEADER: &[&str] = &["REPO", "BRANCH", "WORKTREES", "DIRTY", "LAST FETCH", "SIZE"];
+
+/// Options for `repo list`.
+#[derive(Debug, Clone, Default)]
+pub struct ListOptions {
+    pub tsv: bool,
+    pub json: bool,
+    /// Template printed once per repository.
+    pub format: Option<String>,
+    /// Terminate entries with NUL instead of newline.
+    pub print0: bool,
+    /// Print absolute repository paths instead of `host/owner/repo`.
+    pub full_path: bool,
+    /// Print the shortest suffix of `host/owner/repo` that is unambiguous.
+    pub unique: bool,
+    /// Print an aligned table with the status of each repository.
+    pub long: bool,
+    /// Substring of `host/owner/repo`, or a glob if it contains `*` or `?`.
+    pub query: Option<String>,
+    /// Only repositories from this host (a glob).
+    pub host: Option<String>,
+    /// Only repositories of this owner (a glob).
+    pub owner: Option<String>,
+    /// Only repositories with uncommitted changes in some worktree.
+    pub dirty: bool,
+    /// Only repositories with this tag.
+    pub tag: Option<String>,
+    pub sort: Option<SortKey>,
+    /// Also list directories that aren't git repositories.
+    pub include_broken: bool,
+    /// List the repositories in the archive instead of those in the roots.
+    pub archived: bool,
 }
 
-fn list_worktrees(root: &PathBuf) -> Result<()> {
-    use std::fs;
+impl ListOptions {
+    /// Whether `entry` passes the filters, comparing the query ignoring case
+    /// with `ignore_case`. The dirty filter is checked last since it opens
+    /// every worktree.
+    fn matches(&self, entry: &RepoEntry, ignore_case: bool) -> Result<bool> {
+        let fold = |text: &str| {
+            if ignore_case {
+                text.to_lowercase()
+            } else {
+                text.to_string()
+            }
+        };
+        let name = fold(&entry.spec());
+        let query = match self.query.as_deref().map(fold) {
+            Some(query) if query.contains(['*', '?']) => glob_match(&query, &name),
+            Some(query) => name.contains(&query),
+            None => true,
+        };
+        let host = self
+            .host
+            .as_deref()
+            .is_none_or(|host| glob_match(host, &entry.host));
+        let owner = self
+            .owner
+            .as_deref()
+            .is_none_or(|owner| glob_match(owner, &entry.owner));
+        if !(query && host && owner) {
+            return Ok(false);
+        }
 
-    if !root.exists() {
-        return Ok(());
+        Ok(!self.dirty || !is_archived(entry) && status::collect_repo_status(entry)?.dirty)
     }
+}
 
-    for entry in fs::read_dir(root)? {
-        let entry = entry?;
-        let path = entry.path();
+/// A repository as printed by `repo list --json` and `--tsv`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RepoRecord {
+    pub host: String,
+    pub owner: String,
+    pub repo: String,
+    pub path: PathBuf,
+    /// Number of worktrees.
+    pub worktrees: usize,
+    /// Whether any worktree has uncommitted changes.
+    pub dirty: bool,
+    /// RFC 3339 time of the last fetch, if the repository was ever fetched.
+    pub last_fetch: Option<String>,
+    /// Whether it's a plain `git clone` rather than a bare repository with
+    /// worktrees; `repo adopt --in-place` converts it.
+    pub plain_clone: bool,
+    /// Whether it's archived, with `path` being its archive; `repo
+    /// unarchive` restores it.
+    pub archived: bool,
+}
 
-        if path.is_dir() {
-            list_host_worktrees(&path)?;
+/// Returns the repositories `repo list` prints for `options`, filtered and
+/// in order. Output options such as `json` and `format` are ignored.
+pub fn list(config: &Config, options: &ListOptions) -> Result<Vec<RepoEntry>> {
+    let tagged = options
+        .tag
+        .as_deref()
+        .map(|tag| metadata::tagged(config, tag))
+        .transpose()?;
+    let mut scanned = if options.archived {
+        archive::archived_entries(config)?
+    } else {
+        scan::scan_roots(&config.all_roots())?
+    };
+    scanned.retain(|entry| {
+        if options.archived {
+            return true;
+        }
+        let valid = entry.is_repository();
+        if !valid && options.include_broken {
+            warn!(
+                "{} is not a git repository ({})",
+                entry.spec(),
+                entry.path.display()
+            );
         }
+        valid || options.include_broken
+    });
+    for (first, second) in case::collisions(&scanned) {
+        warn!(
+            "{} and {} differ only in case and would share a directory on case-insensitive filesystems",
+            first.spec(),
+            second.spec()
+        );
     }
-
-    Ok(())
+    let filter = |ignore_case: bool| -> Result<Vec<RepoEntry>> {
+        let matched = status::parallel_map(&scanned, None, |entry| {
+            let has_tag = tagged
+                .as_ref()
+                .is_none_or(|tagged| tagged.contains(&entry.path));
+            if has_tag {
+                options.matches(entry, ignore_case)
+            } else {
+                Ok(false)
+            }
+        });
+        let mut entries = Vec::new();
+        for (entry, matched) in scanned.iter().zip(matched) {
+            if matched? {
+                entries.push(entry.clone());
+            }
+        }
+        Ok(entries)
+    };
+    let mut entries = filter(false)?;
+    if entries.is_empty()
+        && let Some(query) = &options.query
+    {
+        entries = filter(true)?;
+        if !entries.is_empty() {
+            warn!("Nothing matches {query} exactly; matched it ignoring case");
+        }
+    }
+    if let Some(key) = options.sort {
+        sort::sort_entries(
+            &mut entries,
+            key,
+            config,
+            |entry| &entry.path,
+            RepoEntry::spec,
+        );
+    }
+    sort::pinned_first(&mut entries, &metadata::pinned(config)?, |entry| {
+        &entry.path
+    });
+    Ok(entries)
 }
 
-fn list_host_worktrees(host_path: &PathBuf) -> Result<()> {
-    use std::fs;
+/// Returns the [`RepoRecord`] of each entry, from the daemon's cache or by
+/// opening their worktrees in parallel. Archived entries have no worktrees
+/// to open.
+pub fn records(config: &Config, entries: &[RepoEntry]) -> Result<Vec<RepoRecord>> {
+    let cached = daemon::cached_statuses(config, entries);
+    status::parallel_map(entries, None, |entry| {
+        if is_archived(entry) {
+            return Ok(archived_record(entry));
+        }
+        Ok(repo_record(entry, repo_status(entry, &cached)?))
+    })
+    .into_iter()
+    .collect()
+}
 
-    for entry in fs::read_dir(host_path)? {
-        let entry = entry?;
-        let path = entry.path();
+/// Returns the status of `entry` the daemon cached, or collects it.
+fn repo_status(entry: &RepoEntry, cached: &HashMap<PathBuf, CachedStatus>) -> Result<RepoStatus> {
+    match cached.get(&entry.path) {
+        Some(cached) => Ok(cached.status.clone()),
+        None => status::collect_repo_status(entry),
+    }
+}
 
-        if path.is_dir() {
-            list_user_worktrees(&path)?;
+pub fn execute(config: Config, options: ListOptions) -> Result<()> {
+    let format = options
+        .format
+        .as_deref()
+        .map(|template| Format::parse(template, FORMAT_FIELDS))
+        .transpose()?;
+    let entries = list(&config, &options)?;
+    let mut lines = Lines::stdout(options.print0);
+
+    // Records open every worktree, so they're collected in parallel and
+    // printed once all are ready, in order
+
+    if let Some(format) = format {
+        for record in records(&config, &entries)? {
+            lines.write(format.render(&record)?)?;
         }
+        return Ok(());
     }
 
-    Ok(())
-}
-
-fn list_user_worktrees(user_path: &PathBuf) -> Result<()> {
-    use std::fs;
+    if options.json || config.output == OutputFormat::Json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&records(&config, &entries)?)?
+        );
+        return Ok(());
+    }
 
-    for entry in fs::read_dir(user_path)? {
-        let entry = entry?;
-        let path = entry.path();
+    if options.long {
+        let style = Style::stdout(&config);
+        let cached = daemon::cached_statuses(&config, &entries);
+        let rows = status::parallel_map(&entries, None, |entry| {
+            let size = cached
+                .get(&entry.path)
+                .map_or_else(|| status::disk_usage(&entry.path), |cached| cached.size);
+            Ok(long_row(entry, repo_status(entry, &cached)?, size, style))
+        })
+        .into_iter()
+        .collect::<Result<Vec<_>>>()?;
+        for line in output::table(LONG_HEADER, &rows) {
+            println!("{line}");
+        }
+        return Ok(());
+    }
 
-        if path.is_dir() {
-            list_repo_worktrees(&path)?;
+    if options.tsv {
+        lines.write(TSV_HEADER)?;
+        for record in records(&config, &entries)? {
+            lines.write(tsv_row(&record))?;
         }
+        return Ok(());
+    }
+
+    let names = if options.full_path {
+        entries
+            .iter()
+            .map(|entry| entry.path.display().to_string())
+            .collect()
+    } else if options.unique {
+        unique_names(&entries)
+    } else {
+        entries.iter().map(RepoEntry::spec).collect()
+    };
+    for entry in entries.iter().filter(|entry| entry.is_plain_clone()) {
+        warn!(
+            "{} is a plain clone; `neoghq repo adopt --in-place {}` converts it",
+            entry.spec(),
+            entry.spec()
+        );
+    }
+    for name in names {
+        lines.write(name)?;
     }
 
     Ok(())
 }
 
-fn list_repo_worktrees(repo_path: &PathBuf) -> Result<()> {
-    use std::fs;
+/// Returns for each entry the shortest of `repo`, `owner/repo` and
+/// `host/owner/repo` that no other entry shares, as `ghq list --unique` does.
+fn unique_names(entries: &[RepoEntry]) -> Vec<String> {
+    let suffixes = |entry: &RepoEntry| {
+        [
+            entry.repo.clone(),
+            format!("{}/{}", entry.owner, entry.repo),
+            entry.spec(),
+        ]
+    };
+    let all = entries.iter().map(suffixes).collect::<Vec<_>>();
+
+    all.iter()
+        .map(|candidates| {
+            candidates
+                .iter()
+                .enumerate()
+                .find(|(level, candidate)| {
+                    all.iter()
+                        .filter(|other| &other[*level] == *candidate)
+                        .count()
+                        == 1
+                })
+                .map(|(_, candidate)| candidate)
+                .unwrap_or(&candidates[2])
+                .clone()
+        })
+        .collect()
+}
 
-    for entry in fs::read_dir(repo_path)? {
-        let entry = entry?;
-        let path = entry.path();
+fn repo_record(entry: &RepoEntry, status: RepoStatus) -> RepoRecord {
+    RepoRecord {
+        host: entry.host.clone(),
+        owner: entry.owner.clone(),
+        repo: entry.repo.clone(),
+        path: entry.path.clone(),
+        worktrees: status.worktrees,
+        dirty: status.dirty,
+        last_fetch: status.last_fetch.map(status::format_timestamp),
+        plain_clone: entry.is_plain_clone(),
+        archived: false,
+    }
+}
 
-        if path.is_dir() && path.file_name().unwrap() != ".git" {
-            println!("{}", path.display());
-        }
+/// Whether `entry` is one of [`archive::archived_entries`], whose path is
+/// an archive file rather than a repository directory.
+fn is_archived(entry: &RepoEntry) -> bool {
+    entry.path.is_file()
+}
+
+fn archived_record(entry: &RepoEntry) -> RepoRecord {
+    RepoRecord {
+        host: entry.host.clone(),
+        owner: entry.owner.clone(),
+        repo: entry.repo.clone(),
+        path: entry.path.clone(),
+        worktrees: 0,
+        dirty: false,
+        last_fetch: None,
+        plain_clone: false,
+        archived: true,
     }
+}
 
-    Ok(())
+/// A row of `repo list --long` for a repository of `size` bytes.
+fn long_row(entry: &RepoEntry, status: RepoStatus, size: u64, style: Style) -> Vec<String> {
+    let dirty_color = if status.dirty {
+        Color::Red
+    } else {
+        Color::Green
+    };
+    let name = if entry.is_plain_clone() {
+        format!("{} (plain clone)", entry.spec())
+    } else {
+        entry.spec()
+    };
+    vec![
+        style.icon(Icon::Repo, name),
+        status.default_branch.map_or_else(
+            || "-".to_string(),
+            |branch| style.icon(Icon::Branch, branch),
+        ),
+        status.worktrees.to_string(),
+        style.paint(dirty_color, status.dirty_worktrees),
+        status
+            .last_fetch
+            .map_or_else(|| "never".to_string(), status::format_timestamp),
+        output::format_size(size),
+    ]
+}
+
+fn tsv_row(record: &RepoRecord) -> String {
+    let fields = [
+        record.host.clone(),
+        record.owner.clone(),
+        record.repo.clone(),
+        record.path.display().to_string(),
+        record.worktrees.to_string(),
+        record.dirty.to_string(),
+        record.last_fetch.clone().unwrap_or_default(),
+    ];
+
+    fields
+        .iter()
+        .map(|field| escape_tsv_field(field))
+        .collect::<Vec<_>>()
+        .join("\t")
+}
+
+/// Escapes characters that would break the row/column structure.
+fn escape_tsv_field(field: &str) -> String {
+    field
+        .replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
 }
 
 #[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
 mod tests {
     use super::*;
+    use crate::test_utils::{create_managed_repository, create_source_repository};
     use std::fs;
+    use std::path::{Path, PathBuf};
     use tempfile::TempDir;
 
-    mod execute_tests {
-        use super::*;
-
-        #[test]
-        fn test_list_command_executes_successfully() {
-            let result = execute();
-            assert!(result.is_ok());
-        }
+    #[test]
+    fn test_list_command_executes_successfully() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = Config {
+            root: temp_dir.path().to_path_buf(),
+            ..Default::default()
+        };
 
-        #[test]
-        fn test_list_worktrees_with_env_load_error() {
-            unsafe {
-                std::env::set_var("HOME", "/nonexistent");
-                std::env::remove_var("NEOGHQ_ROOT");
-            }
+        let result = execute(config, ListOptions::default());
+        assert!(result.is_ok());
+    }
 
-            let result = execute();
-            assert!(result.is_ok());
+    #[test]
+    fn test_list_command_with_multiple_roots() {
+        let personal = TempDir::new().unwrap();
+        let work = TempDir::new().unwrap();
+        create_managed_repository(&work.path().join("gitlab.com/team/service"));
+
+        let config = Config {
+            root: personal.path().to_path_buf(),
+            roots: vec![crate::config::Root {
+                name: "work".to_string(),
+                path: work.path().to_path_buf(),
+            }],
+            ..Default::default()
+        };
+
+        let result = execute(config, ListOptions::default());
+        assert!(result.is_ok());
+    }
 
-            unsafe {
-                std::env::remove_var("HOME");
-            }
-        }
+    /// The `host/owner/repo` of every directory `repo list` finds under
+    /// `root`, repositories or not.
+    fn listed(root: &Path) -> Result<Vec<String>> {
+        let config = Config {
+            root: root.to_path_buf(),
+            ..Default::default()
+        };
+        let options = ListOptions {
+            include_broken: true,
+            ..Default::default()
+        };
+        Ok(list(&config, &options)?
+            .iter()
+            .map(RepoEntry::spec)
+            .collect())
     }
 
     mod list_worktrees_tests {
@@ -112,16 +480,16 @@ mod tests {
         #[test]
         fn test_list_worktrees_with_empty_root() {
             let temp_dir = TempDir::new().unwrap();
-            let result = list_worktrees(&temp_dir.path().to_path_buf());
-            assert!(result.is_ok());
+            let result = listed(temp_dir.path());
+            assert!(result.unwrap().is_empty());
         }
 
         #[test]
         fn test_list_worktrees_with_nonexistent_root() {
             let temp_dir = TempDir::new().unwrap();
             let nonexistent_path = temp_dir.path().join("nonexistent");
-            let result = list_worktrees(&nonexistent_path);
-            assert!(result.is_ok());
+            let result = listed(&nonexistent_path);
+            assert!(result.unwrap().is_empty());
         }
 
         #[test]
@@ -136,8 +504,14 @@ mod tests {
             fs::create_dir_all(repo_path.join("feature")).unwrap();
             fs::create_dir_all(repo_path.join(".git")).unwrap();
 
-            let result = list_worktrees(&root.to_path_buf());
-            assert!(result.is_ok());
+            // An empty .git isn't a repository, so it's only listed as broken
+            let result = listed(root);
+            assert_eq!(result.unwrap(), ["github.com/user/repo"]);
+            let config = Config {
+                root: root.to_path_buf(),
+                ..Default::default()
+            };
+            assert!(list(&config, &ListOptions::default()).unwrap().is_empty());
         }
 
         #[test]
@@ -148,8 +522,8 @@ mod tests {
             // Create file in root (should be ignored)
             fs::write(root.join("somefile.txt"), "content").unwrap();
 
-            let result = list_worktrees(&root.to_path_buf());
-            assert!(result.is_ok());
+            let result = listed(root);
+            assert!(result.unwrap().is_empty());
         }
     }
 
@@ -161,12 +535,12 @@ mod tests {
             let temp_dir = TempDir::new().unwrap();
             let root = temp_dir.path();
 
-            // Create a file instead of directory to trigger read error
+            // A file where a host directory belongs is skipped, not an error
             let file_path = root.join("notadirectory");
             fs::write(&file_path, "content").unwrap();
 
-            let result = list_host_worktrees(&file_path);
-            assert!(result.is_err());
+            let result = listed(root);
+            assert!(result.unwrap().is_empty());
         }
 
         #[test]
@@ -175,10 +549,11 @@ mod tests {
             let root = temp_dir.path();
 
             // Create file in host directory (should be ignored)
-            fs::write(root.join("somefile.txt"), "content").unwrap();
+            fs::create_dir_all(root.join("github.com")).unwrap();
+            fs::write(root.join("github.com/somefile.txt"), "content").unwrap();
 
-            let result = list_host_worktrees(&root.to_path_buf());
-            assert!(result.is_ok());
+            let result = listed(root);
+            assert!(result.unwrap().is_empty());
         }
     }
 
@@ -190,12 +565,13 @@ mod tests {
             let temp_dir = TempDir::new().unwrap();
             let root = temp_dir.path();
 
-            // Create a file instead of directory to trigger read error
-            let file_path = root.join("notadirectory");
+            // A file where a user directory belongs is skipped, not an error
+            fs::create_dir_all(root.join("github.com")).unwrap();
+            let file_path = root.join("github.com/notadirectory");
             fs::write(&file_path, "content").unwrap();
 
-            let result = list_user_worktrees(&file_path);
-            assert!(result.is_err());
+            let result = listed(root);
+            assert!(result.unwrap().is_empty());
         }
 
         #[test]
@@ -204,10 +580,11 @@ mod tests {
             let root = temp_dir.path();
 
             // Create file in user directory (should be ignored)
-            fs::write(root.join("somefile.txt"), "content").unwrap();
+            fs::create_dir_all(root.join("github.com/user")).unwrap();
+            fs::write(root.join("github.com/user/somefile.txt"), "content").unwrap();
 
-            let result = list_user_worktrees(&root.to_path_buf());
-            assert!(result.is_ok());
+            let result = listed(root);
+            assert!(result.unwrap().is_empty());
         }
     }
 
@@ -219,12 +596,13 @@ mod tests {
             let temp_dir = TempDir::new().unwrap();
             let root = temp_dir.path();
 
-            // Create a file instead of directory to trigger read error
-            let file_path = root.join("notadirectory");
+            // A file where a repository directory belongs is skipped, not an error
+            fs::create_dir_all(root.join("github.com/user")).unwrap();
+            let file_path = root.join("github.com/user/notadirectory");
             fs::write(&file_path, "content").unwrap();
 
-            let result = list_repo_worktrees(&file_path);
-            assert!(result.is_err());
+            let result = listed(root);
+            assert!(result.unwrap().is_empty());
         }
 
         #[test]
@@ -233,10 +611,381 @@ mod tests {
             let root = temp_dir.path();
 
             // Create file in repo directory (should be ignored)
-            fs::write(root.join("somefile.txt"), "content").unwrap();
-
-            let result = list_repo_worktrees(&root.to_path_buf());
-            assert!(result.is_ok());
+            let repo_path = root.join("github.com/user/repo");
+            create_managed_repository(&repo_path);
+            fs::write(repo_path.join("somefile.txt"), "content").unwrap();
+
+            let result = listed(root);
+            assert_eq!(result.unwrap(), ["github.com/user/repo"]);
+            let entry = RepoEntry::from_path(&repo_path).unwrap();
+            assert_eq!(entry.worktrees().unwrap(), [repo_path.join("main")]);
         }
     }
+
+    #[test]
+    fn test_list_command_with_tag() {
+        let temp_dir = TempDir::new().unwrap();
+        create_managed_repository(&temp_dir.path().join("github.com/user/repo"));
+        let mut metadata = crate::metadata::Metadata::default();
+        metadata.add_tag("github.com/user/repo", "work").unwrap();
+        metadata.save(temp_dir.path()).unwrap();
+        let config = Config {
+            root: temp_dir.path().to_path_buf(),
+            ..Default::default()
+        };
+
+        let options = ListOptions {
+            tag: Some("work".to_string()),
+            ..Default::default()
+        };
+        assert!(execute(config, options).is_ok());
+    }
+
+    #[test]
+    fn test_list() {
+        let temp_dir = TempDir::new().unwrap();
+        create_managed_repository(&temp_dir.path().join("github.com/user/repo"));
+        create_managed_repository(&temp_dir.path().join("gitlab.com/team/service"));
+        // Neither a stray directory nor a half-removed repository is listed
+        fs::create_dir_all(temp_dir.path().join("github.com/user/junk/notes")).unwrap();
+        fs::create_dir_all(temp_dir.path().join("github.com/user/removed/.git/objects")).unwrap();
+        let config = Config {
+            root: temp_dir.path().to_path_buf(),
+            ..Default::default()
+        };
+
+        let specs = |options: &ListOptions| {
+            list(&config, options)
+                .unwrap()
+                .iter()
+                .map(RepoEntry::spec)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            specs(&ListOptions::default()),
+            ["github.com/user/repo", "gitlab.com/team/service"]
+        );
+        let options = ListOptions {
+            host: Some("gitlab.*".to_string()),
+            // Ignored by list
+            json: true,
+            ..Default::default()
+        };
+        assert_eq!(specs(&options), ["gitlab.com/team/service"]);
+        // A query nothing matches exactly is retried ignoring case
+        let options = ListOptions {
+            query: Some("User/Repo".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(specs(&options), ["github.com/user/repo"]);
+
+        let entries = list(&config, &ListOptions::default()).unwrap();
+        let records = records(&config, &entries).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].repo, "repo");
+
+        let options = ListOptions {
+            include_broken: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            specs(&options),
+            [
+                "github.com/user/junk",
+                "github.com/user/removed",
+                "github.com/user/repo",
+                "gitlab.com/team/service"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_list_archived() {
+        let temp_dir = TempDir::new().unwrap();
+        create_managed_repository(&temp_dir.path().join("github.com/user/repo"));
+        create_managed_repository(&temp_dir.path().join("github.com/user/old"));
+        create_managed_repository(&temp_dir.path().join("gitlab.com/team/legacy"));
+        let config = Config {
+            root: temp_dir.path().to_path_buf(),
+            ..Default::default()
+        };
+        archive::archive(config.clone(), "user/old".to_string(), false).unwrap();
+        archive::archive(config.clone(), "team/legacy".to_string(), true).unwrap();
+
+        let specs = |options: &ListOptions| {
+            list(&config, options)
+                .unwrap()
+                .iter()
+                .map(RepoEntry::spec)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(specs(&ListOptions::default()), ["github.com/user/repo"]);
+        let archived = ListOptions {
+            archived: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            specs(&archived),
+            ["github.com/user/old", "gitlab.com/team/legacy"]
+        );
+        // The other filters apply to archived repositories too
+        let options = ListOptions {
+            host: Some("gitlab.*".to_string()),
+            ..archived.clone()
+        };
+        assert_eq!(specs(&options), ["gitlab.com/team/legacy"]);
+        let options = ListOptions {
+            dirty: true,
+            ..archived.clone()
+        };
+        assert!(specs(&options).is_empty());
+
+        let entries = list(&config, &archived).unwrap();
+        let records = records(&config, &entries).unwrap();
+        assert!(records.iter().all(|record| record.archived));
+        assert_eq!(
+            records[0].path,
+            temp_dir
+                .path()
+                .join(".neoghq/archive/github.com/user/old.tar.zst")
+        );
+        assert_eq!(records[0].worktrees, 0);
+        assert!(execute(config, archived).is_ok());
+    }
+
+    #[test]
+    fn test_list_command_with_nonexistent_root() {
+        let config = Config {
+            root: PathBuf::from("/nonexistent"),
+            ..Default::default()
+        };
+
+        let result = execute(config, ListOptions::default());
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_list_command_tsv() {
+        let temp_dir = TempDir::new().unwrap();
+        create_managed_repository(&temp_dir.path().join("github.com/user/repo"));
+        let config = Config {
+            root: temp_dir.path().to_path_buf(),
+            ..Default::default()
+        };
+
+        let result = execute(
+            config.clone(),
+            ListOptions {
+                tsv: true,
+                ..Default::default()
+            },
+        );
+        assert!(result.is_ok());
+
+        let result = execute(
+            config,
+            ListOptions {
+                json: true,
+                ..Default::default()
+            },
+        );
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_list_command_tsv_with_nonexistent_root() {
+        let config = Config {
+            root: PathBuf::from("/nonexistent"),
+            ..Default::default()
+        };
+
+        let options = ListOptions {
+            tsv: true,
+            ..Default::default()
+        };
+        assert!(execute(config, options).is_ok());
+    }
+
+    #[test]
+    fn test_repo_record() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("github.com/user/repo");
+        create_managed_repository(&path);
+        fs::write(path.join("main/NEW"), "new").unwrap();
+        fs::remove_file(path.join(".git/FETCH_HEAD")).unwrap();
+        let entry = RepoEntry {
+            host: "github.com".to_string(),
+            owner: "user".to_string(),
+            repo: "repo".to_string(),
+            path: path.clone(),
+        };
+
+        let record = repo_record(&entry, status::collect_repo_status(&entry).unwrap());
+        assert_eq!(record.worktrees, 1);
+        assert!(record.dirty);
+        assert_eq!(record.last_fetch, None);
+
+        let json = serde_json::to_value(&record).unwrap();
+        assert_eq!(json["host"], "github.com");
+        assert_eq!(json["dirty"], true);
+        assert!(json["last_fetch"].is_null());
+
+        let format = Format::parse("{owner}/{repo}:{worktrees}", FORMAT_FIELDS).unwrap();
+        assert_eq!(format.render(&record).unwrap(), "user/repo:1");
+
+        let row = tsv_row(&record);
+
+        assert_eq!(
+            row,
+            format!("github.com\tuser\trepo\t{}\t1\ttrue\t", path.display())
+        );
+        assert_eq!(row.split('\t').count(), TSV_HEADER.split('\t').count());
+        assert!(!record.plain_clone);
+    }
+
+    #[test]
+    fn test_plain_clone_is_marked() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("github.com/user/plain");
+        create_source_repository(&path);
+        let entry = RepoEntry::from_path(&path).unwrap();
+
+        let status = status::collect_repo_status(&entry).unwrap();
+        let record = repo_record(&entry, status.clone());
+        assert!(record.plain_clone);
+        assert_eq!(serde_json::to_value(&record).unwrap()["plain_clone"], true);
+        let row = long_row(&entry, status, 0, Style::default());
+        assert_eq!(row[0], "github.com/user/plain (plain clone)");
+    }
+
+    #[test]
+    fn test_long_row() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("github.com/user/repo");
+        create_managed_repository(&path);
+        fs::write(path.join("main/NEW"), "new").unwrap();
+        fs::remove_file(path.join(".git/FETCH_HEAD")).unwrap();
+        let entry = RepoEntry {
+            host: "github.com".to_string(),
+            owner: "user".to_string(),
+            repo: "repo".to_string(),
+            path,
+        };
+
+        let status = status::collect_repo_status(&entry).unwrap();
+        let row = long_row(&entry, status, 2048, Style::default());
+        assert_eq!(row.len(), LONG_HEADER.len());
+        assert_eq!(
+            row[..5],
+            ["github.com/user/repo", "main", "1", "1", "never"]
+        );
+        assert_eq!(row[5], "2.0 KiB");
+    }
+
+    #[test]
+    fn test_list_options_matches() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("github.com/user/neoghq");
+        create_managed_repository(&path);
+        let entry = RepoEntry {
+            host: "github.com".to_string(),
+            owner: "user".to_string(),
+            repo: "neoghq".to_string(),
+            path: path.clone(),
+        };
+        let matches = |options: ListOptions| options.matches(&entry, false).unwrap();
+
+        assert!(matches(ListOptions::default()));
+        assert!(matches(ListOptions {
+            query: Some("user/neo".to_string()),
+            ..Default::default()
+        }));
+        assert!(matches(ListOptions {
+            query: Some("*/neo*".to_string()),
+            host: Some("github.*".to_string()),
+            owner: Some("user".to_string()),
+            ..Default::default()
+        }));
+        assert!(!matches(ListOptions {
+            query: Some("gitlab".to_string()),
+            ..Default::default()
+        }));
+        let query = ListOptions {
+            query: Some("*User/Neo*".to_string()),
+            ..Default::default()
+        };
+        assert!(!query.matches(&entry, false).unwrap());
+        assert!(query.matches(&entry, true).unwrap());
+        assert!(!matches(ListOptions {
+            owner: Some("other".to_string()),
+            ..Default::default()
+        }));
+        assert!(!matches(ListOptions {
+            dirty: true,
+            ..Default::default()
+        }));
+
+        fs::write(path.join("main/NEW"), "new").unwrap();
+        assert!(matches(ListOptions {
+            dirty: true,
+            ..Default::default()
+        }));
+    }
+
+    #[test]
+    fn test_unique_names() {
+        let entry = |host: &str, owner: &str, repo: &str| RepoEntry {
+            host: host.to_string(),
+            owner: owner.to_string(),
+            repo: repo.to_string(),
+            path: PathBuf::from(format!("/root/{host}/{owner}/{repo}")),
+        };
+        let entries = [
+            entry("github.com", "alice", "neoghq"),
+            entry("github.com", "alice", "dotfiles"),
+            entry("github.com", "bob", "dotfiles"),
+            entry("github.com", "carol", "tools"),
+            entry("gitlab.com", "carol", "tools"),
+        ];
+
+        assert_eq!(
+            unique_names(&entries),
+            [
+                "neoghq",
+                "alice/dotfiles",
+                "bob/dotfiles",
+                "github.com/carol/tools",
+                "gitlab.com/carol/tools",
+            ]
+        );
+        assert_eq!(entries[0].spec(), "github.com/alice/neoghq");
+    }
+
+    #[test]
+    fn test_escape_tsv_field() {
+        assert_eq!(escape_tsv_field("plain"), "plain");
+        assert_eq!(escape_tsv_field("a\tb\nc\\d"), "a\\tb\\nc\\\\d");
+    }
+
+    #[test]
+    fn test_tsv_row_escapes_fields() {
+        let record = RepoRecord {
+            host: "github.com".to_string(),
+            owner: "user".to_string(),
+            repo: "repo".to_string(),
+            path: PathBuf::from("/repos/with\ttab"),
+            worktrees: 2,
+            dirty: false,
+            last_fetch: Some("2024-01-02T03:04:05Z".to_string()),
+            plain_clone: false,
+            archived: false,
+        };
+
+        let row = tsv_row(&record);
+        assert_eq!(
+            row,
+            "github.com\tuser\trepo\t/repos/with\\ttab\t2\tfalse\t2024-01-02T03:04:05Z"
+        );
+        assert_eq!(row.split('\t').count(), TSV_HEADER.split('\t').count());
+    }
 }
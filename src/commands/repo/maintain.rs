@@ -0,0 +1,203 @@
+use super::switch::{find_repository_path, not_found_message};
+use crate::{
+    config::Config,
+    dry_run, exit,
+    lock::RepoLock,
+    metadata::Metadata,
+    scan::{self, RepoEntry},
+    status, transport,
+};
+use anyhow::{Result, anyhow};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// The git commands run on a bare repository, in order. Reflogs are pruned
+/// first so `gc` can drop the objects only they kept alive.
+const TASKS: [&[&str]; 4] = [
+    &["reflog", "expire", "--all"],
+    &["gc", "--auto", "--quiet"],
+    &["repack", "-d", "--quiet"],
+    &["commit-graph", "write", "--reachable", "--no-progress"],
+];
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Runs the maintenance tasks on `repos`, or on every managed repository
+/// with `all`, up to `jobs` repositories at once. With `older_than`, skips
+/// repositories maintained within that many days.
+pub fn execute(
+    config: Config,
+    repos: Vec<String>,
+    all: bool,
+    jobs: Option<usize>,
+    older_than: Option<u64>,
+) -> Result<()> {
+    let entries = if all {
+        scan::scan_roots(&config.all_roots())?
+    } else {
+        repos
+            .iter()
+            .map(|repo| {
+                let Some(repo_path) = find_repository_path(&config, repo)? else {
+                    return Err(exit::not_found(false, not_found_message(&config, repo)?));
+                };
+                RepoEntry::from_path(&repo_path)
+                    .ok_or_else(|| anyhow!("Not a repository directory: {}", repo_path.display()))
+            })
+            .collect::<Result<Vec<_>>>()?
+    };
+
+    let now = status::unix_now();
+    let metadata = load_metadata(&entries)?;
+    let entries = entries
+        .into_iter()
+        .filter(
+            |entry| match (older_than, last_maintenance(&metadata, entry)) {
+                (Some(days), Some(time)) => now.saturating_sub(time) >= days * SECONDS_PER_DAY,
+                _ => true,
+            },
+        )
+        .collect::<Vec<_>>();
+
+    if config.dry_run {
+        for entry in &entries {
+            dry_run::apply(&config, format!("maintain {}", entry.spec()), || Ok(()))?;
+        }
+        return Ok(());
+    }
+
+    let results = status::parallel_map(&entries, jobs, |entry| {
+        maintain(&entry.bare_repository_path())
+    });
+
+    let mut failed = 0;
+    let mut maintained = BTreeMap::<&Path, Vec<String>>::new();
+    for (entry, result) in entries.iter().zip(&results) {
+        match (result, entry.root()) {
+            (Ok(()), Some(root)) => maintained.entry(root).or_default().push(entry.spec()),
+            (Ok(()), None) => {}
+            (Err(error), _) => {
+                failed += 1;
+                warn!("{}: {error:#}", entry.spec());
+            }
+        }
+    }
+    // Updated afresh so edits made while the tasks ran aren't lost
+    for (root, specs) in maintained {
+        Metadata::update(root, |metadata| {
+            for spec in specs {
+                metadata.record_maintenance(&spec, now);
+            }
+            Ok(())
+        })?;
+    }
+    info!(
+        "Maintained {} repositories, {failed} failed",
+        results.len() - failed
+    );
+
+    match failed {
+        0 => Ok(()),
+        count => Err(anyhow!("Failed to maintain {count} repositories")),
+    }
+}
+
+/// Runs every task in [`TASKS`] on the bare repository, stopping at the
+/// first failure.
+fn maintain(bare_repo_path: &Path) -> Result<()> {
+    let _lock = RepoLock::acquire(bare_repo_path)?;
+    for task in TASKS {
+        transport::run_git(Some(bare_repo_path), false, task)?;
+    }
+    Ok(())
+}
+
+/// Loads the metadata of every root holding one of `entries`.
+fn load_metadata(entries: &[RepoEntry]) -> Result<BTreeMap<PathBuf, Metadata>> {
+    let mut metadata = BTreeMap::new();
+    for root in entries.iter().filter_map(RepoEntry::root) {
+        if !metadata.contains_key(root) {
+            metadata.insert(root.to_path_buf(), Metadata::load(root)?);
+        }
+    }
+    Ok(metadata)
+}
+
+fn last_maintenance(metadata: &BTreeMap<PathBuf, Metadata>, entry: &RepoEntry) -> Option<u64> {
+    metadata.get(entry.root()?)?.last_maintenance(&entry.spec())
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_managed_repository;
+
+    #[test]
+    fn test_maintain() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("github.com/user/repo");
+        create_managed_repository(&path);
+        let bare_repo_path = RepoEntry::from_path(&path).unwrap().bare_repository_path();
+
+        maintain(&bare_repo_path).unwrap();
+        assert!(bare_repo_path.join("objects/info/commit-graph").exists());
+        assert!(maintain(&temp_dir.path().join("missing")).is_err());
+    }
+
+    #[test]
+    fn test_execute() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        create_managed_repository(&root.join("github.com/user/repo"));
+        let config = Config {
+            root: root.to_path_buf(),
+            ..Default::default()
+        };
+
+        let dry_run = Config {
+            dry_run: true,
+            ..config.clone()
+        };
+        execute(dry_run, Vec::new(), true, None, None).unwrap();
+        assert_eq!(
+            Metadata::load(root)
+                .unwrap()
+                .last_maintenance("github.com/user/repo"),
+            None
+        );
+
+        execute(
+            config.clone(),
+            vec!["user/repo".to_string()],
+            false,
+            Some(1),
+            None,
+        )
+        .unwrap();
+        let mut metadata = Metadata::load(root).unwrap();
+        assert!(metadata.last_maintenance("github.com/user/repo").is_some());
+
+        // Maintained a day ago, so only --older-than 2 skips it
+        let day_ago = status::unix_now() - SECONDS_PER_DAY;
+        metadata.record_maintenance("github.com/user/repo", day_ago);
+        metadata.save(root).unwrap();
+        execute(config.clone(), Vec::new(), true, None, Some(2)).unwrap();
+        assert_eq!(
+            Metadata::load(root)
+                .unwrap()
+                .last_maintenance("github.com/user/repo"),
+            Some(day_ago)
+        );
+        execute(config.clone(), Vec::new(), true, None, Some(1)).unwrap();
+        assert!(
+            Metadata::load(root)
+                .unwrap()
+                .last_maintenance("github.com/user/repo")
+                > Some(day_ago)
+        );
+
+        assert!(execute(config, vec!["user/missing".to_string()], false, None, None).is_err());
+    }
+}
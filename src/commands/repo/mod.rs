@@ -1,4 +1,17 @@
-pub mod clone;
+pub mod adopt;
+pub mod archive;
+pub mod compact;
 pub mod create;
+pub mod fetch;
+pub mod get;
+pub mod hooks;
+pub mod info;
 pub mod list;
+pub mod maintain;
+pub mod pin;
+pub mod remote;
+pub mod remove;
+pub mod rename;
 pub mod switch;
+pub mod sync_fork;
+pub mod tag;
@@ -0,0 +1,41 @@
+use super::tag::resolve;
+use crate::{config::Config, metadata::Metadata};
+use anyhow::Result;
+
+/// Pins `repo`, so `repo list` and the picker show it first.
+pub fn pin(config: Config, repo: String) -> Result<()> {
+    let (root, spec) = resolve(&config, &repo)?;
+    Metadata::update(&root, |metadata| Ok(metadata.pin(&spec)))?;
+    Ok(())
+}
+
+pub fn unpin(config: Config, repo: String) -> Result<()> {
+    let (root, spec) = resolve(&config, &repo)?;
+    Metadata::update(&root, |metadata| Ok(metadata.unpin(&spec)))?;
+    Ok(())
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use crate::metadata;
+    use std::fs;
+
+    #[test]
+    fn test_pin_and_unpin() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(temp_dir.path().join("github.com/user/repo/.git")).unwrap();
+        let config = Config {
+            root: temp_dir.path().to_path_buf(),
+            ..Default::default()
+        };
+        let repo_path = temp_dir.path().join("github.com/user/repo");
+
+        pin(config.clone(), "user/repo".to_string()).unwrap();
+        assert!(metadata::pinned(&config).unwrap().contains(&repo_path));
+        unpin(config.clone(), "user/repo".to_string()).unwrap();
+        assert!(metadata::pinned(&config).unwrap().is_empty());
+        assert!(pin(config, "user/missing".to_string()).is_err());
+    }
+}
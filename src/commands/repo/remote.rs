@@ -0,0 +1,386 @@
+use super::{
+    rename,
+    switch::{find_repository_path, not_found_message},
+};
+use crate::{
+    config::Config,
+    dry_run, exit,
+    format::Format,
+    giturl,
+    lock::RepoLock,
+    paths, redirect,
+    scan::{self, RepoEntry},
+};
+use anyhow::{Result, anyhow, bail};
+use git2::Repository;
+use serde::Serialize;
+use tracing::{info, warn};
+
+/// Placeholders of URLs given to `add` and `set-url`.
+const URL_FIELDS: &[&str] = &["host", "owner", "repo"];
+
+/// What `set-url` changes a remote's URL to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UrlChange {
+    /// A URL, with `{host}`, `{owner}` and `{repo}` filled in per repository.
+    Set(String),
+    /// The current URL with `old` replaced by `new`.
+    Replace { old: String, new: String },
+}
+
+#[derive(Serialize)]
+struct UrlFields<'a> {
+    host: &'a str,
+    owner: &'a str,
+    repo: &'a str,
+}
+
+/// Prints the remotes of `repos`, or of every managed repository with
+/// `all`, as `<name>\t<url>`, prefixed with the repository when there are
+/// several.
+pub fn list(config: Config, repos: Vec<String>, all: bool) -> Result<()> {
+    let entries = find_entries(&config, &repos, all)?;
+    let prefix = entries.len() > 1;
+    for_each(&entries, "list the remotes of", |entry| {
+        let repo = Repository::open_bare(entry.bare_repository_path())?;
+        for name in repo.remotes()?.iter().flatten() {
+            let remote = repo.find_remote(name)?;
+            let url = remote.url().unwrap_or_default();
+            if prefix {
+                println!("{}\t{name}\t{url}", entry.spec());
+            } else {
+                println!("{name}\t{url}");
+            }
+        }
+        Ok(())
+    })
+}
+
+/// Adds the remote `name` with `url`, filled in per repository, to `repos`
+/// or to every managed repository with `all`. With `all`, repositories that
+/// already have the remote are left alone.
+pub fn add(config: Config, name: String, url: String, repos: Vec<String>, all: bool) -> Result<()> {
+    let url = Format::parse(&url, URL_FIELDS)?;
+    let entries = find_entries(&config, &repos, all)?;
+    for_each(&entries, "add a remote to", |entry| {
+        let repo = Repository::open_bare(entry.bare_repository_path())?;
+        if repo.find_remote(&name).is_ok() {
+            if all {
+                info!("{}: {name} exists", entry.spec());
+                return Ok(());
+            }
+            bail!("Remote {name} exists");
+        }
+        let url = render_url(&url, entry)?;
+        dry_run::apply(
+            &config,
+            format_args!("add remote {name} {url} to {}", entry.spec()),
+            || {
+                let _lock = RepoLock::acquire(&entry.bare_repository_path())?;
+                repo.remote(&name, &url)?;
+                info!("{}: added {name} {url}", entry.spec());
+                Ok(())
+            },
+        )
+    })
+}
+
+/// Removes the remote `name` and its remote-tracking branches from `repos`,
+/// or from every managed repository that has it with `all`.
+pub fn remove(config: Config, name: String, repos: Vec<String>, all: bool) -> Result<()> {
+    let entries = find_entries(&config, &repos, all)?;
+    for_each(&entries, "remove a remote from", |entry| {
+        let repo = Repository::open_bare(entry.bare_repository_path())?;
+        if repo.find_remote(&name).is_err() {
+            if all {
+                return Ok(());
+            }
+            bail!("No remote {name}");
+        }
+        dry_run::apply(
+            &config,
+            format_args!("remove remote {name} from {}", entry.spec()),
+            || {
+                let _lock = RepoLock::acquire(&entry.bare_repository_path())?;
+                repo.remote_delete(&name)?;
+                info!("{}: removed {name}", entry.spec());
+                Ok(())
+            },
+        )
+    })
+}
+
+/// Changes the URL of the remote `name` of `repos`, or of every managed
+/// repository that has it with `all`, such as rewriting an old hostname
+/// after moving to another forge.
+pub fn set_url(
+    config: Config,
+    name: String,
+    change: UrlChange,
+    repos: Vec<String>,
+    all: bool,
+) -> Result<()> {
+    let template = match &change {
+        UrlChange::Set(url) => Some(Format::parse(url, URL_FIELDS)?),
+        UrlChange::Replace { .. } => None,
+    };
+    let entries = find_entries(&config, &repos, all)?;
+    for_each(&entries, "change a remote of", |entry| {
+        let repo = Repository::open_bare(entry.bare_repository_path())?;
+        let Ok(remote) = repo.find_remote(&name) else {
+            if all {
+                return Ok(());
+            }
+            bail!("No remote {name}");
+        };
+        let old_url = remote.url().unwrap_or_default().to_string();
+        let url = match (&change, &template) {
+            (UrlChange::Replace { old, new }, _) => old_url.replace(old.as_str(), new),
+            (UrlChange::Set(_), Some(template)) => render_url(template, entry)?,
+            (UrlChange::Set(url), None) => url.clone(),
+        };
+        if url == old_url {
+            return Ok(());
+        }
+        dry_run::apply(
+            &config,
+            format_args!("change {name} of {} from {old_url} to {url}", entry.spec()),
+            || {
+                let _lock = RepoLock::acquire(&entry.bare_repository_path())?;
+                repo.remote_set_url(&name, &url)?;
+                println!("{}: {name} {old_url} -> {url}", entry.spec());
+                Ok(())
+            },
+        )
+    })
+}
+
+/// Asks whether `origin` of `repos`, or of every managed repository with
+/// `all`, moved, and prints where to. With `follow_redirect`, `origin` is
+/// pointed at the new URL, and with `rename` the repository also moves to
+/// the `host/owner/repo` of that URL.
+pub fn update(
+    config: Config,
+    repos: Vec<String>,
+    all: bool,
+    follow_redirect: bool,
+    rename: bool,
+) -> Result<()> {
+    let entries = find_entries(&config, &repos, all)?;
+    for_each(&entries, "update", |entry| {
+        update_one(&config, entry, follow_redirect, rename)
+    })
+}
+
+/// Resolves `repos`, or every managed repository with `all`.
+fn find_entries(config: &Config, repos: &[String], all: bool) -> Result<Vec<RepoEntry>> {
+    if all {
+        return scan::scan_roots(&config.all_roots());
+    }
+    if repos.is_empty() {
+        bail!("Name the repositories or pass --all");
+    }
+    repos
+        .iter()
+        .map(|repo| {
+            let Some(repo_path) = find_repository_path(config, repo)? else {
+                return Err(exit::not_found(false, not_found_message(config, repo)?));
+            };
+            RepoEntry::from_path(&repo_path)
+                .ok_or_else(|| anyhow!("Not a repository directory: {}", repo_path.display()))
+        })
+        .collect()
+}
+
+/// Runs `f` on every entry, warning about failures and failing at the end
+/// if any did, so one broken repository doesn't stop a bulk change.
+fn for_each(entries: &[RepoEntry], verb: &str, f: impl Fn(&RepoEntry) -> Result<()>) -> Result<()> {
+    let mut failed = 0;
+    for entry in entries {
+        if let Err(error) = f(entry) {
+            failed += 1;
+            warn!("{}: {error:#}", entry.spec());
+        }
+    }
+    match failed {
+        0 => Ok(()),
+        count => Err(anyhow!("Failed to {verb} {count} repositories")),
+    }
+}
+
+fn render_url(url: &Format, entry: &RepoEntry) -> Result<String> {
+    url.render(&UrlFields {
+        host: &entry.host,
+        owner: &paths::owner_from_dir(&entry.owner),
+        repo: &entry.repo,
+    })
+}
+
+fn update_one(
+    config: &Config,
+    entry: &RepoEntry,
+    follow_redirect: bool,
+    rename: bool,
+) -> Result<()> {
+    let bare_repo_path = entry.bare_repository_path();
+    let repo = Repository::open_bare(&bare_repo_path)?;
+    let url = repo
+        .find_remote("origin")
+        .map_err(|_| anyhow!("No origin remote"))?
+        .url()
+        .ok_or_else(|| anyhow!("The origin URL isn't UTF-8"))?
+        .to_string();
+    let Some(target) = redirect::detect(&url)? else {
+        info!("{}: origin hasn't moved", entry.spec());
+        return redirect::record(&bare_repo_path, None);
+    };
+    println!("{}: {url} -> {target}", entry.spec());
+    if !follow_redirect {
+        return redirect::record(&bare_repo_path, Some(&target));
+    }
+
+    dry_run::apply(
+        config,
+        format_args!("point origin of {} at {target}", entry.spec()),
+        || {
+            let _lock = RepoLock::acquire(&bare_repo_path)?;
+            repo.remote_set_url("origin", &target)?;
+            redirect::record(&bare_repo_path, None)
+        },
+    )?;
+    if rename {
+        let moved = giturl::parse(&target)?;
+        let new_spec = format!("{}/{}/{}", moved.host, moved.owner, moved.repo);
+        if new_spec != entry.spec() {
+            rename::execute(config.clone(), entry.spec(), new_spec, false)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_managed_repository;
+
+    #[test]
+    fn test_update_without_redirect_clears_record() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo_path = temp_dir.path().join("github.com/user/repo");
+        let bare = create_managed_repository(&repo_path);
+        redirect::record(bare.path(), Some("https://github.com/org/repo.git")).unwrap();
+        let config = Config {
+            root: temp_dir.path().to_path_buf(),
+            ..Default::default()
+        };
+
+        // The local origin can't redirect
+        update(
+            config.clone(),
+            vec!["user/repo".to_string()],
+            false,
+            true,
+            true,
+        )
+        .unwrap();
+        assert_eq!(redirect::recorded(bare.path()), None);
+        assert!(repo_path.exists());
+
+        bare.remote_delete("origin").unwrap();
+        assert!(update(config, Vec::new(), true, false, false).is_err());
+    }
+
+    #[test]
+    fn test_add_set_url_and_remove() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let one = create_managed_repository(&temp_dir.path().join("git.old.com/user/one"));
+        let two = create_managed_repository(&temp_dir.path().join("git.old.com/team/two"));
+        let config = Config {
+            root: temp_dir.path().to_path_buf(),
+            ..Default::default()
+        };
+        let url = |repo: &Repository, name: &str| {
+            repo.find_remote(name)
+                .ok()
+                .and_then(|remote| remote.url().map(str::to_string))
+        };
+
+        add(
+            config.clone(),
+            "mirror".to_string(),
+            "https://{host}/{owner}/{repo}.git".to_string(),
+            Vec::new(),
+            true,
+        )
+        .unwrap();
+        assert_eq!(
+            url(&one, "mirror").as_deref(),
+            Some("https://git.old.com/user/one.git")
+        );
+        assert_eq!(
+            url(&two, "mirror").as_deref(),
+            Some("https://git.old.com/team/two.git")
+        );
+        // Only --all skips repositories that have the remote
+        let add_again = |all| {
+            add(
+                config.clone(),
+                "mirror".to_string(),
+                "https://example.com".to_string(),
+                vec!["user/one".to_string()],
+                all,
+            )
+        };
+        assert!(add_again(false).is_err());
+
+        set_url(
+            config.clone(),
+            "mirror".to_string(),
+            UrlChange::Replace {
+                old: "git.old.com".to_string(),
+                new: "git.new.com".to_string(),
+            },
+            Vec::new(),
+            true,
+        )
+        .unwrap();
+        assert_eq!(
+            url(&two, "mirror").as_deref(),
+            Some("https://git.new.com/team/two.git")
+        );
+        set_url(
+            config.clone(),
+            "mirror".to_string(),
+            UrlChange::Set("git@git.new.com:{owner}/{repo}.git".to_string()),
+            vec!["user/one".to_string()],
+            false,
+        )
+        .unwrap();
+        assert_eq!(
+            url(&one, "mirror").as_deref(),
+            Some("git@git.new.com:user/one.git")
+        );
+
+        remove(
+            config.clone(),
+            "mirror".to_string(),
+            vec!["user/one".to_string()],
+            false,
+        )
+        .unwrap();
+        assert_eq!(url(&one, "mirror"), None);
+        assert!(
+            remove(
+                config.clone(),
+                "mirror".to_string(),
+                vec!["user/one".to_string()],
+                false
+            )
+            .is_err()
+        );
+        remove(config.clone(), "mirror".to_string(), Vec::new(), true).unwrap();
+        assert_eq!(url(&two, "mirror"), None);
+        assert!(list(config, Vec::new(), false).is_err());
+    }
+}
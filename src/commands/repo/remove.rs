@@ -0,0 +1,238 @@
+use super::switch::find_repository_path;
+use crate::{
+    config::Config,
+    dry_run,
+    events::{self, Event, EventKind},
+    exit::{self, ErrorKind},
+    index, paths,
+    status::{self, WorktreeRisk},
+    trash::{self, TrashKind},
+};
+use anyhow::{Result, anyhow};
+use git2::Repository;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+use tracing::info;
+
+/// Removes `repo` with all its worktrees after asking, unless `yes`. The
+/// question and `--dry-run` list each worktree with the uncommitted changes
+/// and unpushed commits it would lose.
+pub fn execute(config: Config, repo: String, yes: bool) -> Result<()> {
+    let repo_path = find_repository_path(&config, &repo)?
+        .ok_or_else(|| anyhow!("Repository not found: {repo}"))?;
+    let worktrees = describe_worktrees(&repo_path);
+
+    if !yes && !config.dry_run {
+        if !config.interactive() {
+            return Err(exit::error(
+                ErrorKind::InputRequired,
+                format!(
+                    "Refusing to remove {} without confirmation; pass --yes",
+                    repo_path.display()
+                ),
+            ));
+        }
+        let stdin = io::stdin();
+        if !confirm(&repo_path, &worktrees, &mut stdin.lock(), &mut io::stderr())? {
+            return Err(anyhow!("Aborted"));
+        }
+    }
+
+    dry_run::apply(
+        &config,
+        format_args!(
+            "remove {}{}",
+            repo_path.display(),
+            worktrees
+                .iter()
+                .map(|worktree| format!("\n  {worktree}"))
+                .collect::<String>()
+        ),
+        || {
+            match trash::put(&config, TrashKind::Repository, &repo_path, None)? {
+                Some(entry) => info!(
+                    "Moved {} to the trash as {}; `neoghq undo` restores it",
+                    repo_path.display(),
+                    entry.id
+                ),
+                None => {
+                    std::fs::remove_dir_all(&repo_path)?;
+                    info!("Removed {}", repo_path.display());
+                }
+            }
+            if let Some(root) = repo_path.ancestors().nth(3) {
+                index::update(root);
+            }
+            events::emit(&config, &Event::repo(EventKind::RepoRemoved, &repo_path));
+            Ok(())
+        },
+    )
+}
+
+/// Describes each worktree of the repository at `repo_path` with what
+/// removing it would lose. Empty when the bare repository can't be read.
+fn describe_worktrees(repo_path: &Path) -> Vec<String> {
+    let risks = Repository::open_bare(paths::bare_repository_path(repo_path))
+        .map_err(anyhow::Error::from)
+        .and_then(|repo| status::collect_worktree_risks(&repo));
+    match risks {
+        Ok(risks) => risks
+            .iter()
+            .map(|risk| describe_worktree(repo_path, risk))
+            .collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// Formats a worktree as `feature/x: uncommitted changes, 2 unpushed
+/// commits`, naming its branch when the directory is named otherwise.
+fn describe_worktree(repo_path: &Path, risk: &WorktreeRisk) -> String {
+    let name = risk
+        .path
+        .strip_prefix(repo_path)
+        .map(paths::to_slash)
+        .unwrap_or_else(|_| risk.path.display().to_string());
+    let name = match risk.branch.as_deref() {
+        Some(branch) if branch == name => name,
+        Some(branch) => format!("{name} (on {branch})"),
+        None => format!("{name} (detached)"),
+    };
+
+    let mut losses = Vec::new();
+    if risk.dirty {
+        losses.push("uncommitted changes".to_string());
+    }
+    match risk.unpushed {
+        0 => {}
+        1 => losses.push("1 unpushed commit".to_string()),
+        count => losses.push(format!("{count} unpushed commits")),
+    }
+    if losses.is_empty() {
+        return format!("{name}: clean");
+    }
+    format!("{name}: {}", losses.join(", "))
+}
+
+/// Asks whether to remove `repo_path` with all its `worktrees`; only an
+/// explicit yes confirms.
+fn confirm(
+    repo_path: &Path,
+    worktrees: &[String],
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+) -> Result<bool> {
+    writeln!(
+        output,
+        "Remove {} and all its worktrees?",
+        repo_path.display()
+    )?;
+    for worktree in worktrees {
+        writeln!(output, "  {worktree}")?;
+    }
+    write!(output, "[y/N]: ")?;
+    output.flush()?;
+
+    let mut answer = String::new();
+    input.read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use crate::test_utils::{commit_all, create_managed_repository};
+
+    #[test]
+    fn test_confirm() {
+        let path = Path::new("/repos/github.com/user/repo");
+        let confirm = |answer: &str| confirm(path, &[], &mut answer.as_bytes(), &mut Vec::new());
+        assert!(confirm("y\n").unwrap());
+        assert!(confirm("yes\n").unwrap());
+        assert!(!confirm("\n").unwrap());
+        assert!(!confirm("").unwrap());
+
+        let mut output = Vec::new();
+        let worktrees = ["main: clean".to_string()];
+        super::confirm(path, &worktrees, &mut "n\n".as_bytes(), &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "Remove /repos/github.com/user/repo and all its worktrees?\n  main: clean\n[y/N]: "
+        );
+    }
+
+    #[test]
+    fn test_describe_worktrees() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo_path = temp_dir.path().join("github.com/user/repo");
+        let bare = create_managed_repository(&repo_path);
+        let head = bare.head().unwrap().peel_to_commit().unwrap();
+        let branch = bare.branch("feature/x", &head, false).unwrap();
+        let mut opts = git2::WorktreeAddOptions::new();
+        opts.reference(Some(branch.get()));
+        std::fs::create_dir(repo_path.join("review")).unwrap();
+        bare.worktree("review", &repo_path.join("review/x"), Some(&opts))
+            .unwrap();
+        std::fs::write(repo_path.join("main/README"), "changed\n").unwrap();
+        let feature = Repository::open(repo_path.join("review/x")).unwrap();
+        commit_all(&feature, "One");
+        commit_all(&feature, "Two");
+        let repo_path = paths::canonicalize(&repo_path).unwrap();
+
+        let worktrees = describe_worktrees(&repo_path);
+        assert_eq!(
+            worktrees,
+            [
+                "main: uncommitted changes".to_string(),
+                "review/x (on feature/x): 2 unpushed commits".to_string(),
+            ]
+        );
+        assert!(describe_worktrees(temp_dir.path()).is_empty());
+    }
+
+    #[test]
+    fn test_execute_removes_repository() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo_path = temp_dir.path().join("github.com/user/repo");
+        std::fs::create_dir_all(repo_path.join(".git")).unwrap();
+        std::fs::create_dir_all(repo_path.join("main")).unwrap();
+        let config = Config {
+            root: temp_dir.path().to_path_buf(),
+            ..Default::default()
+        };
+
+        let dry_run = Config {
+            dry_run: true,
+            ..config.clone()
+        };
+        execute(dry_run, "user/repo".to_string(), false).unwrap();
+        assert!(repo_path.exists());
+
+        // Tests run without a terminal on stdin, so confirming is impossible
+        assert!(execute(config.clone(), "user/repo".to_string(), false).is_err());
+        assert!(repo_path.exists());
+
+        execute(config.clone(), "user/repo".to_string(), true).unwrap();
+        assert!(!repo_path.exists());
+        assert!(execute(config, "user/repo".to_string(), true).is_err());
+    }
+
+    #[test]
+    fn test_execute_moves_repository_to_trash() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo_path = temp_dir.path().join("github.com/user/repo");
+        std::fs::create_dir_all(repo_path.join(".git")).unwrap();
+        let config = Config {
+            root: temp_dir.path().to_path_buf(),
+            trash_retention_days: 30,
+            ..Default::default()
+        };
+
+        execute(config.clone(), "user/repo".to_string(), true).unwrap();
+        assert!(!repo_path.exists());
+        let entries = trash::list(&config).unwrap();
+        assert_eq!(entries.len(), 1);
+        trash::restore(&entries[0]).unwrap();
+        assert!(repo_path.join(".git").exists());
+    }
+}
@@ -0,0 +1,391 @@
+use super::switch::{find_repository_path, not_found_message};
+use crate::{
+    commands::relocate,
+    config::{CloneProtocol, Config},
+    dry_run, exit, giturl,
+    history::History,
+    index,
+    lock::{self, RepoLock},
+    metadata::Metadata,
+    paths, repo_settings,
+    scan::RepoEntry,
+    spec::{self, RepoSpec},
+};
+use anyhow::{Context, Result, anyhow, bail};
+use git2::Repository;
+use std::path::Path;
+use tracing::info;
+
+/// Moves `repo` to `new_spec`, after the repository was renamed or
+/// transferred upstream, and rewrites the worktree links so every checkout
+/// keeps working. A `new_spec` without a host keeps the current one; a new
+/// host moves it to the root that host belongs in. Tags, pins and the
+/// switch history follow the repository; with `update_remote`, so does the
+/// `origin` URL.
+pub fn execute(config: Config, repo: String, new_spec: String, update_remote: bool) -> Result<()> {
+    let Some(repo_path) = find_repository_path(&config, &repo)? else {
+        return Err(exit::not_found(false, not_found_message(&config, &repo)?));
+    };
+    let entry = RepoEntry::from_path(&repo_path)
+        .ok_or_else(|| anyhow!("Not a repository directory: {}", repo_path.display()))?;
+    let root = entry
+        .root()
+        .ok_or_else(|| anyhow!("Not a repository directory: {}", repo_path.display()))?
+        .to_path_buf();
+    let target = RepoSpec::parse(&config, &new_spec)?;
+    let host = target.host.clone().unwrap_or_else(|| entry.host.clone());
+    let new_root = if host == entry.host {
+        root.clone()
+    } else {
+        paths::resolve_root(&config, &host)
+    };
+    let new_path = paths::repository_dir(&new_root, &host, &target.owner, &target.repo);
+    let new_spec = format!("{host}/{}", target.path());
+    if new_path == repo_path {
+        bail!("{} is already named {new_spec}", entry.spec());
+    }
+    // A change of case alone is the same directory on case-insensitive file systems
+    if new_path.exists() && !same_directory(&repo_path, &new_path) {
+        bail!(
+            "Can't rename {} to {new_spec}: {} exists",
+            entry.spec(),
+            new_path.display()
+        );
+    }
+
+    dry_run::apply(
+        &config,
+        format_args!("move {} to {}", repo_path.display(), new_path.display()),
+        || {
+            let lock = RepoLock::acquire(&entry.bare_repository_path())?;
+            if new_root == root {
+                move_directory(&repo_path, &new_path)?;
+            } else {
+                move_to_root(&repo_path, &new_path)?;
+            }
+            drop(lock);
+            // The lock file moved along with the repository
+            let _ = std::fs::remove_file(lock::lock_path(&paths::bare_repository_path(&new_path)));
+            let relinked = relocate::relink_repository(&new_path, &repo_path, &new_path)?;
+            info!("Rewrote {relinked} worktree links");
+            Ok(())
+        },
+    )?;
+    if update_remote {
+        dry_run::apply(
+            &config,
+            format_args!("point origin of {new_spec} at {host}/{}", target.path()),
+            || update_origin(&new_path, &host, &target.path()),
+        )?;
+    }
+    if config.dry_run {
+        return Ok(());
+    }
+
+    let old_spec = entry.spec();
+    if new_root == root {
+        Metadata::update(&root, |metadata| {
+            metadata.rename(&old_spec, &new_spec);
+            Ok(())
+        })?;
+    } else {
+        let records = Metadata::update(&root, |metadata| Ok(metadata.take_repository(&old_spec)))?;
+        Metadata::update(&new_root, |metadata| {
+            metadata.add_repository(records, &new_spec);
+            Ok(())
+        })?;
+        // Workspaces are kept in the primary root, whichever roots these are
+        Metadata::update(&config.root, |metadata| {
+            metadata.rename_workspace_members(&old_spec, &new_spec);
+            Ok(())
+        })?;
+        index::update(&new_root);
+    }
+    History::rename(&config, &repo_path, &new_path)?;
+    remove_empty_parents(&repo_path, &root);
+    index::update(&root);
+    info!("Renamed {} to {new_spec}", entry.spec());
+    println!("{}", new_path.display());
+    Ok(())
+}
+
+/// Renames `from` to `to`, going through a temporary name when they differ
+/// only in case so case-insensitive file systems apply the new spelling.
+fn move_directory(from: &Path, to: &Path) -> Result<()> {
+    if let Some(parent) = to.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let rename = |from: &Path, to: &Path| {
+        std::fs::rename(from, to)
+            .with_context(|| format!("Failed to move {} to {}", from.display(), to.display()))
+    };
+    if to.exists() {
+        let staging = from.with_extension("neoghq-rename");
+        rename(from, &staging)?;
+        return rename(&staging, to);
+    }
+    rename(from, to)
+}
+
+/// Moves `from` to `to` in another root, copying it and removing the
+/// original when the roots are on different file systems.
+fn move_to_root(from: &Path, to: &Path) -> Result<()> {
+    if let Some(parent) = to.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    match std::fs::rename(from, to) {
+        Err(error) if error.kind() == std::io::ErrorKind::CrossesDevices => {
+            if let Err(error) = copy_tree(from, to) {
+                let _ = std::fs::remove_dir_all(to);
+                return Err(error).with_context(|| {
+                    format!("Failed to copy {} to {}", from.display(), to.display())
+                });
+            }
+            std::fs::remove_dir_all(from)
+                .with_context(|| format!("Failed to remove {}", from.display()))
+        }
+        result => {
+            result.with_context(|| format!("Failed to move {} to {}", from.display(), to.display()))
+        }
+    }
+}
+
+/// Copies the directory `from` to `to`, keeping symlinks as symlinks.
+fn copy_tree(from: &Path, to: &Path) -> std::io::Result<()> {
+    std::fs::create_dir(to)?;
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        let file_type = entry.file_type()?;
+        if file_type.is_symlink() {
+            repo_settings::symlink(&std::fs::read_link(entry.path())?, &target)?;
+        } else if file_type.is_dir() {
+            copy_tree(&entry.path(), &target)?;
+        } else {
+            std::fs::copy(entry.path(), &target)?;
+        }
+    }
+    Ok(())
+}
+
+/// Whether `a` and `b` are the same directory, as two spellings on a
+/// case-insensitive file system are.
+fn same_directory(a: &Path, b: &Path) -> bool {
+    match (paths::canonicalize(a), paths::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// Points `origin` of the repository at `new_path` to `host/path`, keeping
+/// whether it used SSH or HTTPS.
+fn update_origin(new_path: &Path, host: &str, path: &str) -> Result<()> {
+    let repo = Repository::open_bare(paths::bare_repository_path(new_path))?;
+    let remote = repo
+        .find_remote("origin")
+        .map_err(|_| anyhow!("{} has no origin remote", new_path.display()))?;
+    let old_url = remote.url().unwrap_or_default().to_string();
+    if giturl::parse(&old_url).is_err() {
+        bail!(
+            "The origin of {} isn't a remote URL: {old_url}",
+            new_path.display()
+        );
+    }
+    let protocol = if old_url.starts_with("https://") || old_url.starts_with("http://") {
+        CloneProtocol::Https
+    } else {
+        CloneProtocol::Ssh
+    };
+    let url = spec::build_url(host, path, protocol);
+    repo.remote_set_url("origin", &url)?;
+    info!("Changed origin from {old_url} to {url}");
+    Ok(())
+}
+
+/// Removes the owner and host directories `repo_path` leaves empty, up to
+/// `root`.
+fn remove_empty_parents(repo_path: &Path, root: &Path) {
+    for dir in repo_path.ancestors().skip(1) {
+        if dir == root || !dir.starts_with(root) || std::fs::remove_dir(dir).is_err() {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_managed_repository;
+
+    #[test]
+    fn test_execute_moves_repository_and_worktrees() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = paths::canonicalize(temp_dir.path()).unwrap();
+        let old_path = root.join("github.com/user/repo");
+        let bare = create_managed_repository(&old_path);
+        let head = bare.head().unwrap().peel_to_commit().unwrap();
+        let branch = bare.branch("feature/x", &head, false).unwrap();
+        let mut opts = git2::WorktreeAddOptions::new();
+        opts.reference(Some(branch.get()));
+        std::fs::create_dir(old_path.join("feature")).unwrap();
+        bare.worktree("feature-x", &old_path.join("feature/x"), Some(&opts))
+            .unwrap();
+        bare.remote_set_url("origin", "git@github.com:user/repo.git")
+            .unwrap();
+        let mut metadata = Metadata::default();
+        metadata.pin("github.com/user/repo");
+        metadata.save(&root).unwrap();
+        let config = Config {
+            root: root.clone(),
+            ..Default::default()
+        };
+
+        let dry_run = Config {
+            dry_run: true,
+            ..config.clone()
+        };
+        execute(
+            dry_run,
+            "user/repo".to_string(),
+            "org/project".to_string(),
+            true,
+        )
+        .unwrap();
+        assert!(old_path.exists());
+
+        execute(
+            config.clone(),
+            "user/repo".to_string(),
+            "org/project".to_string(),
+            true,
+        )
+        .unwrap();
+        let new_path = root.join("github.com/org/project");
+        assert!(!old_path.exists());
+        assert!(!root.join("github.com/user").exists());
+        assert!(!new_path.join(".git.lock").exists());
+        let bare = Repository::open_bare(new_path.join(".git")).unwrap();
+        for name in ["main", "feature-x"] {
+            assert!(
+                bare.find_worktree(name).unwrap().validate().is_ok(),
+                "{name}"
+            );
+        }
+        let worktree = Repository::open(new_path.join("feature/x")).unwrap();
+        assert!(worktree.statuses(None).is_ok());
+        assert_eq!(
+            bare.find_remote("origin").unwrap().url(),
+            Some("git@github.com:org/project.git")
+        );
+        assert!(
+            Metadata::load(&root)
+                .unwrap()
+                .unpin("github.com/org/project")
+        );
+
+        // Renaming onto an existing repository is refused
+        create_managed_repository(&root.join("github.com/user/other"));
+        assert!(
+            execute(
+                config,
+                "org/project".to_string(),
+                "user/other".to_string(),
+                false
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_execute_to_another_host_keeps_origin() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = paths::canonicalize(temp_dir.path()).unwrap();
+        let bare = create_managed_repository(&root.join("github.com/user/repo"));
+        let origin = bare
+            .find_remote("origin")
+            .unwrap()
+            .url()
+            .unwrap()
+            .to_string();
+        let config = Config {
+            root: root.clone(),
+            ..Default::default()
+        };
+
+        execute(
+            config,
+            "github.com/user/repo".to_string(),
+            "gitlab.com/user/repo".to_string(),
+            false,
+        )
+        .unwrap();
+        let bare = Repository::open_bare(root.join("gitlab.com/user/repo/.git")).unwrap();
+        assert!(bare.find_worktree("main").unwrap().validate().is_ok());
+        assert_eq!(
+            bare.find_remote("origin").unwrap().url(),
+            Some(origin.as_str())
+        );
+        assert!(!root.join("github.com").exists());
+    }
+
+    #[test]
+    fn test_execute_to_a_host_in_another_root() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let base = paths::canonicalize(temp_dir.path()).unwrap();
+        let (root, work) = (base.join("root"), base.join("work"));
+        let old_path = root.join("github.com/user/repo");
+        create_managed_repository(&old_path);
+        let mut metadata = Metadata::default();
+        metadata.pin("github.com/user/repo");
+        metadata.save(&root).unwrap();
+        let mut config = Config {
+            root: root.clone(),
+            ..Default::default()
+        };
+        config.hosts.insert(
+            "git.company.com".to_string(),
+            crate::config::HostConfig {
+                root: Some(work.clone()),
+                ..Default::default()
+            },
+        );
+
+        execute(
+            config,
+            "github.com/user/repo".to_string(),
+            "git.company.com/team/repo".to_string(),
+            false,
+        )
+        .unwrap();
+        let new_path = work.join("git.company.com/team/repo");
+        assert!(!old_path.exists());
+        assert!(!root.join("github.com").exists());
+        let bare = Repository::open_bare(new_path.join(".git")).unwrap();
+        assert!(bare.find_worktree("main").unwrap().validate().is_ok());
+        assert!(!Metadata::load(&root).unwrap().unpin("github.com/user/repo"));
+        assert!(
+            Metadata::load(&work)
+                .unwrap()
+                .unpin("git.company.com/team/repo")
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_copy_tree_keeps_symlinks() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let from = temp_dir.path().join("from");
+        std::fs::create_dir_all(from.join("main/src")).unwrap();
+        std::fs::write(from.join("main/src/lib.rs"), "").unwrap();
+        std::os::unix::fs::symlink("../shared/.env", from.join("main/.env")).unwrap();
+
+        let to = temp_dir.path().join("to");
+        copy_tree(&from, &to).unwrap();
+        assert!(to.join("main/src/lib.rs").is_file());
+        assert_eq!(
+            std::fs::read_link(to.join("main/.env")).unwrap(),
+            Path::new("../shared/.env")
+        );
+    }
+}
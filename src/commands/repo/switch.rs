@@ -1,7 +1,615 @@
-use anyhow::Result;
+use super::compact::restore_default_worktree;
+use crate::{
+    case,
+    cli::{SortKey, SwitchFlags},
+    config::Config,
+    editor,
+    exit::{self, ErrorKind},
+    history::History,
+    ignore::IgnoreList,
+    metadata, paths, picker,
+    scan::{self, RepoEntry},
+    sort,
+    spec::RepoSpec,
+    status, suggest,
+};
+use anyhow::{Result, anyhow};
+use git2::Repository;
+use std::path::{Path, PathBuf};
+use tracing::warn;
 
-pub fn execute(repo: String) -> Result<()> {
-    println!("Switching to repo: {repo}");
-    println!("repo switch functionality not yet implemented");
+/// Prints the default worktree of `repo`, or of a repository chosen in the
+/// picker when no repository is given.
+pub fn execute(config: Config, repo: Option<String>, flags: SwitchFlags) -> Result<()> {
+    let quiet = flags.quiet || flags.exists_only;
+    let repo_path = match repo {
+        Some(repo) => match find_repository_path(&config, &repo)? {
+            Some(repo_path) => repo_path,
+            None => return Err(exit::not_found(quiet, not_found_message(&config, &repo)?)),
+        },
+        // clap can't require the argument when -q comes before the subcommand
+        None if quiet => return Err(anyhow!("--quiet and --exists-only need a repository")),
+        None => pick_repository(&config)?.ok_or_else(|| anyhow!("No repository selected"))?,
+    };
+    let worktree_path = match find_default_worktree(&repo_path)? {
+        Some(worktree_path) => worktree_path,
+        // A repository compacted to its bare repository gets its default
+        // worktree back
+        None if !flags.no_create && repo_path.join(".git").is_dir() => {
+            if flags.exists_only {
+                return Ok(());
+            }
+            let worktree_path = restore_default_worktree(&config, &repo_path)?;
+            if config.dry_run {
+                return Ok(());
+            }
+            worktree_path
+        }
+        None => {
+            return Err(exit::not_found(
+                quiet,
+                format!("No worktree found in repository: {}", repo_path.display()),
+            ));
+        }
+    };
+
+    if !flags.exists_only {
+        // A history that can't be written only affects --sort recent
+        History::record(&config, &repo_path).ok();
+        println!("{}", worktree_path.display());
+    }
+    if flags.open {
+        editor::open(&config, &worktree_path)?;
+    }
     Ok(())
 }
+
+/// Lets the user pick one of the managed repositories, pinned ones first,
+/// then the most recently switched to.
+fn pick_repository(config: &Config) -> Result<Option<PathBuf>> {
+    let mut entries = scan::scan_roots(&config.all_roots())?;
+    sort::sort_entries(
+        &mut entries,
+        SortKey::Recent,
+        config,
+        |entry| &entry.path,
+        RepoEntry::spec,
+    );
+    sort::pinned_first(&mut entries, &metadata::pinned(config)?, |entry| {
+        &entry.path
+    });
+    let items = entries.iter().map(RepoEntry::spec).collect::<Vec<_>>();
+
+    Ok(picker::pick(config, "Repository", &items)?.map(|index| entries[index].path.clone()))
+}
+
+/// Explains that `repo` doesn't exist, suggesting similarly named
+/// repositories.
+pub(crate) fn not_found_message(config: &Config, repo: &str) -> Result<String> {
+    let entries = scan::scan_roots(&config.all_roots())?;
+    let mut names = entries
+        .iter()
+        .map(|entry| format!("{}/{}", entry.owner, entry.repo))
+        .collect::<Vec<_>>();
+    names.sort();
+    names.dedup();
+    let suggestions = suggest::similar(repo, names.iter().map(String::as_str));
+
+    Ok(suggest::with_suggestions(
+        format!("Repository not found: {repo}"),
+        &suggestions,
+    ))
+}
+
+/// Searches every host directory of every configured root for `owner/repo`.
+pub(crate) fn find_repository_path(config: &Config, repo: &str) -> Result<Option<PathBuf>> {
+    let spec = RepoSpec::parse(config, repo)?;
+    let owner = paths::owner_dir(&spec.owner);
+    let (owner, name) = (owner.as_str(), spec.repo.as_str());
+
+    // A host, given directly or by an alias such as `gl:group/proj`, pins
+    // the directory
+    if let Some(host) = &spec.host {
+        let names = [host.as_str()]
+            .into_iter()
+            .chain([owner, name])
+            .collect::<Vec<_>>();
+        for root in config.all_roots() {
+            if let Some(repo_path) = spec.dir(&root.path)
+                && repo_path.join(".git").exists()
+            {
+                return Ok(Some(repo_path));
+            }
+        }
+        for root in config.all_roots() {
+            if let Some(repo_path) = case::find_ignoring_case(&root.path, &names)? {
+                warn!(
+                    "Matched {} for {repo} ignoring case",
+                    display_spec(&repo_path)
+                );
+                return Ok(Some(repo_path));
+            }
+        }
+        return Ok(None);
+    }
+
+    let mut candidates = Vec::new();
+    for root in config.all_roots() {
+        if !root.path.exists() {
+            continue;
+        }
+        let ignore = IgnoreList::load(&root.path)?;
+        for host_path in scan::subdirectories(&root.path)? {
+            let repo_path = host_path.join(owner).join(name);
+            let relative = repo_path.strip_prefix(&root.path).unwrap_or(&repo_path);
+            if !ignore.is_ignored(relative) && repo_path.join(".git").exists() {
+                candidates.push(repo_path);
+            }
+        }
+    }
+    if !candidates.is_empty() {
+        return choose_candidate(config, repo, candidates).map(Some);
+    }
+
+    // Case-sensitive filesystems don't find `User/Repo` as `user/repo`
+    for root in config.all_roots() {
+        if !root.path.exists() {
+            continue;
+        }
+        let ignore = IgnoreList::load(&root.path)?;
+        let names = [owner, name];
+        for host_path in scan::subdirectories(&root.path)? {
+            let Some(repo_path) = case::find_ignoring_case(&host_path, &names)? else {
+                continue;
+            };
+            let relative = repo_path.strip_prefix(&root.path).unwrap_or(&repo_path);
+            if !ignore.is_ignored(relative) {
+                warn!(
+                    "Matched {} for {repo} ignoring case",
+                    display_spec(&repo_path)
+                );
+                return Ok(Some(repo_path));
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+/// Picks the repository `repo` means among same-named repositories on
+/// several hosts: the one on the default host, or the user's choice in the
+/// picker. Without a way to ask, the candidates are listed in the error.
+fn choose_candidate(config: &Config, repo: &str, mut candidates: Vec<PathBuf>) -> Result<PathBuf> {
+    if candidates.len() == 1 {
+        return Ok(candidates.remove(0));
+    }
+    if let Some(host) = &config.default_host
+        && let Some(index) = candidates.iter().position(|candidate| {
+            candidate
+                .parent()
+                .and_then(Path::parent)
+                .and_then(Path::file_name)
+                .is_some_and(|name| name == host.as_str())
+        })
+    {
+        return Ok(candidates.remove(index));
+    }
+
+    let specs = candidates
+        .iter()
+        .map(|candidate| display_spec(candidate))
+        .collect::<Vec<_>>();
+    if config.no_input || (config.picker_command.is_none() && !config.interactive()) {
+        return Err(exit::error(
+            ErrorKind::InputRequired,
+            format!("{repo} is ambiguous; name one of: {}", specs.join(", ")),
+        ));
+    }
+    let index = picker::pick(config, &format!("{repo} on"), &specs)?
+        .ok_or_else(|| anyhow!("No repository selected"))?;
+    Ok(candidates.remove(index))
+}
+
+fn display_spec(repo_path: &Path) -> String {
+    RepoEntry::from_path(repo_path)
+        .map_or_else(|| repo_path.display().to_string(), |entry| entry.spec())
+}
+
+/// Returns the worktree of the branch the bare repository's HEAD names, or
+/// else the first registered worktree. Only when the bare repository can't
+/// be read does it guess from the directories next to `.git`.
+fn find_default_worktree(repo_path: &Path) -> Result<Option<PathBuf>> {
+    let Ok(bare) = Repository::open_bare(repo_path.join(".git")) else {
+        return guess_default_worktree(repo_path);
+    };
+    let Ok(worktrees) = status::list_worktrees(&bare) else {
+        return guess_default_worktree(repo_path);
+    };
+    let mut worktrees = worktrees
+        .into_iter()
+        .filter(|worktree| worktree.path.is_dir())
+        .collect::<Vec<_>>();
+    let default_branch = status::default_branch(&bare);
+    let index = worktrees
+        .iter()
+        .position(|worktree| worktree.branch.is_some() && worktree.branch == default_branch)
+        .or_else(|| (!worktrees.is_empty()).then_some(0));
+    Ok(index.map(|index| worktrees.swap_remove(index).path))
+}
+
+/// Picks a worktree of a repository whose bare repository is damaged: the
+/// first directory with a `.git`, or else any directory.
+fn guess_default_worktree(repo_path: &Path) -> Result<Option<PathBuf>> {
+    let mut directories = scan::subdirectories(repo_path)?
+        .into_iter()
+        .filter(|path| path.file_name().is_some_and(|name| name != ".git"))
+        .collect::<Vec<_>>();
+    directories.sort();
+    Ok(directories
+        .iter()
+        .find(|path| path.join(".git").exists())
+        .or(directories.first())
+        .cloned())
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use crate::config::Root;
+    use crate::test_utils::create_managed_repository;
+    use std::fs;
+
+    fn create_repo(root: &Path, host: &str, owner: &str, repo: &str) -> PathBuf {
+        let repo_path = root.join(host).join(owner).join(repo);
+        fs::create_dir_all(repo_path.join(".git")).unwrap();
+        fs::create_dir_all(repo_path.join("main")).unwrap();
+        repo_path
+    }
+
+    #[test]
+    fn test_find_repository_path_searches_all_roots() {
+        let personal = tempfile::tempdir().unwrap();
+        let work = tempfile::tempdir().unwrap();
+        let repo_path = create_repo(work.path(), "gitlab.company.com", "team", "service");
+
+        let config = Config {
+            root: personal.path().to_path_buf(),
+            roots: vec![Root {
+                name: "work".to_string(),
+                path: work.path().to_path_buf(),
+            }],
+            ..Default::default()
+        };
+
+        let result = find_repository_path(&config, "team/service")
+            .unwrap()
+            .unwrap();
+        assert_eq!(result, repo_path);
+    }
+
+    #[test]
+    fn test_find_repository_path_skips_ignored_directories() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        create_repo(temp_dir.path(), "archive", "user", "repo");
+        fs::write(temp_dir.path().join(".neoghqignore"), "archive\n").unwrap();
+        let config = Config {
+            root: temp_dir.path().to_path_buf(),
+            ..Default::default()
+        };
+
+        assert_eq!(find_repository_path(&config, "user/repo").unwrap(), None);
+        assert_eq!(find_repository_path(&config, "User/Repo").unwrap(), None);
+    }
+
+    #[test]
+    fn test_find_repository_path_ignores_case() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo_path = create_repo(temp_dir.path(), "github.com", "User", "Repo");
+        let config = Config {
+            root: temp_dir.path().to_path_buf(),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            find_repository_path(&config, "user/repo").unwrap(),
+            Some(repo_path)
+        );
+        assert_eq!(find_repository_path(&config, "user/other").unwrap(), None);
+    }
+
+    #[test]
+    fn test_find_repository_path_not_found() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config = Config {
+            root: temp_dir.path().to_path_buf(),
+            ..Default::default()
+        };
+
+        assert_eq!(find_repository_path(&config, "user/repo").unwrap(), None);
+    }
+
+    #[test]
+    fn test_find_repository_path_invalid_format() {
+        let config = Config::default();
+
+        assert!(find_repository_path(&config, "repo").is_err());
+    }
+
+    #[test]
+    fn test_find_repository_path_uses_default_user() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo_path = create_repo(temp_dir.path(), "github.com", "octocat", "repo");
+        let config = Config {
+            root: temp_dir.path().to_path_buf(),
+            user: Some("octocat".to_string()),
+            ..Default::default()
+        };
+
+        let result = find_repository_path(&config, "repo").unwrap().unwrap();
+        assert_eq!(result, repo_path);
+    }
+
+    #[test]
+    fn test_find_repository_path_prefers_default_host() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        create_repo(temp_dir.path(), "a.example.com", "user", "repo");
+        create_repo(temp_dir.path(), "b.example.com", "user", "repo");
+        let repo_path = create_repo(temp_dir.path(), "gitlab.company.com", "user", "repo");
+        let config = Config {
+            root: temp_dir.path().to_path_buf(),
+            default_host: Some("gitlab.company.com".to_string()),
+            ..Default::default()
+        };
+
+        let result = find_repository_path(&config, "user/repo").unwrap().unwrap();
+        assert_eq!(result, repo_path);
+    }
+
+    #[test]
+    fn test_find_repository_path_ambiguous() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        create_repo(temp_dir.path(), "a.example.com", "user", "repo");
+        let repo_path = create_repo(temp_dir.path(), "b.example.com", "user", "repo");
+        let config = Config {
+            root: temp_dir.path().to_path_buf(),
+            no_input: true,
+            ..Default::default()
+        };
+
+        let error = find_repository_path(&config, "user/repo").unwrap_err();
+        assert_eq!(exit::classify(&error), Some(ErrorKind::InputRequired));
+        let message = error.to_string();
+        assert!(message.contains("user/repo is ambiguous"), "{message}");
+        assert!(message.contains("a.example.com/user/repo"), "{message}");
+        assert!(message.contains("b.example.com/user/repo"), "{message}");
+
+        // A host-qualified spec isn't ambiguous
+        assert_eq!(
+            find_repository_path(&config, "b.example.com/user/repo").unwrap(),
+            Some(repo_path.clone())
+        );
+
+        let config = Config {
+            no_input: false,
+            picker_command: Some("grep b.example.com".to_string()),
+            ..config
+        };
+        assert_eq!(
+            find_repository_path(&config, "user/repo").unwrap(),
+            Some(repo_path)
+        );
+    }
+
+    #[test]
+    fn test_find_repository_path_with_alias() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        create_repo(temp_dir.path(), "github.com", "group", "proj");
+        let repo_path = create_repo(temp_dir.path(), "gitlab.com", "group", "proj");
+        let config = Config {
+            root: temp_dir.path().to_path_buf(),
+            ..Default::default()
+        };
+
+        let result = find_repository_path(&config, "gl:group/proj")
+            .unwrap()
+            .unwrap();
+        assert_eq!(result, repo_path);
+        assert_eq!(
+            find_repository_path(&config, "bb:group/proj").unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_find_repository_path_with_host_or_url() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        create_repo(temp_dir.path(), "github.com", "user", "repo");
+        let repo_path = create_repo(temp_dir.path(), "gitlab.com", "User", "Repo");
+        let config = Config {
+            root: temp_dir.path().to_path_buf(),
+            ..Default::default()
+        };
+
+        for spec in [
+            "gitlab.com/User/Repo",
+            "www.GitLab.com/user/repo.git/",
+            "https://gitlab.com/User/Repo.git",
+            "git@gitlab.com:user/repo.git",
+        ] {
+            assert_eq!(
+                find_repository_path(&config, spec).unwrap().as_ref(),
+                Some(&repo_path),
+                "{spec}"
+            );
+        }
+        assert_eq!(
+            find_repository_path(&config, "bitbucket.org/user/repo").unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_find_default_worktree() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo_path = temp_dir.path().join("github.com/user/repo");
+        let bare = create_managed_repository(&repo_path);
+        let head = bare.head().unwrap().peel_to_commit().unwrap();
+        let branch = bare.branch("feature/x", &head, false).unwrap();
+        let mut opts = git2::WorktreeAddOptions::new();
+        opts.reference(Some(branch.get()));
+        fs::create_dir(repo_path.join("feature")).unwrap();
+        bare.worktree("feature-x", &repo_path.join("feature/x"), Some(&opts))
+            .unwrap();
+        // Directories that aren't worktrees sort before `main`
+        fs::create_dir(repo_path.join("aaa-notes")).unwrap();
+
+        assert_eq!(
+            find_default_worktree(&repo_path).unwrap(),
+            Some(repo_path.join("main"))
+        );
+
+        // libgit2 refuses to point HEAD at a branch checked out elsewhere
+        bare.reference_symbolic("HEAD", "refs/heads/feature/x", true, "test")
+            .unwrap();
+        assert_eq!(
+            find_default_worktree(&repo_path).unwrap(),
+            Some(repo_path.join("feature/x"))
+        );
+
+        // Without a worktree on the default branch, any registered one
+        bare.reference_symbolic("HEAD", "refs/heads/missing", true, "test")
+            .unwrap();
+        assert_eq!(
+            find_default_worktree(&repo_path).unwrap(),
+            Some(repo_path.join("feature/x"))
+        );
+    }
+
+    #[test]
+    fn test_find_default_worktree_guesses_in_damaged_repositories() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo_path = create_repo(temp_dir.path(), "github.com", "user", "repo");
+        fs::create_dir(repo_path.join("aaa-notes")).unwrap();
+
+        assert_eq!(
+            find_default_worktree(&repo_path).unwrap(),
+            Some(repo_path.join("aaa-notes"))
+        );
+        fs::write(repo_path.join("main/.git"), "gitdir: missing\n").unwrap();
+        assert_eq!(
+            find_default_worktree(&repo_path).unwrap(),
+            Some(repo_path.join("main"))
+        );
+    }
+
+    #[test]
+    fn test_find_default_worktree_without_worktrees() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(temp_dir.path().join(".git")).unwrap();
+
+        assert_eq!(find_default_worktree(temp_dir.path()).unwrap(), None);
+    }
+
+    #[test]
+    fn test_execute() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        create_repo(temp_dir.path(), "github.com", "user", "repo");
+        let config = Config {
+            root: temp_dir.path().to_path_buf(),
+            ..Default::default()
+        };
+
+        assert!(
+            execute(
+                config.clone(),
+                Some("user/repo".to_string()),
+                SwitchFlags::default()
+            )
+            .is_ok()
+        );
+
+        // Without a repository the picker chooses one
+        let config = Config {
+            picker_command: Some("grep user/repo".to_string()),
+            ..config
+        };
+        assert!(execute(config.clone(), None, SwitchFlags::default()).is_ok());
+        let config = Config {
+            picker_command: Some("false".to_string()),
+            ..config
+        };
+        assert!(execute(config, None, SwitchFlags::default()).is_err());
+    }
+
+    #[test]
+    fn test_execute_restores_compacted_repository() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo_path = temp_dir.path().join("github.com/user/repo");
+        crate::test_utils::create_managed_repository(&repo_path);
+        let config = Config {
+            root: temp_dir.path().to_path_buf(),
+            ..Default::default()
+        };
+        super::super::compact::execute(config.clone(), "user/repo".to_string()).unwrap();
+
+        let no_create = SwitchFlags {
+            no_create: true,
+            ..Default::default()
+        };
+        assert!(execute(config.clone(), Some("user/repo".to_string()), no_create).is_err());
+        assert!(!repo_path.join("main").exists());
+
+        let config = Config {
+            editor_command: Some("touch {path}/opened".to_string()),
+            ..config
+        };
+        let open = SwitchFlags {
+            open: true,
+            ..Default::default()
+        };
+        execute(config, Some("user/repo".to_string()), open).unwrap();
+        assert!(repo_path.join("main/README").exists());
+        assert!(repo_path.join("main/opened").exists());
+    }
+
+    #[test]
+    fn test_execute_not_found_exits_with_status() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config = Config {
+            root: temp_dir.path().to_path_buf(),
+            ..Default::default()
+        };
+        let flags = SwitchFlags {
+            no_create: true,
+            exists_only: true,
+            quiet: true,
+            open: false,
+        };
+
+        let error = execute(config, Some("user/repo".to_string()), flags).unwrap_err();
+        let exit = error.downcast_ref::<exit::ExitError>().unwrap();
+        assert_eq!(exit.kind, exit::ErrorKind::NotFound);
+        assert_eq!(exit.message, None);
+    }
+
+    #[test]
+    fn test_not_found_message_suggests_similar_repositories() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        create_repo(temp_dir.path(), "github.com", "r4ai", "neoghq");
+        create_repo(temp_dir.path(), "github.com", "r4ai", "dotfiles");
+        let config = Config {
+            root: temp_dir.path().to_path_buf(),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            not_found_message(&config, "r4ai/neoghg").unwrap(),
+            "Repository not found: r4ai/neoghg\n\nDid you mean r4ai/neoghq?"
+        );
+        assert_eq!(
+            not_found_message(&config, "someone/else").unwrap(),
+            "Repository not found: someone/else"
+        );
+    }
+}
@@ -0,0 +1,303 @@
+use super::{
+    get::{fetch_remote, push_remote},
+    switch::{find_repository_path, not_found_message},
+};
+use crate::{
+    commands::worktree::rebase::replay,
+    config::Config,
+    dry_run, exit,
+    lock::RepoLock,
+    scan::{self, RepoEntry},
+    status,
+};
+use anyhow::{Context, Result, anyhow, bail};
+use git2::{Repository, build::CheckoutBuilder};
+use tracing::{info, warn};
+
+/// What happened to a fork's default branch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Synced {
+    /// Upstream had nothing new.
+    UpToDate,
+    /// The branch moved forward to upstream by this many commits.
+    FastForwarded(usize),
+    /// This many local commits were replayed on top of upstream.
+    Rebased(usize),
+}
+
+/// Brings the default branch of the forks `repos`, or of every managed
+/// repository with an `upstream` remote with `all`, up to date with
+/// upstream and pushes it to `origin`, like the forge's "Sync fork" button.
+/// The branch is fast-forwarded; when it has commits of its own it is
+/// rebased onto upstream with `rebase`, and left alone otherwise.
+pub fn execute(
+    config: Config,
+    repos: Vec<String>,
+    all: bool,
+    rebase: bool,
+    push: bool,
+) -> Result<()> {
+    let entries = if all {
+        scan::scan_roots(&config.all_roots())?
+            .into_iter()
+            .filter(|entry| {
+                Repository::open_bare(entry.bare_repository_path())
+                    .is_ok_and(|repo| repo.find_remote("upstream").is_ok())
+            })
+            .collect()
+    } else {
+        repos
+            .iter()
+            .map(|repo| {
+                let Some(repo_path) = find_repository_path(&config, repo)? else {
+                    return Err(exit::not_found(false, not_found_message(&config, repo)?));
+                };
+                RepoEntry::from_path(&repo_path)
+                    .ok_or_else(|| anyhow!("Not a repository directory: {}", repo_path.display()))
+            })
+            .collect::<Result<Vec<_>>>()?
+    };
+    if entries.is_empty() {
+        info!("No repositories with an upstream remote");
+    }
+
+    let mut failed = 0;
+    for entry in &entries {
+        if let Err(error) = sync(&config, entry, rebase, push) {
+            failed += 1;
+            warn!("{}: {error:#}", entry.spec());
+        }
+    }
+
+    match failed {
+        0 => Ok(()),
+        count => Err(anyhow!("Failed to sync {count} forks")),
+    }
+}
+
+fn sync(config: &Config, entry: &RepoEntry, rebase: bool, push: bool) -> Result<()> {
+    let bare_repo_path = entry.bare_repository_path();
+    let repo = Repository::open_bare(&bare_repo_path)?;
+    if repo.find_remote("upstream").is_err() {
+        bail!("Not a fork: there is no upstream remote");
+    }
+    let branch = status::default_branch(&repo)
+        .ok_or_else(|| anyhow!("HEAD of the bare repository isn't a branch"))?;
+    for remote in ["upstream", "origin"] {
+        fetch_remote(
+            &bare_repo_path,
+            remote,
+            &config.credentials,
+            config.fetch_prune,
+        )?;
+    }
+
+    let local_ref = format!("refs/heads/{branch}");
+    let local = repo
+        .refname_to_id(&local_ref)
+        .with_context(|| format!("There is no local {branch} branch"))?;
+    let upstream = repo
+        .refname_to_id(&format!("refs/remotes/upstream/{branch}"))
+        .with_context(|| format!("upstream has no {branch} branch"))?;
+    let origin = repo
+        .refname_to_id(&format!("refs/remotes/origin/{branch}"))
+        .ok();
+    let (ahead, behind) = repo.graph_ahead_behind(local, upstream)?;
+    let synced = if behind == 0 {
+        Synced::UpToDate
+    } else if ahead == 0 {
+        Synced::FastForwarded(behind)
+    } else if rebase {
+        Synced::Rebased(ahead)
+    } else {
+        bail!(
+            "{branch} has {ahead} commits upstream doesn't; pass --rebase to replay them on top of upstream"
+        );
+    };
+    // Rebasing rewrites the branch, which is only safe to force-push when
+    // origin has nothing the local branch lacks
+    if push
+        && matches!(synced, Synced::Rebased(_))
+        && let Some(origin) = origin
+        && origin != local
+        && !repo.graph_descendant_of(local, origin)?
+    {
+        bail!("origin/{branch} has commits the local {branch} lacks; pull them first");
+    }
+
+    if synced != Synced::UpToDate {
+        let worktree = status::list_worktrees(&repo)?
+            .into_iter()
+            .find(|worktree| worktree.branch.as_deref() == Some(branch.as_str()));
+        if let Some(worktree) = &worktree
+            && status::is_worktree_dirty(&worktree.path)?
+        {
+            bail!(
+                "The {branch} worktree at {} has uncommitted changes",
+                worktree.path.display()
+            );
+        }
+        dry_run::apply(
+            config,
+            format_args!("update {branch} of {} from upstream", entry.spec()),
+            || {
+                let _lock = RepoLock::acquire(&bare_repo_path)?;
+                let tip = match synced {
+                    Synced::Rebased(_) => replay(&repo, local, upstream, upstream)?,
+                    _ => upstream,
+                };
+                repo.reference(&local_ref, tip, true, "neoghq: sync fork")?;
+                if let Some(worktree) = &worktree {
+                    // Clean, so the checkout only brings in upstream's changes
+                    Repository::open(&worktree.path)?
+                        .checkout_head(Some(CheckoutBuilder::new().force()))?;
+                }
+                Ok(())
+            },
+        )?;
+    }
+
+    let pushed = push && (synced != Synced::UpToDate || origin != Some(local));
+    if pushed {
+        let refspec = match synced {
+            Synced::Rebased(_) => format!("+{local_ref}:{local_ref}"),
+            _ => format!("{local_ref}:{local_ref}"),
+        };
+        dry_run::apply(
+            config,
+            format_args!("push {branch} of {} to origin", entry.spec()),
+            || push_remote(&bare_repo_path, "origin", &[&refspec], &config.credentials),
+        )?;
+    }
+    if config.dry_run {
+        return Ok(());
+    }
+
+    let change = match synced {
+        Synced::UpToDate => "is up to date with upstream".to_string(),
+        Synced::FastForwarded(count) => format!("fast-forwarded {count} commits from upstream"),
+        Synced::Rebased(count) => format!("rebased {count} commits onto upstream"),
+    };
+    let pushed = if pushed { ", pushed to origin" } else { "" };
+    println!("{}: {branch} {change}{pushed}", entry.spec());
+    Ok(())
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use crate::test_utils::{commit_all, create_managed_repository, create_source_repository};
+    use std::path::Path;
+
+    /// Creates a fork at `<root>/github.com/user/repo` whose origin is a bare
+    /// repository and whose upstream is the returned source repository.
+    fn create_fork(root: &Path, temp: &Path) -> (Repository, Repository) {
+        let upstream = create_source_repository(&temp.join("upstream"));
+        let origin_path = temp.join("origin.git");
+        git2::build::RepoBuilder::new()
+            .bare(true)
+            .clone(temp.join("upstream").to_str().unwrap(), &origin_path)
+            .unwrap();
+        let bare = create_managed_repository(&root.join("github.com/user/repo"));
+        bare.remote_set_url("origin", origin_path.to_str().unwrap())
+            .unwrap();
+        bare.remote("upstream", temp.join("upstream").to_str().unwrap())
+            .unwrap();
+        let mut config = bare.config().unwrap();
+        config.set_str("user.name", "Test").unwrap();
+        config.set_str("user.email", "test@example.com").unwrap();
+        // The fork starts where upstream does
+        let head = upstream.head().unwrap().target().unwrap();
+        fetch_remote(
+            bare.path(),
+            "upstream",
+            &Default::default(),
+            Default::default(),
+        )
+        .unwrap();
+        bare.reference("refs/heads/main", head, true, "test")
+            .unwrap();
+        Repository::open(root.join("github.com/user/repo/main"))
+            .unwrap()
+            .checkout_head(Some(CheckoutBuilder::new().force()))
+            .unwrap();
+        (bare, upstream)
+    }
+
+    #[test]
+    fn test_execute_fast_forwards_and_pushes() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path().join("root");
+        let (bare, upstream) = create_fork(&root, temp_dir.path());
+        std::fs::write(temp_dir.path().join("upstream/NEWS"), "news\n").unwrap();
+        let new_head = commit_all(&upstream, "Add news");
+        let config = Config {
+            root: root.clone(),
+            ..Default::default()
+        };
+
+        execute(config.clone(), Vec::new(), true, false, true).unwrap();
+        assert_eq!(bare.refname_to_id("refs/heads/main").unwrap(), new_head);
+        assert!(root.join("github.com/user/repo/main/NEWS").is_file());
+        let origin = Repository::open_bare(temp_dir.path().join("origin.git")).unwrap();
+        assert_eq!(origin.refname_to_id("refs/heads/main").unwrap(), new_head);
+
+        // Nothing new
+        execute(config, vec!["user/repo".to_string()], false, false, true).unwrap();
+    }
+
+    #[test]
+    fn test_execute_rebases_local_commits() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path().join("root");
+        let (bare, upstream) = create_fork(&root, temp_dir.path());
+        let worktree_path = root.join("github.com/user/repo/main");
+        let worktree = Repository::open(&worktree_path).unwrap();
+        std::fs::write(worktree_path.join("LOCAL"), "local\n").unwrap();
+        commit_all(&worktree, "Add local file");
+        std::fs::write(temp_dir.path().join("upstream/NEWS"), "news\n").unwrap();
+        let upstream_head = commit_all(&upstream, "Add news");
+        let config = Config {
+            root: root.clone(),
+            ..Default::default()
+        };
+        let repos = vec!["user/repo".to_string()];
+
+        // Diverged branches need --rebase
+        assert!(execute(config.clone(), repos.clone(), false, false, false).is_err());
+        execute(config, repos, false, true, false).unwrap();
+        let head = bare
+            .find_reference("refs/heads/main")
+            .unwrap()
+            .peel_to_commit()
+            .unwrap();
+        assert_eq!(head.summary(), Some("Add local file"));
+        assert_eq!(head.parent_id(0).unwrap(), upstream_head);
+        assert!(worktree_path.join("NEWS").is_file());
+        assert!(worktree_path.join("LOCAL").is_file());
+        assert!(!status::is_worktree_dirty(&worktree_path).unwrap());
+    }
+
+    #[test]
+    fn test_execute_requires_upstream() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        create_managed_repository(&temp_dir.path().join("github.com/user/repo"));
+        let config = Config {
+            root: temp_dir.path().to_path_buf(),
+            ..Default::default()
+        };
+        assert!(
+            execute(
+                config.clone(),
+                vec!["user/repo".to_string()],
+                false,
+                false,
+                true
+            )
+            .is_err()
+        );
+        // --all skips repositories that aren't forks
+        execute(config, Vec::new(), true, false, true).unwrap();
+    }
+}
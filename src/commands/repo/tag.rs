@@ -0,0 +1,102 @@
+use super::switch::{find_repository_path, not_found_message};
+use crate::{config::Config, exit, metadata::Metadata, scan::RepoEntry};
+use anyhow::{Result, anyhow};
+use std::path::PathBuf;
+
+/// Tags `repo` with each of `tags`.
+pub fn add(config: Config, repo: String, tags: Vec<String>) -> Result<()> {
+    let (root, spec) = resolve(&config, &repo)?;
+    Metadata::update(&root, |metadata| {
+        for tag in &tags {
+            metadata.add_tag(&spec, tag)?;
+        }
+        Ok(())
+    })
+}
+
+/// Removes each of `tags` from `repo`; tags it doesn't have are ignored.
+pub fn remove(config: Config, repo: String, tags: Vec<String>) -> Result<()> {
+    let (root, spec) = resolve(&config, &repo)?;
+    Metadata::update(&root, |metadata| {
+        for tag in &tags {
+            metadata.remove_tag(&spec, tag);
+        }
+        Ok(())
+    })
+}
+
+/// Prints the tags of `repo`, or every tag in use when no repository is
+/// given.
+pub fn list(config: Config, repo: Option<String>) -> Result<()> {
+    let tags = match repo {
+        Some(repo) => {
+            let (root, spec) = resolve(&config, &repo)?;
+            Metadata::load(&root)?.tags(&spec)
+        }
+        None => {
+            let mut tags = std::collections::BTreeSet::new();
+            for root in config.all_roots() {
+                tags.extend(Metadata::load(&root.path)?.all_tags());
+            }
+            tags.into_iter().collect()
+        }
+    };
+    for tag in tags {
+        println!("{tag}");
+    }
+    Ok(())
+}
+
+/// Returns the root holding `repo` and its `host/owner/repo`.
+pub(super) fn resolve(config: &Config, repo: &str) -> Result<(PathBuf, String)> {
+    let Some(repo_path) = find_repository_path(config, repo)? else {
+        return Err(exit::not_found(false, not_found_message(config, repo)?));
+    };
+    let entry = RepoEntry::from_path(&repo_path)
+        .ok_or_else(|| anyhow!("Not a repository directory: {}", repo_path.display()))?;
+    let root = entry
+        .root()
+        .ok_or_else(|| anyhow!("Not a repository directory: {}", repo_path.display()))?;
+    Ok((root.to_path_buf(), entry.spec()))
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_add_remove_list() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(temp_dir.path().join("github.com/user/repo/.git")).unwrap();
+        let config = Config {
+            root: temp_dir.path().to_path_buf(),
+            ..Default::default()
+        };
+        let tags = |tags: &[&str]| tags.iter().map(|tag| tag.to_string()).collect();
+
+        add(
+            config.clone(),
+            "user/repo".to_string(),
+            tags(&["work", "oss"]),
+        )
+        .unwrap();
+        let metadata = Metadata::load(temp_dir.path()).unwrap();
+        assert_eq!(metadata.tags("github.com/user/repo"), ["oss", "work"]);
+
+        remove(
+            config.clone(),
+            "user/repo".to_string(),
+            tags(&["oss", "none"]),
+        )
+        .unwrap();
+        let metadata = Metadata::load(temp_dir.path()).unwrap();
+        assert_eq!(metadata.tags("github.com/user/repo"), ["work"]);
+
+        assert!(list(config.clone(), Some("user/repo".to_string())).is_ok());
+        assert!(list(config.clone(), None).is_ok());
+        assert!(add(config.clone(), "user/repo".to_string(), tags(&["a b"])).is_err());
+        assert!(add(config, "user/missing".to_string(), tags(&["work"])).is_err());
+    }
+}
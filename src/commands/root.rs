@@ -1,6 +1,86 @@
-use anyhow::Result;
+use super::repo::switch::{find_repository_path, not_found_message};
+use crate::{config::Config, exit, output::Lines, paths, scan::RepoEntry};
+use anyhow::{Result, anyhow};
+use std::path::{Path, PathBuf};
 
-pub fn execute() -> Result<()> {
-    println!("Root command");
+/// Prints every root, or only the one containing `repo` when given.
+pub fn execute(config: Config, repo: Option<String>, create: bool, print0: bool) -> Result<()> {
+    let mut lines = Lines::stdout(print0);
+    if let Some(repo) = repo {
+        return lines.write(repo_root(&config, &repo)?.display());
+    }
+    for root in config.all_roots() {
+        if create {
+            paths::ensure_root(&root.path)?;
+        }
+        lines.write(root.path.display())?;
+    }
     Ok(())
 }
+
+/// Returns the root `repo` was found in.
+fn repo_root(config: &Config, repo: &str) -> Result<PathBuf> {
+    let Some(repo_path) = find_repository_path(config, repo)? else {
+        return Err(exit::not_found(false, not_found_message(config, repo)?));
+    };
+    RepoEntry::from_path(&repo_path)
+        .and_then(|entry| entry.root().map(Path::to_path_buf))
+        .ok_or_else(|| anyhow!("Not a repository directory: {}", repo_path.display()))
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use crate::{config::Root, test_utils::create_managed_repository};
+
+    #[test]
+    fn test_execute_with_multiple_roots() {
+        let config = Config {
+            root: PathBuf::from("/personal"),
+            roots: vec![Root {
+                name: "work".to_string(),
+                path: PathBuf::from("/work/repos"),
+            }],
+            ..Default::default()
+        };
+
+        let result = execute(config, None, false, false);
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_execute_creates_roots() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config = Config {
+            root: temp_dir.path().join("personal"),
+            roots: vec![Root {
+                name: "work".to_string(),
+                path: temp_dir.path().join("work/repos"),
+            }],
+            ..Default::default()
+        };
+
+        execute(config, None, true, true).unwrap();
+        assert!(temp_dir.path().join("personal").is_dir());
+        assert!(temp_dir.path().join("work/repos").is_dir());
+    }
+
+    #[test]
+    fn test_execute_with_repo() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let work = temp_dir.path().join("work");
+        create_managed_repository(&work.join("github.com/user/repo"));
+        let config = Config {
+            root: temp_dir.path().join("personal"),
+            roots: vec![Root {
+                name: "work".to_string(),
+                path: work.clone(),
+            }],
+            ..Default::default()
+        };
+
+        assert_eq!(repo_root(&config, "user/repo").unwrap(), work);
+        assert!(execute(config, Some("user/missing".to_string()), false, false).is_err());
+    }
+}
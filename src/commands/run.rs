@@ -0,0 +1,229 @@
+use crate::{
+    config::Config,
+    metadata,
+    search::{self, Target, WorktreeFilter},
+    status,
+    style::{Color, Style},
+};
+use anyhow::{Result, anyhow};
+use std::io::{BufRead, BufReader, Read};
+use std::process::{Command, ExitStatus, Stdio};
+
+/// How the command ended in one repository.
+#[derive(Debug)]
+enum Outcome {
+    Exited(ExitStatus),
+    /// The command could not be started.
+    Failed(String),
+}
+
+impl Outcome {
+    fn success(&self) -> bool {
+        matches!(self, Self::Exited(status) if status.success())
+    }
+}
+
+/// Runs `command` in the default worktree of every repository matching
+/// `filter` and tagged `tag`, up to `jobs` at once.
+pub fn execute(
+    config: Config,
+    filter: Option<String>,
+    tag: Option<String>,
+    jobs: Option<usize>,
+    command: Vec<String>,
+) -> Result<()> {
+    let mut targets = search::targets(&config, filter.as_deref(), &WorktreeFilter::Default)?;
+    if let Some(tag) = &tag {
+        let tagged = metadata::tagged(&config, tag)?;
+        targets.retain(|target| {
+            target
+                .worktree
+                .parent()
+                .is_some_and(|repo| tagged.contains(repo))
+        });
+    }
+    run_all(&config, &targets, jobs, &command)
+}
+
+/// Runs `command` in the worktree of each of `targets`, up to `jobs` at
+/// once, prefixing each output line with the repository, then prints which
+/// repositories failed.
+pub(crate) fn run_all(
+    config: &Config,
+    targets: &[Target],
+    jobs: Option<usize>,
+    command: &[String],
+) -> Result<()> {
+    let (program, args) = command
+        .split_first()
+        .ok_or_else(|| anyhow!("No command given"))?;
+    let display = command.join(" ");
+
+    if config.dry_run {
+        for target in targets {
+            println!("Would run `{display}` in {}", target.worktree.display());
+        }
+        return Ok(());
+    }
+
+    let style = Style::stdout(config);
+    let width = targets
+        .iter()
+        .map(|target| label(target).len())
+        .max()
+        .unwrap_or(0);
+    let outcomes = status::parallel_map(targets, jobs, |target| {
+        let prefix = style.paint(Color::Dim, format!("{:<width$} |", label(target)));
+        run_in(target, program, args, &prefix)
+    });
+
+    let failed = targets
+        .iter()
+        .zip(&outcomes)
+        .filter(|(_, outcome)| !outcome.success())
+        .collect::<Vec<_>>();
+    eprintln!(
+        "{} succeeded, {} failed",
+        targets.len() - failed.len(),
+        failed.len()
+    );
+    for (target, outcome) in &failed {
+        let reason = match outcome {
+            Outcome::Exited(status) => status.to_string(),
+            Outcome::Failed(error) => error.clone(),
+        };
+        eprintln!("  {}: {reason}", style.paint(Color::Red, label(target)));
+    }
+
+    if !failed.is_empty() {
+        return Err(anyhow!(
+            "`{display}` failed in {} of {} repositories",
+            failed.len(),
+            targets.len()
+        ));
+    }
+    Ok(())
+}
+
+/// Returns `owner/repo`, which labels a repository's output lines.
+fn label(target: &Target) -> &str {
+    let mut slashes = target.repo.match_indices('/');
+    match slashes.next() {
+        Some((index, _)) => &target.repo[index + 1..],
+        None => &target.repo,
+    }
+}
+
+/// Runs the command in the worktree of `target`, streaming its stdout and
+/// stderr line by line behind `prefix`.
+fn run_in(target: &Target, program: &str, args: &[String], prefix: &str) -> Outcome {
+    let child = Command::new(program)
+        .args(args)
+        .current_dir(&target.worktree)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn();
+    let mut child = match child {
+        Ok(child) => child,
+        Err(error) => return Outcome::Failed(format!("{program}: {error}")),
+    };
+
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+    std::thread::scope(|scope| {
+        if let Some(stderr) = stderr {
+            scope.spawn(|| stream(stderr, |line| eprintln!("{prefix} {line}")));
+        }
+        if let Some(stdout) = stdout {
+            stream(stdout, |line| println!("{prefix} {line}"));
+        }
+    });
+
+    match child.wait() {
+        Ok(status) => Outcome::Exited(status),
+        Err(error) => Outcome::Failed(error.to_string()),
+    }
+}
+
+fn stream(output: impl Read, print: impl Fn(&str)) {
+    for line in BufReader::new(output).split(b'\n').map_while(Result::ok) {
+        print(String::from_utf8_lossy(&line).trim_end_matches('\r'));
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn target(worktree: &std::path::Path) -> Target {
+        Target {
+            repo: "github.com/user/repo".to_string(),
+            worktree: worktree.to_path_buf(),
+        }
+    }
+
+    #[test]
+    fn test_label() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        assert_eq!(label(&target(temp_dir.path())), "user/repo");
+    }
+
+    #[test]
+    fn test_run_in() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        fs::write(temp_dir.path().join("marker"), "").unwrap();
+        let target = target(temp_dir.path());
+
+        let outcome = run_in(&target, "ls", &["marker".to_string()], "user/repo |");
+        assert!(outcome.success());
+
+        let outcome = run_in(&target, "ls", &["missing".to_string()], "user/repo |");
+        assert!(matches!(outcome, Outcome::Exited(status) if !status.success()));
+
+        let outcome = run_in(&target, "neoghq-no-such-program", &[], "user/repo |");
+        assert!(matches!(outcome, Outcome::Failed(_)));
+    }
+
+    #[test]
+    fn test_execute() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        for name in ["a", "b"] {
+            let repo_path = temp_dir.path().join("github.com/user").join(name);
+            fs::create_dir_all(repo_path.join(".git")).unwrap();
+            fs::create_dir_all(repo_path.join("main")).unwrap();
+        }
+        fs::write(temp_dir.path().join("github.com/user/a/main/marker"), "").unwrap();
+        let config = Config {
+            root: temp_dir.path().to_path_buf(),
+            ..Default::default()
+        };
+        let command = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect();
+
+        assert!(execute(config.clone(), None, None, Some(2), command(&["ls"])).is_ok());
+        assert!(
+            execute(
+                config.clone(),
+                Some("user/a".to_string()),
+                None,
+                None,
+                command(&["ls", "marker"])
+            )
+            .is_ok()
+        );
+        assert!(execute(config.clone(), None, None, None, command(&["ls", "marker"])).is_err());
+        assert!(execute(config.clone(), None, None, None, Vec::new()).is_err());
+
+        // Only tagged repositories: none are, so nothing fails
+        let tag = Some("work".to_string());
+        assert!(execute(config.clone(), None, tag, None, command(&["ls", "marker"])).is_ok());
+
+        let dry_run = Config {
+            dry_run: true,
+            ..config
+        };
+        assert!(execute(dry_run, None, None, None, command(&["false"])).is_ok());
+    }
+}
@@ -0,0 +1,305 @@
+use crate::paths;
+use anyhow::{Context, Result, anyhow, bail};
+use minisign_verify::{PublicKey, Signature};
+use serde::Deserialize;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use tracing::info;
+
+const LATEST_RELEASE_URL: &str = "https://api.github.com/repos/r4ai/neoghq/releases/latest";
+
+/// The minisign key release binaries are signed with. It's pinned here rather
+/// than fetched, so a compromised release can't ship its own key.
+const RELEASE_PUBLIC_KEY: &str = "RWQ8eMmRgKA5y5BABgXSb84XM9bN5rDeagS2PJY2fgRUReP52B7XVwE9";
+
+/// Set when building a package (`NEOGHQ_DISABLE_SELF_UPDATE=1 cargo build`)
+/// so that a packaged neoghq is only ever updated by its package manager.
+const DISABLED_AT_BUILD: Option<&str> = option_env!("NEOGHQ_DISABLE_SELF_UPDATE");
+
+#[derive(Debug, Deserialize)]
+struct Release {
+    tag_name: String,
+    assets: Vec<Asset>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Asset {
+    name: String,
+    browser_download_url: String,
+}
+
+/// Replaces the running binary with the latest GitHub release, or with
+/// `check` only prints the newer version. Each release carries a
+/// `neoghq-<arch>-<os>` binary and a `neoghq-<arch>-<os>.minisig` signature
+/// made with [`RELEASE_PUBLIC_KEY`].
+pub fn execute(check: bool, force: bool) -> Result<()> {
+    let current = env!("CARGO_PKG_VERSION");
+    let release: Release = serde_json::from_slice(&download(LATEST_RELEASE_URL)?)
+        .context("Failed to read the latest release")?;
+    let latest = release.tag_name.trim_start_matches('v');
+    if !is_newer(latest, current)? {
+        info!("neoghq {current} is up to date");
+        return Ok(());
+    }
+    if check {
+        println!("{latest}");
+        info!("neoghq {latest} is available (installed: {current})");
+        return Ok(());
+    }
+
+    let exe = paths::canonicalize(&std::env::current_exe()?)
+        .context("Failed to locate the running binary")?;
+    // --force only overrides the path heuristics, not a packager's choice
+    if let Some(hint) = managed_install(&exe)
+        && (DISABLED_AT_BUILD.is_some() || !force)
+    {
+        bail!(
+            "{} looks like a package-manager install; {hint}",
+            exe.display()
+        );
+    }
+
+    let name = asset_name();
+    let find = |name: &str| {
+        release
+            .assets
+            .iter()
+            .find(|asset| asset.name == name)
+            .ok_or_else(|| anyhow!("Release {} has no {name}", release.tag_name))
+    };
+    let binary_asset = find(&name)?;
+    let signature_asset = find(&format!("{name}.minisig"))?;
+    let signature = String::from_utf8(download(&signature_asset.browser_download_url)?)
+        .context("The signature file is not text")?;
+    info!("Downloading neoghq {latest}");
+    let binary = download(&binary_asset.browser_download_url)?;
+    verify_signature(&binary, &signature, RELEASE_PUBLIC_KEY, &name)?;
+    replace_binary(&exe, &binary)?;
+    info!("Updated neoghq {current} -> {latest}");
+    Ok(())
+}
+
+/// Returns how to update neoghq when `exe` belongs to a package manager or
+/// self-update was disabled when it was built.
+fn managed_install(exe: &Path) -> Option<&'static str> {
+    if DISABLED_AT_BUILD.is_some() {
+        return Some("update it with the package manager that installed it");
+    }
+    let path = exe.to_string_lossy().replace('\\', "/");
+    if path.starts_with("/nix/store/") {
+        Some("update it with nix")
+    } else if path.contains("/Cellar/") || path.starts_with("/opt/homebrew/") {
+        Some("update it with `brew upgrade neoghq`")
+    } else if path.contains("/.cargo/bin/") {
+        Some("update it with `cargo install`")
+    } else if exe.starts_with("/usr/bin") || exe.starts_with("/bin") {
+        Some("update it with your system package manager")
+    } else {
+        None
+    }
+}
+
+/// The release asset built for this platform, e.g. `neoghq-x86_64-linux`.
+fn asset_name() -> String {
+    format!("neoghq-{}-{}", std::env::consts::ARCH, std::env::consts::OS)
+}
+
+/// Compares `major.minor.patch` versions, ignoring pre-release suffixes.
+fn is_newer(latest: &str, current: &str) -> Result<bool> {
+    let parse = |version: &str| {
+        version
+            .split(['-', '+'])
+            .next()
+            .unwrap_or_default()
+            .split('.')
+            .map(|part| part.parse::<u64>())
+            .collect::<Result<Vec<_>, _>>()
+            .with_context(|| format!("Invalid version {version}"))
+    };
+    Ok(parse(latest)? > parse(current)?)
+}
+
+/// Checks `binary` against a minisign `signature` made with `public_key`. The
+/// signed trusted comment must name `asset`, so a validly signed binary for
+/// another platform can't be passed off as this one.
+fn verify_signature(binary: &[u8], signature: &str, public_key: &str, asset: &str) -> Result<()> {
+    let public_key = PublicKey::from_base64(public_key).context("Invalid release public key")?;
+    let signature = Signature::decode(signature).context("Invalid release signature")?;
+    public_key
+        .verify(binary, &signature, false)
+        .context("The downloaded binary is not signed with the release key")?;
+    let file = format!("file:{asset}");
+    if !signature
+        .trusted_comment()
+        .split('\t')
+        .any(|field| field == file)
+    {
+        bail!("The release signature is not for {asset}");
+    }
+    Ok(())
+}
+
+/// Writes `binary` next to `exe`, checks that it runs, and renames it over
+/// `exe`, so an interrupted update never leaves a partial binary behind.
+fn replace_binary(exe: &Path, binary: &[u8]) -> Result<()> {
+    let file_name = exe
+        .file_name()
+        .ok_or_else(|| anyhow!("Invalid binary path {}", exe.display()))?;
+    // Keeps the extension, which Windows needs to run the file
+    let new = exe.with_file_name(format!(
+        ".new-{}-{}",
+        std::process::id(),
+        file_name.to_string_lossy()
+    ));
+    let result = (|| {
+        std::fs::write(&new, binary)
+            .with_context(|| format!("Failed to write {}", new.display()))?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&new, std::fs::Permissions::from_mode(0o755))?;
+        }
+        let status = Command::new(&new)
+            .arg("--version")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .context("Failed to run the downloaded binary")?;
+        if !status.success() {
+            bail!("The downloaded binary failed to run ({status})");
+        }
+        #[cfg(windows)]
+        {
+            // Windows can't replace a running binary, but it can rename it
+            let old = exe.with_file_name(format!(".old-{}", file_name.to_string_lossy()));
+            let _ = std::fs::remove_file(&old);
+            std::fs::rename(exe, &old)
+                .with_context(|| format!("Failed to move {} aside", exe.display()))?;
+        }
+        std::fs::rename(&new, exe).with_context(|| format!("Failed to replace {}", exe.display()))
+    })();
+    if result.is_err() {
+        let _ = std::fs::remove_file(&new);
+    }
+    result
+}
+
+fn download(url: &str) -> Result<Vec<u8>> {
+    let output = Command::new("curl")
+        .args(["--fail", "--silent", "--show-error", "--location", url])
+        .output()
+        .context("Failed to run curl")?;
+    if !output.status.success() {
+        bail!(
+            "Failed to download {url}: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(output.stdout)
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_newer() {
+        assert!(is_newer("0.2.0", "0.1.9").unwrap());
+        assert!(is_newer("1.0.0", "0.10.0").unwrap());
+        assert!(is_newer("0.10.0", "0.9.0").unwrap());
+        assert!(!is_newer("0.1.0", "0.1.0").unwrap());
+        assert!(!is_newer("0.1.0-rc.1", "0.1.0").unwrap());
+        assert!(is_newer("v1", "0.1.0").is_err());
+    }
+
+    #[test]
+    fn test_managed_install() {
+        if DISABLED_AT_BUILD.is_some() {
+            return;
+        }
+        assert!(managed_install(Path::new("/nix/store/abc-neoghq/bin/neoghq")).is_some());
+        assert!(
+            managed_install(Path::new("/opt/homebrew/Cellar/neoghq/0.1.0/bin/neoghq")).is_some()
+        );
+        assert!(managed_install(Path::new("/home/me/.cargo/bin/neoghq")).is_some());
+        assert!(managed_install(Path::new(r"C:\Users\me\.cargo\bin\neoghq.exe")).is_some());
+        assert!(managed_install(Path::new("/usr/bin/neoghq")).is_some());
+        assert!(managed_install(Path::new("/usr/local/bin/neoghq")).is_none());
+        assert!(managed_install(Path::new("/home/me/.local/bin/neoghq")).is_none());
+    }
+
+    #[test]
+    fn test_verify_signature() {
+        // "hello\n" signed with a throwaway key
+        let public_key = "RWTDMNBJsQyL+CpCqZ453w6QoMkojcIavuDNnmW1U3TlG31+Kd2Mn/RH";
+        let signature = "untrusted comment: signature from minisign secret key
+RUTDMNBJsQyL+C5fw3QB6JkeI1xXHBDvySE7vrpFU0/5CoqZQ7wgIGTffEGROr8cXkIaXmrIWal2IiFf/bgMJY9Wo54nNisdBAE=
+trusted comment: timestamp:1760572800\tfile:neoghq-x86_64-linux
+EtH+uyPcVVeDUrvpW/TsEBpzx95hFqHbYYF3msab9GjAHAU/SDEtbT8F1r2Re0sa+RbiB6xAZEgg7iMcRcPuAw==
+";
+        verify_signature(b"hello\n", signature, public_key, "neoghq-x86_64-linux").unwrap();
+        assert!(
+            verify_signature(b"tampered\n", signature, public_key, "neoghq-x86_64-linux").is_err()
+        );
+        assert!(
+            verify_signature(b"hello\n", signature, public_key, "neoghq-aarch64-macos").is_err()
+        );
+        // Signed, but not with the release key
+        assert!(
+            verify_signature(
+                b"hello\n",
+                signature,
+                RELEASE_PUBLIC_KEY,
+                "neoghq-x86_64-linux"
+            )
+            .is_err()
+        );
+        assert!(verify_signature(b"hello\n", "", public_key, "neoghq-x86_64-linux").is_err());
+    }
+
+    #[test]
+    fn test_release_public_key() {
+        PublicKey::from_base64(RELEASE_PUBLIC_KEY).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_replace_binary() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let exe = temp_dir.path().join("neoghq");
+        std::fs::write(&exe, "old").unwrap();
+
+        assert!(replace_binary(&exe, b"#!/bin/sh\nexit 1\n").is_err());
+        assert_eq!(std::fs::read_to_string(&exe).unwrap(), "old");
+        assert_eq!(std::fs::read_dir(temp_dir.path()).unwrap().count(), 1);
+
+        replace_binary(&exe, b"#!/bin/sh\necho neoghq 9.9.9\n").unwrap();
+        let output = Command::new(&exe).arg("--version").output().unwrap();
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "neoghq 9.9.9\n");
+        assert_eq!(std::fs::read_dir(temp_dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_download_file_url() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file = temp_dir.path().join("release.json");
+        std::fs::write(
+            &file,
+            r#"{"tag_name":"v0.2.0","assets":[{"name":"neoghq-x86_64-linux","browser_download_url":"https://example.com/a"}]}"#,
+        )
+        .unwrap();
+
+        let bytes = download(&format!("file://{}", file.display())).unwrap();
+        let release: Release = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(release.tag_name, "v0.2.0");
+        assert_eq!(release.assets[0].name, "neoghq-x86_64-linux");
+        assert!(
+            download(&format!(
+                "file://{}",
+                temp_dir.path().join("missing").display()
+            ))
+            .is_err()
+        );
+    }
+}
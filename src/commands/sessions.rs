@@ -0,0 +1,137 @@
+use crate::{
+    cli::OutputFormat,
+    config::Config,
+    output, paths,
+    scan::RepoEntry,
+    tmux::{self, Session},
+};
+use anyhow::Result;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// A tmux session started in a worktree under one of the roots.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+struct WorktreeSession {
+    #[serde(flatten)]
+    session: Session,
+    /// `host/owner/repo`.
+    repo: String,
+    /// The worktree directory within the repository.
+    worktree: String,
+}
+
+/// Prints the tmux sessions whose start directory is a worktree, such as
+/// those `worktree open --tmux` creates.
+pub fn execute(config: Config, json: bool) -> Result<()> {
+    let roots = config
+        .all_roots()
+        .into_iter()
+        .map(|root| root.path)
+        .collect::<Vec<_>>();
+    let sessions = worktree_sessions(&roots, tmux::sessions()?);
+    if json || config.output == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&sessions)?);
+        return Ok(());
+    }
+
+    let rows = sessions
+        .iter()
+        .map(|session| {
+            vec![
+                session.session.name.clone(),
+                session.repo.clone(),
+                session.worktree.clone(),
+                session.session.windows.to_string(),
+                if session.session.attached { "yes" } else { "" }.to_string(),
+            ]
+        })
+        .collect::<Vec<_>>();
+    for line in output::table(
+        &["SESSION", "REPO", "WORKTREE", "WINDOWS", "ATTACHED"],
+        &rows,
+    ) {
+        println!("{line}");
+    }
+    Ok(())
+}
+
+fn worktree_sessions(roots: &[PathBuf], sessions: Vec<Session>) -> Vec<WorktreeSession> {
+    sessions
+        .into_iter()
+        .filter_map(|session| {
+            let (repo, worktree) = roots.iter().find_map(|root| locate(root, &session.path))?;
+            Some(WorktreeSession {
+                session,
+                repo,
+                worktree,
+            })
+        })
+        .collect()
+}
+
+/// Splits `path` into the repository under `root` and the worktree within
+/// it, if it's that deep.
+fn locate(root: &Path, path: &Path) -> Option<(String, String)> {
+    let relative = path.strip_prefix(root).ok()?;
+    let mut components = relative.components();
+    let repo_dir = root.join(components.by_ref().take(3).collect::<PathBuf>());
+    let worktree = components.as_path();
+    if worktree.as_os_str().is_empty() {
+        return None;
+    }
+    Some((
+        RepoEntry::from_path(&repo_dir)?.spec(),
+        paths::to_slash(worktree),
+    ))
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+
+    fn session(name: &str, path: &str) -> Session {
+        Session {
+            name: name.to_string(),
+            path: PathBuf::from(path),
+            attached: false,
+            windows: 1,
+        }
+    }
+
+    #[test]
+    fn test_worktree_sessions() {
+        let roots = [PathBuf::from("/repos"), PathBuf::from("/work")];
+        let sessions = worktree_sessions(
+            &roots,
+            vec![
+                session("neoghq@main", "/repos/github.com/r4ai/neoghq/main"),
+                session("api@feature/x", "/work/gitlab.com/team/api/feature/x"),
+                session("repo", "/repos/github.com/r4ai/neoghq"),
+                session("scratch", "/tmp"),
+            ],
+        );
+
+        assert_eq!(
+            sessions
+                .iter()
+                .map(|session| (session.repo.as_str(), session.worktree.as_str()))
+                .collect::<Vec<_>>(),
+            [
+                ("github.com/r4ai/neoghq", "main"),
+                ("gitlab.com/team/api", "feature/x"),
+            ]
+        );
+        assert_eq!(
+            serde_json::to_value(&sessions[0]).unwrap(),
+            serde_json::json!({
+                "name": "neoghq@main",
+                "path": "/repos/github.com/r4ai/neoghq/main",
+                "attached": false,
+                "windows": 1,
+                "repo": "github.com/r4ai/neoghq",
+                "worktree": "main",
+            })
+        );
+    }
+}
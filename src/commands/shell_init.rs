@@ -0,0 +1,150 @@
+use crate::cli::InitShell;
+use anyhow::Result;
+
+const POSIX: &str = r#"# neoghq shell integration: eval "$(neoghq shell-init SHELL)"
+ncd() {
+    local dir
+    dir="$(command neoghq repo switch --no-create "$@")" && cd "$dir"
+}
+
+nwt() {
+    local dir
+    dir="$(command neoghq worktree switch --no-create "$@")" && cd "$dir"
+}
+"#;
+
+const FISH: &str = r#"# neoghq shell integration: neoghq shell-init fish | source
+function ncd --description 'cd into a neoghq repository'
+    set -l dir (command neoghq repo switch --no-create $argv); and cd $dir
+end
+
+function nwt --description 'cd into a worktree of the current repository'
+    set -l dir (command neoghq worktree switch --no-create $argv); and cd $dir
+end
+"#;
+
+const NUSHELL: &str = r#"# neoghq shell integration: neoghq shell-init nushell | save -f ~/.neoghq.nu
+# and add `source ~/.neoghq.nu` to config.nu
+def --env ncd [repo?: string] {
+    let args = if $repo == null { [] } else { [$repo] }
+    cd (^neoghq repo switch --no-create ...$args)
+}
+
+def --env nwt [branch?: string] {
+    let args = if $branch == null { [] } else { [$branch] }
+    cd (^neoghq worktree switch --no-create ...$args)
+}
+"#;
+
+// Alt-G picks a repository and Alt-W a worktree, in the fuzzy finder
+const BASH_KEY_BINDINGS: &str = r#"
+bind -x '"\eg": ncd'
+bind -x '"\ew": nwt'
+"#;
+
+const ZSH_KEY_BINDINGS: &str = r#"
+_neoghq_ncd_widget() { ncd </dev/tty; zle reset-prompt; }
+_neoghq_nwt_widget() { nwt </dev/tty; zle reset-prompt; }
+zle -N _neoghq_ncd_widget
+zle -N _neoghq_nwt_widget
+bindkey '\eg' _neoghq_ncd_widget
+bindkey '\ew' _neoghq_nwt_widget
+"#;
+
+const FISH_KEY_BINDINGS: &str = r#"
+bind \eg 'ncd; commandline -f repaint'
+bind \ew 'nwt; commandline -f repaint'
+"#;
+
+const NUSHELL_KEY_BINDINGS: &str = r#"
+$env.config.keybindings = ($env.config.keybindings | append [
+    {
+        name: neoghq_ncd
+        modifier: alt
+        keycode: char_g
+        mode: [emacs vi_normal vi_insert]
+        event: { send: executehostcommand, cmd: "ncd" }
+    }
+    {
+        name: neoghq_nwt
+        modifier: alt
+        keycode: char_w
+        mode: [emacs vi_normal vi_insert]
+        event: { send: executehostcommand, cmd: "nwt" }
+    }
+])
+"#;
+
+pub fn execute(shell: InitShell, key_bindings: bool) -> Result<()> {
+    print!("{}", script(shell, key_bindings));
+    Ok(())
+}
+
+/// Returns shell functions that `cd` into the directory `repo switch` and
+/// `worktree switch` print, since neoghq can't change its parent's
+/// directory itself. With `key_bindings`, Alt-G and Alt-W run them without
+/// an argument, opening the picker.
+fn script(shell: InitShell, key_bindings: bool) -> String {
+    let (functions, bindings) = match shell {
+        InitShell::Bash => (POSIX, BASH_KEY_BINDINGS),
+        InitShell::Zsh => (POSIX, ZSH_KEY_BINDINGS),
+        InitShell::Fish => (FISH, FISH_KEY_BINDINGS),
+        InitShell::Nushell => (NUSHELL, NUSHELL_KEY_BINDINGS),
+    };
+    if key_bindings {
+        format!("{functions}{bindings}")
+    } else {
+        functions.to_string()
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use std::process::Command;
+
+    #[test]
+    fn test_script_defines_functions() {
+        for shell in [
+            InitShell::Bash,
+            InitShell::Zsh,
+            InitShell::Fish,
+            InitShell::Nushell,
+        ] {
+            let functions = script(shell, false);
+            assert!(functions.contains("ncd"), "{shell:?}");
+            assert!(functions.contains("nwt"), "{shell:?}");
+            assert!(script(shell, true).starts_with(&functions), "{shell:?}");
+        }
+    }
+
+    #[test]
+    fn test_key_bindings_are_optional() {
+        assert!(script(InitShell::Bash, true).contains(r#"bind -x '"\eg": ncd'"#));
+        assert!(script(InitShell::Zsh, true).contains("bindkey '\\ew' _neoghq_nwt_widget"));
+        assert!(script(InitShell::Fish, true).contains("bind \\eg 'ncd;"));
+        assert!(script(InitShell::Nushell, true).contains("cmd: \"nwt\""));
+        for shell in [InitShell::Bash, InitShell::Zsh, InitShell::Fish] {
+            assert!(!script(shell, false).contains("bind"), "{shell:?}");
+        }
+    }
+
+    #[test]
+    fn test_nushell_arguments_are_optional() {
+        let script = script(InitShell::Nushell, false);
+        assert!(script.contains("def --env ncd [repo?: string]"));
+        assert!(script.contains("def --env nwt [branch?: string]"));
+    }
+
+    #[test]
+    fn test_posix_script_is_valid() {
+        for script in [POSIX.to_string(), script(InitShell::Bash, true)] {
+            let status = Command::new("sh")
+                .args(["-n", "-c", &script])
+                .status()
+                .unwrap();
+            assert!(status.success());
+        }
+    }
+}
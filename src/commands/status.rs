@@ -0,0 +1,341 @@
+use crate::{
+    cli::OutputFormat,
+    config::Config,
+    daemon::{self, CachedStatus},
+    metadata, redirect,
+    scan::{self, RepoEntry},
+    status::{self, WorktreeStatus},
+    style::{Color, Style},
+};
+use anyhow::Result;
+use git2::Repository;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use tracing::warn;
+
+/// A repository that needs attention, as printed by `status --json`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RepoHealth {
+    pub repo: String,
+    pub path: PathBuf,
+    /// Worktrees with uncommitted changes.
+    pub dirty: Vec<PathBuf>,
+    /// Branches with commits their upstream doesn't have.
+    pub unpushed: Vec<Unpushed>,
+    /// Branches whose upstream was deleted on the remote.
+    pub gone: Vec<String>,
+    /// Error of the last fetch, if it failed.
+    pub fetch_error: Option<String>,
+    /// URL `origin` redirected to at the last fetch, after the repository
+    /// was renamed or transferred.
+    pub moved_to: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Unpushed {
+    pub branch: String,
+    pub ahead: usize,
+}
+
+/// The problems `status` counts, in the order of [`RepoHealth::details`].
+const SECTIONS: [(&str, Color); 5] = [
+    ("with dirty worktrees", Color::Yellow),
+    ("with unpushed commits", Color::Yellow),
+    ("with gone upstreams", Color::Red),
+    ("with failed fetches", Color::Red),
+    ("with moved origins", Color::Yellow),
+];
+
+impl RepoHealth {
+    /// Returns the affected worktrees, branches and fetch error of each of
+    /// [`SECTIONS`].
+    fn details(&self) -> Vec<Vec<String>> {
+        let dirty = self
+            .dirty
+            .iter()
+            .map(|path| {
+                path.file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect();
+        let unpushed = self
+            .unpushed
+            .iter()
+            .map(|unpushed| format!("{} (ahead {})", unpushed.branch, unpushed.ahead))
+            .collect();
+        let fetch_error = self.fetch_error.iter().cloned().collect();
+        let moved_to = self.moved_to.iter().cloned().collect();
+        vec![dirty, unpushed, self.gone.clone(), fetch_error, moved_to]
+    }
+
+    fn is_healthy(&self) -> bool {
+        self.dirty.is_empty()
+            && self.unpushed.is_empty()
+            && self.gone.is_empty()
+            && self.fetch_error.is_none()
+            && self.moved_to.is_none()
+    }
+}
+
+/// The whole workspace, as printed by `status --json`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+struct Summary {
+    /// Number of repositories inspected.
+    repositories: usize,
+    /// Repositories that need attention.
+    attention: Vec<RepoHealth>,
+}
+
+/// Prints how many repositories matching `query` have dirty worktrees,
+/// unpushed commits, gone upstreams, a failed last fetch or a moved origin,
+/// and with `long` which ones.
+pub fn execute(
+    config: Config,
+    query: Option<String>,
+    long: bool,
+    jobs: Option<usize>,
+    json: bool,
+) -> Result<()> {
+    let entries = scan::scan_roots(&config.all_roots())?
+        .into_iter()
+        .filter(|entry| {
+            query
+                .as_deref()
+                .is_none_or(|query| entry.spec().contains(query))
+        })
+        .collect::<Vec<_>>();
+    let cached = daemon::cached_statuses(&config, &entries);
+    let summary = summarize(&entries, &cached, jobs);
+
+    if json || config.output == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&summary)?);
+        return Ok(());
+    }
+
+    let style = Style::stdout(&config);
+    println!("{} repositories", summary.repositories);
+    for (index, (label, color)) in SECTIONS.into_iter().enumerate() {
+        let affected = summary
+            .attention
+            .iter()
+            .filter_map(|health| {
+                let details = health.details().swap_remove(index);
+                (!details.is_empty()).then_some((health, details))
+            })
+            .collect::<Vec<_>>();
+        let count = format!("{:>4}", affected.len());
+        let count = if affected.is_empty() {
+            count
+        } else {
+            style.paint(color, &count)
+        };
+        println!("{count} {label}");
+        if long {
+            for (health, details) in affected {
+                println!("       {}: {}", health.repo, details.join(", "));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Inspects every repository of `entries` the daemon hasn't `cached` in
+/// parallel and keeps the ones that need attention. Repositories that can't
+/// be opened are skipped with a warning.
+fn summarize(
+    entries: &[RepoEntry],
+    cached: &HashMap<PathBuf, CachedStatus>,
+    jobs: Option<usize>,
+) -> Summary {
+    let attention = status::parallel_map(entries, jobs, |entry| {
+        if let Some(cached) = cached.get(&entry.path) {
+            return Some(cached.health.clone());
+        }
+        repo_health(entry)
+            .inspect_err(|error| warn!("Skipping {}: {error:#}", entry.path.display()))
+            .ok()
+    })
+    .into_iter()
+    .flatten()
+    .filter(|health| !health.is_healthy())
+    .collect();
+
+    Summary {
+        repositories: entries.len(),
+        attention,
+    }
+}
+
+/// Inspects the worktrees and last fetch of `entry`.
+pub fn repo_health(entry: &RepoEntry) -> Result<RepoHealth> {
+    let repo = Repository::open_bare(entry.bare_repository_path())?;
+    let mut health = RepoHealth {
+        repo: entry.spec(),
+        path: entry.path.clone(),
+        fetch_error: status::fetch_error(&entry.bare_repository_path()),
+        moved_to: redirect::recorded(&entry.bare_repository_path()),
+        ..Default::default()
+    };
+    let reviews = metadata::review_worktrees(entry);
+    for worktree in status::collect_worktree_statuses(&repo)? {
+        let WorktreeStatus {
+            path,
+            branch,
+            upstream_state,
+            ahead,
+            dirty,
+            ..
+        } = worktree;
+        if dirty && !status::is_review_worktree(&reviews, &path) {
+            health.dirty.push(path);
+        }
+        let Some(branch) = branch else {
+            continue;
+        };
+        if ahead > 0 {
+            health.unpushed.push(Unpushed {
+                branch: branch.clone(),
+                ahead,
+            });
+        }
+        if upstream_state == Some("gone") {
+            health.gone.push(branch);
+        }
+    }
+    Ok(health)
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use crate::test_utils::{commit_all, create_managed_repository};
+
+    fn create_entry(root: &std::path::Path, name: &str) -> (RepoEntry, Repository) {
+        let path = root.join("github.com/user").join(name);
+        let repo = create_managed_repository(&path);
+        repo.find_branch("main", git2::BranchType::Local)
+            .unwrap()
+            .set_upstream(Some("origin/main"))
+            .unwrap();
+        (RepoEntry::from_path(&path).unwrap(), repo)
+    }
+
+    #[test]
+    fn test_summarize() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let (clean, _) = create_entry(temp_dir.path(), "clean");
+        let (dirty, _) = create_entry(temp_dir.path(), "dirty");
+        std::fs::write(dirty.path.join("main/NEW"), "new").unwrap();
+        let (ahead, _) = create_entry(temp_dir.path(), "ahead");
+        std::fs::write(ahead.path.join("main/NEW"), "new").unwrap();
+        commit_all(
+            &Repository::open(ahead.path.join("main")).unwrap(),
+            "Add NEW",
+        );
+        let (failed, _) = create_entry(temp_dir.path(), "failed");
+        status::record_fetch(
+            &failed.bare_repository_path(),
+            &Err(anyhow::anyhow!("could not resolve host")),
+        )
+        .unwrap();
+        let (moved, _) = create_entry(temp_dir.path(), "moved");
+        redirect::record(
+            &moved.bare_repository_path(),
+            Some("https://github.com/org/moved.git"),
+        )
+        .unwrap();
+
+        let summary = summarize(
+            &[clean, dirty, ahead, failed, moved],
+            &HashMap::new(),
+            Some(2),
+        );
+        assert_eq!(summary.repositories, 5);
+        let repos = summary
+            .attention
+            .iter()
+            .map(|health| health.repo.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            repos,
+            [
+                "github.com/user/dirty",
+                "github.com/user/ahead",
+                "github.com/user/failed",
+                "github.com/user/moved"
+            ]
+        );
+        assert_eq!(summary.attention[0].dirty.len(), 1);
+        assert_eq!(
+            summary.attention[1].unpushed,
+            [Unpushed {
+                branch: "main".to_string(),
+                ahead: 1
+            }]
+        );
+        assert_eq!(
+            summary.attention[2].fetch_error.as_deref(),
+            Some("could not resolve host")
+        );
+        assert_eq!(
+            summary.attention[3].moved_to.as_deref(),
+            Some("https://github.com/org/moved.git")
+        );
+    }
+
+    #[test]
+    fn test_summarize_uses_cached_health() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let (entry, _) = create_entry(temp_dir.path(), "repo");
+        let health = RepoHealth {
+            repo: entry.spec(),
+            path: entry.path.clone(),
+            gone: vec!["main".to_string()],
+            ..Default::default()
+        };
+        let cached = HashMap::from([(
+            entry.path.clone(),
+            CachedStatus {
+                status: status::collect_repo_status(&entry).unwrap(),
+                health: health.clone(),
+                size: 0,
+                updated: std::time::SystemTime::now(),
+            },
+        )]);
+
+        let summary = summarize(&[entry], &cached, None);
+        assert_eq!(summary.attention, [health]);
+    }
+
+    #[test]
+    fn test_repo_health_gone_upstream() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let (entry, repo) = create_entry(temp_dir.path(), "repo");
+        repo.find_reference("refs/remotes/origin/main")
+            .unwrap()
+            .delete()
+            .unwrap();
+
+        let health = repo_health(&entry).unwrap();
+        assert_eq!(health.gone, ["main"]);
+        assert!(!health.is_healthy());
+    }
+
+    #[test]
+    fn test_execute() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let (entry, _) = create_entry(temp_dir.path(), "repo");
+        std::fs::write(entry.path.join("main/NEW"), "new").unwrap();
+        let config = Config {
+            root: temp_dir.path().to_path_buf(),
+            ..Default::default()
+        };
+
+        assert!(execute(config.clone(), None, true, None, false).is_ok());
+        assert!(execute(config, Some("repo".to_string()), false, Some(1), true).is_ok());
+    }
+}
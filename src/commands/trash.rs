@@ -0,0 +1,131 @@
+use crate::{
+    cli::OutputFormat,
+    config::Config,
+    dry_run, exit, output, status,
+    trash::{self, TrashEntry},
+};
+use anyhow::Result;
+use std::time::{Duration, UNIX_EPOCH};
+use tracing::info;
+
+/// Prints the removed repositories and worktrees in the trash, most recent
+/// first.
+pub fn list(config: Config, json: bool) -> Result<()> {
+    let entries = trash::list(&config)?;
+    if json || config.output == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+        return Ok(());
+    }
+
+    let rows = entries
+        .iter()
+        .map(|entry| {
+            vec![
+                entry.id.clone(),
+                format!("{:?}", entry.kind).to_lowercase(),
+                status::format_timestamp(UNIX_EPOCH + Duration::from_secs(entry.removed_at)),
+                entry.original.display().to_string(),
+            ]
+        })
+        .collect::<Vec<_>>();
+    for line in output::table(&["ID", "KIND", "REMOVED", "PATH"], &rows) {
+        println!("{line}");
+    }
+    Ok(())
+}
+
+/// Restores the trash entry `id`, or the most recent removal without one,
+/// and prints where it went back to.
+pub fn restore(config: Config, id: Option<String>) -> Result<()> {
+    let entries = trash::list(&config)?;
+    let entry = match &id {
+        Some(id) => entries.iter().find(|entry| entry.id == *id),
+        None => entries.first(),
+    }
+    .ok_or_else(|| {
+        exit::not_found(
+            false,
+            match &id {
+                Some(id) => format!("No trash entry {id}"),
+                None => "The trash is empty".to_string(),
+            },
+        )
+    })?;
+
+    dry_run::apply(
+        &config,
+        format!("restore {}", entry.original.display()),
+        || {
+            trash::restore(entry)?;
+            info!("Restored {}", entry.original.display());
+            println!("{}", entry.original.display());
+            Ok(())
+        },
+    )
+}
+
+/// Deletes everything in the trash for good.
+pub fn empty(config: Config) -> Result<()> {
+    let entries = trash::list(&config)?;
+    for entry in &entries {
+        dry_run::apply(&config, describe_deletion(entry), || trash::delete(entry))?;
+    }
+    info!("Deleted {} trash entries", entries.len());
+    Ok(())
+}
+
+fn describe_deletion(entry: &TrashEntry) -> String {
+    format!(
+        "permanently delete {} ({})",
+        entry.original.display(),
+        entry.id
+    )
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use crate::trash::TrashKind;
+    use std::fs;
+
+    #[test]
+    fn test_list_restore_empty() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        let config = Config {
+            root: root.to_path_buf(),
+            trash_retention_days: 30,
+            ..Default::default()
+        };
+        let repo = root.join("github.com/user/repo");
+        let other = root.join("github.com/user/other");
+        fs::create_dir_all(&repo).unwrap();
+        fs::create_dir_all(&other).unwrap();
+
+        assert!(restore(config.clone(), None).is_err());
+        let first = trash::put(&config, TrashKind::Repository, &repo, None)
+            .unwrap()
+            .unwrap();
+        trash::put(&config, TrashKind::Repository, &other, None).unwrap();
+        assert!(list(config.clone(), false).is_ok());
+        assert!(list(config.clone(), true).is_ok());
+
+        restore(config.clone(), None).unwrap();
+        assert!(other.exists());
+        assert!(restore(config.clone(), Some("missing".to_string())).is_err());
+        restore(config.clone(), Some(first.id)).unwrap();
+        assert!(repo.exists());
+
+        trash::put(&config, TrashKind::Repository, &repo, None).unwrap();
+        let dry_run = Config {
+            dry_run: true,
+            ..config.clone()
+        };
+        empty(dry_run).unwrap();
+        assert_eq!(trash::list(&config).unwrap().len(), 1);
+        empty(config.clone()).unwrap();
+        assert!(trash::list(&config).unwrap().is_empty());
+        assert!(!repo.exists());
+    }
+}
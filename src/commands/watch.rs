@@ -0,0 +1,70 @@
+use crate::{
+    config::Config,
+    scan::RepoEntry,
+    watch::{Change, RootWatcher},
+};
+use anyhow::Result;
+use std::path::Path;
+use std::time::Duration;
+use tracing::info;
+
+/// Watches every root in the foreground, updating their indexes and
+/// reporting repositories and worktrees added or removed.
+pub fn execute(config: Config) -> Result<()> {
+    let roots = config
+        .all_roots()
+        .into_iter()
+        .map(|root| root.path)
+        .collect::<Vec<_>>();
+    let mut watcher = RootWatcher::new(roots)?;
+    info!(
+        "Watching {} directories; press Ctrl-C to stop",
+        watcher.watched()
+    );
+    loop {
+        for change in watcher.poll(Duration::from_secs(3600))? {
+            report(&change);
+        }
+    }
+}
+
+fn report(change: &Change) {
+    match change {
+        Change::RepoAdded(path) => {
+            info!("Found {}", describe(path));
+            if RepoEntry::from_path(path).is_some_and(|entry| entry.is_plain_clone()) {
+                info!("It's a plain clone; `neoghq repo adopt --in-place` converts it");
+            }
+        }
+        Change::RepoRemoved(path) => info!("{} is gone", describe(path)),
+        Change::WorktreesChanged(path) => info!("Worktrees of {} changed", describe(path)),
+    }
+}
+
+fn describe(path: &Path) -> String {
+    RepoEntry::from_path(path).map_or_else(|| path.display().to_string(), |entry| entry.spec())
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_managed_repository;
+
+    #[test]
+    fn test_is_plain_clone() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let managed = temp_dir.path().join("github.com/user/managed");
+        create_managed_repository(&managed);
+        let plain = temp_dir.path().join("github.com/user/plain");
+        git2::Repository::init(&plain).unwrap();
+        let is_plain_clone = |path: &Path| RepoEntry::from_path(path).unwrap().is_plain_clone();
+
+        assert!(!is_plain_clone(&managed));
+        assert!(is_plain_clone(&plain));
+        assert!(!is_plain_clone(
+            &temp_dir.path().join("github.com/user/missing")
+        ));
+        assert_eq!(describe(&plain), "github.com/user/plain");
+    }
+}
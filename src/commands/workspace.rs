@@ -0,0 +1,427 @@
+use super::{
+    repo::switch::{find_repository_path, not_found_message},
+    run,
+};
+use crate::{
+    config::Config,
+    dry_run, editor, exit,
+    metadata::{Metadata, WorkspaceMember},
+    paths,
+    scan::RepoEntry,
+    search::{self, Target, WorktreeFilter},
+    status,
+};
+use anyhow::{Result, anyhow, bail};
+use serde_json::json;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// Creates the workspace `name` from `repos`, each `owner/repo` or
+/// `owner/repo@<worktree>`, and prints its directory.
+pub fn create(config: Config, name: String, repos: Vec<String>, force: bool) -> Result<()> {
+    validate_name(&name)?;
+    let members = repos
+        .iter()
+        .map(|repo| resolve_member(&config, repo))
+        .collect::<Result<Vec<_>>>()?;
+    let targets = member_targets(&config, &members)?;
+    Metadata::update(&config.root, |metadata| {
+        if metadata.workspaces.contains_key(&name) && !force {
+            bail!("Workspace already exists: {name} (pass --force to replace it)");
+        }
+        metadata.workspaces.insert(name.clone(), members.clone());
+        Ok(())
+    })?;
+
+    let dir = write_workspace_dir(&config, &name, &members, &targets)?;
+    info!(
+        "Created workspace {name} with {} repositories",
+        members.len()
+    );
+    println!("{}", dir.display());
+    Ok(())
+}
+
+/// Refreshes the directory of workspace `name` and prints it.
+pub fn switch(config: Config, name: String) -> Result<()> {
+    let members = load_members(&config, &name)?;
+    let targets = member_targets(&config, &members)?;
+    let dir = write_workspace_dir(&config, &name, &members, &targets)?;
+    println!("{}", dir.display());
+    Ok(())
+}
+
+/// Prints the workspace names, or the worktrees of workspace `name`.
+pub fn list(config: Config, name: Option<String>) -> Result<()> {
+    match name {
+        Some(name) => {
+            let members = load_members(&config, &name)?;
+            for target in member_targets(&config, &members)? {
+                println!("{}", target.worktree.display());
+            }
+        }
+        None => {
+            for name in Metadata::load(&config.root)?.workspaces.keys() {
+                println!("{name}");
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Runs `command` in every worktree of workspace `name`.
+pub fn exec(config: Config, name: String, jobs: Option<usize>, command: Vec<String>) -> Result<()> {
+    let members = load_members(&config, &name)?;
+    let targets = member_targets(&config, &members)?;
+    run::run_all(&config, &targets, jobs, &command)
+}
+
+/// Forgets workspace `name` and deletes its directory; the repositories
+/// stay untouched.
+pub fn remove(config: Config, name: String) -> Result<()> {
+    if !Metadata::load(&config.root)?.workspaces.contains_key(&name) {
+        return Err(exit::not_found(
+            false,
+            format!("Workspace not found: {name}"),
+        ));
+    }
+    dry_run::apply(&config, format!("remove workspace {name}"), || {
+        Metadata::update(&config.root, |metadata| {
+            Ok(metadata.workspaces.remove(&name))
+        })?;
+        let dir = workspace_dir(&config, &name);
+        if dir.exists() {
+            std::fs::remove_dir_all(dir)?;
+        }
+        Ok(())
+    })
+}
+
+/// Writes a VS Code workspace with a folder for each worktree of `repo`
+/// into its repository directory and prints its path.
+pub fn code(config: Config, repo: String, open: bool) -> Result<()> {
+    let Some(repo_path) = find_repository_path(&config, &repo)? else {
+        return Err(exit::not_found(false, not_found_message(&config, &repo)?));
+    };
+    let path = write_code_workspace(&repo_path)?;
+    println!("{}", path.display());
+    if open {
+        editor::open(&config, &path)?;
+    }
+    Ok(())
+}
+
+fn validate_name(name: &str) -> Result<()> {
+    if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) {
+        bail!("Invalid workspace name: {name:?}");
+    }
+    Ok(())
+}
+
+fn load_members(config: &Config, name: &str) -> Result<Vec<WorkspaceMember>> {
+    Metadata::load(&config.root)?
+        .workspaces
+        .remove(name)
+        .ok_or_else(|| exit::not_found(false, format!("Workspace not found: {name}")))
+}
+
+/// Parses `owner/repo[@worktree]` into a member naming the repository by
+/// `host/owner/repo`.
+fn resolve_member(config: &Config, repo: &str) -> Result<WorkspaceMember> {
+    let (repo, worktree) = match repo.split_once('@') {
+        Some((repo, worktree)) => (repo, Some(worktree.to_string())),
+        None => (repo, None),
+    };
+    let Some(repo_path) = find_repository_path(config, repo)? else {
+        return Err(exit::not_found(false, not_found_message(config, repo)?));
+    };
+    let entry = RepoEntry::from_path(&repo_path)
+        .ok_or_else(|| anyhow!("Not a repository directory: {}", repo_path.display()))?;
+    Ok(WorkspaceMember {
+        repo: entry.spec(),
+        worktree,
+    })
+}
+
+/// Finds the worktree of each member in the configured roots.
+fn member_targets(config: &Config, members: &[WorkspaceMember]) -> Result<Vec<Target>> {
+    members
+        .iter()
+        .map(|member| {
+            let entry = config
+                .all_roots()
+                .into_iter()
+                .map(|root| root.path.join(&member.repo))
+                .find(|path| path.join(".git").exists())
+                .and_then(|path| RepoEntry::from_path(&path))
+                .ok_or_else(|| {
+                    exit::not_found(false, format!("Repository not found: {}", member.repo))
+                })?;
+            let filter = match &member.worktree {
+                Some(worktree) => WorktreeFilter::Named(worktree.clone()),
+                None => WorktreeFilter::Default,
+            };
+            let worktree = search::select_worktrees(&entry, &filter)?
+                .into_iter()
+                .next()
+                .ok_or_else(|| {
+                    exit::not_found(
+                        false,
+                        format!(
+                            "No worktree {} in {}",
+                            member.worktree.as_deref().unwrap_or("found"),
+                            member.repo
+                        ),
+                    )
+                })?;
+            Ok(Target {
+                repo: member.repo.clone(),
+                worktree,
+            })
+        })
+        .collect()
+}
+
+fn workspace_dir(config: &Config, name: &str) -> PathBuf {
+    config.root.join(".neoghq").join("workspaces").join(name)
+}
+
+/// Recreates `<root>/.neoghq/workspaces/<name>` with a link to each
+/// member's worktree and a `<name>.code-workspace` file for VS Code and
+/// compatible editors.
+fn write_workspace_dir(
+    config: &Config,
+    name: &str,
+    members: &[WorkspaceMember],
+    targets: &[Target],
+) -> Result<PathBuf> {
+    let dir = workspace_dir(config, name);
+    if dir.exists() {
+        // Removes the links, not the worktrees they point to
+        std::fs::remove_dir_all(&dir)?;
+    }
+    std::fs::create_dir_all(&dir)?;
+
+    let names = link_names(members);
+    let mut folders = Vec::new();
+    for (link, target) in names.iter().zip(targets) {
+        link_worktree(&target.worktree, &dir.join(link))?;
+        folders.push(json!({ "name": link, "path": target.worktree }));
+    }
+    let workspace = json!({ "folders": folders, "settings": {} });
+    std::fs::write(
+        dir.join(format!("{name}.code-workspace")),
+        serde_json::to_string_pretty(&workspace)? + "\n",
+    )?;
+    Ok(dir)
+}
+
+/// Writes `<repo>/<repo>.code-workspace`, naming each folder after the
+/// worktree's branch. Folders inside the repository directory are relative
+/// so the file keeps working after `relocate`.
+fn write_code_workspace(repo_path: &Path) -> Result<PathBuf> {
+    let repo = git2::Repository::open_bare(paths::bare_repository_path(repo_path))?;
+    let folders = status::list_worktrees(&repo)?
+        .into_iter()
+        .map(|worktree| {
+            let name = worktree.branch.unwrap_or_else(|| {
+                worktree
+                    .path
+                    .file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .into_owned()
+            });
+            let path = worktree
+                .path
+                .strip_prefix(repo_path)
+                .map_or_else(|_| worktree.path.display().to_string(), paths::to_slash);
+            json!({ "name": name, "path": path })
+        })
+        .collect::<Vec<_>>();
+    let name = repo_path
+        .file_name()
+        .ok_or_else(|| anyhow!("Not a repository directory: {}", repo_path.display()))?;
+    let path = repo_path.join(format!("{}.code-workspace", name.to_string_lossy()));
+    let workspace = json!({ "folders": folders, "settings": {} });
+    std::fs::write(&path, serde_json::to_string_pretty(&workspace)? + "\n")?;
+    Ok(path)
+}
+
+/// Names each member's link after its repository (and worktree, if one is
+/// pinned), qualified by the owner where repository names collide.
+fn link_names(members: &[WorkspaceMember]) -> Vec<String> {
+    let name = |member: &WorkspaceMember, qualified: bool| {
+        let mut segments = member.repo.rsplit('/');
+        let repo = segments.next().unwrap_or_default();
+        let mut name = match (qualified, segments.next()) {
+            (true, Some(owner)) => format!("{owner}-{repo}"),
+            _ => repo.to_string(),
+        };
+        if let Some(worktree) = &member.worktree {
+            name = format!("{name}@{}", worktree.replace('/', "-"));
+        }
+        name
+    };
+    let mut counts = HashMap::new();
+    for member in members {
+        *counts.entry(name(member, false)).or_insert(0) += 1;
+    }
+    members
+        .iter()
+        .map(|member| {
+            let short = name(member, false);
+            if counts[&short] > 1 {
+                name(member, true)
+            } else {
+                short
+            }
+        })
+        .collect()
+}
+
+#[cfg(unix)]
+fn link_worktree(worktree: &Path, link: &Path) -> Result<()> {
+    Ok(std::os::unix::fs::symlink(worktree, link)?)
+}
+
+#[cfg(windows)]
+fn link_worktree(worktree: &Path, link: &Path) -> Result<()> {
+    Ok(std::os::windows::fs::symlink_dir(worktree, link)?)
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn create_repo(root: &Path, owner: &str, repo: &str, worktrees: &[&str]) {
+        let repo_path = root.join("github.com").join(owner).join(repo);
+        fs::create_dir_all(repo_path.join(".git")).unwrap();
+        for worktree in worktrees {
+            fs::create_dir_all(repo_path.join(worktree)).unwrap();
+        }
+    }
+
+    fn member(repo: &str, worktree: Option<&str>) -> WorkspaceMember {
+        WorkspaceMember {
+            repo: repo.to_string(),
+            worktree: worktree.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_link_names() {
+        let members = [
+            member("github.com/alice/api", None),
+            member("github.com/alice/web", None),
+            member("github.com/bob/web", None),
+            member("github.com/alice/api", Some("feature/x")),
+        ];
+        assert_eq!(
+            link_names(&members),
+            ["api", "alice-web", "bob-web", "api@feature-x"]
+        );
+    }
+
+    #[test]
+    fn test_create_switch_list_remove() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        create_repo(root, "user", "api", &["main", "feature"]);
+        create_repo(root, "user", "web", &["main"]);
+        let config = Config {
+            root: root.to_path_buf(),
+            ..Default::default()
+        };
+        let repos = vec!["user/api@feature".to_string(), "user/web".to_string()];
+
+        create(config.clone(), "services".to_string(), repos.clone(), false).unwrap();
+        let metadata = Metadata::load(root).unwrap();
+        assert_eq!(
+            metadata.workspaces["services"],
+            [
+                member("github.com/user/api", Some("feature")),
+                member("github.com/user/web", None)
+            ]
+        );
+        let dir = root.join(".neoghq/workspaces/services");
+        assert_eq!(
+            fs::canonicalize(dir.join("api@feature")).unwrap(),
+            fs::canonicalize(root.join("github.com/user/api/feature")).unwrap()
+        );
+        let workspace = fs::read_to_string(dir.join("services.code-workspace")).unwrap();
+        let workspace: serde_json::Value = serde_json::from_str(&workspace).unwrap();
+        assert_eq!(workspace["folders"][1]["name"], "web");
+
+        assert!(create(config.clone(), "services".to_string(), repos.clone(), false).is_err());
+        assert!(create(config.clone(), "services".to_string(), repos, true).is_ok());
+        assert!(
+            create(
+                config.clone(),
+                "bad".to_string(),
+                vec!["user/nope".to_string()],
+                false
+            )
+            .is_err()
+        );
+        assert!(create(config.clone(), "a/b".to_string(), Vec::new(), false).is_err());
+
+        assert!(switch(config.clone(), "services".to_string()).is_ok());
+        assert!(list(config.clone(), None).is_ok());
+        assert!(list(config.clone(), Some("services".to_string())).is_ok());
+        assert!(
+            exec(
+                config.clone(),
+                "services".to_string(),
+                None,
+                vec!["true".to_string()]
+            )
+            .is_ok()
+        );
+        assert!(switch(config.clone(), "missing".to_string()).is_err());
+
+        remove(config.clone(), "services".to_string()).unwrap();
+        assert!(!dir.exists());
+        assert!(root.join("github.com/user/api/feature").exists());
+        assert!(remove(config, "services".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_code() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        let repo_dir = root.join("github.com/user/api");
+        let repo = crate::test_utils::create_managed_repository(&repo_dir);
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        let branch = repo.branch("feature/x", &head, false).unwrap();
+        let mut opts = git2::WorktreeAddOptions::new();
+        opts.reference(Some(branch.get()));
+        repo.worktree("feature-x", &repo_dir.join("feature-x"), Some(&opts))
+            .unwrap();
+        let config = Config {
+            root: root.to_path_buf(),
+            ..Default::default()
+        };
+
+        code(config.clone(), "user/api".to_string(), false).unwrap();
+        let workspace = fs::read_to_string(repo_dir.join("api.code-workspace")).unwrap();
+        let workspace: serde_json::Value = serde_json::from_str(&workspace).unwrap();
+        let folders = workspace["folders"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|folder| {
+                (
+                    folder["name"].as_str().unwrap(),
+                    folder["path"].as_str().unwrap(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(folders, [("feature/x", "feature-x"), ("main", "main")]);
+
+        assert!(code(config, "user/missing".to_string(), false).is_err());
+    }
+}
@@ -1,6 +1,163 @@
+use super::{remove::remove_worktree, review, scratch};
+use crate::{config::Config, events, lock, repo_context, status};
 use anyhow::Result;
+use git2::{BranchType, Repository};
+use std::path::PathBuf;
+use tracing::info;
 
-pub fn execute() -> Result<()> {
-    println!("Clean command");
+/// Removes the worktrees whose branch is merged into the default branch,
+/// and with `gone` also those whose upstream branch was deleted, as after a
+/// squash merge. Expired scratch worktrees go too, as do review worktrees
+/// whose pull request or branch is finished. Worktrees with
+/// uncommitted changes and the default branch's own worktree are kept.
+pub fn execute(config: Config, gone: bool) -> Result<()> {
+    let context = repo_context::current_repo()?;
+    let repo = context.open()?;
+    let lock = lock::lock_shared(&repo)?;
+    let mut merged = merged_worktrees(&repo)?;
+    if gone {
+        for worktree in gone_worktrees(&repo)? {
+            if !merged.contains(&worktree) {
+                merged.push(worktree);
+            }
+        }
+    }
+    if merged.is_empty() {
+        info!("No merged worktrees to clean");
+    }
+
+    let mut removed = Vec::new();
+    for worktree_path in merged {
+        removed.extend(remove_worktree(&config, &repo, &worktree_path)?);
+    }
+    if let Ok((root, spec)) = context.metadata_key() {
+        removed.extend(scratch::remove_expired(&config, &repo, &root, &spec)?);
+        removed.extend(review::remove_finished(&config, &repo, &root, &spec)?);
+    }
+    drop(lock);
+    for event in &removed {
+        events::emit(&config, event);
+    }
     Ok(())
 }
+
+fn merged_worktrees(repo: &Repository) -> Result<Vec<PathBuf>> {
+    let Some(default_branch) = status::default_branch(repo) else {
+        return Ok(Vec::new());
+    };
+    let Some(default_tip) = repo
+        .find_branch(&default_branch, BranchType::Local)
+        .ok()
+        .and_then(|branch| branch.get().target())
+    else {
+        return Ok(Vec::new());
+    };
+
+    let mut merged = Vec::new();
+    for worktree in status::list_worktrees(repo)? {
+        let Some(branch) = &worktree.branch else {
+            continue;
+        };
+        if *branch == default_branch || Some(worktree.path.as_path()) == repo.workdir() {
+            continue;
+        }
+        let Some(tip) = repo.find_branch(branch, BranchType::Local)?.get().target() else {
+            continue;
+        };
+        let is_merged = tip == default_tip || repo.graph_descendant_of(default_tip, tip)?;
+        if is_merged && !status::is_worktree_dirty(&worktree.path)? {
+            merged.push(worktree.path);
+        }
+    }
+    Ok(merged)
+}
+
+/// Returns the clean worktrees whose branch tracks a deleted remote branch,
+/// except the default branch's.
+fn gone_worktrees(repo: &Repository) -> Result<Vec<PathBuf>> {
+    let default_branch = status::default_branch(repo);
+    let mut gone = Vec::new();
+    for worktree in status::collect_worktree_statuses(repo)? {
+        if !worktree.upstream_gone()
+            || worktree.dirty
+            || worktree.branch == default_branch
+            || Some(worktree.path.as_path()) == repo.workdir()
+        {
+            continue;
+        }
+        gone.push(worktree.path);
+    }
+    Ok(gone)
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_managed_repository;
+
+    #[test]
+    fn test_merged_worktrees() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo_dir = temp_dir.path().join("repo");
+        let repo = create_managed_repository(&repo_dir);
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        for name in ["merged", "dirty", "ahead"] {
+            let branch = repo.branch(name, &head, false).unwrap();
+            let mut opts = git2::WorktreeAddOptions::new();
+            opts.reference(Some(branch.get()));
+            repo.worktree(name, &repo_dir.join(name), Some(&opts))
+                .unwrap();
+        }
+        std::fs::write(repo_dir.join("dirty/NEW"), "new").unwrap();
+        let ahead = Repository::open(repo_dir.join("ahead")).unwrap();
+        crate::test_utils::commit_all(&ahead, "Ahead of main");
+
+        let merged = merged_worktrees(&repo).unwrap();
+        assert_eq!(merged.len(), 1);
+        assert_eq!(
+            merged[0].canonicalize().unwrap(),
+            repo_dir.join("merged").canonicalize().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_gone_worktrees() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo_dir = temp_dir.path().join("repo");
+        let repo = create_managed_repository(&repo_dir);
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        for name in ["gone", "dirty", "tracking"] {
+            let mut branch = repo.branch(name, &head, false).unwrap();
+            repo.reference(
+                &format!("refs/remotes/origin/{name}"),
+                head.id(),
+                true,
+                "test",
+            )
+            .unwrap();
+            branch
+                .set_upstream(Some(&format!("origin/{name}")))
+                .unwrap();
+            let mut opts = git2::WorktreeAddOptions::new();
+            opts.reference(Some(branch.get()));
+            repo.worktree(name, &repo_dir.join(name), Some(&opts))
+                .unwrap();
+        }
+        // Deleted upstream, as a pruning fetch leaves it
+        for name in ["gone", "dirty"] {
+            repo.find_reference(&format!("refs/remotes/origin/{name}"))
+                .unwrap()
+                .delete()
+                .unwrap();
+        }
+        std::fs::write(repo_dir.join("dirty/NEW"), "new").unwrap();
+
+        let gone = gone_worktrees(&repo).unwrap();
+        assert_eq!(gone.len(), 1);
+        assert_eq!(
+            gone[0].canonicalize().unwrap(),
+            repo_dir.join("gone").canonicalize().unwrap()
+        );
+    }
+}
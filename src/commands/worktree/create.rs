@@ -1,7 +1,480 @@
-use anyhow::Result;
+use crate::{
+    commands::repo::{
+        compact::{default_worktree_branch, is_compacted, restore_default_worktree},
+        get::create_worktree,
+    },
+    config::Config,
+    dry_run, editor,
+    events::{self, Event, EventKind},
+    forge::{self, Forge, ForgeIssue},
+    format::Format,
+    giturl,
+    lock::RepoLock,
+    metadata::{Metadata, StackParent},
+    paths,
+    repo_context::{self, RepoContext},
+    repo_settings::RepoSettings,
+    status,
+};
+use anyhow::{Result, anyhow, bail};
+use git2::{Branch, BranchType, Repository};
+use serde::Serialize;
+use std::path::Path;
+use tracing::info;
 
-pub fn execute(branch: String) -> Result<()> {
-    println!("Creating worktree for branch: {branch}");
-    println!("worktree create functionality not yet implemented");
+/// Branch names of `worktree create --issue` without `worktree.issue_branch`.
+const DEFAULT_ISSUE_BRANCH: &str = "{user}/{issue}-{slug}";
+
+/// Placeholders of `worktree.issue_branch`.
+const ISSUE_BRANCH_FIELDS: &[&str] = &["user", "issue", "slug"];
+
+/// Longest slug of an issue title kept in a branch name.
+const MAX_SLUG_LEN: usize = 40;
+
+#[derive(Serialize)]
+struct IssueBranchFields<'a> {
+    user: Option<&'a str>,
+    issue: u64,
+    slug: String,
+}
+
+/// Adds a worktree for `branch` to the repository containing the current
+/// directory and prints its path. A branch that exists neither locally nor
+/// on `origin` is created from the default branch. With `issue`, a number
+/// of an issue of `origin` or an issue URL, the branch is named after the
+/// issue by `worktree.issue_branch` instead. With `stack_on`, a new branch
+/// starts from that local branch instead, which is recorded as its stack
+/// parent for `worktree rebase`. With `open`, the worktree is opened in the
+/// editor as `worktree switch --open` does.
+pub fn execute(
+    config: Config,
+    branch: Option<String>,
+    issue: Option<String>,
+    stack_on: Option<String>,
+    open: bool,
+) -> Result<()> {
+    let context = repo_context::current_repo()?;
+    create(&config, &context, branch, issue, stack_on, open)
+}
+
+fn create(
+    config: &Config,
+    context: &RepoContext,
+    branch: Option<String>,
+    issue: Option<String>,
+    stack_on: Option<String>,
+    open: bool,
+) -> Result<()> {
+    if context.is_plain_clone() {
+        bail!(
+            "{} is a plain clone; convert it with `neoghq repo adopt --in-place {}` first",
+            context.repo_dir.display(),
+            context.repo_dir.display()
+        );
+    }
+    let repo = context.open()?;
+    let issue = issue
+        .map(|issue| find_issue(config, &repo, &issue))
+        .transpose()?;
+    let branch = match (&issue, branch) {
+        (Some(issue), _) => {
+            info!("Issue #{}: {}", issue.number, issue.title);
+            issue_branch(config, issue)?
+        }
+        (None, Some(branch)) => branch,
+        (None, None) => bail!("Name a branch or pass --issue"),
+    };
+    if let Some(worktree) = status::list_worktrees(&repo)?
+        .into_iter()
+        .find(|worktree| worktree.branch.as_deref() == Some(branch.as_str()))
+    {
+        bail!(
+            "{branch} is already checked out in {}",
+            worktree.path.display()
+        );
+    }
+    let worktree_path = paths::worktree_path(&context.repo_dir, &branch);
+    if worktree_path.exists() {
+        bail!("{} already exists", worktree_path.display());
+    }
+    let stack = match &stack_on {
+        Some(parent) if *parent == branch => bail!("{branch} can't be stacked on itself"),
+        Some(parent) => {
+            if repo.find_branch(parent, BranchType::Local).is_err() {
+                bail!("There is no local branch {parent} to stack on");
+            }
+            Some(context.metadata_key()?)
+        }
+        None => None,
+    };
+
+    // A compacted repository gets its default worktree back, as `repo
+    // switch` gives it, unless that's the worktree being created
+    if context.entry().is_some()
+        && is_compacted(&repo)?
+        && default_worktree_branch(config, &context.repo_dir)? != branch
+    {
+        restore_default_worktree(config, &context.repo_dir)?;
+    }
+
+    let spec = context.entry().map_or_else(
+        || context.repo_dir.display().to_string(),
+        |entry| entry.spec(),
+    );
+    let settings = RepoSettings::load(config, &spec, &context.repo_dir)?;
+    dry_run::apply(
+        config,
+        format_args!("create worktree {} for {branch}", worktree_path.display()),
+        || {
+            let _lock = RepoLock::acquire(&context.git_dir)?;
+            create_branch(&repo, &branch, stack_on.as_deref())?;
+            if let Some(issue) = &issue
+                && config.issue_description
+            {
+                describe_branch(&repo, &branch, issue)?;
+            }
+            create_worktree(&context.git_dir, &worktree_path, &branch)?;
+            if let (Some(parent), Some((root, spec))) = (&stack_on, &stack) {
+                record_stack_parent(&repo, root, spec, &branch, parent)?;
+            }
+            settings.setup_worktree(&context.repo_dir, &worktree_path)
+        },
+    )?;
+    if config.dry_run {
+        return Ok(());
+    }
+
+    info!("Created worktree {}", worktree_path.display());
+    events::emit(
+        config,
+        &Event::worktree(
+            EventKind::WorktreeCreated,
+            &context.repo_dir,
+            &worktree_path,
+            Some(&branch),
+        ),
+    );
+    println!("{}", worktree_path.display());
+    if open {
+        editor::open(config, &worktree_path)?;
+    }
+    Ok(())
+}
+
+/// Creates `branch` from the local branch `start`, or from the default
+/// branch, unless it exists locally or on `origin`, where `create_worktree`
+/// picks it up.
+fn create_branch(repo: &Repository, branch: &str, start: Option<&str>) -> Result<()> {
+    if repo.find_branch(branch, BranchType::Local).is_ok()
+        || repo
+            .find_branch(&format!("origin/{branch}"), BranchType::Remote)
+            .is_ok()
+    {
+        return Ok(());
+    }
+    if let Some(start) = start {
+        let start = repo.find_branch(start, BranchType::Local)?;
+        repo.branch(branch, &start.get().peel_to_commit()?, false)?;
+        return Ok(());
+    }
+    let default_branch = status::default_branch(repo)
+        .ok_or_else(|| anyhow!("HEAD of the repository is detached"))?;
+    let start = repo
+        .find_branch(&default_branch, BranchType::Local)
+        .or_else(|_| repo.find_branch(&format!("origin/{default_branch}"), BranchType::Remote))
+        .map_err(|_| anyhow!("The default branch {default_branch} doesn't exist"))?;
+    repo.branch(branch, &start.get().peel_to_commit()?, false)?;
+    Ok(())
+}
+
+/// Records `parent` as the stack parent of `branch`, based on the commit
+/// where `branch` forked from it.
+fn record_stack_parent(
+    repo: &Repository,
+    root: &Path,
+    spec: &str,
+    branch: &str,
+    parent: &str,
+) -> Result<()> {
+    let tip = repo.refname_to_id(&format!("refs/heads/{branch}"))?;
+    let parent_tip = repo.refname_to_id(&format!("refs/heads/{parent}"))?;
+    let base = repo.merge_base(tip, parent_tip)?;
+    Metadata::update(root, |metadata| {
+        metadata.set_stack_parent(
+            spec,
+            branch,
+            StackParent {
+                branch: parent.to_string(),
+                base: base.to_string(),
+            },
+        );
+        Ok(())
+    })
+}
+
+/// Looks up `issue`: a number, optionally with `#`, of an issue of the
+/// repository's `origin`, or an issue URL.
+fn find_issue(config: &Config, repo: &Repository, issue: &str) -> Result<ForgeIssue> {
+    let (host, owner, name, number) = match issue.trim_start_matches('#').parse() {
+        Ok(number) => {
+            let remote = repo
+                .find_remote("origin")
+                .map_err(|_| anyhow!("No origin remote to look up issue {issue} on"))?;
+            let url = giturl::parse(remote.url().unwrap_or_default())?;
+            (url.host, url.owner, url.repo, number)
+        }
+        Err(_) => {
+            let url = forge::parse_issue_url(issue)?;
+            (url.host, url.owner, url.repo, url.number)
+        }
+    };
+    let forge = Forge::for_host(config, &host)
+        .ok_or_else(|| anyhow!("No known issue tracker on {host}; set hosts.\"{host}\".forge"))?;
+    forge.issue(&owner, &name, number)
+}
+
+/// Names the branch for `issue` by `worktree.issue_branch`.
+fn issue_branch(config: &Config, issue: &ForgeIssue) -> Result<String> {
+    let template = config
+        .issue_branch
+        .as_deref()
+        .unwrap_or(DEFAULT_ISSUE_BRANCH);
+    if config.user.is_none() && template.contains("{user}") {
+        bail!("Set general.user, or drop {{user}} from worktree.issue_branch");
+    }
+    let slug = slugify(&issue.title);
+    let branch = Format::parse(template, ISSUE_BRANCH_FIELDS)?.render(&IssueBranchFields {
+        user: config.user.as_deref(),
+        issue: issue.number,
+        slug: if slug.is_empty() {
+            "issue".to_string()
+        } else {
+            slug
+        },
+    })?;
+    if !Branch::name_is_valid(&branch)? {
+        bail!("worktree.issue_branch made an invalid branch name: {branch}");
+    }
+    Ok(branch)
+}
+
+/// Turns an issue title into lowercase ASCII words joined by `-`, cut at a
+/// word boundary to keep branch names short.
+fn slugify(title: &str) -> String {
+    let mut slug = String::new();
+    for word in title
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+    {
+        if !slug.is_empty() {
+            if slug.len() + 1 + word.len() > MAX_SLUG_LEN {
+                break;
+            }
+            slug.push('-');
+        }
+        slug.push_str(&word.to_ascii_lowercase());
+    }
+    slug.truncate(MAX_SLUG_LEN);
+    slug
+}
+
+/// Records the issue's title and link as the git branch description of
+/// `branch`, as `git branch --edit-description` would, unless it has one.
+fn describe_branch(repo: &Repository, branch: &str, issue: &ForgeIssue) -> Result<()> {
+    let key = format!("branch.{branch}.description");
+    let mut config = repo.config()?.open_level(git2::ConfigLevel::Local)?;
+    if config.get_string(&key).is_err() {
+        config.set_str(&key, &format!("{}\n\n{}\n", issue.title, issue.url))?;
+    }
     Ok(())
 }
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_managed_repository;
+
+    #[test]
+    fn test_slugify() {
+        assert_eq!(
+            slugify("Crash on empty input (v2.1)"),
+            "crash-on-empty-input-v2-1"
+        );
+        assert_eq!(
+            slugify("Support the extremely long and verbose issue titles people write"),
+            "support-the-extremely-long-and-verbose"
+        );
+        assert_eq!(slugify("日本語"), "");
+        assert_eq!(slugify(&"a".repeat(50)).len(), MAX_SLUG_LEN);
+    }
+
+    #[test]
+    fn test_issue_branch() {
+        let issue = ForgeIssue {
+            number: 12,
+            title: "Crash on empty input".to_string(),
+            url: "https://github.com/user/repo/issues/12".to_string(),
+        };
+        let mut config = Config::default();
+        assert!(issue_branch(&config, &issue).is_err());
+        config.user = Some("alice".to_string());
+        assert_eq!(
+            issue_branch(&config, &issue).unwrap(),
+            "alice/12-crash-on-empty-input"
+        );
+        config.issue_branch = Some("fix/{issue}".to_string());
+        assert_eq!(issue_branch(&config, &issue).unwrap(), "fix/12");
+        config.issue_branch = Some("{slug}..{issue}".to_string());
+        assert!(issue_branch(&config, &issue).is_err());
+    }
+
+    #[test]
+    fn test_find_issue_and_describe_branch() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo = create_managed_repository(&temp_dir.path().join("github.com/user/repo"));
+        repo.remote_set_url("origin", "git@github.com:user/repo.git")
+            .unwrap();
+        let issues = temp_dir.path().join("api/repos/user/repo/issues");
+        std::fs::create_dir_all(&issues).unwrap();
+        std::fs::write(
+            issues.join("12"),
+            r#"{"number": 12, "title": "Crash on empty input",
+                "html_url": "https://github.com/user/repo/issues/12"}"#,
+        )
+        .unwrap();
+        let config = Config {
+            hosts: [(
+                "github.com".to_string(),
+                crate::config::HostConfig {
+                    api_url: Some(format!("file://{}", temp_dir.path().join("api").display())),
+                    ..Default::default()
+                },
+            )]
+            .into(),
+            ..Default::default()
+        };
+
+        let issue = find_issue(&config, &repo, "#12").unwrap();
+        assert_eq!(issue.title, "Crash on empty input");
+        assert_eq!(
+            find_issue(&config, &repo, "https://github.com/user/repo/issues/12").unwrap(),
+            issue
+        );
+        assert!(find_issue(&config, &repo, "https://example.com/user/repo/issues/12").is_err());
+
+        create_branch(&repo, "alice/12-crash", None).unwrap();
+        describe_branch(&repo, "alice/12-crash", &issue).unwrap();
+        assert_eq!(
+            repo.config()
+                .unwrap()
+                .get_string("branch.alice/12-crash.description")
+                .unwrap(),
+            "Crash on empty input\n\nhttps://github.com/user/repo/issues/12\n"
+        );
+    }
+
+    #[test]
+    fn test_create_branch() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo_dir = temp_dir.path().join("github.com/user/repo");
+        let repo = create_managed_repository(&repo_dir);
+        let main = repo.head().unwrap().target().unwrap();
+
+        create_branch(&repo, "feature/x", None).unwrap();
+        let branch = repo.find_branch("feature/x", BranchType::Local).unwrap();
+        assert_eq!(branch.get().target(), Some(main));
+
+        // Existing branches are left alone
+        let commit = repo.find_commit(main).unwrap();
+        repo.branch("existing", &commit, false).unwrap();
+        create_branch(&repo, "existing", None).unwrap();
+    }
+
+    #[test]
+    fn test_create_worktree_for_new_branch() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo_dir = temp_dir.path().join("github.com/user/repo");
+        let repo = create_managed_repository(&repo_dir);
+        let git_dir = repo_dir.join(".git");
+
+        create_branch(&repo, "feature/x", None).unwrap();
+        create_worktree(&git_dir, &repo_dir.join("feature/x"), "feature/x").unwrap();
+        let worktree = Repository::open(repo_dir.join("feature/x")).unwrap();
+        assert_eq!(status::head_branch(&worktree).as_deref(), Some("feature/x"));
+        assert!(repo_dir.join("feature/x/README").is_file());
+    }
+
+    #[test]
+    fn test_create_opens_the_worktree() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo_dir = temp_dir.path().join("github.com/user/repo");
+        create_managed_repository(&repo_dir);
+        let config = Config {
+            root: temp_dir.path().to_path_buf(),
+            editor_command: Some("touch {path}/opened".to_string()),
+            ..Default::default()
+        };
+        let context = repo_context::from_path(&repo_dir.join("main")).unwrap();
+
+        create(
+            &config,
+            &context,
+            Some("feature/x".to_string()),
+            None,
+            None,
+            true,
+        )
+        .unwrap();
+        assert!(repo_dir.join("feature/x/opened").exists());
+
+        create(
+            &config,
+            &context,
+            Some("feature/y".to_string()),
+            None,
+            None,
+            false,
+        )
+        .unwrap();
+        assert!(!repo_dir.join("feature/y/opened").exists());
+    }
+
+    #[test]
+    fn test_create_restores_the_default_worktree_of_a_compacted_repository() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo_dir = temp_dir.path().join("github.com/user/repo");
+        create_managed_repository(&repo_dir);
+        let config = Config {
+            root: temp_dir.path().to_path_buf(),
+            ..Default::default()
+        };
+        crate::commands::repo::compact::execute(config.clone(), "user/repo".to_string()).unwrap();
+        let context = repo_context::from_path(&repo_dir).unwrap();
+
+        create(
+            &config,
+            &context,
+            Some("feature/x".to_string()),
+            None,
+            None,
+            false,
+        )
+        .unwrap();
+        assert!(Repository::open(repo_dir.join("main")).is_ok());
+        assert!(Repository::open(repo_dir.join("feature/x")).is_ok());
+
+        // Creating the default worktree itself creates it only once
+        crate::commands::repo::compact::execute(config.clone(), "user/repo".to_string()).unwrap();
+        create(
+            &config,
+            &context,
+            Some("main".to_string()),
+            None,
+            None,
+            false,
+        )
+        .unwrap();
+        assert!(Repository::open(repo_dir.join("main")).is_ok());
+    }
+}
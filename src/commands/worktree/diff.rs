@@ -0,0 +1,113 @@
+use super::switch::{find_worktree_path, not_found_message};
+use crate::{
+    config::Config,
+    exit, repo_context, status,
+    style::{Color, Style},
+};
+use anyhow::Result;
+use git2::{Diff, DiffFormat, DiffStatsFormat, Repository, Tree};
+
+/// Width of the `--stat` graph, as `git diff --stat` uses on a terminal.
+const STAT_WIDTH: usize = 80;
+
+/// Prints the diff from `from` to `to` in the current repository. Each is a
+/// worktree, named by its branch or directory, compared with its
+/// uncommitted changes, or else any revision such as a branch without a
+/// worktree. With `stat`, only the changed files and their line counts are
+/// printed.
+pub fn execute(config: Config, from: String, to: String, stat: bool) -> Result<()> {
+    let repo = repo_context::current_repo()?.open()?;
+    let diff = diff(&repo, &from, &to)?;
+    if stat {
+        let stats = diff.stats()?;
+        let text = stats.to_buf(
+            DiffStatsFormat::FULL | DiffStatsFormat::INCLUDE_SUMMARY,
+            STAT_WIDTH,
+        )?;
+        print!("{}", String::from_utf8_lossy(&text));
+        return Ok(());
+    }
+
+    let style = Style::stdout(&config);
+    diff.print(DiffFormat::Patch, |_, _, line| {
+        let content = String::from_utf8_lossy(line.content());
+        let content = content.strip_suffix('\n').unwrap_or(&content);
+        let text = match line.origin() {
+            origin @ ('+' | '-' | ' ') => format!("{origin}{content}"),
+            _ => content.to_string(),
+        };
+        let text = match line.origin() {
+            '+' => style.paint(Color::Green, text),
+            '-' => style.paint(Color::Red, text),
+            'F' | 'H' => style.paint(Color::Dim, text),
+            _ => text,
+        };
+        println!("{text}");
+        true
+    })?;
+    Ok(())
+}
+
+fn diff<'a>(repo: &'a Repository, from: &str, to: &str) -> Result<Diff<'a>> {
+    let from = resolve_tree(repo, from)?;
+    let to = resolve_tree(repo, to)?;
+    let mut diff = repo.diff_tree_to_tree(Some(&from), Some(&to), None)?;
+    diff.find_similar(None)?;
+    Ok(diff)
+}
+
+/// Returns the contents of the worktree named `name`, or the tree of the
+/// revision `name`.
+fn resolve_tree<'a>(repo: &'a Repository, name: &str) -> Result<Tree<'a>> {
+    if let Some(worktree) = find_worktree_path(repo, name)? {
+        return status::worktree_tree(repo, &worktree);
+    }
+    match repo.revparse_single(name) {
+        Ok(object) => Ok(object.peel_to_tree()?),
+        Err(_) => Err(exit::not_found(false, not_found_message(repo, name)?)),
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use crate::test_utils::{commit_all, create_managed_repository};
+
+    #[test]
+    fn test_diff_worktrees_and_revisions() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo_dir = temp_dir.path().join("github.com/user/repo");
+        let repo = create_managed_repository(&repo_dir);
+        let mut config = repo.config().unwrap();
+        config.set_str("user.name", "Test").unwrap();
+        config.set_str("user.email", "test@example.com").unwrap();
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        let branch = repo.branch("feature", &head, false).unwrap();
+        let mut opts = git2::WorktreeAddOptions::new();
+        opts.reference(Some(branch.get()));
+        repo.worktree("feature", &repo_dir.join("feature"), Some(&opts))
+            .unwrap();
+        let feature = Repository::open(repo_dir.join("feature")).unwrap();
+        std::fs::write(repo_dir.join("feature/README"), "Hello, feature!\n").unwrap();
+        commit_all(&feature, "Greet the feature");
+        // Uncommitted and untracked changes count too
+        std::fs::write(repo_dir.join("feature/NEW"), "new\n").unwrap();
+        repo.branch("other", &head, false).unwrap();
+
+        let stats = diff(&repo, "main", "feature").unwrap().stats().unwrap();
+        assert_eq!(
+            (stats.files_changed(), stats.insertions(), stats.deletions()),
+            (2, 2, 1)
+        );
+        // A branch without a worktree is compared at its commit
+        let stats = diff(&repo, "other", "main").unwrap().stats().unwrap();
+        assert_eq!(stats.files_changed(), 0);
+        assert!(diff(&repo, "main", "missing").is_err());
+        // Neither worktree's index was touched
+        assert_eq!(
+            status::changed_files(&repo_dir.join("feature")).unwrap()[0].status,
+            "??"
+        );
+    }
+}
@@ -0,0 +1,147 @@
+use crate::{
+    repo_context,
+    status::{self, Upstream},
+};
+use anyhow::Result;
+use git2::{BranchType, Repository};
+
+/// A change made to a branch's tracking configuration.
+#[derive(Debug, PartialEq, Eq)]
+enum Fix {
+    /// Tracking was set to the given remote-tracking branch.
+    Set { branch: String, upstream: String },
+    /// Tracking of a deleted remote branch was removed.
+    Cleared { branch: String, upstream: String },
+}
+
+pub fn execute() -> Result<()> {
+    let repo = repo_context::current_repo()?.open()?;
+
+    let fixes = fix_upstreams(&repo)?;
+    if fixes.is_empty() {
+        println!("All worktree branches track their upstream correctly");
+    }
+    for fix in fixes {
+        match fix {
+            Fix::Set { branch, upstream } => {
+                println!("{branch}: now tracking {upstream}")
+            }
+            Fix::Cleared { branch, upstream } => {
+                println!("{branch}: stopped tracking deleted {upstream}")
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Repairs tracking for the branches checked out in the repository's
+/// worktrees. Branches without an upstream, or whose upstream was deleted,
+/// are pointed at `origin/<branch>` if it exists; otherwise deleted upstreams
+/// are cleared so ahead/behind counts aren't reported against them.
+fn fix_upstreams(repo: &Repository) -> Result<Vec<Fix>> {
+    let mut fixes = Vec::new();
+
+    for worktree in status::list_worktrees(repo)? {
+        let Some(branch) = worktree.branch else {
+            continue;
+        };
+
+        let gone = match status::upstream_state(repo, &branch)? {
+            Upstream::Tracking { .. } => continue,
+            Upstream::Missing => None,
+            Upstream::Gone { name } => Some(name),
+        };
+
+        let candidate = format!("origin/{branch}");
+        let mut local = repo.find_branch(&branch, BranchType::Local)?;
+        if repo.find_branch(&candidate, BranchType::Remote).is_ok() {
+            local.set_upstream(Some(&candidate))?;
+            fixes.push(Fix::Set {
+                branch,
+                upstream: candidate,
+            });
+        } else if let Some(upstream) = gone {
+            local.set_upstream(None)?;
+            fixes.push(Fix::Cleared { branch, upstream });
+        }
+    }
+
+    Ok(fixes)
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_managed_repository;
+
+    #[test]
+    fn test_fix_upstreams_sets_missing_upstream() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let bare = create_managed_repository(&temp_dir.path().join("repo"));
+        bare.find_branch("main", BranchType::Local)
+            .unwrap()
+            .set_upstream(None)
+            .unwrap();
+
+        let fixes = fix_upstreams(&bare).unwrap();
+
+        assert_eq!(
+            fixes,
+            vec![Fix::Set {
+                branch: "main".to_string(),
+                upstream: "origin/main".to_string(),
+            }]
+        );
+        assert!(matches!(
+            status::upstream_state(&bare, "main").unwrap(),
+            Upstream::Tracking { .. }
+        ));
+        assert!(fix_upstreams(&bare).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_fix_upstreams_clears_deleted_upstream() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let bare = create_managed_repository(&temp_dir.path().join("repo"));
+        bare.find_branch("main", BranchType::Local)
+            .unwrap()
+            .set_upstream(Some("origin/main"))
+            .unwrap();
+        bare.find_reference("refs/remotes/origin/main")
+            .unwrap()
+            .delete()
+            .unwrap();
+
+        let fixes = fix_upstreams(&bare).unwrap();
+
+        assert_eq!(
+            fixes,
+            vec![Fix::Cleared {
+                branch: "main".to_string(),
+                upstream: "origin/main".to_string(),
+            }]
+        );
+        assert_eq!(
+            status::upstream_state(&bare, "main").unwrap(),
+            Upstream::Missing
+        );
+    }
+
+    #[test]
+    fn test_fix_upstreams_leaves_untracked_branch_without_remote() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let bare = create_managed_repository(&temp_dir.path().join("repo"));
+        bare.find_branch("main", BranchType::Local)
+            .unwrap()
+            .set_upstream(None)
+            .unwrap();
+        bare.find_reference("refs/remotes/origin/main")
+            .unwrap()
+            .delete()
+            .unwrap();
+
+        assert!(fix_upstreams(&bare).unwrap().is_empty());
+    }
+}
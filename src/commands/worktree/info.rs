@@ -0,0 +1,109 @@
+use super::switch::{find_worktree_path, not_found_message};
+use crate::{
+    cli::OutputFormat,
+    config::Config,
+    exit, repo_context,
+    status::{self, FileChange, WorktreeStatus},
+};
+use anyhow::{Result, anyhow};
+use git2::Repository;
+use serde::Serialize;
+use std::path::Path;
+
+/// A worktree as printed by `worktree info --json`: its status plus the
+/// files with uncommitted changes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+struct WorktreeInfo {
+    #[serde(flatten)]
+    status: WorktreeStatus,
+    changes: Vec<FileChange>,
+}
+
+/// Prints the details of the worktree for `branch`, or of the current
+/// worktree when no branch is given.
+pub fn execute(config: Config, branch: Option<String>, json: bool) -> Result<()> {
+    let context = repo_context::current_repo()?;
+    let repo = context.open()?;
+    let worktree_path = match branch {
+        Some(branch) => match find_worktree_path(&repo, &branch)? {
+            Some(worktree_path) => worktree_path,
+            None => return Err(exit::not_found(false, not_found_message(&repo, &branch)?)),
+        },
+        None => context.worktree.ok_or_else(|| {
+            anyhow!(
+                "Not inside a worktree: {}",
+                std::env::current_dir().unwrap_or_default().display()
+            )
+        })?,
+    };
+    let info = worktree_info(&repo, &worktree_path)?;
+
+    if json || config.output == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&info)?);
+        return Ok(());
+    }
+
+    let status = &info.status;
+    println!("Path:      {}", status.path.display());
+    println!(
+        "Branch:    {}",
+        status.branch.as_deref().unwrap_or("(detached)")
+    );
+    if let Some(tracking) = &status.tracking {
+        println!("Upstream:  {tracking}");
+    }
+    if info.changes.is_empty() {
+        println!("Changes:   none");
+    } else {
+        println!("Changes:");
+        for change in &info.changes {
+            println!("  {} {}", change.status, change.path);
+        }
+    }
+    Ok(())
+}
+
+fn worktree_info(repo: &Repository, worktree_path: &Path) -> Result<WorktreeInfo> {
+    let target = worktree_path.canonicalize()?;
+    let status = status::collect_worktree_statuses(repo)?
+        .into_iter()
+        .find(|status| status.path.canonicalize().is_ok_and(|path| path == target))
+        .ok_or_else(|| {
+            anyhow!(
+                "Not a worktree of this repository: {}",
+                worktree_path.display()
+            )
+        })?;
+
+    Ok(WorktreeInfo {
+        changes: status::changed_files(&status.path)?,
+        status,
+    })
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_managed_repository;
+
+    #[test]
+    fn test_worktree_info() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo_dir = temp_dir.path().join("repo");
+        let repo = create_managed_repository(&repo_dir);
+        std::fs::write(repo_dir.join("main/NEW"), "new").unwrap();
+
+        let info = worktree_info(&repo, &repo_dir.join("main")).unwrap();
+        assert_eq!(info.status.branch.as_deref(), Some("main"));
+        assert!(info.status.dirty);
+        assert_eq!(info.changes.len(), 1);
+        assert_eq!(info.changes[0].path, "NEW");
+
+        let json = serde_json::to_value(&info).unwrap();
+        assert_eq!(json["branch"], "main");
+        assert_eq!(json["changes"][0]["status"], "??");
+
+        assert!(worktree_info(&repo, temp_dir.path()).is_err());
+    }
+}
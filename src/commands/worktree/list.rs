@@ -1,18 +1,206 @@
+use crate::{
+    cli::{OutputFormat, SortKey},
+    config::Config,
+    format::Format,
+    metadata::Metadata,
+    output::Lines,
+    repo_context, sort,
+    status::{self, WorktreeInfo},
+    style::{Color, Icon, Style},
+};
 use anyhow::Result;
+use std::path::Path;
 
-pub fn execute() -> Result<()> {
-    println!("Listing all worktrees:");
-    println!("worktree list functionality not yet implemented");
+/// Placeholders accepted by `worktree list --format`.
+const FORMAT_FIELDS: &[&str] = &["path", "branch"];
+
+/// Options for `worktree list`.
+#[derive(Debug, Clone, Default)]
+pub struct ListOptions {
+    pub json: bool,
+    /// Template printed once per worktree.
+    pub format: Option<String>,
+    /// Terminate entries with NUL instead of newline.
+    pub print0: bool,
+    pub sort: Option<SortKey>,
+    /// Indent stacked branches under their stack parent.
+    pub stack: bool,
+}
+
+/// Returns the worktrees of the repository containing `from`, in the order
+/// `worktree list` prints them.
+pub fn list(config: &Config, from: &Path, sort: Option<SortKey>) -> Result<Vec<WorktreeInfo>> {
+    let repo = repo_context::from_path(from)?.open()?;
+    let mut worktrees = status::list_worktrees(&repo)?;
+    if let Some(key) = sort {
+        sort::sort_entries(
+            &mut worktrees,
+            key,
+            config,
+            |worktree| &worktree.path,
+            |worktree| worktree.branch.clone().unwrap_or_default(),
+        );
+    }
+    Ok(worktrees)
+}
+
+pub fn execute(config: Config, options: ListOptions) -> Result<()> {
+    let format = options
+        .format
+        .map(|template| Format::parse(&template, FORMAT_FIELDS))
+        .transpose()?;
+    let worktrees = list(&config, &std::env::current_dir()?, options.sort)?;
+    let mut lines = Lines::stdout(options.print0);
+
+    if let Some(format) = format {
+        for worktree in &worktrees {
+            lines.write(format.render(worktree)?)?;
+        }
+        return Ok(());
+    }
+
+    if options.json || config.output == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&worktrees)?);
+        return Ok(());
+    }
+
+    let style = if options.print0 {
+        Style::default()
+    } else {
+        Style::stdout(&config)
+    };
+    let entries = if options.stack {
+        let context = repo_context::from_path(&std::env::current_dir()?)?;
+        match context.metadata_key() {
+            Ok((root, spec)) => stack_order(&worktrees, &Metadata::load(&root)?, &spec),
+            Err(_) => worktrees.iter().map(|worktree| (0, worktree)).collect(),
+        }
+    } else {
+        worktrees.iter().map(|worktree| (0, worktree)).collect()
+    };
+    for (depth, worktree) in entries {
+        let branch = match &worktree.branch {
+            Some(branch) => style.paint(Color::Green, style.icon(Icon::Branch, branch)),
+            None => style.paint(Color::Yellow, style.icon(Icon::Detached, "(detached)")),
+        };
+        let indent = "  ".repeat(depth);
+        lines.write(format_args!(
+            "{}\t{indent}{branch}",
+            worktree.path.display()
+        ))?;
+    }
     Ok(())
 }
 
+/// Orders `worktrees` so each stacked branch follows its stack parent, with
+/// its depth in the stack. Branches whose parent has no worktree start a
+/// stack of their own.
+fn stack_order<'a>(
+    worktrees: &'a [WorktreeInfo],
+    metadata: &Metadata,
+    spec: &str,
+) -> Vec<(usize, &'a WorktreeInfo)> {
+    let find = |branch: &str| {
+        worktrees
+            .iter()
+            .find(|worktree| worktree.branch.as_deref() == Some(branch))
+    };
+    let mut ordered: Vec<(usize, &WorktreeInfo)> = Vec::new();
+    for worktree in worktrees {
+        let parent = worktree
+            .branch
+            .as_deref()
+            .and_then(|branch| metadata.stack_parent(spec, branch))
+            .and_then(|parent| find(&parent.branch));
+        if parent.is_some() {
+            continue;
+        }
+        let mut pending = vec![(0, worktree)];
+        while let Some((depth, worktree)) = pending.pop() {
+            if ordered.iter().any(|(_, seen)| seen.path == worktree.path) {
+                continue;
+            }
+            ordered.push((depth, worktree));
+            if let Some(branch) = &worktree.branch {
+                let children = metadata.stack_children(spec, branch);
+                pending.extend(
+                    children
+                        .iter()
+                        .rev()
+                        .filter_map(|child| find(child))
+                        .map(|child| (depth + 1, child)),
+                );
+            }
+        }
+    }
+    // Branches stacked in a cycle have no root; list them flat
+    for worktree in worktrees {
+        if !ordered.iter().any(|(_, seen)| seen.path == worktree.path) {
+            ordered.push((0, worktree));
+        }
+    }
+    ordered
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn test_execute() {
-        let result = execute();
-        assert!(result.is_ok());
+        // Lists the worktrees of the repository the tests run in
+        let options = |json, format: &str, print0| ListOptions {
+            json,
+            format: (!format.is_empty()).then(|| format.to_string()),
+            print0,
+            sort: Some(SortKey::Name),
+            stack: false,
+        };
+        assert!(execute(Config::default(), options(false, "", false)).is_ok());
+        assert!(execute(Config::default(), options(true, "", false)).is_ok());
+        assert!(execute(Config::default(), options(false, "{branch}", true)).is_ok());
+        assert!(execute(Config::default(), options(false, "{owner}", false)).is_err());
+    }
+
+    #[test]
+    fn test_list() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo_path = temp_dir.path().join("github.com/user/repo");
+        crate::test_utils::create_managed_repository(&repo_path);
+
+        let worktrees = list(&Config::default(), &repo_path.join("main"), None).unwrap();
+        assert_eq!(worktrees.len(), 1);
+        assert_eq!(worktrees[0].branch.as_deref(), Some("main"));
+        assert!(list(&Config::default(), temp_dir.path(), None).is_err());
+    }
+
+    #[test]
+    fn test_stack_order() {
+        let worktree = |branch: &str| WorktreeInfo {
+            path: Path::new("/repo").join(branch),
+            branch: Some(branch.to_string()),
+        };
+        let worktrees = ["api", "main", "ui", "ui-tests"].map(worktree);
+        let mut metadata = Metadata::default();
+        let mut stack = |branch: &str, parent: &str| {
+            metadata.set_stack_parent(
+                "github.com/user/repo",
+                branch,
+                crate::metadata::StackParent {
+                    branch: parent.to_string(),
+                    base: String::new(),
+                },
+            )
+        };
+        stack("ui-tests", "ui");
+        stack("ui", "api");
+        // A parent without a worktree doesn't hide its children
+        stack("api", "gone");
+
+        let order: Vec<_> = stack_order(&worktrees, &metadata, "github.com/user/repo")
+            .into_iter()
+            .map(|(depth, worktree)| (depth, worktree.branch.as_deref().unwrap()))
+            .collect();
+        assert_eq!(order, [(0, "api"), (1, "ui"), (2, "ui-tests"), (0, "main")]);
     }
 }
@@ -1,6 +1,55 @@
+use crate::{
+    config::Config,
+    events::{self, Event},
+    lock,
+    metadata::Metadata,
+};
+use anyhow::Result;
+use git2::Repository;
+use std::path::Path;
+use tracing::warn;
+
 pub mod clean;
 pub mod create;
+pub mod diff;
+pub mod fix_upstream;
+pub mod info;
 pub mod list;
+pub mod open;
+pub mod push;
+pub mod rebase;
 pub mod remove;
+pub mod review;
+pub mod scratch;
 pub mod status;
 pub mod switch;
+
+/// Runs `sweep` on each repository `repositories` names in the metadata of
+/// a root, holding the repository's lock, and emits the events it returns,
+/// as the daemon does to remove scratch and review worktrees. A repository
+/// that fails is logged and skipped.
+pub(crate) fn sweep_repositories(
+    config: &Config,
+    repositories: impl Fn(&Metadata) -> Vec<String>,
+    sweep: impl Fn(&Config, &Repository, &Path, &str) -> Result<Vec<Event>>,
+) -> Result<()> {
+    for root in config.all_roots() {
+        for spec in repositories(&Metadata::load(&root.path)?) {
+            let removed = Repository::open_bare(root.path.join(&spec).join(".git"))
+                .map_err(anyhow::Error::from)
+                .and_then(|repo| {
+                    let _lock = lock::lock_shared(&repo)?;
+                    sweep(config, &repo, &root.path, &spec)
+                });
+            match removed {
+                Ok(removed) => {
+                    for event in &removed {
+                        events::emit(config, event);
+                    }
+                }
+                Err(error) => warn!("{spec}: {error:#}"),
+            }
+        }
+    }
+    Ok(())
+}
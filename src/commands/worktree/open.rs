@@ -0,0 +1,114 @@
+use super::switch::{find_worktree_path, not_found_message, pick_worktree};
+use crate::{config::Config, exit, history::History, repo_context, scan::RepoEntry, status, tmux};
+use anyhow::{Context, Result, anyhow, bail};
+use git2::Repository;
+use std::path::Path;
+use std::process::Command;
+
+/// Opens the worktree for `branch`, or one chosen in the picker, in a tmux
+/// session named `repo@branch` or with `open.command`.
+pub fn execute(config: Config, branch: Option<String>, use_tmux: bool) -> Result<()> {
+    let repo = repo_context::current_repo()?.open()?;
+    let worktree_path = match branch {
+        Some(branch) => match find_worktree_path(&repo, &branch)? {
+            Some(worktree_path) => worktree_path,
+            None => return Err(exit::not_found(false, not_found_message(&repo, &branch)?)),
+        },
+        None => pick_worktree(&config, &repo)?.ok_or_else(|| anyhow!("No worktree selected"))?,
+    };
+    let repo_dir = repo.path().parent().unwrap_or(repo.path());
+    let session = session_name(repo_dir, &worktree_path);
+    History::record(&config, &worktree_path).ok();
+
+    if use_tmux {
+        return tmux::open(&session, &worktree_path);
+    }
+    match &config.open_command {
+        Some(command) => run_open_command(command, repo_dir, &worktree_path, &session),
+        None => bail!("Nothing to open the worktree with: pass --tmux or set open.command"),
+    }
+}
+
+/// Returns `repo@branch` for the worktree, using its directory name when
+/// HEAD is detached.
+fn session_name(repo_dir: &Path, worktree_path: &Path) -> String {
+    let name = |path: &Path| {
+        path.file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned()
+    };
+    let branch = Repository::open(worktree_path)
+        .ok()
+        .and_then(|repo| status::head_branch(&repo))
+        .unwrap_or_else(|| name(worktree_path));
+    tmux::session_name(&name(repo_dir), &branch)
+}
+
+/// Runs `open.command` through `sh -c` in the worktree, which it finds in
+/// `NEOGHQ_WORKTREE` along with `NEOGHQ_REPO` and `NEOGHQ_SESSION`.
+fn run_open_command(
+    command: &str,
+    repo_dir: &Path,
+    worktree_path: &Path,
+    session: &str,
+) -> Result<()> {
+    let repo = RepoEntry::from_path(repo_dir)
+        .map_or_else(|| repo_dir.display().to_string(), |entry| entry.spec());
+    let status = Command::new("sh")
+        .args(["-c", command])
+        .current_dir(worktree_path)
+        .env("NEOGHQ_WORKTREE", worktree_path)
+        .env("NEOGHQ_REPO", repo)
+        .env("NEOGHQ_SESSION", session)
+        .status()
+        .with_context(|| format!("Failed to run open command {command}"))?;
+    if !status.success() {
+        bail!("Open command {command} failed with {status}");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_managed_repository;
+
+    #[test]
+    fn test_session_name() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo_dir = temp_dir.path().join("github.com/user/my.repo");
+        create_managed_repository(&repo_dir);
+
+        assert_eq!(
+            session_name(&repo_dir, &repo_dir.join("main")),
+            "my_repo@main"
+        );
+        assert_eq!(
+            session_name(&repo_dir, &repo_dir.join("missing")),
+            "my_repo@missing"
+        );
+    }
+
+    #[test]
+    fn test_run_open_command() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo_dir = temp_dir.path().join("github.com/user/repo");
+        create_managed_repository(&repo_dir);
+        let worktree = repo_dir.join("main");
+        let log = temp_dir.path().join("log");
+
+        let command = format!(
+            "echo \"$NEOGHQ_REPO $NEOGHQ_SESSION $(basename \"$PWD\")\" > {}",
+            log.display()
+        );
+        run_open_command(&command, &repo_dir, &worktree, "repo@main").unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&log).unwrap(),
+            "github.com/user/repo repo@main main\n"
+        );
+
+        assert!(run_open_command("exit 1", &repo_dir, &worktree, "repo@main").is_err());
+    }
+}
@@ -0,0 +1,147 @@
+use crate::{
+    commands::repo::get::{force_push_with_lease, push_remote},
+    config::Config,
+    dry_run,
+    repo_context::{self, RepoContext},
+    status::{self, Upstream},
+};
+use anyhow::{Result, anyhow, bail};
+use git2::{BranchType, Repository};
+use tracing::info;
+
+/// Pushes the branch of the current worktree to the remote it tracks, or to
+/// `origin` the first time, and then makes it track the pushed branch so
+/// the first push needs no `-u`.
+pub fn execute(config: Config, force_with_lease: bool) -> Result<()> {
+    push(&config, &repo_context::current_repo()?, force_with_lease)
+}
+
+fn push(config: &Config, context: &RepoContext, force_with_lease: bool) -> Result<()> {
+    let worktree = context
+        .worktree
+        .as_ref()
+        .ok_or_else(|| anyhow!("Not inside a worktree"))?;
+    let branch = status::head_branch(&Repository::open(worktree)?)
+        .ok_or_else(|| anyhow!("HEAD is detached; check out a branch to push"))?;
+    let repo = context.open()?;
+    let reference = format!("refs/heads/{branch}");
+    let remote = repo
+        .branch_upstream_remote(&reference)
+        .ok()
+        .and_then(|name| name.as_str().map(str::to_string))
+        .unwrap_or_else(|| "origin".to_string());
+    if repo.find_remote(&remote).is_err() {
+        bail!("No remote {remote} to push {branch} to");
+    }
+
+    if force_with_lease {
+        let expected = repo
+            .refname_to_id(&format!("refs/remotes/{remote}/{branch}"))
+            .ok();
+        dry_run::apply(
+            config,
+            format_args!("force-push {branch} to {remote} unless it changed there"),
+            || {
+                force_push_with_lease(
+                    &context.git_dir,
+                    &remote,
+                    &reference,
+                    expected,
+                    &config.credentials,
+                )
+            },
+        )?;
+    } else {
+        dry_run::apply(config, format_args!("push {branch} to {remote}"), || {
+            push_remote(
+                &context.git_dir,
+                &remote,
+                &[&format!("{reference}:{reference}")],
+                &config.credentials,
+            )
+        })?;
+    }
+    if config.dry_run {
+        return Ok(());
+    }
+
+    match status::upstream_state(&repo, &branch)? {
+        Upstream::Tracking { .. } => println!("Pushed {branch} to {remote}"),
+        Upstream::Missing | Upstream::Gone { .. } => {
+            let upstream = format!("{remote}/{branch}");
+            repo.find_branch(&branch, BranchType::Local)?
+                .set_upstream(Some(&upstream))?;
+            info!("{branch} now tracks {upstream}");
+            println!("Pushed {branch} to {remote}, now tracking {upstream}");
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use crate::{
+        commands::repo::get::fetch_remote,
+        test_utils::{commit_all, create_managed_repository},
+    };
+
+    #[test]
+    fn test_push_sets_upstream_and_leases() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo_dir = temp_dir.path().join("github.com/user/repo");
+        let bare = create_managed_repository(&repo_dir);
+        let origin_path = temp_dir.path().join("origin.git");
+        let origin = Repository::init_bare(&origin_path).unwrap();
+        bare.remote_set_url("origin", origin_path.to_str().unwrap())
+            .unwrap();
+        let head = bare.head().unwrap().peel_to_commit().unwrap();
+        let branch = bare.branch("feature", &head, false).unwrap();
+        let mut opts = git2::WorktreeAddOptions::new();
+        opts.reference(Some(branch.get()));
+        let worktree_path = repo_dir.join("feature");
+        bare.worktree("feature", &worktree_path, Some(&opts))
+            .unwrap();
+        let context = repo_context::from_path(&worktree_path).unwrap();
+        let config = Config::default();
+
+        push(&config, &context, false).unwrap();
+        assert_eq!(
+            origin.refname_to_id("refs/heads/feature").unwrap(),
+            head.id()
+        );
+        assert!(matches!(
+            status::upstream_state(&bare, "feature").unwrap(),
+            Upstream::Tracking { name, .. } if name == "origin/feature"
+        ));
+
+        // Rewrite a pushed commit, then let someone else move the branch
+        let worktree = Repository::open(&worktree_path).unwrap();
+        std::fs::write(worktree_path.join("NOTES"), "notes\n").unwrap();
+        let pushed = commit_all(&worktree, "Add notes");
+        push(&config, &context, false).unwrap();
+        let base = worktree.find_object(head.id(), None).unwrap();
+        worktree.reset(&base, git2::ResetType::Soft, None).unwrap();
+        let rewritten = commit_all(&worktree, "Add notes, reworded");
+        assert!(push(&config, &context, false).is_err());
+        assert_ne!(pushed, rewritten);
+        origin
+            .reference("refs/heads/feature", head.id(), true, "someone else")
+            .unwrap();
+        assert!(push(&config, &context, true).is_err());
+
+        fetch_remote(
+            &context.git_dir,
+            "origin",
+            &config.credentials,
+            config.fetch_prune,
+        )
+        .unwrap();
+        push(&config, &context, true).unwrap();
+        assert_eq!(
+            origin.refname_to_id("refs/heads/feature").unwrap(),
+            rewritten
+        );
+    }
+}
@@ -0,0 +1,475 @@
+use crate::{
+    config::Config,
+    dry_run,
+    exit::{self, ErrorKind},
+    lock::RepoLock,
+    metadata::{Metadata, StackParent},
+    repo_context::{self, RepoContext},
+    status,
+    transport::run_git,
+};
+use anyhow::{Context, Result, anyhow, bail};
+use git2::{Oid, Repository, RepositoryState, build::CheckoutBuilder};
+use std::path::Path;
+use tracing::info;
+
+/// Rebases the branch of the current worktree onto its stack parent, or
+/// onto the default branch when it isn't stacked. With `stack`, the
+/// branches stacked on it are restacked too, each onto its rebased parent.
+/// With `resolve`, a branch whose commits conflict is rebased by git in its
+/// worktree, opening `git mergetool` on each conflict, and the run goes on
+/// once it's resolved.
+pub fn execute(config: Config, stack: bool, resolve: bool) -> Result<()> {
+    rebase(&config, &repo_context::current_repo()?, stack, resolve)
+}
+
+fn rebase(config: &Config, context: &RepoContext, stack: bool, resolve: bool) -> Result<()> {
+    if resolve && config.no_input {
+        return Err(exit::error(
+            ErrorKind::InputRequired,
+            "--resolve opens git mergetool, which --no-input rules out",
+        ));
+    }
+    let worktree = context
+        .worktree
+        .as_ref()
+        .ok_or_else(|| anyhow!("Not inside a worktree"))?;
+    let branch = status::head_branch(&Repository::open(worktree)?)
+        .ok_or_else(|| anyhow!("HEAD is detached; check out a branch to rebase"))?;
+    let (root, spec) = context.metadata_key()?;
+    // Restacking works on a copy; only the stacked branches' records are
+    // written back, into the metadata as it is by then
+    let mut metadata = Metadata::load(&root)?;
+    let repo = context.open()?;
+
+    let mut branches = vec![branch];
+    if stack {
+        let mut next = 0;
+        while next < branches.len() {
+            for child in metadata.stack_children(&spec, &branches[next]) {
+                if !branches.contains(&child) {
+                    branches.push(child);
+                }
+            }
+            next += 1;
+        }
+    }
+    for branch in &branches {
+        restack(
+            config,
+            context,
+            &repo,
+            &mut metadata,
+            &spec,
+            branch,
+            resolve,
+        )?;
+    }
+    if !config.dry_run {
+        Metadata::update(&root, |current| {
+            for branch in &branches {
+                if let Some(parent) = metadata.stack_parent(&spec, branch) {
+                    current.set_stack_parent(&spec, branch, parent.clone());
+                }
+            }
+            Ok(())
+        })?;
+    }
+    Ok(())
+}
+
+/// Rebases `branch` onto its parent. Only the commits after the parent
+/// commit it was based on are replayed, so a parent that was amended or
+/// rebased itself doesn't drag its old commits along; a parent that was
+/// deleted, as after merging it, hands the branch to its own parent.
+fn restack(
+    config: &Config,
+    context: &RepoContext,
+    repo: &Repository,
+    metadata: &mut Metadata,
+    spec: &str,
+    branch: &str,
+    resolve: bool,
+) -> Result<()> {
+    let tip = repo
+        .refname_to_id(&format!("refs/heads/{branch}"))
+        .with_context(|| format!("There is no branch {branch}"))?;
+    let recorded = metadata.stack_parent(spec, branch).cloned();
+    let onto_branch = match &recorded {
+        Some(parent) => existing_ancestor(repo, metadata, spec, &parent.branch)?,
+        None => status::default_branch(repo)
+            .ok_or_else(|| anyhow!("HEAD of the repository is detached"))?,
+    };
+    let onto = repo.refname_to_id(&format!("refs/heads/{onto_branch}"))?;
+    let base = match &recorded {
+        Some(parent) => Oid::from_str(&parent.base)?,
+        None => repo.merge_base(tip, onto)?,
+    };
+    let restacked = |metadata: &mut Metadata| {
+        if recorded.is_some() {
+            metadata.set_stack_parent(
+                spec,
+                branch,
+                StackParent {
+                    branch: onto_branch.clone(),
+                    base: onto.to_string(),
+                },
+            );
+        }
+    };
+    if tip == onto || repo.graph_descendant_of(tip, onto)? {
+        println!("{branch}: up to date with {onto_branch}");
+        restacked(metadata);
+        return Ok(());
+    }
+
+    let worktree = status::list_worktrees(repo)?
+        .into_iter()
+        .find(|worktree| worktree.branch.as_deref() == Some(branch));
+    if let Some(worktree) = &worktree
+        && status::is_worktree_dirty(&worktree.path)?
+    {
+        bail!(
+            "The {branch} worktree at {} has uncommitted changes",
+            worktree.path.display()
+        );
+    }
+    let (count, _) = repo.graph_ahead_behind(tip, base)?;
+    dry_run::apply(
+        config,
+        format_args!("rebase {count} commits of {branch} onto {onto_branch}"),
+        || {
+            let lock = RepoLock::acquire(&context.git_dir)?;
+            let new_tip = match replay(repo, tip, base, onto) {
+                Ok(new_tip) => new_tip,
+                Err(error) if resolve && error.is::<ReplayConflict>() => {
+                    // Not held while the mergetool waits on the user
+                    drop(lock);
+                    let worktree = worktree.as_ref().ok_or_else(|| {
+                        anyhow!(
+                            "{branch} has no worktree to resolve its conflicts in; \
+                             add one with `neoghq worktree create {branch}`"
+                        )
+                    })?;
+                    return rebase_with_mergetool(&worktree.path, branch, base, onto);
+                }
+                Err(error) => {
+                    return Err(error).with_context(|| {
+                        format!(
+                            "Rebase it yourself with `git rebase --onto {onto_branch} {base} {branch}`"
+                        )
+                    });
+                }
+            };
+            repo.reference(
+                &format!("refs/heads/{branch}"),
+                new_tip,
+                true,
+                "neoghq: restack",
+            )?;
+            if let Some(worktree) = &worktree {
+                // Clean, so the checkout only brings in the rebased files
+                Repository::open(&worktree.path)?
+                    .checkout_head(Some(CheckoutBuilder::new().force()))?;
+            }
+            Ok(())
+        },
+    )?;
+    if !config.dry_run {
+        println!("{branch}: rebased {count} commits onto {onto_branch}");
+    }
+    restacked(metadata);
+    Ok(())
+}
+
+/// Rebases `branch`, checked out at `worktree_path`, with git, opening
+/// `git mergetool` on each commit that conflicts and continuing once it's
+/// resolved. Conflicts left unresolved abort the rebase.
+fn rebase_with_mergetool(worktree_path: &Path, branch: &str, base: Oid, onto: Oid) -> Result<()> {
+    let git = |args: &[&str]| run_git(Some(worktree_path), true, args);
+    let in_conflict = || -> Result<bool> {
+        let worktree = Repository::open(worktree_path)?;
+        Ok(worktree.state() != RepositoryState::Clean && worktree.index()?.has_conflicts())
+    };
+    let mut result = git(&["rebase", "--onto", &onto.to_string(), &base.to_string()]);
+    while let Err(error) = result {
+        if !in_conflict()? {
+            if Repository::open(worktree_path)?.state() != RepositoryState::Clean {
+                git(&["rebase", "--abort"])?;
+            }
+            return Err(error.context(format!("Failed to rebase {branch}")));
+        }
+        info!(
+            "Resolving the conflicts of {branch} in {}",
+            worktree_path.display()
+        );
+        // Whatever the tool exits with, the conflicts it leaves decide
+        let _ = git(&["mergetool", "--no-prompt"]);
+        if in_conflict()? {
+            git(&["rebase", "--abort"])?;
+            bail!(
+                "The conflicts of {branch} in {} were left unresolved; its rebase was aborted",
+                worktree_path.display()
+            );
+        }
+        result = git(&["-c", "core.editor=true", "rebase", "--continue"]);
+    }
+    Ok(())
+}
+
+/// Returns `branch`, or when it was deleted the closest stack ancestor that
+/// still exists, else the default branch.
+fn existing_ancestor(
+    repo: &Repository,
+    metadata: &Metadata,
+    spec: &str,
+    branch: &str,
+) -> Result<String> {
+    let mut branch = branch.to_string();
+    // A hand-edited metadata file could hold a cycle
+    for _ in 0..64 {
+        if repo.find_branch(&branch, git2::BranchType::Local).is_ok() {
+            return Ok(branch);
+        }
+        match metadata.stack_parent(spec, &branch) {
+            Some(parent) => branch = parent.branch.clone(),
+            None => break,
+        }
+    }
+    status::default_branch(repo).ok_or_else(|| anyhow!("HEAD of the repository is detached"))
+}
+
+/// A commit that conflicts with the commits it's replayed onto.
+#[derive(Debug, thiserror::Error)]
+#[error("{0} conflicts with the commits it's replayed onto")]
+pub(crate) struct ReplayConflict(Oid);
+
+/// Replays the commits reachable from `tip` but not from `upstream` on top
+/// of `onto`, in memory so no worktree is touched, and returns the new tip.
+/// Commits `onto` already has are dropped; conflicts abort the rebase.
+pub(crate) fn replay(repo: &Repository, tip: Oid, upstream: Oid, onto: Oid) -> Result<Oid> {
+    let branch = repo.find_annotated_commit(tip)?;
+    let upstream = repo.find_annotated_commit(upstream)?;
+    let onto_commit = repo.find_annotated_commit(onto)?;
+    let mut options = git2::RebaseOptions::new();
+    options.inmemory(true);
+    let mut rebase = repo.rebase(
+        Some(&branch),
+        Some(&upstream),
+        Some(&onto_commit),
+        Some(&mut options),
+    )?;
+    let committer = repo.signature()?;
+    let mut new_tip = onto;
+    while let Some(operation) = rebase.next() {
+        let id = operation?.id();
+        if rebase.inmemory_index()?.has_conflicts() {
+            rebase.abort()?;
+            return Err(ReplayConflict(id).into());
+        }
+        match rebase.commit(None, &committer, None) {
+            Ok(id) => new_tip = id,
+            Err(error) if error.code() == git2::ErrorCode::Applied => {}
+            Err(error) => return Err(error.into()),
+        }
+    }
+    rebase.finish(Some(&committer))?;
+    Ok(new_tip)
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use crate::test_utils::{commit_all, create_managed_repository};
+    use std::path::Path;
+
+    fn add_worktree(repo: &Repository, repo_dir: &Path, name: &str, from: Oid) -> Repository {
+        let commit = repo.find_commit(from).unwrap();
+        let branch = repo.branch(name, &commit, false).unwrap();
+        let mut opts = git2::WorktreeAddOptions::new();
+        opts.reference(Some(branch.get()));
+        repo.worktree(name, &repo_dir.join(name), Some(&opts))
+            .unwrap();
+        Repository::open(repo_dir.join(name)).unwrap()
+    }
+
+    fn commit_file(worktree: &Repository, name: &str) -> Oid {
+        let dir = worktree.workdir().unwrap();
+        std::fs::write(dir.join(name), name).unwrap();
+        commit_all(worktree, &format!("Add {name}"))
+    }
+
+    #[test]
+    fn test_rebase_stack() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        let repo_dir = root.join("github.com/user/repo");
+        let repo = create_managed_repository(&repo_dir);
+        let mut config = repo.config().unwrap();
+        config.set_str("user.name", "Test").unwrap();
+        config.set_str("user.email", "test@example.com").unwrap();
+        let spec = "github.com/user/repo";
+        let main = repo.refname_to_id("refs/heads/main").unwrap();
+
+        let one = add_worktree(&repo, &repo_dir, "one", main);
+        let one_base = commit_file(&one, "one");
+        let two = add_worktree(&repo, &repo_dir, "two", one_base);
+        commit_file(&two, "two");
+        let mut metadata = Metadata::default();
+        metadata.set_stack_parent(
+            spec,
+            "one",
+            StackParent {
+                branch: "main".to_string(),
+                base: main.to_string(),
+            },
+        );
+        metadata.set_stack_parent(
+            spec,
+            "two",
+            StackParent {
+                branch: "one".to_string(),
+                base: one_base.to_string(),
+            },
+        );
+        metadata.save(root).unwrap();
+
+        // Amend one, and move main forward
+        let one_head = one.find_object(main, None).unwrap();
+        one.reset(&one_head, git2::ResetType::Soft, None).unwrap();
+        let amended = commit_all(&one, "Add one, amended");
+        let main_worktree = Repository::open(repo_dir.join("main")).unwrap();
+        commit_file(&main_worktree, "news");
+
+        let context = repo_context::from_path(&repo_dir.join("one")).unwrap();
+        rebase(&Config::default(), &context, true, false).unwrap();
+
+        let new_main = repo.refname_to_id("refs/heads/main").unwrap();
+        let new_one = repo.find_reference("refs/heads/one").unwrap();
+        let new_one = new_one.peel_to_commit().unwrap();
+        assert_eq!(new_one.parent_id(0).unwrap(), new_main);
+        assert_ne!(new_one.id(), amended);
+        let new_two = repo
+            .find_reference("refs/heads/two")
+            .unwrap()
+            .peel_to_commit()
+            .unwrap();
+        // Only two's own commit is replayed, not the old version of one
+        assert_eq!(new_two.parent_id(0).unwrap(), new_one.id());
+        assert!(repo_dir.join("two/news").is_file());
+        assert!(repo_dir.join("two/two").is_file());
+        let metadata = Metadata::load(root).unwrap();
+        assert_eq!(
+            metadata.stack_parent(spec, "two").unwrap().base,
+            new_one.id().to_string()
+        );
+
+        // Once one is squash-merged and deleted, two moves to main
+        let merged = commit_file(&main_worktree, "one");
+        repo.find_worktree("one")
+            .unwrap()
+            .prune(Some(
+                git2::WorktreePruneOptions::new()
+                    .valid(true)
+                    .working_tree(true),
+            ))
+            .unwrap();
+        repo.find_branch("one", git2::BranchType::Local)
+            .unwrap()
+            .delete()
+            .unwrap();
+        let context = repo_context::from_path(&repo_dir.join("two")).unwrap();
+        rebase(&Config::default(), &context, false, false).unwrap();
+        let new_two = repo
+            .find_reference("refs/heads/two")
+            .unwrap()
+            .peel_to_commit()
+            .unwrap();
+        assert_eq!(new_two.parent_id(0).unwrap(), merged);
+        assert_eq!(
+            Metadata::load(root)
+                .unwrap()
+                .stack_parent(spec, "two")
+                .unwrap()
+                .branch,
+            "main"
+        );
+    }
+
+    #[test]
+    fn test_rebase_stack_resolves_conflicts_with_mergetool() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        let repo_dir = root.join("github.com/user/repo");
+        let repo = create_managed_repository(&repo_dir);
+        let mut config = repo.config().unwrap();
+        config.set_str("user.name", "Test").unwrap();
+        config.set_str("user.email", "test@example.com").unwrap();
+        config.set_bool("mergetool.keepBackup", false).unwrap();
+        config
+            .set_bool("mergetool.theirs.trustExitCode", true)
+            .unwrap();
+        let spec = "github.com/user/repo";
+        let main = repo.refname_to_id("refs/heads/main").unwrap();
+
+        let one = add_worktree(&repo, &repo_dir, "one", main);
+        std::fs::write(repo_dir.join("one/README"), "one\n").unwrap();
+        let one_tip = commit_all(&one, "Change README on one");
+        let two = add_worktree(&repo, &repo_dir, "two", one_tip);
+        commit_file(&two, "two");
+        let mut metadata = Metadata::default();
+        metadata.set_stack_parent(
+            spec,
+            "two",
+            StackParent {
+                branch: "one".to_string(),
+                base: one_tip.to_string(),
+            },
+        );
+        metadata.save(root).unwrap();
+        let main_worktree = Repository::open(repo_dir.join("main")).unwrap();
+        std::fs::write(repo_dir.join("main/README"), "main\n").unwrap();
+        let new_main = commit_all(&main_worktree, "Change README on main");
+        let context = repo_context::from_path(&repo_dir.join("one")).unwrap();
+
+        // Without --resolve, or with a tool that gives up, nothing moves
+        assert!(rebase(&Config::default(), &context, true, false).is_err());
+        config.set_str("mergetool.theirs.cmd", "false").unwrap();
+        config.set_str("merge.tool", "theirs").unwrap();
+        assert!(rebase(&Config::default(), &context, true, true).is_err());
+        assert_eq!(repo.refname_to_id("refs/heads/one").unwrap(), one_tip);
+        assert_eq!(one.state(), RepositoryState::Clean);
+        let no_input = Config {
+            no_input: true,
+            ..Default::default()
+        };
+        assert!(rebase(&no_input, &context, true, true).is_err());
+
+        config
+            .set_str("mergetool.theirs.cmd", "cp \"$REMOTE\" \"$MERGED\"")
+            .unwrap();
+        rebase(&Config::default(), &context, true, true).unwrap();
+        let new_one = repo
+            .find_reference("refs/heads/one")
+            .unwrap()
+            .peel_to_commit()
+            .unwrap();
+        assert_eq!(new_one.parent_id(0).unwrap(), new_main);
+        assert_eq!(
+            std::fs::read_to_string(repo_dir.join("one/README")).unwrap(),
+            "one\n"
+        );
+        // The rest of the stack carried on after the resolution
+        let new_two = repo
+            .find_reference("refs/heads/two")
+            .unwrap()
+            .peel_to_commit()
+            .unwrap();
+        assert_eq!(new_two.parent_id(0).unwrap(), new_one.id());
+        assert_eq!(
+            std::fs::read_to_string(repo_dir.join("two/README")).unwrap(),
+            "one\n"
+        );
+    }
+}
@@ -1,6 +1,173 @@
-use anyhow::Result;
+use super::switch::{find_worktree_path, not_found_message};
+use crate::{
+    config::Config,
+    dry_run,
+    events::{self, Event, EventKind},
+    exit::{self, ErrorKind},
+    lock, repo_context, status,
+    trash::{self, TrashKind},
+};
+use anyhow::{Result, anyhow};
+use git2::{Repository, WorktreePruneOptions};
+use std::path::Path;
+use tracing::info;
 
-pub fn execute(path: String) -> Result<()> {
-    println!("Remove command: {path}");
+/// Removes the worktree with `branch` checked out, or whose directory is
+/// named `branch`. The branch itself is kept, and uncommitted changes only
+/// with `force`.
+pub fn execute(config: Config, branch: String, force: bool) -> Result<()> {
+    let repo = repo_context::current_repo()?.open()?;
+    let lock = lock::lock_shared(&repo)?;
+    let Some(worktree_path) = find_worktree_path(&repo, &branch)? else {
+        return Err(exit::not_found(false, not_found_message(&repo, &branch)?));
+    };
+    if !force && status::is_worktree_dirty(&worktree_path)? {
+        return Err(exit::error(
+            ErrorKind::DirtyWorktree,
+            format!(
+                "Worktree has uncommitted changes: {} (use --force to remove it anyway)",
+                worktree_path.display()
+            ),
+        ));
+    }
+
+    let event = remove_worktree(&config, &repo, &worktree_path)?;
+    drop(lock);
+    if let Some(event) = event {
+        events::emit(&config, &event);
+    }
     Ok(())
 }
+
+/// Deletes the linked worktree at `worktree_path` and its administrative
+/// files in `repo`. Returns the event for the hooks, which the caller emits
+/// once it released the repository lock; none with `--dry-run`.
+pub(super) fn remove_worktree(
+    config: &Config,
+    repo: &Repository,
+    worktree_path: &Path,
+) -> Result<Option<Event>> {
+    let target = worktree_path.canonicalize()?;
+    let worktree = repo
+        .worktrees()?
+        .iter()
+        .flatten()
+        .filter_map(|name| repo.find_worktree(name).ok())
+        .find(|worktree| {
+            worktree
+                .path()
+                .canonicalize()
+                .is_ok_and(|path| path == target)
+        })
+        .ok_or_else(|| {
+            anyhow!(
+                "Not a linked worktree: {}; only worktrees added to the repository can be removed",
+                worktree_path.display()
+            )
+        })?;
+
+    let branch = Repository::open(worktree_path)
+        .ok()
+        .and_then(|worktree| status::head_branch(&worktree));
+    let mut event = None;
+    dry_run::apply(
+        config,
+        format_args!("remove worktree {}", worktree_path.display()),
+        || {
+            let record = worktree
+                .name()
+                .map(|name| repo.path().join("worktrees").join(name));
+            match trash::put(
+                config,
+                TrashKind::Worktree,
+                worktree_path,
+                record.as_deref(),
+            )? {
+                Some(entry) => info!(
+                    "Moved worktree {} to the trash as {}; `neoghq undo` restores it",
+                    worktree_path.display(),
+                    entry.id
+                ),
+                None => {
+                    worktree.prune(Some(
+                        WorktreePruneOptions::new().valid(true).working_tree(true),
+                    ))?;
+                    info!("Removed worktree {}", worktree_path.display());
+                }
+            }
+            let repo_path = repo.path().parent().unwrap_or(repo.path());
+            event = Some(Event::worktree(
+                EventKind::WorktreeRemoved,
+                repo_path,
+                worktree_path,
+                branch.as_deref(),
+            ));
+            Ok(())
+        },
+    )?;
+    Ok(event)
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_managed_repository;
+
+    #[test]
+    fn test_remove_worktree() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo_dir = temp_dir.path().join("repo");
+        let repo = create_managed_repository(&repo_dir);
+        let worktree_path = repo_dir.join("main");
+
+        let dry_run = Config {
+            dry_run: true,
+            ..Default::default()
+        };
+        let event = remove_worktree(&dry_run, &repo, &worktree_path).unwrap();
+        assert!(worktree_path.exists());
+        assert!(event.is_none());
+
+        let event = remove_worktree(&Config::default(), &repo, &worktree_path)
+            .unwrap()
+            .unwrap();
+        assert!(!worktree_path.exists());
+        assert_eq!(event.event, EventKind::WorktreeRemoved);
+        assert_eq!(event.branch.as_deref(), Some("main"));
+        assert_eq!(event.repo_path, repo_dir);
+        assert!(repo.worktrees().unwrap().is_empty());
+        assert!(repo.find_branch("main", git2::BranchType::Local).is_ok());
+    }
+
+    #[test]
+    fn test_remove_worktree_moves_it_to_trash() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo_dir = temp_dir.path().join("github.com/user/repo");
+        let repo = create_managed_repository(&repo_dir);
+        let worktree_path = repo_dir.join("main");
+        let config = Config {
+            root: temp_dir.path().to_path_buf(),
+            trash_retention_days: 30,
+            ..Default::default()
+        };
+
+        remove_worktree(&config, &repo, &worktree_path).unwrap();
+        assert!(!worktree_path.exists());
+        assert!(repo.worktrees().unwrap().is_empty());
+
+        let entries = trash::list(&config).unwrap();
+        trash::restore(&entries[0]).unwrap();
+        assert_eq!(repo.worktrees().unwrap().len(), 1);
+        assert!(repo.find_worktree("main").unwrap().validate().is_ok());
+    }
+
+    #[test]
+    fn test_remove_worktree_rejects_main_working_tree() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo = crate::test_utils::create_source_repository(temp_dir.path());
+
+        assert!(remove_worktree(&Config::default(), &repo, temp_dir.path()).is_err());
+        assert!(temp_dir.path().join("README").exists());
+    }
+}
@@ -0,0 +1,415 @@
+use super::remove::remove_worktree;
+use crate::{
+    commands::repo::get::fetch_refspecs,
+    config::Config,
+    dry_run,
+    events::{self, Event, EventKind},
+    forge::{self, Forge, PullRequestState},
+    giturl,
+    lock::RepoLock,
+    metadata::{Metadata, Review},
+    repo_context,
+    repo_settings::RepoSettings,
+    status,
+    transport::run_git,
+};
+use anyhow::{Context, Result, anyhow, bail};
+use git2::Repository;
+use std::path::Path;
+use tracing::{info, warn};
+
+/// Adds a detached worktree for reviewing `target` and prints its path:
+/// `review-<number>` for a pull request of `origin`, by number, optionally
+/// with `#`, or URL, else `review-<branch>` for a branch of `origin`. It's
+/// recorded in the root's metadata as a review, which `status` doesn't
+/// count as dirty, and `worktree clean` or the daemon removes it once the
+/// pull request is merged or closed, or the branch is merged or deleted.
+/// With `read_only`, its tracked files are made read-only too.
+pub fn execute(config: Config, target: String, read_only: bool) -> Result<()> {
+    let context = repo_context::current_repo()?;
+    if context.is_plain_clone() {
+        bail!(
+            "{} is a plain clone; convert it with `neoghq repo adopt --in-place {}` first",
+            context.repo_dir.display(),
+            context.repo_dir.display()
+        );
+    }
+    let (root, spec) = context.metadata_key()?;
+    let repo = context.open()?;
+    let review = parse_target(&repo, &target)?;
+    let name = match &review {
+        Review::PullRequest(number) => format!("review-{number}"),
+        Review::Branch(branch) => format!("review-{}", branch.replace('/', "-")),
+    };
+    let worktree_path = context.repo_dir.join(&name);
+    if worktree_path.exists() {
+        bail!(
+            "{} already exists; remove it with `neoghq worktree remove {name}` first",
+            worktree_path.display()
+        );
+    }
+    let source = match &review {
+        Review::PullRequest(number) => {
+            let (forge, owner, repo_name) = origin_forge(&config, &repo)?;
+            let pull_request = forge.pull_request(&owner, &repo_name, *number)?;
+            if pull_request.state != PullRequestState::Open {
+                bail!("Pull request #{number} is no longer open");
+            }
+            info!("Pull request #{number}: {}", pull_request.title);
+            forge.pull_request_ref(*number)
+        }
+        Review::Branch(branch) => format!("refs/heads/{branch}"),
+    };
+
+    let settings = RepoSettings::load(&config, &spec, &context.repo_dir)?;
+    dry_run::apply(
+        &config,
+        format_args!(
+            "create review worktree {} for {target}",
+            worktree_path.display()
+        ),
+        || {
+            let _lock = RepoLock::acquire(&context.git_dir)?;
+            add_review(&config, &repo, &worktree_path, &source, read_only)?;
+            Metadata::update(&root, |metadata| {
+                metadata.add_review(&spec, &name, review.clone());
+                Ok(())
+            })?;
+            settings.setup_worktree(&context.repo_dir, &worktree_path)
+        },
+    )?;
+    if config.dry_run {
+        return Ok(());
+    }
+
+    info!("Created review worktree {}", worktree_path.display());
+    events::emit(
+        &config,
+        &Event::worktree(
+            EventKind::WorktreeCreated,
+            &context.repo_dir,
+            &worktree_path,
+            None,
+        ),
+    );
+    println!("{}", worktree_path.display());
+    Ok(())
+}
+
+/// Fetches `source` of `origin`, such as the head of a pull request, and
+/// adds a detached worktree at it to the bare `repo`.
+fn add_review(
+    config: &Config,
+    repo: &Repository,
+    worktree_path: &Path,
+    source: &str,
+    read_only: bool,
+) -> Result<()> {
+    // Fetched to a ref of its own until the worktree's HEAD keeps it
+    let local = format!(
+        "refs/neoghq/review/{}",
+        worktree_path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+    );
+    let mut refspecs = vec![format!("+{source}:{local}")];
+    if let Some(branch) = source.strip_prefix("refs/heads/") {
+        // `worktree clean` tells whether the branch is merged by origin/<branch>
+        refspecs.push(format!("+{source}:refs/remotes/origin/{branch}"));
+    }
+    let refspecs = refspecs.iter().map(String::as_str).collect::<Vec<_>>();
+    fetch_refspecs(repo.path(), "origin", &refspecs, &config.credentials)
+        .with_context(|| format!("Failed to fetch {source} from origin"))?;
+    let mut reference = repo.find_reference(&local)?;
+    let commit = reference.peel_to_commit()?.id();
+    let added = run_git(
+        Some(repo.path()),
+        true,
+        &[
+            "worktree",
+            "add",
+            "--quiet",
+            "--detach",
+            &worktree_path.to_string_lossy(),
+            &commit.to_string(),
+        ],
+    );
+    reference.delete()?;
+    added?;
+    if read_only {
+        set_read_only(worktree_path, true)?;
+    }
+    Ok(())
+}
+
+/// Reads `target` as a pull request of `origin`, by number or URL, or else
+/// as a branch.
+fn parse_target(repo: &Repository, target: &str) -> Result<Review> {
+    if let Ok(number) = target.trim_start_matches('#').parse() {
+        return Ok(Review::PullRequest(number));
+    }
+    if !target.contains("://") {
+        return Ok(Review::Branch(target.to_string()));
+    }
+    let url = forge::parse_pull_request_url(target)?;
+    let origin = origin_url(repo)?;
+    if url.host != origin.host.to_lowercase()
+        || !url.owner.eq_ignore_ascii_case(&origin.owner)
+        || !url.repo.eq_ignore_ascii_case(&origin.repo)
+    {
+        bail!(
+            "{target} isn't a pull request of origin, {}/{}/{}",
+            origin.host,
+            origin.owner,
+            origin.repo
+        );
+    }
+    Ok(Review::PullRequest(url.number))
+}
+
+fn origin_url(repo: &Repository) -> Result<giturl::GitUrl> {
+    let remote = repo
+        .find_remote("origin")
+        .map_err(|_| anyhow!("No origin remote to review"))?;
+    giturl::parse(remote.url().unwrap_or_default())
+}
+
+/// Returns the forge of `origin` and the repository's owner and name on it.
+fn origin_forge(config: &Config, repo: &Repository) -> Result<(Forge, String, String)> {
+    let url = origin_url(repo)?;
+    let forge = Forge::for_host(config, &url.host).ok_or_else(|| {
+        anyhow!(
+            "No known pull requests on {}; set hosts.\"{}\".forge",
+            url.host,
+            url.host
+        )
+    })?;
+    Ok((forge, url.owner, url.repo))
+}
+
+/// Makes the files `worktree` tracks read-only, or writable by their owner
+/// again.
+fn set_read_only(worktree: &Path, read_only: bool) -> Result<()> {
+    let index = Repository::open(worktree)?.index()?;
+    for entry in index.iter() {
+        let path = worktree.join(String::from_utf8_lossy(&entry.path).as_ref());
+        let Ok(metadata) = std::fs::symlink_metadata(&path) else {
+            continue;
+        };
+        if !metadata.is_file() {
+            continue;
+        }
+        let mut permissions = metadata.permissions();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = permissions.mode();
+            permissions.set_mode(if read_only {
+                mode & !0o222
+            } else {
+                mode | 0o200
+            });
+        }
+        #[cfg(not(unix))]
+        #[allow(clippy::permissions_set_readonly_false)]
+        permissions.set_readonly(read_only);
+        std::fs::set_permissions(&path, permissions)
+            .with_context(|| format!("Failed to change the permissions of {}", path.display()))?;
+    }
+    Ok(())
+}
+
+/// Removes the review worktrees of the repository `spec` under `root`,
+/// whose lock the caller holds, once their pull request is merged or closed
+/// or their branch is merged into the default branch or deleted, as of the
+/// last fetch, and forgets those already gone. Reviews with uncommitted
+/// changes are kept. Returns the events for the hooks.
+pub(super) fn remove_finished(
+    config: &Config,
+    repo: &Repository,
+    root: &Path,
+    spec: &str,
+) -> Result<Vec<Event>> {
+    let metadata = Metadata::load(root)?;
+    let repo_dir = root.join(spec);
+    let mut events = Vec::new();
+    let mut forgotten = Vec::new();
+    for (name, review) in metadata.reviews(spec) {
+        let worktree_path = repo_dir.join(&name);
+        if !worktree_path.exists() {
+            forgotten.push(name);
+            continue;
+        }
+        let finished = match &review {
+            Review::PullRequest(number) => {
+                origin_forge(config, repo).and_then(|(forge, owner, repo_name)| {
+                    let pull_request = forge.pull_request(&owner, &repo_name, *number)?;
+                    Ok(pull_request.state != PullRequestState::Open)
+                })
+            }
+            Review::Branch(branch) => branch_finished(repo, branch),
+        };
+        match finished {
+            Ok(true) => {}
+            Ok(false) => continue,
+            Err(error) => {
+                warn!(
+                    "Keeping review worktree {}: {error:#}",
+                    worktree_path.display()
+                );
+                continue;
+            }
+        }
+        if status::is_worktree_dirty(&worktree_path)? {
+            info!(
+                "Keeping finished review worktree {}: it has uncommitted changes",
+                worktree_path.display()
+            );
+            continue;
+        }
+        if !config.dry_run {
+            set_read_only(&worktree_path, false)?;
+        }
+        events.extend(remove_worktree(config, repo, &worktree_path)?);
+        if !config.dry_run {
+            forgotten.push(name);
+        }
+    }
+    if !forgotten.is_empty() {
+        Metadata::update(root, |metadata| {
+            for name in &forgotten {
+                metadata.remove_review(spec, name);
+            }
+            Ok(())
+        })?;
+    }
+    Ok(events)
+}
+
+/// Returns whether the branch `branch` of `origin` is gone or merged into
+/// origin's default branch.
+fn branch_finished(repo: &Repository, branch: &str) -> Result<bool> {
+    let Ok(tip) = repo.refname_to_id(&format!("refs/remotes/origin/{branch}")) else {
+        return Ok(true);
+    };
+    let Some(default_branch) = status::default_branch(repo) else {
+        return Ok(false);
+    };
+    if branch == default_branch {
+        return Ok(false);
+    }
+    let Ok(default_tip) = repo
+        .refname_to_id(&format!("refs/remotes/origin/{default_branch}"))
+        .or_else(|_| repo.refname_to_id(&format!("refs/heads/{default_branch}")))
+    else {
+        return Ok(false);
+    };
+    Ok(tip == default_tip || repo.graph_descendant_of(default_tip, tip)?)
+}
+
+/// Removes the finished review worktrees of every repository, as the daemon
+/// does on each refresh.
+pub fn remove_all_finished(config: &Config) -> Result<()> {
+    super::sweep_repositories(config, Metadata::reviewed_repositories, remove_finished)
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use crate::test_utils::{commit_all, create_managed_repository, create_source_repository};
+
+    #[test]
+    fn test_review_branch() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path().join("root");
+        let spec = "github.com/user/repo";
+        let repo_dir = root.join(spec);
+        let repo = create_managed_repository(&repo_dir);
+        let origin_path = temp_dir.path().join("origin");
+        let origin = create_source_repository(&origin_path);
+        let main = origin.head().unwrap().peel_to_commit().unwrap();
+        for branch in ["feature/one", "two"] {
+            origin.branch(branch, &main, false).unwrap();
+        }
+        origin.set_head("refs/heads/two").unwrap();
+        std::fs::write(origin_path.join("TWO"), "two\n").unwrap();
+        commit_all(&origin, "Add two");
+        origin.set_head("refs/heads/main").unwrap();
+        origin
+            .checkout_head(Some(git2::build::CheckoutBuilder::new().force()))
+            .unwrap();
+        repo.remote_set_url("origin", origin_path.to_str().unwrap())
+            .unwrap();
+        let config = Config {
+            root: root.clone(),
+            ..Default::default()
+        };
+        let review = |branch: &str| {
+            let name = format!("review-{}", branch.replace('/', "-"));
+            let review = Review::Branch(branch.to_string());
+            let source = format!("refs/heads/{branch}");
+            add_review(&config, &repo, &repo_dir.join(&name), &source, true)?;
+            Metadata::update(&root, |metadata| {
+                metadata.add_review(spec, &name, review);
+                Ok(())
+            })
+        };
+        review("feature/one").unwrap();
+        review("two").unwrap();
+        assert!(review("missing").is_err());
+        assert!(
+            std::fs::metadata(repo_dir.join("review-two/TWO"))
+                .unwrap()
+                .permissions()
+                .readonly()
+        );
+
+        // Reviews don't count as dirty
+        std::fs::write(repo_dir.join("review-two/NOTES"), "notes\n").unwrap();
+        let entry = crate::scan::RepoEntry::from_path(&repo_dir).unwrap();
+        assert!(!status::collect_repo_status(&entry).unwrap().dirty);
+        std::fs::remove_file(repo_dir.join("review-two/NOTES")).unwrap();
+
+        // feature/one is at main, so it's merged; two isn't yet
+        remove_all_finished(&config).unwrap();
+        assert!(!repo_dir.join("review-feature-one").exists());
+        assert!(repo_dir.join("review-two").exists());
+
+        // Once two is deleted on origin, its review goes too
+        repo.find_reference("refs/remotes/origin/two")
+            .unwrap()
+            .delete()
+            .unwrap();
+        remove_all_finished(&config).unwrap();
+        assert!(!repo_dir.join("review-two").exists());
+        assert!(repo_dir.join("main").exists());
+        assert!(
+            Metadata::load(&root)
+                .unwrap()
+                .reviewed_repositories()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_parse_target() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo = create_managed_repository(&temp_dir.path().join("repo"));
+        repo.remote_set_url("origin", "https://github.com/User/Repo.git")
+            .unwrap();
+        for target in ["42", "#42", "https://github.com/user/repo/pull/42"] {
+            assert_eq!(
+                parse_target(&repo, target).unwrap(),
+                Review::PullRequest(42)
+            );
+        }
+        assert_eq!(
+            parse_target(&repo, "feature/x").unwrap(),
+            Review::Branch("feature/x".to_string())
+        );
+        assert!(parse_target(&repo, "https://github.com/other/repo/pull/42").is_err());
+        assert!(parse_target(&repo, "https://github.com/user/repo/issues/42").is_err());
+    }
+}
@@ -0,0 +1,273 @@
+use super::remove::remove_worktree;
+use crate::{
+    config::Config,
+    dry_run,
+    events::{self, Event, EventKind},
+    lock::RepoLock,
+    metadata::Metadata,
+    repo_context,
+    repo_settings::RepoSettings,
+    status,
+    transport::run_git,
+};
+use anyhow::{Context, Result, anyhow, bail};
+use git2::Repository;
+use std::path::Path;
+use std::time::{Duration, UNIX_EPOCH};
+use tracing::info;
+
+/// How long a scratch worktree lives without `--ttl`.
+const DEFAULT_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Adds a detached worktree named `scratch-<commit>` at `reference`, or at
+/// the current worktree's HEAD, and prints its path. It's recorded in the
+/// root's metadata to expire after `ttl`, such as `2d`, when `worktree
+/// clean` or the daemon removes it; no branch is created.
+pub fn execute(config: Config, ttl: Option<String>, reference: Option<String>) -> Result<()> {
+    let ttl = ttl.as_deref().map_or(Ok(DEFAULT_TTL), parse_ttl)?;
+    let context = repo_context::current_repo()?;
+    if context.is_plain_clone() {
+        bail!(
+            "{} is a plain clone; convert it with `neoghq repo adopt --in-place {}` first",
+            context.repo_dir.display(),
+            context.repo_dir.display()
+        );
+    }
+    let (root, spec) = context.metadata_key()?;
+    let repo = context.open()?;
+    let revision = reference.as_deref().unwrap_or("HEAD");
+    let commit = match (&reference, &context.worktree) {
+        (None, Some(worktree)) => Repository::open(worktree)?
+            .revparse_single(revision)
+            .and_then(|object| object.peel_to_commit())
+            .map(|commit| commit.id()),
+        _ => repo
+            .revparse_single(revision)
+            .and_then(|object| object.peel_to_commit())
+            .map(|commit| commit.id()),
+    }
+    .and_then(|id| repo.find_commit(id))
+    .with_context(|| format!("No commit {revision}"))?;
+
+    let short_id = commit.as_object().short_id()?;
+    let base_name = format!("scratch-{}", short_id.as_str().unwrap_or_default());
+    let name = std::iter::once(base_name.clone())
+        .chain((2..).map(|n| format!("{base_name}-{n}")))
+        .find(|name| !context.repo_dir.join(name).exists())
+        .ok_or_else(|| anyhow!("No free name for a scratch worktree"))?;
+    let worktree_path = context.repo_dir.join(&name);
+    let expires = status::unix_now()
+        .checked_add(ttl.as_secs())
+        .ok_or_else(|| {
+            anyhow!(
+                "Invalid TTL of {}s; it ends too far in the future",
+                ttl.as_secs()
+            )
+        })?;
+    let settings = RepoSettings::load(&config, &spec, &context.repo_dir)?;
+    dry_run::apply(
+        &config,
+        format_args!(
+            "create scratch worktree {} at {}",
+            worktree_path.display(),
+            commit.id()
+        ),
+        || {
+            let _lock = RepoLock::acquire(&context.git_dir)?;
+            run_git(
+                Some(&context.git_dir),
+                true,
+                &[
+                    "worktree",
+                    "add",
+                    "--quiet",
+                    "--detach",
+                    &worktree_path.to_string_lossy(),
+                    &commit.id().to_string(),
+                ],
+            )?;
+            Metadata::update(&root, |metadata| {
+                metadata.add_scratch(&spec, &name, expires);
+                Ok(())
+            })?;
+            settings.setup_worktree(&context.repo_dir, &worktree_path)
+        },
+    )?;
+    if config.dry_run {
+        return Ok(());
+    }
+
+    info!(
+        "Created scratch worktree {}, expiring at {}",
+        worktree_path.display(),
+        status::format_timestamp(UNIX_EPOCH + Duration::from_secs(expires))
+    );
+    events::emit(
+        &config,
+        &Event::worktree(
+            EventKind::WorktreeCreated,
+            &context.repo_dir,
+            &worktree_path,
+            None,
+        ),
+    );
+    println!("{}", worktree_path.display());
+    Ok(())
+}
+
+/// Removes the expired scratch worktrees of the repository `spec` under
+/// `root`, whose lock the caller holds, and forgets those already gone.
+/// Scratch worktrees with uncommitted changes are kept. Returns the events
+/// for the hooks.
+pub(super) fn remove_expired(
+    config: &Config,
+    repo: &Repository,
+    root: &Path,
+    spec: &str,
+) -> Result<Vec<Event>> {
+    let metadata = Metadata::load(root)?;
+    let repo_dir = root.join(spec);
+    let now = status::unix_now();
+    let mut events = Vec::new();
+    let mut forgotten = Vec::new();
+    for (_, name, expires) in metadata
+        .scratch_worktrees()
+        .into_iter()
+        .filter(|(scratch_spec, ..)| scratch_spec == spec)
+    {
+        let worktree_path = repo_dir.join(&name);
+        if !worktree_path.exists() {
+            forgotten.push(name);
+            continue;
+        }
+        if expires > now {
+            continue;
+        }
+        if status::is_worktree_dirty(&worktree_path)? {
+            info!(
+                "Keeping expired scratch worktree {}: it has uncommitted changes",
+                worktree_path.display()
+            );
+            continue;
+        }
+        events.extend(remove_worktree(config, repo, &worktree_path)?);
+        if !config.dry_run {
+            forgotten.push(name);
+        }
+    }
+    if !forgotten.is_empty() {
+        Metadata::update(root, |metadata| {
+            for name in &forgotten {
+                metadata.remove_scratch(spec, name);
+            }
+            Ok(())
+        })?;
+    }
+    Ok(events)
+}
+
+/// Removes the expired scratch worktrees of every repository, as the
+/// daemon does on each refresh.
+pub fn remove_all_expired(config: &Config) -> Result<()> {
+    super::sweep_repositories(config, Metadata::scratch_repositories, remove_expired)
+}
+
+/// Parses a lifetime such as `30m`, `12h`, `2d` or `1w`.
+fn parse_ttl(ttl: &str) -> Result<Duration> {
+    let invalid = || anyhow!("Invalid TTL {ttl}; use a number with m, h, d or w, such as 2d");
+    let split = ttl
+        .find(|c: char| !c.is_ascii_digit())
+        .ok_or_else(invalid)?;
+    let (number, unit) = ttl.split_at(split);
+    let unit_seconds = match unit {
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => return Err(invalid()),
+    };
+    match number.parse::<u64>() {
+        Ok(number) if number > 0 => number
+            .checked_mul(unit_seconds)
+            .map(Duration::from_secs)
+            .ok_or_else(invalid),
+        _ => Err(invalid()),
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_managed_repository;
+
+    #[test]
+    fn test_parse_ttl() {
+        assert_eq!(parse_ttl("30m").unwrap(), Duration::from_secs(1800));
+        assert_eq!(parse_ttl("2d").unwrap(), Duration::from_secs(172_800));
+        assert_eq!(parse_ttl("1w").unwrap(), Duration::from_secs(604_800));
+        for invalid in [
+            "",
+            "2",
+            "d",
+            "0h",
+            "2days",
+            "-1d",
+            "1.5h",
+            "400000000000000000w",
+        ] {
+            assert!(parse_ttl(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn test_remove_expired() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        let spec = "github.com/user/repo";
+        let repo_dir = root.join(spec);
+        let repo = create_managed_repository(&repo_dir);
+        let head = repo.head().unwrap().target().unwrap().to_string();
+        let mut metadata = Metadata::default();
+        for (name, expires) in [
+            ("expired", 1),
+            ("dirty", 1),
+            ("fresh", status::unix_now() + 3600),
+        ] {
+            run_git(
+                Some(repo.path()),
+                true,
+                &[
+                    "worktree",
+                    "add",
+                    "--quiet",
+                    "--detach",
+                    &repo_dir.join(name).to_string_lossy(),
+                    &head,
+                ],
+            )
+            .unwrap();
+            metadata.add_scratch(spec, name, expires);
+        }
+        metadata.add_scratch(spec, "deleted", 1);
+        metadata.save(root).unwrap();
+        std::fs::write(repo_dir.join("dirty/NEW"), "new").unwrap();
+
+        let config = Config {
+            root: root.to_path_buf(),
+            ..Default::default()
+        };
+        remove_all_expired(&config).unwrap();
+        assert!(!repo_dir.join("expired").exists());
+        assert!(repo_dir.join("dirty").exists());
+        assert!(repo_dir.join("fresh").exists());
+        assert!(repo_dir.join("main").exists());
+        let names = Metadata::load(root)
+            .unwrap()
+            .scratch_worktrees()
+            .into_iter()
+            .map(|(_, name, _)| name)
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["dirty", "fresh"]);
+    }
+}
@@ -1,6 +1,159 @@
+use crate::{
+    cli::OutputFormat,
+    config::Config,
+    repo_context,
+    status::{self, WorktreeStatus},
+    style::{Color, Icon, Style},
+};
 use anyhow::Result;
 
-pub fn execute() -> Result<()> {
-    println!("Status command");
+/// Prints the status of every worktree of the current repository. With
+/// `diffstat`, each also shows how much it changes against the default
+/// branch.
+pub fn execute(config: Config, json: bool, diffstat: bool) -> Result<()> {
+    let repo = repo_context::current_repo()?.open()?;
+    let mut statuses = status::collect_worktree_statuses(&repo)?;
+    if diffstat {
+        for status in &mut statuses {
+            status.diffstat = Some(status::diffstat_against_default(&repo, &status.path)?);
+        }
+    }
+
+    if json || config.output == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&statuses)?);
+        return Ok(());
+    }
+
+    let style = Style::stdout(&config);
+    for status in &statuses {
+        println!("{}", status_line(status, style));
+    }
+    let gone = statuses
+        .iter()
+        .filter(|status| status.upstream_gone())
+        .count();
+    if gone > 0 {
+        println!();
+        println!(
+            "The upstream of {gone} worktree branches was deleted; run `neoghq worktree clean --gone` to remove them"
+        );
+    }
+    if statuses
+        .iter()
+        .any(|status| status.needs_fix() && !status.upstream_gone())
+    {
+        println!();
+        println!("Run `neoghq worktree fix-upstream` to repair branch tracking");
+    }
+
     Ok(())
 }
+
+/// Colors the branch red when the worktree is dirty and green when clean,
+/// and the upstream yellow when it needs `fix-upstream`.
+pub(crate) fn status_line(status: &WorktreeStatus, style: Style) -> String {
+    let tracking = match (&status.branch, &status.tracking) {
+        (Some(branch), Some(upstream)) => {
+            let branch_color = if status.dirty {
+                Color::Red
+            } else {
+                Color::Green
+            };
+            let upstream_color = if status.needs_fix() {
+                Color::Yellow
+            } else {
+                Color::Dim
+            };
+            format!(
+                "{} {}",
+                style.paint(branch_color, style.icon(Icon::Branch, branch)),
+                style.paint(upstream_color, format!("({upstream})"))
+            )
+        }
+        _ => style.paint(Color::Yellow, style.icon(Icon::Detached, "(detached)")),
+    };
+    let dirty = if status.dirty {
+        style.paint(Color::Red, " *")
+    } else {
+        String::new()
+    };
+    let diffstat = match &status.diffstat {
+        Some(diffstat) => format!("\t{}", style.paint(Color::Dim, diffstat)),
+        None => String::new(),
+    };
+    format!("{}\t{tracking}{dirty}{diffstat}", status.path.display())
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_managed_repository;
+    use git2::BranchType;
+
+    #[test]
+    fn test_status_lines_annotate_upstream() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo_dir = temp_dir.path().join("repo");
+        let bare = create_managed_repository(&repo_dir);
+        let mut main = bare.find_branch("main", BranchType::Local).unwrap();
+        main.set_upstream(Some("origin/main")).unwrap();
+
+        let statuses = status::collect_worktree_statuses(&bare).unwrap();
+        assert_eq!(statuses.len(), 1);
+        assert!(status_line(&statuses[0], Style::default()).ends_with("\tmain (origin/main)"));
+        assert!(!statuses[0].needs_fix());
+
+        main.set_upstream(None).unwrap();
+        std::fs::write(repo_dir.join("main/NEW"), "new").unwrap();
+        let statuses = status::collect_worktree_statuses(&bare).unwrap();
+        assert!(status_line(&statuses[0], Style::default()).ends_with("\tmain (no upstream) *"));
+        assert!(statuses[0].needs_fix());
+    }
+
+    #[test]
+    fn test_status_json() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let bare = create_managed_repository(&temp_dir.path().join("repo"));
+        let mut main = bare.find_branch("main", BranchType::Local).unwrap();
+        main.set_upstream(Some("origin/main")).unwrap();
+
+        let statuses = status::collect_worktree_statuses(&bare).unwrap();
+        let json = serde_json::to_value(&statuses).unwrap();
+        assert_eq!(json[0]["branch"], "main");
+        assert_eq!(json[0]["upstream"], "origin/main");
+        assert_eq!(json[0]["upstream_state"], "tracking");
+        assert_eq!(json[0]["ahead"], 0);
+        assert_eq!(json[0]["dirty"], false);
+        assert!(json[0].get("tracking").is_none());
+        assert!(json[0].get("diffstat").is_none());
+    }
+
+    #[test]
+    fn test_diffstat_against_default() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo_dir = temp_dir.path().join("repo");
+        let bare = create_managed_repository(&repo_dir);
+        std::fs::write(repo_dir.join("main/NEW"), "one\ntwo\n").unwrap();
+        std::fs::remove_file(repo_dir.join("main/README")).unwrap();
+
+        let mut statuses = status::collect_worktree_statuses(&bare).unwrap();
+        statuses[0].diffstat =
+            Some(status::diffstat_against_default(&bare, &statuses[0].path).unwrap());
+        assert_eq!(
+            statuses[0].diffstat,
+            Some(status::DiffStat {
+                files: 2,
+                insertions: 2,
+                deletions: 1,
+            })
+        );
+        assert!(status_line(&statuses[0], Style::default()).ends_with(" *\t2 files, +2 -1"));
+        // The worktree's index is left alone
+        assert_eq!(status::changed_files(&statuses[0].path).unwrap().len(), 2);
+        assert_eq!(
+            status::changed_files(&statuses[0].path).unwrap()[0].status,
+            "??"
+        );
+    }
+}
@@ -1,7 +1,152 @@
-use anyhow::Result;
+use crate::{
+    cli::{SortKey, SwitchFlags},
+    config::Config,
+    editor, exit,
+    history::History,
+    picker, repo_context, sort, status, suggest,
+};
+use anyhow::{Result, anyhow};
+use git2::Repository;
+use std::path::PathBuf;
 
-pub fn execute(branch: String) -> Result<()> {
-    println!("Switching to worktree for branch: {branch}");
-    println!("worktree switch functionality not yet implemented");
+/// Prints the worktree for `branch`, or for one chosen in the picker when
+/// no branch is given.
+pub fn execute(config: Config, branch: Option<String>, flags: SwitchFlags) -> Result<()> {
+    let repo = repo_context::current_repo()?.open()?;
+    let quiet = flags.quiet || flags.exists_only;
+    let worktree_path = match branch {
+        Some(branch) => match find_worktree_path(&repo, &branch)? {
+            Some(worktree_path) => worktree_path,
+            None => return Err(exit::not_found(quiet, not_found_message(&repo, &branch)?)),
+        },
+        // clap can't require the argument when -q comes before the subcommand
+        None if quiet => return Err(anyhow!("--quiet and --exists-only need a branch")),
+        None => pick_worktree(&config, &repo)?.ok_or_else(|| anyhow!("No worktree selected"))?,
+    };
+
+    if !flags.exists_only {
+        // A history that can't be written only affects --sort recent
+        History::record(&config, &worktree_path).ok();
+        println!("{}", worktree_path.display());
+    }
+    if flags.open {
+        editor::open(&config, &worktree_path)?;
+    }
     Ok(())
 }
+
+/// Lets the user pick one of the worktrees of `repo`, most recently
+/// switched to first.
+pub(super) fn pick_worktree(config: &Config, repo: &Repository) -> Result<Option<PathBuf>> {
+    let mut worktrees = status::list_worktrees(repo)?;
+    sort::sort_entries(
+        &mut worktrees,
+        SortKey::Recent,
+        config,
+        |worktree| &worktree.path,
+        |worktree| worktree.branch.clone().unwrap_or_default(),
+    );
+    let items = worktrees
+        .iter()
+        .map(|worktree| {
+            format!(
+                "{}  {}",
+                worktree.branch.as_deref().unwrap_or("(detached)"),
+                worktree.path.display()
+            )
+        })
+        .collect::<Vec<_>>();
+
+    Ok(picker::pick(config, "Worktree", &items)?.map(|index| worktrees[index].path.clone()))
+}
+
+/// Explains that there is no worktree for `branch`, suggesting similarly
+/// named branches and worktree directories.
+pub(super) fn not_found_message(repo: &Repository, branch: &str) -> Result<String> {
+    let mut names = Vec::new();
+    for worktree in status::list_worktrees(repo)? {
+        names.extend(worktree.branch);
+        if let Some(name) = worktree.path.file_name() {
+            names.push(name.to_string_lossy().into_owned());
+        }
+    }
+    names.sort();
+    names.dedup();
+    let suggestions = suggest::similar(branch, names.iter().map(String::as_str));
+
+    Ok(suggest::with_suggestions(
+        format!("Worktree not found: {branch}"),
+        &suggestions,
+    ))
+}
+
+/// Finds the worktree with `branch` checked out, or whose directory is
+/// named `branch`.
+pub(super) fn find_worktree_path(repo: &Repository, branch: &str) -> Result<Option<PathBuf>> {
+    let worktrees = status::list_worktrees(repo)?;
+    let by_branch = worktrees
+        .iter()
+        .find(|worktree| worktree.branch.as_deref() == Some(branch));
+    let by_name = || {
+        worktrees
+            .iter()
+            .find(|worktree| worktree.path.file_name().is_some_and(|name| name == branch))
+    };
+
+    Ok(by_branch
+        .or_else(by_name)
+        .map(|worktree| worktree.path.clone()))
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_managed_repository;
+
+    #[test]
+    fn test_find_worktree_path() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo_dir = temp_dir.path().join("repo");
+        let repo = create_managed_repository(&repo_dir);
+
+        let result = find_worktree_path(&repo, "main").unwrap().unwrap();
+        assert_eq!(
+            result.canonicalize().unwrap(),
+            repo_dir.join("main").canonicalize().unwrap()
+        );
+        assert_eq!(find_worktree_path(&repo, "missing").unwrap(), None);
+    }
+
+    #[test]
+    fn test_not_found_message() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo = create_managed_repository(&temp_dir.path().join("repo"));
+
+        assert_eq!(
+            not_found_message(&repo, "mian").unwrap(),
+            "Worktree not found: mian\n\nDid you mean main?"
+        );
+        assert_eq!(
+            not_found_message(&repo, "feature/unrelated").unwrap(),
+            "Worktree not found: feature/unrelated"
+        );
+    }
+
+    #[test]
+    fn test_pick_worktree() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo_dir = temp_dir.path().join("repo");
+        let repo = create_managed_repository(&repo_dir);
+        let config = Config {
+            picker_command: Some("grep '^main '".to_string()),
+            ..Default::default()
+        };
+
+        let result = pick_worktree(&config, &repo).unwrap().unwrap();
+        assert_eq!(
+            result.canonicalize().unwrap(),
+            repo_dir.join("main").canonicalize().unwrap()
+        );
+    }
+}
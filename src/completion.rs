@@ -0,0 +1,117 @@
+//! Dynamic values for shell completion. Each candidate function runs inside
+//! `COMPLETE=<shell> neoghq -- …` and must never fail, so errors simply
+//! yield no candidates.
+
+use crate::{
+    config::{Config, Env},
+    repo_context, scan, status,
+};
+use anyhow::Result;
+use clap_complete::engine::CompletionCandidate;
+use git2::{BranchType, Repository};
+
+/// Completes `owner/repo` names of managed repositories.
+pub fn repo_candidates() -> Vec<CompletionCandidate> {
+    let Ok(config) = Env::load().and_then(Config::load) else {
+        return Vec::new();
+    };
+    repo_names(&config)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(name, host)| CompletionCandidate::new(name).help(Some(host.into())))
+        .collect()
+}
+
+/// Completes branches checked out in worktrees of the current repository.
+pub fn worktree_candidates() -> Vec<CompletionCandidate> {
+    current_repository()
+        .and_then(|repo| worktree_branches(&repo))
+        .unwrap_or_default()
+        .into_iter()
+        .map(CompletionCandidate::new)
+        .collect()
+}
+
+/// Completes local and remote-tracking branches of the current repository.
+pub fn branch_candidates() -> Vec<CompletionCandidate> {
+    current_repository()
+        .and_then(|repo| branch_names(&repo))
+        .unwrap_or_default()
+        .into_iter()
+        .map(CompletionCandidate::new)
+        .collect()
+}
+
+fn current_repository() -> Result<Repository> {
+    repo_context::current_repo()?.open()
+}
+
+/// Returns `(owner/repo, host)` for every managed repository.
+fn repo_names(config: &Config) -> Result<Vec<(String, String)>> {
+    Ok(scan::scan_roots(&config.all_roots())?
+        .into_iter()
+        .map(|entry| (format!("{}/{}", entry.owner, entry.repo), entry.host))
+        .collect())
+}
+
+fn worktree_branches(repo: &Repository) -> Result<Vec<String>> {
+    Ok(status::list_worktrees(repo)?
+        .into_iter()
+        .filter_map(|worktree| worktree.branch)
+        .collect())
+}
+
+/// Returns local branch names followed by remote branches without their
+/// remote prefix, since `worktree create` accepts either.
+fn branch_names(repo: &Repository) -> Result<Vec<String>> {
+    let mut names = Vec::new();
+    for branch_type in [BranchType::Local, BranchType::Remote] {
+        for branch in repo.branches(Some(branch_type))? {
+            let Some(name) = branch?.0.name()?.map(str::to_string) else {
+                continue;
+            };
+            let name = match branch_type {
+                BranchType::Local => name,
+                BranchType::Remote => match name.split_once('/') {
+                    Some((_, "HEAD")) | None => continue,
+                    Some((_, name)) => name.to_string(),
+                },
+            };
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+    }
+    Ok(names)
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_managed_repository;
+
+    #[test]
+    fn test_repo_names() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(temp_dir.path().join("github.com/user/repo/.git")).unwrap();
+        let config = Config {
+            root: temp_dir.path().to_path_buf(),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            repo_names(&config).unwrap(),
+            [("user/repo".to_string(), "github.com".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_worktree_and_branch_names() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo = create_managed_repository(&temp_dir.path().join("repo"));
+
+        assert_eq!(worktree_branches(&repo).unwrap(), ["main"]);
+        assert!(branch_names(&repo).unwrap().contains(&"main".to_string()));
+    }
+}
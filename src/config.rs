@@ -1,12 +1,34 @@
-use anyhow::Result;
-use std::path::PathBuf;
+use crate::backend::GitBackend;
+use crate::cli::{ColorChoice, OutputFormat};
+use crate::gitconfig::GhqConfig;
+use crate::repo_settings::RepoSettings;
+use crate::style::Icons;
+use anyhow::{Context, Result, anyhow};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 const DEFAULT_NEOGHQ_ROOT: &str = "~/src/repos";
+const DEFAULT_ROOT_NAME: &str = "default";
+const DEFAULT_BRANCH: &str = "main";
+const DEFAULT_TRASH_RETENTION_DAYS: u64 = 30;
+const DEFAULT_DAEMON_FETCH_INTERVAL_MINUTES: u64 = 30;
+/// The longest `daemon.fetch_interval_minutes`: a year.
+pub const MAX_DAEMON_FETCH_INTERVAL_MINUTES: u64 = 60 * 24 * 365;
+const DEFAULT_DAEMON_STATUS_INTERVAL_SECONDS: u64 = 60;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct Env {
     pub neoghq_root: Option<PathBuf>,
     pub home: Option<PathBuf>,
+    pub config_path: Option<PathBuf>,
+    pub cache_dir: Option<PathBuf>,
+    pub profile: Option<String>,
+    pub ghq: GhqConfig,
+    /// Value of `init.defaultBranch` from the user's gitconfig.
+    pub init_default_branch: Option<String>,
 }
 
 impl Env {
@@ -14,40 +36,891 @@ impl Env {
     pub fn load() -> Result<Self> {
         let neoghq_root = std::env::var("NEOGHQ_ROOT").ok().map(PathBuf::from);
         let home = dirs::home_dir();
+        let config_path = std::env::var("NEOGHQ_CONFIG")
+            .ok()
+            .map(PathBuf::from)
+            .or_else(|| {
+                std::env::var("XDG_CONFIG_HOME")
+                    .ok()
+                    .map(PathBuf::from)
+                    .or_else(|| home.as_ref().map(|home| home.join(".config")))
+                    .map(|config_dir| config_dir.join("neoghq").join("config.toml"))
+            });
+        let cache_dir = std::env::var("XDG_CACHE_HOME")
+            .ok()
+            .map(PathBuf::from)
+            .or_else(|| home.as_ref().map(|home| home.join(".cache")))
+            .map(|cache_dir| cache_dir.join("neoghq"));
+        let profile = std::env::var("NEOGHQ_PROFILE").ok();
+        let ghq = GhqConfig::load_default();
+        let init_default_branch = git2::Config::open_default()
+            .and_then(|config| config.get_string("init.defaultBranch"))
+            .ok();
 
-        Ok(Self { neoghq_root, home })
+        Ok(Self {
+            neoghq_root,
+            home,
+            config_path,
+            cache_dir,
+            profile,
+            ghq,
+            init_default_branch,
+        })
     }
 }
 
-#[derive(Debug, Clone)]
+/// Where an effective setting came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Origin {
+    Default,
+    File(PathBuf),
+    Env(&'static str),
+    Profile(String),
+    Gitconfig(&'static str),
+    Flag(&'static str),
+}
+
+impl std::fmt::Display for Origin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Default => write!(f, "default"),
+            Self::File(path) => write!(f, "file:{}", path.display()),
+            Self::Env(name) => write!(f, "env:{name}"),
+            Self::Profile(name) => write!(f, "profile:{name}"),
+            Self::Gitconfig(key) => write!(f, "gitconfig:{key}"),
+            Self::Flag(name) => write!(f, "flag:{name}"),
+        }
+    }
+}
+
+/// The effective value of a setting and where it came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Setting {
+    pub value: String,
+    pub origin: Origin,
+}
+
+/// A named directory under which repositories are managed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Root {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+/// When to write a local crash report.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CrashReportMode {
+    /// Only when neoghq panics.
+    #[default]
+    Panic,
+    /// When neoghq panics or exits with an error.
+    Always,
+    /// Never.
+    Never,
+}
+
+/// Commit identity written into repositories neoghq clones or creates.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Identity {
+    pub name: String,
+    pub email: String,
+}
+
+/// Where credentials for fetching and pushing come from.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Credentials {
+    /// Private key used for SSH remotes.
+    pub ssh_key: Option<PathBuf>,
+    /// Environment variable holding an HTTPS token.
+    pub token_env: Option<String>,
+    /// Command printing an HTTPS token on stdout.
+    pub token_command: Option<String>,
+    /// Whether the git CLI may ask for a username, password or passphrase.
+    pub prompt: bool,
+}
+
+/// Protocol used to build clone URLs from repository specs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum CloneProtocol {
+    /// https://<host>/<owner>/<repo>
+    #[default]
+    Https,
+    /// git@<host>:<owner>/<repo>.git
+    Ssh,
+}
+
+/// A spec prefix (`gl:group/proj`) standing for a host.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct HostAlias {
+    pub host: String,
+    pub protocol: Option<CloneProtocol>,
+}
+
+/// Settings that apply to repositories from a single host.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct HostConfig {
+    /// Root that repositories from this host are placed in.
+    pub root: Option<PathBuf>,
+    /// Protocol of clone URLs built for this host, overriding
+    /// `clone.protocol`.
+    pub protocol: Option<CloneProtocol>,
+    /// API the host serves; github.com and gitlab.com are known.
+    pub forge: Option<ForgeKind>,
+    /// Base URL of the host's API, when it isn't where `forge` puts it.
+    pub api_url: Option<String>,
+}
+
+/// The kind of forge a host runs, for [`crate::forge::Forge`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ForgeKind {
+    Github,
+    Gitlab,
+}
+
+/// Which refs fetches delete once the remote no longer has them, from
+/// `[fetch] prune` and `prune_tags`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Prune {
+    /// Remote-tracking branches.
+    pub branches: bool,
+    /// Local tags, as `git fetch --prune-tags` does; implies `branches`.
+    pub tags: bool,
+}
+
+/// How often `neoghq daemon` refreshes repositories, from `[daemon]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DaemonConfig {
+    /// Average time between fetches of a repository; zero never fetches.
+    pub fetch_interval: Duration,
+    /// Time between status refreshes of every repository.
+    pub status_interval: Duration,
+    /// Whether to watch the roots for repositories and worktrees added or
+    /// removed outside neoghq.
+    pub watch: bool,
+}
+
+impl Default for DaemonConfig {
+    fn default() -> Self {
+        Self {
+            fetch_interval: Duration::from_secs(DEFAULT_DAEMON_FETCH_INTERVAL_MINUTES * 60),
+            status_interval: Duration::from_secs(DEFAULT_DAEMON_STATUS_INTERVAL_SECONDS),
+            watch: true,
+        }
+    }
+}
+
+/// Git hooks shared by every repository, from `[git_hooks]`. At most one
+/// of the two is set.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GitHooksConfig {
+    /// Directory whose hooks are copied into each repository's `hooks`.
+    pub template_dir: Option<PathBuf>,
+    /// Directory each repository's `core.hooksPath` is set to.
+    pub hooks_path: Option<PathBuf>,
+}
+
+impl GitHooksConfig {
+    pub fn is_empty(&self) -> bool {
+        self.template_dir.is_none() && self.hooks_path.is_none()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct Config {
+    /// The primary root, used when no other root is selected.
     pub root: PathBuf,
+    /// Additional named roots configured in the `[roots]` table.
+    pub roots: Vec<Root>,
+    /// Per-host settings configured in `[hosts."<host>"]` tables.
+    pub hosts: BTreeMap<String, HostConfig>,
+    /// Default owner for repository specs given without one.
+    pub user: Option<String>,
+    /// ghq settings from gitconfig, used where neoghq has no equivalent.
+    pub ghq: GhqConfig,
+    /// Directory for caches and crash reports.
+    pub cache_dir: Option<PathBuf>,
+    pub crash_reports: CrashReportMode,
+    /// Branch used when none is given, from `git.default_branch`.
+    pub default_branch: Option<String>,
+    /// Branch new repositories start on, from gitconfig's `init.defaultBranch`.
+    pub init_default_branch: Option<String>,
+    /// Library used to inspect local repositories, from `git.backend`.
+    pub git_backend: GitBackend,
+    /// The configuration file this config was loaded from.
+    pub config_path: Option<PathBuf>,
+    /// The profile this config was loaded with.
+    pub profile: Option<String>,
+    /// Host used for repository specs given without one.
+    pub default_host: Option<String>,
+    /// Protocol of clone URLs built from specs, from `[clone] protocol`.
+    pub clone_protocol: CloneProtocol,
+    /// Set by `--protocol` of `repo get` and `repo create`; wins over every
+    /// configured protocol.
+    pub protocol_override: Option<CloneProtocol>,
+    /// Whether cloning a fork adds the repository it was forked from as the
+    /// `upstream` remote, from `[clone] fork_upstream`.
+    pub fork_upstream: bool,
+    /// Template of branches `worktree create --issue` names, from
+    /// `[worktree] issue_branch`.
+    pub issue_branch: Option<String>,
+    /// Whether those branches get the issue's title and link as their git
+    /// branch description, from `[worktree] issue_description`.
+    pub issue_description: bool,
+    pub git_hooks: GitHooksConfig,
+    pub credentials: Credentials,
+    pub identity: Option<Identity>,
+    /// Spec prefixes configured in `[aliases.<name>]` tables.
+    pub aliases: BTreeMap<String, HostAlias>,
+    /// Per-repository settings configured in `[repo."<host>/<owner>/<repo>"]`
+    /// tables.
+    pub repos: BTreeMap<String, RepoSettings>,
+    /// Template directories or repository URLs configured in `[templates]`.
+    pub templates: BTreeMap<String, String>,
+    /// External fuzzy finder, such as `fzf`, used instead of the built-in
+    /// picker.
+    pub picker_command: Option<String>,
+    /// Shell command `worktree open` runs in the worktree, from
+    /// `[open] command`.
+    pub open_command: Option<String>,
+    /// Editor `--open` runs, from `[editor] command`.
+    pub editor_command: Option<String>,
+    /// Template `neoghq prompt` prints, from `[prompt] format`.
+    pub prompt_format: Option<String>,
+    /// Output format chosen with the global `--output` flag.
+    pub output: OutputFormat,
+    /// When to color output, from `[ui] color` or the global `--color` flag.
+    pub color: ColorChoice,
+    pub icons: Icons,
+    /// Days removed repositories and worktrees stay in the trash before
+    /// they are deleted for good; 0 deletes them right away.
+    pub trash_retention_days: u64,
+    pub daemon: DaemonConfig,
+    /// Whether fetching a repository whose `origin` redirects, as renamed
+    /// and transferred GitHub repositories do, points `origin` at the new
+    /// URL, from `[fetch] follow_redirects`.
+    pub follow_redirects: bool,
+    /// What fetches prune, from `[fetch] prune` and `prune_tags`. Without
+    /// it, git's own `fetch.prune` and `remote.<name>.prune` apply.
+    pub fetch_prune: Prune,
+    /// Set by the global `--dry-run` flag; see [`crate::dry_run::apply`].
+    pub dry_run: bool,
+    /// Set by the global `--no-input` flag; see [`Config::interactive`].
+    pub no_input: bool,
+    /// Effective values of settings with several possible sources, keyed by
+    /// their configuration key.
+    pub origins: BTreeMap<String, Setting>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ConfigFile {
+    #[serde(default)]
+    general: GeneralSection,
+    #[serde(default)]
+    roots: BTreeMap<String, PathBuf>,
+    #[serde(default)]
+    hosts: BTreeMap<String, HostConfig>,
+    #[serde(default)]
+    git: GitSection,
+    #[serde(default)]
+    clone: CloneSection,
+    #[serde(default)]
+    git_hooks: GitHooksSection,
+    #[serde(default)]
+    profile: BTreeMap<String, ProfileSection>,
+    #[serde(default)]
+    aliases: BTreeMap<String, HostAlias>,
+    #[serde(default)]
+    repo: BTreeMap<String, RepoSettings>,
+    #[serde(default)]
+    defaults: BTreeMap<String, Vec<String>>,
+    #[serde(default)]
+    templates: BTreeMap<String, String>,
+    #[serde(default)]
+    picker: PickerSection,
+    #[serde(default)]
+    open: OpenSection,
+    #[serde(default)]
+    editor: EditorSection,
+    #[serde(default)]
+    prompt: PromptSection,
+    #[serde(default)]
+    ui: UiSection,
+    #[serde(default)]
+    trash: TrashSection,
+    #[serde(default)]
+    daemon: DaemonSection,
+    #[serde(default)]
+    fetch: FetchSection,
+    #[serde(default)]
+    worktree: WorktreeSection,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+struct ProfileSection {
+    root: Option<PathBuf>,
+    default_host: Option<String>,
+    ssh_key: Option<PathBuf>,
+    token_env: Option<String>,
+    token_command: Option<String>,
+    user_name: Option<String>,
+    user_email: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct PickerSection {
+    command: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct OpenSection {
+    command: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct EditorSection {
+    command: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct PromptSection {
+    format: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct UiSection {
+    color: Option<ColorChoice>,
+    icons: Option<Icons>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct TrashSection {
+    retention_days: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct DaemonSection {
+    fetch_interval_minutes: Option<u64>,
+    status_interval_seconds: Option<u64>,
+    watch: Option<bool>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct FetchSection {
+    follow_redirects: Option<bool>,
+    prune: Option<bool>,
+    prune_tags: Option<bool>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct WorktreeSection {
+    issue_branch: Option<String>,
+    issue_description: Option<bool>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct GitSection {
+    default_branch: Option<String>,
+    backend: Option<GitBackend>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct CloneSection {
+    protocol: Option<CloneProtocol>,
+    fork_upstream: Option<bool>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct GitHooksSection {
+    template_dir: Option<PathBuf>,
+    hooks_path: Option<PathBuf>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct GeneralSection {
+    root: Option<PathBuf>,
+    user: Option<String>,
+    default_host: Option<String>,
+    crash_reports: Option<CrashReportMode>,
+}
+
+impl ConfigFile {
+    fn load(path: Option<&Path>) -> Result<Self> {
+        let Some(path) = path.filter(|path| path.exists()) else {
+            return Ok(Self::default());
+        };
+
+        let mut value = read_config_value(path)?;
+        interpolate_env(&mut value, &|name| std::env::var(name).ok())
+            .with_context(|| format!("Failed to parse config file: {}", path.display()))?;
+        Self::deserialize(value)
+            .with_context(|| format!("Failed to parse config file: {}", path.display()))
+    }
+}
+
+/// Checks that an interpolated configuration value has the expected types.
+pub fn check_config_value(value: toml::Value) -> Result<()> {
+    ConfigFile::deserialize(value)?;
+    Ok(())
+}
+
+/// Reads the configuration file as an untyped TOML value.
+pub fn read_config_value(path: &Path) -> Result<toml::Value> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read config file: {}", path.display()))?;
+    toml::from_str(&content)
+        .with_context(|| format!("Failed to parse config file: {}", path.display()))
+}
+
+/// Replaces `${VAR}` and `${VAR:-default}` in every string of `value` with
+/// the variable's value, as returned by `lookup`. `$${` is a literal `${`.
+pub fn interpolate_env(
+    value: &mut toml::Value,
+    lookup: &dyn Fn(&str) -> Option<String>,
+) -> Result<()> {
+    match value {
+        toml::Value::String(text) => *text = interpolate_str(text, lookup)?,
+        toml::Value::Array(values) => {
+            for value in values {
+                interpolate_env(value, lookup)?;
+            }
+        }
+        toml::Value::Table(table) => {
+            for (_, value) in table.iter_mut() {
+                interpolate_env(value, lookup)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn interpolate_str(text: &str, lookup: &dyn Fn(&str) -> Option<String>) -> Result<String> {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(index) = rest.find('$') {
+        result.push_str(&rest[..index]);
+        rest = &rest[index..];
+
+        if let Some(after) = rest.strip_prefix("$${") {
+            result.push_str("${");
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix("${") {
+            let end = after
+                .find('}')
+                .ok_or_else(|| anyhow!("Unterminated ${{...}} in {text:?}"))?;
+            let (name, default) = match after[..end].split_once(":-") {
+                Some((name, default)) => (name, Some(default)),
+                None => (&after[..end], None),
+            };
+            let value = lookup(name)
+                .filter(|value| !value.is_empty() || default.is_none())
+                .or_else(|| default.map(str::to_string))
+                .ok_or_else(|| anyhow!("Environment variable {name} is not set"))?;
+            result.push_str(&value);
+            rest = &after[end + 1..];
+        } else {
+            result.push('$');
+            rest = &rest[1..];
+        }
+    }
+    result.push_str(rest);
+    Ok(result)
 }
 
 impl Config {
     pub fn load(env: Env) -> Result<Self> {
         let home_dir = env.home;
+        let mut file = ConfigFile::load(env.config_path.as_deref())?;
+
+        let profile = match &env.profile {
+            Some(name) => file
+                .profile
+                .remove(name)
+                .ok_or_else(|| anyhow!("Unknown profile: {name}"))?,
+            None => ProfileSection::default(),
+        };
+
+        let file_origin = || match &env.config_path {
+            Some(path) => Origin::File(path.clone()),
+            None => Origin::Default,
+        };
+        let mut origins = BTreeMap::new();
+        let mut record = |key: &str, value: String, origin: Origin| {
+            origins.insert(key.to_string(), Setting { value, origin });
+        };
+
+        // Get the neoghq root directory, falling back to ghq's roots
+        let mut ghq_roots = Vec::new();
+        let (root, root_origin) = if let Some(root) = env.neoghq_root {
+            (root, Origin::Env("NEOGHQ_ROOT"))
+        } else if let Some(root) = profile.root {
+            (
+                root,
+                Origin::Profile(env.profile.clone().unwrap_or_default()),
+            )
+        } else if let Some(root) = file.general.root {
+            (root, file_origin())
+        } else if !env.ghq.roots.is_empty() {
+            ghq_roots = env.ghq.roots[1..].to_vec();
+            (env.ghq.roots[0].clone(), Origin::Gitconfig("ghq.root"))
+        } else {
+            (PathBuf::from(DEFAULT_NEOGHQ_ROOT), Origin::Default)
+        };
+        let root = expand_tilde(root, home_dir.as_deref());
+        record("general.root", root.display().to_string(), root_origin);
+
+        let ghq_roots = ghq_roots
+            .into_iter()
+            .enumerate()
+            .map(|(i, path)| (format!("ghq-{}", i + 2), path));
+        let roots = file
+            .roots
+            .into_iter()
+            .map(|(name, path)| (name, path, file_origin()))
+            .chain(ghq_roots.map(|(name, path)| (name, path, Origin::Gitconfig("ghq.root"))))
+            .map(|(name, path, origin)| {
+                let path = expand_tilde(path, home_dir.as_deref());
+                record(&format!("roots.{name}"), path.display().to_string(), origin);
+                Root { name, path }
+            })
+            .collect();
+
+        let hosts = file
+            .hosts
+            .into_iter()
+            .map(|(host, host_config)| {
+                let root = host_config
+                    .root
+                    .map(|root| expand_tilde(root, home_dir.as_deref()));
+                (
+                    host,
+                    HostConfig {
+                        root,
+                        ..host_config
+                    },
+                )
+            })
+            .collect();
+
+        let user = match (file.general.user, &env.ghq.user) {
+            (Some(user), _) => {
+                record("general.user", user.clone(), file_origin());
+                Some(user)
+            }
+            (None, Some(user)) => {
+                record("general.user", user.clone(), Origin::Gitconfig("ghq.user"));
+                Some(user.clone())
+            }
+            (None, None) => None,
+        };
+
+        let crash_reports = file.general.crash_reports.unwrap_or_default();
+        record(
+            "general.crash_reports",
+            format!("{crash_reports:?}").to_lowercase(),
+            match file.general.crash_reports {
+                Some(_) => file_origin(),
+                None => Origin::Default,
+            },
+        );
+        record(
+            "git.default_branch",
+            file.git
+                .default_branch
+                .clone()
+                .unwrap_or_else(|| DEFAULT_BRANCH.to_string()),
+            match file.git.default_branch {
+                Some(_) => file_origin(),
+                None => Origin::Default,
+            },
+        );
+        record(
+            "git.backend",
+            format!("{:?}", file.git.backend.unwrap_or_default()).to_lowercase(),
+            match file.git.backend {
+                Some(_) => file_origin(),
+                None => Origin::Default,
+            },
+        );
 
-        // Get the neoghq root directory
-        let root = env
-            .neoghq_root
-            .unwrap_or_else(|| PathBuf::from(DEFAULT_NEOGHQ_ROOT));
-
-        // Expand the root path if it contains a tilde
-        let root = if root.starts_with("~") {
-            if let Some(home_dir) = &home_dir {
-                home_dir.join(
-                    // this unwrap is safe because we checked that root starts with "~"
-                    root.strip_prefix("~").unwrap(),
+        record(
+            "trash.retention_days",
+            file.trash
+                .retention_days
+                .unwrap_or(DEFAULT_TRASH_RETENTION_DAYS)
+                .to_string(),
+            match file.trash.retention_days {
+                Some(_) => file_origin(),
+                None => Origin::Default,
+            },
+        );
+
+        let fetch_interval_minutes = file
+            .daemon
+            .fetch_interval_minutes
+            .unwrap_or(DEFAULT_DAEMON_FETCH_INTERVAL_MINUTES);
+        record(
+            "daemon.fetch_interval_minutes",
+            fetch_interval_minutes.to_string(),
+            match file.daemon.fetch_interval_minutes {
+                Some(_) => file_origin(),
+                None => Origin::Default,
+            },
+        );
+        // Zero would refresh in a busy loop
+        let status_interval_seconds = file
+            .daemon
+            .status_interval_seconds
+            .unwrap_or(DEFAULT_DAEMON_STATUS_INTERVAL_SECONDS)
+            .max(1);
+        record(
+            "daemon.status_interval_seconds",
+            status_interval_seconds.to_string(),
+            match file.daemon.status_interval_seconds {
+                Some(_) => file_origin(),
+                None => Origin::Default,
+            },
+        );
+        let watch = file.daemon.watch.unwrap_or(true);
+        record(
+            "daemon.watch",
+            watch.to_string(),
+            match file.daemon.watch {
+                Some(_) => file_origin(),
+                None => Origin::Default,
+            },
+        );
+        let fetch_interval = fetch_interval_minutes
+            .checked_mul(60)
+            .filter(|_| fetch_interval_minutes <= MAX_DAEMON_FETCH_INTERVAL_MINUTES)
+            .map(Duration::from_secs)
+            .ok_or_else(|| {
+                anyhow!(
+                    "daemon.fetch_interval_minutes must be at most {MAX_DAEMON_FETCH_INTERVAL_MINUTES}, got {fetch_interval_minutes}"
                 )
-            } else {
-                root
+            })?;
+        let daemon = DaemonConfig {
+            fetch_interval,
+            status_interval: Duration::from_secs(status_interval_seconds),
+            watch,
+        };
+
+        let templates = file
+            .templates
+            .into_iter()
+            .map(|(name, source)| {
+                let source = if source.starts_with('~') {
+                    expand_tilde(PathBuf::from(source), home_dir.as_deref())
+                        .display()
+                        .to_string()
+                } else {
+                    source
+                };
+                (name, source)
+            })
+            .collect();
+
+        if file.git_hooks.template_dir.is_some() && file.git_hooks.hooks_path.is_some() {
+            return Err(anyhow!(
+                "git_hooks.template_dir and git_hooks.hooks_path can't both be set"
+            ));
+        }
+        let git_hooks = GitHooksConfig {
+            template_dir: file
+                .git_hooks
+                .template_dir
+                .map(|path| expand_tilde(path, home_dir.as_deref())),
+            hooks_path: file
+                .git_hooks
+                .hooks_path
+                .map(|path| expand_tilde(path, home_dir.as_deref())),
+        };
+
+        let credentials = Credentials {
+            ssh_key: profile
+                .ssh_key
+                .map(|path| expand_tilde(path, home_dir.as_deref())),
+            token_env: profile.token_env,
+            token_command: profile.token_command,
+            prompt: false,
+        };
+        let identity = match (profile.user_name, profile.user_email) {
+            (Some(name), Some(email)) => Some(Identity { name, email }),
+            (None, None) => None,
+            _ => {
+                return Err(anyhow!(
+                    "Profile identity needs both user_name and user_email"
+                ));
             }
-        } else {
-            root
         };
 
-        Ok(Self { root })
+        Ok(Self {
+            root,
+            roots,
+            hosts,
+            user,
+            ghq: env.ghq,
+            cache_dir: env.cache_dir,
+            crash_reports,
+            default_branch: file.git.default_branch,
+            init_default_branch: env.init_default_branch,
+            git_backend: file.git.backend.unwrap_or_default(),
+            config_path: env.config_path,
+            profile: env.profile.clone(),
+            default_host: profile.default_host.or(file.general.default_host),
+            clone_protocol: file.clone.protocol.unwrap_or_default(),
+            protocol_override: None,
+            fork_upstream: file.clone.fork_upstream.unwrap_or(true),
+            issue_branch: file.worktree.issue_branch,
+            issue_description: file.worktree.issue_description.unwrap_or(true),
+            git_hooks,
+            credentials,
+            identity,
+            aliases: file.aliases,
+            repos: file.repo,
+            templates,
+            picker_command: file.picker.command,
+            open_command: file.open.command,
+            editor_command: file.editor.command,
+            prompt_format: file.prompt.format,
+            output: OutputFormat::default(),
+            color: file.ui.color.unwrap_or_default(),
+            icons: file.ui.icons.unwrap_or_default(),
+            trash_retention_days: file
+                .trash
+                .retention_days
+                .unwrap_or(DEFAULT_TRASH_RETENTION_DAYS),
+            daemon,
+            follow_redirects: file.fetch.follow_redirects.unwrap_or(false),
+            fetch_prune: Prune {
+                branches: file.fetch.prune.unwrap_or(false)
+                    || file.fetch.prune_tags.unwrap_or(false),
+                tags: file.fetch.prune_tags.unwrap_or(false),
+            },
+            dry_run: false,
+            no_input: false,
+            origins,
+        })
+    }
+
+    /// Returns the branch used when none is given.
+    pub fn default_branch(&self) -> &str {
+        self.default_branch.as_deref().unwrap_or(DEFAULT_BRANCH)
+    }
+
+    /// Returns the branch `repo create` starts new repositories on:
+    /// `git.default_branch`, then gitconfig's `init.defaultBranch`.
+    pub fn initial_branch(&self) -> &str {
+        self.default_branch
+            .as_deref()
+            .or(self.init_default_branch.as_deref())
+            .unwrap_or(DEFAULT_BRANCH)
+    }
+
+    /// Whether commands may prompt: not with `--no-input`, and only when
+    /// stdin is a terminal so CI jobs never wait for an answer.
+    pub fn interactive(&self) -> bool {
+        !self.no_input && std::io::stdin().is_terminal()
+    }
+
+    /// Returns every configured root, starting with the primary one, then
+    /// the `[roots]` table and the `[hosts."<host>"] root` paths, named
+    /// after their host.
+    pub fn all_roots(&self) -> Vec<Root> {
+        let mut roots = vec![Root {
+            name: DEFAULT_ROOT_NAME.to_string(),
+            path: self.root.clone(),
+        }];
+        let host_roots = self.hosts.iter().filter_map(|(host, config)| {
+            config.root.as_ref().map(|path| Root {
+                name: host.clone(),
+                path: path.clone(),
+            })
+        });
+        for root in self.roots.iter().cloned().chain(host_roots) {
+            if !roots.iter().any(|r| r.path == root.path) {
+                roots.push(root);
+            }
+        }
+        roots
+    }
+
+    /// Makes `selector`, a root name or a path, the only root for the
+    /// global `--root`: the other roots and `[hosts."<host>"] root` rules
+    /// are dropped, so nothing outside it is read or written.
+    pub fn use_only_root(&mut self, selector: &str) -> Result<()> {
+        let root = match self
+            .all_roots()
+            .into_iter()
+            .find(|root| root.name == selector)
+        {
+            Some(root) => root.path,
+            None => std::path::absolute(selector)?,
+        };
+        self.root = root;
+        self.roots.clear();
+        for host in self.hosts.values_mut() {
+            host.root = None;
+        }
+        self.origins.retain(|key, _| !key.starts_with("roots."));
+        self.origins.insert(
+            "general.root".to_string(),
+            Setting {
+                value: self.root.display().to_string(),
+                origin: Origin::Flag("--root"),
+            },
+        );
+        Ok(())
+    }
+
+    /// Returns the root selected with `--root`, if any.
+    pub fn root_flag(&self) -> Option<&Path> {
+        self.origins
+            .get("general.root")
+            .is_some_and(|setting| setting.origin == Origin::Flag("--root"))
+            .then_some(self.root.as_path())
+    }
+
+    /// Returns the protocol of clone URLs built for `host`: `--protocol`,
+    /// then `[hosts."<host>"] protocol`, then `clone.protocol`.
+    pub fn clone_protocol_for(&self, host: &str) -> CloneProtocol {
+        self.protocol_override
+            .or_else(|| self.hosts.get(host).and_then(|host| host.protocol))
+            .unwrap_or(self.clone_protocol)
+    }
+
+    /// Returns the root configured for `host`, if any.
+    pub fn host_root(&self, host: &str) -> Option<&Path> {
+        self.hosts.get(host).and_then(|host| host.root.as_deref())
+    }
+}
+
+/// Reads the `[defaults]` table, mapping subcommand paths such as
+/// `"repo list"` to arguments inserted before the command line is parsed.
+pub fn load_default_args(env: &Env) -> Result<BTreeMap<String, Vec<String>>> {
+    Ok(ConfigFile::load(env.config_path.as_deref())?.defaults)
+}
+
+fn expand_tilde(path: PathBuf, home_dir: Option<&Path>) -> PathBuf {
+    // Expand the path if it starts with a tilde
+    if path.starts_with("~") {
+        if let Some(home_dir) = home_dir {
+            home_dir.join(
+                // this unwrap is safe because we checked that path starts with "~"
+                path.strip_prefix("~")
+                    .unwrap()
+                    // Rebuilding the path turns `~/src/repos` into `src\repos` on Windows
+                    .components()
+                    .collect::<PathBuf>(),
+            )
+        } else {
+            path
+        }
+    } else {
+        path
     }
 }
 
@@ -61,7 +934,7 @@ mod tests {
         let temp_dir = tempfile::tempdir().unwrap();
         let env = Env {
             neoghq_root: Some(temp_dir.path().to_path_buf()),
-            home: None,
+            ..Default::default()
         };
         let config = Config::load(env).unwrap();
 
@@ -75,8 +948,8 @@ mod tests {
         std::fs::create_dir_all(&src_repos_dir).unwrap();
 
         let env = Env {
-            neoghq_root: None,
             home: Some(temp_dir.path().to_path_buf()),
+            ..Default::default()
         };
         let config = Config::load(env).unwrap();
 
@@ -92,9 +965,722 @@ mod tests {
         let env = Env {
             neoghq_root: Some(PathBuf::from("~/neoghq")),
             home: Some(temp_dir.path().to_path_buf()),
+            ..Default::default()
         };
         let config = Config::load(env).unwrap();
 
         assert_eq!(config.root, neoghq_dir.canonicalize().unwrap());
     }
+
+    #[test]
+    fn test_config_load_with_config_file_roots() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+        std::fs::write(
+            &config_path,
+            r#"
+[general]
+root = "~/personal"
+
+[roots]
+work = "/work/repos"
+"#,
+        )
+        .unwrap();
+
+        let env = Env {
+            home: Some(temp_dir.path().to_path_buf()),
+            config_path: Some(config_path),
+            ..Default::default()
+        };
+        let config = Config::load(env).unwrap();
+
+        assert_eq!(config.root, temp_dir.path().join("personal"));
+        assert_eq!(
+            config.all_roots(),
+            vec![
+                Root {
+                    name: "default".to_string(),
+                    path: temp_dir.path().join("personal"),
+                },
+                Root {
+                    name: "work".to_string(),
+                    path: PathBuf::from("/work/repos"),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_config_load_env_root_overrides_config_file() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+        std::fs::write(&config_path, "[general]\nroot = \"/from/file\"\n").unwrap();
+
+        let env = Env {
+            neoghq_root: Some(PathBuf::from("/from/env")),
+            config_path: Some(config_path),
+            ..Default::default()
+        };
+        let config = Config::load(env).unwrap();
+
+        assert_eq!(config.root, PathBuf::from("/from/env"));
+    }
+
+    #[test]
+    fn test_config_load_with_invalid_config_file() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+        std::fs::write(&config_path, "[general\n").unwrap();
+
+        let env = Env {
+            config_path: Some(config_path),
+            ..Default::default()
+        };
+
+        assert!(Config::load(env).is_err());
+    }
+
+    #[test]
+    fn test_config_load_with_host_roots() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+        std::fs::write(
+            &config_path,
+            r#"
+[hosts."gitlab.company.com"]
+root = "~/work"
+protocol = "ssh"
+
+[hosts."git.company.com"]
+root = "~/work"
+"#,
+        )
+        .unwrap();
+
+        let env = Env {
+            home: Some(temp_dir.path().to_path_buf()),
+            config_path: Some(config_path),
+            ..Default::default()
+        };
+        let config = Config::load(env).unwrap();
+
+        assert_eq!(
+            config.host_root("gitlab.company.com"),
+            Some(temp_dir.path().join("work").as_path())
+        );
+        assert_eq!(config.host_root("github.com"), None);
+        // Both hosts share one root, walked once
+        assert_eq!(
+            config.all_roots()[1..],
+            [Root {
+                name: "git.company.com".to_string(),
+                path: temp_dir.path().join("work"),
+            }]
+        );
+        assert_eq!(
+            config.clone_protocol_for("gitlab.company.com"),
+            CloneProtocol::Ssh
+        );
+        assert_eq!(
+            config.clone_protocol_for("github.com"),
+            CloneProtocol::Https
+        );
+        let config = Config {
+            protocol_override: Some(CloneProtocol::Https),
+            ..config
+        };
+        assert_eq!(
+            config.clone_protocol_for("gitlab.company.com"),
+            CloneProtocol::Https
+        );
+    }
+
+    #[test]
+    fn test_config_load_falls_back_to_ghq_settings() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let env = Env {
+            home: Some(temp_dir.path().to_path_buf()),
+            ghq: GhqConfig {
+                roots: vec![PathBuf::from("~/ghq"), PathBuf::from("/work/ghq")],
+                user: Some("octocat".to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let config = Config::load(env).unwrap();
+
+        assert_eq!(config.root, temp_dir.path().join("ghq"));
+        assert_eq!(
+            config.roots,
+            vec![Root {
+                name: "ghq-2".to_string(),
+                path: PathBuf::from("/work/ghq"),
+            }]
+        );
+        assert_eq!(config.user.as_deref(), Some("octocat"));
+    }
+
+    #[test]
+    fn test_config_load_neoghq_settings_override_ghq_settings() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+        std::fs::write(
+            &config_path,
+            "[general]\nroot = \"/neoghq\"\nuser = \"neo\"\n",
+        )
+        .unwrap();
+
+        let env = Env {
+            config_path: Some(config_path),
+            ghq: GhqConfig {
+                roots: vec![PathBuf::from("/ghq")],
+                user: Some("octocat".to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let config = Config::load(env).unwrap();
+
+        assert_eq!(config.root, PathBuf::from("/neoghq"));
+        assert!(config.roots.is_empty());
+        assert_eq!(config.user.as_deref(), Some("neo"));
+    }
+
+    #[test]
+    fn test_config_load_crash_reports() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+        std::fs::write(&config_path, "[general]\ncrash_reports = \"always\"\n").unwrap();
+
+        let env = Env {
+            config_path: Some(config_path),
+            ..Default::default()
+        };
+        let config = Config::load(env).unwrap();
+
+        assert_eq!(config.crash_reports, CrashReportMode::Always);
+        assert_eq!(Config::default().crash_reports, CrashReportMode::Panic);
+    }
+
+    #[test]
+    fn test_config_load_git_hooks() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+        std::fs::write(&config_path, "[git_hooks]\nhooks_path = \"~/hooks\"\n").unwrap();
+        let env = || Env {
+            config_path: Some(config_path.clone()),
+            home: Some(PathBuf::from("/home/neo")),
+            ..Default::default()
+        };
+
+        let config = Config::load(env()).unwrap();
+        assert_eq!(
+            config.git_hooks,
+            GitHooksConfig {
+                template_dir: None,
+                hooks_path: Some(PathBuf::from("/home/neo/hooks")),
+            }
+        );
+
+        std::fs::write(
+            &config_path,
+            "[git_hooks]\nhooks_path = \"/hooks\"\ntemplate_dir = \"/templates\"\n",
+        )
+        .unwrap();
+        assert!(Config::load(env()).is_err());
+    }
+
+    #[test]
+    fn test_config_load_git_section() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+        std::fs::write(
+            &config_path,
+            "[git]\ndefault_branch = \"trunk\"\n\n[clone]\nprotocol = \"ssh\"\n",
+        )
+        .unwrap();
+
+        let env = Env {
+            config_path: Some(config_path.clone()),
+            ..Default::default()
+        };
+        let config = Config::load(env).unwrap();
+
+        assert_eq!(config.default_branch(), "trunk");
+        assert_eq!(config.config_path, Some(config_path));
+        assert_eq!(config.clone_protocol, CloneProtocol::Ssh);
+        assert_eq!(Config::default().default_branch(), "main");
+        assert_eq!(Config::default().clone_protocol, CloneProtocol::Https);
+    }
+
+    #[test]
+    fn test_config_load_picker() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+        std::fs::write(&config_path, "[picker]\ncommand = \"fzf --height 40%\"\n").unwrap();
+
+        let env = Env {
+            config_path: Some(config_path),
+            ..Default::default()
+        };
+        let config = Config::load(env).unwrap();
+
+        assert_eq!(config.picker_command.as_deref(), Some("fzf --height 40%"));
+    }
+
+    #[test]
+    fn test_config_load_trash_retention() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+        let env = Env {
+            config_path: Some(config_path.clone()),
+            ..Default::default()
+        };
+        assert_eq!(Config::load(env.clone()).unwrap().trash_retention_days, 30);
+
+        std::fs::write(&config_path, "[trash]\nretention_days = 0\n").unwrap();
+        let config = Config::load(env).unwrap();
+        assert_eq!(config.trash_retention_days, 0);
+        assert_eq!(config.origins["trash.retention_days"].value, "0");
+    }
+
+    #[test]
+    fn test_config_load_follow_redirects() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+        let env = Env {
+            config_path: Some(config_path.clone()),
+            ..Default::default()
+        };
+        assert!(!Config::load(env.clone()).unwrap().follow_redirects);
+
+        std::fs::write(&config_path, "[fetch]\nfollow_redirects = true\n").unwrap();
+        assert!(Config::load(env).unwrap().follow_redirects);
+    }
+
+    #[test]
+    fn test_config_load_fetch_prune() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+        let env = Env {
+            config_path: Some(config_path.clone()),
+            ..Default::default()
+        };
+        assert_eq!(
+            Config::load(env.clone()).unwrap().fetch_prune,
+            Prune::default()
+        );
+
+        std::fs::write(&config_path, "[fetch]\nprune = true\n").unwrap();
+        assert_eq!(
+            Config::load(env.clone()).unwrap().fetch_prune,
+            Prune {
+                branches: true,
+                tags: false
+            }
+        );
+        std::fs::write(&config_path, "[fetch]\nprune_tags = true\n").unwrap();
+        assert_eq!(
+            Config::load(env).unwrap().fetch_prune,
+            Prune {
+                branches: true,
+                tags: true
+            }
+        );
+    }
+
+    #[test]
+    fn test_config_load_daemon() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+        let env = Env {
+            config_path: Some(config_path.clone()),
+            ..Default::default()
+        };
+        assert_eq!(
+            Config::load(env.clone()).unwrap().daemon,
+            DaemonConfig::default()
+        );
+
+        std::fs::write(
+            &config_path,
+            "[daemon]\nfetch_interval_minutes = 0\nstatus_interval_seconds = 0\nwatch = false\n",
+        )
+        .unwrap();
+        let config = Config::load(env).unwrap();
+        assert_eq!(config.daemon.fetch_interval, Duration::ZERO);
+        assert_eq!(config.daemon.status_interval, Duration::from_secs(1));
+        assert_eq!(config.origins["daemon.status_interval_seconds"].value, "1");
+        assert!(!config.daemon.watch);
+    }
+
+    #[test]
+    fn test_config_load_rejects_overflowing_fetch_interval() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+        let env = Env {
+            config_path: Some(config_path.clone()),
+            ..Default::default()
+        };
+
+        std::fs::write(
+            &config_path,
+            format!("[daemon]\nfetch_interval_minutes = {}\n", i64::MAX),
+        )
+        .unwrap();
+        assert!(Config::load(env.clone()).is_err());
+
+        std::fs::write(
+            &config_path,
+            format!("[daemon]\nfetch_interval_minutes = {MAX_DAEMON_FETCH_INTERVAL_MINUTES}\n"),
+        )
+        .unwrap();
+        assert_eq!(
+            Config::load(env).unwrap().daemon.fetch_interval,
+            Duration::from_secs(MAX_DAEMON_FETCH_INTERVAL_MINUTES * 60)
+        );
+    }
+
+    #[test]
+    fn test_config_load_ui() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+        std::fs::write(
+            &config_path,
+            "[ui]\ncolor = \"never\"\nicons = \"nerd-font\"\n",
+        )
+        .unwrap();
+
+        let env = Env {
+            config_path: Some(config_path),
+            ..Default::default()
+        };
+        let config = Config::load(env).unwrap();
+
+        assert_eq!(config.color, ColorChoice::Never);
+        assert_eq!(config.icons, Icons::NerdFont);
+        assert_eq!(Config::default().color, ColorChoice::Auto);
+    }
+
+    #[test]
+    fn test_load_default_args() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+        std::fs::write(&config_path, "[defaults]\n\"repo list\" = [\"--tsv\"]\n").unwrap();
+
+        let env = Env {
+            config_path: Some(config_path),
+            ..Default::default()
+        };
+        let defaults = load_default_args(&env).unwrap();
+
+        assert_eq!(defaults["repo list"], ["--tsv"]);
+        assert!(load_default_args(&Env::default()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_config_load_with_templates() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+        std::fs::write(
+            &config_path,
+            "[templates]\nlocal = \"~/templates/lib\"\nremote = \"https://example.com/t.git\"\n",
+        )
+        .unwrap();
+
+        let env = Env {
+            config_path: Some(config_path),
+            home: Some(PathBuf::from("/home/user")),
+            ..Default::default()
+        };
+        let config = Config::load(env).unwrap();
+
+        assert_eq!(config.templates["local"], "/home/user/templates/lib");
+        assert_eq!(config.templates["remote"], "https://example.com/t.git");
+    }
+
+    #[test]
+    fn test_interpolate_env() {
+        let lookup = |name: &str| match name {
+            "HOME" => Some("/home/user".to_string()),
+            "EMPTY" => Some(String::new()),
+            _ => None,
+        };
+        let mut value: toml::Value = toml::from_str(
+            r#"
+root = "${HOME}/repos"
+token = "${MISSING:-fallback}"
+empty = "${EMPTY:-default}"
+literal = "$${HOME} costs $5"
+list = ["${HOME}"]
+"#,
+        )
+        .unwrap();
+
+        interpolate_env(&mut value, &lookup).unwrap();
+
+        assert_eq!(value["root"].as_str(), Some("/home/user/repos"));
+        assert_eq!(value["token"].as_str(), Some("fallback"));
+        assert_eq!(value["empty"].as_str(), Some("default"));
+        assert_eq!(value["literal"].as_str(), Some("${HOME} costs $5"));
+        assert_eq!(value["list"][0].as_str(), Some("/home/user"));
+    }
+
+    #[test]
+    fn test_interpolate_env_errors() {
+        let lookup = |_: &str| None;
+
+        let mut value = toml::Value::String("${MISSING}".to_string());
+        assert!(interpolate_env(&mut value, &lookup).is_err());
+
+        let mut value = toml::Value::String("${UNTERMINATED".to_string());
+        assert!(interpolate_env(&mut value, &lookup).is_err());
+    }
+
+    #[test]
+    fn test_config_load_records_origins() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+        std::fs::write(&config_path, "[general]\nuser = \"me\"\nroot = \"/file\"\n").unwrap();
+
+        let env = Env {
+            neoghq_root: Some(PathBuf::from("/env")),
+            config_path: Some(config_path.clone()),
+            ghq: GhqConfig {
+                roots: vec![PathBuf::from("/ghq")],
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let config = Config::load(env).unwrap();
+
+        let origin = |key: &str| config.origins[key].origin.to_string();
+        assert_eq!(origin("general.root"), "env:NEOGHQ_ROOT");
+        assert_eq!(config.origins["general.root"].value, "/env");
+        assert_eq!(
+            origin("general.user"),
+            format!("file:{}", config_path.display())
+        );
+        assert_eq!(origin("general.crash_reports"), "default");
+        assert_eq!(origin("git.default_branch"), "default");
+
+        let config = Config::load(Env {
+            ghq: GhqConfig {
+                roots: vec![PathBuf::from("/ghq"), PathBuf::from("/ghq2")],
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(
+            config.origins["general.root"].origin,
+            Origin::Gitconfig("ghq.root")
+        );
+        assert_eq!(
+            config.origins["roots.ghq-2"].origin,
+            Origin::Gitconfig("ghq.root")
+        );
+    }
+
+    #[test]
+    fn test_initial_branch() {
+        let mut config = Config {
+            init_default_branch: Some("master".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(config.initial_branch(), "master");
+
+        config.default_branch = Some("trunk".to_string());
+        assert_eq!(config.initial_branch(), "trunk");
+
+        assert_eq!(Config::default().initial_branch(), "main");
+    }
+
+    #[test]
+    fn test_config_load_with_profile() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+        std::fs::write(
+            &config_path,
+            r#"
+[general]
+root = "~/personal"
+default_host = "github.com"
+
+[profile.work]
+root = "~/work"
+default_host = "gitlab.company.com"
+ssh_key = "~/.ssh/id_work"
+token_env = "WORK_TOKEN"
+user_name = "Jane Doe"
+user_email = "jane@company.com"
+"#,
+        )
+        .unwrap();
+
+        let env = Env {
+            home: Some(temp_dir.path().to_path_buf()),
+            config_path: Some(config_path.clone()),
+            profile: Some("work".to_string()),
+            ..Default::default()
+        };
+        let config = Config::load(env).unwrap();
+
+        assert_eq!(config.root, temp_dir.path().join("work"));
+        assert_eq!(config.default_host.as_deref(), Some("gitlab.company.com"));
+        assert_eq!(
+            config.credentials,
+            Credentials {
+                ssh_key: Some(temp_dir.path().join(".ssh/id_work")),
+                token_env: Some("WORK_TOKEN".to_string()),
+                token_command: None,
+                prompt: false,
+            }
+        );
+        assert_eq!(
+            config.identity,
+            Some(Identity {
+                name: "Jane Doe".to_string(),
+                email: "jane@company.com".to_string(),
+            })
+        );
+
+        // Without a profile the base settings apply
+        let env = Env {
+            home: Some(temp_dir.path().to_path_buf()),
+            config_path: Some(config_path),
+            ..Default::default()
+        };
+        let config = Config::load(env).unwrap();
+
+        assert_eq!(config.root, temp_dir.path().join("personal"));
+        assert_eq!(config.default_host.as_deref(), Some("github.com"));
+        assert_eq!(config.identity, None);
+    }
+
+    #[test]
+    fn test_config_load_with_unknown_profile() {
+        let env = Env {
+            profile: Some("missing".to_string()),
+            ..Default::default()
+        };
+
+        assert!(Config::load(env).is_err());
+    }
+
+    #[test]
+    fn test_config_load_with_incomplete_profile_identity() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+        std::fs::write(&config_path, "[profile.work]\nuser_name = \"Jane\"\n").unwrap();
+
+        let env = Env {
+            config_path: Some(config_path),
+            profile: Some("work".to_string()),
+            ..Default::default()
+        };
+
+        assert!(Config::load(env).is_err());
+    }
+
+    #[test]
+    fn test_config_load_with_aliases() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+        std::fs::write(
+            &config_path,
+            "[aliases.work]\nhost = \"git.company.com\"\nprotocol = \"ssh\"\n",
+        )
+        .unwrap();
+
+        let env = Env {
+            config_path: Some(config_path),
+            ..Default::default()
+        };
+        let config = Config::load(env).unwrap();
+
+        assert_eq!(
+            config.aliases.get("work"),
+            Some(&HostAlias {
+                host: "git.company.com".to_string(),
+                protocol: Some(CloneProtocol::Ssh),
+            })
+        );
+    }
+
+    #[test]
+    fn test_config_load_with_repo_settings() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+        std::fs::write(
+            &config_path,
+            r#"
+[repo."github.com/org/monorepo"]
+default_worktree = "develop"
+sparse = ["services/api"]
+links = [".env"]
+
+[repo."github.com/org/monorepo".hooks]
+post_create = ["make setup"]
+"#,
+        )
+        .unwrap();
+
+        let env = Env {
+            config_path: Some(config_path),
+            ..Default::default()
+        };
+        let config = Config::load(env).unwrap();
+
+        let settings = &config.repos["github.com/org/monorepo"];
+        assert_eq!(settings.default_worktree.as_deref(), Some("develop"));
+        assert_eq!(settings.sparse, Some(vec!["services/api".to_string()]));
+        assert_eq!(settings.links, Some(vec![PathBuf::from(".env")]));
+        assert_eq!(
+            settings.hooks.post_create,
+            Some(vec!["make setup".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_use_only_root() {
+        let config = Config {
+            root: PathBuf::from("/personal"),
+            roots: vec![Root {
+                name: "work".to_string(),
+                path: PathBuf::from("/work/repos"),
+            }],
+            hosts: [(
+                "gitlab.company.com".to_string(),
+                HostConfig {
+                    root: Some(PathBuf::from("/gitlab")),
+                    protocol: Some(CloneProtocol::Ssh),
+                    ..Default::default()
+                },
+            )]
+            .into(),
+            ..Default::default()
+        };
+
+        let mut work = config.clone();
+        work.use_only_root("work").unwrap();
+        assert_eq!(work.all_roots().len(), 1);
+        assert_eq!(work.root, PathBuf::from("/work/repos"));
+        assert_eq!(work.host_root("gitlab.company.com"), None);
+        assert_eq!(
+            work.clone_protocol_for("gitlab.company.com"),
+            CloneProtocol::Ssh
+        );
+        assert_eq!(work.origins["general.root"].origin, Origin::Flag("--root"));
+        assert_eq!(work.root_flag(), Some(Path::new("/work/repos")));
+        assert_eq!(config.root_flag(), None);
+
+        let mut other = config;
+        other.use_only_root("relative").unwrap();
+        assert_eq!(
+            other.root,
+            std::env::current_dir().unwrap().join("relative")
+        );
+    }
 }
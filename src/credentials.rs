@@ -0,0 +1,125 @@
+use crate::config::Credentials;
+use anyhow::{Result, anyhow};
+use git2::{Cred, CredentialType, RemoteCallbacks};
+use tracing::debug;
+
+/// Builds git2 callbacks that answer credential requests from the
+/// configured SSH key or HTTPS token source.
+pub fn remote_callbacks(credentials: &Credentials) -> RemoteCallbacks<'_> {
+    let mut callbacks = RemoteCallbacks::new();
+    let mut attempts = 0;
+
+    callbacks.credentials(move |url, username, allowed| {
+        // libgit2 keeps asking while credentials are rejected; give up early
+        attempts += 1;
+        debug!("Credentials requested for {url} (attempt {attempts}, allowed {allowed:?})");
+        if attempts > 3 {
+            return Err(git2::Error::new(
+                git2::ErrorCode::Auth,
+                git2::ErrorClass::Callback,
+                "authentication failed",
+            ));
+        }
+
+        if allowed.contains(CredentialType::SSH_KEY)
+            && let Some(ssh_key) = &credentials.ssh_key
+        {
+            debug!("Using SSH key {}", ssh_key.display());
+            return Cred::ssh_key(username.unwrap_or("git"), None, ssh_key, None);
+        }
+
+        if allowed.contains(CredentialType::USER_PASS_PLAINTEXT)
+            && let Some(token) =
+                resolve_token(credentials).map_err(|e| git2::Error::from_str(&e.to_string()))?
+        {
+            debug!("Using the configured HTTPS token");
+            return Cred::userpass_plaintext(username.unwrap_or("x-access-token"), &token);
+        }
+
+        if allowed.contains(CredentialType::SSH_KEY) {
+            debug!("Using the SSH agent");
+            return Cred::ssh_key_from_agent(username.unwrap_or("git"));
+        }
+
+        debug!("No configured credentials apply; trying defaults");
+        Cred::default()
+    });
+
+    callbacks
+}
+
+/// Reads the HTTPS token from the configured environment variable or command.
+pub fn resolve_token(credentials: &Credentials) -> Result<Option<String>> {
+    if let Some(name) = &credentials.token_env {
+        return std::env::var(name)
+            .map(Some)
+            .map_err(|_| anyhow!("Token environment variable {name} is not set"));
+    }
+
+    if let Some(command) = &credentials.token_command {
+        let output = std::process::Command::new("sh")
+            .args(["-c", command])
+            .output()?;
+        if !output.status.success() {
+            return Err(anyhow!("Token command failed: {command}"));
+        }
+        let token = String::from_utf8(output.stdout)?.trim().to_string();
+        return Ok(Some(token));
+    }
+
+    Ok(None)
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_token_without_source() {
+        let token = resolve_token(&Credentials::default()).unwrap();
+        assert_eq!(token, None);
+    }
+
+    #[test]
+    fn test_resolve_token_from_env() {
+        let credentials = Credentials {
+            token_env: Some("PATH".to_string()),
+            ..Default::default()
+        };
+
+        let token = resolve_token(&credentials).unwrap();
+        assert_eq!(token, std::env::var("PATH").ok());
+    }
+
+    #[test]
+    fn test_resolve_token_from_missing_env() {
+        let credentials = Credentials {
+            token_env: Some("NEOGHQ_TEST_UNSET_TOKEN".to_string()),
+            ..Default::default()
+        };
+
+        assert!(resolve_token(&credentials).is_err());
+    }
+
+    #[test]
+    fn test_resolve_token_from_command() {
+        let credentials = Credentials {
+            token_command: Some("echo ' secret-token '".to_string()),
+            ..Default::default()
+        };
+
+        let token = resolve_token(&credentials).unwrap();
+        assert_eq!(token.as_deref(), Some("secret-token"));
+    }
+
+    #[test]
+    fn test_resolve_token_from_failing_command() {
+        let credentials = Credentials {
+            token_command: Some("exit 1".to_string()),
+            ..Default::default()
+        };
+
+        assert!(resolve_token(&credentials).is_err());
+    }
+}
@@ -0,0 +1,544 @@
+use crate::commands::repo::fetch::fetch_with_retries;
+use crate::commands::status::{RepoHealth, repo_health};
+use crate::commands::worktree::{review, scratch};
+use crate::config::{Config, Credentials};
+use crate::repo_settings::RepoSettings;
+use crate::scan::{self, RepoEntry};
+use crate::status::{self, RepoStatus};
+use anyhow::{Context, Result, anyhow, bail};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::hash::BuildHasher;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+use tracing::{debug, info, warn};
+
+/// How long a client waits for the daemon before computing the status
+/// itself.
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// A repository's state as last computed by the daemon.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CachedStatus {
+    pub status: RepoStatus,
+    pub health: RepoHealth,
+    /// Disk usage in bytes, measured after each fetch.
+    pub size: u64,
+    pub updated: SystemTime,
+}
+
+/// What `daemon status` reports about the running daemon.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DaemonInfo {
+    pub pid: u32,
+    pub socket: PathBuf,
+    /// RFC 3339 time the daemon started.
+    pub started: String,
+    /// Repositories with a cached status.
+    pub repositories: usize,
+    /// Repositories excluded with `daemon = false`.
+    pub disabled: usize,
+    /// RFC 3339 time of the last status refresh.
+    pub last_refresh: Option<String>,
+    /// RFC 3339 time of the next scheduled fetch.
+    pub next_fetch: Option<String>,
+}
+
+/// A request sent to the daemon as one line of JSON, answered by one line
+/// of [`Response`].
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "request", rename_all = "snake_case")]
+enum Request {
+    Statuses { paths: Vec<PathBuf> },
+    Info,
+    Stop,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "response", rename_all = "snake_case")]
+enum Response {
+    Statuses {
+        statuses: Vec<(PathBuf, CachedStatus)>,
+    },
+    Info {
+        info: DaemonInfo,
+    },
+    Stopping,
+}
+
+/// Returns the socket the daemon listens on, `<cache dir>/daemon.sock`.
+pub fn socket_path(config: &Config) -> Result<PathBuf> {
+    let cache_dir = config
+        .cache_dir
+        .as_ref()
+        .ok_or_else(|| anyhow!("No cache directory for the daemon socket; set XDG_CACHE_HOME"))?;
+    Ok(cache_dir.join("daemon.sock"))
+}
+
+/// Returns the cached status of those `entries` the daemon knows, or
+/// nothing when no daemon is running; callers compute the rest themselves.
+pub fn cached_statuses(config: &Config, entries: &[RepoEntry]) -> HashMap<PathBuf, CachedStatus> {
+    let paths = entries.iter().map(|entry| entry.path.clone()).collect();
+    match send(config, &Request::Statuses { paths }) {
+        Some(Ok(Response::Statuses { statuses })) => statuses.into_iter().collect(),
+        Some(Ok(response)) => {
+            debug!("Unexpected daemon response: {response:?}");
+            HashMap::new()
+        }
+        Some(Err(error)) => {
+            debug!("Failed to query the daemon: {error:#}");
+            HashMap::new()
+        }
+        None => HashMap::new(),
+    }
+}
+
+/// Returns what the running daemon reports about itself, `None` when no
+/// daemon is running.
+pub fn info(config: &Config) -> Result<Option<DaemonInfo>> {
+    match send(config, &Request::Info).transpose()? {
+        Some(Response::Info { info }) => Ok(Some(info)),
+        Some(response) => bail!("Unexpected daemon response: {response:?}"),
+        None => Ok(None),
+    }
+}
+
+/// Asks the running daemon to exit. Returns whether one was running.
+pub fn stop(config: &Config) -> Result<bool> {
+    match send(config, &Request::Stop).transpose()? {
+        Some(Response::Stopping) => Ok(true),
+        Some(response) => bail!("Unexpected daemon response: {response:?}"),
+        None => Ok(false),
+    }
+}
+
+/// Sends `request` to the daemon, `None` when none is listening.
+#[cfg(unix)]
+fn send(config: &Config, request: &Request) -> Option<Result<Response>> {
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::net::UnixStream;
+
+    let stream = UnixStream::connect(socket_path(config).ok()?).ok()?;
+    Some((|| {
+        stream.set_read_timeout(Some(CLIENT_TIMEOUT))?;
+        stream.set_write_timeout(Some(CLIENT_TIMEOUT))?;
+        let mut line = serde_json::to_string(request)?;
+        line.push('\n');
+        (&stream).write_all(line.as_bytes())?;
+        let mut response = String::new();
+        BufReader::new(&stream).read_line(&mut response)?;
+        Ok(serde_json::from_str(&response)?)
+    })())
+}
+
+#[cfg(not(unix))]
+fn send(_config: &Config, _request: &Request) -> Option<Result<Response>> {
+    None
+}
+
+/// What the daemon has learned so far, shared by the refresh thread and
+/// the connections.
+#[derive(Debug, Default)]
+struct State {
+    statuses: HashMap<PathBuf, CachedStatus>,
+    disabled: usize,
+    last_refresh: Option<SystemTime>,
+    /// When each enabled repository is fetched next.
+    next_fetch: HashMap<PathBuf, Instant>,
+}
+
+/// Runs the daemon in the foreground until a `daemon stop`: refreshes the
+/// status of every repository each `daemon.status_interval_seconds`,
+/// fetches each about every `daemon.fetch_interval_minutes`, and answers
+/// clients on [`socket_path`].
+#[cfg(unix)]
+pub fn run(config: Config) -> Result<()> {
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::fs::PermissionsExt;
+    use std::os::unix::net::UnixListener;
+    use std::sync::mpsc::RecvTimeoutError;
+    use std::sync::{Arc, Mutex};
+
+    let socket = socket_path(&config)?;
+    if let Some(info) = info(&config)? {
+        bail!("The daemon is already running (PID {})", info.pid);
+    }
+    if let Some(parent) = socket.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    // Left behind by a daemon that didn't exit cleanly
+    let _ = std::fs::remove_file(&socket);
+    let listener = UnixListener::bind(&socket)
+        .with_context(|| format!("Failed to listen on {}", socket.display()))?;
+    std::fs::set_permissions(&socket, std::fs::Permissions::from_mode(0o600))?;
+    info!("Daemon listening on {}", socket.display());
+
+    let started = SystemTime::now();
+    let state = Arc::new(Mutex::new(State::default()));
+    let (wake, woken) = std::sync::mpsc::channel();
+    if config.daemon.watch {
+        watch_roots(&config, wake);
+    }
+    {
+        let config = config.clone();
+        let state = Arc::clone(&state);
+        std::thread::spawn(move || {
+            loop {
+                if let Err(error) = refresh(&config, &state) {
+                    warn!("Failed to refresh repositories: {error:#}");
+                }
+                match woken.recv_timeout(config.daemon.status_interval) {
+                    // Without a watcher nothing wakes the thread early
+                    Err(RecvTimeoutError::Disconnected) => {
+                        std::thread::sleep(config.daemon.status_interval)
+                    }
+                    _ => while woken.try_recv().is_ok() {},
+                }
+            }
+        });
+    }
+
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(error) => {
+                warn!("Failed to accept a connection: {error}");
+                continue;
+            }
+        };
+        let result = (|| -> Result<bool> {
+            stream.set_read_timeout(Some(CLIENT_TIMEOUT))?;
+            let mut line = String::new();
+            BufReader::new(&stream).read_line(&mut line)?;
+            let request = serde_json::from_str(&line)?;
+            let stop = matches!(request, Request::Stop);
+            let response = {
+                let state = state.lock().map_err(|_| anyhow!("Daemon state poisoned"))?;
+                respond(request, &state, &socket, started)
+            };
+            let mut line = serde_json::to_string(&response)?;
+            line.push('\n');
+            (&stream).write_all(line.as_bytes())?;
+            Ok(stop)
+        })();
+        match result {
+            Ok(true) => break,
+            Ok(false) => {}
+            Err(error) => debug!("Failed to answer a client: {error:#}"),
+        }
+    }
+
+    let _ = std::fs::remove_file(&socket);
+    info!("Daemon stopped");
+    Ok(())
+}
+
+/// Watches the roots on a thread of its own, sending to `wake` whenever
+/// repositories or worktrees change so their status is refreshed right away.
+/// The daemon keeps running without it if the watcher can't start.
+#[cfg(unix)]
+fn watch_roots(config: &Config, wake: std::sync::mpsc::Sender<()>) {
+    let roots = config
+        .all_roots()
+        .into_iter()
+        .map(|root| root.path)
+        .collect();
+    let mut watcher = match crate::watch::RootWatcher::new(roots) {
+        Ok(watcher) => watcher,
+        Err(error) => {
+            warn!("Failed to watch the roots: {error:#}");
+            return;
+        }
+    };
+    info!("Watching {} directories", watcher.watched());
+    std::thread::spawn(move || {
+        loop {
+            match watcher.poll(Duration::from_secs(3600)) {
+                Ok(changes) if changes.is_empty() => {}
+                Ok(changes) => {
+                    debug!("{} changes under the roots", changes.len());
+                    if wake.send(()).is_err() {
+                        return;
+                    }
+                }
+                Err(error) => {
+                    warn!("Stopped watching the roots: {error:#}");
+                    return;
+                }
+            }
+        }
+    });
+}
+
+#[cfg(not(unix))]
+pub fn run(_config: Config) -> Result<()> {
+    bail!("The daemon needs Unix domain sockets, which this platform lacks")
+}
+
+fn respond(request: Request, state: &State, socket: &Path, started: SystemTime) -> Response {
+    match request {
+        Request::Statuses { paths } => Response::Statuses {
+            statuses: paths
+                .into_iter()
+                .filter_map(|path| {
+                    let status = state.statuses.get(&path)?.clone();
+                    Some((path, status))
+                })
+                .collect(),
+        },
+        Request::Info => {
+            let now = Instant::now();
+            let next_fetch = state.next_fetch.values().min().map(|next| {
+                status::format_timestamp(SystemTime::now() + next.saturating_duration_since(now))
+            });
+            Response::Info {
+                info: DaemonInfo {
+                    pid: std::process::id(),
+                    socket: socket.to_path_buf(),
+                    started: status::format_timestamp(started),
+                    repositories: state.statuses.len(),
+                    disabled: state.disabled,
+                    last_refresh: state.last_refresh.map(status::format_timestamp),
+                    next_fetch,
+                },
+            }
+        }
+        Request::Stop => Response::Stopping,
+    }
+}
+
+/// Fetches the repositories that are due, then recomputes the status of
+/// every enabled one.
+fn refresh(config: &Config, state: &std::sync::Mutex<State>) -> Result<()> {
+    let poisoned = || anyhow!("Daemon state poisoned");
+    let (enabled, disabled): (Vec<_>, Vec<_>) = scan::scan_roots(&config.all_roots())?
+        .into_iter()
+        .partition(|entry| is_enabled(config, entry));
+
+    let now = Instant::now();
+    let fetch_interval = config.daemon.fetch_interval;
+    let (due, mut next_fetch) = {
+        let state = state.lock().map_err(|_| poisoned())?;
+        let mut next_fetch = HashMap::new();
+        let mut due = Vec::new();
+        for entry in &enabled {
+            match state.next_fetch.get(&entry.path) {
+                Some(next) if *next <= now => due.push(entry.clone()),
+                Some(next) => {
+                    next_fetch.insert(entry.path.clone(), *next);
+                }
+                // Spread the first fetches over a whole interval
+                None => {
+                    if let Some(next) = now.checked_add(fetch_interval.mul_f64(random_fraction())) {
+                        next_fetch.insert(entry.path.clone(), next);
+                    }
+                }
+            }
+        }
+        (due, next_fetch)
+    };
+    if fetch_interval.is_zero() {
+        next_fetch.clear();
+    }
+
+    // Nobody is there to answer a prompt
+    let credentials = Credentials {
+        prompt: false,
+        ..config.credentials.clone()
+    };
+    status::parallel_map(&due, None, |entry| {
+        if let Err(error) = fetch_with_retries(
+            entry,
+            &credentials,
+            0,
+            config.follow_redirects,
+            config.fetch_prune,
+        ) {
+            warn!("{}: {error:#}", entry.spec());
+        }
+    });
+    // A fetch past what the clock can count up to never comes due
+    for entry in &due {
+        if let Some(next) = now.checked_add(jittered(fetch_interval)) {
+            next_fetch.insert(entry.path.clone(), next);
+        }
+    }
+    if !due.is_empty() {
+        info!("Fetched {} repositories", due.len());
+    }
+    if let Err(error) = scratch::remove_all_expired(config) {
+        warn!("Failed to remove expired scratch worktrees: {error:#}");
+    }
+    if let Err(error) = review::remove_all_finished(config) {
+        warn!("Failed to remove finished review worktrees: {error:#}");
+    }
+
+    let previous = {
+        let state = state.lock().map_err(|_| poisoned())?;
+        state.statuses.clone()
+    };
+    let snapshots = status::parallel_map(&enabled, None, |entry| {
+        let size = previous
+            .get(&entry.path)
+            .filter(|_| !due.iter().any(|due| due.path == entry.path))
+            .map(|cached| cached.size);
+        snapshot(entry, size)
+            .inspect_err(|error| warn!("Skipping {}: {error:#}", entry.path.display()))
+            .ok()
+            .map(|snapshot| (entry.path.clone(), snapshot))
+    });
+
+    let mut state = state.lock().map_err(|_| poisoned())?;
+    state.statuses = snapshots.into_iter().flatten().collect();
+    state.disabled = disabled.len();
+    state.last_refresh = Some(SystemTime::now());
+    state.next_fetch = next_fetch;
+    Ok(())
+}
+
+fn is_enabled(config: &Config, entry: &RepoEntry) -> bool {
+    match RepoSettings::load(config, &entry.spec(), &entry.path) {
+        Ok(settings) => settings.daemon != Some(false),
+        Err(error) => {
+            warn!("{error:#}");
+            true
+        }
+    }
+}
+
+/// Computes the cached state of `entry`, measuring its size unless `size`
+/// is still current.
+fn snapshot(entry: &RepoEntry, size: Option<u64>) -> Result<CachedStatus> {
+    Ok(CachedStatus {
+        status: status::collect_repo_status(entry)?,
+        health: repo_health(entry)?,
+        size: size.unwrap_or_else(|| status::disk_usage(&entry.path)),
+        updated: SystemTime::now(),
+    })
+}
+
+/// Returns `interval` give or take 10%, so repositories fetched together
+/// once drift apart instead of hitting the remote at the same moment.
+fn jittered(interval: Duration) -> Duration {
+    interval.mul_f64(0.9 + 0.2 * random_fraction())
+}
+
+/// Returns a number in `[0, 1)`, from the randomly keyed hasher of the
+/// standard library.
+fn random_fraction() -> f64 {
+    let random = std::collections::hash_map::RandomState::new().hash_one(Instant::now());
+    (random >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use crate::config::DaemonConfig;
+    use crate::test_utils::create_managed_repository;
+
+    #[test]
+    fn test_jittered() {
+        let interval = Duration::from_secs(1000);
+        for _ in 0..100 {
+            let next = jittered(interval);
+            assert!(next >= Duration::from_secs(900) && next <= Duration::from_secs(1100));
+        }
+        assert_eq!(jittered(Duration::ZERO), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_refresh_skips_disabled_repositories() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        create_managed_repository(&temp_dir.path().join("github.com/user/repo"));
+        create_managed_repository(&temp_dir.path().join("github.com/user/big"));
+        std::fs::write(
+            temp_dir.path().join("github.com/user/big/.neoghq.toml"),
+            "daemon = false\n",
+        )
+        .unwrap();
+        let config = Config {
+            root: temp_dir.path().to_path_buf(),
+            ..Default::default()
+        };
+
+        let state = std::sync::Mutex::new(State::default());
+        refresh(&config, &state).unwrap();
+        let state = state.into_inner().unwrap();
+        let repo = temp_dir.path().join("github.com/user/repo");
+        assert_eq!(state.statuses.keys().collect::<Vec<_>>(), [&repo]);
+        assert_eq!(state.statuses[&repo].status.worktrees, 1);
+        assert!(state.statuses[&repo].size > 0);
+        assert_eq!(state.disabled, 1);
+        // The first fetch is scheduled, not run right away
+        assert!(state.next_fetch[&repo] <= Instant::now() + Duration::from_secs(30 * 60));
+    }
+
+    #[test]
+    fn test_refresh_schedules_the_longest_fetch_interval() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        create_managed_repository(&temp_dir.path().join("github.com/user/repo"));
+        let config = Config {
+            root: temp_dir.path().to_path_buf(),
+            daemon: DaemonConfig {
+                fetch_interval: Duration::from_secs(
+                    crate::config::MAX_DAEMON_FETCH_INTERVAL_MINUTES * 60,
+                ),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let state = std::sync::Mutex::new(State::default());
+        refresh(&config, &state).unwrap();
+        let repo = temp_dir.path().join("github.com/user/repo");
+        assert!(state.into_inner().unwrap().next_fetch.contains_key(&repo));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_serves_cached_statuses() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo_path = temp_dir.path().join("github.com/user/repo");
+        create_managed_repository(&repo_path);
+        std::fs::write(repo_path.join("main/NEW"), "new\n").unwrap();
+        let config = Config {
+            root: temp_dir.path().to_path_buf(),
+            cache_dir: Some(temp_dir.path().join("cache")),
+            daemon: DaemonConfig {
+                fetch_interval: Duration::ZERO,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert!(info(&config).unwrap().is_none());
+        assert!(!stop(&config).unwrap());
+
+        let server = std::thread::spawn({
+            let config = config.clone();
+            move || run(config)
+        });
+        let started = Instant::now();
+        let info = loop {
+            match info(&config).unwrap() {
+                Some(info) if info.last_refresh.is_some() => break info,
+                _ if started.elapsed() > Duration::from_secs(10) => panic!("daemon didn't start"),
+                _ => std::thread::sleep(Duration::from_millis(50)),
+            }
+        };
+        assert_eq!(info.pid, std::process::id());
+        assert_eq!(info.repositories, 1);
+        assert!(info.next_fetch.is_none());
+        assert!(run(config.clone()).is_err());
+
+        let entries = scan::scan_roots(&config.all_roots()).unwrap();
+        let statuses = cached_statuses(&config, &entries);
+        assert!(statuses[&repo_path].status.dirty);
+        assert_eq!(statuses[&repo_path].health.dirty.len(), 1);
+
+        assert!(stop(&config).unwrap());
+        server.join().unwrap().unwrap();
+        assert!(!socket_path(&config).unwrap().exists());
+        assert!(cached_statuses(&config, &entries).is_empty());
+    }
+}
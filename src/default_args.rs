@@ -0,0 +1,174 @@
+use clap::Command;
+use std::collections::BTreeMap;
+
+/// Global flag that disables `[defaults]` for a single invocation.
+pub const NO_DEFAULTS_FLAG: &str = "--no-defaults";
+
+/// Inserts the `[defaults]` arguments configured for the invoked subcommand
+/// right after its name, so flags given on the command line come later and
+/// override them. Keys are canonical subcommand paths such as `"repo list"`;
+/// aliases (`repo clone`) resolve to their canonical name.
+pub fn apply_default_args(
+    command: &Command,
+    args: Vec<String>,
+    defaults: &BTreeMap<String, Vec<String>>,
+) -> Vec<String> {
+    if defaults.is_empty() || args.iter().any(|arg| arg == NO_DEFAULTS_FLAG) {
+        return args;
+    }
+
+    let mut path = Vec::new();
+    let mut insert_at = None;
+    let mut current = command;
+    let mut index = 1;
+    while index < args.len() {
+        let arg = &args[index];
+        if arg == "--" {
+            break;
+        }
+        if let Some(long) = arg.strip_prefix("--") {
+            // Skip global options, and the value of those that take one
+            let takes_value = [command, current]
+                .into_iter()
+                .flat_map(Command::get_arguments)
+                .find(|a| a.get_long() == Some(long))
+                .is_some_and(|a| a.get_action().takes_values());
+            index += if takes_value { 2 } else { 1 };
+            continue;
+        }
+        if arg.starts_with('-') {
+            index += 1;
+            continue;
+        }
+
+        let Some(subcommand) = current.find_subcommand(arg) else {
+            break;
+        };
+        path.push(subcommand.get_name().to_string());
+        current = subcommand;
+        index += 1;
+        if defaults.contains_key(&path.join(" ")) {
+            insert_at = Some((index, path.join(" ")));
+        }
+    }
+
+    let Some((index, key)) = insert_at else {
+        return args;
+    };
+    let mut args = args;
+    args.splice(index..index, defaults[&key].iter().cloned());
+    args
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use crate::cli::Cli;
+    use clap::{CommandFactory, Parser};
+
+    fn apply(args: &[&str], defaults: &[(&str, &[&str])]) -> Vec<String> {
+        let defaults = defaults
+            .iter()
+            .map(|(key, args)| {
+                (
+                    key.to_string(),
+                    args.iter().map(|arg| arg.to_string()).collect(),
+                )
+            })
+            .collect();
+        let args = args.iter().map(|arg| arg.to_string()).collect();
+        apply_default_args(&Cli::command(), args, &defaults)
+    }
+
+    #[test]
+    fn test_apply_default_args_after_subcommand() {
+        assert_eq!(
+            apply(&["neoghq", "repo", "list"], &[("repo list", &["--tsv"])]),
+            ["neoghq", "repo", "list", "--tsv"]
+        );
+    }
+
+    #[test]
+    fn test_apply_default_args_before_cli_flags() {
+        assert_eq!(
+            apply(
+                &[
+                    "neoghq",
+                    "--profile",
+                    "work",
+                    "repo",
+                    "get",
+                    "x",
+                    "--root",
+                    "a"
+                ],
+                &[("repo get", &["--root", "b"])]
+            ),
+            [
+                "neoghq",
+                "--profile",
+                "work",
+                "repo",
+                "get",
+                "--root",
+                "b",
+                "x",
+                "--root",
+                "a"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_apply_default_args_resolves_aliases() {
+        assert_eq!(
+            apply(
+                &["neoghq", "repo", "clone", "x"],
+                &[("repo get", &["--root", "b"])]
+            ),
+            ["neoghq", "repo", "clone", "--root", "b", "x"]
+        );
+    }
+
+    #[test]
+    fn test_apply_default_args_prefers_most_specific_key() {
+        assert_eq!(
+            apply(
+                &["neoghq", "repo", "list"],
+                &[("repo", &["--flag"]), ("repo list", &["--tsv"])]
+            ),
+            ["neoghq", "repo", "list", "--tsv"]
+        );
+    }
+
+    #[test]
+    fn test_apply_default_args_without_match() {
+        assert_eq!(
+            apply(&["neoghq", "root"], &[("repo list", &["--tsv"])]),
+            ["neoghq", "root"]
+        );
+    }
+
+    #[test]
+    fn test_apply_default_args_disabled() {
+        assert_eq!(
+            apply(
+                &["neoghq", "--no-defaults", "repo", "list"],
+                &[("repo list", &["--tsv"])]
+            ),
+            ["neoghq", "--no-defaults", "repo", "list"]
+        );
+    }
+
+    #[test]
+    fn test_cli_flags_override_default_args() {
+        let args = apply(
+            &["neoghq", "repo", "get", "x", "--root", "a"],
+            &[("repo get", &["--root", "b"])],
+        );
+
+        let cli = Cli::try_parse_from(args).unwrap();
+        assert_eq!(cli.root.as_deref(), Some("a"));
+    }
+}
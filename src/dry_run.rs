@@ -0,0 +1,46 @@
+use crate::config::Config;
+use anyhow::Result;
+use std::fmt::Display;
+
+/// Applies a change that deletes or rewrites something, or with the global
+/// `--dry-run` only prints it as `Would <description>`. Commands route every
+/// destructive step through here so `--dry-run` covers all of them.
+pub fn apply(
+    config: &Config,
+    description: impl Display,
+    change: impl FnOnce() -> Result<()>,
+) -> Result<()> {
+    if config.dry_run {
+        println!("Would {description}");
+        return Ok(());
+    }
+    change()
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply() {
+        let mut applied = false;
+        let config = Config {
+            dry_run: true,
+            ..Default::default()
+        };
+        apply(&config, "remove x", || {
+            applied = true;
+            Ok(())
+        })
+        .unwrap();
+        assert!(!applied);
+
+        apply(&Config::default(), "remove x", || {
+            applied = true;
+            Ok(())
+        })
+        .unwrap();
+        assert!(applied);
+    }
+}
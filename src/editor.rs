@@ -0,0 +1,103 @@
+use crate::config::Config;
+use anyhow::{Context, Result, anyhow, bail};
+use std::path::Path;
+use std::process::Command;
+
+/// Opens `path` with `editor.command`, falling back to `$VISUAL` and then
+/// `$EDITOR`. The command runs through `sh -c` in `path` (or its directory,
+/// for a file) with its output on stderr, so `--open` never mixes editor
+/// output into the path a command prints for the shell.
+pub fn open(config: &Config, path: &Path) -> Result<()> {
+    let command = config
+        .editor_command
+        .clone()
+        .or_else(|| {
+            std::env::var("VISUAL")
+                .ok()
+                .filter(|value| !value.is_empty())
+        })
+        .or_else(|| {
+            std::env::var("EDITOR")
+                .ok()
+                .filter(|value| !value.is_empty())
+        })
+        .ok_or_else(|| {
+            anyhow!(
+                "No editor to open {} with: set editor.command",
+                path.display()
+            )
+        })?;
+    let command = command_line(&command, path);
+    let working_dir = if path.is_dir() {
+        path
+    } else {
+        path.parent().unwrap_or(path)
+    };
+    let status = Command::new("sh")
+        .args(["-c", &command])
+        .current_dir(working_dir)
+        .stdout(std::io::stderr())
+        .status()
+        .with_context(|| format!("Failed to run editor command {command}"))?;
+    if !status.success() {
+        bail!("Editor command {command} failed with {status}");
+    }
+    Ok(())
+}
+
+/// Substitutes the quoted path for each `{path}` in `command`, or appends it
+/// when there is none, so both `code -n {path}` and `nvim` work.
+fn command_line(command: &str, path: &Path) -> String {
+    let quoted = shell_quote(&path.to_string_lossy());
+    if command.contains("{path}") {
+        command.replace("{path}", &quoted)
+    } else {
+        format!("{command} {quoted}")
+    }
+}
+
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_line() {
+        let path = Path::new("/repos/it's here");
+        assert_eq!(
+            command_line("code -n {path}", path),
+            r"code -n '/repos/it'\''s here'"
+        );
+        assert_eq!(command_line("nvim", path), r"nvim '/repos/it'\''s here'");
+    }
+
+    #[test]
+    fn test_open_runs_editor_command() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let log = temp_dir.path().join("log");
+        let config = Config {
+            editor_command: Some(format!("echo {{path}} \"$(pwd -P)\" > {}", log.display())),
+            ..Default::default()
+        };
+
+        open(&config, temp_dir.path()).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&log).unwrap(),
+            format!(
+                "{} {}\n",
+                temp_dir.path().display(),
+                temp_dir.path().canonicalize().unwrap().display()
+            )
+        );
+
+        let config = Config {
+            editor_command: Some("false".to_string()),
+            ..Default::default()
+        };
+        assert!(open(&config, temp_dir.path()).is_err());
+    }
+}
@@ -0,0 +1,264 @@
+use crate::config::Config;
+use crate::scan::RepoEntry;
+use anyhow::{Context, Result, anyhow};
+use serde::Serialize;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use tracing::{debug, warn};
+
+/// Directory next to the configuration file holding the event hooks.
+const HOOKS_DIR: &str = "hooks.d";
+
+/// Changes neoghq tells the hooks in `hooks.d` about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum EventKind {
+    #[serde(rename = "repo.cloned")]
+    RepoCloned,
+    #[serde(rename = "repo.removed")]
+    RepoRemoved,
+    #[serde(rename = "worktree.created")]
+    WorktreeCreated,
+    #[serde(rename = "worktree.removed")]
+    WorktreeRemoved,
+}
+
+impl EventKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::RepoCloned => "repo.cloned",
+            Self::RepoRemoved => "repo.removed",
+            Self::WorktreeCreated => "worktree.created",
+            Self::WorktreeRemoved => "worktree.removed",
+        }
+    }
+}
+
+/// The JSON payload a hook reads from stdin.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Event {
+    pub event: EventKind,
+    /// `host/owner/repo`, unless the repository lives outside a root.
+    pub repo: Option<String>,
+    /// The repository directory holding `.git` and the worktrees.
+    pub repo_path: PathBuf,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub worktree_path: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+}
+
+impl Event {
+    pub fn repo(event: EventKind, repo_path: &Path) -> Self {
+        Self {
+            event,
+            repo: RepoEntry::from_path(repo_path).map(|entry| entry.spec()),
+            repo_path: repo_path.to_path_buf(),
+            worktree_path: None,
+            branch: None,
+        }
+    }
+
+    pub fn worktree(
+        event: EventKind,
+        repo_path: &Path,
+        worktree_path: &Path,
+        branch: Option<&str>,
+    ) -> Self {
+        Self {
+            worktree_path: Some(worktree_path.to_path_buf()),
+            branch: branch.map(str::to_string),
+            ..Self::repo(event, repo_path)
+        }
+    }
+}
+
+/// Runs every executable in `hooks.d` with the event name as its argument
+/// and `event` as JSON on stdin. Hooks only observe: their failures are
+/// logged, and their output goes to stderr so it never mixes with paths
+/// commands print for the shell.
+pub fn emit(config: &Config, event: &Event) {
+    let Some(dir) = hooks_dir(config) else {
+        return;
+    };
+    let hooks = match hooks(&dir) {
+        Ok(hooks) => hooks,
+        Err(error) => {
+            warn!("Failed to read {}: {error:#}", dir.display());
+            return;
+        }
+    };
+    if hooks.is_empty() {
+        return;
+    }
+    let payload = match serde_json::to_vec(event) {
+        Ok(payload) => payload,
+        Err(error) => {
+            warn!("Failed to encode {} event: {error}", event.event.name());
+            return;
+        }
+    };
+    let working_dir = event
+        .worktree_path
+        .as_deref()
+        .filter(|path| path.is_dir())
+        .or_else(|| Some(event.repo_path.as_path()).filter(|path| path.is_dir()));
+    for hook in hooks {
+        debug!("Running {} hook {}", event.event.name(), hook.display());
+        if let Err(error) = run_hook(&hook, event.event, &payload, working_dir) {
+            warn!("{error:#}");
+        }
+    }
+}
+
+fn hooks_dir(config: &Config) -> Option<PathBuf> {
+    Some(config.config_path.as_deref()?.parent()?.join(HOOKS_DIR))
+}
+
+/// Returns the executables in `dir` sorted by name, so hooks can be ordered
+/// with numeric prefixes. Hidden files, such as editor backups, are skipped.
+fn hooks(dir: &Path) -> Result<Vec<PathBuf>> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(error) => return Err(error.into()),
+    };
+    let mut hooks = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        let hidden = path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+        if !hidden && is_executable(&path) {
+            hooks.push(path);
+        }
+    }
+    hooks.sort();
+    Ok(hooks)
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path)
+        .is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+fn run_hook(
+    hook: &Path,
+    event: EventKind,
+    payload: &[u8],
+    working_dir: Option<&Path>,
+) -> Result<()> {
+    let mut command = Command::new(hook);
+    command
+        .arg(event.name())
+        .env("NEOGHQ_EVENT", event.name())
+        .stdin(Stdio::piped())
+        .stdout(std::io::stderr());
+    if let Some(dir) = working_dir {
+        command.current_dir(dir);
+    }
+    let mut child = command
+        .spawn()
+        .with_context(|| format!("Failed to run hook {}", hook.display()))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // A hook that doesn't read its payload closes the pipe early
+        let _ = stdin.write_all(payload);
+    }
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(anyhow!(
+            "Hook {} failed on {} with {status}",
+            hook.display(),
+            event.name()
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_payload() {
+        let event = Event::worktree(
+            EventKind::WorktreeCreated,
+            Path::new("/repos/github.com/user/repo"),
+            Path::new("/repos/github.com/user/repo/main"),
+            Some("main"),
+        );
+        assert_eq!(
+            serde_json::to_value(&event).unwrap(),
+            serde_json::json!({
+                "event": "worktree.created",
+                "repo": "github.com/user/repo",
+                "repo_path": "/repos/github.com/user/repo",
+                "worktree_path": "/repos/github.com/user/repo/main",
+                "branch": "main",
+            })
+        );
+
+        let event = Event::repo(
+            EventKind::RepoRemoved,
+            Path::new("/repos/github.com/user/repo"),
+        );
+        assert_eq!(
+            serde_json::to_value(&event).unwrap(),
+            serde_json::json!({
+                "event": "repo.removed",
+                "repo": "github.com/user/repo",
+                "repo_path": "/repos/github.com/user/repo",
+            })
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_emit_runs_executable_hooks_in_order() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let hooks_dir = temp_dir.path().join(HOOKS_DIR);
+        std::fs::create_dir(&hooks_dir).unwrap();
+        let log = temp_dir.path().join("log");
+        let write_hook = |name: &str, script: String, mode: u32| {
+            let path = hooks_dir.join(name);
+            std::fs::write(&path, script).unwrap();
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(mode)).unwrap();
+        };
+        let log_hook = |label: &str| {
+            format!(
+                "#!/bin/sh\necho \"{label} $1 $(cat)\" >> {}\n",
+                log.display()
+            )
+        };
+        write_hook("20-second", log_hook("second"), 0o755);
+        write_hook("10-first", log_hook("first"), 0o755);
+        write_hook("15-failing", "#!/bin/sh\nexit 1\n".to_string(), 0o755);
+        write_hook("30-not-executable", log_hook("skipped"), 0o644);
+        write_hook(".hidden", log_hook("hidden"), 0o755);
+        let config = Config {
+            config_path: Some(temp_dir.path().join("config.toml")),
+            ..Default::default()
+        };
+
+        let event = Event::repo(EventKind::RepoCloned, &temp_dir.path().join("a/b/c"));
+        emit(&config, &event);
+        let payload = serde_json::to_string(&event).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&log).unwrap(),
+            format!("first repo.cloned {payload}\nsecond repo.cloned {payload}\n")
+        );
+
+        // Without a configuration file there is no hooks directory
+        emit(&Config::default(), &event);
+    }
+}
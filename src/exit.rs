@@ -0,0 +1,117 @@
+/// Failures with their own exit status, so wrapper scripts can branch on
+/// the kind of failure instead of parsing messages. Other errors exit with
+/// status 1, and clap's usage errors with 2.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// The target of a lookup such as `switch` doesn't exist.
+    NotFound,
+    /// A worktree has uncommitted changes that the command would lose.
+    DirtyWorktree,
+    /// The remote rejected the credentials, or none were available.
+    Auth,
+    /// The remote could not be reached.
+    Network,
+    /// The command needs an answer but prompting is impossible.
+    InputRequired,
+}
+
+impl ErrorKind {
+    pub const fn code(self) -> u8 {
+        match self {
+            Self::NotFound => 3,
+            Self::DirtyWorktree => 4,
+            Self::Auth => 5,
+            Self::Network => 6,
+            Self::InputRequired => 7,
+        }
+    }
+}
+
+/// An error that ends neoghq with a specific exit status. Without a
+/// message nothing is printed, which scripts calling neoghq on every editor
+/// buffer open rely on.
+#[derive(Debug, thiserror::Error)]
+#[error("{}", message.as_deref().unwrap_or("exiting"))]
+pub struct ExitError {
+    pub kind: ErrorKind,
+    pub message: Option<String>,
+}
+
+/// Returns an error of `kind` that prints `message`.
+pub fn error(kind: ErrorKind, message: impl Into<String>) -> anyhow::Error {
+    ExitError {
+        kind,
+        message: Some(message.into()),
+    }
+    .into()
+}
+
+/// Returns the error for a missing lookup target, printed unless `quiet`.
+pub fn not_found(quiet: bool, message: String) -> anyhow::Error {
+    ExitError {
+        kind: ErrorKind::NotFound,
+        message: (!quiet).then_some(message),
+    }
+    .into()
+}
+
+/// Finds the kind of `error`: an [`ExitError`] anywhere in the chain, or a
+/// libgit2 authentication or network failure.
+pub fn classify(error: &anyhow::Error) -> Option<ErrorKind> {
+    error.chain().find_map(|cause| {
+        if let Some(exit) = cause.downcast_ref::<ExitError>() {
+            return Some(exit.kind);
+        }
+        let git_error = cause.downcast_ref::<git2::Error>()?;
+        if git_error.code() == git2::ErrorCode::Auth {
+            return Some(ErrorKind::Auth);
+        }
+        match git_error.class() {
+            git2::ErrorClass::Net | git2::ErrorClass::Http | git2::ErrorClass::Ssl => {
+                Some(ErrorKind::Network)
+            }
+            _ => None,
+        }
+    })
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn test_not_found() {
+        let error = not_found(false, "Repository not found: u/r".to_string());
+        let exit = error.downcast_ref::<ExitError>().unwrap();
+        assert_eq!(exit.kind.code(), 3);
+        assert_eq!(error.to_string(), "Repository not found: u/r");
+
+        let error = not_found(true, "Repository not found: u/r".to_string());
+        assert_eq!(error.downcast_ref::<ExitError>().unwrap().message, None);
+    }
+
+    #[test]
+    fn test_classify() {
+        let error = error(ErrorKind::DirtyWorktree, "dirty");
+        assert_eq!(classify(&error), Some(ErrorKind::DirtyWorktree));
+
+        let auth = git2::Error::new(
+            git2::ErrorCode::Auth,
+            git2::ErrorClass::Callback,
+            "authentication failed",
+        );
+        let error = Err::<(), _>(auth).context("Cloning").unwrap_err();
+        assert_eq!(classify(&error), Some(ErrorKind::Auth));
+
+        let network = git2::Error::new(
+            git2::ErrorCode::GenericError,
+            git2::ErrorClass::Net,
+            "failed to resolve address",
+        );
+        assert_eq!(classify(&network.into()), Some(ErrorKind::Network));
+
+        assert_eq!(classify(&anyhow::anyhow!("other")), None);
+    }
+}
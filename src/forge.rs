@@ -0,0 +1,545 @@
+use crate::{
+    config::{Config, ForgeKind},
+    credentials,
+};
+use anyhow::{Context, Result, anyhow, bail};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use tracing::debug;
+
+/// The REST API of the forge hosting a repository: GitHub (github.com or
+/// GitHub Enterprise) or GitLab. Requests go through `curl`, with the HTTPS
+/// token from `[credentials]`, else `GITHUB_TOKEN`/`GH_TOKEN` or
+/// `GITLAB_TOKEN`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Forge {
+    pub kind: ForgeKind,
+    /// Base URL of the API, such as `https://api.github.com`.
+    pub api_url: String,
+    token: Option<String>,
+}
+
+/// What the forge knows about a repository.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForgeRepository {
+    pub default_branch: Option<String>,
+    /// The repository this one was forked from.
+    pub parent: Option<ForkParent>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForkParent {
+    /// The owner of the parent. Nested groups are joined with `/`.
+    pub owner: String,
+    pub repo: String,
+    pub https_url: String,
+    pub ssh_url: String,
+}
+
+/// An issue, as `worktree create --issue` names branches after it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForgeIssue {
+    pub number: u64,
+    pub title: String,
+    /// The issue's page.
+    pub url: String,
+}
+
+/// A pull request, or a GitLab merge request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForgePullRequest {
+    pub number: u64,
+    pub title: String,
+    pub state: PullRequestState,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PullRequestState {
+    Open,
+    Merged,
+    /// Closed without merging.
+    Closed,
+}
+
+/// The repository and number of an issue or pull request page URL such as
+/// `https://github.com/owner/repo/issues/12` or
+/// `https://gitlab.com/group/project/-/merge_requests/12`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ItemUrl {
+    pub host: String,
+    /// Nested groups are joined with `/`.
+    pub owner: String,
+    pub repo: String,
+    pub number: u64,
+}
+
+/// Parses an issue page URL of GitHub or GitLab.
+pub fn parse_issue_url(url: &str) -> Result<ItemUrl> {
+    parse_item_url(url, &["issues"]).ok_or_else(|| anyhow!("Not an issue URL: {url}"))
+}
+
+/// Parses a pull request page URL of GitHub, or a merge request one of
+/// GitLab.
+pub fn parse_pull_request_url(url: &str) -> Result<ItemUrl> {
+    parse_item_url(url, &["pull", "merge_requests"])
+        .ok_or_else(|| anyhow!("Not a pull request URL: {url}"))
+}
+
+/// Parses `https://<host>/<owner>/<repo>[/-]/<kind>/<number>` for one of
+/// `kinds`.
+fn parse_item_url(url: &str, kinds: &[&str]) -> Option<ItemUrl> {
+    let rest = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))?;
+    let (host, path) = rest.split_once('/')?;
+    let (repo_path, number) = kinds
+        .iter()
+        .find_map(|kind| path.trim_end_matches('/').rsplit_once(&format!("/{kind}/")))?;
+    let number = number.parse().ok()?;
+    let repo_path = repo_path.strip_suffix("/-").unwrap_or(repo_path);
+    let (owner, repo) = repo_path.rsplit_once('/')?;
+    if owner.is_empty() || repo.is_empty() {
+        return None;
+    }
+    Some(ItemUrl {
+        host: host.to_lowercase(),
+        owner: owner.to_string(),
+        repo: repo.to_string(),
+        number,
+    })
+}
+
+impl Forge {
+    /// Returns the API of `host`: github.com and gitlab.com are known, other
+    /// hosts need `hosts.<host>.forge`.
+    pub fn for_host(config: &Config, host: &str) -> Option<Self> {
+        let host_config = config.hosts.get(host);
+        let kind = host_config.and_then(|host| host.forge).or(match host {
+            "github.com" => Some(ForgeKind::Github),
+            "gitlab.com" => Some(ForgeKind::Gitlab),
+            _ => None,
+        })?;
+        let api_url = host_config
+            .and_then(|host| host.api_url.clone())
+            .unwrap_or_else(|| match (kind, host) {
+                (ForgeKind::Github, "github.com") => "https://api.github.com".to_string(),
+                (ForgeKind::Github, host) => format!("https://{host}/api/v3"),
+                (ForgeKind::Gitlab, host) => format!("https://{host}/api/v4"),
+            });
+        let token = credentials::resolve_token(&config.credentials)
+            .ok()
+            .flatten()
+            .or_else(|| {
+                let names: &[&str] = match kind {
+                    ForgeKind::Github => &["GITHUB_TOKEN", "GH_TOKEN"],
+                    ForgeKind::Gitlab => &["GITLAB_TOKEN"],
+                };
+                names.iter().find_map(|name| std::env::var(name).ok())
+            });
+        Some(Self {
+            kind,
+            api_url: api_url.trim_end_matches('/').to_string(),
+            token,
+        })
+    }
+
+    /// Looks up `owner/repo`.
+    pub fn repository(&self, owner: &str, repo: &str) -> Result<ForgeRepository> {
+        match self.kind {
+            ForgeKind::Github => {
+                let repository: GithubRepository = self.get(&format!("repos/{owner}/{repo}"))?;
+                Ok(repository.into())
+            }
+            ForgeKind::Gitlab => {
+                let id = format!("{owner}/{repo}").replace('/', "%2F");
+                let project: GitlabProject = self.get(&format!("projects/{id}"))?;
+                Ok(project.into())
+            }
+        }
+    }
+
+    /// Looks up issue `number` of `owner/repo`.
+    pub fn issue(&self, owner: &str, repo: &str, number: u64) -> Result<ForgeIssue> {
+        match self.kind {
+            ForgeKind::Github => {
+                let issue: GithubIssue =
+                    self.get(&format!("repos/{owner}/{repo}/issues/{number}"))?;
+                Ok(ForgeIssue {
+                    number: issue.number,
+                    title: issue.title,
+                    url: issue.html_url,
+                })
+            }
+            ForgeKind::Gitlab => {
+                let id = format!("{owner}/{repo}").replace('/', "%2F");
+                let issue: GitlabIssue = self.get(&format!("projects/{id}/issues/{number}"))?;
+                Ok(ForgeIssue {
+                    number: issue.iid,
+                    title: issue.title,
+                    url: issue.web_url,
+                })
+            }
+        }
+    }
+
+    /// Looks up pull request, or merge request, `number` of `owner/repo`.
+    pub fn pull_request(&self, owner: &str, repo: &str, number: u64) -> Result<ForgePullRequest> {
+        match self.kind {
+            ForgeKind::Github => {
+                let pull: GithubPullRequest =
+                    self.get(&format!("repos/{owner}/{repo}/pulls/{number}"))?;
+                let state = match (pull.merged_at, pull.state.as_str()) {
+                    (Some(_), _) => PullRequestState::Merged,
+                    (None, "open") => PullRequestState::Open,
+                    (None, _) => PullRequestState::Closed,
+                };
+                Ok(ForgePullRequest {
+                    number: pull.number,
+                    title: pull.title,
+                    state,
+                })
+            }
+            ForgeKind::Gitlab => {
+                let id = format!("{owner}/{repo}").replace('/', "%2F");
+                let merge_request: GitlabMergeRequest =
+                    self.get(&format!("projects/{id}/merge_requests/{number}"))?;
+                let state = match merge_request.state.as_str() {
+                    "opened" => PullRequestState::Open,
+                    "merged" => PullRequestState::Merged,
+                    _ => PullRequestState::Closed,
+                };
+                Ok(ForgePullRequest {
+                    number: merge_request.iid,
+                    title: merge_request.title,
+                    state,
+                })
+            }
+        }
+    }
+
+    /// Returns the ref the forge publishes the head of pull request
+    /// `number` as, fetchable from the repository.
+    pub fn pull_request_ref(&self, number: u64) -> String {
+        match self.kind {
+            ForgeKind::Github => format!("refs/pull/{number}/head"),
+            ForgeKind::Gitlab => format!("refs/merge-requests/{number}/head"),
+        }
+    }
+
+    /// Fetches `path` under the API and parses the JSON response.
+    fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T> {
+        let url = format!("{}/{path}", self.api_url);
+        debug!("Requesting {url}");
+        let mut child = Command::new("curl")
+            .args(["--fail", "--silent", "--show-error", "--location"])
+            // Headers come from stdin so the token stays out of the process list
+            .args(["--header", "@-", "--", &url])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .context("Failed to run curl")?;
+        if let Some(mut stdin) = child.stdin.take() {
+            let mut headers = String::from("Accept: application/json\n");
+            if let Some(token) = &self.token {
+                headers.push_str(&match self.kind {
+                    ForgeKind::Github => format!("Authorization: Bearer {token}\n"),
+                    ForgeKind::Gitlab => format!("PRIVATE-TOKEN: {token}\n"),
+                });
+            }
+            stdin.write_all(headers.as_bytes())?;
+        }
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            bail!(
+                "Failed to request {url}: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        serde_json::from_slice(&output.stdout).with_context(|| format!("Failed to read {url}"))
+    }
+}
+
+#[derive(Deserialize)]
+struct GithubRepository {
+    default_branch: Option<String>,
+    parent: Option<GithubParent>,
+}
+
+#[derive(Deserialize)]
+struct GithubParent {
+    name: String,
+    owner: GithubOwner,
+    clone_url: String,
+    ssh_url: String,
+}
+
+#[derive(Deserialize)]
+struct GithubOwner {
+    login: String,
+}
+
+impl From<GithubRepository> for ForgeRepository {
+    fn from(repository: GithubRepository) -> Self {
+        Self {
+            default_branch: repository.default_branch,
+            parent: repository.parent.map(|parent| ForkParent {
+                owner: parent.owner.login,
+                repo: parent.name,
+                https_url: parent.clone_url,
+                ssh_url: parent.ssh_url,
+            }),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct GithubIssue {
+    number: u64,
+    title: String,
+    html_url: String,
+}
+
+#[derive(Deserialize)]
+struct GitlabIssue {
+    iid: u64,
+    title: String,
+    web_url: String,
+}
+
+#[derive(Deserialize)]
+struct GithubPullRequest {
+    number: u64,
+    title: String,
+    /// `open` or `closed`.
+    state: String,
+    merged_at: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct GitlabMergeRequest {
+    iid: u64,
+    title: String,
+    /// `opened`, `merged`, `closed` or `locked`.
+    state: String,
+}
+
+#[derive(Deserialize)]
+struct GitlabProject {
+    default_branch: Option<String>,
+    forked_from_project: Option<GitlabParent>,
+}
+
+#[derive(Deserialize)]
+struct GitlabParent {
+    path_with_namespace: String,
+    http_url_to_repo: String,
+    ssh_url_to_repo: String,
+}
+
+impl From<GitlabProject> for ForgeRepository {
+    fn from(project: GitlabProject) -> Self {
+        Self {
+            default_branch: project.default_branch,
+            parent: project.forked_from_project.and_then(|parent| {
+                let (owner, repo) = parent.path_with_namespace.rsplit_once('/')?;
+                Some(ForkParent {
+                    owner: owner.to_string(),
+                    repo: repo.to_string(),
+                    https_url: parent.http_url_to_repo,
+                    ssh_url: parent.ssh_url_to_repo,
+                })
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use crate::config::HostConfig;
+
+    #[test]
+    fn test_for_host() {
+        let config = Config {
+            hosts: [(
+                "git.company.com".to_string(),
+                HostConfig {
+                    forge: Some(ForgeKind::Gitlab),
+                    ..Default::default()
+                },
+            )]
+            .into(),
+            ..Default::default()
+        };
+        let api_url = |host| Forge::for_host(&config, host).map(|forge| forge.api_url);
+        assert_eq!(
+            api_url("github.com").as_deref(),
+            Some("https://api.github.com")
+        );
+        assert_eq!(
+            api_url("gitlab.com").as_deref(),
+            Some("https://gitlab.com/api/v4")
+        );
+        assert_eq!(
+            api_url("git.company.com").as_deref(),
+            Some("https://git.company.com/api/v4")
+        );
+        assert_eq!(api_url("example.com"), None);
+    }
+
+    #[test]
+    fn test_repository_github() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let dir = temp_dir.path().join("repos/user");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("fork"),
+            r#"{"default_branch": "main", "fork": true, "parent": {
+                "name": "project", "owner": {"login": "org"},
+                "clone_url": "https://github.com/org/project.git",
+                "ssh_url": "git@github.com:org/project.git"}}"#,
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("own"),
+            r#"{"default_branch": "trunk", "fork": false}"#,
+        )
+        .unwrap();
+        let forge = Forge {
+            kind: ForgeKind::Github,
+            api_url: format!("file://{}", temp_dir.path().display()),
+            token: Some("secret".to_string()),
+        };
+
+        let repository = forge.repository("user", "fork").unwrap();
+        assert_eq!(
+            repository.parent,
+            Some(ForkParent {
+                owner: "org".to_string(),
+                repo: "project".to_string(),
+                https_url: "https://github.com/org/project.git".to_string(),
+                ssh_url: "git@github.com:org/project.git".to_string(),
+            })
+        );
+        let repository = forge.repository("user", "own").unwrap();
+        assert_eq!(repository.default_branch.as_deref(), Some("trunk"));
+        assert_eq!(repository.parent, None);
+        assert!(forge.repository("user", "missing").is_err());
+    }
+
+    #[test]
+    fn test_issue_github() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let dir = temp_dir.path().join("repos/user/repo/issues");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("12"),
+            r#"{"number": 12, "title": "Crash on empty input",
+                "html_url": "https://github.com/user/repo/issues/12"}"#,
+        )
+        .unwrap();
+        let forge = Forge {
+            kind: ForgeKind::Github,
+            api_url: format!("file://{}", temp_dir.path().display()),
+            token: None,
+        };
+        assert_eq!(
+            forge.issue("user", "repo", 12).unwrap(),
+            ForgeIssue {
+                number: 12,
+                title: "Crash on empty input".to_string(),
+                url: "https://github.com/user/repo/issues/12".to_string(),
+            }
+        );
+        assert!(forge.issue("user", "repo", 13).is_err());
+    }
+
+    #[test]
+    fn test_parse_issue_url() {
+        assert_eq!(
+            parse_issue_url("https://GitHub.com/user/repo/issues/12").unwrap(),
+            ItemUrl {
+                host: "github.com".to_string(),
+                owner: "user".to_string(),
+                repo: "repo".to_string(),
+                number: 12,
+            }
+        );
+        let gitlab = parse_issue_url("https://gitlab.com/group/sub/project/-/issues/7/").unwrap();
+        assert_eq!(gitlab.owner, "group/sub");
+        assert_eq!(gitlab.repo, "project");
+        assert_eq!(gitlab.number, 7);
+        for url in [
+            "https://github.com/user/repo/pull/12",
+            "https://github.com/issues/12",
+            "git@github.com:user/repo.git",
+            "https://github.com/user/repo/issues/x",
+        ] {
+            assert!(parse_issue_url(url).is_err(), "{url}");
+        }
+        assert_eq!(
+            parse_pull_request_url("https://github.com/user/repo/pull/12")
+                .unwrap()
+                .number,
+            12
+        );
+        let gitlab =
+            parse_pull_request_url("https://gitlab.com/group/project/-/merge_requests/7").unwrap();
+        assert_eq!((gitlab.owner.as_str(), gitlab.number), ("group", 7));
+        assert!(parse_pull_request_url("https://github.com/user/repo/issues/12").is_err());
+    }
+
+    #[test]
+    fn test_pull_request_github() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let dir = temp_dir.path().join("repos/user/repo/pulls");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("12"),
+            r#"{"number": 12, "title": "Fix the crash", "state": "closed",
+                "merged_at": "2026-01-02T03:04:05Z"}"#,
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("13"),
+            r#"{"number": 13, "title": "Try again", "state": "open", "merged_at": null}"#,
+        )
+        .unwrap();
+        let forge = Forge {
+            kind: ForgeKind::Github,
+            api_url: format!("file://{}", temp_dir.path().display()),
+            token: None,
+        };
+        assert_eq!(
+            forge.pull_request("user", "repo", 12).unwrap(),
+            ForgePullRequest {
+                number: 12,
+                title: "Fix the crash".to_string(),
+                state: PullRequestState::Merged,
+            }
+        );
+        assert_eq!(
+            forge.pull_request("user", "repo", 13).unwrap().state,
+            PullRequestState::Open
+        );
+        assert_eq!(forge.pull_request_ref(13), "refs/pull/13/head");
+    }
+
+    #[test]
+    fn test_gitlab_project() {
+        let project: GitlabProject = serde_json::from_str(
+            r#"{"default_branch": "main", "forked_from_project": {
+                "path_with_namespace": "group/sub/project",
+                "http_url_to_repo": "https://gitlab.com/group/sub/project.git",
+                "ssh_url_to_repo": "git@gitlab.com:group/sub/project.git"}}"#,
+        )
+        .unwrap();
+        let parent = ForgeRepository::from(project).parent.unwrap();
+        assert_eq!(parent.owner, "group/sub");
+        assert_eq!(parent.repo, "project");
+    }
+}
@@ -0,0 +1,139 @@
+use anyhow::{Result, anyhow};
+use serde::Serialize;
+
+/// A `--format` template such as `{host}/{owner}/{repo}\t{path}`.
+///
+/// Placeholders name fields of the command's JSON output; `{{` and `}}`
+/// stand for literal braces, and `\t`, `\n` and `\\` are unescaped so
+/// templates can be written in single quotes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Format {
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Literal(String),
+    Field(String),
+}
+
+impl Format {
+    /// Parses `template`, accepting only placeholders listed in `fields`.
+    pub fn parse(template: &str, fields: &[&str]) -> Result<Self> {
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut chars = template.chars();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.as_str().starts_with('{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.as_str().starts_with('}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let rest = chars.as_str();
+                    let end = rest
+                        .find('}')
+                        .ok_or_else(|| anyhow!("Unclosed placeholder in format: {template}"))?;
+                    let name = &rest[..end];
+                    if !fields.contains(&name) {
+                        return Err(anyhow!(
+                            "Unknown placeholder {{{name}}}; available: {}",
+                            fields
+                                .iter()
+                                .map(|field| format!("{{{field}}}"))
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        ));
+                    }
+                    if !literal.is_empty() {
+                        parts.push(Part::Literal(std::mem::take(&mut literal)));
+                    }
+                    parts.push(Part::Field(name.to_string()));
+                    chars = rest[end + 1..].chars();
+                }
+                '}' => return Err(anyhow!("Unmatched }} in format: {template}")),
+                '\\' => match chars.next() {
+                    Some('t') => literal.push('\t'),
+                    Some('n') => literal.push('\n'),
+                    Some('\\') => literal.push('\\'),
+                    Some(other) => literal.extend(['\\', other]),
+                    None => literal.push('\\'),
+                },
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            parts.push(Part::Literal(literal));
+        }
+
+        Ok(Self { parts })
+    }
+
+    /// Fills the placeholders from `record`'s serialized fields; `null`
+    /// renders as an empty string.
+    pub fn render(&self, record: &impl Serialize) -> Result<String> {
+        let value = serde_json::to_value(record)?;
+        let mut output = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Literal(literal) => output.push_str(literal),
+                Part::Field(name) => match &value[name] {
+                    serde_json::Value::Null => {}
+                    serde_json::Value::String(string) => output.push_str(string),
+                    other => output.push_str(&other.to_string()),
+                },
+            }
+        }
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize)]
+    struct Record {
+        host: &'static str,
+        repo: &'static str,
+        dirty: bool,
+        branch: Option<&'static str>,
+    }
+
+    const FIELDS: &[&str] = &["host", "repo", "dirty", "branch"];
+
+    fn record() -> Record {
+        Record {
+            host: "github.com",
+            repo: "neoghq",
+            dirty: true,
+            branch: None,
+        }
+    }
+
+    #[test]
+    fn test_render() {
+        let format = Format::parse(r"{host}/{repo}\t{dirty}[{branch}]", FIELDS).unwrap();
+        assert_eq!(
+            format.render(&record()).unwrap(),
+            "github.com/neoghq\ttrue[]"
+        );
+
+        let format = Format::parse("{{{repo}}} \\\\ }}", FIELDS).unwrap();
+        assert_eq!(format.render(&record()).unwrap(), "{neoghq} \\ }");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = Format::parse("{owner}", FIELDS).unwrap_err();
+        assert!(error.to_string().contains("{host}, {repo}"));
+        assert!(Format::parse("{host", FIELDS).is_err());
+        assert!(Format::parse("host}", FIELDS).is_err());
+    }
+}
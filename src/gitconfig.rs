@@ -0,0 +1,139 @@
+use anyhow::Result;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// ghq settings read from the user's gitconfig, so existing ghq users get
+/// sensible defaults without writing a neoghq config.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GhqConfig {
+    /// Values of `ghq.root`, in the order they were defined.
+    pub roots: Vec<PathBuf>,
+    /// Value of `ghq.user`.
+    pub user: Option<String>,
+    /// Values of `ghq.<url>.vcs`, keyed by URL prefix.
+    pub vcs: BTreeMap<String, String>,
+}
+
+impl GhqConfig {
+    #[cfg_attr(coverage_nightly, coverage(off))]
+    pub fn load_default() -> Self {
+        git2::Config::open_default()
+            .and_then(|mut config| config.snapshot())
+            .ok()
+            .and_then(|config| Self::load(&config).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn load(config: &git2::Config) -> Result<Self> {
+        let mut roots = Vec::new();
+        let mut entries = config.multivar("ghq.root", None)?;
+        while let Some(entry) = entries.next() {
+            if let Some(value) = entry?.value() {
+                roots.push(PathBuf::from(value));
+            }
+        }
+
+        let user = config.get_string("ghq.user").ok();
+
+        let mut vcs = BTreeMap::new();
+        let mut entries = config.entries(Some(r"^ghq\..+\.vcs$"))?;
+        while let Some(entry) = entries.next() {
+            let entry = entry?;
+            let (Some(name), Some(value)) = (entry.name(), entry.value()) else {
+                continue;
+            };
+            if let Some(url) = name
+                .strip_prefix("ghq.")
+                .and_then(|name| name.strip_suffix(".vcs"))
+            {
+                vcs.insert(url.to_string(), value.to_string());
+            }
+        }
+
+        Ok(Self { roots, user, vcs })
+    }
+
+    /// Returns the VCS configured for the longest `ghq.<url>` prefix of `url`.
+    pub fn vcs_for(&self, url: &str) -> Option<&str> {
+        self.vcs
+            .iter()
+            .filter(|(prefix, _)| url.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, vcs)| vcs.as_str())
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+
+    fn open_gitconfig(content: &str) -> (tempfile::TempDir, git2::Config) {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("gitconfig");
+        std::fs::write(&path, content).unwrap();
+        let config = git2::Config::open(&path).unwrap();
+        (temp_dir, config)
+    }
+
+    #[test]
+    fn test_load_ghq_settings() {
+        let (_temp_dir, config) = open_gitconfig(
+            r#"
+[ghq]
+    root = ~/ghq
+    root = /work/ghq
+    user = octocat
+[ghq "https://svn.example.com/"]
+    vcs = git-svn
+[ghq "https://git.example.com/"]
+    vcs = git
+"#,
+        );
+
+        let ghq = GhqConfig::load(&config).unwrap();
+
+        assert_eq!(
+            ghq.roots,
+            vec![PathBuf::from("~/ghq"), PathBuf::from("/work/ghq")]
+        );
+        assert_eq!(ghq.user.as_deref(), Some("octocat"));
+        assert_eq!(ghq.vcs.len(), 2);
+        assert_eq!(
+            ghq.vcs_for("https://svn.example.com/project"),
+            Some("git-svn")
+        );
+        assert_eq!(
+            ghq.vcs_for("https://git.example.com/user/repo"),
+            Some("git")
+        );
+        assert_eq!(ghq.vcs_for("https://github.com/user/repo"), None);
+    }
+
+    #[test]
+    fn test_load_without_ghq_settings() {
+        let (_temp_dir, config) = open_gitconfig("[user]\n    name = Test\n");
+
+        let ghq = GhqConfig::load(&config).unwrap();
+
+        assert_eq!(ghq, GhqConfig::default());
+    }
+
+    #[test]
+    fn test_vcs_for_prefers_longest_prefix() {
+        let ghq = GhqConfig {
+            vcs: [
+                ("https://example.com/".to_string(), "git".to_string()),
+                (
+                    "https://example.com/svn/".to_string(),
+                    "git-svn".to_string(),
+                ),
+            ]
+            .into(),
+            ..Default::default()
+        };
+
+        assert_eq!(ghq.vcs_for("https://example.com/svn/repo"), Some("git-svn"));
+        assert_eq!(ghq.vcs_for("https://example.com/user/repo"), Some("git"));
+    }
+}
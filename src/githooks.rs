@@ -0,0 +1,196 @@
+//! Shared git hooks from `[git_hooks]`: either copied from a template
+//! directory into a repository's `hooks`, or run from one directory every
+//! repository's `core.hooksPath` points at. Worktrees use the hooks of
+//! their bare repository, so installing there covers all of them.
+
+use crate::config::GitHooksConfig;
+use anyhow::{Context, Result, anyhow};
+use git2::{ConfigLevel, Repository};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+/// What [`install`] changed in a repository.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Installed {
+    /// Nothing is configured, or everything was already in place.
+    Nothing,
+    /// `core.hooksPath` was set to the directory.
+    HooksPath(PathBuf),
+    /// The named hooks were copied from the template directory.
+    Hooks(Vec<String>),
+}
+
+/// Applies `hooks` to the repository whose git directory is `git_dir`.
+/// Hooks already in the repository that differ from the template are kept
+/// unless `force`.
+pub fn install(hooks: &GitHooksConfig, git_dir: &Path, force: bool) -> Result<Installed> {
+    if let Some(hooks_path) = &hooks.hooks_path {
+        return set_hooks_path(git_dir, hooks_path);
+    }
+    let Some(template_dir) = &hooks.template_dir else {
+        return Ok(Installed::Nothing);
+    };
+
+    let repo = Repository::open(git_dir)?;
+    // Git runs the hooks in core.hooksPath instead, so copies would be ignored
+    if let Ok(hooks_path) = repo.config()?.get_path("core.hooksPath") {
+        warn!(
+            "{} uses core.hooksPath {}; not copying hooks from {}",
+            git_dir.display(),
+            hooks_path.display(),
+            template_dir.display()
+        );
+        return Ok(Installed::Nothing);
+    }
+    copy_hooks(template_dir, &git_dir.join("hooks"), force)
+}
+
+fn set_hooks_path(git_dir: &Path, hooks_path: &Path) -> Result<Installed> {
+    let repo = Repository::open(git_dir)?;
+    let mut config = repo.config()?.open_level(ConfigLevel::Local)?;
+    if config
+        .get_path("core.hooksPath")
+        .is_ok_and(|current| current == hooks_path)
+    {
+        return Ok(Installed::Nothing);
+    }
+    let value = hooks_path
+        .to_str()
+        .ok_or_else(|| anyhow!("{} isn't UTF-8", hooks_path.display()))?;
+    config.set_str("core.hooksPath", value)?;
+    Ok(Installed::HooksPath(hooks_path.to_path_buf()))
+}
+
+/// Copies every hook in `template_dir` into `hooks_dir`, keeping their
+/// permissions. Git's `*.sample` files are skipped.
+fn copy_hooks(template_dir: &Path, hooks_dir: &Path, force: bool) -> Result<Installed> {
+    let entries = fs::read_dir(template_dir)
+        .with_context(|| format!("Failed to read hooks from {}", template_dir.display()))?;
+    let mut hooks = entries
+        .map(|entry| Ok(entry?.path()))
+        .collect::<Result<Vec<_>>>()?;
+    hooks.sort();
+
+    fs::create_dir_all(hooks_dir)?;
+    let mut copied = Vec::new();
+    for hook in hooks {
+        let Some(name) = hook.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        if !hook.is_file() || name.ends_with(".sample") {
+            continue;
+        }
+        let target = hooks_dir.join(name);
+        let content = fs::read(&hook)?;
+        match fs::read(&target) {
+            Ok(current) if current == content => continue,
+            Ok(_) if !force => {
+                warn!(
+                    "{} differs from {}; keeping it (use --force to replace it)",
+                    target.display(),
+                    hook.display()
+                );
+                continue;
+            }
+            _ => {}
+        }
+        fs::copy(&hook, &target)
+            .with_context(|| format!("Failed to install {}", target.display()))?;
+        copied.push(name.to_string());
+    }
+
+    if copied.is_empty() {
+        return Ok(Installed::Nothing);
+    }
+    Ok(Installed::Hooks(copied))
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_managed_repository;
+
+    #[test]
+    fn test_install_copies_template_hooks() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo_dir = temp_dir.path().join("github.com/user/repo");
+        create_managed_repository(&repo_dir);
+        let git_dir = repo_dir.join(".git");
+        let template_dir = temp_dir.path().join("hooks");
+        fs::create_dir(&template_dir).unwrap();
+        fs::write(template_dir.join("pre-commit"), "#!/bin/sh\nmake lint\n").unwrap();
+        fs::write(template_dir.join("pre-push.sample"), "#!/bin/sh\n").unwrap();
+        let hooks = GitHooksConfig {
+            template_dir: Some(template_dir.clone()),
+            hooks_path: None,
+        };
+
+        assert_eq!(
+            install(&hooks, &git_dir, false).unwrap(),
+            Installed::Hooks(vec!["pre-commit".to_string()])
+        );
+        assert_eq!(
+            fs::read_to_string(git_dir.join("hooks/pre-commit")).unwrap(),
+            "#!/bin/sh\nmake lint\n"
+        );
+        assert!(!git_dir.join("hooks/pre-push.sample").exists());
+        assert_eq!(
+            install(&hooks, &git_dir, false).unwrap(),
+            Installed::Nothing
+        );
+
+        // Local changes are kept unless forced
+        fs::write(git_dir.join("hooks/pre-commit"), "#!/bin/sh\n").unwrap();
+        assert_eq!(
+            install(&hooks, &git_dir, false).unwrap(),
+            Installed::Nothing
+        );
+        assert_eq!(
+            install(&hooks, &git_dir, true).unwrap(),
+            Installed::Hooks(vec!["pre-commit".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_install_sets_and_honors_hooks_path() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo_dir = temp_dir.path().join("github.com/user/repo");
+        create_managed_repository(&repo_dir);
+        let git_dir = repo_dir.join(".git");
+        let shared = temp_dir.path().join("shared");
+        let hooks = GitHooksConfig {
+            template_dir: None,
+            hooks_path: Some(shared.clone()),
+        };
+
+        assert_eq!(
+            install(&hooks, &git_dir, false).unwrap(),
+            Installed::HooksPath(shared.clone())
+        );
+        let repo = Repository::open(&git_dir).unwrap();
+        assert_eq!(
+            repo.config().unwrap().get_path("core.hooksPath").unwrap(),
+            shared
+        );
+        assert_eq!(
+            install(&hooks, &git_dir, false).unwrap(),
+            Installed::Nothing
+        );
+
+        // With core.hooksPath set, copies into hooks would never run
+        let template_dir = temp_dir.path().join("templates");
+        fs::create_dir(&template_dir).unwrap();
+        fs::write(template_dir.join("pre-commit"), "#!/bin/sh\n").unwrap();
+        let hooks = GitHooksConfig {
+            template_dir: Some(template_dir),
+            hooks_path: None,
+        };
+        assert_eq!(
+            install(&hooks, &git_dir, false).unwrap(),
+            Installed::Nothing
+        );
+        assert!(!git_dir.join("hooks/pre-commit").exists());
+    }
+}
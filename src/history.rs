@@ -0,0 +1,245 @@
+use crate::{config::Config, status};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// When and how often each repository and worktree was switched to, kept
+/// in `<cache dir>/history.json` for `--sort recent`, the picker and
+/// `neoghq history`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct History {
+    /// Unix time of the last visit, keyed by directory.
+    #[serde(default)]
+    visits: BTreeMap<PathBuf, u64>,
+    /// Number of visits, keyed by directory. Directories visited before
+    /// counts were kept count once.
+    #[serde(default)]
+    counts: BTreeMap<PathBuf, u64>,
+}
+
+/// A directory in the history, as printed by `history --json`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Visit {
+    pub path: PathBuf,
+    /// Unix time of the last visit.
+    pub last_visit: u64,
+    pub count: u64,
+    pub frecency: u64,
+}
+
+impl History {
+    /// Loads the history; a missing or unreadable file is an empty history.
+    pub fn load(config: &Config) -> Self {
+        history_path(config)
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    /// Records a visit to `path` now.
+    pub fn record(config: &Config, path: &Path) -> Result<()> {
+        let Some(history_path) = history_path(config) else {
+            return Ok(());
+        };
+
+        let mut history = Self::load(config);
+        let count = history.count(path);
+        history
+            .visits
+            .insert(path.to_path_buf(), status::unix_now());
+        history.counts.insert(path.to_path_buf(), count + 1);
+
+        if let Some(parent) = history_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(history_path, serde_json::to_string(&history)?)?;
+        Ok(())
+    }
+
+    /// Moves the visits to `old` and the directories in it to `new`, so a
+    /// renamed repository keeps its rank.
+    pub fn rename(config: &Config, old: &Path, new: &Path) -> Result<()> {
+        let Some(history_path) = history_path(config) else {
+            return Ok(());
+        };
+        let mut history = Self::load(config);
+        if !history.rename_paths(old, new) {
+            return Ok(());
+        }
+        std::fs::write(history_path, serde_json::to_string(&history)?)?;
+        Ok(())
+    }
+
+    /// Returns whether any directory was in `old`.
+    fn rename_paths(&mut self, old: &Path, new: &Path) -> bool {
+        let moved = |map: &mut BTreeMap<PathBuf, u64>| {
+            let paths = map
+                .keys()
+                .filter(|path| path.starts_with(old))
+                .cloned()
+                .collect::<Vec<_>>();
+            for path in &paths {
+                let value = map.remove(path).unwrap_or_default();
+                let relative = path.strip_prefix(old).unwrap_or(path);
+                map.insert(new.join(relative), value);
+            }
+            !paths.is_empty()
+        };
+        let visits = moved(&mut self.visits);
+        moved(&mut self.counts) || visits
+    }
+
+    /// Returns the Unix time `path` was last switched to.
+    pub fn last_visit(&self, path: &Path) -> Option<u64> {
+        self.visits.get(path).copied()
+    }
+
+    fn count(&self, path: &Path) -> u64 {
+        match self.counts.get(path) {
+            Some(count) => *count,
+            None => u64::from(self.visits.contains_key(path)),
+        }
+    }
+
+    /// Ranks `path` by how often and how recently it was switched to, as
+    /// zoxide does: the visit count weighted by the age of the last visit.
+    /// Unvisited directories score 0.
+    pub fn frecency(&self, path: &Path) -> u64 {
+        let Some(last_visit) = self.last_visit(path) else {
+            return 0;
+        };
+        let age = status::unix_now().saturating_sub(last_visit);
+        let weight = match age {
+            0..3_600 => 400,
+            3_600..86_400 => 200,
+            86_400..604_800 => 50,
+            _ => 25,
+        };
+        self.count(path) * weight
+    }
+
+    /// Returns every visited directory, most recently visited first.
+    pub fn entries(&self) -> Vec<Visit> {
+        let mut entries = self
+            .visits
+            .iter()
+            .map(|(path, last_visit)| Visit {
+                path: path.clone(),
+                last_visit: *last_visit,
+                count: self.count(path),
+                frecency: self.frecency(path),
+            })
+            .collect::<Vec<_>>();
+        entries.sort_by(|a, b| {
+            b.last_visit
+                .cmp(&a.last_visit)
+                .then_with(|| a.path.cmp(&b.path))
+        });
+        entries
+    }
+}
+
+fn history_path(config: &Config) -> Option<PathBuf> {
+    config
+        .cache_dir
+        .as_ref()
+        .map(|cache_dir| cache_dir.join("history.json"))
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_and_load() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config = Config {
+            cache_dir: Some(temp_dir.path().join("cache")),
+            ..Default::default()
+        };
+        let repo = Path::new("/repos/github.com/user/repo");
+
+        assert_eq!(History::load(&config).last_visit(repo), None);
+        History::record(&config, repo).unwrap();
+        assert!(History::load(&config).last_visit(repo).unwrap() > 0);
+    }
+
+    #[test]
+    fn test_rename() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config = Config {
+            cache_dir: Some(temp_dir.path().join("cache")),
+            ..Default::default()
+        };
+        let old = Path::new("/repos/github.com/user/repo");
+        let new = Path::new("/repos/github.com/org/repo");
+        let other = Path::new("/repos/github.com/user/repo2");
+        History::record(&config, &old.join("main")).unwrap();
+        History::record(&config, other).unwrap();
+
+        History::rename(&config, old, new).unwrap();
+        let history = History::load(&config);
+        assert_eq!(history.last_visit(&old.join("main")), None);
+        assert!(history.last_visit(&new.join("main")).is_some());
+        assert_eq!(history.count(&new.join("main")), 1);
+        assert!(history.last_visit(other).is_some());
+    }
+
+    #[test]
+    fn test_frecency() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config = Config {
+            cache_dir: Some(temp_dir.path().join("cache")),
+            ..Default::default()
+        };
+        let often = Path::new("/repos/often");
+        let once = Path::new("/repos/once");
+        for _ in 0..3 {
+            History::record(&config, often).unwrap();
+        }
+        History::record(&config, once).unwrap();
+
+        let history = History::load(&config);
+        assert_eq!(history.frecency(often), 1200);
+        assert_eq!(history.frecency(once), 400);
+        assert_eq!(history.frecency(Path::new("/repos/never")), 0);
+
+        let entries = history.entries();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(
+            entries
+                .iter()
+                .find(|visit| visit.path == often)
+                .unwrap()
+                .count,
+            3
+        );
+    }
+
+    #[test]
+    fn test_load_history_without_counts() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config = Config {
+            cache_dir: Some(temp_dir.path().to_path_buf()),
+            ..Default::default()
+        };
+        std::fs::write(
+            temp_dir.path().join("history.json"),
+            r#"{"visits":{"/repos/old":1}}"#,
+        )
+        .unwrap();
+
+        let history = History::load(&config);
+        assert_eq!(history.count(Path::new("/repos/old")), 1);
+        assert_eq!(history.frecency(Path::new("/repos/old")), 25);
+    }
+
+    #[test]
+    fn test_without_cache_dir() {
+        let config = Config::default();
+        History::record(&config, Path::new("/repo")).unwrap();
+        assert_eq!(History::load(&config), History::default());
+    }
+}
@@ -0,0 +1,138 @@
+use anyhow::{Context, Result};
+use std::path::{Component, Path};
+
+/// Name of the file at the top of a root listing directories to skip.
+pub const IGNORE_FILE: &str = ".neoghqignore";
+
+/// Directories under a root that walkers skip entirely, read from the
+/// root's `.neoghqignore`. Each line is a path relative to the root whose
+/// segments may use `*` and `?` wildcards; `#` starts a comment.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IgnoreList {
+    patterns: Vec<Vec<String>>,
+}
+
+impl IgnoreList {
+    pub fn load(root: &Path) -> Result<Self> {
+        let path = root.join(IGNORE_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        Ok(Self::parse(&content))
+    }
+
+    pub fn parse(content: &str) -> Self {
+        let patterns = content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| {
+                line.split('/')
+                    .filter(|segment| !segment.is_empty())
+                    .map(str::to_string)
+                    .collect::<Vec<_>>()
+            })
+            .filter(|segments| !segments.is_empty())
+            .collect();
+        Self { patterns }
+    }
+
+    /// Returns whether `relative` (a path relative to the root) or one of
+    /// its parents is ignored.
+    pub fn is_ignored(&self, relative: &Path) -> bool {
+        let segments = relative
+            .components()
+            .filter_map(|component| match component {
+                Component::Normal(segment) => segment.to_str(),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        self.patterns.iter().any(|pattern| {
+            pattern.len() <= segments.len()
+                && pattern
+                    .iter()
+                    .zip(&segments)
+                    .all(|(pattern, segment)| glob_match(pattern, segment))
+        })
+    }
+}
+
+/// Matches `text` against a pattern where `*` matches any run of characters
+/// and `?` matches a single character.
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let text = text.chars().collect::<Vec<_>>();
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    t = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("archive", "archive"));
+        assert!(glob_match("*", "anything"));
+        assert!(glob_match("data-*", "data-2024"));
+        assert!(glob_match("*.mirror", "linux.mirror"));
+        assert!(glob_match("r?po", "repo"));
+        assert!(glob_match("a*b*c", "aXXbYYc"));
+        assert!(!glob_match("data-*", "metadata"));
+        assert!(!glob_match("r?po", "rpo"));
+    }
+
+    #[test]
+    fn test_is_ignored() {
+        let ignore =
+            IgnoreList::parse("# large data\n/datasets/\ngithub.com/torvalds\n\n*/archive-*\n");
+
+        assert!(ignore.is_ignored(Path::new("datasets")));
+        assert!(ignore.is_ignored(Path::new("datasets/raw/images")));
+        assert!(ignore.is_ignored(Path::new("github.com/torvalds/linux")));
+        assert!(ignore.is_ignored(Path::new("gitlab.com/archive-2020")));
+        assert!(!ignore.is_ignored(Path::new("github.com")));
+        assert!(!ignore.is_ignored(Path::new("github.com/user/repo")));
+    }
+
+    #[test]
+    fn test_load() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        assert_eq!(
+            IgnoreList::load(temp_dir.path()).unwrap(),
+            IgnoreList::default()
+        );
+
+        std::fs::write(temp_dir.path().join(IGNORE_FILE), "mnt\n").unwrap();
+        let ignore = IgnoreList::load(temp_dir.path()).unwrap();
+        assert!(ignore.is_ignored(Path::new("mnt/backup")));
+    }
+}
@@ -0,0 +1,347 @@
+use crate::ignore::{IGNORE_FILE, IgnoreList};
+use crate::paths;
+use crate::scan::{self, RepoEntry};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::debug;
+
+const INDEX_VERSION: u32 = 1;
+
+/// Directories modified this recently may change again without their
+/// modification time changing, on file systems with coarse timestamps.
+const RACY_NANOS: u64 = 2_000_000_000;
+
+/// The repositories of a root with the modification times of the
+/// directories listing them, kept in `<root>/.neoghq/index.json` so listing
+/// and completion don't walk the root every time.
+///
+/// Adding or removing a repository changes the modification time of its
+/// owner directory, a new owner that of its host directory, and a new host
+/// that of the root. Checking those times finds every change without
+/// reading a single directory. Times recorded too close to when they were
+/// taken are stored as 0, so those directories are read again next time.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct Index {
+    version: u32,
+    /// Modification time of the root in nanoseconds.
+    root: u64,
+    /// Modification time of `.neoghqignore`, if the root has one.
+    ignore: Option<u64>,
+    /// Whether the walk followed symlinked directories.
+    #[serde(default)]
+    follow_symlinks: bool,
+    /// Modification times of the host (`host`) and owner (`host/owner`)
+    /// directories.
+    directories: BTreeMap<String, u64>,
+    /// `host/owner/repo` of every repository.
+    repos: BTreeSet<String>,
+}
+
+/// Returns the repositories under `root` like [`scan::scan_root`], from the
+/// root's index when it's current. Owners whose directory changed are read
+/// again; any other change rebuilds the index.
+pub fn scan(root: &Path) -> Result<Vec<RepoEntry>> {
+    if !root.exists() {
+        return Ok(Vec::new());
+    }
+    let index = match load(root) {
+        Some(mut index) => match refresh(root, &mut index)? {
+            Freshness::Current => index,
+            Freshness::Refreshed => {
+                save(root, &index);
+                index
+            }
+            Freshness::Stale => build(root)?,
+        },
+        None => build(root)?,
+    };
+    Ok(entries(root, &index))
+}
+
+/// Rebuilds the index of `root` from the file system and returns how many
+/// repositories it holds.
+pub fn rebuild(root: &Path) -> Result<usize> {
+    if !root.exists() {
+        return Ok(0);
+    }
+    Ok(build(root)?.repos.len())
+}
+
+/// Brings the index of `root` up to date after a command added or removed
+/// repositories. Failures are only logged: the next read checks the index
+/// again anyway.
+pub fn update(root: &Path) {
+    if let Err(error) = scan(root) {
+        debug!(
+            "Failed to update the index of {}: {error:#}",
+            root.display()
+        );
+    }
+}
+
+enum Freshness {
+    Current,
+    /// Some owners were read again.
+    Refreshed,
+    /// The root, `.neoghqignore` or a host directory changed.
+    Stale,
+}
+
+fn refresh(root: &Path, index: &mut Index) -> Result<Freshness> {
+    if index.version != INDEX_VERSION
+        || mtime(root) != Some(index.root)
+        || mtime(&root.join(IGNORE_FILE)) != index.ignore
+        || index.follow_symlinks != scan::follows_symlinks()
+    {
+        return Ok(Freshness::Stale);
+    }
+
+    let mut changed = Vec::new();
+    for (directory, time) in &index.directories {
+        let current = mtime(&root.join(directory));
+        match (directory.contains('/'), current == Some(*time)) {
+            (_, true) => {}
+            (false, false) => return Ok(Freshness::Stale),
+            // A removed owner also changes its host directory
+            (true, false) => changed.push(directory.clone()),
+        }
+    }
+    if changed.is_empty() {
+        return Ok(Freshness::Current);
+    }
+
+    let ignore = IgnoreList::load(root)?;
+    let now = nanos(SystemTime::now()).unwrap_or_default();
+    for owner in changed {
+        let prefix = format!("{owner}/");
+        index.repos.retain(|repo| !repo.starts_with(&prefix));
+        let owner_path = root.join(&owner);
+        let Some(time) = stable_mtime(&owner_path, now) else {
+            return Ok(Freshness::Stale);
+        };
+        index.directories.insert(owner.clone(), time);
+        // A full scan skips and reports the unreadable owner
+        let Ok(repo_paths) = scan::subdirectories(&owner_path) else {
+            return Ok(Freshness::Stale);
+        };
+        for repo_path in repo_paths {
+            let repo = format!("{prefix}{}", file_name(&repo_path));
+            if !ignore.is_ignored(Path::new(&repo)) {
+                index.repos.insert(repo);
+            }
+        }
+    }
+    Ok(Freshness::Refreshed)
+}
+
+/// Walks `root` and saves the result as its index. The modification times
+/// are taken before reading the repositories, so a change made meanwhile
+/// makes the index stale rather than hiding it.
+fn build(root: &Path) -> Result<Index> {
+    let now = nanos(SystemTime::now()).unwrap_or_default();
+    let mut index = Index {
+        version: INDEX_VERSION,
+        root: stable_mtime(root, now).unwrap_or_default(),
+        ignore: stable_mtime(&root.join(IGNORE_FILE), now),
+        follow_symlinks: scan::follows_symlinks(),
+        ..Default::default()
+    };
+    let ignore = IgnoreList::load(root)?;
+    // Entries always use `/`, which the freshness check splits on
+    let relative = |path: &Path| paths::to_slash(path.strip_prefix(root).unwrap_or(path));
+    // Hidden directories such as `.neoghq` hold neoghq's own data, not hosts
+    for host_path in scan::subdirectories(root)? {
+        let host = relative(&host_path);
+        if host.starts_with('.') || ignore.is_ignored(Path::new(&host)) {
+            continue;
+        }
+        index
+            .directories
+            .insert(host, stable_mtime(&host_path, now).unwrap_or_default());
+        // scan_root below warns about hosts it can't read
+        for owner_path in scan::subdirectories(&host_path).unwrap_or_default() {
+            let owner = relative(&owner_path);
+            if !ignore.is_ignored(Path::new(&owner)) {
+                index
+                    .directories
+                    .insert(owner, stable_mtime(&owner_path, now).unwrap_or_default());
+            }
+        }
+    }
+    index.repos = scan::scan_root(root)?.iter().map(RepoEntry::spec).collect();
+
+    save(root, &index);
+    Ok(index)
+}
+
+fn entries(root: &Path, index: &Index) -> Vec<RepoEntry> {
+    let mut entries = index
+        .repos
+        .iter()
+        .filter_map(|repo| RepoEntry::from_path(&root.join(repo)))
+        .collect::<Vec<_>>();
+    entries.sort_by(|a, b| a.path.cmp(&b.path));
+    entries
+}
+
+fn index_path(root: &Path) -> PathBuf {
+    root.join(".neoghq").join("index.json")
+}
+
+fn load(root: &Path) -> Option<Index> {
+    let content = std::fs::read_to_string(index_path(root)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// Writes the index, which only speeds up later runs, so a read-only root
+/// is no error.
+fn save(root: &Path, index: &Index) {
+    let path = index_path(root);
+    let result = (|| -> Result<()> {
+        std::fs::create_dir_all(path.parent().unwrap_or(root))?;
+        // Written aside first so concurrent readers never see half an index
+        let partial = path.with_extension(format!("json.{}", std::process::id()));
+        std::fs::write(&partial, serde_json::to_string(index)?)?;
+        std::fs::rename(&partial, &path)?;
+        Ok(())
+    })();
+    if let Err(error) = result {
+        debug!("Failed to write {}: {error:#}", path.display());
+    }
+}
+
+fn mtime(path: &Path) -> Option<u64> {
+    nanos(std::fs::metadata(path).ok()?.modified().ok()?)
+}
+
+/// Nanoseconds since the Unix epoch, the precision the index records.
+fn nanos(time: SystemTime) -> Option<u64> {
+    u64::try_from(time.duration_since(UNIX_EPOCH).ok()?.as_nanos()).ok()
+}
+
+/// Returns the modification time of `path` to record at `now`, or 0 when
+/// it's too recent to rely on.
+fn stable_mtime(path: &Path, now: u64) -> Option<u64> {
+    let time = mtime(path)?;
+    Some(if now.saturating_sub(time) < RACY_NANOS {
+        0
+    } else {
+        time
+    })
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned()
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::time::Duration;
+
+    fn create_repo(root: &Path, spec: &str) {
+        fs::create_dir_all(root.join(spec).join(".git")).unwrap();
+    }
+
+    /// Backdates every directory under `root` so the index trusts them.
+    fn settle(root: &Path) {
+        let an_hour_ago = SystemTime::now() - Duration::from_secs(3600);
+        let mut pending = vec![root.to_path_buf()];
+        while let Some(dir) = pending.pop() {
+            pending.extend(scan::subdirectories(&dir).unwrap());
+            fs::File::open(&dir)
+                .unwrap()
+                .set_modified(an_hour_ago)
+                .unwrap();
+        }
+    }
+
+    fn specs(root: &Path) -> Vec<String> {
+        scan(root).unwrap().iter().map(RepoEntry::spec).collect()
+    }
+
+    #[test]
+    fn test_scan_builds_and_uses_index() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        create_repo(root, "github.com/user/repo");
+        fs::create_dir_all(root.join(".neoghq")).unwrap();
+        settle(root);
+
+        assert_eq!(specs(root), ["github.com/user/repo"]);
+        let mut index = load(root).unwrap();
+        assert!(index.directories.contains_key("github.com/user"));
+
+        // An unchanged tree is listed from the index alone
+        index.repos.insert("github.com/user/cached".to_string());
+        save(root, &index);
+        assert_eq!(
+            specs(root),
+            ["github.com/user/cached", "github.com/user/repo"]
+        );
+
+        assert_eq!(rebuild(root).unwrap(), 1);
+        assert_eq!(specs(root), ["github.com/user/repo"]);
+    }
+
+    #[test]
+    fn test_scan_notices_changes() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        create_repo(root, "github.com/user/repo");
+        fs::create_dir_all(root.join(".neoghq")).unwrap();
+        settle(root);
+        specs(root);
+
+        // Noticed even within the timestamp granularity of the file system
+        create_repo(root, "github.com/user/other");
+        assert_eq!(
+            specs(root),
+            ["github.com/user/other", "github.com/user/repo"]
+        );
+
+        fs::remove_dir_all(root.join("github.com/user/repo")).unwrap();
+        create_repo(root, "github.com/team/service");
+        create_repo(root, "gitlab.com/group/project");
+        assert_eq!(
+            specs(root),
+            [
+                "github.com/team/service",
+                "github.com/user/other",
+                "gitlab.com/group/project"
+            ]
+        );
+
+        fs::write(root.join(IGNORE_FILE), "gitlab.com\n").unwrap();
+        assert_eq!(
+            specs(root),
+            ["github.com/team/service", "github.com/user/other"]
+        );
+        assert_eq!(
+            specs(root),
+            scan::scan_root(root)
+                .unwrap()
+                .iter()
+                .map(RepoEntry::spec)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_scan_missing_root() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path().join("missing");
+
+        assert!(scan(&root).unwrap().is_empty());
+        assert_eq!(rebuild(&root).unwrap(), 0);
+        assert!(!root.exists());
+    }
+}
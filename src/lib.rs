@@ -0,0 +1,65 @@
+#![cfg_attr(coverage_nightly, feature(coverage_attribute))]
+//! neoghq manages git repositories as bare clones with one worktree per
+//! branch, laid out as `<root>/<host>/<owner>/<repo>/<branch>`.
+//!
+//! The `neoghq` binary is a thin front end over this crate, so other tools
+//! can use the same layout without shelling out:
+//!
+//! - [`config::Config`] loads `~/.config/neoghq/config.toml` with its roots,
+//!   profiles and per-repository settings.
+//! - [`spec`] and [`giturl`] turn specs such as `owner/repo` or clone URLs
+//!   into hosts, owners and repositories.
+//! - [`scan`] and [`index`] find the repositories under the roots, as
+//!   [`scan::RepoEntry`] values.
+//! - [`status`] reports worktrees, branches, upstreams and uncommitted
+//!   changes, and [`paths`] maps branches to worktree directories.
+//! - [`commands`] holds the command implementations. Listings are also
+//!   available as typed results, [`commands::repo::list::list`] and
+//!   [`commands::worktree::list::list`], so callers don't parse output.
+
+pub mod backend;
+pub mod case;
+pub mod cli;
+pub mod commands;
+pub mod completion;
+pub mod config;
+pub mod crash_report;
+pub mod credentials;
+pub mod daemon;
+pub mod default_args;
+pub mod dry_run;
+pub mod editor;
+pub mod events;
+pub mod exit;
+pub mod forge;
+pub mod format;
+pub mod gitconfig;
+pub mod githooks;
+pub mod giturl;
+pub mod history;
+pub mod ignore;
+pub mod index;
+pub mod lock;
+pub mod logging;
+pub mod metadata;
+pub mod output;
+pub mod paths;
+pub mod picker;
+pub mod redirect;
+pub mod repo_context;
+pub mod repo_settings;
+pub mod scan;
+pub mod search;
+pub mod signing;
+pub mod sort;
+pub mod spec;
+pub mod status;
+pub mod style;
+pub mod suggest;
+pub mod templates;
+#[cfg(test)]
+pub mod test_utils;
+pub mod tmux;
+pub mod transport;
+pub mod trash;
+pub mod watch;
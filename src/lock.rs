@@ -0,0 +1,228 @@
+use anyhow::{Context, Result, bail};
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::{Seek, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tracing::info;
+
+/// How long to wait for another neoghq process to release a repository.
+const TIMEOUT: Duration = Duration::from_secs(30);
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// An exclusive lock on one repository, released when dropped. Clones,
+/// fetches and worktree changes hold it so concurrent neoghq processes
+/// can't interleave writes to the bare repository's worktree metadata.
+///
+/// The lock is an OS file lock on `<repo>/.git.lock`, which holds the
+/// owner's PID for messages. The OS releases it when its process dies, so
+/// a lock file left behind is simply locked again. It sits next to the
+/// bare repository rather than inside it so a clone can take it before
+/// `.git` exists.
+#[derive(Debug)]
+pub struct RepoLock {
+    path: PathBuf,
+    file: File,
+}
+
+impl RepoLock {
+    /// Locks the repository of `bare_repo_path`, waiting while another
+    /// process holds it.
+    pub fn acquire(bare_repo_path: &Path) -> Result<Self> {
+        Self::acquire_with_timeout(bare_repo_path, TIMEOUT)
+    }
+
+    fn acquire_with_timeout(bare_repo_path: &Path, timeout: Duration) -> Result<Self> {
+        let path = lock_path(bare_repo_path);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let started = Instant::now();
+        let mut waiting = false;
+        loop {
+            let mut file = OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create(true)
+                .truncate(false)
+                .open(&path)
+                .with_context(|| format!("Failed to create {}", path.display()))?;
+            match file.try_lock() {
+                // The previous owner removes the file before unlocking it,
+                // so a lock on a file no longer at `path` guards nothing
+                Ok(()) if is_same_file(&file, &path) => {
+                    file.set_len(0)?;
+                    file.rewind()?;
+                    writeln!(file, "{}", std::process::id())?;
+                    return Ok(Self { path, file });
+                }
+                Ok(()) => continue,
+                Err(TryLockError::WouldBlock) => {}
+                Err(TryLockError::Error(error)) => {
+                    return Err(error)
+                        .with_context(|| format!("Failed to lock {}", path.display()));
+                }
+            }
+
+            if started.elapsed() >= timeout {
+                bail!(
+                    "{} is locked by another neoghq process{}",
+                    bare_repo_path.display(),
+                    read_owner(&path)
+                        .map(|pid| format!(" (PID {pid})"))
+                        .unwrap_or_default(),
+                );
+            }
+            if !waiting {
+                info!(
+                    "Waiting for another neoghq process to release {}",
+                    bare_repo_path.display()
+                );
+                waiting = true;
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        }
+    }
+}
+
+/// Locks `repo` if it's a bare repository in neoghq's layout; the lock
+/// file would show up as untracked in the working directory of a plain
+/// clone, which git's own locks already cover.
+pub fn lock_shared(repo: &git2::Repository) -> Result<Option<RepoLock>> {
+    if !repo.is_bare() {
+        return Ok(None);
+    }
+    RepoLock::acquire(repo.path()).map(Some)
+}
+
+impl Drop for RepoLock {
+    fn drop(&mut self) {
+        // The repository may have been moved or deleted while locked; a file
+        // at the path that isn't ours belongs to whoever locks it
+        if is_same_file(&self.file, &self.path)
+            && read_owner(&self.path) == Some(std::process::id())
+        {
+            let _ = std::fs::remove_file(&self.path);
+        }
+        // Closing the file, after this, releases the lock
+    }
+}
+
+pub(crate) fn lock_path(bare_repo_path: &Path) -> PathBuf {
+    let mut name = bare_repo_path
+        .file_name()
+        .unwrap_or_default()
+        .to_os_string();
+    name.push(".lock");
+    bare_repo_path.with_file_name(name)
+}
+
+fn read_owner(path: &Path) -> Option<u32> {
+    std::fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+/// Whether `file` is the file at `path`, rather than one removed from it.
+#[cfg(unix)]
+fn is_same_file(file: &File, path: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    match (file.metadata(), std::fs::metadata(path)) {
+        (Ok(open), Ok(current)) => open.dev() == current.dev() && open.ino() == current.ino(),
+        _ => false,
+    }
+}
+
+/// Windows can't remove a file while it's open, so a locked file is always
+/// the one at its path.
+#[cfg(not(unix))]
+fn is_same_file(_file: &File, path: &Path) -> bool {
+    path.exists()
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lock_path() {
+        assert_eq!(
+            lock_path(Path::new("/repos/github.com/user/repo/.git")),
+            Path::new("/repos/github.com/user/repo/.git.lock")
+        );
+    }
+
+    #[test]
+    fn test_acquire_and_release() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let bare = temp_dir.path().join("github.com/user/repo/.git");
+
+        let lock = RepoLock::acquire(&bare).unwrap();
+        let path = lock_path(&bare);
+        assert_eq!(read_owner(&path), Some(std::process::id()));
+        assert!(RepoLock::acquire_with_timeout(&bare, Duration::ZERO).is_err());
+
+        drop(lock);
+        assert!(!path.exists());
+        assert!(RepoLock::acquire_with_timeout(&bare, Duration::ZERO).is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_stale_lock_is_taken_over() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let bare = temp_dir.path().join(".git");
+        // A child that has exited and been reaped no longer exists
+        let mut child = std::process::Command::new("true").spawn().unwrap();
+        let pid = child.id();
+        child.wait().unwrap();
+        std::fs::write(lock_path(&bare), format!("{pid}\n")).unwrap();
+
+        let _lock = RepoLock::acquire_with_timeout(&bare, Duration::ZERO).unwrap();
+        assert_eq!(read_owner(&lock_path(&bare)), Some(std::process::id()));
+    }
+
+    #[test]
+    fn test_racing_takeovers_of_stale_lock() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::{Arc, Barrier};
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let bare = temp_dir.path().join(".git");
+        std::fs::write(lock_path(&bare), "999999999\n").unwrap();
+        let threads = 8;
+        let barrier = Arc::new(Barrier::new(threads));
+        let holders = Arc::new(AtomicUsize::new(0));
+        let handles = (0..threads)
+            .map(|_| {
+                let (bare, barrier, holders) = (bare.clone(), barrier.clone(), holders.clone());
+                std::thread::spawn(move || {
+                    barrier.wait();
+                    for _ in 0..5 {
+                        let lock = RepoLock::acquire(&bare).unwrap();
+                        assert_eq!(holders.fetch_add(1, Ordering::SeqCst), 0);
+                        std::thread::sleep(Duration::from_millis(1));
+                        holders.fetch_sub(1, Ordering::SeqCst);
+                        drop(lock);
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        assert!(!lock_path(&bare).exists());
+    }
+
+    #[test]
+    fn test_drop_keeps_lock_of_another_owner() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let bare = temp_dir.path().join(".git");
+        let lock = RepoLock::acquire(&bare).unwrap();
+        // Replaced behind our back, as by `rm` and another process
+        std::fs::remove_file(lock_path(&bare)).unwrap();
+        std::fs::write(lock_path(&bare), "1\n").unwrap();
+        drop(lock);
+        assert_eq!(read_owner(&lock_path(&bare)), Some(1));
+    }
+}
@@ -0,0 +1,80 @@
+use std::fmt;
+use tracing::{Level, Subscriber};
+use tracing_subscriber::{
+    EnvFilter,
+    fmt::{FmtContext, FormatEvent, FormatFields, format::Writer},
+    registry::LookupSpan,
+};
+
+/// Environment variable holding a log filter such as `debug` or
+/// `neoghq::transport=trace`; it takes precedence over `-v` and `-q`.
+const LOG_ENV: &str = "NEOGHQ_LOG";
+
+/// Sends log events to stderr so stdout only carries command results.
+/// Progress messages are `info` events and show by default; `-q` keeps only
+/// errors, `-v` adds debug and `-vv` trace events.
+pub fn init(verbose: u8, quiet: bool) {
+    let filter = std::env::var(LOG_ENV)
+        .ok()
+        .and_then(|directives| EnvFilter::try_new(directives).ok())
+        .unwrap_or_else(|| EnvFilter::new(default_level(verbose, quiet).as_str()));
+
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr)
+        .event_format(Plain)
+        .init();
+}
+
+fn default_level(verbose: u8, quiet: bool) -> Level {
+    match (quiet, verbose) {
+        (true, _) => Level::ERROR,
+        (false, 0) => Level::INFO,
+        (false, 1) => Level::DEBUG,
+        (false, _) => Level::TRACE,
+    }
+}
+
+/// Prints `info` events as bare messages and prefixes the rest with their
+/// level, like `warning: ...`.
+struct Plain;
+
+impl<S, N> FormatEvent<S, N> for Plain
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &tracing::Event<'_>,
+    ) -> fmt::Result {
+        let prefix = match *event.metadata().level() {
+            Level::INFO => None,
+            Level::WARN => Some("warning"),
+            Level::ERROR => Some("error"),
+            Level::DEBUG => Some("debug"),
+            Level::TRACE => Some("trace"),
+        };
+        if let Some(prefix) = prefix {
+            write!(writer, "{prefix}: ")?;
+        }
+        ctx.field_format().format_fields(writer.by_ref(), event)?;
+        writeln!(writer)
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_level() {
+        assert_eq!(default_level(0, false), Level::INFO);
+        assert_eq!(default_level(1, false), Level::DEBUG);
+        assert_eq!(default_level(3, false), Level::TRACE);
+        assert_eq!(default_level(2, true), Level::ERROR);
+    }
+}
@@ -1,20 +1,62 @@
 #![cfg_attr(coverage_nightly, feature(coverage_attribute))]
 
-mod cli;
-mod commands;
-mod config;
-
 use anyhow::Result;
-use clap::Parser;
-use cli::Cli;
-use commands::execute_command;
+use clap::{CommandFactory, Parser};
+use neoghq::{
+    backend, cli, cli::Cli, commands::execute_command, config, crash_report::CrashReporter,
+    default_args, exit, logging, scan,
+};
 
 fn main() -> Result<()> {
-    let env = config::Env::load()?;
-    let config = config::Config::load(env)?;
-    let cli = Cli::parse();
+    clap_complete::CompleteEnv::with_factory(Cli::command).complete();
 
-    execute_command(cli.command, config)?;
+    let args = std::env::args().collect::<Vec<_>>();
+    let mut env = config::Env::load()?;
+    if let Some(config_path) = cli::find_config_arg(&args) {
+        env.config_path = Some(config_path);
+    }
+    // Replaced once the configuration is loaded, so panics while loading it
+    // are reported too
+    CrashReporter::early(&env, args.clone()).install_panic_hook();
+    let args =
+        default_args::apply_default_args(&Cli::command(), args, &config::load_default_args(&env)?);
+    let cli = Cli::parse_from(args);
+    logging::init(cli.verbose, cli.quiet);
+    if let Some(profile) = cli.profile {
+        env.profile = Some(profile);
+    }
+    let mut config = config::Config::load(env)?;
+    config.output = cli.output;
+    config.dry_run = cli.dry_run;
+    config.no_input = cli.no_input;
+    config.credentials.prompt = config.interactive();
+    if let Some(root) = &cli.root {
+        config.use_only_root(root)?;
+    }
+    if let Some(color) = cli.color {
+        config.color = color;
+    }
+
+    backend::init(config.git_backend);
+    scan::follow_symlinks(cli.follow_symlinks);
+
+    let reporter = CrashReporter::new(&config, std::env::args());
+    reporter.install_panic_hook();
+
+    if let Err(error) = execute_command(cli.command, config) {
+        if let Some(exit) = error.downcast_ref::<exit::ExitError>() {
+            if let Some(message) = &exit.message {
+                eprintln!("Error: {message}");
+            }
+            std::process::exit(exit.kind.code().into());
+        }
+        reporter.report_error(&error);
+        if let Some(kind) = exit::classify(&error) {
+            eprintln!("Error: {error:?}");
+            std::process::exit(kind.code().into());
+        }
+        return Err(error);
+    }
 
     Ok(())
 }
@@ -0,0 +1,697 @@
+use crate::{config::Config, lock::RepoLock, scan::RepoEntry};
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Per-root data about repositories that neither git nor the directory
+/// layout records, kept in `<root>/.neoghq/metadata.json`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Metadata {
+    /// Tags of each repository, keyed by `host/owner/repo`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    tags: BTreeMap<String, BTreeSet<String>>,
+    /// Repositories listed first by `repo list` and the picker, by
+    /// `host/owner/repo`.
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pinned: BTreeSet<String>,
+    /// Unix time each repository was last maintained, by `host/owner/repo`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    maintained: BTreeMap<String, u64>,
+    /// The `host/owner/repo` each repository was cloned as, by its
+    /// lowercased spec, so other spellings find the same directory.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    specs: BTreeMap<String, String>,
+    /// The `host/owner/repo` each fork was forked from, by the fork's
+    /// `host/owner/repo`, as the forge reported when it was cloned.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    forks: BTreeMap<String, String>,
+    /// The parent of each stacked branch, by branch, by `host/owner/repo`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    stacks: BTreeMap<String, BTreeMap<String, StackParent>>,
+    /// Unix time each scratch worktree expires at, by its directory name,
+    /// by `host/owner/repo`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    scratch: BTreeMap<String, BTreeMap<String, u64>>,
+    /// What each review worktree checks out, by its directory name, by
+    /// `host/owner/repo`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    reviews: BTreeMap<String, BTreeMap<String, Review>>,
+    /// Named groups of repositories operated on together, kept in the
+    /// default root.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub workspaces: BTreeMap<String, Vec<WorkspaceMember>>,
+}
+
+/// The branch a stacked branch was created on, and the commit of it the
+/// branch is based on, so restacking replays only the branch's own commits.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StackParent {
+    pub branch: String,
+    /// Object id of the parent's commit the branch starts from.
+    pub base: String,
+}
+
+/// What a review worktree checks out.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Review {
+    /// A pull request, or merge request, of `origin` by number.
+    PullRequest(u64),
+    /// A branch of `origin`.
+    Branch(String),
+}
+
+/// A repository of a workspace, and optionally the worktree to use instead
+/// of its default one.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorkspaceMember {
+    /// `host/owner/repo`.
+    pub repo: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub worktree: Option<String>,
+}
+
+impl Metadata {
+    /// Loads the metadata of `root`; a missing file is empty metadata.
+    pub fn load(root: &Path) -> Result<Self> {
+        let path = metadata_path(root);
+        match std::fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content)
+                .with_context(|| format!("Failed to parse {}", path.display())),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    /// Saves the metadata of `root` through a temporary file renamed over
+    /// it, so an interrupted write never leaves it truncated.
+    pub fn save(&self, root: &Path) -> Result<()> {
+        let path = metadata_path(root);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let temporary = path.with_extension(format!("json.{}.tmp", std::process::id()));
+        std::fs::write(&temporary, serde_json::to_string_pretty(self)? + "\n")?;
+        if let Err(error) = std::fs::rename(&temporary, &path) {
+            let _ = std::fs::remove_file(&temporary);
+            return Err(error).with_context(|| format!("Failed to write {}", path.display()));
+        }
+        Ok(())
+    }
+
+    /// Loads the metadata of `root`, lets `change` modify it and saves it,
+    /// locked so concurrent neoghq processes don't drop each other's changes.
+    pub fn update<T>(root: &Path, change: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        let _lock = RepoLock::acquire(&metadata_path(root))?;
+        let mut metadata = Self::load(root)?;
+        let result = change(&mut metadata)?;
+        metadata.save(root)?;
+        Ok(result)
+    }
+
+    /// Returns the tags of the repository `spec` (`host/owner/repo`), sorted.
+    pub fn tags(&self, spec: &str) -> Vec<String> {
+        self.tags
+            .get(spec)
+            .map(|tags| tags.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Returns every tag in use, sorted.
+    pub fn all_tags(&self) -> BTreeSet<String> {
+        self.tags.values().flatten().cloned().collect()
+    }
+
+    /// Tags the repository `spec`; returns whether it wasn't tagged yet.
+    pub fn add_tag(&mut self, spec: &str, tag: &str) -> Result<bool> {
+        validate_tag(tag)?;
+        Ok(self
+            .tags
+            .entry(spec.to_string())
+            .or_default()
+            .insert(tag.to_string()))
+    }
+
+    /// Pins the repository `spec`; returns whether it wasn't pinned yet.
+    pub fn pin(&mut self, spec: &str) -> bool {
+        self.pinned.insert(spec.to_string())
+    }
+
+    /// Unpins the repository `spec`; returns whether it was pinned.
+    pub fn unpin(&mut self, spec: &str) -> bool {
+        self.pinned.remove(spec)
+    }
+
+    /// Returns the Unix time the repository `spec` was last maintained.
+    pub fn last_maintenance(&self, spec: &str) -> Option<u64> {
+        self.maintained.get(spec).copied()
+    }
+
+    pub fn record_maintenance(&mut self, spec: &str, time: u64) {
+        self.maintained.insert(spec.to_string(), time);
+    }
+
+    /// Returns the spec the repository `spec` was cloned as, which may
+    /// differ from `spec` in case.
+    pub fn canonical_spec(&self, spec: &str) -> Option<&str> {
+        self.specs.get(&spec.to_lowercase()).map(String::as_str)
+    }
+
+    pub fn record_spec(&mut self, spec: &str) {
+        self.specs.insert(spec.to_lowercase(), spec.to_string());
+    }
+
+    /// Returns the repository the fork `spec` was forked from.
+    pub fn fork_parent(&self, spec: &str) -> Option<&str> {
+        self.forks.get(spec).map(String::as_str)
+    }
+
+    pub fn record_fork(&mut self, spec: &str, parent: &str) {
+        self.forks.insert(spec.to_string(), parent.to_string());
+    }
+
+    /// Returns the parent of the stacked `branch` of the repository `spec`.
+    pub fn stack_parent(&self, spec: &str, branch: &str) -> Option<&StackParent> {
+        self.stacks.get(spec)?.get(branch)
+    }
+
+    /// Returns the branches of the repository `spec` stacked directly on
+    /// `branch`, sorted.
+    pub fn stack_children(&self, spec: &str, branch: &str) -> Vec<String> {
+        self.stacks.get(spec).map_or_else(Vec::new, |stack| {
+            stack
+                .iter()
+                .filter(|(_, parent)| parent.branch == branch)
+                .map(|(child, _)| child.clone())
+                .collect()
+        })
+    }
+
+    pub fn set_stack_parent(&mut self, spec: &str, branch: &str, parent: StackParent) {
+        self.stacks
+            .entry(spec.to_string())
+            .or_default()
+            .insert(branch.to_string(), parent);
+    }
+
+    /// Forgets the parent of `branch`; returns whether it was stacked.
+    pub fn remove_stack_parent(&mut self, spec: &str, branch: &str) -> bool {
+        let Some(stack) = self.stacks.get_mut(spec) else {
+            return false;
+        };
+        let removed = stack.remove(branch).is_some();
+        if stack.is_empty() {
+            self.stacks.remove(spec);
+        }
+        removed
+    }
+
+    /// Returns the scratch worktrees of every repository as
+    /// `(host/owner/repo, directory name, expiry)`.
+    pub fn scratch_worktrees(&self) -> Vec<(String, String, u64)> {
+        self.scratch
+            .iter()
+            .flat_map(|(spec, worktrees)| {
+                worktrees
+                    .iter()
+                    .map(|(name, expires)| (spec.clone(), name.clone(), *expires))
+            })
+            .collect()
+    }
+
+    pub fn add_scratch(&mut self, spec: &str, name: &str, expires: u64) {
+        self.scratch
+            .entry(spec.to_string())
+            .or_default()
+            .insert(name.to_string(), expires);
+    }
+
+    /// Forgets the scratch worktree `name`; returns whether it was recorded.
+    pub fn remove_scratch(&mut self, spec: &str, name: &str) -> bool {
+        let Some(worktrees) = self.scratch.get_mut(spec) else {
+            return false;
+        };
+        let removed = worktrees.remove(name).is_some();
+        if worktrees.is_empty() {
+            self.scratch.remove(spec);
+        }
+        removed
+    }
+
+    /// Returns the review worktrees of the repository `spec` by directory
+    /// name.
+    pub fn reviews(&self, spec: &str) -> Vec<(String, Review)> {
+        self.reviews.get(spec).map_or_else(Vec::new, |reviews| {
+            reviews
+                .iter()
+                .map(|(name, review)| (name.clone(), review.clone()))
+                .collect()
+        })
+    }
+
+    /// Returns the repositories with review worktrees, by `host/owner/repo`.
+    /// Returns the repositories with scratch worktrees, sorted.
+    pub fn scratch_repositories(&self) -> Vec<String> {
+        self.scratch.keys().cloned().collect()
+    }
+
+    pub fn reviewed_repositories(&self) -> Vec<String> {
+        self.reviews.keys().cloned().collect()
+    }
+
+    pub fn add_review(&mut self, spec: &str, name: &str, review: Review) {
+        self.reviews
+            .entry(spec.to_string())
+            .or_default()
+            .insert(name.to_string(), review);
+    }
+
+    /// Forgets the review worktree `name`; returns whether it was recorded.
+    pub fn remove_review(&mut self, spec: &str, name: &str) -> bool {
+        let Some(reviews) = self.reviews.get_mut(spec) else {
+            return false;
+        };
+        let removed = reviews.remove(name).is_some();
+        if reviews.is_empty() {
+            self.reviews.remove(spec);
+        }
+        removed
+    }
+
+    /// Moves everything recorded about the repository `old` to `new`, after
+    /// `repo rename`.
+    pub fn rename(&mut self, old: &str, new: &str) {
+        let records = self.take_repository(old);
+        self.add_repository(records, new);
+        self.rename_workspace_members(old, new);
+    }
+
+    /// Removes and returns everything recorded about the repository `spec`
+    /// but its workspace memberships, for `repo rename` to another root.
+    pub fn take_repository(&mut self, spec: &str) -> Self {
+        let mut records = Self::default();
+        let key = || spec.to_string();
+        if let Some(tags) = self.tags.remove(spec) {
+            records.tags.insert(key(), tags);
+        }
+        if self.pinned.remove(spec) {
+            records.pinned.insert(key());
+        }
+        if let Some(time) = self.maintained.remove(spec) {
+            records.maintained.insert(key(), time);
+        }
+        if let Some(parent) = self.forks.remove(spec) {
+            records.forks.insert(key(), parent);
+        }
+        if let Some(stack) = self.stacks.remove(spec) {
+            records.stacks.insert(key(), stack);
+        }
+        if let Some(worktrees) = self.scratch.remove(spec) {
+            records.scratch.insert(key(), worktrees);
+        }
+        if let Some(reviews) = self.reviews.remove(spec) {
+            records.reviews.insert(key(), reviews);
+        }
+        if self
+            .specs
+            .get(&spec.to_lowercase())
+            .is_some_and(|recorded| recorded == spec)
+        {
+            self.specs.remove(&spec.to_lowercase());
+            records.record_spec(spec);
+        }
+        records
+    }
+
+    /// Records `records`, taken with `take_repository`, as those of the
+    /// repository `spec`.
+    pub fn add_repository(&mut self, records: Self, spec: &str) {
+        let key = || spec.to_string();
+        if let Some(tags) = records.tags.into_values().next() {
+            self.tags.insert(key(), tags);
+        }
+        if !records.pinned.is_empty() {
+            self.pinned.insert(key());
+        }
+        if let Some(time) = records.maintained.into_values().next() {
+            self.maintained.insert(key(), time);
+        }
+        if let Some(parent) = records.forks.into_values().next() {
+            self.forks.insert(key(), parent);
+        }
+        if let Some(stack) = records.stacks.into_values().next() {
+            self.stacks.insert(key(), stack);
+        }
+        if let Some(worktrees) = records.scratch.into_values().next() {
+            self.scratch.insert(key(), worktrees);
+        }
+        if let Some(reviews) = records.reviews.into_values().next() {
+            self.reviews.insert(key(), reviews);
+        }
+        if !records.specs.is_empty() {
+            self.record_spec(spec);
+        }
+    }
+
+    /// Points the workspace members naming the repository `old` at `new`.
+    pub fn rename_workspace_members(&mut self, old: &str, new: &str) {
+        for member in self.workspaces.values_mut().flatten() {
+            if member.repo == old {
+                member.repo = new.to_string();
+            }
+        }
+    }
+
+    /// Removes a tag from the repository `spec`; returns whether it had it.
+    pub fn remove_tag(&mut self, spec: &str, tag: &str) -> bool {
+        let Some(tags) = self.tags.get_mut(spec) else {
+            return false;
+        };
+        let removed = tags.remove(tag);
+        if tags.is_empty() {
+            self.tags.remove(spec);
+        }
+        removed
+    }
+}
+
+/// Returns the directories of the repositories tagged `tag` in any root.
+pub fn tagged(config: &Config, tag: &str) -> Result<HashSet<PathBuf>> {
+    repo_paths(config, |metadata| {
+        metadata
+            .tags
+            .iter()
+            .filter(|(_, tags)| tags.contains(tag))
+            .map(|(spec, _)| spec.clone())
+            .collect()
+    })
+}
+
+/// Returns the directories of the pinned repositories in any root.
+pub fn pinned(config: &Config) -> Result<HashSet<PathBuf>> {
+    repo_paths(config, |metadata| metadata.pinned.iter().cloned().collect())
+}
+
+/// Returns the directory names of the review worktrees of the repository
+/// `entry`, which don't count as dirty; none when its metadata can't be
+/// read.
+pub fn review_worktrees(entry: &RepoEntry) -> HashSet<String> {
+    entry
+        .root()
+        .and_then(|root| Metadata::load(root).ok())
+        .and_then(|mut metadata| metadata.reviews.remove(&entry.spec()))
+        .map(|reviews| reviews.into_keys().collect())
+        .unwrap_or_default()
+}
+
+/// Resolves the `host/owner/repo` names `select` picks from each root's
+/// metadata to repository directories.
+fn repo_paths(
+    config: &Config,
+    select: impl Fn(&Metadata) -> Vec<String>,
+) -> Result<HashSet<PathBuf>> {
+    let mut paths = HashSet::new();
+    for root in config.all_roots() {
+        let metadata = Metadata::load(&root.path)?;
+        paths.extend(select(&metadata).iter().map(|spec| root.path.join(spec)));
+    }
+    Ok(paths)
+}
+
+fn validate_tag(tag: &str) -> Result<()> {
+    if tag.is_empty() || tag.contains(char::is_whitespace) || tag.contains(',') {
+        bail!("Invalid tag: {tag:?} (tags can't be empty or contain spaces or commas)");
+    }
+    Ok(())
+}
+
+fn metadata_path(root: &Path) -> PathBuf {
+    root.join(".neoghq").join("metadata.json")
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use crate::config::Root;
+
+    #[test]
+    fn test_tags() {
+        let mut metadata = Metadata::default();
+        assert!(metadata.add_tag("github.com/user/repo", "work").unwrap());
+        assert!(!metadata.add_tag("github.com/user/repo", "work").unwrap());
+        assert!(
+            metadata
+                .add_tag("github.com/user/repo", "client-a")
+                .unwrap()
+        );
+        assert!(metadata.add_tag("github.com/user/other", "oss").unwrap());
+        assert!(
+            metadata
+                .add_tag("github.com/user/repo", "two words")
+                .is_err()
+        );
+        assert!(metadata.add_tag("github.com/user/repo", "").is_err());
+
+        assert_eq!(metadata.tags("github.com/user/repo"), ["client-a", "work"]);
+        assert_eq!(
+            metadata.all_tags().into_iter().collect::<Vec<_>>(),
+            ["client-a", "oss", "work"]
+        );
+
+        assert!(metadata.remove_tag("github.com/user/other", "oss"));
+        assert!(!metadata.remove_tag("github.com/user/other", "oss"));
+        assert!(metadata.tags("github.com/user/other").is_empty());
+        assert!(!metadata.tags.contains_key("github.com/user/other"));
+    }
+
+    #[test]
+    fn test_rename() {
+        let mut metadata = Metadata::default();
+        metadata.add_tag("github.com/user/repo", "work").unwrap();
+        metadata.pin("github.com/user/repo");
+        metadata.record_maintenance("github.com/user/repo", 1);
+        metadata.record_spec("github.com/user/repo");
+        metadata.record_fork("github.com/user/repo", "github.com/upstream/repo");
+        metadata.workspaces.insert(
+            "all".to_string(),
+            vec![WorkspaceMember {
+                repo: "github.com/user/repo".to_string(),
+                worktree: None,
+            }],
+        );
+
+        metadata.rename("github.com/user/repo", "github.com/org/Repo");
+        assert!(metadata.tags("github.com/user/repo").is_empty());
+        assert_eq!(metadata.tags("github.com/org/Repo"), ["work"]);
+        assert!(!metadata.unpin("github.com/user/repo"));
+        assert_eq!(metadata.last_maintenance("github.com/org/Repo"), Some(1));
+        assert_eq!(metadata.canonical_spec("github.com/user/repo"), None);
+        assert_eq!(
+            metadata.canonical_spec("github.com/org/repo"),
+            Some("github.com/org/Repo")
+        );
+        assert_eq!(metadata.workspaces["all"][0].repo, "github.com/org/Repo");
+        assert_eq!(
+            metadata.fork_parent("github.com/org/Repo"),
+            Some("github.com/upstream/repo")
+        );
+        assert!(metadata.unpin("github.com/org/Repo"));
+    }
+
+    #[test]
+    fn test_stacks() {
+        let mut metadata = Metadata::default();
+        let parent = |branch: &str| StackParent {
+            branch: branch.to_string(),
+            base: "0".repeat(40),
+        };
+        metadata.set_stack_parent("github.com/user/repo", "part-2", parent("part-1"));
+        metadata.set_stack_parent("github.com/user/repo", "part-1", parent("main"));
+        metadata.set_stack_parent("github.com/user/repo", "part-2b", parent("part-1"));
+
+        assert_eq!(
+            metadata.stack_children("github.com/user/repo", "part-1"),
+            ["part-2", "part-2b"]
+        );
+        assert_eq!(
+            metadata
+                .stack_parent("github.com/user/repo", "part-1")
+                .map(|parent| parent.branch.as_str()),
+            Some("main")
+        );
+        metadata.rename("github.com/user/repo", "github.com/org/repo");
+        assert!(
+            metadata
+                .stack_children("github.com/user/repo", "part-1")
+                .is_empty()
+        );
+        assert!(metadata.remove_stack_parent("github.com/org/repo", "part-2"));
+        assert!(!metadata.remove_stack_parent("github.com/org/repo", "part-2"));
+        assert_eq!(
+            metadata.stack_children("github.com/org/repo", "part-1"),
+            ["part-2b"]
+        );
+    }
+
+    #[test]
+    fn test_scratch() {
+        let mut metadata = Metadata::default();
+        metadata.add_scratch("github.com/user/repo", "scratch-1a2b3c4", 100);
+        metadata.add_scratch("github.com/user/other", "scratch-5d6e7f8", 200);
+        metadata.rename("github.com/user/repo", "github.com/org/repo");
+        assert_eq!(
+            metadata.scratch_worktrees(),
+            [
+                (
+                    "github.com/org/repo".to_string(),
+                    "scratch-1a2b3c4".to_string(),
+                    100
+                ),
+                (
+                    "github.com/user/other".to_string(),
+                    "scratch-5d6e7f8".to_string(),
+                    200
+                ),
+            ]
+        );
+        assert!(metadata.remove_scratch("github.com/org/repo", "scratch-1a2b3c4"));
+        assert!(!metadata.remove_scratch("github.com/org/repo", "scratch-1a2b3c4"));
+        assert_eq!(metadata.scratch_worktrees().len(), 1);
+        assert_eq!(metadata.scratch_repositories(), ["github.com/user/other"]);
+    }
+
+    #[test]
+    fn test_reviews() {
+        let mut metadata = Metadata::default();
+        metadata.add_review("github.com/user/repo", "review-42", Review::PullRequest(42));
+        metadata.add_review(
+            "github.com/user/repo",
+            "review-feature-x",
+            Review::Branch("feature/x".to_string()),
+        );
+        metadata.rename("github.com/user/repo", "github.com/org/repo");
+        assert_eq!(metadata.reviewed_repositories(), ["github.com/org/repo"]);
+        assert_eq!(
+            metadata.reviews("github.com/org/repo"),
+            [
+                ("review-42".to_string(), Review::PullRequest(42)),
+                (
+                    "review-feature-x".to_string(),
+                    Review::Branch("feature/x".to_string())
+                ),
+            ]
+        );
+        assert!(metadata.remove_review("github.com/org/repo", "review-42"));
+        assert!(!metadata.remove_review("github.com/org/repo", "review-42"));
+        assert!(metadata.remove_review("github.com/org/repo", "review-feature-x"));
+        assert!(metadata.reviewed_repositories().is_empty());
+    }
+
+    #[test]
+    fn test_canonical_spec() {
+        let mut metadata = Metadata::default();
+        assert_eq!(metadata.canonical_spec("github.com/user/repo"), None);
+
+        metadata.record_spec("github.com/User/Repo");
+        assert_eq!(
+            metadata.canonical_spec("github.com/user/repo"),
+            Some("github.com/User/Repo")
+        );
+        assert_eq!(
+            metadata.canonical_spec("GitHub.com/USER/repo"),
+            Some("github.com/User/Repo")
+        );
+    }
+
+    #[test]
+    fn test_pinned() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut metadata = Metadata::default();
+        assert!(metadata.pin("github.com/user/repo"));
+        assert!(!metadata.pin("github.com/user/repo"));
+        assert!(metadata.pin("github.com/user/other"));
+        assert!(metadata.unpin("github.com/user/other"));
+        assert!(!metadata.unpin("github.com/user/other"));
+        metadata.save(temp_dir.path()).unwrap();
+        let config = Config {
+            root: temp_dir.path().to_path_buf(),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            pinned(&config).unwrap(),
+            HashSet::from([temp_dir.path().join("github.com/user/repo")])
+        );
+    }
+
+    #[test]
+    fn test_load_and_save() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        assert_eq!(Metadata::load(root).unwrap(), Metadata::default());
+
+        let mut metadata = Metadata::default();
+        metadata.add_tag("github.com/user/repo", "work").unwrap();
+        metadata.save(root).unwrap();
+        assert_eq!(Metadata::load(root).unwrap(), metadata);
+
+        // The temporary file was renamed over it
+        let names = std::fs::read_dir(root.join(".neoghq"))
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["metadata.json"]);
+
+        std::fs::write(root.join(".neoghq/metadata.json"), "{").unwrap();
+        assert!(Metadata::load(root).is_err());
+    }
+
+    #[test]
+    fn test_concurrent_updates_keep_every_change() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+
+        let handles = (0..8)
+            .map(|n| {
+                let root = root.to_path_buf();
+                std::thread::spawn(move || {
+                    Metadata::update(&root, |metadata| {
+                        Ok(metadata.pin(&format!("github.com/user/repo{n}")))
+                    })
+                    .unwrap()
+                })
+            })
+            .collect::<Vec<_>>();
+        for handle in handles {
+            assert!(handle.join().unwrap());
+        }
+        assert_eq!(Metadata::load(root).unwrap().pinned.len(), 8);
+        assert!(Metadata::update(root, |_| -> Result<()> { bail!("no") }).is_err());
+        assert_eq!(Metadata::load(root).unwrap().pinned.len(), 8);
+    }
+
+    #[test]
+    fn test_tagged() {
+        let personal = tempfile::tempdir().unwrap();
+        let work = tempfile::tempdir().unwrap();
+        let mut metadata = Metadata::default();
+        metadata.add_tag("github.com/user/repo", "work").unwrap();
+        metadata.save(work.path()).unwrap();
+        let config = Config {
+            root: personal.path().to_path_buf(),
+            roots: vec![Root {
+                name: "work".to_string(),
+                path: work.path().to_path_buf(),
+            }],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            tagged(&config, "work").unwrap(),
+            HashSet::from([work.path().join("github.com/user/repo")])
+        );
+        assert!(tagged(&config, "oss").unwrap().is_empty());
+    }
+}
@@ -0,0 +1,133 @@
+use crate::style::visible_width;
+use anyhow::Result;
+use std::io::{self, Write};
+
+/// Writes the entries of a listing, newline-terminated or, with `--print0`,
+/// NUL-terminated so paths containing newlines survive `xargs -0`.
+pub struct Lines<W: Write> {
+    out: W,
+    terminator: u8,
+}
+
+impl Lines<io::StdoutLock<'static>> {
+    pub fn stdout(print0: bool) -> Self {
+        Self::new(io::stdout().lock(), print0)
+    }
+}
+
+impl<W: Write> Lines<W> {
+    pub fn new(out: W, print0: bool) -> Self {
+        Self {
+            out,
+            terminator: if print0 { b'\0' } else { b'\n' },
+        }
+    }
+
+    pub fn write(&mut self, entry: impl std::fmt::Display) -> Result<()> {
+        write!(self.out, "{entry}")?;
+        self.out.write_all(&[self.terminator])?;
+        Ok(())
+    }
+}
+
+/// Lays out `rows` under `header` in columns padded to the widest cell,
+/// two spaces apart. The last column is not padded. Cells may be colored;
+/// escape sequences don't count towards the width.
+pub fn table<S: AsRef<str>>(header: &[&str], rows: &[Vec<S>]) -> Vec<String> {
+    let mut widths = header
+        .iter()
+        .map(|cell| visible_width(cell))
+        .collect::<Vec<_>>();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(visible_width(cell.as_ref()));
+        }
+    }
+    let line = |cells: Vec<&str>| {
+        let last = cells.len().saturating_sub(1);
+        cells
+            .iter()
+            .zip(&widths)
+            .enumerate()
+            .map(|(index, (cell, width))| {
+                if index == last {
+                    cell.to_string()
+                } else {
+                    format!("{cell}{}", " ".repeat(width - visible_width(cell)))
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("  ")
+    };
+
+    std::iter::once(line(header.to_vec()))
+        .chain(
+            rows.iter()
+                .map(|row| line(row.iter().map(AsRef::as_ref).collect())),
+        )
+        .collect()
+}
+
+/// Formats a byte count with a binary unit, e.g. `1.5 MiB`.
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{size:.1} {}", UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lines() {
+        let mut out = Vec::new();
+        let mut lines = Lines::new(&mut out, false);
+        lines.write("a b").unwrap();
+        lines.write("c").unwrap();
+        assert_eq!(out, b"a b\nc\n");
+
+        let mut out = Vec::new();
+        let mut lines = Lines::new(&mut out, true);
+        lines.write("with\nnewline").unwrap();
+        lines.write("c").unwrap();
+        assert_eq!(out, b"with\nnewline\0c\0");
+    }
+
+    #[test]
+    fn test_table() {
+        let rows = vec![vec!["a", "long value", "x"], vec!["bbbb", "v", "y"]];
+        assert_eq!(
+            table(&["NAME", "VALUE", "LAST"], &rows),
+            [
+                "NAME  VALUE       LAST",
+                "a     long value  x",
+                "bbbb  v           y",
+            ]
+        );
+
+        let rows = vec![vec!["\x1b[31ma\x1b[0m", "x"], vec!["bb", "y"]];
+        assert_eq!(
+            table(&["N", "V"], &rows),
+            ["N   V", "\x1b[31ma\x1b[0m   x", "bb  y"]
+        );
+    }
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(0), "0 B");
+        assert_eq!(format_size(1023), "1023 B");
+        assert_eq!(format_size(1536), "1.5 KiB");
+        assert_eq!(format_size(3 * 1024 * 1024 * 1024), "3.0 GiB");
+    }
+}
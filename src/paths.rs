@@ -0,0 +1,198 @@
+use crate::config::Config;
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// Picks the root a repository from `host` belongs in: a
+/// `[hosts."<host>"]` rule, else the primary root. The global `--root`
+/// leaves only the primary root.
+pub fn resolve_root(config: &Config, host: &str) -> PathBuf {
+    match config.host_root(host) {
+        Some(root) => root.to_path_buf(),
+        None => config.root.clone(),
+    }
+}
+
+/// Creates `root` if it doesn't exist yet, so the first `repo get` after
+/// installing works without any setup.
+pub fn ensure_root(root: &Path) -> Result<()> {
+    if !root.exists() {
+        info!("Creating root directory {}", root.display());
+        std::fs::create_dir_all(root)?;
+    }
+    Ok(())
+}
+
+/// Joins nested GitLab groups in an owner directory, as GitLab's API
+/// encodes them, so every repository sits exactly three levels below its
+/// root: `gitlab.com/group%2Fsubgroup/project`.
+const NESTED_OWNER_SEPARATOR: &str = "%2F";
+
+/// Returns the directory holding the bare repository and worktrees of a repo.
+pub fn repository_dir(root: &Path, host: &str, owner: &str, repo: &str) -> PathBuf {
+    root.join(host).join(owner_dir(owner)).join(repo)
+}
+
+/// Returns the directory name of `owner`, with nested groups such as
+/// `group/subgroup` flattened into one.
+pub fn owner_dir(owner: &str) -> String {
+    owner.replace('/', NESTED_OWNER_SEPARATOR)
+}
+
+/// Returns the owner an owner directory stands for, as remote URLs spell it.
+pub fn owner_from_dir(dir: &str) -> String {
+    dir.replace(NESTED_OWNER_SEPARATOR, "/")
+}
+
+/// Returns the bare repository path of a repo.
+pub fn bare_repository_path(repo_dir: &Path) -> PathBuf {
+    repo_dir.join(".git")
+}
+
+/// Returns the worktree path of `branch` inside a repo.
+pub fn worktree_path(repo_dir: &Path, branch: &str) -> PathBuf {
+    repo_dir.join(branch)
+}
+
+/// Windows' `MAX_PATH`; longer paths only work in their `\\?\` form.
+const MAX_PATH: usize = 260;
+
+/// Like [`Path::canonicalize`], but on Windows returns `C:\...` or
+/// `\\server\share\...` instead of the `\\?\` form, which shells, editors and
+/// git don't accept. Paths too long for that form keep the prefix.
+pub fn canonicalize(path: &Path) -> std::io::Result<PathBuf> {
+    let canonical = path.canonicalize()?;
+    if cfg!(windows)
+        && let Some(simplified) = strip_verbatim(&canonical.to_string_lossy())
+    {
+        return Ok(PathBuf::from(simplified));
+    }
+    Ok(canonical)
+}
+
+fn strip_verbatim(path: &str) -> Option<String> {
+    let simplified = if let Some(unc) = path.strip_prefix(r"\\?\UNC\") {
+        format!(r"\\{unc}")
+    } else {
+        let rest = path.strip_prefix(r"\\?\")?;
+        let drive = rest.as_bytes();
+        if drive.len() < 3 || !drive[0].is_ascii_alphabetic() || &drive[1..3] != br":\" {
+            return None;
+        }
+        rest.to_string()
+    };
+    (simplified.len() < MAX_PATH).then_some(simplified)
+}
+
+/// Joins the components of a relative path with `/`, so specs, branch
+/// directories and index entries read the same on every platform.
+pub fn to_slash(path: &Path) -> String {
+    path.components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use crate::config::{HostConfig, Root};
+
+    fn create_config() -> Config {
+        Config {
+            root: PathBuf::from("/personal"),
+            roots: vec![Root {
+                name: "oss".to_string(),
+                path: PathBuf::from("/oss"),
+            }],
+            hosts: [(
+                "gitlab.company.com".to_string(),
+                HostConfig {
+                    root: Some(PathBuf::from("/work/repos")),
+                    ..Default::default()
+                },
+            )]
+            .into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_ensure_root() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path().join("src/repos");
+
+        ensure_root(&root).unwrap();
+        assert!(root.is_dir());
+        // An existing root is left alone
+        ensure_root(&root).unwrap();
+    }
+
+    #[test]
+    fn test_resolve_root_defaults_to_primary_root() {
+        let config = create_config();
+
+        let root = resolve_root(&config, "github.com");
+        assert_eq!(root, PathBuf::from("/personal"));
+    }
+
+    #[test]
+    fn test_resolve_root_uses_host_rule() {
+        let config = create_config();
+
+        let root = resolve_root(&config, "gitlab.company.com");
+        assert_eq!(root, PathBuf::from("/work/repos"));
+    }
+
+    #[test]
+    fn test_repository_layout_paths() {
+        let repo_dir = repository_dir(Path::new("/tmp/neoghq"), "github.com", "user", "repo");
+
+        assert_eq!(repo_dir, PathBuf::from("/tmp/neoghq/github.com/user/repo"));
+        assert_eq!(
+            bare_repository_path(&repo_dir),
+            PathBuf::from("/tmp/neoghq/github.com/user/repo/.git")
+        );
+        assert_eq!(
+            worktree_path(&repo_dir, "main"),
+            PathBuf::from("/tmp/neoghq/github.com/user/repo/main")
+        );
+    }
+
+    #[test]
+    fn test_strip_verbatim() {
+        assert_eq!(
+            strip_verbatim(r"\\?\C:\src\repos").as_deref(),
+            Some(r"C:\src\repos")
+        );
+        assert_eq!(
+            strip_verbatim(r"\\?\UNC\server\share\repos").as_deref(),
+            Some(r"\\server\share\repos")
+        );
+        assert_eq!(strip_verbatim(r"C:\src\repos"), None);
+        assert_eq!(strip_verbatim(r"\\?\Volume{1234}\repos"), None);
+        let long = format!(r"\\?\C:\{}", "a".repeat(MAX_PATH));
+        assert_eq!(strip_verbatim(&long), None);
+    }
+
+    #[test]
+    fn test_to_slash() {
+        assert_eq!(
+            to_slash(&Path::new("feature").join("x")),
+            "feature/x".to_string()
+        );
+        assert_eq!(to_slash(Path::new("main")), "main");
+        assert_eq!(to_slash(Path::new("")), "");
+    }
+
+    #[test]
+    fn test_canonicalize() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        assert_eq!(
+            canonicalize(temp_dir.path()).unwrap(),
+            temp_dir.path().canonicalize().unwrap()
+        );
+        assert!(canonicalize(&temp_dir.path().join("missing")).is_err());
+    }
+}
@@ -0,0 +1,106 @@
+use crate::{
+    config::Config,
+    exit::{self, ErrorKind},
+};
+use anyhow::{Result, anyhow};
+use dialoguer::{FuzzySelect, theme::ColorfulTheme};
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Lets the user choose one of `items` and returns its index, or `None`
+/// if the selection was cancelled. Uses `picker.command` when configured,
+/// and the built-in fuzzy finder on the terminal otherwise. Fails instead
+/// of prompting with `--no-input`; the built-in finder also needs stdin to
+/// be a terminal.
+pub fn pick(config: &Config, prompt: &str, items: &[String]) -> Result<Option<usize>> {
+    if items.is_empty() {
+        return Ok(None);
+    }
+    if config.no_input || (config.picker_command.is_none() && !config.interactive()) {
+        return Err(exit::error(
+            ErrorKind::InputRequired,
+            format!(
+                "{prompt} required: cannot open the picker without a terminal or with --no-input"
+            ),
+        ));
+    }
+
+    match &config.picker_command {
+        Some(command) => pick_with_command(command, items),
+        None => FuzzySelect::with_theme(&ColorfulTheme::default())
+            .with_prompt(prompt)
+            .items(items)
+            .default(0)
+            .interact_opt()
+            .map_err(|error| anyhow!("Could not start the picker: {error}")),
+    }
+}
+
+/// Runs `command` with one item per line on stdin and maps the line it
+/// prints back to an index.
+fn pick_with_command(command: &str, items: &[String]) -> Result<Option<usize>> {
+    let mut child = Command::new("sh")
+        .args(["-c", command])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|error| anyhow!("Failed to run picker command {command}: {error}"))?;
+
+    {
+        let mut stdin = child.stdin.take().unwrap();
+        for item in items {
+            // The picker may exit before reading everything
+            if writeln!(stdin, "{item}").is_err() {
+                break;
+            }
+        }
+    }
+
+    let output = child.wait_with_output()?;
+    // fzf exits with 130 when cancelled and 1 when nothing matched
+    if !output.status.success() {
+        return Ok(None);
+    }
+    let selected = String::from_utf8_lossy(&output.stdout);
+    let selected = selected.lines().next().unwrap_or_default();
+    Ok(items.iter().position(|item| item == selected))
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+
+    fn items() -> Vec<String> {
+        ["github.com/a/one", "github.com/b/two", "gitlab.com/c/three"]
+            .map(String::from)
+            .to_vec()
+    }
+
+    #[test]
+    fn test_pick_with_command() {
+        assert_eq!(pick_with_command("grep two", &items()).unwrap(), Some(1));
+        assert_eq!(pick_with_command("tail -n 1", &items()).unwrap(), Some(2));
+        assert_eq!(pick_with_command("grep missing", &items()).unwrap(), None);
+        assert_eq!(pick_with_command("exit 130", &items()).unwrap(), None);
+    }
+
+    #[test]
+    fn test_pick_without_items() {
+        assert_eq!(pick(&Config::default(), "Repository", &[]).unwrap(), None);
+    }
+
+    #[test]
+    fn test_pick_without_input() {
+        let items = ["a".to_string()];
+        let config = Config {
+            picker_command: Some("head -n 1".to_string()),
+            no_input: true,
+            ..Default::default()
+        };
+        assert!(pick(&config, "Repository", &items).is_err());
+
+        // Tests run without a terminal on stdin
+        assert!(pick(&Config::default(), "Repository", &items).is_err());
+    }
+}
@@ -0,0 +1,131 @@
+use anyhow::{Context, Result, anyhow};
+use std::path::Path;
+use std::process::Command;
+use tracing::debug;
+
+/// Where the URL `origin` last redirected to is kept, inside the bare
+/// repository.
+const REDIRECT_FILE: &str = "neoghq-redirect";
+
+/// Asks the server of the HTTP(S) `url` whether the repository moved, as
+/// GitHub answers for renamed and transferred repositories, and returns
+/// the URL it redirects to. Other URLs, and hosts that can't be reached
+/// without the git executable, are never reported as moved.
+pub fn detect(url: &str) -> Result<Option<String>> {
+    if !url.starts_with("https://") && !url.starts_with("http://") {
+        return Ok(None);
+    }
+    let output = Command::new("git")
+        .args([
+            "-c",
+            "http.followRedirects=true",
+            "ls-remote",
+            "--",
+            url,
+            "HEAD",
+        ])
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .output();
+    let output = match output {
+        Ok(output) => output,
+        Err(error) => {
+            debug!("Can't check {url} for redirects: {error}");
+            return Ok(None);
+        }
+    };
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Failed to check {url} for redirects: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(parse_redirect(
+        &String::from_utf8_lossy(&output.stderr),
+        url,
+    ))
+}
+
+/// Reads the URL from git's `warning: redirecting to <url>`, unless it's
+/// `url` itself.
+fn parse_redirect(stderr: &str, url: &str) -> Option<String> {
+    let target = stderr
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("warning: redirecting to "))?
+        .trim()
+        .trim_end_matches('/');
+    let normalize = |url: &str| {
+        let url = url.trim_end_matches('/');
+        url.strip_suffix(".git").unwrap_or(url).to_string()
+    };
+    (normalize(target) != normalize(url)).then(|| target.to_string())
+}
+
+/// Remembers where `origin` of the bare repository at `bare_repo_path`
+/// redirects to, or with `None` that it doesn't, so `status` can report it.
+pub fn record(bare_repo_path: &Path, target: Option<&str>) -> Result<()> {
+    let path = bare_repo_path.join(REDIRECT_FILE);
+    match target {
+        Some(target) => std::fs::write(&path, format!("{target}\n"))
+            .with_context(|| format!("Failed to write {}", path.display())),
+        None => match std::fs::remove_file(&path) {
+            Err(error) if error.kind() != std::io::ErrorKind::NotFound => Err(error.into()),
+            _ => Ok(()),
+        },
+    }
+}
+
+/// Returns where `origin` of the bare repository redirected to when it was
+/// last fetched.
+pub fn recorded(bare_repo_path: &Path) -> Option<String> {
+    std::fs::read_to_string(bare_repo_path.join(REDIRECT_FILE))
+        .ok()
+        .map(|target| target.trim_end().to_string())
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_redirect() {
+        let url = "https://github.com/user/repo";
+        assert_eq!(
+            parse_redirect(
+                "warning: redirecting to https://github.com/org/project.git/\n",
+                url
+            )
+            .as_deref(),
+            Some("https://github.com/org/project.git")
+        );
+        // Adding .git or a slash isn't a move
+        assert_eq!(
+            parse_redirect(
+                "warning: redirecting to https://github.com/user/repo.git/\n",
+                url
+            ),
+            None
+        );
+        assert_eq!(parse_redirect("", url), None);
+    }
+
+    #[test]
+    fn test_detect_skips_other_urls() {
+        assert_eq!(detect("git@github.com:user/repo.git").unwrap(), None);
+        assert_eq!(detect("/srv/git/repo").unwrap(), None);
+    }
+
+    #[test]
+    fn test_record() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        assert_eq!(recorded(temp_dir.path()), None);
+        record(temp_dir.path(), Some("https://github.com/org/project.git")).unwrap();
+        assert_eq!(
+            recorded(temp_dir.path()).as_deref(),
+            Some("https://github.com/org/project.git")
+        );
+        record(temp_dir.path(), None).unwrap();
+        record(temp_dir.path(), None).unwrap();
+        assert_eq!(recorded(temp_dir.path()), None);
+    }
+}
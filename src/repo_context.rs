@@ -0,0 +1,191 @@
+use crate::{paths, scan::RepoEntry};
+use anyhow::{Context, Result, anyhow};
+use git2::Repository;
+use std::path::{Path, PathBuf};
+
+/// The repository a command works on, found from a directory anywhere
+/// inside it: a worktree or one of its subdirectories, the repository
+/// directory, or the bare repository itself.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RepoContext {
+    /// The directory holding `.git` and the worktrees,
+    /// `<root>/<host>/<owner>/<repo>` for managed repositories.
+    pub repo_dir: PathBuf,
+    /// The git directory shared by every worktree.
+    pub git_dir: PathBuf,
+    /// The worktree the directory is in, if any.
+    pub worktree: Option<PathBuf>,
+}
+
+impl RepoContext {
+    /// Opens the shared repository, bare in neoghq's layout.
+    pub fn open(&self) -> Result<Repository> {
+        Repository::open(&self.git_dir)
+            .with_context(|| format!("Failed to open {}", self.git_dir.display()))
+    }
+
+    /// Returns the entry of the repository when it's laid out as
+    /// `<host>/<owner>/<repo>`.
+    pub fn entry(&self) -> Option<RepoEntry> {
+        RepoEntry::from_path(&self.repo_dir)
+    }
+
+    /// Returns the root the repository lives under and its
+    /// `host/owner/repo`, which the root's metadata records it under.
+    pub fn metadata_key(&self) -> Result<(PathBuf, String)> {
+        self.entry()
+            .and_then(|entry| Some((entry.root()?.to_path_buf(), entry.spec())))
+            .ok_or_else(|| {
+                anyhow!(
+                    "{} isn't a repository under a root",
+                    self.repo_dir.display()
+                )
+            })
+    }
+
+    /// Whether the repository is a plain clone rather than a bare
+    /// repository with worktrees.
+    pub fn is_plain_clone(&self) -> bool {
+        self.git_dir == self.repo_dir.join(".git") && self.worktree.as_ref() == Some(&self.repo_dir)
+    }
+}
+
+/// Resolves the repository containing the current directory.
+pub fn current_repo() -> Result<RepoContext> {
+    from_path(&std::env::current_dir()?)
+}
+
+/// Resolves the repository containing `from` by walking up to the closest
+/// `.git`: a worktree's `.git` file leads through its administrative
+/// directory to the shared repository, and a `.git` directory is the shared
+/// repository itself.
+pub fn from_path(from: &Path) -> Result<RepoContext> {
+    let from = paths::canonicalize(from).unwrap_or_else(|_| from.to_path_buf());
+    // Inside the bare repository, the repository directory is its parent
+    let start = from
+        .ancestors()
+        .find(|dir| dir.file_name().is_some_and(|name| name == ".git"))
+        .and_then(Path::parent)
+        .unwrap_or(&from);
+    for dir in start.ancestors() {
+        let dot_git = dir.join(".git");
+        if dot_git.is_file() {
+            let git_dir = common_dir(&linked_git_dir(&dot_git)?)?;
+            let repo_dir = git_dir
+                .parent()
+                .ok_or_else(|| anyhow!("Repository has no root: {}", git_dir.display()))?
+                .to_path_buf();
+            return Ok(RepoContext {
+                repo_dir,
+                git_dir,
+                worktree: Some(dir.to_path_buf()),
+            });
+        }
+        if dot_git.is_dir() {
+            let bare = Repository::open(&dot_git)
+                .with_context(|| format!("Failed to open {}", dot_git.display()))?
+                .is_bare();
+            return Ok(RepoContext {
+                repo_dir: dir.to_path_buf(),
+                git_dir: dot_git,
+                worktree: (!bare).then(|| dir.to_path_buf()),
+            });
+        }
+    }
+    Err(anyhow!("Not inside a git repository: {}", from.display()))
+}
+
+/// Reads the `gitdir:` line of a worktree's `.git` file.
+fn linked_git_dir(dot_git: &Path) -> Result<PathBuf> {
+    let content = std::fs::read_to_string(dot_git)?;
+    let git_dir = content
+        .trim()
+        .strip_prefix("gitdir:")
+        .ok_or_else(|| anyhow!("{} has no gitdir line", dot_git.display()))?
+        .trim();
+    let worktree = dot_git.parent().unwrap_or(dot_git);
+    paths::canonicalize(&worktree.join(git_dir))
+        .with_context(|| format!("{} points at a missing {git_dir}", dot_git.display()))
+}
+
+/// Follows a worktree's administrative directory to the shared one.
+fn common_dir(git_dir: &Path) -> Result<PathBuf> {
+    match std::fs::read_to_string(git_dir.join("commondir")) {
+        Ok(common_dir) => Ok(paths::canonicalize(&git_dir.join(common_dir.trim()))?),
+        Err(_) => Ok(git_dir.to_path_buf()),
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use crate::test_utils::{create_managed_repository, create_source_repository};
+
+    #[test]
+    fn test_from_path() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo_dir = paths::canonicalize(temp_dir.path())
+            .unwrap()
+            .join("github.com/user/repo");
+        let bare = create_managed_repository(&repo_dir);
+        let head = bare.head().unwrap().peel_to_commit().unwrap();
+        let branch = bare.branch("feature/x", &head, false).unwrap();
+        let mut opts = git2::WorktreeAddOptions::new();
+        opts.reference(Some(branch.get()));
+        std::fs::create_dir(repo_dir.join("feature")).unwrap();
+        bare.worktree("feature-x", &repo_dir.join("feature/x"), Some(&opts))
+            .unwrap();
+        std::fs::create_dir_all(repo_dir.join("feature/x/src/deep")).unwrap();
+        let git_dir = repo_dir.join(".git");
+
+        let context = from_path(&repo_dir.join("feature/x/src/deep")).unwrap();
+        assert_eq!(
+            context,
+            RepoContext {
+                repo_dir: repo_dir.clone(),
+                git_dir: git_dir.clone(),
+                worktree: Some(repo_dir.join("feature/x")),
+            }
+        );
+        assert_eq!(context.entry().unwrap().spec(), "github.com/user/repo");
+        assert!(context.open().unwrap().is_bare());
+        assert!(!context.is_plain_clone());
+
+        assert_eq!(
+            from_path(&repo_dir.join("main")).unwrap().worktree,
+            Some(repo_dir.join("main"))
+        );
+        // Outside any worktree
+        for dir in [
+            repo_dir.clone(),
+            repo_dir.join("feature"),
+            git_dir.join("refs/heads"),
+        ] {
+            let context = from_path(&dir).unwrap();
+            assert_eq!(context.repo_dir, repo_dir, "{}", dir.display());
+            assert_eq!(context.git_dir, git_dir);
+            assert_eq!(context.worktree, None);
+        }
+    }
+
+    #[test]
+    fn test_from_path_plain_clone_and_outside() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let clone = paths::canonicalize(temp_dir.path()).unwrap().join("clone");
+        create_source_repository(&clone);
+        std::fs::create_dir(clone.join("src")).unwrap();
+
+        let context = from_path(&clone.join("src")).unwrap();
+        assert_eq!(context.repo_dir, clone);
+        assert_eq!(context.worktree, Some(clone.clone()));
+        assert!(context.is_plain_clone());
+
+        let outside = temp_dir.path().join("outside");
+        std::fs::create_dir(&outside).unwrap();
+        // The temporary directory may itself live inside a repository
+        if let Ok(context) = from_path(&outside) {
+            assert!(!context.repo_dir.starts_with(temp_dir.path()));
+        }
+    }
+}
@@ -0,0 +1,516 @@
+use crate::config::Config;
+use crate::scan::RepoEntry;
+use crate::status;
+use anyhow::{Context, Result, anyhow};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tracing::{debug, warn};
+
+/// Name of the per-repository settings file, placed in the repository
+/// directory next to `.git` and the worktrees.
+pub const REPO_SETTINGS_FILE: &str = ".neoghq.toml";
+
+/// Settings for a single repository, from a `[repo."<host>/<owner>/<repo>"]`
+/// table in the global config or the repository's `.neoghq.toml`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct RepoSettings {
+    /// Branch checked out when `repo get` is given none.
+    pub default_worktree: Option<String>,
+    /// Files in the repository directory symlinked into every new worktree,
+    /// or copied where the user may not create symlinks.
+    pub links: Option<Vec<PathBuf>>,
+    /// Sparse-checkout patterns applied to every new worktree.
+    pub sparse: Option<Vec<String>>,
+    /// Templates rendered into every new worktree, keyed by their path in
+    /// the worktree. Relative templates are read from the repository
+    /// directory.
+    pub render: Option<BTreeMap<PathBuf, PathBuf>>,
+    /// Whether `neoghq daemon` fetches and caches the repository; unset
+    /// means yes.
+    pub daemon: Option<bool>,
+    #[serde(default)]
+    pub hooks: RepoHooks,
+}
+
+/// Commands run through `sh -c` inside a worktree.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct RepoHooks {
+    /// Run after a worktree is created.
+    pub post_create: Option<Vec<String>>,
+}
+
+impl RepoSettings {
+    /// Loads the settings for the repository `spec` (`host/owner/repo`)
+    /// stored in `repo_dir`. Values in `.neoghq.toml` take precedence over
+    /// the global config.
+    pub fn load(config: &Config, spec: &str, repo_dir: &Path) -> Result<Self> {
+        let global = config.repos.get(spec).cloned().unwrap_or_default();
+
+        let path = repo_dir.join(REPO_SETTINGS_FILE);
+        if !path.exists() {
+            return Ok(global);
+        }
+
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let local: Self = toml::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+
+        Ok(local.or(global))
+    }
+
+    fn or(self, fallback: Self) -> Self {
+        Self {
+            default_worktree: self.default_worktree.or(fallback.default_worktree),
+            links: self.links.or(fallback.links),
+            sparse: self.sparse.or(fallback.sparse),
+            render: self.render.or(fallback.render),
+            daemon: self.daemon.or(fallback.daemon),
+            hooks: RepoHooks {
+                post_create: self.hooks.post_create.or(fallback.hooks.post_create),
+            },
+        }
+    }
+
+    /// Prepares a newly created worktree: applies sparse patterns, links
+    /// shared files, renders templates, then runs the `post_create` hooks.
+    pub fn setup_worktree(&self, repo_dir: &Path, worktree_path: &Path) -> Result<()> {
+        if let Some(patterns) = self.sparse.as_ref().filter(|p| !p.is_empty()) {
+            apply_sparse_checkout(worktree_path, patterns)?;
+        }
+
+        for link in self.links.iter().flatten() {
+            link_shared_file(repo_dir, worktree_path, link)?;
+        }
+
+        if let Some(templates) = self.render.as_ref().filter(|t| !t.is_empty()) {
+            let variables = template_variables(repo_dir, worktree_path);
+            for (target, template) in templates {
+                render_template(repo_dir, worktree_path, target, template, &variables)?;
+            }
+        }
+
+        for command in self.hooks.post_create.iter().flatten() {
+            run_hook(worktree_path, command)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// libgit2 has no sparse-checkout support, so this uses the git CLI.
+fn apply_sparse_checkout(worktree_path: &Path, patterns: &[String]) -> Result<()> {
+    let status = Command::new("git")
+        .arg("-C")
+        .arg(worktree_path)
+        .args(["sparse-checkout", "set"])
+        .args(patterns)
+        .status()
+        .context("Failed to run git sparse-checkout")?;
+
+    if !status.success() {
+        return Err(anyhow!("git sparse-checkout set failed with {status}"));
+    }
+    Ok(())
+}
+
+fn escapes(path: &Path) -> bool {
+    path.is_absolute() || path.components().any(|c| c.as_os_str() == "..")
+}
+
+fn link_shared_file(repo_dir: &Path, worktree_path: &Path, link: &Path) -> Result<()> {
+    if escapes(link) {
+        return Err(anyhow!(
+            "Shared file must be relative to the repository directory: {}",
+            link.display()
+        ));
+    }
+
+    let source = repo_dir.join(link);
+    let target = worktree_path.join(link);
+    if !source.exists() {
+        warn!("Skipping missing shared file: {}", source.display());
+        return Ok(());
+    }
+    if target.symlink_metadata().is_ok() {
+        return Ok(());
+    }
+
+    if let Some(parent) = target.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    match symlink(&source, &target) {
+        Err(error) if symlink_not_permitted(&error) => {
+            warn!(
+                "Copying {} instead of linking it: creating symlinks needs Developer Mode or administrator rights",
+                link.display()
+            );
+            copy_recursively(&source, &target)
+                .with_context(|| format!("Failed to copy {}", target.display()))
+        }
+        result => result.with_context(|| format!("Failed to link {}", target.display())),
+    }
+}
+
+/// Whether symlinking failed only because the user may not create symlinks,
+/// as on Windows outside Developer Mode.
+fn symlink_not_permitted(error: &std::io::Error) -> bool {
+    // ERROR_PRIVILEGE_NOT_HELD
+    cfg!(windows) && error.raw_os_error() == Some(1314)
+}
+
+fn copy_recursively(source: &Path, target: &Path) -> std::io::Result<()> {
+    if !source.is_dir() {
+        return std::fs::copy(source, target).map(|_| ());
+    }
+    std::fs::create_dir_all(target)?;
+    for entry in std::fs::read_dir(source)? {
+        let entry = entry?;
+        copy_recursively(&entry.path(), &target.join(entry.file_name()))?;
+    }
+    Ok(())
+}
+
+/// The `{{name}}` variables templates may use. Double braces leave shell
+/// syntax such as `${VAR}` alone.
+fn template_variables(repo_dir: &Path, worktree_path: &Path) -> Vec<(&'static str, String)> {
+    let worktree_name = worktree_path
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned();
+    let branch = git2::Repository::open(worktree_path)
+        .ok()
+        .and_then(|repo| status::head_branch(&repo))
+        .unwrap_or_else(|| worktree_name.clone());
+    let repo = RepoEntry::from_path(repo_dir).map_or_else(String::new, |entry| entry.spec());
+    vec![
+        ("branch", branch),
+        ("worktree", worktree_path.display().to_string()),
+        ("worktree_name", worktree_name),
+        ("repo", repo),
+        ("repo_dir", repo_dir.display().to_string()),
+    ]
+}
+
+fn render(content: &str, variables: &[(&str, String)]) -> String {
+    variables
+        .iter()
+        .fold(content.to_string(), |content, (name, value)| {
+            content.replace(&format!("{{{{{name}}}}}"), value)
+        })
+}
+
+/// Writes `template` with its variables replaced to `target` in the
+/// worktree, unless the checkout already has that file, and lets direnv
+/// load a rendered `.envrc`.
+fn render_template(
+    repo_dir: &Path,
+    worktree_path: &Path,
+    target: &Path,
+    template: &Path,
+    variables: &[(&str, String)],
+) -> Result<()> {
+    if escapes(target) {
+        return Err(anyhow!(
+            "Rendered file must be relative to the worktree: {}",
+            target.display()
+        ));
+    }
+
+    let source = repo_dir.join(template);
+    let path = worktree_path.join(target);
+    if !source.exists() {
+        warn!("Skipping missing template: {}", source.display());
+        return Ok(());
+    }
+    if path.symlink_metadata().is_ok() {
+        warn!("Not rendering {}: it already exists", path.display());
+        return Ok(());
+    }
+
+    let content = std::fs::read_to_string(&source)
+        .with_context(|| format!("Failed to read {}", source.display()))?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&path, render(&content, variables))
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+
+    if target.file_name().is_some_and(|name| name == ".envrc") {
+        allow_envrc(&path);
+    }
+    Ok(())
+}
+
+/// Runs `direnv allow` on a rendered `.envrc` when direnv is installed.
+fn allow_envrc(path: &Path) {
+    match Command::new("direnv").arg("allow").arg(path).status() {
+        Ok(status) if status.success() => {}
+        Ok(status) => warn!("direnv allow {} failed with {status}", path.display()),
+        Err(error) => debug!("Not running direnv allow: {error}"),
+    }
+}
+
+#[cfg(unix)]
+pub(crate) fn symlink(source: &Path, target: &Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(source, target)
+}
+
+#[cfg(windows)]
+pub(crate) fn symlink(source: &Path, target: &Path) -> std::io::Result<()> {
+    if source.is_dir() {
+        std::os::windows::fs::symlink_dir(source, target)
+    } else {
+        std::os::windows::fs::symlink_file(source, target)
+    }
+}
+
+fn run_hook(worktree_path: &Path, command: &str) -> Result<()> {
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .current_dir(worktree_path)
+        .status()
+        .with_context(|| format!("Failed to run hook: {command}"))?;
+
+    if !status.success() {
+        return Err(anyhow!("Hook `{command}` failed with {status}"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn config_with(spec: &str, settings: RepoSettings) -> Config {
+        Config {
+            repos: BTreeMap::from([(spec.to_string(), settings)]),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_load_without_settings() {
+        let temp_dir = tempfile::tempdir().unwrap();
+
+        let settings =
+            RepoSettings::load(&Config::default(), "github.com/u/r", temp_dir.path()).unwrap();
+
+        assert_eq!(settings, RepoSettings::default());
+    }
+
+    #[test]
+    fn test_load_from_global_config() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config = config_with(
+            "github.com/u/r",
+            RepoSettings {
+                default_worktree: Some("develop".to_string()),
+                ..Default::default()
+            },
+        );
+
+        let settings = RepoSettings::load(&config, "github.com/u/r", temp_dir.path()).unwrap();
+        assert_eq!(settings.default_worktree.as_deref(), Some("develop"));
+
+        let other = RepoSettings::load(&config, "github.com/u/other", temp_dir.path()).unwrap();
+        assert_eq!(other.default_worktree, None);
+    }
+
+    #[test]
+    fn test_load_repo_file_overrides_global_config() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            temp_dir.path().join(REPO_SETTINGS_FILE),
+            r#"
+default_worktree = "trunk"
+sparse = ["services/api"]
+
+[hooks]
+post_create = ["make setup"]
+"#,
+        )
+        .unwrap();
+        let config = config_with(
+            "github.com/u/r",
+            RepoSettings {
+                default_worktree: Some("develop".to_string()),
+                links: Some(vec![PathBuf::from(".env")]),
+                daemon: Some(false),
+                ..Default::default()
+            },
+        );
+
+        let settings = RepoSettings::load(&config, "github.com/u/r", temp_dir.path()).unwrap();
+
+        assert_eq!(
+            settings,
+            RepoSettings {
+                default_worktree: Some("trunk".to_string()),
+                links: Some(vec![PathBuf::from(".env")]),
+                sparse: Some(vec!["services/api".to_string()]),
+                render: None,
+                daemon: Some(false),
+                hooks: RepoHooks {
+                    post_create: Some(vec!["make setup".to_string()]),
+                },
+            }
+        );
+    }
+
+    #[test]
+    fn test_load_with_invalid_repo_file() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        std::fs::write(temp_dir.path().join(REPO_SETTINGS_FILE), "sparse = 1").unwrap();
+
+        let result = RepoSettings::load(&Config::default(), "github.com/u/r", temp_dir.path());
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_setup_worktree_links_files_and_runs_hooks() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo_dir = temp_dir.path();
+        let worktree = repo_dir.join("main");
+        std::fs::create_dir_all(&worktree).unwrap();
+        std::fs::write(repo_dir.join(".env"), "SECRET=1").unwrap();
+        let settings = RepoSettings {
+            links: Some(vec![PathBuf::from(".env"), PathBuf::from("missing")]),
+            hooks: RepoHooks {
+                post_create: Some(vec!["touch created".to_string()]),
+            },
+            ..Default::default()
+        };
+
+        settings.setup_worktree(repo_dir, &worktree).unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(worktree.join(".env")).unwrap(),
+            "SECRET=1"
+        );
+        assert!(
+            worktree
+                .join(".env")
+                .symlink_metadata()
+                .unwrap()
+                .is_symlink()
+        );
+        assert!(!worktree.join("missing").exists());
+        assert!(worktree.join("created").exists());
+    }
+
+    #[test]
+    fn test_setup_worktree_rejects_escaping_links() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let settings = RepoSettings {
+            links: Some(vec![PathBuf::from("../outside")]),
+            ..Default::default()
+        };
+
+        let result = settings.setup_worktree(temp_dir.path(), temp_dir.path());
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_copy_recursively() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let source = temp_dir.path().join("config");
+        std::fs::create_dir_all(source.join("nested")).unwrap();
+        std::fs::write(source.join("settings.json"), "{}").unwrap();
+        std::fs::write(source.join("nested/local.env"), "A=1").unwrap();
+
+        let target = temp_dir.path().join("main/config");
+        std::fs::create_dir(temp_dir.path().join("main")).unwrap();
+        copy_recursively(&source, &target).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(target.join("settings.json")).unwrap(),
+            "{}"
+        );
+        assert_eq!(
+            std::fs::read_to_string(target.join("nested/local.env")).unwrap(),
+            "A=1"
+        );
+        assert!(!target.symlink_metadata().unwrap().is_symlink());
+
+        copy_recursively(
+            &source.join("settings.json"),
+            &temp_dir.path().join("copy.json"),
+        )
+        .unwrap();
+        assert!(temp_dir.path().join("copy.json").is_file());
+    }
+
+    #[test]
+    fn test_setup_worktree_with_failing_hook() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let settings = RepoSettings {
+            hooks: RepoHooks {
+                post_create: Some(vec!["exit 3".to_string()]),
+            },
+            ..Default::default()
+        };
+
+        let result = settings.setup_worktree(temp_dir.path(), temp_dir.path());
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_setup_worktree_renders_templates() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo_dir = temp_dir.path().join("github.com/u/r");
+        crate::test_utils::create_managed_repository(&repo_dir);
+        let worktree = repo_dir.join("main");
+        std::fs::write(
+            repo_dir.join("envrc.template"),
+            "export BRANCH={{branch}} DIR={{worktree_name}} REPO={{repo}}\nexport PORT=${PORT:-3000}\n",
+        )
+        .unwrap();
+        std::fs::write(repo_dir.join("readme.template"), "{{branch}}\n").unwrap();
+        let settings = RepoSettings {
+            render: Some(BTreeMap::from([
+                (PathBuf::from(".envrc"), PathBuf::from("envrc.template")),
+                (
+                    PathBuf::from("config/local"),
+                    PathBuf::from("envrc.template"),
+                ),
+                // Tracked files are left alone
+                (PathBuf::from("README"), PathBuf::from("readme.template")),
+                (PathBuf::from("missing"), PathBuf::from("missing.template")),
+            ])),
+            ..Default::default()
+        };
+
+        settings.setup_worktree(&repo_dir, &worktree).unwrap();
+
+        let expected =
+            "export BRANCH=main DIR=main REPO=github.com/u/r\nexport PORT=${PORT:-3000}\n";
+        assert_eq!(
+            std::fs::read_to_string(worktree.join(".envrc")).unwrap(),
+            expected
+        );
+        assert_eq!(
+            std::fs::read_to_string(worktree.join("config/local")).unwrap(),
+            expected
+        );
+        assert_ne!(
+            std::fs::read_to_string(worktree.join("README")).unwrap(),
+            "main\n"
+        );
+        assert!(!worktree.join("missing").exists());
+
+        let escaping = RepoSettings {
+            render: Some(BTreeMap::from([(
+                PathBuf::from("../outside"),
+                PathBuf::from("envrc.template"),
+            )])),
+            ..Default::default()
+        };
+        assert!(escaping.setup_worktree(&repo_dir, &worktree).is_err());
+    }
+}
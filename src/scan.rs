@@ -0,0 +1,447 @@
+use crate::config::Root;
+use crate::ignore::IgnoreList;
+use crate::index;
+use anyhow::Result;
+use rayon::prelude::*;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use tracing::{debug, warn};
+
+static FOLLOW_SYMLINKS: AtomicBool = AtomicBool::new(false);
+
+/// Makes the walkers descend into symlinked directories for the rest of the
+/// process, as the global `--follow-symlinks` flag asks. By default they're
+/// skipped, so a link to `/` or to another root can't pull a foreign tree
+/// into the listing.
+pub fn follow_symlinks(follow: bool) {
+    FOLLOW_SYMLINKS.store(follow, Ordering::Relaxed);
+}
+
+/// Whether the walkers follow symlinked directories.
+pub fn follows_symlinks() -> bool {
+    FOLLOW_SYMLINKS.load(Ordering::Relaxed)
+}
+
+/// A repository found under a root at `<root>/<host>/<owner>/<repo>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RepoEntry {
+    pub host: String,
+    pub owner: String,
+    pub repo: String,
+    pub path: PathBuf,
+}
+
+impl RepoEntry {
+    /// Builds the entry for a repository directory laid out as
+    /// `<host>/<owner>/<repo>`.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let name = |path: &Path| Some(path.file_name()?.to_string_lossy().into_owned());
+        let owner_dir = path.parent()?;
+        Some(Self {
+            host: name(owner_dir.parent()?)?,
+            owner: name(owner_dir)?,
+            repo: name(path)?,
+            path: path.to_path_buf(),
+        })
+    }
+
+    /// Returns `host/owner/repo`.
+    pub fn spec(&self) -> String {
+        format!("{}/{}/{}", self.host, self.owner, self.repo)
+    }
+
+    /// Returns the root the repository lives under.
+    pub fn root(&self) -> Option<&Path> {
+        self.path.ancestors().nth(3)
+    }
+
+    pub fn bare_repository_path(&self) -> PathBuf {
+        self.path.join(".git")
+    }
+
+    /// Whether `.git` looks like a git repository, checked by its `HEAD`
+    /// and `config` files rather than by opening it. Leftovers of
+    /// interrupted removals and stray directories fail this.
+    pub fn is_repository(&self) -> bool {
+        let git_dir = self.bare_repository_path();
+        git_dir.join("HEAD").is_file() && git_dir.join("config").is_file()
+    }
+
+    /// Whether the repository was cloned with plain `git clone`, which has
+    /// a working tree instead of a bare repository with worktrees.
+    pub fn is_plain_clone(&self) -> bool {
+        git2::Repository::open_bare(self.bare_repository_path())
+            .and_then(|repo| repo.config()?.get_bool("core.bare"))
+            .is_ok_and(|bare| !bare)
+    }
+
+    /// Returns the worktree directories of the repository, sorted by path.
+    pub fn worktrees(&self) -> Result<Vec<PathBuf>> {
+        let mut worktrees = subdirectories(&self.path)?
+            .into_iter()
+            .filter(|path| path.file_name().unwrap() != ".git")
+            .collect::<Vec<_>>();
+        worktrees.sort();
+        Ok(worktrees)
+    }
+}
+
+/// Finds every repository under the given roots, in root order and sorted
+/// by path within each root, using each root's index when it's current.
+pub fn scan_roots(roots: &[Root]) -> Result<Vec<RepoEntry>> {
+    let mut entries = Vec::new();
+    for root in roots {
+        entries.extend(index::scan(&root.path)?);
+    }
+    Ok(entries)
+}
+
+/// Finds every repository under `root`. Directories below the root that
+/// can't be read are skipped with a warning rather than failing the scan.
+pub fn scan_root(root: &Path) -> Result<Vec<RepoEntry>> {
+    scan(root, &mut |path, error| {
+        if path == root {
+            return Err(error.into());
+        }
+        warn!("Skipping {}: {error}", path.display());
+        Ok(())
+    })
+}
+
+/// Finds every repository under `root` like [`scan_root`], but collects the
+/// directories that can't be read into `unreadable` instead of failing.
+pub fn scan_root_lossy(
+    root: &Path,
+    unreadable: &mut Vec<(PathBuf, std::io::Error)>,
+) -> Result<Vec<RepoEntry>> {
+    scan(root, &mut |path, error| {
+        unreadable.push((path.to_path_buf(), error));
+        Ok(())
+    })
+}
+
+/// Walks `<root>/<host>/<owner>/<repo>`, handing directories that can't be
+/// read to `on_unreadable`, which either fails the scan or skips them. Each
+/// level is read in parallel, since large roots have hundreds of owners.
+/// The walk never reads below the repository level, so even a symlink
+/// cycle followed with `--follow-symlinks` ends after three levels.
+fn scan(
+    root: &Path,
+    on_unreadable: &mut dyn FnMut(&Path, std::io::Error) -> Result<()>,
+) -> Result<Vec<RepoEntry>> {
+    if !root.exists() {
+        return Ok(Vec::new());
+    }
+
+    let ignore = IgnoreList::load(root)?;
+    let visible = |path: &PathBuf| !ignore.is_ignored(path.strip_prefix(root).unwrap_or(path));
+
+    // Hidden directories such as `.neoghq` hold neoghq's own data, not hosts
+    let host = |path: &PathBuf| !file_name(path).starts_with('.');
+
+    let hosts = read_level(&[root.to_path_buf()], on_unreadable)?
+        .into_iter()
+        .flat_map(|(_, hosts)| hosts)
+        .filter(visible)
+        .filter(host)
+        .collect::<Vec<_>>();
+    let owners = read_level(&hosts, on_unreadable)?
+        .into_iter()
+        .flat_map(|(_, owners)| owners)
+        .filter(visible)
+        .collect::<Vec<_>>();
+
+    let mut entries = Vec::new();
+    for (owner_path, repos) in read_level(&owners, on_unreadable)? {
+        for repo_path in repos.into_iter().filter(visible) {
+            entries.push(RepoEntry {
+                host: file_name(owner_path.parent().unwrap_or(&owner_path)),
+                owner: file_name(&owner_path),
+                repo: file_name(&repo_path),
+                path: repo_path,
+            });
+        }
+    }
+
+    entries.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(entries)
+}
+
+/// Reads the subdirectories of each of `parents` in parallel, pairing them
+/// with their parent in order. Failures go to `on_unreadable` in order too.
+fn read_level(
+    parents: &[PathBuf],
+    on_unreadable: &mut dyn FnMut(&Path, std::io::Error) -> Result<()>,
+) -> Result<Vec<(PathBuf, Vec<PathBuf>)>> {
+    let results = parents
+        .par_iter()
+        .map(|parent| subdirectories(parent))
+        .collect::<Vec<_>>();
+    let mut levels = Vec::with_capacity(parents.len());
+    for (parent, result) in parents.iter().zip(results) {
+        match result {
+            Ok(directories) => levels.push((parent.clone(), directories)),
+            Err(error) => on_unreadable(parent, error)?,
+        }
+    }
+    Ok(levels)
+}
+
+/// Returns the directories in `path`, including symlinked ones only when
+/// the walkers follow symlinks (see [`follow_symlinks`]).
+pub(crate) fn subdirectories(path: &Path) -> std::io::Result<Vec<PathBuf>> {
+    read_subdirectories(path, follows_symlinks())
+}
+
+fn read_subdirectories(path: &Path, follow_symlinks: bool) -> std::io::Result<Vec<PathBuf>> {
+    let mut directories = Vec::new();
+    for entry in std::fs::read_dir(path)? {
+        let entry = entry?;
+        let path = entry.path();
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            directories.push(path);
+        } else if file_type.is_symlink() && path.is_dir() {
+            if !follow_symlinks {
+                debug!("Skipping symlinked directory {}", path.display());
+            } else if links_to_ancestor(&path) {
+                warn!("Skipping {}: it links to its own parent", path.display());
+            } else {
+                directories.push(path);
+            }
+        }
+    }
+    Ok(directories)
+}
+
+/// Whether the symlink at `link` points at a directory containing it, which
+/// would list the same repositories again under another name.
+fn links_to_ancestor(link: &Path) -> bool {
+    let (Ok(target), Some(Ok(parent))) = (
+        std::fs::canonicalize(link),
+        link.parent().map(std::fs::canonicalize),
+    ) else {
+        return false;
+    };
+    parent.starts_with(target)
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name().unwrap().to_string_lossy().into_owned()
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use crate::{giturl, paths};
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_scan_root_with_structure() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let repo_path = root.join("github.com").join("user").join("repo");
+        fs::create_dir_all(repo_path.join("main")).unwrap();
+        fs::create_dir_all(repo_path.join("feature")).unwrap();
+        fs::create_dir_all(repo_path.join(".git")).unwrap();
+
+        let entries = scan_root(root).unwrap();
+
+        assert_eq!(
+            entries,
+            vec![RepoEntry {
+                host: "github.com".to_string(),
+                owner: "user".to_string(),
+                repo: "repo".to_string(),
+                path: repo_path.clone(),
+            }]
+        );
+        assert_eq!(entries[0].bare_repository_path(), repo_path.join(".git"));
+        assert_eq!(entries[0].root(), Some(root));
+        assert_eq!(
+            entries[0].worktrees().unwrap(),
+            vec![repo_path.join("feature"), repo_path.join("main")]
+        );
+    }
+
+    #[test]
+    fn test_scan_root_sorts_entries() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("github.com/b/repo")).unwrap();
+        fs::create_dir_all(root.join("github.com/a/repo")).unwrap();
+        fs::create_dir_all(root.join("codeberg.org/c/repo")).unwrap();
+
+        let specs = scan_root(root)
+            .unwrap()
+            .iter()
+            .map(|entry| format!("{}/{}/{}", entry.host, entry.owner, entry.repo))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            specs,
+            [
+                "codeberg.org/c/repo",
+                "github.com/a/repo",
+                "github.com/b/repo"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_scan_root_finds_nested_group_clone() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let url = giturl::parse("https://gitlab.com/group/subgroup/project.git").unwrap();
+        let repo_path = paths::repository_dir(root, &url.host, &url.owner, &url.repo);
+        fs::create_dir_all(repo_path.join(".git")).unwrap();
+        fs::create_dir_all(repo_path.join("main")).unwrap();
+
+        let entries = scan_root(root).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].path, repo_path);
+        assert_eq!(entries[0].repo, "project");
+        assert_eq!(paths::owner_from_dir(&entries[0].owner), "group/subgroup");
+        assert_eq!(entries[0].root(), Some(root));
+        assert_eq!(RepoEntry::from_path(&repo_path).as_ref(), Some(&entries[0]));
+
+        let config = crate::config::Config {
+            root: root.to_path_buf(),
+            ..Default::default()
+        };
+        for spec in ["gitlab.com/group/subgroup/project", &entries[0].spec()] {
+            assert_eq!(
+                crate::commands::repo::switch::find_repository_path(&config, spec).unwrap(),
+                Some(repo_path.clone()),
+                "{spec}"
+            );
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_read_subdirectories_symlinks() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("root");
+        let elsewhere = temp_dir.path().join("elsewhere");
+        fs::create_dir_all(root.join("github.com")).unwrap();
+        fs::create_dir_all(&elsewhere).unwrap();
+        std::os::unix::fs::symlink(&elsewhere, root.join("linked.example.com")).unwrap();
+        std::os::unix::fs::symlink(&root, root.join("loop")).unwrap();
+        std::os::unix::fs::symlink(root.join("missing"), root.join("dangling")).unwrap();
+
+        let names = |follow: bool| {
+            let mut names = read_subdirectories(&root, follow)
+                .unwrap()
+                .iter()
+                .map(|path| file_name(path))
+                .collect::<Vec<_>>();
+            names.sort();
+            names
+        };
+        assert_eq!(names(false), ["github.com"]);
+        // A link back to the root is skipped even when following
+        assert_eq!(names(true), ["github.com", "linked.example.com"]);
+    }
+
+    #[test]
+    fn test_scan_root_ignores_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::write(root.join("somefile.txt"), "content").unwrap();
+        fs::create_dir_all(root.join("github.com/user")).unwrap();
+        fs::write(root.join("github.com/somefile.txt"), "content").unwrap();
+        fs::write(root.join("github.com/user/somefile.txt"), "content").unwrap();
+
+        assert!(scan_root(root).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_scan_root_honors_ignore_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("github.com/user/repo")).unwrap();
+        fs::create_dir_all(root.join("github.com/mirrors/linux")).unwrap();
+        fs::create_dir_all(root.join("datasets/raw/images")).unwrap();
+        fs::write(root.join(".neoghqignore"), "datasets\ngithub.com/mirrors\n").unwrap();
+
+        let entries = scan_root(root).unwrap();
+
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].path, root.join("github.com/user/repo"));
+    }
+
+    #[test]
+    fn test_scan_root_skips_hidden_directories() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join(".neoghq/workspaces/services")).unwrap();
+        fs::create_dir_all(root.join("github.com/user/repo")).unwrap();
+
+        let entries = scan_root(root).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].host, "github.com");
+    }
+
+    #[test]
+    fn test_scan_root_with_nonexistent_root() {
+        let temp_dir = TempDir::new().unwrap();
+
+        let entries = scan_root(&temp_dir.path().join("nonexistent")).unwrap();
+        assert!(entries.is_empty());
+    }
+
+    #[test]
+    fn test_subdirectories_with_read_error() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("notadirectory");
+        fs::write(&file_path, "content").unwrap();
+
+        assert!(subdirectories(&file_path).is_err());
+    }
+
+    #[test]
+    fn test_scan_root_lossy_collects_unreadable_directories() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("notadirectory");
+        fs::write(&file_path, "content").unwrap();
+
+        assert!(scan_root(&file_path).is_err());
+        let mut unreadable = Vec::new();
+        assert!(
+            scan_root_lossy(&file_path, &mut unreadable)
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(unreadable.len(), 1);
+        assert_eq!(unreadable[0].0, file_path);
+    }
+
+    #[test]
+    fn test_scan_roots() {
+        let personal = TempDir::new().unwrap();
+        let work = TempDir::new().unwrap();
+        fs::create_dir_all(personal.path().join("github.com/user/repo")).unwrap();
+        fs::create_dir_all(work.path().join("gitlab.com/team/service")).unwrap();
+        let roots = [
+            Root {
+                name: "default".to_string(),
+                path: personal.path().to_path_buf(),
+            },
+            Root {
+                name: "work".to_string(),
+                path: work.path().to_path_buf(),
+            },
+        ];
+
+        let specs = scan_roots(&roots)
+            .unwrap()
+            .iter()
+            .map(|entry| format!("{}/{}/{}", entry.host, entry.owner, entry.repo))
+            .collect::<Vec<_>>();
+
+        assert_eq!(specs, ["github.com/user/repo", "gitlab.com/team/service"]);
+    }
+}
@@ -0,0 +1,162 @@
+use crate::{
+    config::Config,
+    ignore::glob_match,
+    scan::{self, RepoEntry},
+    status,
+};
+use anyhow::Result;
+use git2::Repository;
+use std::path::{Path, PathBuf};
+
+/// Which worktrees of each repository `grep` and `find` search.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WorktreeFilter {
+    /// The worktree of the default branch, or the first worktree if the
+    /// default branch isn't checked out.
+    Default,
+    /// Every worktree.
+    All,
+    /// The worktree with this directory name.
+    Named(String),
+}
+
+impl WorktreeFilter {
+    /// Parses `default`, `all` or a worktree directory name.
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "default" => Self::Default,
+            "all" => Self::All,
+            name => Self::Named(name.to_string()),
+        }
+    }
+}
+
+/// A worktree to search and the repository it belongs to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Target {
+    pub repo: String,
+    pub worktree: PathBuf,
+}
+
+/// Returns the worktrees selected by `worktree` of every repository whose
+/// `host/owner/repo` matches the `repo` glob, in repository order.
+pub fn targets(
+    config: &Config,
+    repo: Option<&str>,
+    worktree: &WorktreeFilter,
+) -> Result<Vec<Target>> {
+    let mut targets = Vec::new();
+    for entry in scan::scan_roots(&config.all_roots())? {
+        let spec = entry.spec();
+        if !repo.is_none_or(|repo| glob_match(repo, &spec) || spec.ends_with(repo)) {
+            continue;
+        }
+        targets.extend(
+            select_worktrees(&entry, worktree)?
+                .into_iter()
+                .map(|worktree| Target {
+                    repo: spec.clone(),
+                    worktree,
+                }),
+        );
+    }
+    Ok(targets)
+}
+
+/// Returns the worktrees of `entry` selected by `filter`.
+pub fn select_worktrees(entry: &RepoEntry, filter: &WorktreeFilter) -> Result<Vec<PathBuf>> {
+    let worktrees = entry.worktrees()?;
+    let named = |name: &str| {
+        worktrees
+            .iter()
+            .find(|path| path.file_name().is_some_and(|file_name| file_name == name))
+            .cloned()
+    };
+    Ok(match filter {
+        WorktreeFilter::All => worktrees,
+        WorktreeFilter::Named(name) => named(name).into_iter().collect(),
+        WorktreeFilter::Default => Repository::open_bare(entry.bare_repository_path())
+            .ok()
+            .and_then(|repo| status::default_branch(&repo))
+            .and_then(|branch| named(&branch))
+            .or_else(|| worktrees.first().cloned())
+            .into_iter()
+            .collect(),
+    })
+}
+
+/// Returns the files of `worktree` that git doesn't ignore, including
+/// hidden ones, sorted by path.
+pub fn files(worktree: &Path) -> Vec<PathBuf> {
+    let mut files = ::ignore::WalkBuilder::new(worktree)
+        .hidden(false)
+        .require_git(false)
+        .filter_entry(|entry| entry.file_name() != ".git")
+        .build()
+        .flatten()
+        .filter(|entry| {
+            entry
+                .file_type()
+                .is_some_and(|file_type| file_type.is_file())
+        })
+        .map(::ignore::DirEntry::into_path)
+        .collect::<Vec<_>>();
+    files.sort();
+    files
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_managed_repository;
+    use std::fs;
+
+    #[test]
+    fn test_targets() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo_path = temp_dir.path().join("github.com/user/repo");
+        create_managed_repository(&repo_path);
+        fs::create_dir_all(repo_path.join("feature")).unwrap();
+        create_managed_repository(&temp_dir.path().join("github.com/user/other"));
+        let config = Config {
+            root: temp_dir.path().to_path_buf(),
+            ..Default::default()
+        };
+
+        let targets = targets(&config, Some("user/repo"), &WorktreeFilter::Default).unwrap();
+        assert_eq!(
+            targets,
+            [Target {
+                repo: "github.com/user/repo".to_string(),
+                worktree: repo_path.join("main"),
+            }]
+        );
+
+        let all = super::targets(&config, Some("*/user/*"), &WorktreeFilter::All).unwrap();
+        assert_eq!(all.len(), 3);
+
+        let named = WorktreeFilter::parse("feature");
+        assert_eq!(named, WorktreeFilter::Named("feature".to_string()));
+        let named = super::targets(&config, None, &named).unwrap();
+        assert_eq!(named.len(), 1);
+        assert_eq!(named[0].worktree, repo_path.join("feature"));
+    }
+
+    #[test]
+    fn test_files() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let worktree = temp_dir.path();
+        git2::Repository::init(worktree).unwrap();
+        fs::write(worktree.join(".gitignore"), "target\n").unwrap();
+        fs::create_dir_all(worktree.join("src")).unwrap();
+        fs::create_dir_all(worktree.join("target")).unwrap();
+        fs::write(worktree.join("src/lib.rs"), "").unwrap();
+        fs::write(worktree.join("target/out"), "").unwrap();
+
+        assert_eq!(
+            files(worktree),
+            [worktree.join(".gitignore"), worktree.join("src/lib.rs")]
+        );
+    }
+}
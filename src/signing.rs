@@ -0,0 +1,351 @@
+use anyhow::{Context, Result, anyhow};
+use git2::{Oid, Repository, Signature, Tree};
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+/// Creates a commit object as `git commit` would, signed when
+/// `commit.gpgSign` is enabled, without updating any reference. Every
+/// commit neoghq makes goes through here so signing policies apply to all
+/// of them.
+pub fn commit(
+    repo: &Repository,
+    signature: &Signature,
+    message: &str,
+    tree: &Tree,
+    parents: &[&git2::Commit],
+) -> Result<Oid> {
+    let buffer = repo.commit_create_buffer(signature, signature, message, tree, parents)?;
+    let buffer = std::str::from_utf8(&buffer)?;
+    Ok(match sign_commit(&repo.config()?, buffer)? {
+        Some(gpg_signature) => repo.commit_signed(buffer, &gpg_signature, None)?,
+        None => repo.commit(None, signature, signature, message, tree, parents)?,
+    })
+}
+
+/// Signs a commit buffer as `git commit -S` would, following the
+/// `commit.gpgSign`, `gpg.format`, `gpg.program`, `user.signingKey` and
+/// `gpg.ssh.defaultKeyCommand` settings. Returns `None` when commit signing
+/// is disabled.
+pub fn sign_commit(config: &git2::Config, buffer: &str) -> Result<Option<String>> {
+    if !config.get_bool("commit.gpgSign").unwrap_or(false) {
+        return Ok(None);
+    }
+
+    let format = config
+        .get_string("gpg.format")
+        .unwrap_or_else(|_| "openpgp".to_string());
+    let key = config.get_string("user.signingKey").ok();
+
+    // Removed once the signing program is done with it
+    let mut literal_key_file = None;
+    let mut command = match format.as_str() {
+        "openpgp" | "x509" => {
+            let (program_key, default_program) = if format == "x509" {
+                ("gpg.x509.program", "gpgsm")
+            } else {
+                ("gpg.program", "gpg")
+            };
+            let program = config
+                .get_string(program_key)
+                .unwrap_or_else(|_| default_program.to_string());
+            let mut command = Command::new(program);
+            command.args(["--status-fd=2", "-bsa"]);
+            // Like git, fall back to the committer's identity
+            if let Some(key) = key.or_else(|| committer_ident(config)) {
+                command.args(["-u", &key]);
+            }
+            command
+        }
+        "ssh" => {
+            let key = match key {
+                Some(key) => key,
+                None => default_ssh_key(config)?,
+            };
+            let program = config
+                .get_string("gpg.ssh.program")
+                .unwrap_or_else(|_| "ssh-keygen".to_string());
+            let mut command = Command::new(program);
+            command.args(["-Y", "sign", "-n", "git"]);
+            match literal_ssh_key(&key) {
+                // The private key of a public key given literally is in ssh-agent
+                Some(public_key) => {
+                    let path = write_key_file(public_key)?;
+                    command.arg("-U").arg("-f").arg(&path);
+                    literal_key_file = Some(path);
+                }
+                None => {
+                    command.arg("-f").arg(expand_tilde(&key));
+                }
+            }
+            command
+        }
+        format => return Err(anyhow!("Unsupported gpg.format: {format}")),
+    };
+
+    let output = run_signing_program(&mut command, buffer);
+    if let Some(path) = literal_key_file {
+        let _ = std::fs::remove_file(path);
+    }
+    let output = output?;
+
+    if !output.status.success() || output.stdout.is_empty() {
+        return Err(anyhow!(
+            "Failed to sign the commit: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(Some(String::from_utf8(output.stdout)?))
+}
+
+fn run_signing_program(command: &mut Command, buffer: &str) -> Result<std::process::Output> {
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to start the commit signing program")?;
+    child.stdin.take().unwrap().write_all(buffer.as_bytes())?;
+    Ok(child.wait_with_output()?)
+}
+
+/// Returns `Name <email>` from `user.name` and `user.email`.
+fn committer_ident(config: &git2::Config) -> Option<String> {
+    let name = config.get_string("user.name").ok()?;
+    let email = config.get_string("user.email").ok()?;
+    Some(format!("{name} <{email}>"))
+}
+
+/// Runs `gpg.ssh.defaultKeyCommand` for the key to sign with when
+/// `user.signingKey` isn't set; its first line is the key.
+fn default_ssh_key(config: &git2::Config) -> Result<String> {
+    let command = config
+        .get_string("gpg.ssh.defaultKeyCommand")
+        .map_err(|_| {
+            anyhow!("user.signingKey or gpg.ssh.defaultKeyCommand is required for SSH signing")
+        })?;
+    let output = Command::new("sh")
+        .args(["-c", &command])
+        .stderr(Stdio::inherit())
+        .output()
+        .with_context(|| format!("Failed to run gpg.ssh.defaultKeyCommand: {command}"))?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    match stdout.lines().next().map(str::trim) {
+        Some(key) if output.status.success() && !key.is_empty() => Ok(key.to_string()),
+        _ => Err(anyhow!(
+            "gpg.ssh.defaultKeyCommand printed no key: {command}"
+        )),
+    }
+}
+
+/// Returns the public key when `key` is one given literally, as
+/// `key::ssh-ed25519 AAAA…` or `ssh-ed25519 AAAA…`, rather than a path.
+fn literal_ssh_key(key: &str) -> Option<&str> {
+    if let Some(key) = key.strip_prefix("key::") {
+        return Some(key);
+    }
+    ["ssh-", "ecdsa-", "sk-"]
+        .iter()
+        .any(|prefix| key.starts_with(prefix))
+        .then_some(key)
+}
+
+/// Writes a literal public key where `ssh-keygen -f` can read it.
+fn write_key_file(public_key: &str) -> Result<PathBuf> {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    let path = std::env::temp_dir().join(format!(
+        ".neoghq-signing-key-{}-{nanos}.pub",
+        std::process::id()
+    ));
+    std::fs::write(&path, format!("{public_key}\n"))
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+fn expand_tilde(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+
+    fn open_gitconfig(temp_dir: &std::path::Path, content: &str) -> git2::Config {
+        let path = temp_dir.join("gitconfig");
+        std::fs::write(&path, content).unwrap();
+        git2::Config::open(&path).unwrap()
+    }
+
+    #[test]
+    fn test_sign_commit_disabled() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config = open_gitconfig(temp_dir.path(), "");
+
+        assert_eq!(sign_commit(&config, "tree 0\n").unwrap(), None);
+    }
+
+    #[test]
+    fn test_sign_commit_with_program() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let program = temp_dir.path().join("fake-gpg");
+        std::fs::write(
+            &program,
+            "#!/bin/sh\ncat > /dev/null\necho \"signed by $4\"\n",
+        )
+        .unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&program, std::fs::Permissions::from_mode(0o755)).unwrap();
+        }
+        let config = open_gitconfig(
+            temp_dir.path(),
+            &format!(
+                "[commit]\n\tgpgSign = true\n[user]\n\tsigningKey = ABC123\n[gpg]\n\tprogram = {}\n",
+                program.display()
+            ),
+        );
+
+        let signature = sign_commit(&config, "tree 0\n").unwrap();
+        assert_eq!(signature.as_deref(), Some("signed by ABC123\n"));
+    }
+
+    /// Writes an executable script that prints its arguments as the signature.
+    fn fake_program(dir: &std::path::Path) -> std::path::PathBuf {
+        let program = dir.join("fake-signer");
+        std::fs::write(&program, "#!/bin/sh\ncat > /dev/null\necho \"$@\"\n").unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&program, std::fs::Permissions::from_mode(0o755)).unwrap();
+        }
+        program
+    }
+
+    #[test]
+    fn test_sign_commit_defaults_to_committer_ident() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let program = fake_program(temp_dir.path());
+        let config = open_gitconfig(
+            temp_dir.path(),
+            &format!(
+                "[commit]\n\tgpgSign = true\n[user]\n\tname = Jane Doe\n\temail = jane@example.com\n[gpg]\n\tprogram = {}\n",
+                program.display()
+            ),
+        );
+
+        let signature = sign_commit(&config, "tree 0\n").unwrap().unwrap();
+        assert_eq!(
+            signature,
+            "--status-fd=2 -bsa -u Jane Doe <jane@example.com>\n"
+        );
+    }
+
+    #[test]
+    fn test_sign_commit_ssh_keys() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let program = fake_program(temp_dir.path());
+        let ssh_config = |key: &str| {
+            open_gitconfig(
+                temp_dir.path(),
+                &format!(
+                    "[commit]\n\tgpgSign = true\n[gpg]\n\tformat = ssh\n[gpg \"ssh\"]\n\tprogram = {}\n{key}",
+                    program.display()
+                ),
+            )
+        };
+
+        let config = ssh_config("[user]\n\tsigningKey = /keys/id_ed25519\n");
+        assert_eq!(
+            sign_commit(&config, "tree 0\n").unwrap().unwrap(),
+            "-Y sign -n git -f /keys/id_ed25519\n"
+        );
+
+        // A literal public key is written to a file, signed with the agent's key
+        let config = ssh_config("[user]\n\tsigningKey = key::ssh-ed25519 AAAA\n");
+        let signature = sign_commit(&config, "tree 0\n").unwrap().unwrap();
+        let key_file = signature
+            .trim()
+            .strip_prefix("-Y sign -n git -U -f ")
+            .unwrap();
+        assert!(!std::path::Path::new(key_file).exists());
+
+        let config = ssh_config("\tdefaultKeyCommand = echo ssh-ed25519 BBBB\n");
+        let signature = sign_commit(&config, "tree 0\n").unwrap().unwrap();
+        assert!(
+            signature.starts_with("-Y sign -n git -U -f "),
+            "{signature}"
+        );
+    }
+
+    #[test]
+    fn test_literal_ssh_key() {
+        assert_eq!(
+            literal_ssh_key("key::ssh-ed25519 AAAA"),
+            Some("ssh-ed25519 AAAA")
+        );
+        assert_eq!(
+            literal_ssh_key("ecdsa-sha2-nistp256 AAAA"),
+            Some("ecdsa-sha2-nistp256 AAAA")
+        );
+        assert_eq!(literal_ssh_key("~/.ssh/id_ed25519.pub"), None);
+    }
+
+    #[test]
+    fn test_commit_signed() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let program = fake_program(temp_dir.path());
+        let repo = Repository::init(temp_dir.path().join("repo")).unwrap();
+        let mut config = repo
+            .config()
+            .unwrap()
+            .open_level(git2::ConfigLevel::Local)
+            .unwrap();
+        config.set_bool("commit.gpgSign", true).unwrap();
+        config
+            .set_str("gpg.program", program.to_str().unwrap())
+            .unwrap();
+        config.set_str("user.signingKey", "ABC123").unwrap();
+        let signature = Signature::now("Jane Doe", "jane@example.com").unwrap();
+        let tree = repo
+            .find_tree(repo.index().unwrap().write_tree().unwrap())
+            .unwrap();
+
+        let oid = commit(&repo, &signature, "Initial commit", &tree, &[]).unwrap();
+
+        let (gpg_signature, _) = repo.extract_signature(&oid, None).unwrap();
+        assert_eq!(
+            gpg_signature.as_str(),
+            Some("--status-fd=2 -bsa -u ABC123\n")
+        );
+    }
+
+    #[test]
+    fn test_sign_commit_with_failing_program() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config = open_gitconfig(
+            temp_dir.path(),
+            "[commit]\n\tgpgSign = true\n[gpg]\n\tprogram = false\n",
+        );
+
+        assert!(sign_commit(&config, "tree 0\n").is_err());
+    }
+
+    #[test]
+    fn test_sign_commit_ssh_requires_key() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config = open_gitconfig(
+            temp_dir.path(),
+            "[commit]\n\tgpgSign = true\n[gpg]\n\tformat = ssh\n",
+        );
+
+        assert!(sign_commit(&config, "tree 0\n").is_err());
+    }
+}
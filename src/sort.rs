@@ -0,0 +1,96 @@
+use crate::{cli::SortKey, config::Config, history::History, status};
+use std::cmp::Reverse;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// Sorts listing entries by `key`. `path` gives the directory of an entry
+/// and `name` the name it is listed under, which also breaks ties.
+///
+/// Every key except `name` puts the largest value first: the most recently
+/// modified, the most frecent (switched to often and lately), or the
+/// biggest on disk.
+pub fn sort_entries<T>(
+    entries: &mut [T],
+    key: SortKey,
+    config: &Config,
+    path: impl Fn(&T) -> &Path,
+    name: impl Fn(&T) -> String,
+) {
+    let history = match key {
+        SortKey::Recent => History::load(config),
+        _ => History::default(),
+    };
+    let primary = |entry: &T| match key {
+        SortKey::Name => 0,
+        SortKey::Mtime => modified(path(entry)),
+        SortKey::Recent => history.frecency(path(entry)),
+        SortKey::Size => status::disk_usage(path(entry)),
+    };
+
+    entries.sort_by_cached_key(|entry| (Reverse(primary(entry)), name(entry)));
+}
+
+/// Moves the entries whose directory is in `pinned` to the front, keeping
+/// the order within pinned and unpinned entries.
+pub fn pinned_first<T>(entries: &mut [T], pinned: &HashSet<PathBuf>, path: impl Fn(&T) -> &Path) {
+    entries.sort_by_key(|entry| !pinned.contains(path(entry)));
+}
+
+/// Returns when `path` was last modified, in seconds since the epoch.
+fn modified(path: &Path) -> u64 {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |duration| duration.as_secs())
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+
+    fn sorted(key: SortKey, config: &Config, paths: &[PathBuf]) -> Vec<String> {
+        let mut entries = paths.to_vec();
+        sort_entries(
+            &mut entries,
+            key,
+            config,
+            |path| path,
+            |path| path.file_name().unwrap().to_string_lossy().into_owned(),
+        );
+        entries
+            .iter()
+            .map(|path| path.file_name().unwrap().to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn test_sort_entries() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let paths = ["b", "a", "c"].map(|name| temp_dir.path().join(name));
+        for (path, size) in paths.iter().zip([10, 30, 20]) {
+            std::fs::create_dir_all(path).unwrap();
+            std::fs::write(path.join("file"), vec![0; size]).unwrap();
+        }
+        let config = Config {
+            cache_dir: Some(temp_dir.path().join("cache")),
+            ..Default::default()
+        };
+        History::record(&config, &paths[2]).unwrap();
+
+        assert_eq!(sorted(SortKey::Name, &config, &paths), ["a", "b", "c"]);
+        assert_eq!(sorted(SortKey::Size, &config, &paths), ["a", "c", "b"]);
+        assert_eq!(sorted(SortKey::Recent, &config, &paths), ["c", "a", "b"]);
+        assert_eq!(sorted(SortKey::Mtime, &config, &paths).len(), 3);
+    }
+
+    #[test]
+    fn test_pinned_first() {
+        let mut paths = ["a", "b", "c", "d"].map(PathBuf::from).to_vec();
+        let pinned = HashSet::from([PathBuf::from("c"), PathBuf::from("a")]);
+        pinned_first(&mut paths, &pinned, |path| path);
+        assert_eq!(paths, ["a", "c", "b", "d"].map(PathBuf::from));
+    }
+}
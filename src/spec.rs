@@ -0,0 +1,481 @@
+use crate::{
+    config::{CloneProtocol, Config, HostAlias},
+    giturl, paths,
+};
+use anyhow::{Result, anyhow};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Aliases available without any configuration.
+const BUILTIN_ALIASES: &[(&str, &str)] = &[
+    ("gh", "github.com"),
+    ("gl", "gitlab.com"),
+    ("bb", "bitbucket.org"),
+];
+
+/// A repository spec whose alias prefix has been expanded to a host.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AliasedSpec {
+    pub host: String,
+    pub protocol: CloneProtocol,
+    /// The `owner/repo` part of the spec.
+    pub path: String,
+}
+
+/// Expands `alias:owner/repo` using the configured or built-in aliases.
+pub fn expand_alias(config: &Config, spec: &str) -> Option<AliasedSpec> {
+    let (alias, path) = spec.split_once(':')?;
+    if path.starts_with("//") || path.is_empty() {
+        return None;
+    }
+
+    let alias = config.aliases.get(alias).cloned().or_else(|| {
+        BUILTIN_ALIASES
+            .iter()
+            .find(|(name, _)| *name == alias)
+            .map(|(_, host)| HostAlias {
+                host: host.to_string(),
+                protocol: None,
+            })
+    })?;
+
+    let protocol = match (config.protocol_override, alias.protocol) {
+        (Some(protocol), _) | (None, Some(protocol)) => protocol,
+        (None, None) => config.clone_protocol_for(&alias.host),
+    };
+    Some(AliasedSpec {
+        host: alias.host,
+        protocol,
+        path: path.trim_matches('/').to_string(),
+    })
+}
+
+/// A repository as named on the command line: `host/owner/repo`, or
+/// `owner/repo` when the host is left to search for, parsed from a URL, an
+/// alias spec or a path. Hosts are lowercased without `www.`, and trailing
+/// slashes and `.git` suffixes are dropped, so every spelling of a
+/// repository names the same directory.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RepoSpec {
+    pub host: Option<String>,
+    /// The owner of the repository. Nested groups are joined with `/`.
+    pub owner: String,
+    pub repo: String,
+}
+
+impl RepoSpec {
+    /// Parses `input`. A lone `repo` belongs to `general.user`, and the
+    /// first of three or more segments is a host when it contains a dot or
+    /// a port, or is `localhost`.
+    pub fn parse(config: &Config, input: &str) -> Result<Self> {
+        let input = input.trim();
+        if let Some(aliased) = expand_alias(config, input) {
+            let (owner, repo) = split_path(&aliased.path).ok_or_else(|| {
+                anyhow!("Invalid repository spec: {input} (expected alias:owner/repo)")
+            })?;
+            return Ok(Self {
+                host: Some(giturl::normalize_host(&aliased.host)),
+                owner,
+                repo,
+            });
+        }
+        if is_url(input) {
+            let url = giturl::parse(input)?;
+            return Ok(Self {
+                host: Some(url.host),
+                owner: url.owner,
+                repo: url.repo,
+            });
+        }
+
+        let path = input.trim_matches('/');
+        let path = path.strip_suffix(".git").unwrap_or(path);
+        let mut segments = path.split('/').collect::<Vec<_>>();
+        if !segments
+            .iter()
+            .all(|segment| giturl::is_path_segment(segment))
+        {
+            return Err(anyhow!("Invalid repository spec: {input:?}"));
+        }
+        let host = match segments.first() {
+            Some(first) if segments.len() >= 3 && is_host(first) => {
+                Some(giturl::normalize_host(segments.remove(0)))
+            }
+            _ => None,
+        };
+        match segments.as_slice() {
+            [] => Err(anyhow!("Invalid repository spec: {input:?}")),
+            [repo] => match config.user.as_deref() {
+                Some(user) => Ok(Self {
+                    host,
+                    owner: user.to_string(),
+                    repo: repo.to_string(),
+                }),
+                None => Err(anyhow!(
+                    "Invalid repository format: {input} (expected owner/repo)"
+                )),
+            },
+            [owner @ .., repo] => Ok(Self {
+                host,
+                owner: owner.join("/"),
+                repo: repo.to_string(),
+            }),
+        }
+    }
+
+    /// Returns `owner/repo`.
+    pub fn path(&self) -> String {
+        format!("{}/{}", self.owner, self.repo)
+    }
+
+    /// Returns the repository directory under `root`, if the host is known.
+    pub fn dir(&self, root: &Path) -> Option<PathBuf> {
+        Some(
+            root.join(self.host.as_ref()?)
+                .join(paths::owner_dir(&self.owner))
+                .join(&self.repo),
+        )
+    }
+}
+
+impl fmt::Display for RepoSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.host {
+            Some(host) => write!(f, "{host}/{}", self.path()),
+            None => f.write_str(&self.path()),
+        }
+    }
+}
+
+/// Whether `input` is a remote URL (`https://...`, `git@host:owner/repo`)
+/// rather than a spec.
+fn is_url(input: &str) -> bool {
+    input.contains("://")
+        || input
+            .split_once(':')
+            .is_some_and(|(authority, _)| authority.contains('@') || authority.contains('.'))
+}
+
+/// Whether `segment` is a hostname with a dot or a port, or `localhost`.
+fn is_host(segment: &str) -> bool {
+    let (host, port) = match segment.split_once(':') {
+        Some((host, port)) => (host, Some(port)),
+        None => (segment, None),
+    };
+    if port.is_some_and(|port| port.parse::<u16>().is_err()) || !giturl::is_hostname(host) {
+        return false;
+    }
+    port.is_some() || host.contains('.') || host == "localhost"
+}
+
+fn split_path(path: &str) -> Option<(String, String)> {
+    let path = path.trim_matches('/');
+    let path = path.strip_suffix(".git").unwrap_or(path);
+    let (owner, repo) = path.rsplit_once('/')?;
+    owner
+        .split('/')
+        .chain([repo])
+        .all(giturl::is_path_segment)
+        .then(|| (owner.to_string(), repo.to_string()))
+}
+
+/// Turns a repository spec into a clone URL: URLs are returned unchanged,
+/// aliases expand to their host, and other specs use `general.default_host`
+/// (github.com without one). The protocol is the alias's, else the one
+/// [`Config::clone_protocol_for`] picks for the host.
+pub fn resolve_url(config: &Config, spec: &str) -> Result<String> {
+    if let Some(aliased) = expand_alias(config, spec) {
+        return Ok(build_url(&aliased.host, &aliased.path, aliased.protocol));
+    }
+    if is_url(spec.trim()) {
+        return Ok(spec.to_string());
+    }
+    let parsed = RepoSpec::parse(config, spec)?;
+    let host = parsed
+        .host
+        .as_deref()
+        .or(config.default_host.as_deref())
+        .unwrap_or(DEFAULT_HOST);
+    Ok(build_url(
+        host,
+        &parsed.path(),
+        config.clone_protocol_for(host),
+    ))
+}
+
+/// Host of specs given without one when `general.default_host` isn't set.
+const DEFAULT_HOST: &str = "github.com";
+
+/// Builds a clone URL for `owner/repo` on `host`.
+pub fn build_url(host: &str, path: &str, protocol: CloneProtocol) -> String {
+    match protocol {
+        CloneProtocol::Https => format!("https://{host}/{path}"),
+        CloneProtocol::Ssh => format!("git@{host}:{path}.git"),
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use crate::config::HostConfig;
+
+    fn create_config() -> Config {
+        Config {
+            aliases: [(
+                "work".to_string(),
+                HostAlias {
+                    host: "git.company.com".to_string(),
+                    protocol: Some(CloneProtocol::Ssh),
+                },
+            )]
+            .into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_expand_builtin_alias() {
+        let config = create_config();
+
+        assert_eq!(
+            expand_alias(&config, "gl:group/proj"),
+            Some(AliasedSpec {
+                host: "gitlab.com".to_string(),
+                protocol: CloneProtocol::Https,
+                path: "group/proj".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_expand_configured_alias() {
+        let config = create_config();
+
+        assert_eq!(
+            expand_alias(&config, "work:team/repo"),
+            Some(AliasedSpec {
+                host: "git.company.com".to_string(),
+                protocol: CloneProtocol::Ssh,
+                path: "team/repo".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_configured_alias_overrides_builtin() {
+        let mut config = create_config();
+        config.aliases.insert(
+            "gh".to_string(),
+            HostAlias {
+                host: "github.company.com".to_string(),
+                protocol: None,
+            },
+        );
+
+        let aliased = expand_alias(&config, "gh:user/repo").unwrap();
+        assert_eq!(aliased.host, "github.company.com");
+    }
+
+    #[test]
+    fn test_expand_alias_ignores_non_alias_specs() {
+        let config = create_config();
+
+        assert_eq!(expand_alias(&config, "user/repo"), None);
+        assert_eq!(expand_alias(&config, "git@github.com:user/repo.git"), None);
+        assert_eq!(expand_alias(&config, "https://github.com/user/repo"), None);
+        assert_eq!(expand_alias(&config, "unknown:user/repo"), None);
+        assert_eq!(expand_alias(&config, "gl:"), None);
+    }
+
+    #[test]
+    fn test_resolve_url() {
+        let config = create_config();
+
+        let resolve = |spec: &str| resolve_url(&config, spec).unwrap();
+
+        assert_eq!(
+            resolve("gl:group/sub/proj"),
+            "https://gitlab.com/group/sub/proj"
+        );
+        assert_eq!(
+            resolve("work:team/repo"),
+            "git@git.company.com:team/repo.git"
+        );
+        assert_eq!(
+            resolve("https://github.com/user/repo"),
+            "https://github.com/user/repo"
+        );
+        assert_eq!(resolve("user/repo"), "https://github.com/user/repo");
+        assert_eq!(
+            resolve("GitLab.com/group/proj.git"),
+            "https://gitlab.com/group/proj"
+        );
+        assert!(resolve_url(&config, "repo").is_err());
+    }
+
+    #[test]
+    fn test_resolve_url_uses_configured_host_and_protocol() {
+        let config = Config {
+            default_host: Some("git.company.com".to_string()),
+            clone_protocol: CloneProtocol::Ssh,
+            user: Some("me".to_string()),
+            ..create_config()
+        };
+
+        assert_eq!(
+            resolve_url(&config, "repo").unwrap(),
+            "git@git.company.com:me/repo.git"
+        );
+        assert_eq!(
+            resolve_url(&config, "github.com/user/repo").unwrap(),
+            "git@github.com:user/repo.git"
+        );
+    }
+
+    #[test]
+    fn test_resolve_url_uses_host_protocol_and_override() {
+        let config = Config {
+            hosts: [(
+                "gitlab.com".to_string(),
+                HostConfig {
+                    protocol: Some(CloneProtocol::Ssh),
+                    ..Default::default()
+                },
+            )]
+            .into(),
+            ..create_config()
+        };
+
+        assert_eq!(
+            resolve_url(&config, "gitlab.com/group/proj").unwrap(),
+            "git@gitlab.com:group/proj.git"
+        );
+        assert_eq!(
+            resolve_url(&config, "gl:group/proj").unwrap(),
+            "git@gitlab.com:group/proj.git"
+        );
+        assert_eq!(
+            resolve_url(&config, "user/repo").unwrap(),
+            "https://github.com/user/repo"
+        );
+
+        let config = Config {
+            protocol_override: Some(CloneProtocol::Https),
+            ..config
+        };
+        assert_eq!(
+            resolve_url(&config, "work:team/repo").unwrap(),
+            "https://git.company.com/team/repo"
+        );
+        assert_eq!(
+            resolve_url(&config, "gitlab.com/group/proj").unwrap(),
+            "https://gitlab.com/group/proj"
+        );
+    }
+
+    #[test]
+    fn test_repo_spec_parse() {
+        let config = create_config();
+        let parse = |input: &str| RepoSpec::parse(&config, input).unwrap().to_string();
+
+        assert_eq!(parse("user/repo"), "user/repo");
+        assert_eq!(parse("/user/repo.git/"), "user/repo");
+        assert_eq!(parse("github.com/user/repo"), "github.com/user/repo");
+        assert_eq!(parse("WWW.GitHub.com/user/repo"), "github.com/user/repo");
+        assert_eq!(parse("localhost/user/repo"), "localhost/user/repo");
+        assert_eq!(parse("group/sub/proj"), "group/sub/proj");
+        assert_eq!(
+            parse("gitlab.com/group/sub/proj"),
+            "gitlab.com/group/sub/proj"
+        );
+        assert_eq!(parse("gl:group/proj"), "gitlab.com/group/proj");
+        assert_eq!(parse("work:team/repo.git"), "git.company.com/team/repo");
+        assert_eq!(
+            parse("https://www.github.com/user/repo.git"),
+            "github.com/user/repo"
+        );
+        assert_eq!(
+            parse("git@GitHub.com:user/repo.git"),
+            "github.com/user/repo"
+        );
+
+        assert!(RepoSpec::parse(&config, "repo").is_err());
+        assert!(RepoSpec::parse(&config, "/").is_err());
+        assert!(RepoSpec::parse(&config, "gl:repo").is_err());
+    }
+
+    #[test]
+    fn test_repo_spec_rejects_dot_and_empty_segments() {
+        let config = Config {
+            user: Some("me".to_string()),
+            ..create_config()
+        };
+
+        for input in [
+            "../escape/repo",
+            "./user/repo",
+            "user/..",
+            "user/.",
+            "..",
+            ".",
+            "github.com/../repo",
+            "github.com/user/..",
+            "github.com//repo",
+            "user//repo",
+            "gl:../repo",
+            "gl:group/./repo",
+            "gl:group//repo",
+            "https://github.com/user//repo",
+            "git@github.com:../evil",
+        ] {
+            assert!(
+                RepoSpec::parse(&config, input).is_err(),
+                "{input} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn test_repo_spec_host_must_be_a_hostname() {
+        let config = create_config();
+        let parse = |input: &str| RepoSpec::parse(&config, input).unwrap();
+
+        assert!(is_host("github.com"));
+        assert!(is_host("git.company.com:2222"));
+        assert!(is_host("localhost"));
+        assert!(!is_host(".."));
+        assert!(!is_host("a..b"));
+        assert!(!is_host(".github.com"));
+        assert!(!is_host("github.com."));
+        assert!(!is_host("host:port"));
+        assert!(!is_host("owner"));
+
+        let spec = parse("a..b/user/repo");
+        assert_eq!(spec.host, None);
+        assert_eq!(spec.owner, "a..b/user");
+        assert_eq!(
+            parse("localhost:8080/user/repo").host.as_deref(),
+            Some("localhost:8080")
+        );
+    }
+
+    #[test]
+    fn test_repo_spec_uses_configured_user() {
+        let config = Config {
+            user: Some("me".to_string()),
+            ..create_config()
+        };
+        let spec = RepoSpec::parse(&config, "repo").unwrap();
+
+        assert_eq!(spec.host, None);
+        assert_eq!(spec.path(), "me/repo");
+        assert_eq!(spec.dir(Path::new("/root")), None);
+        assert_eq!(
+            RepoSpec::parse(&config, "github.com/user/repo")
+                .unwrap()
+                .dir(Path::new("/root")),
+            Some(PathBuf::from("/root/github.com/user/repo"))
+        );
+    }
+}
@@ -0,0 +1,778 @@
+#[cfg(feature = "gix")]
+use crate::backend::{self, GitBackend};
+use crate::{metadata, scan::RepoEntry};
+use anyhow::{Result, anyhow};
+use git2::{BranchType, ErrorCode, Oid, Repository};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Status of a repository, collected from its bare repository and worktrees.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RepoStatus {
+    pub worktrees: usize,
+    /// Whether any worktree has uncommitted changes.
+    pub dirty: bool,
+    /// Number of worktrees with uncommitted changes.
+    pub dirty_worktrees: usize,
+    /// The branch HEAD of the bare repository points at.
+    pub default_branch: Option<String>,
+    /// When the bare repository was last fetched.
+    pub last_fetch: Option<SystemTime>,
+}
+
+pub fn collect_repo_status(entry: &RepoEntry) -> Result<RepoStatus> {
+    let worktrees = entry.worktrees()?;
+    let reviews = metadata::review_worktrees(entry);
+    let mut dirty_worktrees = 0;
+    for worktree in &worktrees {
+        if !is_review_worktree(&reviews, worktree) && is_worktree_dirty(worktree)? {
+            dirty_worktrees += 1;
+        }
+    }
+    let default_branch = Repository::open_bare(entry.bare_repository_path())
+        .ok()
+        .and_then(|repo| default_branch(&repo));
+
+    let last_fetch = std::fs::metadata(entry.bare_repository_path().join("FETCH_HEAD"))
+        .and_then(|metadata| metadata.modified())
+        .ok();
+
+    Ok(RepoStatus {
+        worktrees: worktrees.len(),
+        dirty: dirty_worktrees > 0,
+        dirty_worktrees,
+        default_branch,
+        last_fetch,
+    })
+}
+
+/// Returns whether `worktree` is one of `reviews`, as
+/// `metadata::review_worktrees` names them.
+pub fn is_review_worktree(reviews: &HashSet<String>, worktree: &Path) -> bool {
+    worktree
+        .file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| reviews.contains(name))
+}
+
+/// Returns whether the worktree has uncommitted changes, including untracked
+/// files. Directories that aren't git worktrees are reported as clean.
+pub fn is_worktree_dirty(worktree: &Path) -> Result<bool> {
+    #[cfg(feature = "gix")]
+    if backend::current() == GitBackend::Gix {
+        return backend::gix::is_worktree_dirty(worktree);
+    }
+
+    let Ok(repo) = git2::Repository::open(worktree) else {
+        return Ok(false);
+    };
+
+    let mut options = git2::StatusOptions::new();
+    options.include_untracked(true).include_ignored(false);
+    let statuses = repo.statuses(Some(&mut options))?;
+
+    Ok(!statuses.is_empty())
+}
+
+/// A file with uncommitted changes in a worktree.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FileChange {
+    pub path: String,
+    /// Two-letter code as in `git status --short`: staged then unstaged
+    /// change, such as `M `, ` M`, `A ` or `??` for untracked files.
+    pub status: String,
+}
+
+/// Returns the uncommitted changes in `worktree`, including untracked
+/// files, sorted by path.
+pub fn changed_files(worktree: &Path) -> Result<Vec<FileChange>> {
+    let repo = Repository::open(worktree)?;
+    let mut options = git2::StatusOptions::new();
+    options
+        .include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .include_ignored(false);
+
+    let mut changes = repo
+        .statuses(Some(&mut options))?
+        .iter()
+        .map(|entry| FileChange {
+            path: entry.path().unwrap_or_default().to_string(),
+            status: short_status(entry.status()),
+        })
+        .collect::<Vec<_>>();
+    changes.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(changes)
+}
+
+fn short_status(status: git2::Status) -> String {
+    use git2::Status;
+
+    if status.is_wt_new() {
+        return "??".to_string();
+    }
+    if status.is_conflicted() {
+        return "UU".to_string();
+    }
+    let staged = [
+        (Status::INDEX_NEW, 'A'),
+        (Status::INDEX_MODIFIED, 'M'),
+        (Status::INDEX_DELETED, 'D'),
+        (Status::INDEX_RENAMED, 'R'),
+        (Status::INDEX_TYPECHANGE, 'T'),
+    ];
+    let unstaged = [
+        (Status::WT_MODIFIED, 'M'),
+        (Status::WT_DELETED, 'D'),
+        (Status::WT_RENAMED, 'R'),
+        (Status::WT_TYPECHANGE, 'T'),
+    ];
+    let code = |flags: &[(Status, char)]| {
+        flags
+            .iter()
+            .find(|(flag, _)| status.contains(*flag))
+            .map_or(' ', |(_, code)| *code)
+    };
+    format!("{}{}", code(&staged), code(&unstaged))
+}
+
+/// A worktree of a repository and the branch checked out in it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WorktreeInfo {
+    pub path: PathBuf,
+    /// `None` when HEAD is detached.
+    pub branch: Option<String>,
+}
+
+/// Returns the worktrees of `repo`, including its own working directory if
+/// it isn't bare, sorted by path.
+pub fn list_worktrees(repo: &Repository) -> Result<Vec<WorktreeInfo>> {
+    let mut worktrees = Vec::new();
+    if let Some(workdir) = repo.workdir() {
+        worktrees.push(WorktreeInfo {
+            path: workdir.to_path_buf(),
+            branch: head_branch(repo),
+        });
+    }
+
+    for name in repo.worktrees()?.iter().flatten() {
+        let worktree = repo.find_worktree(name)?;
+        let Ok(worktree_repo) = Repository::open_from_worktree(&worktree) else {
+            continue;
+        };
+        worktrees.push(WorktreeInfo {
+            path: worktree.path().to_path_buf(),
+            branch: head_branch(&worktree_repo),
+        });
+    }
+
+    worktrees.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(worktrees)
+}
+
+/// Returns the branch HEAD of the shared repository points at, which
+/// neoghq treats as the default branch.
+pub fn default_branch(repo: &Repository) -> Option<String> {
+    let head = repo.find_reference("HEAD").ok()?;
+    let target = head.symbolic_target()?;
+    target.strip_prefix("refs/heads/").map(str::to_string)
+}
+
+/// Returns the branch HEAD of `repo` points at, `None` when detached.
+pub fn head_branch(repo: &Repository) -> Option<String> {
+    let head = repo.head().ok()?;
+    if !head.is_branch() {
+        return None;
+    }
+    head.shorthand().map(str::to_string)
+}
+
+/// The branch, upstream and dirty state of a worktree, as printed by
+/// `worktree status --json`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WorktreeStatus {
+    pub path: PathBuf,
+    /// `None` when HEAD is detached.
+    pub branch: Option<String>,
+    /// The configured upstream, such as `origin/main`.
+    pub upstream: Option<String>,
+    /// `tracking`, `missing` or `gone`; `None` when HEAD is detached.
+    pub upstream_state: Option<&'static str>,
+    pub ahead: usize,
+    pub behind: usize,
+    pub dirty: bool,
+    /// Changes against the default branch, filled in by
+    /// `worktree status --diffstat`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub diffstat: Option<DiffStat>,
+    #[serde(skip)]
+    pub tracking: Option<Upstream>,
+}
+
+impl WorktreeStatus {
+    /// Whether the branch lacks an upstream or tracks a deleted one.
+    pub fn needs_fix(&self) -> bool {
+        self.tracking
+            .as_ref()
+            .is_some_and(|upstream| !matches!(upstream, Upstream::Tracking { .. }))
+    }
+
+    /// Whether the branch tracks a remote branch that was deleted, as
+    /// happens once it's merged and a pruning fetch ran.
+    pub fn upstream_gone(&self) -> bool {
+        matches!(self.tracking, Some(Upstream::Gone { .. }))
+    }
+}
+
+/// Returns the status of every worktree of `repo`, sorted by path.
+pub fn collect_worktree_statuses(repo: &Repository) -> Result<Vec<WorktreeStatus>> {
+    let mut statuses = Vec::new();
+    for worktree in list_worktrees(repo)? {
+        let tracking = match &worktree.branch {
+            Some(branch) => Some(upstream_state(repo, branch)?),
+            None => None,
+        };
+        let (upstream, upstream_state, ahead, behind) = match &tracking {
+            Some(Upstream::Tracking {
+                name,
+                ahead,
+                behind,
+            }) => (Some(name.clone()), Some("tracking"), *ahead, *behind),
+            Some(Upstream::Missing) => (None, Some("missing"), 0, 0),
+            Some(Upstream::Gone { name }) => (Some(name.clone()), Some("gone"), 0, 0),
+            None => (None, None, 0, 0),
+        };
+
+        statuses.push(WorktreeStatus {
+            dirty: is_worktree_dirty(&worktree.path)?,
+            path: worktree.path,
+            branch: worktree.branch,
+            upstream,
+            upstream_state,
+            ahead,
+            behind,
+            diffstat: None,
+            tracking,
+        });
+    }
+    Ok(statuses)
+}
+
+/// The size of a diff, as summed up by `git diff --shortstat`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct DiffStat {
+    pub files: usize,
+    pub insertions: usize,
+    pub deletions: usize,
+}
+
+impl fmt::Display for DiffStat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} files, +{} -{}",
+            self.files, self.insertions, self.deletions
+        )
+    }
+}
+
+/// Returns the contents of `worktree`, uncommitted changes and untracked
+/// files included, as a tree of `repo`, the way `git add --all` would stage
+/// them. The worktree's index is left alone.
+pub fn worktree_tree<'a>(repo: &'a Repository, worktree: &Path) -> Result<git2::Tree<'a>> {
+    let worktree = Repository::open(worktree)?;
+    let mut index = worktree.index()?;
+    index.add_all(["*"], git2::IndexAddOption::DEFAULT, None)?;
+    index.update_all(["*"], None)?;
+    // Worktrees share the object database, so the tree is in `repo` too
+    Ok(repo.find_tree(index.write_tree()?)?)
+}
+
+/// Returns what `worktree` changes against the default branch: its
+/// contents, uncommitted changes included, compared with where its HEAD
+/// forked from the default branch.
+pub fn diffstat_against_default(repo: &Repository, worktree: &Path) -> Result<DiffStat> {
+    let default_branch =
+        default_branch(repo).ok_or_else(|| anyhow!("HEAD of the repository is detached"))?;
+    let default_tip = repo.refname_to_id(&format!("refs/heads/{default_branch}"))?;
+    let head = Repository::open(worktree)?.head()?.peel_to_commit()?.id();
+    let base = repo.find_commit(repo.merge_base(head, default_tip)?)?;
+    let diff = repo.diff_tree_to_tree(
+        Some(&base.tree()?),
+        Some(&worktree_tree(repo, worktree)?),
+        None,
+    )?;
+    let stats = diff.stats()?;
+    Ok(DiffStat {
+        files: stats.files_changed(),
+        insertions: stats.insertions(),
+        deletions: stats.deletions(),
+    })
+}
+
+/// How a local branch tracks its remote counterpart.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Upstream {
+    /// The branch tracks an existing remote-tracking branch.
+    Tracking {
+        name: String,
+        ahead: usize,
+        behind: usize,
+    },
+    /// The branch has no upstream configured.
+    Missing,
+    /// The configured upstream no longer exists on the remote.
+    Gone { name: String },
+}
+
+impl fmt::Display for Upstream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Tracking {
+                name,
+                ahead: 0,
+                behind: 0,
+            } => write!(f, "{name}"),
+            Self::Tracking {
+                name,
+                ahead,
+                behind,
+            } => {
+                let counts = [(*ahead, "ahead"), (*behind, "behind")]
+                    .into_iter()
+                    .filter(|(count, _)| *count > 0)
+                    .map(|(count, label)| format!("{label} {count}"))
+                    .collect::<Vec<_>>();
+                write!(f, "{name} [{}]", counts.join(", "))
+            }
+            Self::Missing => write!(f, "no upstream"),
+            Self::Gone { name } => write!(f, "{name} [gone]"),
+        }
+    }
+}
+
+/// Returns how the local `branch` tracks its upstream.
+pub fn upstream_state(repo: &Repository, branch: &str) -> Result<Upstream> {
+    let local = repo.find_branch(branch, BranchType::Local)?;
+    let upstream = match local.upstream() {
+        Ok(upstream) => upstream,
+        Err(error) if error.code() == ErrorCode::NotFound => {
+            return Ok(match configured_upstream(repo, branch)? {
+                Some(name) => Upstream::Gone { name },
+                None => Upstream::Missing,
+            });
+        }
+        Err(error) => return Err(error.into()),
+    };
+
+    let name = upstream.name()?.unwrap_or_default().to_string();
+    let (ahead, behind) = match (local.get().target(), upstream.get().target()) {
+        (Some(local), Some(upstream)) => repo.graph_ahead_behind(local, upstream)?,
+        _ => (0, 0),
+    };
+
+    Ok(Upstream::Tracking {
+        name,
+        ahead,
+        behind,
+    })
+}
+
+/// Counts the commits reachable from `commit` that no remote-tracking
+/// branch contains, which would be lost with the worktree holding them.
+pub fn unpushed_commits(repo: &Repository, commit: Oid) -> Result<usize> {
+    let mut walk = repo.revwalk()?;
+    walk.push(commit)?;
+    for branch in repo.branches(Some(BranchType::Remote))? {
+        if let Some(tip) = branch?.0.get().target() {
+            walk.hide(tip)?;
+        }
+    }
+    Ok(walk.count())
+}
+
+/// What removing a worktree would lose.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WorktreeRisk {
+    pub path: PathBuf,
+    /// `None` when HEAD is detached.
+    pub branch: Option<String>,
+    pub dirty: bool,
+    /// Commits of HEAD no remote-tracking branch contains.
+    pub unpushed: usize,
+}
+
+impl WorktreeRisk {
+    /// Whether removing the worktree loses nothing.
+    pub fn is_safe(&self) -> bool {
+        !self.dirty && self.unpushed == 0
+    }
+}
+
+/// Returns the uncommitted and unpushed work of every worktree of `repo`,
+/// sorted by path.
+pub fn collect_worktree_risks(repo: &Repository) -> Result<Vec<WorktreeRisk>> {
+    let mut risks = Vec::new();
+    for worktree in list_worktrees(repo)? {
+        let head = Repository::open(&worktree.path)
+            .ok()
+            .and_then(|repo| repo.head().ok()?.target());
+        risks.push(WorktreeRisk {
+            dirty: is_worktree_dirty(&worktree.path)?,
+            unpushed: match head {
+                Some(head) => unpushed_commits(repo, head)?,
+                None => 0,
+            },
+            path: worktree.path,
+            branch: worktree.branch,
+        });
+    }
+    Ok(risks)
+}
+
+/// Returns the upstream configured for `branch` as `<remote>/<branch>`,
+/// whether or not it still exists.
+fn configured_upstream(repo: &Repository, branch: &str) -> Result<Option<String>> {
+    let config = repo.config()?;
+    let Ok(merge) = config.get_string(&format!("branch.{branch}.merge")) else {
+        return Ok(None);
+    };
+    let remote = config
+        .get_string(&format!("branch.{branch}.remote"))
+        .unwrap_or_else(|_| "origin".to_string());
+    let merge = merge.strip_prefix("refs/heads/").unwrap_or(&merge);
+
+    Ok(Some(format!("{remote}/{merge}")))
+}
+
+/// Returns the total size of the files under `path`, without following
+/// symlinks. Unreadable entries count as empty.
+pub fn disk_usage(path: &Path) -> u64 {
+    let Ok(metadata) = std::fs::symlink_metadata(path) else {
+        return 0;
+    };
+    if !metadata.is_dir() {
+        return metadata.len();
+    }
+
+    std::fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| disk_usage(&entry.path()))
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+/// Returns [`disk_usage`] of each of `paths`, in order, walking up to
+/// `jobs` paths at once (by default one per CPU).
+pub fn disk_usages(paths: &[PathBuf], jobs: Option<usize>) -> Vec<u64> {
+    parallel_map(paths, jobs, |path| disk_usage(path))
+}
+
+/// Applies `f` to each of `items` on up to `jobs` threads (by default one
+/// per CPU) and returns the results in the order of `items`.
+pub fn parallel_map<T, R, F>(items: &[T], jobs: Option<usize>, f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync + Send,
+{
+    let Some(jobs) = jobs else {
+        return items.par_iter().map(f).collect();
+    };
+    match rayon::ThreadPoolBuilder::new().num_threads(jobs).build() {
+        Ok(pool) => pool.install(|| items.par_iter().map(f).collect()),
+        // Without threads to spare, do the work on this one
+        Err(_) => items.iter().map(f).collect(),
+    }
+}
+
+/// File in the bare repository holding the error of the last failed fetch.
+const FETCH_ERROR_FILE: &str = "neoghq-fetch-error";
+
+/// Remembers the outcome of fetching the bare repository at
+/// `bare_repo_path`, so `status` can report repositories whose last fetch
+/// failed.
+pub fn record_fetch(bare_repo_path: &Path, result: &Result<()>) -> Result<()> {
+    let path = bare_repo_path.join(FETCH_ERROR_FILE);
+    match result {
+        Ok(()) => match std::fs::remove_file(path) {
+            Err(error) if error.kind() != std::io::ErrorKind::NotFound => Err(error.into()),
+            _ => Ok(()),
+        },
+        Err(error) => Ok(std::fs::write(path, format!("{error:#}\n"))?),
+    }
+}
+
+/// Returns the error of the last fetch of the bare repository, if it failed.
+pub fn fetch_error(bare_repo_path: &Path) -> Option<String> {
+    std::fs::read_to_string(bare_repo_path.join(FETCH_ERROR_FILE))
+        .ok()
+        .map(|error| error.trim_end().to_string())
+}
+
+/// Returns the current Unix time in seconds.
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+/// Formats a time as an RFC 3339 UTC timestamp (`2025-01-31T12:34:56Z`).
+pub fn format_timestamp(time: SystemTime) -> String {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as i64)
+        .unwrap_or(0);
+    let (days, seconds_of_day) = (seconds.div_euclid(86_400), seconds.rem_euclid(86_400));
+
+    // Convert days since the epoch to a civil date (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        seconds_of_day / 3600,
+        seconds_of_day % 3600 / 60,
+        seconds_of_day % 60
+    )
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use crate::test_utils::{commit_all, create_managed_repository};
+    use std::time::Duration;
+
+    fn create_entry(root: &Path) -> RepoEntry {
+        let path = root.join("github.com/user/repo");
+        create_managed_repository(&path);
+        RepoEntry {
+            host: "github.com".to_string(),
+            owner: "user".to_string(),
+            repo: "repo".to_string(),
+            path,
+        }
+    }
+
+    #[test]
+    fn test_collect_repo_status_clean() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let entry = create_entry(temp_dir.path());
+        std::fs::remove_file(entry.bare_repository_path().join("FETCH_HEAD")).unwrap();
+
+        let status = collect_repo_status(&entry).unwrap();
+
+        assert_eq!(status.worktrees, 1);
+        assert!(!status.dirty);
+        assert_eq!(status.dirty_worktrees, 0);
+        assert_eq!(status.default_branch.as_deref(), Some("main"));
+        assert_eq!(status.last_fetch, None);
+    }
+
+    #[test]
+    fn test_collect_repo_status_dirty_with_fetch_head() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let entry = create_entry(temp_dir.path());
+        std::fs::write(entry.path.join("main/README"), "changed").unwrap();
+        std::fs::write(entry.bare_repository_path().join("FETCH_HEAD"), "").unwrap();
+
+        let status = collect_repo_status(&entry).unwrap();
+
+        assert!(status.dirty);
+        assert_eq!(status.dirty_worktrees, 1);
+        assert!(status.last_fetch.is_some());
+    }
+
+    #[test]
+    fn test_is_worktree_dirty_with_untracked_file() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let entry = create_entry(temp_dir.path());
+        std::fs::write(entry.path.join("main/NEW"), "new").unwrap();
+
+        assert!(is_worktree_dirty(&entry.path.join("main")).unwrap());
+    }
+
+    #[test]
+    fn test_is_worktree_dirty_outside_repository() {
+        let temp_dir = tempfile::tempdir().unwrap();
+
+        assert!(!is_worktree_dirty(temp_dir.path()).unwrap());
+    }
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(UNIX_EPOCH), "1970-01-01T00:00:00Z");
+        assert_eq!(
+            format_timestamp(UNIX_EPOCH + Duration::from_secs(951_782_400)),
+            "2000-02-29T00:00:00Z"
+        );
+        assert_eq!(
+            format_timestamp(UNIX_EPOCH + Duration::from_secs(1_735_689_599)),
+            "2024-12-31T23:59:59Z"
+        );
+    }
+
+    #[test]
+    fn test_list_worktrees() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo_dir = temp_dir.path().join("repo");
+        let bare = create_managed_repository(&repo_dir);
+
+        let worktrees = list_worktrees(&bare).unwrap();
+
+        assert_eq!(worktrees.len(), 1);
+        assert_eq!(
+            worktrees[0].path.canonicalize().unwrap(),
+            repo_dir.join("main").canonicalize().unwrap()
+        );
+        assert_eq!(worktrees[0].branch.as_deref(), Some("main"));
+    }
+
+    #[test]
+    fn test_upstream_state() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo_dir = temp_dir.path().join("repo");
+        let bare = create_managed_repository(&repo_dir);
+        let mut main = bare.find_branch("main", BranchType::Local).unwrap();
+
+        main.set_upstream(None).unwrap();
+        assert_eq!(upstream_state(&bare, "main").unwrap(), Upstream::Missing);
+
+        main.set_upstream(Some("origin/main")).unwrap();
+        let worktree = Repository::open(repo_dir.join("main")).unwrap();
+        std::fs::write(repo_dir.join("main/NEW"), "new").unwrap();
+        commit_all(&worktree, "Add NEW");
+        let state = upstream_state(&bare, "main").unwrap();
+        assert_eq!(
+            state,
+            Upstream::Tracking {
+                name: "origin/main".to_string(),
+                ahead: 1,
+                behind: 0,
+            }
+        );
+        assert_eq!(state.to_string(), "origin/main [ahead 1]");
+
+        bare.find_reference("refs/remotes/origin/main")
+            .unwrap()
+            .delete()
+            .unwrap();
+        let state = upstream_state(&bare, "main").unwrap();
+        assert_eq!(
+            state,
+            Upstream::Gone {
+                name: "origin/main".to_string()
+            }
+        );
+        assert_eq!(state.to_string(), "origin/main [gone]");
+    }
+
+    #[test]
+    fn test_collect_worktree_risks() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo_dir = temp_dir.path().join("repo");
+        let bare = create_managed_repository(&repo_dir);
+        let risks = collect_worktree_risks(&bare).unwrap();
+        assert_eq!(risks.len(), 1);
+        assert!(risks[0].is_safe());
+
+        let worktree = Repository::open(repo_dir.join("main")).unwrap();
+        std::fs::write(repo_dir.join("main/NEW"), "new").unwrap();
+        commit_all(&worktree, "Add NEW");
+        std::fs::write(repo_dir.join("main/NEW"), "changed").unwrap();
+        let head = worktree.head().unwrap().target().unwrap();
+        assert_eq!(unpushed_commits(&bare, head).unwrap(), 1);
+
+        let risks = collect_worktree_risks(&bare).unwrap();
+        assert_eq!(risks[0].branch.as_deref(), Some("main"));
+        assert!(risks[0].dirty);
+        assert_eq!(risks[0].unpushed, 1);
+        assert!(!risks[0].is_safe());
+    }
+
+    #[test]
+    fn test_changed_files() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo = crate::test_utils::create_source_repository(temp_dir.path());
+        std::fs::write(temp_dir.path().join("README"), "changed\n").unwrap();
+        std::fs::write(temp_dir.path().join("staged"), "new\n").unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("staged")).unwrap();
+        index.write().unwrap();
+        std::fs::write(temp_dir.path().join("untracked"), "new\n").unwrap();
+
+        let changes = changed_files(temp_dir.path()).unwrap();
+        let summary = changes
+            .iter()
+            .map(|change| format!("{} {}", change.status, change.path))
+            .collect::<Vec<_>>();
+        assert_eq!(summary, [" M README", "A  staged", "?? untracked"]);
+    }
+
+    #[test]
+    fn test_disk_usage() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(temp_dir.path().join("sub")).unwrap();
+        std::fs::write(temp_dir.path().join("a"), "12345").unwrap();
+        std::fs::write(temp_dir.path().join("sub/b"), "123").unwrap();
+
+        assert_eq!(disk_usage(temp_dir.path()), 8);
+        assert_eq!(disk_usage(&temp_dir.path().join("missing")), 0);
+    }
+
+    #[test]
+    fn test_disk_usages() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let paths = (0..5)
+            .map(|size| {
+                let path = temp_dir.path().join(size.to_string());
+                std::fs::write(&path, vec![0; size]).unwrap();
+                path
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(disk_usages(&paths, Some(2)), [0, 1, 2, 3, 4]);
+        assert_eq!(disk_usages(&paths, None), [0, 1, 2, 3, 4]);
+        assert!(disk_usages(&[], None).is_empty());
+    }
+
+    #[test]
+    fn test_parallel_map() {
+        let items = (0..100).collect::<Vec<u32>>();
+        let doubled = parallel_map(&items, Some(4), |item| item * 2);
+        assert_eq!(
+            doubled,
+            items.iter().map(|item| item * 2).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_record_fetch() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let bare = temp_dir.path();
+
+        assert_eq!(fetch_error(bare), None);
+        record_fetch(bare, &Err(anyhow::anyhow!("could not resolve host"))).unwrap();
+        assert_eq!(fetch_error(bare).as_deref(), Some("could not resolve host"));
+        record_fetch(bare, &Ok(())).unwrap();
+        assert_eq!(fetch_error(bare), None);
+        record_fetch(bare, &Ok(())).unwrap();
+    }
+}
@@ -0,0 +1,157 @@
+use crate::{cli::ColorChoice, config::Config};
+use serde::Deserialize;
+use std::fmt::Display;
+use std::io::IsTerminal;
+
+/// Icon set shown in front of repositories and branches, from `[ui] icons`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Icons {
+    /// No icons.
+    #[default]
+    None,
+    /// Glyphs from a Nerd Font patched terminal font.
+    NerdFont,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Color {
+    Red,
+    Green,
+    Yellow,
+    Dim,
+}
+
+impl Color {
+    fn code(self) -> &'static str {
+        match self {
+            Self::Red => "31",
+            Self::Green => "32",
+            Self::Yellow => "33",
+            Self::Dim => "2",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Icon {
+    Repo,
+    Branch,
+    Detached,
+}
+
+impl Icon {
+    fn nerd_font(self) -> &'static str {
+        match self {
+            Self::Repo => "\u{f401}",
+            Self::Branch => "\u{e0a0}",
+            Self::Detached => "\u{f417}",
+        }
+    }
+}
+
+/// Decides how human-readable output on stdout is decorated. All text
+/// output goes through this so `--color`, `NO_COLOR` and `[ui] icons` apply
+/// everywhere.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Style {
+    color: bool,
+    icons: bool,
+}
+
+impl Style {
+    /// The style for stdout. With `--color auto`, colors are used only on a
+    /// terminal and when `NO_COLOR` is unset or empty.
+    pub fn stdout(config: &Config) -> Self {
+        let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+        let color = match config.color {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => !no_color && std::io::stdout().is_terminal(),
+        };
+        Self {
+            color,
+            icons: config.icons == Icons::NerdFont,
+        }
+    }
+
+    pub fn paint(&self, color: Color, text: impl Display) -> String {
+        if self.color {
+            format!("\x1b[{}m{text}\x1b[0m", color.code())
+        } else {
+            text.to_string()
+        }
+    }
+
+    /// Returns `text` preceded by `icon` when icons are enabled.
+    pub fn icon(&self, icon: Icon, text: impl Display) -> String {
+        if self.icons {
+            format!("{} {text}", icon.nerd_font())
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+/// Width of `text` on a terminal, ignoring color escape sequences.
+pub fn visible_width(text: &str) -> usize {
+    let mut width = 0;
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            chars.by_ref().find(|c| *c == 'm');
+        } else {
+            width += 1;
+        }
+    }
+    width
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_style() {
+        let plain = Style::default();
+        assert_eq!(plain.paint(Color::Red, "dirty"), "dirty");
+        assert_eq!(plain.icon(Icon::Branch, "main"), "main");
+
+        let styled = Style {
+            color: true,
+            icons: true,
+        };
+        assert_eq!(styled.paint(Color::Red, "dirty"), "\x1b[31mdirty\x1b[0m");
+        assert_eq!(styled.icon(Icon::Branch, "main"), "\u{e0a0} main");
+    }
+
+    #[test]
+    fn test_style_stdout_respects_color_choice() {
+        let config = Config {
+            color: ColorChoice::Always,
+            icons: Icons::NerdFont,
+            ..Default::default()
+        };
+        assert_eq!(
+            Style::stdout(&config),
+            Style {
+                color: true,
+                icons: true
+            }
+        );
+
+        let config = Config {
+            color: ColorChoice::Never,
+            ..Default::default()
+        };
+        assert_eq!(Style::stdout(&config), Style::default());
+    }
+
+    #[test]
+    fn test_visible_width() {
+        assert_eq!(visible_width("main"), 4);
+        assert_eq!(visible_width("\x1b[32mmain\x1b[0m"), 4);
+        assert_eq!(visible_width("\u{e0a0} main"), 6);
+    }
+}
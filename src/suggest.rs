@@ -0,0 +1,104 @@
+//! "Did you mean" hints for names that don't match any repository or
+//! worktree.
+
+/// Most suggestions listed in an error message.
+const MAX_SUGGESTIONS: usize = 3;
+
+/// Returns the candidates close to `query` by edit distance, closest first.
+/// Candidates containing `query` count as close, so `neo` suggests
+/// `r4ai/neoghq`, and a query with fewer `/`-separated segments is compared
+/// with the same number of trailing segments, so `neoghg` does too.
+pub fn similar<'a>(query: &str, candidates: impl IntoIterator<Item = &'a str>) -> Vec<&'a str> {
+    let threshold = (query.chars().count() / 3).max(2);
+    let segments = query.split('/').count();
+    let mut matches = candidates
+        .into_iter()
+        .filter_map(|candidate| {
+            let distance = if candidate.contains(query) {
+                0
+            } else {
+                strsim::levenshtein(query, suffix(candidate, segments))
+            };
+            (distance <= threshold).then_some((distance, candidate))
+        })
+        .collect::<Vec<_>>();
+    matches.sort();
+    matches.dedup_by(|a, b| a.1 == b.1);
+
+    matches
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, candidate)| candidate)
+        .collect()
+}
+
+/// Returns the last `segments` `/`-separated segments of `name`.
+fn suffix(name: &str, segments: usize) -> &str {
+    name.rmatch_indices('/')
+        .nth(segments - 1)
+        .map_or(name, |(index, _)| &name[index + 1..])
+}
+
+/// Appends a hint listing `suggestions` to `message`, if there are any.
+pub fn with_suggestions(message: String, suggestions: &[&str]) -> String {
+    match suggestions {
+        [] => message,
+        [suggestion] => format!("{message}\n\nDid you mean {suggestion}?"),
+        suggestions => {
+            let list = suggestions
+                .iter()
+                .map(|suggestion| format!("    {suggestion}"))
+                .collect::<Vec<_>>()
+                .join("\n");
+            format!("{message}\n\nDid you mean one of these?\n{list}")
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_similar() {
+        let candidates = [
+            "r4ai/neoghq",
+            "r4ai/dotfiles",
+            "alice/neoghq",
+            "x-motemen/ghq",
+        ];
+        assert_eq!(similar("r4ai/neoghg", candidates), ["r4ai/neoghq"]);
+        assert_eq!(
+            similar("neoghg", candidates),
+            ["alice/neoghq", "r4ai/neoghq"]
+        );
+        assert_eq!(similar("dotfile", candidates), ["r4ai/dotfiles"]);
+        assert!(similar("zzz/unrelated", candidates).is_empty());
+        assert_eq!(similar("q", ["a", "b", "c", "d", "q1"]).len(), 3);
+    }
+
+    #[test]
+    fn test_suffix() {
+        assert_eq!(suffix("github.com/r4ai/neoghq", 1), "neoghq");
+        assert_eq!(suffix("github.com/r4ai/neoghq", 2), "r4ai/neoghq");
+        assert_eq!(
+            suffix("github.com/r4ai/neoghq", 3),
+            "github.com/r4ai/neoghq"
+        );
+        assert_eq!(suffix("neoghq", 2), "neoghq");
+    }
+
+    #[test]
+    fn test_with_suggestions() {
+        assert_eq!(with_suggestions("Not found".to_string(), &[]), "Not found");
+        assert_eq!(
+            with_suggestions("Not found".to_string(), &["a/b"]),
+            "Not found\n\nDid you mean a/b?"
+        );
+        assert_eq!(
+            with_suggestions("Not found".to_string(), &["a/b", "c/d"]),
+            "Not found\n\nDid you mean one of these?\n    a/b\n    c/d"
+        );
+    }
+}
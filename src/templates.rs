@@ -0,0 +1,366 @@
+use crate::{
+    cli::{GitignoreTemplate, License},
+    config::Config,
+    credentials,
+};
+use anyhow::{Context, Result, anyhow};
+use std::path::{Path, PathBuf};
+
+/// Values substituted for `{{name}}` placeholders in template file names and
+/// contents.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateVars {
+    pub host: String,
+    pub owner: String,
+    pub repo_name: String,
+    pub author: String,
+    pub year: String,
+}
+
+impl TemplateVars {
+    pub fn substitute(&self, text: &str) -> String {
+        [
+            ("host", &self.host),
+            ("owner", &self.owner),
+            ("repo_name", &self.repo_name),
+            ("author", &self.author),
+            ("year", &self.year),
+        ]
+        .iter()
+        .fold(text.to_string(), |text, (name, value)| {
+            text.replace(&format!("{{{{{name}}}}}", name = name), value)
+                .replace(&format!("{{{{ {name} }}}}", name = name), value)
+        })
+    }
+}
+
+/// Copies the template `spec` into `dest`. `spec` is a name from the
+/// `[templates]` table or the `templates/` directory next to the config
+/// file, a local directory, or the URL of a template repository.
+pub fn apply_template(config: &Config, spec: &str, dest: &Path, vars: &TemplateVars) -> Result<()> {
+    let source = resolve_template(config, spec)?;
+    if is_url(&source) {
+        return apply_remote_template(config, &source, dest, vars);
+    }
+
+    let source = PathBuf::from(source);
+    if !source.is_dir() {
+        return Err(anyhow!("Template is not a directory: {}", source.display()));
+    }
+    copy_directory(&source, dest, vars)
+}
+
+fn resolve_template(config: &Config, spec: &str) -> Result<String> {
+    if let Some(source) = config.templates.get(spec) {
+        return Ok(source.clone());
+    }
+    if is_url(spec) || spec.contains(std::path::MAIN_SEPARATOR) || Path::new(spec).is_dir() {
+        return Ok(spec.to_string());
+    }
+
+    let named = config
+        .config_path
+        .as_ref()
+        .and_then(|path| path.parent())
+        .map(|dir| dir.join("templates").join(spec))
+        .filter(|dir| dir.is_dir())
+        .ok_or_else(|| anyhow!("Unknown template: {spec}"))?;
+    Ok(named.display().to_string())
+}
+
+fn is_url(spec: &str) -> bool {
+    spec.contains("://") || (spec.contains('@') && spec.contains(':'))
+}
+
+fn copy_directory(source: &Path, dest: &Path, vars: &TemplateVars) -> Result<()> {
+    std::fs::create_dir_all(dest)?;
+    for entry in std::fs::read_dir(source)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        if name == ".git" {
+            continue;
+        }
+
+        let target = dest.join(vars.substitute(&name.to_string_lossy()));
+        if entry.file_type()?.is_dir() {
+            copy_directory(&entry.path(), &target, vars)?;
+        } else {
+            write_file(&target, &std::fs::read(entry.path())?, vars)?;
+        }
+    }
+    Ok(())
+}
+
+/// Writes the files at the tip of a template repository's default branch.
+fn apply_remote_template(
+    config: &Config,
+    url: &str,
+    dest: &Path,
+    vars: &TemplateVars,
+) -> Result<()> {
+    let clone_dir = std::env::temp_dir().join(format!("neoghq-template-{}", std::process::id()));
+    let result = (|| {
+        let mut fetch_options = git2::FetchOptions::new();
+        fetch_options.remote_callbacks(credentials::remote_callbacks(&config.credentials));
+        let repo = git2::build::RepoBuilder::new()
+            .bare(true)
+            .fetch_options(fetch_options)
+            .clone(url, &clone_dir)
+            .with_context(|| format!("Failed to clone template {url}"))?;
+
+        let tree = repo.head()?.peel_to_tree()?;
+        let mut error = None;
+        tree.walk(git2::TreeWalkMode::PreOrder, |parent, entry| {
+            let Some(git2::ObjectType::Blob) = entry.kind() else {
+                return git2::TreeWalkResult::Ok;
+            };
+            let path = format!("{parent}{}", entry.name().unwrap_or_default());
+            let written = entry
+                .to_object(&repo)
+                .map_err(anyhow::Error::from)
+                .and_then(|object| {
+                    let blob = object.peel_to_blob()?;
+                    write_file(&dest.join(vars.substitute(&path)), blob.content(), vars)
+                });
+            match written {
+                Ok(()) => git2::TreeWalkResult::Ok,
+                Err(e) => {
+                    error = Some(e);
+                    git2::TreeWalkResult::Abort
+                }
+            }
+        })?;
+        error.map_or(Ok(()), Err)
+    })();
+
+    let _ = std::fs::remove_dir_all(&clone_dir);
+    result
+}
+
+/// Writes `content`, substituting placeholders if it is UTF-8 text.
+fn write_file(path: &Path, content: &[u8], vars: &TemplateVars) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    match std::str::from_utf8(content) {
+        Ok(text) => std::fs::write(path, vars.substitute(text))?,
+        Err(_) => std::fs::write(path, content)?,
+    }
+    Ok(())
+}
+
+/// Writes a `LICENSE` file for `license`.
+pub fn write_license(dest: &Path, license: License, vars: &TemplateVars) -> Result<()> {
+    let text = match license {
+        License::Mit => MIT_LICENSE,
+        License::Isc => ISC_LICENSE,
+        License::Unlicense => UNLICENSE,
+    };
+    write_file(&dest.join("LICENSE"), text.as_bytes(), vars)
+}
+
+/// Appends the ignore patterns for `template` to `.gitignore`.
+pub fn write_gitignore(dest: &Path, template: GitignoreTemplate) -> Result<()> {
+    let patterns = match template {
+        GitignoreTemplate::Rust => "/target\n",
+        GitignoreTemplate::Node => "node_modules/\ndist/\n.env\nnpm-debug.log*\n",
+        GitignoreTemplate::Python => "__pycache__/\n*.py[cod]\n.venv/\n*.egg-info/\ndist/\n",
+        GitignoreTemplate::Go => "/bin/\n*.test\n*.out\n",
+    };
+
+    let path = dest.join(".gitignore");
+    let mut content = std::fs::read_to_string(&path).unwrap_or_default();
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
+    }
+    content.push_str(patterns);
+    std::fs::write(path, content)?;
+    Ok(())
+}
+
+const MIT_LICENSE: &str = "MIT License
+
+Copyright (c) {{year}} {{author}}
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the \"Software\"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED \"AS IS\", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+";
+
+const ISC_LICENSE: &str = "ISC License
+
+Copyright (c) {{year}} {{author}}
+
+Permission to use, copy, modify, and/or distribute this software for any
+purpose with or without fee is hereby granted, provided that the above
+copyright notice and this permission notice appear in all copies.
+
+THE SOFTWARE IS PROVIDED \"AS IS\" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+";
+
+const UNLICENSE: &str = "This is free and unencumbered software released into the public domain.
+
+Anyone is free to copy, modify, publish, use, compile, sell, or
+distribute this software, either in source code form or as a compiled
+binary, for any purpose, commercial or non-commercial, and by any
+means.
+
+In jurisdictions that recognize copyright laws, the author or authors
+of this software dedicate any and all copyright interest in the
+software to the public domain. We make this dedication for the benefit
+of the public at large and to the detriment of our heirs and
+successors. We intend this dedication to be an overt act of
+relinquishment in perpetuity of all present and future rights to this
+software under copyright law.
+
+THE SOFTWARE IS PROVIDED \"AS IS\", WITHOUT WARRANTY OF ANY KIND,
+EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF
+MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT.
+IN NO EVENT SHALL THE AUTHORS BE LIABLE FOR ANY CLAIM, DAMAGES OR
+OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE,
+ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR
+OTHER DEALINGS IN THE SOFTWARE.
+
+For more information, please refer to <https://unlicense.org/>
+";
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_source_repository;
+    use std::collections::BTreeMap;
+
+    fn vars() -> TemplateVars {
+        TemplateVars {
+            host: "github.com".to_string(),
+            owner: "user".to_string(),
+            repo_name: "widget".to_string(),
+            author: "Jane Doe".to_string(),
+            year: "2025".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_substitute() {
+        assert_eq!(
+            vars().substitute("# {{repo_name}} by {{ owner }} ({{unknown}})"),
+            "# widget by user ({{unknown}})"
+        );
+    }
+
+    #[test]
+    fn test_apply_template_from_directory() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let template = temp_dir.path().join("template");
+        std::fs::create_dir_all(template.join("src/{{repo_name}}")).unwrap();
+        std::fs::create_dir_all(template.join(".git")).unwrap();
+        std::fs::write(template.join("README.md"), "# {{repo_name}}\n").unwrap();
+        std::fs::write(template.join("src/{{repo_name}}/lib.rs"), "// {{owner}}\n").unwrap();
+        std::fs::write(template.join("logo.bin"), [0xff, 0xfe, b'{']).unwrap();
+        let dest = temp_dir.path().join("dest");
+
+        apply_template(
+            &Config::default(),
+            template.to_str().unwrap(),
+            &dest,
+            &vars(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(dest.join("README.md")).unwrap(),
+            "# widget\n"
+        );
+        assert_eq!(
+            std::fs::read_to_string(dest.join("src/widget/lib.rs")).unwrap(),
+            "// user\n"
+        );
+        assert_eq!(
+            std::fs::read(dest.join("logo.bin")).unwrap(),
+            [0xff, 0xfe, b'{']
+        );
+        assert!(!dest.join(".git").exists());
+    }
+
+    #[test]
+    fn test_apply_template_by_name() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let named = temp_dir.path().join("neoghq/templates/rust-cli");
+        std::fs::create_dir_all(&named).unwrap();
+        std::fs::write(named.join("Cargo.toml"), "name = \"{{repo_name}}\"\n").unwrap();
+        let aliased = temp_dir.path().join("aliased");
+        std::fs::create_dir_all(&aliased).unwrap();
+        std::fs::write(aliased.join("ALIASED"), "").unwrap();
+        let config = Config {
+            config_path: Some(temp_dir.path().join("neoghq/config.toml")),
+            templates: BTreeMap::from([("lib".to_string(), aliased.display().to_string())]),
+            ..Default::default()
+        };
+
+        let dest = temp_dir.path().join("dest");
+        apply_template(&config, "rust-cli", &dest, &vars()).unwrap();
+        apply_template(&config, "lib", &dest, &vars()).unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(dest.join("Cargo.toml")).unwrap(),
+            "name = \"widget\"\n"
+        );
+        assert!(dest.join("ALIASED").exists());
+        assert!(apply_template(&config, "missing", &dest, &vars()).is_err());
+    }
+
+    #[test]
+    fn test_apply_template_from_repository() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let source = temp_dir.path().join("source");
+        create_source_repository(&source);
+        let dest = temp_dir.path().join("dest");
+
+        apply_template(
+            &Config::default(),
+            &format!("file://{}", source.display()),
+            &dest,
+            &vars(),
+        )
+        .unwrap();
+
+        assert!(dest.join("README").exists());
+    }
+
+    #[test]
+    fn test_write_license_and_gitignore() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        std::fs::write(temp_dir.path().join(".gitignore"), "*.log").unwrap();
+
+        write_license(temp_dir.path(), License::Mit, &vars()).unwrap();
+        write_gitignore(temp_dir.path(), GitignoreTemplate::Rust).unwrap();
+
+        let license = std::fs::read_to_string(temp_dir.path().join("LICENSE")).unwrap();
+        assert!(license.contains("Copyright (c) 2025 Jane Doe"));
+        assert_eq!(
+            std::fs::read_to_string(temp_dir.path().join(".gitignore")).unwrap(),
+            "*.log\n/target\n"
+        );
+    }
+}
@@ -0,0 +1,59 @@
+use git2::{Repository, Signature};
+use std::path::Path;
+
+/// Creates a non-bare repository with a single commit on `main`, usable as a
+/// local clone source so tests don't depend on the network.
+pub fn create_source_repository(path: &Path) -> Repository {
+    let repo = Repository::init(path).unwrap();
+    repo.set_head("refs/heads/main").unwrap();
+    std::fs::write(path.join("README"), "Hello, World!\n").unwrap();
+    commit_all(&repo, "Initial commit");
+    repo
+}
+
+/// Stages every file in the working directory and commits it on HEAD.
+pub fn commit_all(repo: &Repository, message: &str) -> git2::Oid {
+    let mut index = repo.index().unwrap();
+    index
+        .add_all(["*"].iter(), git2::IndexAddOption::DEFAULT, None)
+        .unwrap();
+    index.write().unwrap();
+    let tree_id = index.write_tree().unwrap();
+    let tree = repo.find_tree(tree_id).unwrap();
+    let signature = Signature::now("Test", "test@example.com").unwrap();
+    let parents = match repo.head().ok().and_then(|head| head.peel_to_commit().ok()) {
+        Some(parent) => vec![parent],
+        None => vec![],
+    };
+    let parents = parents.iter().collect::<Vec<_>>();
+    repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        message,
+        &tree,
+        &parents,
+    )
+    .unwrap()
+}
+
+/// Creates a repository in neoghq's layout: a bare repository at
+/// `<repo_dir>/.git` with a `main` worktree at `<repo_dir>/main`.
+pub fn create_managed_repository(repo_dir: &Path) -> Repository {
+    let source_dir = tempfile::tempdir().unwrap();
+    create_source_repository(source_dir.path());
+
+    let bare_repo_path = repo_dir.join(".git");
+    let bare = git2::build::RepoBuilder::new()
+        .bare(true)
+        .clone(source_dir.path().to_str().unwrap(), &bare_repo_path)
+        .unwrap();
+    {
+        let reference = bare.find_reference("refs/heads/main").unwrap();
+        let mut opts = git2::WorktreeAddOptions::new();
+        opts.reference(Some(&reference));
+        bare.worktree("main", &repo_dir.join("main"), Some(&opts))
+            .unwrap();
+    }
+    bare
+}
@@ -0,0 +1,134 @@
+use anyhow::{Context, Result, bail};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// A tmux session as listed by `tmux list-sessions`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Session {
+    pub name: String,
+    /// The directory the session was started in.
+    pub path: PathBuf,
+    pub attached: bool,
+    pub windows: usize,
+}
+
+/// Returns the session name for a worktree, `repo@branch`. tmux doesn't
+/// allow `.` or `:` in session names, so they become `_`.
+pub fn session_name(repo: &str, branch: &str) -> String {
+    format!("{repo}@{branch}").replace(['.', ':'], "_")
+}
+
+/// Creates session `name` in `path` unless it exists, then switches the
+/// current client to it inside tmux or attaches to it outside.
+pub fn open(name: &str, path: &Path) -> Result<()> {
+    // `=` matches the name exactly rather than as a prefix
+    let target = format!("={name}");
+    if !tmux(&["has-session", "-t", &target])?.success() {
+        let status = tmux(&[
+            "new-session",
+            "-d",
+            "-s",
+            name,
+            "-c",
+            &path.to_string_lossy(),
+        ])?;
+        if !status.success() {
+            bail!("tmux failed to create session {name} with {status}");
+        }
+    }
+    let command = if std::env::var_os("TMUX").is_some() {
+        "switch-client"
+    } else {
+        "attach-session"
+    };
+    let status = tmux(&[command, "-t", &target])?;
+    if !status.success() {
+        bail!("tmux {command} failed with {status}");
+    }
+    Ok(())
+}
+
+/// Lists the sessions of the tmux server, none when it isn't running.
+pub fn sessions() -> Result<Vec<Session>> {
+    let output = Command::new("tmux")
+        .args([
+            "list-sessions",
+            "-F",
+            "#{session_name}\t#{session_path}\t#{session_attached}\t#{session_windows}",
+        ])
+        .stderr(Stdio::null())
+        .output();
+    match output {
+        Ok(output) if output.status.success() => {
+            Ok(parse_sessions(&String::from_utf8_lossy(&output.stdout)))
+        }
+        // No server, or no tmux at all
+        _ => Ok(Vec::new()),
+    }
+}
+
+fn parse_sessions(output: &str) -> Vec<Session> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split('\t');
+            Some(Session {
+                name: fields.next()?.to_string(),
+                path: PathBuf::from(fields.next()?),
+                attached: fields
+                    .next()?
+                    .parse::<usize>()
+                    .is_ok_and(|clients| clients > 0),
+                windows: fields.next()?.parse().unwrap_or_default(),
+            })
+        })
+        .collect()
+}
+
+fn tmux(args: &[&str]) -> Result<std::process::ExitStatus> {
+    Command::new("tmux")
+        .args(args)
+        .status()
+        .context("Failed to run tmux")
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_name() {
+        assert_eq!(session_name("neoghq", "main"), "neoghq@main");
+        assert_eq!(
+            session_name("vue.js", "release:v3.4"),
+            "vue_js@release_v3_4"
+        );
+        assert_eq!(session_name("repo", "feature/x"), "repo@feature/x");
+    }
+
+    #[test]
+    fn test_parse_sessions() {
+        let sessions = parse_sessions(
+            "neoghq@main\t/repos/github.com/r4ai/neoghq/main\t1\t3\nscratch\t/tmp\t0\t1\nbroken\n",
+        );
+        assert_eq!(
+            sessions,
+            [
+                Session {
+                    name: "neoghq@main".to_string(),
+                    path: PathBuf::from("/repos/github.com/r4ai/neoghq/main"),
+                    attached: true,
+                    windows: 3,
+                },
+                Session {
+                    name: "scratch".to_string(),
+                    path: PathBuf::from("/tmp"),
+                    attached: false,
+                    windows: 1,
+                },
+            ]
+        );
+    }
+}
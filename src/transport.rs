@@ -0,0 +1,272 @@
+use crate::config::Prune;
+use anyhow::{Context, Result, anyhow};
+use std::path::Path;
+use std::process::Command;
+use tracing::debug;
+
+/// Network transport a remote URL needs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transport {
+    Https,
+    Ssh,
+    /// Local paths, `file://` and `git://`, which libgit2 always supports.
+    Builtin,
+}
+
+impl Transport {
+    pub fn for_url(url: &str) -> Self {
+        match url.split_once("://") {
+            Some((scheme, _)) => match scheme {
+                "https" | "http" => Self::Https,
+                "ssh" | "git+ssh" | "ssh+git" => Self::Ssh,
+                _ => Self::Builtin,
+            },
+            // scp-like `[user@]host:path`, but not Windows drive letters
+            None if url.find(':').is_some_and(|index| index > 1)
+                && !url.starts_with('/')
+                && !url.starts_with('.') =>
+            {
+                Self::Ssh
+            }
+            None => Self::Builtin,
+        }
+    }
+
+    fn feature_name(self) -> &'static str {
+        match self {
+            Self::Https => "HTTPS",
+            Self::Ssh => "SSH",
+            Self::Builtin => "local",
+        }
+    }
+}
+
+/// How fetches and clones are performed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    Libgit2,
+    /// The `git` executable, used when libgit2 lacks the needed transport.
+    GitCli,
+}
+
+/// Transports libgit2 was built with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Features {
+    pub https: bool,
+    pub ssh: bool,
+}
+
+impl Features {
+    pub fn detect() -> Self {
+        let version = git2::Version::get();
+        Self {
+            https: version.https(),
+            ssh: version.ssh(),
+        }
+    }
+
+    fn supports(self, transport: Transport) -> bool {
+        match transport {
+            Transport::Https => self.https,
+            Transport::Ssh => self.ssh,
+            Transport::Builtin => true,
+        }
+    }
+}
+
+/// Picks the backend able to talk to `url`, falling back to the git CLI
+/// when libgit2 was built without the transport it needs.
+pub fn select_backend(url: &str) -> Result<Backend> {
+    select_backend_with(Features::detect(), git_cli_available, url)
+}
+
+fn select_backend_with(
+    features: Features,
+    git_cli_available: impl FnOnce() -> bool,
+    url: &str,
+) -> Result<Backend> {
+    let transport = Transport::for_url(url);
+    if features.supports(transport) {
+        debug!("Using libgit2 for {url}");
+        return Ok(Backend::Libgit2);
+    }
+    if git_cli_available() {
+        debug!(
+            "libgit2 lacks {} support; using the git CLI for {url}",
+            transport.feature_name()
+        );
+        return Ok(Backend::GitCli);
+    }
+
+    let feature = transport.feature_name();
+    Err(anyhow!(
+        "Cannot access {url}: this build of libgit2 has no {feature} support and \
+         the git executable was not found. Install git, or rebuild neoghq with \
+         libgit2 {feature} support."
+    ))
+}
+
+fn git_cli_available() -> bool {
+    Command::new("git")
+        .arg("--version")
+        .output()
+        .is_ok_and(|output| output.status.success())
+}
+
+/// Clones `url` as a bare repository with the same remote-tracking layout
+/// libgit2 produces (`refs/remotes/origin/*`).
+pub fn git_clone_bare(url: &str, path: &Path, prompt: bool) -> Result<()> {
+    run_git(
+        None,
+        prompt,
+        &[
+            "clone",
+            "--bare",
+            "--quiet",
+            "--",
+            url,
+            &path.to_string_lossy(),
+        ],
+    )?;
+    run_git(
+        Some(path),
+        prompt,
+        &[
+            "config",
+            "remote.origin.fetch",
+            "+refs/heads/*:refs/remotes/origin/*",
+        ],
+    )?;
+    git_fetch(path, "origin", Prune::default(), prompt)
+}
+
+/// Fetches `remote` into the bare repository at `bare_repo_path`, pruning
+/// what `prune` asks for.
+pub fn git_fetch(bare_repo_path: &Path, remote: &str, prune: Prune, prompt: bool) -> Result<()> {
+    let mut args = vec!["fetch", "--quiet"];
+    if prune.branches {
+        args.push("--prune");
+    }
+    if prune.tags {
+        args.push("--prune-tags");
+    }
+    args.push(remote);
+    run_git(Some(bare_repo_path), prompt, &args)
+}
+
+/// Pushes `refspecs` from the bare repository at `bare_repo_path` to
+/// `remote`.
+pub fn git_push(
+    bare_repo_path: &Path,
+    remote: &str,
+    refspecs: &[&str],
+    prompt: bool,
+) -> Result<()> {
+    let mut args = vec!["push", "--quiet", remote];
+    args.extend_from_slice(refspecs);
+    run_git(Some(bare_repo_path), prompt, &args)
+}
+
+/// Runs git with `args`. Unless `prompt` is set, git fails instead of
+/// asking for credentials, and ssh instead of asking for passphrases or
+/// host keys.
+pub fn run_git(dir: Option<&Path>, prompt: bool, args: &[&str]) -> Result<()> {
+    let mut command = Command::new("git");
+    if let Some(dir) = dir {
+        command.arg("-C").arg(dir);
+    }
+    if !prompt {
+        command.env("GIT_TERMINAL_PROMPT", "0");
+        if std::env::var_os("GIT_SSH_COMMAND").is_none() {
+            command.env("GIT_SSH_COMMAND", "ssh -o BatchMode=yes");
+        }
+    }
+    command.args(args);
+    debug!("Running {command:?}");
+    let status = command.status().context("Failed to run git")?;
+
+    if !status.success() {
+        return Err(anyhow!("git {} failed with {status}", args[0]));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use crate::test_utils::{commit_all, create_source_repository};
+
+    #[test]
+    fn test_transport_for_url() {
+        let cases = [
+            ("https://github.com/u/r", Transport::Https),
+            ("http://git.local/u/r", Transport::Https),
+            ("ssh://git@github.com/u/r", Transport::Ssh),
+            ("git+ssh://github.com/u/r", Transport::Ssh),
+            ("git@github.com:u/r.git", Transport::Ssh),
+            ("github.com:u/r", Transport::Ssh),
+            ("file:///tmp/repo", Transport::Builtin),
+            ("git://github.com/u/r", Transport::Builtin),
+            ("/tmp/repo", Transport::Builtin),
+            ("./repo", Transport::Builtin),
+            ("C:/repo", Transport::Builtin),
+        ];
+
+        for (url, expected) in cases {
+            assert_eq!(Transport::for_url(url), expected, "{url}");
+        }
+    }
+
+    #[test]
+    fn test_select_backend() {
+        let full = Features {
+            https: true,
+            ssh: true,
+        };
+        let no_ssh = Features {
+            https: true,
+            ssh: false,
+        };
+        let ssh_url = "git@github.com:u/r.git";
+
+        assert_eq!(
+            select_backend_with(full, || false, ssh_url).unwrap(),
+            Backend::Libgit2
+        );
+        assert_eq!(
+            select_backend_with(no_ssh, || true, ssh_url).unwrap(),
+            Backend::GitCli
+        );
+        assert_eq!(
+            select_backend_with(no_ssh, || false, "https://github.com/u/r").unwrap(),
+            Backend::Libgit2
+        );
+
+        let error = select_backend_with(no_ssh, || false, ssh_url).unwrap_err();
+        assert!(error.to_string().contains("no SSH support"));
+    }
+
+    #[test]
+    fn test_git_clone_bare_and_fetch() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let source_path = temp_dir.path().join("source");
+        let source = create_source_repository(&source_path);
+        let bare_repo_path = temp_dir.path().join("repo/.git");
+
+        git_clone_bare(source_path.to_str().unwrap(), &bare_repo_path, false).unwrap();
+
+        let bare = git2::Repository::open_bare(&bare_repo_path).unwrap();
+        assert!(bare.find_reference("refs/remotes/origin/main").is_ok());
+
+        std::fs::write(source_path.join("NEW"), "new").unwrap();
+        let commit = commit_all(&source, "Add NEW");
+        git_fetch(&bare_repo_path, "origin", Prune::default(), false).unwrap();
+        assert_eq!(
+            bare.find_reference("refs/remotes/origin/main")
+                .unwrap()
+                .target(),
+            Some(commit)
+        );
+    }
+}
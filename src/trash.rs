@@ -0,0 +1,333 @@
+use crate::{config::Config, status};
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tracing::{debug, warn};
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// What a trash entry holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrashKind {
+    /// A whole repository directory with its bare repository and worktrees.
+    Repository,
+    /// A linked worktree, kept with its record from the bare repository.
+    Worktree,
+}
+
+/// A removal kept in `<root>/.neoghq/trash/<id>/`, as printed by
+/// `trash list --json`. The removed directory is kept as `content` next to
+/// `entry.json`, and a worktree's record as `record`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrashEntry {
+    pub id: String,
+    pub kind: TrashKind,
+    /// Where the directory was before it was removed.
+    pub original: PathBuf,
+    /// Where a worktree's record in the bare repository was.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub record: Option<PathBuf>,
+    /// Unix time of the removal.
+    pub removed_at: u64,
+    /// The entry's directory in the trash.
+    #[serde(skip)]
+    pub dir: PathBuf,
+}
+
+impl TrashEntry {
+    fn content(&self) -> PathBuf {
+        self.dir.join("content")
+    }
+
+    fn saved_record(&self) -> PathBuf {
+        self.dir.join("record")
+    }
+}
+
+/// Moves `path`, and the worktree `record` if given, into the trash of the
+/// root containing it. Returns `None` without touching anything when the
+/// trash is disabled (`trash.retention_days = 0`) or `path` lies outside
+/// every root, so the caller deletes it for good.
+pub fn put(
+    config: &Config,
+    kind: TrashKind,
+    path: &Path,
+    record: Option<&Path>,
+) -> Result<Option<TrashEntry>> {
+    if config.trash_retention_days == 0 {
+        return Ok(None);
+    }
+    let Some(root) = root_containing(config, path) else {
+        debug!(
+            "{} is outside every root; not using the trash",
+            path.display()
+        );
+        return Ok(None);
+    };
+    purge_expired(&root, config.trash_retention_days)?;
+
+    let removed_at = status::unix_now();
+    let trash = trash_dir(&root);
+    let mut id = removed_at.to_string();
+    let mut suffix = 1;
+    while trash.join(&id).exists() {
+        suffix += 1;
+        id = format!("{removed_at}-{suffix}");
+    }
+    let entry = TrashEntry {
+        dir: trash.join(&id),
+        id,
+        kind,
+        original: path.to_path_buf(),
+        record: record.map(Path::to_path_buf),
+        removed_at,
+    };
+
+    std::fs::create_dir_all(&entry.dir)?;
+    let mut moves = vec![(path, entry.content())];
+    moves.extend(record.map(|record| (record, entry.saved_record())));
+    if let Err(error) = fill(&entry, &moves) {
+        let _ = std::fs::remove_dir(&entry.dir);
+        return Err(error);
+    }
+    Ok(Some(entry))
+}
+
+/// Moves each source of `moves` to its target in the trash and only then
+/// writes `entry.json`, so the trash never lists an entry whose directories
+/// didn't make it in. On failure, what was moved is put back.
+fn fill(entry: &TrashEntry, moves: &[(&Path, PathBuf)]) -> Result<()> {
+    let mut moved = Vec::new();
+    let result = (|| {
+        for (source, target) in moves {
+            std::fs::rename(source, target)
+                .with_context(|| format!("Failed to move {} to the trash", source.display()))?;
+            moved.push((*source, target));
+        }
+        let json = serde_json::to_string_pretty(entry)? + "\n";
+        Ok(std::fs::write(entry.dir.join("entry.json"), json)?)
+    })();
+    if result.is_err() {
+        for (source, target) in moved.into_iter().rev() {
+            if let Err(error) = std::fs::rename(target, source) {
+                warn!(
+                    "Failed to move {} back from the trash; it's kept at {}: {error}",
+                    source.display(),
+                    target.display()
+                );
+            }
+        }
+    }
+    result
+}
+
+/// Returns the entries in the trash of every root, most recent first.
+pub fn list(config: &Config) -> Result<Vec<TrashEntry>> {
+    let mut entries = Vec::new();
+    for root in config.all_roots() {
+        entries.extend(root_entries(&root.path)?);
+    }
+    entries.sort_by(|a, b| {
+        b.removed_at
+            .cmp(&a.removed_at)
+            .then_with(|| b.id.cmp(&a.id))
+    });
+    Ok(entries)
+}
+
+/// Moves the directories of `entry` back where they were and drops it from
+/// the trash. Fails without changing anything if something took their place.
+pub fn restore(entry: &TrashEntry) -> Result<()> {
+    let moves = std::iter::once((entry.content(), entry.original.clone()))
+        .chain(
+            entry
+                .record
+                .clone()
+                .map(|record| (entry.saved_record(), record)),
+        )
+        .collect::<Vec<_>>();
+    for (_, target) in &moves {
+        if target.exists() {
+            bail!("Can't restore {}: the path exists", target.display());
+        }
+    }
+    for (source, target) in moves {
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::rename(&source, &target)
+            .with_context(|| format!("Failed to restore {}", target.display()))?;
+    }
+    std::fs::remove_dir_all(&entry.dir)?;
+    Ok(())
+}
+
+/// Deletes `entry` for good.
+pub fn delete(entry: &TrashEntry) -> Result<()> {
+    Ok(std::fs::remove_dir_all(&entry.dir)?)
+}
+
+/// Deletes the entries of `root` removed more than `retention_days` ago.
+fn purge_expired(root: &Path, retention_days: u64) -> Result<()> {
+    let cutoff = status::unix_now().saturating_sub(retention_days.saturating_mul(SECONDS_PER_DAY));
+    for entry in root_entries(root)? {
+        if entry.removed_at < cutoff {
+            debug!("Deleting expired trash entry {}", entry.dir.display());
+            delete(&entry)?;
+        }
+    }
+    Ok(())
+}
+
+fn root_entries(root: &Path) -> Result<Vec<TrashEntry>> {
+    let Ok(dirs) = std::fs::read_dir(trash_dir(root)) else {
+        return Ok(Vec::new());
+    };
+    let mut entries = Vec::new();
+    for dir in dirs {
+        let dir = dir?.path();
+        // Skips entries left half-written by an interrupted removal
+        let Ok(content) = std::fs::read_to_string(dir.join("entry.json")) else {
+            continue;
+        };
+        let entry = serde_json::from_str::<TrashEntry>(&content)
+            .with_context(|| format!("Failed to parse {}", dir.join("entry.json").display()))?;
+        entries.push(TrashEntry { dir, ..entry });
+    }
+    Ok(entries)
+}
+
+/// Returns the configured root that `path` lies under.
+fn root_containing(config: &Config, path: &Path) -> Option<PathBuf> {
+    let canonical = |path: &Path| path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    let path = canonical(path);
+    config
+        .all_roots()
+        .into_iter()
+        .find(|root| path.starts_with(canonical(&root.path)))
+        .map(|root| root.path)
+}
+
+fn trash_dir(root: &Path) -> PathBuf {
+    root.join(".neoghq").join("trash")
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn config(root: &Path) -> Config {
+        Config {
+            root: root.to_path_buf(),
+            trash_retention_days: 30,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_put_and_restore() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        let repo = root.join("github.com/user/repo");
+        let record = root.join("github.com/user/other/.git/worktrees/main");
+        fs::create_dir_all(repo.join("main")).unwrap();
+        fs::create_dir_all(&record).unwrap();
+        let config = config(root);
+
+        let entry = put(&config, TrashKind::Worktree, &repo, Some(&record))
+            .unwrap()
+            .unwrap();
+        assert!(!repo.exists());
+        assert!(!record.exists());
+        assert_eq!(list(&config).unwrap(), std::slice::from_ref(&entry));
+
+        let second = put(
+            &config,
+            TrashKind::Repository,
+            &root.join("github.com/user"),
+            None,
+        )
+        .unwrap()
+        .unwrap();
+        assert_ne!(second.id, entry.id);
+        assert_eq!(list(&config).unwrap()[0], second);
+
+        fs::create_dir_all(&repo).unwrap();
+        assert!(restore(&entry).is_err());
+        assert!(entry.dir.exists());
+        fs::remove_dir_all(root.join("github.com/user")).unwrap();
+        restore(&second).unwrap();
+        restore(&entry).unwrap();
+        assert!(repo.join("main").exists());
+        assert!(record.exists());
+        assert!(list(&config).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_put_rolls_back_when_the_record_cannot_move() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        let repo = root.join("github.com/user/repo");
+        fs::create_dir_all(repo.join("main")).unwrap();
+        let config = config(root);
+
+        let missing = root.join("github.com/user/repo/.git/worktrees/gone");
+        assert!(put(&config, TrashKind::Worktree, &repo, Some(&missing)).is_err());
+        assert!(repo.join("main").exists());
+        assert!(list(&config).unwrap().is_empty());
+        assert_eq!(fs::read_dir(trash_dir(root)).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_put_without_trash() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        let config = config(temp_dir.path());
+
+        assert_eq!(
+            put(&config, TrashKind::Repository, outside.path(), None).unwrap(),
+            None
+        );
+        let disabled = Config {
+            trash_retention_days: 0,
+            ..config
+        };
+        let repo = temp_dir.path().join("github.com/user/repo");
+        fs::create_dir_all(&repo).unwrap();
+        assert_eq!(
+            put(&disabled, TrashKind::Repository, &repo, None).unwrap(),
+            None
+        );
+        assert!(repo.exists());
+    }
+
+    #[test]
+    fn test_purge_expired() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        let repo = root.join("github.com/user/repo");
+        fs::create_dir_all(&repo).unwrap();
+        let entry = put(&config(root), TrashKind::Repository, &repo, None)
+            .unwrap()
+            .unwrap();
+        let old = TrashEntry {
+            removed_at: status::unix_now() - 31 * SECONDS_PER_DAY,
+            ..entry.clone()
+        };
+        fs::write(
+            entry.dir.join("entry.json"),
+            serde_json::to_string(&old).unwrap(),
+        )
+        .unwrap();
+
+        purge_expired(root, 60).unwrap();
+        assert!(entry.dir.exists());
+        purge_expired(root, u64::MAX).unwrap();
+        assert!(entry.dir.exists());
+        purge_expired(root, 30).unwrap();
+        assert!(!entry.dir.exists());
+    }
+}
@@ -0,0 +1,322 @@
+use crate::ignore::IGNORE_FILE;
+use crate::index;
+use crate::scan;
+use anyhow::{Result, bail};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, RecvTimeoutError, channel};
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
+
+/// How long the file system has to stay quiet before a burst of events,
+/// such as a clone writing its first files, is handled.
+const QUIET: Duration = Duration::from_millis(500);
+
+/// The longest a steady stream of events delays handling them.
+const MAX_DELAY: Duration = Duration::from_secs(5);
+
+/// A change to a root made by neoghq or by anything else, such as a plain
+/// `git clone` or `rm -rf`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    RepoAdded(PathBuf),
+    RepoRemoved(PathBuf),
+    /// Entries were added to or removed from a repository directory, which
+    /// holds its worktrees.
+    WorktreesChanged(PathBuf),
+}
+
+/// Watches roots for repositories and worktrees coming and going, keeping
+/// their indexes current.
+///
+/// Only the root, host, owner and repository directories are watched, each
+/// without recursion, so edits inside worktrees never wake the watcher.
+pub struct RootWatcher {
+    watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<notify::Event>>,
+    roots: Vec<PathBuf>,
+    watched: BTreeSet<PathBuf>,
+    /// The repositories of each root as of the last change.
+    repos: HashMap<PathBuf, BTreeSet<PathBuf>>,
+}
+
+impl RootWatcher {
+    /// Starts watching `roots`. Roots that don't exist yet are skipped.
+    pub fn new(roots: Vec<PathBuf>) -> Result<Self> {
+        let (sender, events) = channel();
+        let mut watcher = Self {
+            watcher: notify::recommended_watcher(sender)?,
+            events,
+            roots,
+            watched: BTreeSet::new(),
+            repos: HashMap::new(),
+        };
+        for root in watcher.roots.clone() {
+            let repos = watcher.scan(&root);
+            watcher.repos.insert(root, repos);
+        }
+        watcher.sync_watches();
+        Ok(watcher)
+    }
+
+    /// Returns the number of directories being watched.
+    pub fn watched(&self) -> usize {
+        self.watched.len()
+    }
+
+    /// Waits up to `timeout` for changes and returns them once the file
+    /// system settles, after updating the index of every root they touch.
+    /// Returns nothing if only unrelated files changed.
+    pub fn poll(&mut self, timeout: Duration) -> Result<Vec<Change>> {
+        let mut paths = Vec::new();
+        match self.events.recv_timeout(timeout) {
+            Ok(event) => self.collect(event, &mut paths),
+            Err(RecvTimeoutError::Timeout) => return Ok(Vec::new()),
+            Err(RecvTimeoutError::Disconnected) => bail!("The file watcher stopped"),
+        }
+        let deadline = Instant::now() + MAX_DELAY;
+        while Instant::now() < deadline {
+            match self.events.recv_timeout(QUIET) {
+                Ok(event) => self.collect(event, &mut paths),
+                Err(_) => break,
+            }
+        }
+        Ok(self.handle(&paths))
+    }
+
+    fn collect(&self, event: notify::Result<notify::Event>, paths: &mut Vec<PathBuf>) {
+        match event {
+            Ok(event) => {
+                if matches!(
+                    event.kind,
+                    EventKind::Create(_) | EventKind::Remove(_) | EventKind::Modify(_)
+                ) {
+                    paths.extend(event.paths);
+                }
+            }
+            Err(error) => {
+                // Events may have been dropped, so check every root
+                warn!("File watcher error: {error}");
+                paths.extend(self.roots.iter().cloned());
+            }
+        }
+    }
+
+    fn handle(&mut self, paths: &[PathBuf]) -> Vec<Change> {
+        let (stale, touched) = classify(&self.roots, paths);
+        let mut changes = Vec::new();
+        for root in &stale {
+            let repos = self.scan(root);
+            let known = self.repos.entry(root.clone()).or_default();
+            changes.extend(repos.difference(known).cloned().map(Change::RepoAdded));
+            changes.extend(known.difference(&repos).cloned().map(Change::RepoRemoved));
+            *known = repos;
+        }
+        for repo in touched {
+            let known = self.repos.values().any(|repos| repos.contains(&repo));
+            let handled = changes.iter().any(|change| match change {
+                Change::RepoAdded(path) | Change::RepoRemoved(path) => *path == repo,
+                Change::WorktreesChanged(_) => false,
+            });
+            if known && !handled {
+                changes.push(Change::WorktreesChanged(repo));
+            }
+        }
+        if !stale.is_empty() {
+            self.sync_watches();
+        }
+        changes
+    }
+
+    fn scan(&self, root: &Path) -> BTreeSet<PathBuf> {
+        match index::scan(root) {
+            Ok(entries) => entries.into_iter().map(|entry| entry.path).collect(),
+            Err(error) => {
+                warn!("Failed to scan {}: {error:#}", root.display());
+                BTreeSet::new()
+            }
+        }
+    }
+
+    /// Watches the directories that exist now and forgets the ones that
+    /// are gone.
+    fn sync_watches(&mut self) {
+        let wanted = self
+            .roots
+            .iter()
+            .flat_map(|root| directories(root))
+            .collect::<BTreeSet<_>>();
+        for path in self.watched.difference(&wanted) {
+            // Removing a directory already removed its watch on most platforms
+            let _ = self.watcher.unwatch(path);
+        }
+        let mut watched = BTreeSet::new();
+        for path in wanted {
+            if self.watched.contains(&path) {
+                watched.insert(path);
+                continue;
+            }
+            match self.watcher.watch(&path, RecursiveMode::NonRecursive) {
+                Ok(()) => {
+                    watched.insert(path);
+                }
+                Err(error) => debug!("Failed to watch {}: {error}", path.display()),
+            }
+        }
+        self.watched = watched;
+    }
+}
+
+/// Returns the root and its host, owner and repository directories.
+/// Hidden directories, such as `.neoghq`, are left out.
+fn directories(root: &Path) -> Vec<PathBuf> {
+    if !root.is_dir() {
+        return Vec::new();
+    }
+    let mut directories = vec![root.to_path_buf()];
+    let mut level = vec![root.to_path_buf()];
+    for _ in 0..3 {
+        level = level
+            .iter()
+            .flat_map(|dir| scan::subdirectories(dir).unwrap_or_default())
+            .filter(|path| !is_hidden(path))
+            .collect();
+        directories.extend(level.iter().cloned());
+    }
+    directories
+}
+
+/// Splits changed paths into the roots whose repositories may have changed
+/// and the repositories whose worktrees may have.
+fn classify(roots: &[PathBuf], paths: &[PathBuf]) -> (BTreeSet<PathBuf>, BTreeSet<PathBuf>) {
+    let mut stale = BTreeSet::new();
+    let mut touched = BTreeSet::new();
+    for path in paths {
+        let Some(root) = roots
+            .iter()
+            .filter(|root| path.starts_with(root))
+            .max_by_key(|root| root.components().count())
+        else {
+            continue;
+        };
+        let depth = path
+            .strip_prefix(root)
+            .map_or(0, |p| p.components().count());
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        match depth {
+            0 => {
+                stale.insert(root.clone());
+            }
+            1..=3 if !is_hidden(path) || name == IGNORE_FILE => {
+                stale.insert(root.clone());
+            }
+            // `.git` and lock files change while neoghq works on a repository
+            4 if !is_hidden(path) && !name.ends_with(".lock") => {
+                if let Some(repo) = path.parent() {
+                    touched.insert(repo.to_path_buf());
+                }
+            }
+            _ => {}
+        }
+    }
+    (stale, touched)
+}
+
+fn is_hidden(path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|name| name.to_string_lossy().starts_with('.'))
+}
+
+#[cfg(test)]
+#[cfg_attr(coverage_nightly, coverage(off))]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_classify() {
+        let root = PathBuf::from("/repos");
+        let roots = vec![root.clone(), PathBuf::from("/repos/nested")];
+        let paths = [
+            "/repos/github.com/user/new",
+            "/repos/.neoghq",
+            "/repos/github.com/user/repo/feature",
+            "/repos/github.com/user/repo/.git",
+            "/repos/github.com/user/repo/main.lock",
+            "/repos/github.com/user/repo/main/src",
+            "/repos/nested/host",
+            "/elsewhere/file",
+        ]
+        .map(PathBuf::from);
+
+        let (stale, touched) = classify(&roots, &paths);
+        assert_eq!(
+            stale,
+            BTreeSet::from([root.clone(), PathBuf::from("/repos/nested")])
+        );
+        assert_eq!(
+            touched,
+            BTreeSet::from([PathBuf::from("/repos/github.com/user/repo")])
+        );
+
+        let (stale, _) = classify(&roots, &[root.join(IGNORE_FILE)]);
+        assert_eq!(stale, BTreeSet::from([root]));
+    }
+
+    #[test]
+    fn test_directories() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("github.com/user/repo/main/src")).unwrap();
+        fs::create_dir_all(root.join("github.com/user/repo/.git")).unwrap();
+        fs::create_dir_all(root.join(".neoghq")).unwrap();
+
+        assert_eq!(
+            directories(root),
+            [
+                root.to_path_buf(),
+                root.join("github.com"),
+                root.join("github.com/user"),
+                root.join("github.com/user/repo"),
+            ]
+        );
+        assert!(directories(&root.join("missing")).is_empty());
+    }
+
+    #[test]
+    fn test_poll_reports_changes_made_outside_neoghq() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path().to_path_buf();
+        let repo = root.join("github.com/user/repo");
+        fs::create_dir_all(repo.join(".git")).unwrap();
+        let mut watcher = RootWatcher::new(vec![root.clone()]).unwrap();
+        assert_eq!(watcher.watched(), 4);
+        let poll = |watcher: &mut RootWatcher| {
+            let started = Instant::now();
+            loop {
+                let changes = watcher.poll(Duration::from_secs(5)).unwrap();
+                if !changes.is_empty() || started.elapsed() > Duration::from_secs(10) {
+                    return changes;
+                }
+            }
+        };
+
+        let other = root.join("gitlab.com/group/other");
+        fs::create_dir_all(other.join(".git")).unwrap();
+        assert_eq!(poll(&mut watcher), [Change::RepoAdded(other.clone())]);
+        let specs = index::scan(&root)
+            .unwrap()
+            .iter()
+            .map(scan::RepoEntry::spec)
+            .collect::<Vec<_>>();
+        assert_eq!(specs, ["github.com/user/repo", "gitlab.com/group/other"]);
+
+        // The new repository is watched too
+        fs::create_dir(other.join("main")).unwrap();
+        assert_eq!(poll(&mut watcher), [Change::WorktreesChanged(other)]);
+
+        fs::remove_dir_all(&repo).unwrap();
+        assert_eq!(poll(&mut watcher), [Change::RepoRemoved(repo)]);
+    }
+}